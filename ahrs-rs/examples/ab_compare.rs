@@ -0,0 +1,59 @@
+use imu_common::traits::IMUSink;
+use imu_common::types::sensors::{SensorClusterBuilder, SensorReadings};
+use imu_common::types::timed::Sample3D;
+
+use ahrs_rs::{AHRSFilter, EkfConfig, EstimatorConfig, OrientationComparator};
+use resampler_rs::{ResamplerBuilder, SmothingPolicy};
+use tokio::time::Duration;
+
+/// Compares the orientation estimated from the same input by Madgwick and by an EKF.
+#[tokio::main]
+async fn main() {
+    let tag = "Test";
+    let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
+    let resampling_period_millis = 100.0;
+    let resampling_delay_millis = 500.0;
+
+    let (_handle_phyphox, phyphox) =
+        phyphox_rs::run_mock_service(tag, sensor_cluster.clone(), 50.0, true, 20000).unwrap();
+    let (_handle_resampler, resampler) = ResamplerBuilder::new(tag, sensor_cluster.clone())
+        .with_resampling_period_millis(resampling_period_millis)
+        .with_resampling_delay_millis(resampling_delay_millis)
+        .with_smoothing_policy(SmothingPolicy::WeightedAverage)
+        .run::<SensorReadings<Sample3D>, _>()
+        .unwrap();
+    resampler
+        .attach_listeners(&*phyphox, &sensor_cluster)
+        .unwrap();
+
+    let mut pipelines = Vec::new();
+    for (pipeline_tag, estimator) in [
+        ("A", EstimatorConfig::default()),
+        ("B", EstimatorConfig::Ekf(EkfConfig::default())),
+    ] {
+        let ahrs = AHRSFilter::with_clusters(
+            pipeline_tag,
+            vec![(tag, sensor_cluster.clone())],
+            resampling_period_millis,
+            estimator.into(),
+        )
+        .unwrap();
+        ahrs.attach_listeners(&*resampler, &sensor_cluster).unwrap();
+        pipelines.push(ahrs);
+    }
+
+    let output = pipelines[0].get_output_sensor(tag).unwrap();
+    let comparator = OrientationComparator::new("AB").with_tolerance(0.01);
+    comparator
+        .attach(&pipelines[0], &output, &pipelines[1], &output)
+        .unwrap();
+
+    for _ in 0..20 {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let stats = comparator.get_stats();
+        println!(
+            "pairs: {}, mean: {:.4} rad, rms: {:.4} rad, max: {:.4} rad",
+            stats.n_pairs, stats.mean_angle, stats.rms_angle, stats.max_angle
+        );
+    }
+}
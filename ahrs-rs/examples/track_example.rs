@@ -1,13 +1,14 @@
 use uuid::Uuid;
 
 use imu_common::traits::{IMUReadings, IMUSample, IMUSink};
-use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::sensors::{SensorClusterBuilder, SensorReadings, SensorType};
 use imu_common::types::timed::{Sample3D, SampleQuaternion};
 
-use ahrs_rs::{self, AHRSFilter};
+use ahrs_rs::{self, AHRSConfig, AHRSFilter};
 use imu_common::types::clock::Clock;
-use phyphox_rs;
-use resampler_rs::{self, SmothingPolicy};
+use imu_common::types::control::ControlChannel;
+use publisher::adapters;
+use resampler_rs::{ResamplerBuilder, SmothingPolicy};
 use std::sync::Arc;
 use test_utils::renderable::Box3D;
 use test_utils::sinks::{MockValue, Plot3D, SinkMock};
@@ -18,7 +19,7 @@ fn process_samples(
     _sensor_type: SensorType,
     samples: Arc<SensorReadings<SampleQuaternion>>,
 ) {
-    if let Some(q) = samples.get_samples().last() {
+    if let Some(q) = samples.get_samples_ref().last() {
         println!(
             "{:?}, {:?}, {}",
             q.get_measurement().inner().euler_angles(),
@@ -31,13 +32,9 @@ fn process_samples(
 #[tokio::main]
 async fn main() {
     let box_3d = Box3D::new();
-    let plot_3d = Plot3D::new(box_3d);
+    let plot_3d = Plot3D::new(box_3d).with_trail(50);
 
-    let sensor_cluster = vec![
-        SensorType::Accelerometer(Uuid::new_v4()),
-        SensorType::Gyroscope(Uuid::new_v4()),
-        SensorType::Magnetometer(Uuid::new_v4()),
-    ];
+    let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
     let orientation_measurement = SensorType::Other(Uuid::new_v4(), "Orientation".to_string());
     let tag = "Test";
 
@@ -55,13 +52,12 @@ async fn main() {
     // start resampler
     let resampling_period_millis = 100.0;
     let resampling_delay_millis = 500.0;
-    let (_handle_resampler, resampler) = resampler_rs::run::<SensorReadings<Sample3D>, _>(
-        tag,
-        sensor_cluster.clone(),
-        resampling_period_millis,
-        resampling_delay_millis,
-        SmothingPolicy::WeightedAverage,
-    );
+    let (_handle_resampler, resampler) = ResamplerBuilder::new(tag, sensor_cluster.clone())
+        .with_resampling_period_millis(resampling_period_millis)
+        .with_resampling_delay_millis(resampling_delay_millis)
+        .with_smoothing_policy(SmothingPolicy::WeightedAverage)
+        .run::<SensorReadings<Sample3D>, _>()
+        .unwrap();
 
     // start ahrs
     let ahrs = AHRSFilter::new(
@@ -69,31 +65,36 @@ async fn main() {
         sensor_cluster.clone(),
         orientation_measurement.clone(),
         resampling_period_millis,
+        AHRSConfig::default(),
     )
     .unwrap();
 
     // connect resampler to phyphox
-    resampler
-        .attach_listeners(&*phyphox, &sensor_cluster)
-        .unwrap();
+    let _resampler_subscription = adapters::attach(&phyphox, &*resampler, &sensor_cluster).unwrap();
 
-    // connect ahrs to phyphox
-    ahrs.attach_listeners(&*resampler, &sensor_cluster).unwrap();
+    // connect ahrs to resampler
+    let _ahrs_subscription = adapters::attach(&resampler, &ahrs, &sensor_cluster).unwrap();
     // connect samples from ahrs
 
     IMUSink::<SensorReadings<SampleQuaternion>, SampleQuaternion>::attach_listeners(
         &plot_3d,
         &ahrs,
-        &[orientation_measurement.clone()],
+        std::slice::from_ref(&orientation_measurement),
     )
     .unwrap();
 
     // connect samples from ahrs
     let mut sink = SinkMock::<SampleQuaternion>::new();
     sink.register_callback(process_samples);
-    sink.attach_listeners(&ahrs, &[orientation_measurement.clone()])
+    sink.attach_listeners(&ahrs, &[orientation_measurement])
         .unwrap();
 
+    // tune the ahrs and the resampler from the terminal, e.g. `set ahrs beta=0.2`
+    let control = ControlChannel::new();
+    control.register("ahrs", Arc::new(ahrs.clone())).unwrap();
+    control.register("resampler", resampler.clone()).unwrap();
+    std::thread::spawn(move || control.serve(std::io::stdin().lock(), std::io::stdout()));
+
     let timeout_duration = Duration::from_secs(500);
     let _ = tokio::time::timeout(timeout_duration, async {
         handle_phyphox.await.unwrap();
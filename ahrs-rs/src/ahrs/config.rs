@@ -0,0 +1,85 @@
+use imu_common::errors::ImuError;
+
+use crate::estimators::{EstimatorConfig, MadgwickConfig};
+
+/// Number of orientations discarded by default while the estimator converges.
+pub const DEFAULT_WARM_UP_SAMPLES: usize = 100;
+
+/// What `AHRSFilter` publishes when the accelerometer or magnetometer readings can't be used,
+/// for instance when the accelerometer reads zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GyroFallback {
+    /// Publish the last valid orientation again.
+    #[default]
+    Hold,
+    /// Integrate the gyroscope reading alone.
+    Integrate,
+    /// Publish nothing for this sample.
+    Skip,
+}
+
+/// `AHRSFilter` configuration.
+///
+/// # Examples
+///
+/// ```
+/// use ahrs_rs::{AHRSConfig, EstimatorConfig, GyroFallback, MadgwickConfig};
+///
+/// let config = AHRSConfig::default()
+///     .with_beta(0.2)
+///     .with_warm_up_samples(20)
+///     .with_gyro_fallback(GyroFallback::Integrate);
+/// assert_eq!(config.estimator, EstimatorConfig::Madgwick(MadgwickConfig::new(0.2)));
+/// assert!(config.validate().is_ok());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AHRSConfig {
+    /// Estimation algorithm and its parameters.
+    pub estimator: EstimatorConfig,
+    /// Number of initial orientations discarded while the estimator converges.
+    pub warm_up_samples: usize,
+    /// Behavior when the accelerometer or magnetometer readings are invalid.
+    pub gyro_fallback: GyroFallback,
+}
+
+impl Default for AHRSConfig {
+    fn default() -> Self {
+        Self::new(EstimatorConfig::default())
+    }
+}
+
+impl From<EstimatorConfig> for AHRSConfig {
+    fn from(estimator: EstimatorConfig) -> Self {
+        Self::new(estimator)
+    }
+}
+
+impl AHRSConfig {
+    pub fn new(estimator: EstimatorConfig) -> Self {
+        Self {
+            estimator,
+            warm_up_samples: DEFAULT_WARM_UP_SAMPLES,
+            gyro_fallback: GyroFallback::default(),
+        }
+    }
+
+    /// Uses a Madgwick estimator with gain `beta`.
+    pub fn with_beta(mut self, beta: f64) -> Self {
+        self.estimator = EstimatorConfig::Madgwick(MadgwickConfig::new(beta));
+        self
+    }
+
+    pub fn with_warm_up_samples(mut self, warm_up_samples: usize) -> Self {
+        self.warm_up_samples = warm_up_samples;
+        self
+    }
+
+    pub fn with_gyro_fallback(mut self, gyro_fallback: GyroFallback) -> Self {
+        self.gyro_fallback = gyro_fallback;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), ImuError> {
+        self.estimator.validate()
+    }
+}
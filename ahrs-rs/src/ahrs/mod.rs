@@ -1,52 +1,95 @@
 pub(crate) mod buffer;
+pub(crate) mod config;
 pub(crate) mod sink;
 pub(crate) mod source;
+pub(crate) mod tuning;
 
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 
-use ahrs::{Ahrs, Madgwick};
-
-use crate::utils;
 use buffer::{AHRSInputSamples, SensorIndex, N_SENSORS};
+use imu_common::errors::ImuError;
 use imu_common::traits::IMUSample;
-use imu_common::types::sensors::{SensorReadings, SensorType};
-use imu_common::types::timed::SampleQuaternion;
+use imu_common::types::sensors::{
+    check_nine_axis_cluster, SensorClusterBuilder, SensorReadings, SensorType,
+};
+use imu_common::types::timed::{Sample3D, SampleQuaternion};
 use imu_common::types::untimed::UnitQuaternion;
-use publisher::PublisherManager;
+use nalgebra::UnitQuaternion as NUnitQuaternion;
+use publisher::{EventBus, PublisherManager};
+
+use crate::estimators::{EstimatorConfig, OrientationEstimator};
+use crate::utils;
+use config::{AHRSConfig, GyroFallback};
 
-const MADGWICK_BETA: f64 = 0.08;
-const DISCARD_N_INITIAL_SAMPLES: usize = 100;
+/// Name of the sensors under which `AHRSFilter::with_clusters` publishes each orientation.
+pub const QUATERNION_SENSOR_NAME: &str = "Quaternion";
+
+// samples of a sensor waiting for the other sensors of the cluster, beyond which the oldest are
+// dropped, so a sensor going silent doesn't make the queues of the others grow without bound
+const MAX_PENDING_SAMPLES: usize = 1024;
 
 pub struct AHRSFilterManager {
-    ahrs_filter: Madgwick<f64>,
+    ahrs_filter: Box<dyn OrientationEstimator>,
     buffer: AHRSInputSamples,
+    // samples of each sensor not moved to the buffer yet, oldest first
+    pending: [VecDeque<Sample3D>; N_SENSORS],
     cache: UnitQuaternion,
+    reference: Option<NUnitQuaternion<f64>>,
     sensor_cluster: [SensorType; N_SENSORS],
     n_samples: usize,
+    warm_up_samples: usize,
+    gyro_fallback: GyroFallback,
+    sampling_period_secs: f64,
+    is_diverged: bool,
+    // reason of a divergence not reported yet
+    divergence: Option<&'static str>,
 }
 
 impl AHRSFilterManager {
     fn new(
         sensor_cluster: Vec<SensorType>,
         sampling_period_millis: f64,
-    ) -> Result<Self, &'static str> {
-        let sensor_cluster: [SensorType; N_SENSORS] = sensor_cluster
-            .try_into()
-            .map_err(|_| "Invalid sensor cluster")?;
-        if !utils::check_sensor_cluster(&sensor_cluster) {
-            return Err("Invalid sensor cluster");
-        }
+        config: &AHRSConfig,
+    ) -> Result<Self, ImuError> {
+        check_nine_axis_cluster(&sensor_cluster)?;
+        let sensor_cluster: [SensorType; N_SENSORS] = sensor_cluster.try_into().map_err(|_| {
+            ImuError::InvalidSensorCluster(format!("Expected {} sensors", N_SENSORS))
+        })?;
 
         Ok(Self {
-            ahrs_filter: Madgwick::new(sampling_period_millis / 1000.0, MADGWICK_BETA),
+            ahrs_filter: config.estimator.build(sampling_period_millis / 1000.0),
             buffer: AHRSInputSamples::new(),
+            pending: Default::default(),
             sensor_cluster,
             cache: UnitQuaternion::default(),
+            reference: None,
             n_samples: 0,
+            warm_up_samples: config.warm_up_samples,
+            gyro_fallback: config.gyro_fallback,
+            sampling_period_secs: sampling_period_millis / 1000.0,
+            is_diverged: false,
+            divergence: None,
         })
     }
 
-    fn update_filter(&mut self, buffer: AHRSInputSamples) -> SampleQuaternion {
+    /// Replaces the estimator, which starts from the current orientation.
+    fn set_estimator(&mut self, estimator: &EstimatorConfig) {
+        let orientation = self.ahrs_filter.get_orientation();
+        self.ahrs_filter = estimator.build(self.sampling_period_secs);
+        self.ahrs_filter.set_orientation(orientation);
+    }
+
+    /// Makes the current orientation the reference of the published ones.
+    fn zero_orientation(&mut self) {
+        self.reference = Some(self.cache.inner());
+    }
+
+    /// Updates the estimator with the readings in `buffer`. Returns `None` if the readings were
+    /// invalid and the fallback is `GyroFallback::Skip`. The returned orientation is relative to
+    /// the reference, if any. The first failure of the estimator after a successful update is
+    /// returned by `take_divergence`.
+    fn update_filter(&mut self, buffer: AHRSInputSamples) -> Option<SampleQuaternion> {
         let gyro = buffer
             .get_samples_by_index(usize::from(SensorIndex::Gyroscope))
             .unwrap();
@@ -56,17 +99,75 @@ impl AHRSFilterManager {
         let mag = buffer
             .get_samples_by_index(usize::from(SensorIndex::Magnetometer))
             .unwrap();
-        let q = match self.ahrs_filter.update(&gyro, &accel, &mag) {
-            Ok(q) => q,
-            Err(_) => &self.cache.inner(),
+        let update = self.ahrs_filter.update(&gyro, &accel, &mag);
+        match update {
+            Ok(_) => self.is_diverged = false,
+            Err(reason) if !self.is_diverged => {
+                self.is_diverged = true;
+                self.divergence = Some(reason);
+            }
+            Err(_) => {}
+        }
+        let q = match (update, self.gyro_fallback) {
+            (Ok(q), _) => q,
+            (Err(_), GyroFallback::Integrate) if gyro.iter().all(|v| v.is_finite()) => {
+                self.ahrs_filter.update_gyro(&gyro)
+            }
+            (Err(_), GyroFallback::Skip) => return None,
+            (Err(_), _) => self.cache.inner(),
         };
         self.n_samples += 1;
-        let sample_quaternion = SampleQuaternion::from_unit_quaternion(
+        self.cache = q.into();
+        let q = match self.reference {
+            Some(reference) => reference.inverse() * q,
+            None => q,
+        };
+        Some(SampleQuaternion::from_unit_quaternion(
             buffer.get_timestamp(),
-            UnitQuaternion::from_unit_quaternion(*q),
-        );
-        self.cache = sample_quaternion.get_measurement();
-        sample_quaternion
+            q.into(),
+        ))
+    }
+
+    /// Returns the reason of the last divergence of the estimator, if it wasn't returned yet.
+    fn take_divergence(&mut self) -> Option<&'static str> {
+        self.divergence.take()
+    }
+
+    /// Returns true once the warm-up samples have been discarded.
+    fn is_warmed_up(&self) -> bool {
+        self.n_samples > self.warm_up_samples
+    }
+
+    /// Queues `samples` of `sensor_type`, and returns the readings of the cluster completed by
+    /// them, oldest first. Readings may hold several samples of a sensor, e.g. when the resampler
+    /// batches its outputs, so every sample is paired with the samples of the other sensors at the
+    /// same position in their queues.
+    fn push_samples(
+        &mut self,
+        sensor_type: &SensorType,
+        samples: &[Sample3D],
+    ) -> Vec<AHRSInputSamples> {
+        let Some(sensor_idx) = utils::get_sensor_index(sensor_type) else {
+            return Vec::new();
+        };
+        let pending = &mut self.pending[sensor_idx];
+        pending.extend(samples.iter().cloned());
+        let n_dropped = pending.len().saturating_sub(MAX_PENDING_SAMPLES);
+        pending.drain(..n_dropped);
+
+        let mut ready = Vec::new();
+        while self.pending.iter().all(|pending| !pending.is_empty()) {
+            for sensor_type in &self.sensor_cluster {
+                let sensor_idx = utils::get_sensor_index(sensor_type).unwrap();
+                let sample = self.pending[sensor_idx].pop_front().unwrap();
+                self.buffer
+                    .set_samples_by_type(sensor_type, sample.get_measurement().into());
+                self.buffer.set_timestamp(sample.get_timestamp_secs());
+            }
+            debug_assert!(self.buffer.samples_ready());
+            ready.push(self.clone_and_clear());
+        }
+        ready
     }
 
     fn clone_and_clear(&mut self) -> AHRSInputSamples {
@@ -83,12 +184,35 @@ impl AHRSFilterManager {
     }
 }
 
+pub(crate) struct Estimator {
+    pub(crate) tag: String,
+    pub(crate) new_measurement: SensorType,
+    pub(crate) filter: Mutex<AHRSFilterManager>,
+}
+
+/// Estimates the orientation of one or more sensor clusters.
+///
+/// Every cluster is processed by its own estimator, and its orientation is published as
+/// `SampleQuaternion` readings under its own sensor type. The estimation algorithm, the number
+/// of warm-up samples and the behavior on invalid readings are chosen with an [`AHRSConfig`].
+///
+/// Estimators rejecting their readings are reported to an [`EventBus`], the global one unless
+/// set with `with_event_bus`.
+///
+/// Clones share the same estimators. The filter only holds its sources weakly, so it is freed,
+/// together with the listeners attached to it, once the last clone is dropped.
 #[derive(Clone)]
 pub struct AHRSFilter {
-    filter: Arc<Mutex<AHRSFilterManager>>,
+    state: Arc<AHRSFilterState>,
+}
+
+struct AHRSFilterState {
+    estimators: Vec<Estimator>,
+    routes: HashMap<SensorType, usize>,
     tag: String,
     publishers: PublisherManager<SensorReadings<SampleQuaternion>, SensorType>,
-    new_measurement: SensorType,
+    config: Mutex<AHRSConfig>,
+    events: Mutex<EventBus>,
 }
 
 impl AHRSFilter {
@@ -97,21 +221,193 @@ impl AHRSFilter {
         sensor_cluster: Vec<SensorType>,
         new_measurement: SensorType,
         sampling_period_millis: f64,
-    ) -> Result<Self, &'static str> {
-        AHRSFilterManager::new(sensor_cluster, sampling_period_millis)
-            .map(move |filter| Self {
-                filter: Arc::new(Mutex::new(filter)),
-                tag: tag.to_string(),
-                publishers: PublisherManager::new(&[new_measurement.clone()]),
-                new_measurement,
+        config: AHRSConfig,
+    ) -> Result<Self, ImuError> {
+        Self::from_estimators(
+            tag,
+            vec![(tag.to_string(), sensor_cluster, new_measurement)],
+            sampling_period_millis,
+            config,
+        )
+    }
+
+    /// Creates a filter estimating the orientation of several devices, given as (tag, cluster)
+    /// pairs. The orientation of each device is published under `get_output_sensor(tag)`.
+    pub fn with_clusters(
+        tag: &str,
+        clusters: Vec<(&str, Vec<SensorType>)>,
+        sampling_period_millis: f64,
+        config: AHRSConfig,
+    ) -> Result<Self, ImuError> {
+        let estimators = clusters
+            .into_iter()
+            .map(|(cluster_tag, sensor_cluster)| {
+                (
+                    cluster_tag.to_string(),
+                    sensor_cluster,
+                    Self::output_sensor(cluster_tag),
+                )
             })
-            .map_err(|_| "Invalid sensor cluster")
+            .collect();
+        Self::from_estimators(tag, estimators, sampling_period_millis, config)
+    }
+
+    fn from_estimators(
+        tag: &str,
+        clusters: Vec<(String, Vec<SensorType>, SensorType)>,
+        sampling_period_millis: f64,
+        config: AHRSConfig,
+    ) -> Result<Self, ImuError> {
+        config.validate()?;
+        let mut estimators = Vec::with_capacity(clusters.len());
+        let mut routes = HashMap::new();
+        let mut outputs = Vec::with_capacity(clusters.len());
+        for (index, (cluster_tag, sensor_cluster, new_measurement)) in
+            clusters.into_iter().enumerate()
+        {
+            for sensor_type in &sensor_cluster {
+                if routes.insert(sensor_type.clone(), index).is_some() {
+                    return Err(ImuError::InvalidSensorCluster(format!(
+                        "Sensor {} shared by several clusters",
+                        sensor_type
+                    )));
+                }
+            }
+            if outputs.contains(&new_measurement) {
+                return Err(ImuError::InvalidSensorCluster(format!(
+                    "Duplicated cluster tag {}",
+                    cluster_tag
+                )));
+            }
+            let filter = AHRSFilterManager::new(sensor_cluster, sampling_period_millis, &config)?;
+            outputs.push(new_measurement.clone());
+            estimators.push(Estimator {
+                tag: cluster_tag,
+                new_measurement,
+                filter: Mutex::new(filter),
+            });
+        }
+        if estimators.is_empty() {
+            return Err(ImuError::InvalidSensorCluster(
+                "No sensor cluster given".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            state: Arc::new(AHRSFilterState {
+                estimators,
+                routes,
+                tag: tag.to_string(),
+                publishers: PublisherManager::new(&outputs),
+                config: Mutex::new(config),
+                events: Mutex::new(EventBus::global()),
+            }),
+        })
+    }
+
+    /// Reports the divergences of the estimators to `events`, instead of the global bus.
+    pub fn with_event_bus(self, events: EventBus) -> Self {
+        *self.state.events.lock().unwrap() = events;
+        self
+    }
+
+    fn output_sensor(cluster_tag: &str) -> SensorType {
+        SensorClusterBuilder::new()
+            .with_tag(cluster_tag)
+            .other(QUATERNION_SENSOR_NAME)
+            .build()
+            .map(|cluster| cluster[0].clone())
+            .expect("Single sensor cluster is valid")
+    }
+
+    /// Returns the sensor type under which the orientation of `cluster_tag` is published.
+    pub fn get_output_sensor(&self, cluster_tag: &str) -> Option<SensorType> {
+        self.state
+            .estimators
+            .iter()
+            .find(|e| e.tag == cluster_tag)
+            .map(|e| e.new_measurement.clone())
+    }
+
+    /// Returns the current configuration.
+    pub fn get_config(&self) -> AHRSConfig {
+        *self.state.config.lock().unwrap()
+    }
+
+    /// Switches the estimation algorithm or its parameters while the filter runs. Estimators
+    /// start from their current orientation, but other state (e.g. the Mahony integral term
+    /// or the EKF covariance) is reset. The number of warm-up samples can't be changed.
+    /// Returns an error if the configuration is invalid.
+    pub fn set_estimator_config(&self, estimator: EstimatorConfig) -> Result<(), ImuError> {
+        let config = AHRSConfig {
+            estimator,
+            ..self.get_config()
+        };
+        self.reconfigure(config)
+    }
+
+    /// Makes the current orientation of every cluster the reference, so the next orientations
+    /// are published relative to it, e.g. to re-center a head tracker during a session.
+    pub fn zero_orientation(&self) {
+        for estimator in self.state.estimators.iter() {
+            estimator.filter.lock().unwrap().zero_orientation();
+        }
+    }
+
+    /// Makes the current orientation of `cluster_tag` the reference of its next orientations.
+    /// Returns an error if there is no such cluster.
+    pub fn zero_cluster_orientation(&self, cluster_tag: &str) -> Result<(), ImuError> {
+        let estimator = self
+            .state
+            .estimators
+            .iter()
+            .find(|e| e.tag == cluster_tag)
+            .ok_or_else(|| {
+                ImuError::InvalidInput(format!("Unknown cluster tag {}", cluster_tag))
+            })?;
+        estimator.filter.lock().unwrap().zero_orientation();
+        Ok(())
+    }
+
+    /// Publishes absolute orientations again, undoing `zero_orientation`.
+    pub fn clear_reference(&self) {
+        for estimator in self.state.estimators.iter() {
+            estimator.filter.lock().unwrap().reference = None;
+        }
+    }
+
+    pub fn set_gyro_fallback(&self, gyro_fallback: GyroFallback) {
+        let config = self.get_config().with_gyro_fallback(gyro_fallback);
+        let _ = self.reconfigure(config);
+    }
+
+    fn reconfigure(&self, config: AHRSConfig) -> Result<(), ImuError> {
+        config.validate()?;
+        let mut current = self.state.config.lock().unwrap();
+        for estimator in self.state.estimators.iter() {
+            let mut filter = estimator.filter.lock().unwrap();
+            if current.estimator != config.estimator {
+                filter.set_estimator(&config.estimator);
+            }
+            filter.gyro_fallback = config.gyro_fallback;
+        }
+        *current = config;
+        Ok(())
+    }
+
+    pub(crate) fn get_estimator(&self, sensor_type: &SensorType) -> Option<&Estimator> {
+        self.state
+            .routes
+            .get(sensor_type)
+            .and_then(|index| self.state.estimators.get(*index))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::estimators::MadgwickConfig;
+    use ahrs::{Ahrs, Madgwick};
     use imu_common::traits::IMUSample;
     use imu_common::types::timed::Sample3D;
     use nalgebra::Vector3;
@@ -137,17 +433,22 @@ mod tests {
             SensorType::Magnetometer(mag_id),
         ];
         let sampling_period_secs = 0.05;
-        let mut ahrs_filter =
-            AHRSFilterManager::new(sensor_cluster, sampling_period_secs * 1000.0).unwrap();
+        let config = MadgwickConfig::default();
+        let mut ahrs_filter = AHRSFilterManager::new(
+            sensor_cluster,
+            sampling_period_secs * 1000.0,
+            &AHRSConfig::default().with_beta(config.beta),
+        )
+        .unwrap();
         let n_samples = accel_readings.len();
 
-        let mut madgwick = Madgwick::new(0.05, MADGWICK_BETA);
+        let mut madgwick = Madgwick::new(0.05, config.beta);
 
         for i in 0..n_samples {
-            let gyro = Vector3::from_vec(gyro_readings[i].get_measurement().into());
-            let accel = Vector3::from_vec(accel_readings[i].get_measurement().into());
-            let mag = Vector3::from_vec(mag_readings[i].get_measurement().into());
-            let q_expected = *madgwick.update(&gyro, &accel, &mag).unwrap();
+            let gyro = Vector3::from(gyro_readings[i].get_measurement());
+            let accel = Vector3::from(accel_readings[i].get_measurement());
+            let mag = Vector3::from(mag_readings[i].get_measurement());
+            let q_expected = *Ahrs::update(&mut madgwick, &gyro, &accel, &mag).unwrap();
 
             ahrs_filter
                 .buffer
@@ -160,9 +461,267 @@ mod tests {
                 .set_samples_by_index(SensorIndex::Accelerometer.into(), accel);
 
             let buffer_clone = ahrs_filter.clone_and_clear();
-            let q_computed = ahrs_filter.update_filter(buffer_clone);
+            let q_computed = ahrs_filter.update_filter(buffer_clone).unwrap();
 
             assert_eq!(q_expected, q_computed.get_measurement().inner());
         }
     }
+
+    #[test]
+    fn test_multi_cluster_filter() {
+        use imu_common::traits::{IMUReadings, IMUSink, IMUSource};
+        use publisher::Listener;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let left = SensorType::cluster_for_tag("Left");
+        let right = SensorType::cluster_for_tag("Right");
+        assert!(AHRSFilter::with_clusters(
+            "Test",
+            vec![("Left", left.clone()), ("Other", left.clone())],
+            10.0,
+            AHRSConfig::default()
+        )
+        .is_err());
+
+        let ahrs = AHRSFilter::with_clusters(
+            "Test",
+            vec![("Left", left.clone()), ("Right", right)],
+            10.0,
+            AHRSConfig::default(),
+        )
+        .unwrap();
+        let left_output = ahrs.get_output_sensor("Left").unwrap();
+        let right_output = ahrs.get_output_sensor("Right").unwrap();
+        assert_ne!(left_output, right_output);
+
+        let counters = [Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0))];
+        for (output, counter) in [&left_output, &right_output].into_iter().zip(&counters) {
+            let counter = counter.clone();
+            let mut listener = Listener::new(
+                move |_id, readings: Arc<SensorReadings<SampleQuaternion>>| {
+                    assert_eq!(readings.get_sensor_tag(), "Left");
+                    counter.fetch_add(1, Ordering::SeqCst);
+                },
+            );
+            ahrs.register_listener(&mut listener, output).unwrap();
+        }
+
+        for i in 0..2 * config::DEFAULT_WARM_UP_SAMPLES {
+            for sensor_type in &left {
+                let sample = Sample3D::new(i as f64 * 0.01, [0.0, 0.1, 9.8]);
+                let readings = SensorReadings::from_vec("Left", sensor_type.clone(), vec![sample]);
+                ahrs.process_samples(Uuid::new_v4(), Arc::new(readings));
+            }
+        }
+
+        assert!(counters[0].load(Ordering::SeqCst) > 0);
+        assert_eq!(counters[1].load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_zero_orientation() {
+        use imu_common::traits::{IMUReadings, IMUSink, IMUSource};
+        use publisher::Listener;
+
+        let cluster = SensorType::cluster_for_tag("test_zero_orientation");
+        let output = SensorType::Other(Uuid::new_v4(), "Orientation".to_string());
+        let ahrs = AHRSFilter::new(
+            "Test",
+            cluster.clone(),
+            output.clone(),
+            10.0,
+            AHRSConfig::default(),
+        )
+        .unwrap();
+        let orientations = Arc::new(Mutex::new(Vec::new()));
+        let mut listener = Listener::new({
+            let orientations = orientations.clone();
+            move |_id, readings: Arc<SensorReadings<SampleQuaternion>>| {
+                orientations.lock().unwrap().extend(readings.get_samples());
+            }
+        });
+        ahrs.register_listener(&mut listener, &output).unwrap();
+
+        // tilted and still
+        let readings = [[0.0, 4.9, 8.5], [0.0, 0.0, 0.0], [20.0, 0.0, -40.0]];
+        let update = |n: usize| {
+            for i in 0..n {
+                for (sensor_type, reading) in cluster.iter().zip(readings) {
+                    let sample = Sample3D::new(i as f64 * 0.01, reading);
+                    let readings =
+                        SensorReadings::from_vec("Test", sensor_type.clone(), vec![sample]);
+                    ahrs.process_samples(Uuid::new_v4(), Arc::new(readings));
+                }
+            }
+        };
+        let last_angle = || {
+            let orientations = orientations.lock().unwrap();
+            orientations
+                .last()
+                .unwrap()
+                .get_measurement()
+                .inner()
+                .angle()
+        };
+
+        update(2000);
+        let absolute = last_angle();
+        assert!(absolute > 0.1);
+
+        ahrs.zero_orientation();
+        update(1);
+        assert!(last_angle() < 0.01);
+        assert!(ahrs.zero_cluster_orientation("Other").is_err());
+
+        ahrs.clear_reference();
+        update(1);
+        assert!((last_angle() - absolute).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_drop_filter() {
+        use imu_common::traits::{IMUReadings, IMUSink, IMUSource};
+        use publisher::Listener;
+        use resampler_rs::ResamplerPipeline;
+
+        let cluster = SensorType::cluster_for_tag("test_drop_filter");
+        let output = SensorType::Other(Uuid::new_v4(), "Orientation".to_string());
+        let resampler =
+            ResamplerPipeline::<SensorReadings<Sample3D>, Sample3D>::new("Test", cluster.clone());
+        let ahrs = AHRSFilter::new(
+            "Test",
+            cluster.clone(),
+            output.clone(),
+            10.0,
+            AHRSConfig::default(),
+        )
+        .unwrap();
+        ahrs.attach_listeners(&resampler, &cluster).unwrap();
+
+        let marker = Arc::new(());
+        let mut listener = Listener::new({
+            let marker = marker.clone();
+            move |_id, _readings: Arc<SensorReadings<SampleQuaternion>>| {
+                let _ = &marker;
+            }
+        });
+        ahrs.register_listener(&mut listener, &output).unwrap();
+        drop(listener);
+        assert_eq!(Arc::strong_count(&marker), 2);
+
+        // the resampler doesn't keep the filter, nor its listeners, alive
+        drop(ahrs);
+        assert_eq!(Arc::strong_count(&marker), 1);
+        let readings = SensorReadings::from_vec(
+            "Test",
+            cluster[0].clone(),
+            vec![Sample3D::new(0.0, [0.0, 0.0, 9.8])],
+        );
+        resampler.notify_listeners(cluster[0].clone(), Arc::new(readings));
+    }
+
+    #[test]
+    fn test_warm_up_and_gyro_fallback() {
+        use imu_common::traits::{IMUReadings, IMUSink, IMUSource};
+        use publisher::Listener;
+
+        let cluster = SensorType::cluster_for_tag("Test");
+        let output = SensorType::Other(Uuid::new_v4(), "Orientation".to_string());
+        let run = |config: AHRSConfig| {
+            let ahrs =
+                AHRSFilter::new("Test", cluster.clone(), output.clone(), 10.0, config).unwrap();
+            let published = Arc::new(Mutex::new(Vec::new()));
+            let mut listener = Listener::new({
+                let published = published.clone();
+                move |_id, readings: Arc<SensorReadings<SampleQuaternion>>| {
+                    let mut published = published.lock().unwrap();
+                    published.extend(readings.iter().map(|s| s.get_measurement()));
+                }
+            });
+            ahrs.register_listener(&mut listener, &output).unwrap();
+
+            // Spin around z with a valid accelerometer for 10 samples, then with a zero one
+            for i in 0..20 {
+                for sensor_type in &cluster {
+                    let measurement = match sensor_type {
+                        SensorType::Gyroscope(_) => [0.0, 0.0, 1.0],
+                        SensorType::Accelerometer(_) if i >= 10 => [0.0, 0.0, 0.0],
+                        SensorType::Accelerometer(_) => [0.0, 0.0, 9.8],
+                        _ => [20.0, 0.0, -40.0],
+                    };
+                    let sample = Sample3D::new(i as f64 * 0.01, measurement);
+                    let readings =
+                        SensorReadings::from_vec("Test", sensor_type.clone(), vec![sample]);
+                    ahrs.process_samples(Uuid::new_v4(), Arc::new(readings));
+                }
+            }
+            let published = published.lock().unwrap().clone();
+            published
+        };
+
+        let config = AHRSConfig::default().with_warm_up_samples(5);
+        assert_eq!(run(config).len(), 15);
+
+        let held = run(config.with_gyro_fallback(GyroFallback::Hold));
+        assert!(held[5..].iter().all(|q| *q == held[4]));
+
+        let integrated = run(config.with_gyro_fallback(GyroFallback::Integrate));
+        assert_eq!(integrated.len(), 15);
+        let step = integrated[14].inner().angle_to(&integrated[13].inner());
+        assert!((step - 0.01).abs() < 1e-6);
+
+        let skipped = run(config.with_gyro_fallback(GyroFallback::Skip));
+        assert_eq!(skipped.len(), 5);
+
+        assert!(AHRSFilter::new(
+            "Test",
+            cluster.clone(),
+            output.clone(),
+            10.0,
+            AHRSConfig::default().with_beta(-1.0)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_divergence_events() {
+        use imu_common::traits::{IMUReadings, IMUSink};
+        use publisher::events::EventKind;
+        use publisher::ChannelConfig;
+        use std::time::Duration;
+
+        let cluster = SensorType::cluster_for_tag("Test");
+        let output = SensorType::Other(Uuid::new_v4(), "Orientation".to_string());
+        let events = EventBus::new();
+        let receiver = events.subscribe(ChannelConfig::new());
+        let ahrs = AHRSFilter::new("Test", cluster.clone(), output, 10.0, AHRSConfig::default())
+            .unwrap()
+            .with_event_bus(events);
+
+        // the accelerometer reads zero for two runs of samples
+        for i in 0..20 {
+            for sensor_type in &cluster {
+                let measurement = match sensor_type {
+                    SensorType::Gyroscope(_) => [0.0, 0.0, 1.0],
+                    SensorType::Accelerometer(_) if (5..10).contains(&i) || i >= 15 => {
+                        [0.0, 0.0, 0.0]
+                    }
+                    SensorType::Accelerometer(_) => [0.0, 0.0, 9.8],
+                    _ => [20.0, 0.0, -40.0],
+                };
+                let sample = Sample3D::new(i as f64 * 0.01, measurement);
+                let readings = SensorReadings::from_vec("Test", sensor_type.clone(), vec![sample]);
+                ahrs.process_samples(Uuid::new_v4(), Arc::new(readings));
+            }
+        }
+
+        let divergences: Vec<String> =
+            std::iter::from_fn(|| receiver.recv_timeout(Duration::from_millis(100)))
+                .filter_map(|event| match &event.kind {
+                    EventKind::FilterDiverged { filter, .. } => Some(filter.clone()),
+                    _ => None,
+                })
+                .collect();
+        assert_eq!(divergences, vec!["Test".to_string(); 2]);
+    }
 }
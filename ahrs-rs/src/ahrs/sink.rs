@@ -1,11 +1,12 @@
-use nalgebra::Vector3;
-use publisher::{listener, Listener};
+use publisher::events::EventKind;
+use publisher::{adapters, Listener};
 use std::sync::Arc;
 use uuid::Uuid;
 
 use super::AHRSFilter;
-use super::DISCARD_N_INITIAL_SAMPLES;
-use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSink, IMUSource};
+use imu_common::types::capabilities::{SampleKind, SinkRequirements, Unit};
 use imu_common::types::sensors::{SensorReadings, SensorType};
 use imu_common::types::timed::Sample3D;
 
@@ -17,43 +18,66 @@ where
         &self,
         source: &dyn IMUSource<T, Sample3D>,
         sensor_cluster: &[SensorType],
-    ) -> Result<Vec<Uuid>, String> {
-        let mut listener = listener!(self.process_samples);
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::negotiate(self, source, sensor_cluster)?;
+        // the listener only holds the filter weakly, so it can be dropped while the source is
+        // still running
+        let mut listener = Listener::weak(&self.state, |state, id, samples: Arc<T>| {
+            IMUSink::<T, Sample3D>::process_samples(&AHRSFilter { state }, id, samples);
+        });
         let mut ids = Vec::with_capacity(sensor_cluster.len());
         for sensor_type in sensor_cluster {
             if let Ok(id) = source.register_listener(&mut listener, sensor_type) {
                 ids.push(id)
             } else {
-                return Err("Incorrect sensor".to_string());
+                return Err(ImuError::IncompatibleSensor(sensor_type.to_string()));
             }
         }
         Ok(ids)
     }
 
+    fn get_requirements(&self) -> SinkRequirements {
+        SinkRequirements::new()
+            .with_sample_kind(SampleKind::Vector3D)
+            .with_sensor_kind("accelerometer")
+            .with_sensor_kind("gyroscope")
+            .with_sensor_kind("magnetometer")
+            .with_unit("accelerometer", Unit::MetersPerSecondSquared)
+            .with_unit("gyroscope", Unit::RadiansPerSecond)
+            .with_unit("magnetometer", Unit::MicroTesla)
+    }
+
+    /// Every sample of the readings updates the estimator once, so batched readings publish as
+    /// many orientations, together.
     fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
-        // Copy sample to receiving buffer
+        // Queue samples until every sensor of the cluster has one
         let sensor_type = samples.get_sensor_type();
-        if let Some(rx_samples) = samples.get_samples().first() {
-            let mut ahrs_lock = self.filter.lock().unwrap();
-            ahrs_lock.buffer.set_samples_by_type(
-                &sensor_type,
-                Vector3::from_vec(rx_samples.get_measurement().inner().to_vec()),
-            );
-            ahrs_lock
-                .buffer
-                .set_timestamp(rx_samples.get_timestamp_secs());
-
-            if ahrs_lock.buffer.samples_ready() {
-                let buffer_clone = ahrs_lock.clone_and_clear();
-                let q = ahrs_lock.update_filter(buffer_clone);
-                let mut readings = SensorReadings::new(&self.tag, self.new_measurement.clone());
-                if ahrs_lock.n_samples > DISCARD_N_INITIAL_SAMPLES {
-                    readings.add_sample(q.clone());
-                    self.publishers
-                        .notify_listeners(self.new_measurement.clone(), Arc::new(readings));
-                }
+        let Some(estimator) = self.get_estimator(&sensor_type) else {
+            return;
+        };
+        let mut ahrs_lock = estimator.filter.lock().unwrap();
+        let mut readings = SensorReadings::new(&estimator.tag, estimator.new_measurement.clone());
+        for buffer in ahrs_lock.push_samples(&sensor_type, samples.get_samples_ref()) {
+            let q = ahrs_lock.update_filter(buffer);
+            if let Some(reason) = ahrs_lock.take_divergence() {
+                self.state
+                    .events
+                    .lock()
+                    .unwrap()
+                    .emit(EventKind::FilterDiverged {
+                        filter: estimator.tag.clone(),
+                        reason: reason.to_string(),
+                    });
             }
-            drop(ahrs_lock);
+            if let Some(q) = q.filter(|_| ahrs_lock.is_warmed_up()) {
+                readings.add_sample(q);
+            }
+        }
+        drop(ahrs_lock);
+        if !readings.get_samples_ref().is_empty() {
+            self.state
+                .publishers
+                .notify_listeners(estimator.new_measurement.clone(), Arc::new(readings));
         }
     }
 }
@@ -2,29 +2,30 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use super::AHRSFilter;
+use imu_common::errors::ImuError;
 use imu_common::traits::{IMUSource, Notifiable};
 use imu_common::types::sensors::{SensorReadings, SensorType};
 use imu_common::types::timed::SampleQuaternion;
 
 impl IMUSource<SensorReadings<SampleQuaternion>, SampleQuaternion> for AHRSFilter {
     fn get_tag(&self) -> &str {
-        self.tag.as_str()
+        self.state.tag.as_str()
     }
 
     fn get_available_sensors(&self) -> Vec<SensorType> {
-        self.publishers.get_available_publisher_types()
+        self.state.publishers.get_available_publisher_types()
     }
 
     fn unregister_listener(&self, id: Uuid) {
-        let _ = self.publishers.remove_listener(id);
+        let _ = self.state.publishers.remove_listener(id);
     }
 
     fn register_listener(
         &self,
         listener: &mut dyn Notifiable<SensorReadings<SampleQuaternion>>,
         sensor_type: &SensorType,
-    ) -> Result<Uuid, String> {
-        self.publishers.add_listener(listener, sensor_type)
+    ) -> Result<Uuid, ImuError> {
+        self.state.publishers.add_listener(listener, sensor_type)
     }
 
     fn notify_listeners(
@@ -32,6 +33,6 @@ impl IMUSource<SensorReadings<SampleQuaternion>, SampleQuaternion> for AHRSFilte
         sensor_type: SensorType,
         data: Arc<SensorReadings<SampleQuaternion>>,
     ) {
-        self.publishers.notify_listeners(sensor_type, data);
+        self.state.publishers.notify_listeners(sensor_type, data);
     }
 }
@@ -0,0 +1,156 @@
+//! Live tuning of `AHRSFilter` through a `ControlChannel`.
+//!
+//! Parameters:
+//! - `estimator`: `madgwick`, `mahony` or `ekf`. Switching the algorithm starts from its default
+//!   gains, unless they are given in the same command.
+//! - `beta` (Madgwick), `kp` and `ki` (Mahony), `gyro_noise`, `accel_noise` and `mag_noise`
+//!   (EKF): gains of the current algorithm.
+//! - `gyro_fallback`: `hold`, `integrate` or `skip`.
+
+use imu_common::errors::ImuError;
+use imu_common::traits::Tunable;
+use imu_common::types::registry::SourceParams;
+
+use super::config::GyroFallback;
+use super::AHRSFilter;
+use crate::estimators::{EkfConfig, EstimatorConfig, MadgwickConfig, MahonyConfig};
+
+impl Tunable for AHRSFilter {
+    fn get_parameters(&self) -> SourceParams {
+        let config = self.get_config();
+        let params = SourceParams::new().with(
+            "gyro_fallback",
+            match config.gyro_fallback {
+                GyroFallback::Hold => "hold",
+                GyroFallback::Integrate => "integrate",
+                GyroFallback::Skip => "skip",
+            },
+        );
+        match config.estimator {
+            EstimatorConfig::Madgwick(c) => {
+                params.with("estimator", "madgwick").with("beta", c.beta)
+            }
+            EstimatorConfig::Mahony(c) => params
+                .with("estimator", "mahony")
+                .with("kp", c.kp)
+                .with("ki", c.ki),
+            EstimatorConfig::Ekf(c) => params
+                .with("estimator", "ekf")
+                .with("gyro_noise", c.gyro_noise)
+                .with("accel_noise", c.accel_noise)
+                .with("mag_noise", c.mag_noise),
+        }
+    }
+
+    fn set_parameters(&self, params: &SourceParams) -> Result<(), ImuError> {
+        let mut config = self.get_config();
+        if params.contains("estimator") {
+            config.estimator = match params.get_str("estimator")? {
+                "madgwick" => EstimatorConfig::Madgwick(MadgwickConfig::default()),
+                "mahony" => EstimatorConfig::Mahony(MahonyConfig::default()),
+                "ekf" => EstimatorConfig::Ekf(EkfConfig::default()),
+                other => {
+                    return Err(ImuError::InvalidParameter(format!(
+                        "Unknown estimator {}",
+                        other
+                    )))
+                }
+            };
+        }
+        if params.contains("gyro_fallback") {
+            config.gyro_fallback = match params.get_str("gyro_fallback")? {
+                "hold" => GyroFallback::Hold,
+                "integrate" => GyroFallback::Integrate,
+                "skip" => GyroFallback::Skip,
+                other => {
+                    return Err(ImuError::InvalidParameter(format!(
+                        "Unknown gyro fallback {}",
+                        other
+                    )))
+                }
+            };
+        }
+        for (name, _) in params.iter() {
+            let gain = match (&mut config.estimator, name) {
+                (_, "estimator" | "gyro_fallback") => continue,
+                (EstimatorConfig::Madgwick(c), "beta") => &mut c.beta,
+                (EstimatorConfig::Mahony(c), "kp") => &mut c.kp,
+                (EstimatorConfig::Mahony(c), "ki") => &mut c.ki,
+                (EstimatorConfig::Ekf(c), "gyro_noise") => &mut c.gyro_noise,
+                (EstimatorConfig::Ekf(c), "accel_noise") => &mut c.accel_noise,
+                (EstimatorConfig::Ekf(c), "mag_noise") => &mut c.mag_noise,
+                _ => {
+                    return Err(ImuError::InvalidParameter(format!(
+                        "Unknown parameter {} for {:?}",
+                        name, config.estimator
+                    )))
+                }
+            };
+            *gain = params.get_float(name)?;
+        }
+        self.reconfigure(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ahrs::config::AHRSConfig;
+    use imu_common::types::sensors::SensorType;
+    use uuid::Uuid;
+
+    fn filter() -> AHRSFilter {
+        AHRSFilter::new(
+            "Test",
+            SensorType::cluster_for_tag("Test"),
+            SensorType::Other(Uuid::new_v4(), "Orientation".to_string()),
+            10.0,
+            AHRSConfig::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_tune_estimator() {
+        let ahrs = filter();
+        assert_eq!(ahrs.get_parameters().get_str("estimator"), Ok("madgwick"));
+
+        ahrs.set_parameters(&SourceParams::new().with("beta", 0.3))
+            .unwrap();
+        assert_eq!(
+            ahrs.get_config().estimator,
+            EstimatorConfig::Madgwick(MadgwickConfig::new(0.3))
+        );
+
+        let params = SourceParams::new()
+            .with("estimator", "mahony")
+            .with("ki", 0.01)
+            .with("gyro_fallback", "skip");
+        ahrs.set_parameters(&params).unwrap();
+        let config = ahrs.get_config();
+        assert_eq!(
+            config.estimator,
+            EstimatorConfig::Mahony(MahonyConfig::new(MahonyConfig::default().kp, 0.01))
+        );
+        assert_eq!(config.gyro_fallback, GyroFallback::Skip);
+        assert_eq!(ahrs.get_parameters().get_float("ki"), Ok(0.01));
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let ahrs = filter();
+        let invalid = [
+            SourceParams::new().with("kp", 1.0),
+            SourceParams::new().with("beta", -1.0),
+            SourceParams::new().with("beta", "high"),
+            SourceParams::new().with("estimator", "kalman"),
+            SourceParams::new()
+                .with("gyro_fallback", "integrate")
+                .with("gain", 1.0),
+        ];
+        for params in invalid {
+            assert!(ahrs.set_parameters(&params).is_err(), "{:?}", params);
+        }
+        assert_eq!(ahrs.get_config(), AHRSConfig::default());
+    }
+}
@@ -0,0 +1,314 @@
+//! Module compare
+//!
+//! A/B comparison of two orientation pipelines. Both pipelines are attached to the same input
+//! source (e.g. a `ReplaySource`), and [`OrientationComparator`] listens to their outputs. It
+//! republishes both orientations plus the relative rotation between them, and keeps running
+//! statistics of the angular difference.
+
+pub(crate) mod source;
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSource};
+use imu_common::types::sensors::{SensorClusterBuilder, SensorReadings, SensorType};
+use imu_common::types::timed::SampleQuaternion;
+use publisher::{Listener, PublisherManager};
+
+const DEFAULT_TOLERANCE_SECS: f64 = 1e-3;
+const MAX_PENDING_SAMPLES: usize = 1000;
+
+/// Output streams of an [`OrientationComparator`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComparisonChannel {
+    /// Orientation estimated by the first pipeline.
+    A,
+    /// Orientation estimated by the second pipeline.
+    B,
+    /// Rotation taking orientation A to orientation B.
+    Difference,
+}
+
+impl ComparisonChannel {
+    fn name(&self) -> &'static str {
+        match self {
+            ComparisonChannel::A => "A",
+            ComparisonChannel::B => "B",
+            ComparisonChannel::Difference => "Difference",
+        }
+    }
+}
+
+/// Statistics of the angle between both orientations, in radians.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ComparisonStats {
+    pub n_pairs: usize,
+    pub mean_angle: f64,
+    pub rms_angle: f64,
+    pub max_angle: f64,
+}
+
+#[derive(Default)]
+struct Pairing {
+    pending: [VecDeque<SampleQuaternion>; 2],
+    n_pairs: usize,
+    sum_angle: f64,
+    sum_squared_angle: f64,
+    max_angle: f64,
+}
+
+/// Compares the orientations published by two pipelines fed with the same input.
+///
+/// Samples of both pipelines are paired by timestamp. Samples with no counterpart within the
+/// tolerance are published on their own channel, but not compared.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use ahrs_rs::{AHRSFilter, ComparisonChannel, EstimatorConfig, MahonyConfig, OrientationComparator};
+/// # use imu_common::types::sensors::{SensorClusterBuilder, SensorType};
+/// # use uuid::Uuid;
+/// let cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
+/// let output = SensorType::Other(Uuid::new_v4(), "Orientation".to_string());
+/// let madgwick = EstimatorConfig::default();
+/// let mahony = EstimatorConfig::Mahony(MahonyConfig::default());
+/// let ahrs_a = AHRSFilter::new("A", cluster.clone(), output.clone(), 10.0, madgwick.into()).unwrap();
+/// let ahrs_b = AHRSFilter::new("B", cluster.clone(), output.clone(), 10.0, mahony.into()).unwrap();
+/// // ... attach both filters to the same source ...
+///
+/// let comparator = OrientationComparator::new("AB");
+/// comparator.attach(&ahrs_a, &output, &ahrs_b, &output).unwrap();
+/// let difference = comparator.get_output_sensor(ComparisonChannel::Difference);
+/// ```
+#[derive(Clone)]
+pub struct OrientationComparator {
+    tag: String,
+    outputs: [SensorType; 3],
+    tolerance_secs: f64,
+    pairing: Arc<Mutex<Pairing>>,
+    publishers: PublisherManager<SensorReadings<SampleQuaternion>, SensorType>,
+}
+
+impl OrientationComparator {
+    pub fn new(tag: &str) -> Self {
+        let outputs = [
+            ComparisonChannel::A,
+            ComparisonChannel::B,
+            ComparisonChannel::Difference,
+        ]
+        .map(|channel| Self::output_sensor(tag, channel));
+        Self {
+            tag: tag.to_string(),
+            publishers: PublisherManager::new(&outputs),
+            outputs,
+            tolerance_secs: DEFAULT_TOLERANCE_SECS,
+            pairing: Arc::new(Mutex::new(Pairing::default())),
+        }
+    }
+
+    /// Maximum timestamp difference between two samples for them to be compared.
+    pub fn with_tolerance(mut self, tolerance_secs: f64) -> Self {
+        self.tolerance_secs = tolerance_secs.max(0.0);
+        self
+    }
+
+    fn output_sensor(tag: &str, channel: ComparisonChannel) -> SensorType {
+        SensorClusterBuilder::new()
+            .with_tag(tag)
+            .other(channel.name())
+            .build()
+            .map(|cluster| cluster[0].clone())
+            .expect("Single sensor cluster is valid")
+    }
+
+    /// Returns the sensor type under which `channel` is published.
+    pub fn get_output_sensor(&self, channel: ComparisonChannel) -> SensorType {
+        match channel {
+            ComparisonChannel::A => self.outputs[0].clone(),
+            ComparisonChannel::B => self.outputs[1].clone(),
+            ComparisonChannel::Difference => self.outputs[2].clone(),
+        }
+    }
+
+    /// Listens to `sensor_a` of `pipeline_a` and `sensor_b` of `pipeline_b`.
+    pub fn attach(
+        &self,
+        pipeline_a: &dyn IMUSource<SensorReadings<SampleQuaternion>, SampleQuaternion>,
+        sensor_a: &SensorType,
+        pipeline_b: &dyn IMUSource<SensorReadings<SampleQuaternion>, SampleQuaternion>,
+        sensor_b: &SensorType,
+    ) -> Result<Vec<Uuid>, ImuError> {
+        let mut ids = Vec::with_capacity(2);
+        for (side, pipeline, sensor_type) in [(0, pipeline_a, sensor_a), (1, pipeline_b, sensor_b)]
+        {
+            let comparator = self.clone();
+            let mut listener = Listener::new(
+                move |_id, readings: Arc<SensorReadings<SampleQuaternion>>| {
+                    comparator.process_samples(side, &readings);
+                },
+            );
+            ids.push(pipeline.register_listener(&mut listener, sensor_type)?);
+        }
+        Ok(ids)
+    }
+
+    pub fn get_stats(&self) -> ComparisonStats {
+        let pairing = self.pairing.lock().unwrap();
+        if pairing.n_pairs == 0 {
+            return ComparisonStats::default();
+        }
+        let n = pairing.n_pairs as f64;
+        ComparisonStats {
+            n_pairs: pairing.n_pairs,
+            mean_angle: pairing.sum_angle / n,
+            rms_angle: (pairing.sum_squared_angle / n).sqrt(),
+            max_angle: pairing.max_angle,
+        }
+    }
+
+    fn process_samples(&self, side: usize, readings: &SensorReadings<SampleQuaternion>) {
+        let samples = readings.get_samples();
+        if samples.is_empty() {
+            return;
+        }
+        self.publish(self.outputs[side].clone(), samples.clone());
+
+        let mut differences = Vec::new();
+        let mut pairing = self.pairing.lock().unwrap();
+        pairing.pending[side].extend(samples);
+        while let (Some(a), Some(b)) = (pairing.pending[0].front(), pairing.pending[1].front()) {
+            let (ta, tb) = (a.get_timestamp_secs(), b.get_timestamp_secs());
+            if (ta - tb).abs() <= self.tolerance_secs {
+                let (qa, qb) = (a.get_measurement().inner(), b.get_measurement().inner());
+                let angle = qa.angle_to(&qb);
+                pairing.n_pairs += 1;
+                pairing.sum_angle += angle;
+                pairing.sum_squared_angle += angle * angle;
+                pairing.max_angle = pairing.max_angle.max(angle);
+                differences.push(SampleQuaternion::from_unit_quaternion(
+                    ta.max(tb),
+                    qa.rotation_to(&qb).into(),
+                ));
+                pairing.pending[0].pop_front();
+                pairing.pending[1].pop_front();
+            } else if ta < tb {
+                pairing.pending[0].pop_front();
+            } else {
+                pairing.pending[1].pop_front();
+            }
+        }
+        // A silent pipeline shouldn't make the other one grow without bound
+        let pending = &mut pairing.pending[side];
+        if pending.len() > MAX_PENDING_SAMPLES {
+            pending.drain(..pending.len() - MAX_PENDING_SAMPLES);
+        }
+        drop(pairing);
+
+        if !differences.is_empty() {
+            self.publish(self.outputs[2].clone(), differences);
+        }
+    }
+
+    fn publish(&self, sensor_type: SensorType, samples: Vec<SampleQuaternion>) {
+        let readings = SensorReadings::from_vec(&self.tag, sensor_type.clone(), samples);
+        self.publishers
+            .notify_listeners(sensor_type, Arc::new(readings));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::traits::Notifiable;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[derive(Clone)]
+    struct TestPipeline {
+        output: SensorType,
+        publishers: PublisherManager<SensorReadings<SampleQuaternion>, SensorType>,
+    }
+
+    impl TestPipeline {
+        fn new() -> Self {
+            let output = SensorType::Other(Uuid::new_v4(), "Orientation".to_string());
+            Self {
+                publishers: PublisherManager::new(std::slice::from_ref(&output)),
+                output,
+            }
+        }
+
+        fn publish(&self, timestamp: f64, yaw: f64) {
+            let q = nalgebra::UnitQuaternion::from_euler_angles(0.0, 0.0, yaw);
+            let sample = SampleQuaternion::from_unit_quaternion(timestamp, q.into());
+            let readings = SensorReadings::from_vec("Test", self.output.clone(), vec![sample]);
+            self.publishers
+                .notify_listeners(self.output.clone(), Arc::new(readings));
+        }
+    }
+
+    impl IMUSource<SensorReadings<SampleQuaternion>, SampleQuaternion> for TestPipeline {
+        fn get_tag(&self) -> &str {
+            "Test"
+        }
+        fn get_available_sensors(&self) -> Vec<SensorType> {
+            vec![self.output.clone()]
+        }
+        fn unregister_listener(&self, id: Uuid) {
+            let _ = self.publishers.remove_listener(id);
+        }
+        fn register_listener(
+            &self,
+            listener: &mut dyn Notifiable<SensorReadings<SampleQuaternion>>,
+            sensor_type: &SensorType,
+        ) -> Result<Uuid, ImuError> {
+            self.publishers.add_listener(listener, sensor_type)
+        }
+        fn notify_listeners(
+            &self,
+            sensor_type: SensorType,
+            data: Arc<SensorReadings<SampleQuaternion>>,
+        ) {
+            self.publishers.notify_listeners(sensor_type, data);
+        }
+    }
+
+    #[test]
+    fn test_compare_pipelines() {
+        let (a, b) = (TestPipeline::new(), TestPipeline::new());
+        let comparator = OrientationComparator::new("AB");
+        comparator.attach(&a, &a.output, &b, &b.output).unwrap();
+
+        let differences = Arc::new(Mutex::new(Vec::new()));
+        let mut listener = Listener::new({
+            let differences = differences.clone();
+            move |_id, readings: Arc<SensorReadings<SampleQuaternion>>| {
+                differences.lock().unwrap().extend(readings.get_samples());
+            }
+        });
+        comparator
+            .register_listener(
+                &mut listener,
+                &comparator.get_output_sensor(ComparisonChannel::Difference),
+            )
+            .unwrap();
+
+        a.publish(0.0, 0.0);
+        b.publish(0.0, 0.0);
+        // B misses a sample, which is not compared
+        a.publish(0.1, 0.0);
+        b.publish(0.2, FRAC_PI_2);
+        a.publish(0.2, 0.0);
+
+        let stats = comparator.get_stats();
+        assert_eq!(stats.n_pairs, 2);
+        assert!((stats.max_angle - FRAC_PI_2).abs() < 1e-9);
+        assert!((stats.mean_angle - FRAC_PI_2 / 2.0).abs() < 1e-9);
+
+        let differences = differences.lock().unwrap();
+        assert_eq!(differences.len(), 2);
+        let (_, _, yaw) = differences[1].get_measurement().inner().euler_angles();
+        assert!((yaw - FRAC_PI_2).abs() < 1e-9);
+    }
+}
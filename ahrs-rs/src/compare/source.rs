@@ -0,0 +1,38 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::OrientationComparator;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUSource, Notifiable};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::SampleQuaternion;
+
+impl IMUSource<SensorReadings<SampleQuaternion>, SampleQuaternion> for OrientationComparator {
+    fn get_tag(&self) -> &str {
+        self.tag.as_str()
+    }
+
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.publishers.get_available_publisher_types()
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<SampleQuaternion>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(
+        &self,
+        sensor_type: SensorType,
+        data: Arc<SensorReadings<SampleQuaternion>>,
+    ) {
+        self.publishers.notify_listeners(sensor_type, data);
+    }
+}
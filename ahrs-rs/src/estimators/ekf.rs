@@ -0,0 +1,210 @@
+use nalgebra::{DMatrix, DVector, Matrix4, Quaternion, SMatrix, UnitQuaternion, Vector3, Vector4};
+
+use super::OrientationEstimator;
+
+const JACOBIAN_STEP: f64 = 1e-6;
+
+/// Extended Kalman filter parameters. Noise levels are standard deviations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EkfConfig {
+    /// Gyroscope noise, in rad/s.
+    pub gyro_noise: f64,
+    /// Noise of the normalized accelerometer reading. Larger values trust the gyroscope more
+    /// during linear accelerations.
+    pub accel_noise: f64,
+    /// Noise of the normalized magnetometer reading. Larger values reject more magnetic
+    /// disturbances.
+    pub mag_noise: f64,
+    /// Initial uncertainty of every quaternion component.
+    pub initial_uncertainty: f64,
+}
+
+impl Default for EkfConfig {
+    fn default() -> Self {
+        Self {
+            gyro_noise: 0.05,
+            accel_noise: 0.1,
+            mag_noise: 0.2,
+            initial_uncertainty: 1.0,
+        }
+    }
+}
+
+/// Extended Kalman filter estimating the orientation quaternion.
+///
+/// The gyroscope drives the prediction step, and the directions of gravity and of the magnetic
+/// field correct it. Readings with zero norm are skipped, so the filter falls back to
+/// accelerometer only or gyroscope only updates.
+#[derive(Clone, Debug)]
+pub struct Ekf {
+    sampling_period_secs: f64,
+    config: EkfConfig,
+    /// (w, x, y, z)
+    state: Vector4<f64>,
+    covariance: Matrix4<f64>,
+}
+
+impl Ekf {
+    pub fn new(sampling_period_secs: f64, config: EkfConfig) -> Self {
+        Self {
+            sampling_period_secs,
+            config,
+            state: Vector4::new(1.0, 0.0, 0.0, 0.0),
+            covariance: Matrix4::identity() * config.initial_uncertainty.powi(2),
+        }
+    }
+
+    fn predict(&mut self, gyro: &Vector3<f64>) {
+        let half_dt = 0.5 * self.sampling_period_secs;
+        let (wx, wy, wz) = (gyro.x, gyro.y, gyro.z);
+        #[rustfmt::skip]
+        let omega = Matrix4::new(
+            0.0, -wx, -wy, -wz,
+            wx,  0.0,  wz, -wy,
+            wy, -wz,  0.0,  wx,
+            wz,  wy, -wx,  0.0,
+        );
+        let f = Matrix4::identity() + omega * half_dt;
+
+        // gyroscope noise enters through q' = q * (0, w) / 2
+        let (w, x, y, z) = (self.state[0], self.state[1], self.state[2], self.state[3]);
+        #[rustfmt::skip]
+        let g = SMatrix::<f64, 4, 3>::new(
+            -x, -y, -z,
+             w, -z,  y,
+             z,  w, -x,
+            -y,  x,  w,
+        ) * half_dt;
+        let q = g * g.transpose() * self.config.gyro_noise.powi(2);
+
+        self.state = (f * self.state).normalize();
+        self.covariance = f * self.covariance * f.transpose() + q;
+    }
+
+    fn correct(&mut self, accel: &Vector3<f64>, mag: &Vector3<f64>) {
+        let accel = accel.try_normalize(0.0);
+        let mag = mag.try_normalize(0.0);
+        let Some(accel) = accel else {
+            return;
+        };
+
+        // Magnetic field reference, from the current estimate, with no east component
+        let mag_reference = mag.map(|mag| {
+            let h = to_unit_quaternion(&self.state) * mag;
+            Vector3::new(h.x.hypot(h.y), 0.0, h.z)
+        });
+
+        let measure = |state: &Vector4<f64>| {
+            let inverse = to_unit_quaternion(state).inverse();
+            let mut h = (inverse * Vector3::z()).as_slice().to_vec();
+            if let Some(reference) = mag_reference {
+                h.extend((inverse * reference).as_slice());
+            }
+            DVector::from_vec(h)
+        };
+
+        let mut measurement = accel.as_slice().to_vec();
+        let mut noise = vec![self.config.accel_noise.powi(2); 3];
+        if let Some(mag) = mag {
+            measurement.extend(mag.as_slice());
+            noise.extend([self.config.mag_noise.powi(2); 3]);
+        }
+        let measurement = DVector::from_vec(measurement);
+        let predicted = measure(&self.state);
+
+        // Central differences, as the measurement model includes the quaternion normalization
+        let mut jacobian = DMatrix::zeros(predicted.len(), 4);
+        for i in 0..4 {
+            let mut step = Vector4::zeros();
+            step[i] = JACOBIAN_STEP;
+            let derivative = (measure(&(self.state + step)) - measure(&(self.state - step)))
+                / (2.0 * JACOBIAN_STEP);
+            jacobian.set_column(i, &derivative);
+        }
+
+        let covariance = DMatrix::from_column_slice(4, 4, self.covariance.as_slice());
+        let innovation_covariance = &jacobian * &covariance * jacobian.transpose()
+            + DMatrix::from_diagonal(&DVector::from_vec(noise));
+        let Some(inverse) = innovation_covariance.try_inverse() else {
+            return;
+        };
+        let gain = &covariance * jacobian.transpose() * inverse;
+
+        let correction = &gain * (measurement - predicted);
+        self.state = (self.state + Vector4::from_column_slice(correction.as_slice())).normalize();
+        let covariance = (DMatrix::identity(4, 4) - &gain * &jacobian) * covariance;
+        self.covariance = Matrix4::from_column_slice(covariance.as_slice());
+    }
+}
+
+fn to_unit_quaternion(state: &Vector4<f64>) -> UnitQuaternion<f64> {
+    UnitQuaternion::from_quaternion(Quaternion::new(state[0], state[1], state[2], state[3]))
+}
+
+impl OrientationEstimator for Ekf {
+    fn update(
+        &mut self,
+        gyro: &Vector3<f64>,
+        accel: &Vector3<f64>,
+        mag: &Vector3<f64>,
+    ) -> Result<UnitQuaternion<f64>, &'static str> {
+        if !(gyro.iter().chain(accel.iter()).chain(mag.iter())).all(|v| v.is_finite()) {
+            return Err("Non finite reading");
+        }
+        self.predict(gyro);
+        self.correct(accel, mag);
+        Ok(self.get_orientation())
+    }
+
+    fn update_gyro(&mut self, gyro: &Vector3<f64>) -> UnitQuaternion<f64> {
+        self.predict(gyro);
+        self.get_orientation()
+    }
+
+    fn get_orientation(&self) -> UnitQuaternion<f64> {
+        to_unit_quaternion(&self.state)
+    }
+
+    fn set_orientation(&mut self, orientation: UnitQuaternion<f64>) {
+        let q = orientation.quaternion();
+        self.state = Vector4::new(q.w, q.i, q.j, q.k);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::estimators::tests::static_readings;
+
+    #[test]
+    fn test_gyro_only_integration() {
+        let mut ekf = Ekf::new(0.01, EkfConfig::default());
+        let gyro = Vector3::new(0.0, 0.0, 1.0);
+        for _ in 0..100 {
+            ekf.update(&gyro, &Vector3::zeros(), &Vector3::zeros())
+                .unwrap();
+        }
+        let expected = UnitQuaternion::from_euler_angles(0.0, 0.0, 1.0);
+        assert!(ekf.get_orientation().angle_to(&expected) < 1e-3);
+    }
+
+    #[test]
+    fn test_tracks_rotation() {
+        let mut ekf = Ekf::new(0.01, EkfConfig::default());
+        let gyro = Vector3::new(0.2, 0.0, 0.5);
+        let mut truth = UnitQuaternion::identity();
+        for _ in 0..1000 {
+            truth *= UnitQuaternion::from_scaled_axis(gyro * 0.01);
+            let (accel, mag) = static_readings(&truth);
+            ekf.update(&gyro, &accel, &mag).unwrap();
+        }
+        assert!(ekf.get_orientation().angle_to(&truth) < 1e-2);
+        assert!(ekf
+            .update(
+                &Vector3::new(f64::NAN, 0.0, 0.0),
+                &Vector3::z(),
+                &Vector3::x()
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,297 @@
+//! Module estimators
+//!
+//! Orientation estimation algorithms used by `AHRSFilter`. Every algorithm implements
+//! [`OrientationEstimator`], and is selected and configured with an [`EstimatorConfig`]:
+//!
+//! - [`EstimatorConfig::Madgwick`]: gradient descent filter (default).
+//! - [`EstimatorConfig::Mahony`]: nonlinear complementary filter with PI feedback.
+//! - [`EstimatorConfig::Ekf`]: extended Kalman filter on the orientation quaternion.
+
+mod ekf;
+
+use ahrs::{Ahrs, Madgwick, Mahony};
+use imu_common::errors::ImuError;
+use nalgebra::{UnitQuaternion, Vector3};
+
+pub use ekf::{Ekf, EkfConfig};
+
+/// Estimates orientation from 9 axis readings sampled at a fixed rate.
+pub trait OrientationEstimator: Send {
+    /// Updates the estimate with a gyroscope (rad/s), accelerometer and magnetometer reading,
+    /// and returns the new orientation. A zero magnetometer reading, published by sources
+    /// without magnetometer, updates the estimate from the gyroscope and accelerometer alone.
+    fn update(
+        &mut self,
+        gyro: &Vector3<f64>,
+        accel: &Vector3<f64>,
+        mag: &Vector3<f64>,
+    ) -> Result<UnitQuaternion<f64>, &'static str>;
+
+    /// Updates the estimate integrating a gyroscope (rad/s) reading alone, and returns the new
+    /// orientation.
+    fn update_gyro(&mut self, gyro: &Vector3<f64>) -> UnitQuaternion<f64>;
+
+    /// Returns the current orientation.
+    fn get_orientation(&self) -> UnitQuaternion<f64>;
+
+    /// Overrides the current orientation, used to carry the estimate over when the estimator is
+    /// replaced.
+    fn set_orientation(&mut self, orientation: UnitQuaternion<f64>);
+}
+
+/// Madgwick filter parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MadgwickConfig {
+    /// Gradient descent gain. Larger values converge faster but let more accelerometer and
+    /// magnetometer noise through.
+    pub beta: f64,
+}
+
+impl Default for MadgwickConfig {
+    fn default() -> Self {
+        Self { beta: 0.08 }
+    }
+}
+
+impl MadgwickConfig {
+    pub fn new(beta: f64) -> Self {
+        Self { beta }
+    }
+}
+
+/// Mahony filter parameters.
+///
+/// The accelerometer and magnetometer error is fed back to the gyroscope rate through a PI
+/// controller. Lowering `kp` trusts the gyroscope more, which helps in magnetically noisy
+/// environments, and a small `ki` removes the drift caused by gyroscope bias.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MahonyConfig {
+    /// Proportional gain.
+    pub kp: f64,
+    /// Integral gain, used to cancel the gyroscope bias.
+    pub ki: f64,
+}
+
+impl Default for MahonyConfig {
+    fn default() -> Self {
+        Self { kp: 0.5, ki: 0.0 }
+    }
+}
+
+impl MahonyConfig {
+    pub fn new(kp: f64, ki: f64) -> Self {
+        Self { kp, ki }
+    }
+}
+
+/// Orientation estimation algorithm and its parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EstimatorConfig {
+    Madgwick(MadgwickConfig),
+    Mahony(MahonyConfig),
+    Ekf(EkfConfig),
+}
+
+impl Default for EstimatorConfig {
+    fn default() -> Self {
+        EstimatorConfig::Madgwick(MadgwickConfig::default())
+    }
+}
+
+impl EstimatorConfig {
+    /// Checks that gains are non negative and noise levels positive.
+    pub fn validate(&self) -> Result<(), ImuError> {
+        let valid_gain = |gain: f64| gain.is_finite() && gain >= 0.0;
+        let valid_noise = |noise: f64| noise.is_finite() && noise > 0.0;
+        let valid = match self {
+            EstimatorConfig::Madgwick(config) => valid_gain(config.beta),
+            EstimatorConfig::Mahony(config) => valid_gain(config.kp) && valid_gain(config.ki),
+            EstimatorConfig::Ekf(config) => [
+                config.gyro_noise,
+                config.accel_noise,
+                config.mag_noise,
+                config.initial_uncertainty,
+            ]
+            .into_iter()
+            .all(valid_noise),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(ImuError::InvalidParameter(
+                "Invalid estimator configuration".to_string(),
+            ))
+        }
+    }
+
+    /// Creates an estimator updated every `sampling_period_secs` seconds.
+    pub fn build(&self, sampling_period_secs: f64) -> Box<dyn OrientationEstimator> {
+        match self {
+            EstimatorConfig::Madgwick(config) => {
+                Box::new(Madgwick::new(sampling_period_secs, config.beta))
+            }
+            EstimatorConfig::Mahony(config) => {
+                Box::new(Mahony::new(sampling_period_secs, config.kp, config.ki))
+            }
+            EstimatorConfig::Ekf(config) => Box::new(Ekf::new(sampling_period_secs, *config)),
+        }
+    }
+}
+
+impl OrientationEstimator for Madgwick<f64> {
+    fn update(
+        &mut self,
+        gyro: &Vector3<f64>,
+        accel: &Vector3<f64>,
+        mag: &Vector3<f64>,
+    ) -> Result<UnitQuaternion<f64>, &'static str> {
+        let result = if mag.iter().all(|v| *v == 0.0) {
+            Ahrs::update_imu(self, gyro, accel)
+        } else {
+            Ahrs::update(self, gyro, accel, mag)
+        };
+        result
+            .copied()
+            .map_err(|_| "Invalid accelerometer or magnetometer reading")
+    }
+
+    fn update_gyro(&mut self, gyro: &Vector3<f64>) -> UnitQuaternion<f64> {
+        *Ahrs::update_gyro(self, gyro)
+    }
+
+    fn get_orientation(&self) -> UnitQuaternion<f64> {
+        self.quat
+    }
+
+    fn set_orientation(&mut self, orientation: UnitQuaternion<f64>) {
+        self.quat = orientation;
+    }
+}
+
+impl OrientationEstimator for Mahony<f64> {
+    fn update(
+        &mut self,
+        gyro: &Vector3<f64>,
+        accel: &Vector3<f64>,
+        mag: &Vector3<f64>,
+    ) -> Result<UnitQuaternion<f64>, &'static str> {
+        let result = if mag.iter().all(|v| *v == 0.0) {
+            Ahrs::update_imu(self, gyro, accel)
+        } else {
+            Ahrs::update(self, gyro, accel, mag)
+        };
+        result
+            .copied()
+            .map_err(|_| "Invalid accelerometer or magnetometer reading")
+    }
+
+    fn update_gyro(&mut self, gyro: &Vector3<f64>) -> UnitQuaternion<f64> {
+        *Ahrs::update_gyro(self, gyro)
+    }
+
+    fn get_orientation(&self) -> UnitQuaternion<f64> {
+        self.quat
+    }
+
+    fn set_orientation(&mut self, orientation: UnitQuaternion<f64>) {
+        self.quat = orientation;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Readings of a static sensor with orientation `q`, with gravity along z and the magnetic
+    /// field pointing north and down.
+    pub(crate) fn static_readings(q: &UnitQuaternion<f64>) -> (Vector3<f64>, Vector3<f64>) {
+        let gravity = Vector3::new(0.0, 0.0, 9.8);
+        let field = Vector3::new(20.0, 0.0, -40.0);
+        (q.inverse() * gravity, q.inverse() * field)
+    }
+
+    #[test]
+    fn test_estimators_converge() {
+        let expected = UnitQuaternion::from_euler_angles(0.3, -0.2, 1.0);
+        let (accel, mag) = static_readings(&expected);
+        let configs = [
+            EstimatorConfig::Madgwick(MadgwickConfig::new(0.5)),
+            EstimatorConfig::Mahony(MahonyConfig::new(2.0, 0.0)),
+            EstimatorConfig::Ekf(EkfConfig::default()),
+        ];
+        for config in configs {
+            let mut estimator = config.build(0.01);
+            for _ in 0..5000 {
+                estimator.update(&Vector3::zeros(), &accel, &mag).unwrap();
+            }
+            let error = estimator.get_orientation().angle_to(&expected);
+            assert!(error < 1e-2, "{:?} error {}", config, error);
+        }
+    }
+
+    #[test]
+    fn test_set_orientation() {
+        let expected = UnitQuaternion::from_euler_angles(0.3, -0.2, 1.0);
+        for config in [
+            EstimatorConfig::default(),
+            EstimatorConfig::Mahony(MahonyConfig::default()),
+            EstimatorConfig::Ekf(EkfConfig::default()),
+        ] {
+            let mut estimator = config.build(0.01);
+            estimator.set_orientation(expected);
+            assert!(estimator.get_orientation().angle_to(&expected) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(EstimatorConfig::Mahony(MahonyConfig::new(2.0, 0.01))
+            .validate()
+            .is_ok());
+        assert!(EstimatorConfig::Mahony(MahonyConfig::new(-1.0, 0.0))
+            .validate()
+            .is_err());
+        assert!(EstimatorConfig::Madgwick(MadgwickConfig::new(f64::NAN))
+            .validate()
+            .is_err());
+        let ekf = EkfConfig {
+            mag_noise: 0.0,
+            ..Default::default()
+        };
+        assert!(EstimatorConfig::Ekf(ekf).validate().is_err());
+    }
+
+    #[test]
+    fn test_invalid_readings() {
+        for config in [
+            EstimatorConfig::default(),
+            EstimatorConfig::Mahony(MahonyConfig::default()),
+        ] {
+            let mut estimator = config.build(0.01);
+            let result = estimator.update(&Vector3::zeros(), &Vector3::zeros(), &Vector3::x());
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn test_without_magnetometer() {
+        let expected = UnitQuaternion::from_euler_angles(0.3, -0.2, 0.0);
+        let (accel, _) = static_readings(&expected);
+        for config in [
+            EstimatorConfig::Madgwick(MadgwickConfig::new(0.5)),
+            EstimatorConfig::Mahony(MahonyConfig::new(2.0, 0.0)),
+            EstimatorConfig::Ekf(EkfConfig::default()),
+        ] {
+            let mut estimator = config.build(0.01);
+            for _ in 0..5000 {
+                estimator
+                    .update(&Vector3::zeros(), &accel, &Vector3::zeros())
+                    .unwrap();
+            }
+            // only roll and pitch are observable
+            let (roll, pitch, _) = estimator.get_orientation().euler_angles();
+            assert!((roll - 0.3).abs() < 1e-2, "{:?} roll {}", config, roll);
+            assert!((pitch + 0.2).abs() < 1e-2, "{:?} pitch {}", config, pitch);
+        }
+    }
+}
@@ -1,5 +1,12 @@
 pub mod ahrs;
+pub mod compare;
+pub mod estimators;
 pub(crate) mod utils;
 
 pub use ahrs::buffer::AHRSInputSamples;
-pub use ahrs::AHRSFilter;
+pub use ahrs::config::{AHRSConfig, GyroFallback, DEFAULT_WARM_UP_SAMPLES};
+pub use ahrs::{AHRSFilter, QUATERNION_SENSOR_NAME};
+pub use compare::{ComparisonChannel, ComparisonStats, OrientationComparator};
+pub use estimators::{
+    EkfConfig, EstimatorConfig, MadgwickConfig, MahonyConfig, OrientationEstimator,
+};
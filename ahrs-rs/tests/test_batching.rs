@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use ahrs_rs::{AHRSConfig, AHRSFilter};
+use imu_common::traits::IMUSink;
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::{Sample3D, SampleQuaternion};
+use resampler_rs::{OutputBatching, ResamplerBuilder};
+use test_utils::harness::{VirtualPipelineHarness, VirtualSource};
+use uuid::Uuid;
+
+const START_SECS: f64 = 1000.0;
+// periods with an exact binary representation, so timestamps can be compared exactly
+const STEP_MILLIS: f64 = 15.625;
+const SAMPLING_PERIOD_MILLIS: f64 = 7.8125;
+const RESAMPLING_DELAY_MILLIS: f64 = 125.0;
+
+#[test]
+fn test_batched_resampler_output() {
+    let sensor_cluster = SensorType::cluster_for_tag("test_batched_resampler_output");
+    let mut harness = VirtualPipelineHarness::new(START_SECS, STEP_MILLIS);
+    let source = Arc::new(VirtualSource::<Sample3D>::new(
+        "test",
+        sensor_cluster.clone(),
+        SAMPLING_PERIOD_MILLIS,
+        |sensor, _timestamp| match sensor {
+            SensorType::Gyroscope(_) => [0.0, 0.0, 1.0].into(),
+            SensorType::Accelerometer(_) => [0.0, 0.0, 9.8].into(),
+            _ => [20.0, 0.0, -40.0].into(),
+        },
+    ));
+    let mut resampler = ResamplerBuilder::new("test", sensor_cluster.clone())
+        .with_resampling_period_millis(STEP_MILLIS)
+        .with_resampling_delay_millis(RESAMPLING_DELAY_MILLIS)
+        .with_output_batching(OutputBatching::Ticks(4))
+        .offline::<SensorReadings<Sample3D>, Sample3D>(Arc::new(harness.get_clock()))
+        .unwrap();
+    let pipeline = resampler.get_pipeline();
+    pipeline
+        .attach_listeners(&*source, &sensor_cluster)
+        .unwrap();
+
+    let output = SensorType::Other(Uuid::new_v4(), "Orientation".to_string());
+    let ahrs = AHRSFilter::new(
+        "test",
+        sensor_cluster.clone(),
+        output.clone(),
+        STEP_MILLIS,
+        AHRSConfig::default().with_warm_up_samples(0),
+    )
+    .unwrap();
+    ahrs.attach_listeners(&*pipeline, &sensor_cluster).unwrap();
+    let resampled = harness.capture(&*pipeline, &sensor_cluster[0]).unwrap();
+    let orientations = harness.capture::<SampleQuaternion>(&ahrs, &output).unwrap();
+
+    harness.add_stage(move |timestamp| source.emit_until(timestamp));
+    harness.add_stage(move |timestamp| resampler.step(timestamp));
+    harness.run_for_millis(1000.0);
+
+    // one orientation for every resampled sample, not one per batch
+    assert!(resampled.len() >= 48);
+    assert_eq!(orientations.len(), resampled.len());
+    assert_eq!(orientations.get_timestamps(), resampled.get_timestamps());
+}
@@ -1,12 +1,13 @@
-use resampler_rs::run;
+use resampler_rs::ResamplerBuilder;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use ahrs_rs::AHRSFilter;
+use ahrs_rs::{AHRSConfig, AHRSFilter, EstimatorConfig, MahonyConfig};
 use imu_common::traits::IMUSink;
 use imu_common::types::sensors::{SensorReadings, SensorType};
 use imu_common::types::timed::{Sample3D, SampleQuaternion};
 use phyphox_rs::services;
+use publisher::adapters;
 use resampler_rs::SmothingPolicy;
 use test_utils::sinks::{MockValue, SinkMock};
 use tokio::time::Duration;
@@ -21,6 +22,18 @@ fn process_samples(
 
 #[tokio::test]
 async fn test_ahrs() {
+    run_ahrs(AHRSConfig::default()).await;
+}
+
+#[tokio::test]
+async fn test_ahrs_mahony() {
+    run_ahrs(AHRSConfig::new(EstimatorConfig::Mahony(MahonyConfig::new(
+        1.0, 0.01,
+    ))))
+    .await;
+}
+
+async fn run_ahrs(config: AHRSConfig) {
     let sensor_tag = "Test";
     // Sample update frequency
     let update_period_millis = 50.0;
@@ -49,13 +62,12 @@ async fn test_ahrs() {
     let resampling_period_millis = 10.0;
     let resampling_delay_millis = 500.0;
     let resampling_policy = SmothingPolicy::WeightedAverage;
-    let (_handle_resampler, resampler) = run::<SensorReadings<Sample3D>, _>(
-        sensor_tag,
-        sensor_cluster.clone(),
-        resampling_period_millis,
-        resampling_delay_millis,
-        resampling_policy,
-    );
+    let (_handle_resampler, resampler) = ResamplerBuilder::new(sensor_tag, sensor_cluster.clone())
+        .with_resampling_period_millis(resampling_period_millis)
+        .with_resampling_delay_millis(resampling_delay_millis)
+        .with_smoothing_policy(resampling_policy)
+        .run::<SensorReadings<Sample3D>, _>()
+        .unwrap();
 
     // start ahrs
     let ahrs_measurement = SensorType::Other(Uuid::new_v4(), "Other".to_string());
@@ -64,16 +76,15 @@ async fn test_ahrs() {
         sensor_cluster.clone(),
         ahrs_measurement.clone(),
         resampling_period_millis,
+        config,
     )
     .unwrap();
 
     // connect resampler to phyphox
-    resampler
-        .attach_listeners(&*phyphox, &sensor_cluster)
-        .unwrap();
+    let _resampler_subscription = adapters::attach(&phyphox, &*resampler, &sensor_cluster).unwrap();
 
-    // connect ahrs to phyphox
-    ahrs.attach_listeners(&*resampler, &sensor_cluster).unwrap();
+    // connect ahrs to resampler
+    let _ahrs_subscription = adapters::attach(&resampler, &ahrs, &sensor_cluster).unwrap();
 
     // connect samples from ahrs
     let mut sink = SinkMock::<SampleQuaternion>::new();
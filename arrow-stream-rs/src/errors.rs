@@ -0,0 +1,21 @@
+//! Module errors
+
+/// Errors of the Arrow stream sink.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArrowStreamError {
+    /// Error binding the server socket.
+    Socket(String),
+    /// Error encoding readings as Arrow record batches.
+    Encoding(String),
+}
+
+impl std::fmt::Display for ArrowStreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArrowStreamError::Socket(e) => write!(f, "Socket error: {}", e),
+            ArrowStreamError::Encoding(e) => write!(f, "Encoding error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ArrowStreamError {}
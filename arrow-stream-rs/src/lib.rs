@@ -0,0 +1,37 @@
+//! # Crate arrow-stream-rs
+//!
+//! ## arrow-stream-rs
+//!
+//! The `arrow-stream-rs` crate streams readings in the [Arrow IPC streaming format](https://arrow.apache.org/docs/format/Columnar.html#ipc-streaming-format),
+//! so Python, Julia or R consumers can attach live to a running pipeline and receive columnar
+//! data they can use without parsing.
+//!
+//! [`ArrowStreamSink`] is an `IMUSink` running a TCP server. Every client receives an Arrow
+//! stream: the schema once connected, and then a record batch for every batch of readings the
+//! sink receives, with one row per sample:
+//!
+//! | tag  | sensor                    | timestamp | x   | y   | z   |
+//! |------|---------------------------|-----------|-----|-----|-----|
+//! | Utf8 | Utf8 (`SensorType` display) | Float64 (s) | Float64 | Float64 | Float64 |
+//!
+//! Measurement columns depend on the sample type: `value` for scalars, `x`, `y`, `z` for 3D
+//! samples and `w`, `x`, `y`, `z` for quaternions. For instance, with `pyarrow`:
+//!
+//! ```python
+//! import socket
+//! import pyarrow as pa
+//!
+//! stream = socket.create_connection(("localhost", 9000)).makefile("rb")
+//! for batch in pa.ipc.open_stream(stream):
+//!     print(batch.to_pandas())
+//! ```
+//!
+//! Slow clients skip the record batches they can't keep up with instead of delaying the rest.
+//! The stream is ended when the sink stops.
+
+pub mod errors;
+mod schema;
+mod sink;
+
+pub use errors::ArrowStreamError;
+pub use sink::{run_server, ArrowStreamSink};
@@ -0,0 +1,144 @@
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use std::sync::Arc;
+
+use crate::errors::ArrowStreamError;
+use imu_common::traits::{IMUSample, VecF64Convertible};
+use imu_common::types::sensors::SensorType;
+
+/// Returns the names of the measurement columns of samples with `n_values` values.
+fn measurement_columns(n_values: usize) -> Vec<String> {
+    let names: &[&str] = match n_values {
+        1 => &["value"],
+        3 => &["x", "y", "z"],
+        4 => &["w", "x", "y", "z"],
+        _ => &[],
+    };
+    if names.is_empty() {
+        (0..n_values).map(|n| format!("v{}", n)).collect()
+    } else {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+}
+
+/// Returns the schema of the record batches of samples `S`.
+pub(crate) fn schema_for<S>() -> SchemaRef
+where
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    let values: Vec<f64> = S::Untimed::default().into();
+    let n_values = values.len();
+    let mut fields = vec![
+        Field::new("tag", DataType::Utf8, false),
+        Field::new("sensor", DataType::Utf8, false),
+        Field::new("timestamp", DataType::Float64, false),
+    ];
+    fields.extend(
+        measurement_columns(n_values)
+            .into_iter()
+            .map(|name| Field::new(name, DataType::Float64, false)),
+    );
+    Arc::new(Schema::new(fields))
+}
+
+/// Returns a record batch with a row per sample.
+pub(crate) fn to_record_batch<S>(
+    schema: &SchemaRef,
+    tag: &str,
+    sensor_type: &SensorType,
+    samples: &[S],
+) -> Result<RecordBatch, ArrowStreamError>
+where
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    // tag, sensor and timestamp come before the measurement
+    let n_values = schema.fields().len() - 3;
+    let mut values: Vec<Vec<f64>> = vec![Vec::with_capacity(samples.len()); n_values];
+    for sample in samples {
+        let measurement: Vec<f64> = sample.get_measurement().into();
+        if measurement.len() != n_values {
+            return Err(ArrowStreamError::Encoding(format!(
+                "Expected {} values in samples of {}, got {}",
+                n_values,
+                sensor_type,
+                measurement.len()
+            )));
+        }
+        for (column, value) in values.iter_mut().zip(measurement) {
+            column.push(value);
+        }
+    }
+    let sensor = sensor_type.to_string();
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(vec![tag; samples.len()])),
+        Arc::new(StringArray::from(vec![sensor.as_str(); samples.len()])),
+        Arc::new(Float64Array::from_iter_values(
+            samples.iter().map(|sample| sample.get_timestamp_secs()),
+        )),
+    ];
+    columns.extend(
+        values
+            .into_iter()
+            .map(|column| Arc::new(Float64Array::from(column)) as ArrayRef),
+    );
+    RecordBatch::try_new(schema.clone(), columns)
+        .map_err(|e| ArrowStreamError::Encoding(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Float64Type;
+    use imu_common::types::timed::{Sample3D, SampleQuaternion, SampleScalar};
+    use uuid::Uuid;
+
+    fn column_names(schema: &SchemaRef) -> Vec<String> {
+        schema.fields().iter().map(|f| f.name().clone()).collect()
+    }
+
+    #[test]
+    fn test_schema() {
+        assert_eq!(
+            column_names(&schema_for::<Sample3D>()),
+            vec!["tag", "sensor", "timestamp", "x", "y", "z"]
+        );
+        assert_eq!(
+            column_names(&schema_for::<SampleQuaternion>())[3..],
+            ["w", "x", "y", "z"]
+        );
+        assert_eq!(column_names(&schema_for::<SampleScalar>())[3..], ["value"]);
+        assert_eq!(measurement_columns(2), vec!["v0", "v1"]);
+    }
+
+    #[test]
+    fn test_record_batch() {
+        let schema = schema_for::<Sample3D>();
+        let sensor_type = SensorType::Gyroscope(Uuid::new_v4());
+        let samples = vec![
+            Sample3D::new(1.0, [0.1, 0.2, 0.3]),
+            Sample3D::new(1.5, [0.4, 0.5, 0.6]),
+        ];
+
+        let batch = to_record_batch(&schema, "Phone", &sensor_type, &samples).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.column(0).as_string::<i32>().value(1), "Phone");
+        assert_eq!(
+            batch.column(1).as_string::<i32>().value(0),
+            sensor_type.to_string()
+        );
+        assert_eq!(
+            batch.column(2).as_primitive::<Float64Type>().values(),
+            &[1.0, 1.5]
+        );
+        assert_eq!(
+            batch.column(5).as_primitive::<Float64Type>().values(),
+            &[0.3, 0.6]
+        );
+
+        let empty = to_record_batch::<Sample3D>(&schema, "Phone", &sensor_type, &[]).unwrap();
+        assert_eq!(empty.num_rows(), 0);
+    }
+}
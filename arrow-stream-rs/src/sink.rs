@@ -0,0 +1,263 @@
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::SchemaRef;
+use log::{debug, error, warn};
+use publisher::{adapters, DropGuard, ShutdownToken};
+use std::marker::PhantomData;
+use std::net::{SocketAddr, TcpListener as StdTcpListener};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, Notify};
+use uuid::Uuid;
+
+use crate::errors::ArrowStreamError;
+use crate::schema;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource, VecF64Convertible};
+use imu_common::types::sensors::SensorType;
+
+/// Number of record batches buffered for every client. Clients further behind skip batches.
+const CLIENT_BUFFER_CAPACITY: usize = 256;
+/// Continuation marker and zero length ending an Arrow IPC stream.
+const END_OF_STREAM: [u8; 8] = [0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0];
+
+/// Encodes record batches as IPC messages. Batches don't use dictionaries, so every message
+/// can be sent to a client once it has received the schema.
+struct Encoder {
+    writer: StreamWriter<Vec<u8>>,
+}
+
+impl Encoder {
+    /// Returns the encoder and the schema message.
+    fn try_new(schema: &SchemaRef) -> Result<(Self, Arc<[u8]>), ArrowStreamError> {
+        let mut writer = StreamWriter::try_new(Vec::new(), schema)
+            .map_err(|e| ArrowStreamError::Encoding(e.to_string()))?;
+        let schema_message = Arc::from(std::mem::take(writer.get_mut()));
+        Ok((Self { writer }, schema_message))
+    }
+
+    fn encode(&mut self, batch: &arrow_array::RecordBatch) -> Result<Arc<[u8]>, ArrowStreamError> {
+        self.writer
+            .write(batch)
+            .map_err(|e| ArrowStreamError::Encoding(e.to_string()))?;
+        Ok(Arc::from(std::mem::take(self.writer.get_mut())))
+    }
+}
+
+/// Sink streaming the readings it receives as Arrow record batches to every client connected to
+/// its TCP server. Readings are only encoded while there are clients connected.
+///
+/// The server stops once every clone of the sink is dropped, including the ones held by the
+/// sources it is attached to.
+pub struct ArrowStreamSink<S> {
+    sender: broadcast::Sender<Arc<[u8]>>,
+    schema: SchemaRef,
+    schema_message: Arc<[u8]>,
+    encoder: Arc<Mutex<Encoder>>,
+    local_addr: SocketAddr,
+    abort_signal: Arc<Notify>,
+    n_clients: Arc<AtomicUsize>,
+    shutdown: ShutdownToken,
+    // `None` in the clones used by the server tasks, so they don't keep the sink alive
+    _guard: Option<Arc<DropGuard>>,
+    _phantom: PhantomData<fn() -> S>,
+}
+
+impl<S> Clone for ArrowStreamSink<S> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            schema: self.schema.clone(),
+            schema_message: self.schema_message.clone(),
+            encoder: self.encoder.clone(),
+            local_addr: self.local_addr,
+            abort_signal: self.abort_signal.clone(),
+            n_clients: self.n_clients.clone(),
+            shutdown: self.shutdown.clone(),
+            _guard: self._guard.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> ArrowStreamSink<S>
+where
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns the number of connected clients.
+    pub fn get_client_count(&self) -> usize {
+        self.n_clients.load(Ordering::Relaxed)
+    }
+
+    /// Returns the schema of the record batches.
+    pub fn get_schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Stops accepting clients, and ends the streams of the open connections.
+    pub fn stop(&self) {
+        abort(&self.abort_signal);
+    }
+
+    /// Streams `samples` of `sensor_type` to the connected clients as a record batch.
+    pub fn send(
+        &self,
+        tag: &str,
+        sensor_type: &SensorType,
+        samples: &[S],
+    ) -> Result<(), ArrowStreamError> {
+        if self.sender.receiver_count() == 0 {
+            return Ok(());
+        }
+        let batch = schema::to_record_batch(&self.schema, tag, sensor_type, samples)?;
+        let message = self.encoder.lock().unwrap().encode(&batch)?;
+        // sending only fails if there are no clients
+        let _ = self.sender.send(message);
+        Ok(())
+    }
+
+    async fn serve(&self, listener: TcpListener) {
+        loop {
+            tokio::select! {
+                _ = self.abort_signal.notified() => return,
+                _ = self.shutdown.wait() => {
+                    self.stop();
+                    return;
+                }
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        let sink = self.clone();
+                        tokio::spawn(async move { sink.serve_client(stream, peer).await });
+                    }
+                    Err(e) => error!("Error accepting Arrow stream client: {}", e),
+                }
+            }
+        }
+    }
+
+    async fn serve_client(&self, mut stream: TcpStream, peer: SocketAddr) {
+        // subscribe before sending the schema, so no batch is missed
+        let mut receiver = self.sender.subscribe();
+        if let Err(e) = stream.write_all(&self.schema_message).await {
+            warn!("Error sending the schema to {}: {}", peer, e);
+            return;
+        }
+        debug!("Arrow stream client {} connected", peer);
+        self.n_clients.fetch_add(1, Ordering::Relaxed);
+
+        loop {
+            tokio::select! {
+                _ = self.abort_signal.notified() => {
+                    let _ = stream.write_all(&END_OF_STREAM).await;
+                    let _ = stream.shutdown().await;
+                    break;
+                }
+                message = receiver.recv() => match message {
+                    Ok(message) => {
+                        if stream.write_all(&message).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("Arrow stream client {} skipped {} record batches", peer, n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+        self.n_clients.fetch_sub(1, Ordering::Relaxed);
+        debug!("Arrow stream client {} disconnected", peer);
+    }
+}
+
+impl<T, S> IMUSink<T, S> for ArrowStreamSink<S>
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, S>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        if let Err(e) = self.send(
+            samples.get_sensor_tag(),
+            &samples.get_sensor_type(),
+            samples.get_samples_ref(),
+        ) {
+            error!("Error streaming readings: {}", e);
+        }
+    }
+}
+
+/// Starts an Arrow stream server listening on `addr` in a background task, streaming samples
+/// `S`. `addr` can use port 0 to pick a free port. Must be called inside a tokio runtime.
+///
+/// Returns a Socket error if the server can't be bound.
+///
+/// # Returns
+///
+/// Returns a tuple containing:
+/// * A `tokio::task::JoinHandle<()>` of the server, which ends when `ArrowStreamSink::stop` is
+///   called, the sink is dropped or the global `ShutdownToken` is shut down.
+/// * The `ArrowStreamSink` to attach to sources.
+pub fn run_server<S>(
+    addr: &str,
+) -> Result<(tokio::task::JoinHandle<()>, ArrowStreamSink<S>), ArrowStreamError>
+where
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    let socket_error = |e: std::io::Error| ArrowStreamError::Socket(e.to_string());
+    let listener = StdTcpListener::bind(addr).map_err(socket_error)?;
+    listener.set_nonblocking(true).map_err(socket_error)?;
+    let local_addr = listener.local_addr().map_err(socket_error)?;
+    let listener = TcpListener::from_std(listener).map_err(socket_error)?;
+
+    let schema = schema::schema_for::<S>();
+    let (encoder, schema_message) = Encoder::try_new(&schema)?;
+    let (sender, _) = broadcast::channel(CLIENT_BUFFER_CAPACITY);
+    let abort_signal = Arc::new(Notify::new());
+    let sink = ArrowStreamSink {
+        sender,
+        schema,
+        schema_message,
+        encoder: Arc::new(Mutex::new(encoder)),
+        local_addr,
+        abort_signal: abort_signal.clone(),
+        n_clients: Arc::new(AtomicUsize::new(0)),
+        shutdown: ShutdownToken::global(),
+        _guard: None,
+        _phantom: PhantomData,
+    };
+    let handle = tokio::spawn({
+        let sink = sink.clone();
+        async move { sink.serve(listener).await }
+    });
+    let guard = DropGuard::new(move || abort(&abort_signal));
+    Ok((
+        handle,
+        ArrowStreamSink {
+            _guard: Some(Arc::new(guard)),
+            ..sink
+        },
+    ))
+}
+
+fn abort(abort_signal: &Notify) {
+    abort_signal.notify_waiters();
+    // the server may not be waiting yet
+    abort_signal.notify_one();
+}
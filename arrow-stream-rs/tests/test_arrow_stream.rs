@@ -0,0 +1,112 @@
+use arrow_array::cast::AsArray;
+use arrow_array::types::Float64Type;
+use arrow_array::RecordBatch;
+use arrow_ipc::reader::StreamReader;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use arrow_stream_rs::{run_server, ArrowStreamSink};
+use imu_common::traits::{IMUReadings, IMUSink};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+
+/// Reads the record batches of the stream served at `addr` until it ends.
+fn read_stream(addr: SocketAddr) -> tokio::task::JoinHandle<Vec<RecordBatch>> {
+    tokio::task::spawn_blocking(move || {
+        let stream = TcpStream::connect(addr).unwrap();
+        let reader = StreamReader::try_new(stream, None).unwrap();
+        reader.map(|batch| batch.unwrap()).collect()
+    })
+}
+
+async fn wait_for_client(sink: &ArrowStreamSink<Sample3D>) {
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while sink.get_client_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_stream_readings() {
+    let (handle, sink) = run_server::<Sample3D>("127.0.0.1:0").unwrap();
+    let reader = read_stream(sink.local_addr());
+    wait_for_client(&sink).await;
+
+    let accelerometer = SensorType::Accelerometer(Uuid::new_v4());
+    let gyroscope = SensorType::Gyroscope(Uuid::new_v4());
+    let readings = SensorReadings::from_vec(
+        "Phone",
+        accelerometer.clone(),
+        vec![
+            Sample3D::new(1.0, [0.1, 0.2, 9.8]),
+            Sample3D::new(1.1, [0.1, 0.2, 9.7]),
+        ],
+    );
+    sink.process_samples(Uuid::new_v4(), Arc::new(readings));
+    let readings = SensorReadings::from_vec(
+        "Phone",
+        gyroscope.clone(),
+        vec![Sample3D::new(1.0, [0.0; 3])],
+    );
+    sink.process_samples(Uuid::new_v4(), Arc::new(readings));
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    sink.stop();
+    let batches = tokio::time::timeout(Duration::from_secs(2), reader)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].schema(), sink.get_schema());
+    assert_eq!(batches[0].num_rows(), 2);
+    assert_eq!(
+        batches[0].column(1).as_string::<i32>().value(0),
+        accelerometer.to_string()
+    );
+    let timestamps = batches[0].column_by_name("timestamp").unwrap();
+    assert_eq!(
+        timestamps.as_primitive::<Float64Type>().values(),
+        &[1.0, 1.1]
+    );
+    let z = batches[0].column_by_name("z").unwrap();
+    assert_eq!(z.as_primitive::<Float64Type>().values(), &[9.8, 9.7]);
+    assert_eq!(
+        batches[1].column(1).as_string::<i32>().value(0),
+        gyroscope.to_string()
+    );
+
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_stop_on_drop() {
+    let (handle, sink) = run_server::<Sample3D>("127.0.0.1:0").unwrap();
+    let reader = read_stream(sink.local_addr());
+    wait_for_client(&sink).await;
+
+    let clone = sink.clone();
+    drop(sink);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!handle.is_finished());
+
+    drop(clone);
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+    // the stream is ended without batches
+    let batches = tokio::time::timeout(Duration::from_secs(2), reader)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(batches.is_empty());
+}
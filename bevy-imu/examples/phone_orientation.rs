@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::prelude::*;
+use bevy_imu::{ImuControlled, ImuOrientation, ImuPlugin};
+
+// Headless app printing the rotation of an entity following the phone. Replace
+// `ImuPlugin::mock()` with `ImuPlugin::phyphox("http://<phone ip>")` to use a phone, and
+// `MinimalPlugins` with `DefaultPlugins` (and the bevy rendering features) to draw it.
+fn main() {
+    App::new()
+        .add_plugins(
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+                1.0 / 30.0,
+            ))),
+        )
+        .add_plugins(ImuPlugin::mock())
+        .add_systems(Startup, |mut commands: Commands| {
+            commands.spawn((Transform::default(), ImuControlled));
+        })
+        .add_systems(Update, print_rotation)
+        .run();
+}
+
+fn print_rotation(orientation: Res<ImuOrientation>, query: Query<&Transform, With<ImuControlled>>) {
+    if orientation.is_changed() {
+        for transform in &query {
+            println!(
+                "{:.2} {:?}",
+                orientation.timestamp,
+                transform.rotation.to_euler(EulerRot::YXZ)
+            );
+        }
+    }
+}
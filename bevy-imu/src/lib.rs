@@ -0,0 +1,298 @@
+//! # Crate bevy-imu
+//!
+//! Bevy plugin streaming the orientation and acceleration of a phone running phyphox.
+//!
+//! [`ImuPlugin`] starts a phyphox source, a resampler and an AHRS filter on its own tokio
+//! runtime, and copies their latest readings into the [`ImuOrientation`] and
+//! [`ImuAcceleration`] resources at the beginning of every frame. Entities with an
+//! [`ImuControlled`] component follow the orientation of the phone.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use bevy_imu::{ImuControlled, ImuPlugin};
+//!
+//! App::new()
+//!     .add_plugins((MinimalPlugins, ImuPlugin::phyphox("http://192.168.1.34")))
+//!     .add_systems(Startup, |mut commands: Commands| {
+//!         commands.spawn((Transform::default(), ImuControlled));
+//!     })
+//!     .run();
+//! ```
+
+use std::f32::consts::FRAC_PI_2;
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use uuid::Uuid;
+
+use ahrs_rs::{AHRSConfig, AHRSFilter};
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
+use imu_common::types::sensors::{SensorClusterBuilder, SensorReadings, SensorType};
+use imu_common::types::timed::{Sample3D, SampleQuaternion};
+use publisher::Listener;
+use resampler_rs::{ResamplerBuilder, ResamplerPipeline, SmothingPolicy};
+
+type Source = Arc<dyn IMUSource<SensorReadings<Sample3D>, Sample3D>>;
+
+/// Where the readings come from.
+#[derive(Clone, Debug)]
+enum SourceConfig {
+    Phyphox(String),
+    Mock,
+}
+
+/// Plugin running the IMU pipeline and exposing its output as resources.
+///
+/// Starting the pipeline panics if the source can't be created, for instance if the phyphox
+/// url is invalid.
+#[derive(Clone, Debug)]
+pub struct ImuPlugin {
+    source: SourceConfig,
+    tag: String,
+    sampling_period_millis: f64,
+    resampling_period_millis: f64,
+    resampling_delay_millis: f64,
+    ahrs_config: AHRSConfig,
+}
+
+impl ImuPlugin {
+    /// Reads a phone running phyphox, reachable at `base_url`.
+    pub fn phyphox(base_url: &str) -> Self {
+        Self {
+            source: SourceConfig::Phyphox(base_url.to_string()),
+            tag: "Phone".to_string(),
+            sampling_period_millis: 200.0,
+            resampling_period_millis: 50.0,
+            resampling_delay_millis: 500.0,
+            ahrs_config: AHRSConfig::default(),
+        }
+    }
+
+    /// Replays the readings of the phyphox mock, to run without a phone.
+    pub fn mock() -> Self {
+        Self {
+            source: SourceConfig::Mock,
+            sampling_period_millis: 50.0,
+            ..Self::phyphox("")
+        }
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = tag.to_string();
+        self
+    }
+
+    /// Sets the period at which the source is polled.
+    pub fn with_sampling_period(mut self, period_millis: f64) -> Self {
+        self.sampling_period_millis = period_millis;
+        self
+    }
+
+    /// Sets the period of the resampled readings, which is also the AHRS update period.
+    pub fn with_resampling_period(mut self, period_millis: f64) -> Self {
+        self.resampling_period_millis = period_millis;
+        self
+    }
+
+    pub fn with_resampling_delay(mut self, delay_millis: f64) -> Self {
+        self.resampling_delay_millis = delay_millis;
+        self
+    }
+
+    pub fn with_ahrs_config(mut self, ahrs_config: AHRSConfig) -> Self {
+        self.ahrs_config = ahrs_config;
+        self
+    }
+}
+
+impl Plugin for ImuPlugin {
+    fn build(&self, app: &mut App) {
+        let pipeline = ImuPipeline::start(self).expect("Failed to start the IMU pipeline");
+        app.insert_resource(pipeline)
+            .init_resource::<ImuOrientation>()
+            .init_resource::<ImuAcceleration>()
+            .add_systems(PreUpdate, (update_readings, follow_orientation).chain());
+    }
+}
+
+/// Latest orientation estimated by the AHRS filter, in the sensor frame (z up).
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ImuOrientation {
+    pub rotation: Quat,
+    /// Timestamp of the reading in seconds. Zero until the first orientation is received.
+    pub timestamp: f64,
+}
+
+/// Latest resampled accelerometer reading in m/s², in the sensor frame (z up).
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ImuAcceleration {
+    pub acceleration: Vec3,
+    /// Timestamp of the reading in seconds. Zero until the first reading is received.
+    pub timestamp: f64,
+}
+
+/// Marks entities whose `Transform` rotation follows [`ImuOrientation`]. The rotation is
+/// converted to the Bevy frame, so the sensor z axis maps to the Bevy y axis.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct ImuControlled;
+
+#[derive(Default)]
+struct LatestReadings {
+    orientation: Option<SampleQuaternion>,
+    acceleration: Option<Sample3D>,
+}
+
+/// Running pipeline. Dropping it stops the runtime and every task of the pipeline.
+#[derive(Resource)]
+struct ImuPipeline {
+    latest: Arc<Mutex<LatestReadings>>,
+    _source: Source,
+    _resampler: Arc<ResamplerPipeline<SensorReadings<Sample3D>, Sample3D>>,
+    _ahrs: AHRSFilter,
+    _runtime: tokio::runtime::Runtime,
+}
+
+impl ImuPipeline {
+    fn start(config: &ImuPlugin) -> Result<Self, ImuError> {
+        let runtime = tokio::runtime::Runtime::new().map_err(|e| ImuError::Other(e.to_string()))?;
+        let guard = runtime.enter();
+
+        let tag = config.tag.as_str();
+        let sensor_cluster = SensorClusterBuilder::new().nine_axis().build()?;
+        let source: Source = match &config.source {
+            SourceConfig::Phyphox(url) => {
+                phyphox_rs::run_service(
+                    url,
+                    tag,
+                    sensor_cluster.clone(),
+                    config.sampling_period_millis,
+                )
+                .map_err(|e| ImuError::Other(format!("{:?}", e)))?
+                .1
+            }
+            SourceConfig::Mock => {
+                phyphox_rs::run_mock_service(
+                    tag,
+                    sensor_cluster.clone(),
+                    config.sampling_period_millis,
+                    false,
+                    u64::MAX,
+                )
+                .map_err(|e| ImuError::Other(format!("{:?}", e)))?
+                .1
+            }
+        };
+
+        let (_, resampler) = ResamplerBuilder::new(tag, sensor_cluster.clone())
+            .with_resampling_period_millis(config.resampling_period_millis)
+            .with_resampling_delay_millis(config.resampling_delay_millis)
+            .with_smoothing_policy(SmothingPolicy::WeightedAverage)
+            .run::<SensorReadings<Sample3D>, _>()
+            .map_err(|e| ImuError::InvalidParameter(e.to_string()))?;
+        let orientation_sensor = SensorType::Other(Uuid::new_v4(), "Orientation".to_string());
+        let ahrs = AHRSFilter::new(
+            tag,
+            sensor_cluster.clone(),
+            orientation_sensor.clone(),
+            config.resampling_period_millis,
+            config.ahrs_config,
+        )?;
+        resampler.attach_listeners(&*source, &sensor_cluster)?;
+        ahrs.attach_listeners(&*resampler, &sensor_cluster)?;
+
+        // only the latest readings are rendered, so of batched readings, the last sample is kept
+        let latest = Arc::new(Mutex::new(LatestReadings::default()));
+        let mut orientation_listener = Listener::new({
+            let latest = latest.clone();
+            move |_id, readings: Arc<SensorReadings<SampleQuaternion>>| {
+                if let Some(sample) = readings.get_samples_ref().last() {
+                    latest.lock().unwrap().orientation = Some(sample.clone());
+                }
+            }
+        });
+        ahrs.register_listener(&mut orientation_listener, &orientation_sensor)?;
+
+        let mut acceleration_listener = Listener::new({
+            let latest = latest.clone();
+            move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+                if let Some(sample) = readings.get_samples_ref().last() {
+                    latest.lock().unwrap().acceleration = Some(sample.clone());
+                }
+            }
+        });
+        let accelerometer = sensor_cluster
+            .iter()
+            .find(|s| matches!(s, SensorType::Accelerometer(_)))
+            .ok_or_else(|| ImuError::InvalidSensorCluster("Missing accelerometer".to_string()))?;
+        resampler.register_listener(&mut acceleration_listener, accelerometer)?;
+
+        drop(guard);
+        Ok(Self {
+            latest,
+            _source: source,
+            _resampler: resampler,
+            _ahrs: ahrs,
+            _runtime: runtime,
+        })
+    }
+}
+
+/// Copies the latest readings into the resources. Resources are only written when a new
+/// reading arrives, so change detection can be used to react to new readings.
+fn update_readings(
+    pipeline: Res<ImuPipeline>,
+    mut orientation: ResMut<ImuOrientation>,
+    mut acceleration: ResMut<ImuAcceleration>,
+) {
+    let latest = pipeline.latest.lock().unwrap();
+    if let Some(sample) = &latest.orientation {
+        if sample.get_timestamp_secs() != orientation.timestamp {
+            *orientation = ImuOrientation {
+                rotation: sample.get_measurement().into(),
+                timestamp: sample.get_timestamp_secs(),
+            };
+        }
+    }
+    if let Some(sample) = &latest.acceleration {
+        if sample.get_timestamp_secs() != acceleration.timestamp {
+            *acceleration = ImuAcceleration {
+                acceleration: sample.get_measurement().into(),
+                timestamp: sample.get_timestamp_secs(),
+            };
+        }
+    }
+}
+
+fn follow_orientation(
+    orientation: Res<ImuOrientation>,
+    mut query: Query<&mut Transform, With<ImuControlled>>,
+) {
+    if !orientation.is_changed() {
+        return;
+    }
+    let rotation = to_bevy_frame(orientation.rotation);
+    for mut transform in &mut query {
+        transform.rotation = rotation;
+    }
+}
+
+/// Expresses a rotation of the sensor frame (z up) in the Bevy frame (y up).
+fn to_bevy_frame(rotation: Quat) -> Quat {
+    let z_up_to_y_up = Quat::from_rotation_x(-FRAC_PI_2);
+    z_up_to_y_up * rotation * z_up_to_y_up.inverse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bevy_frame() {
+        // yaw around the sensor z axis is a rotation around the bevy y axis
+        let yaw = to_bevy_frame(Quat::from_rotation_z(0.5));
+        assert!(yaw.abs_diff_eq(Quat::from_rotation_y(0.5), 1e-6));
+        let up = to_bevy_frame(Quat::IDENTITY) * Vec3::Y;
+        assert!(up.abs_diff_eq(Vec3::Y, 1e-6));
+    }
+}
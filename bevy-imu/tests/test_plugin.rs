@@ -0,0 +1,49 @@
+use std::time::{Duration, Instant};
+
+use ahrs_rs::AHRSConfig;
+use bevy::prelude::*;
+use bevy_imu::{ImuAcceleration, ImuControlled, ImuOrientation, ImuPlugin};
+
+#[test]
+fn test_mock_pipeline() {
+    let plugin = ImuPlugin::mock()
+        .with_resampling_period(10.0)
+        .with_resampling_delay(100.0)
+        .with_ahrs_config(AHRSConfig::default().with_warm_up_samples(5));
+    let mut app = App::new();
+    app.add_plugins(plugin);
+    let entity = app
+        .world_mut()
+        .spawn((Transform::default(), ImuControlled))
+        .id();
+
+    let start = Instant::now();
+    let received = |app: &App| {
+        app.world().resource::<ImuOrientation>().timestamp > 0.0
+            && app
+                .world()
+                .resource::<ImuAcceleration>()
+                .acceleration
+                .length()
+                > 0.0
+    };
+    while !received(&app) {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "No readings received"
+        );
+        std::thread::sleep(Duration::from_millis(20));
+        app.update();
+    }
+
+    let orientation = *app.world().resource::<ImuOrientation>();
+    let transform = app.world().get::<Transform>(entity).unwrap();
+    let z_up_to_y_up = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+    let expected = z_up_to_y_up * orientation.rotation * z_up_to_y_up.inverse();
+    assert!(
+        transform.rotation.abs_diff_eq(expected, 1e-5),
+        "{:?} {:?}",
+        transform.rotation,
+        expected
+    );
+}
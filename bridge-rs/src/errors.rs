@@ -0,0 +1,30 @@
+//! Module errors
+
+/// Errors of the mobile bridge source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BridgeError {
+    /// Error binding or reading the socket.
+    Socket(String),
+
+    /// Error indicating that a sensor is already published by another source.
+    DuplicatedSensor(String),
+
+    /// Error indicating that a sensor has no counterpart in the protocol.
+    UnsupportedSensor(String),
+
+    /// Error indicating that a received message is malformed.
+    InvalidMessage(String),
+}
+
+impl std::fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BridgeError::Socket(e) => write!(f, "Socket error: {}", e),
+            BridgeError::DuplicatedSensor(e) => write!(f, "Duplicated sensor: {}", e),
+            BridgeError::UnsupportedSensor(e) => write!(f, "Unsupported sensor: {}", e),
+            BridgeError::InvalidMessage(e) => write!(f, "Invalid message: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BridgeError {}
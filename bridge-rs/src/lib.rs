@@ -0,0 +1,22 @@
+//! # Crate bridge-rs
+//!
+//! ## bridge-rs
+//!
+//! The `bridge-rs` crate receives the native sensors of a phone, streamed by a companion
+//! Android or iOS app, and publishes them like any other source. Unlike the Phyphox REST API,
+//! which is polled, the app pushes every sensor event as soon as it is delivered by the OS, so
+//! readings arrive at the full rate of the sensors.
+//!
+//! Features include:
+//! - A small documented protocol, so apps can be written in any language. See [`protocol`].
+//! - UDP for the lowest latency, or WebSocket when the network drops datagrams.
+//! - Compact binary batches for high rates, and JSON messages for quick prototypes.
+//! - Registration of listeners to receive sensor data once received.
+
+pub mod errors;
+pub mod protocol;
+mod source;
+
+pub use errors::BridgeError;
+pub use protocol::{Hello, SensorKind};
+pub use source::{run_service, BridgeSource, Transport};
@@ -0,0 +1,315 @@
+//! Module protocol
+//!
+//! Messages sent by the companion app to [`BridgeSource`](crate::BridgeSource). The same
+//! messages are sent as UDP datagrams or as WebSocket messages: JSON messages as text and
+//! binary batches as binary messages.
+//!
+//! The app first sends a hello message describing the device. It is optional, as datagrams can
+//! be lost, and is repeated every time the app reconnects:
+//!
+//! ```json
+//! {"type": "hello", "version": 1, "device": "Pixel 8", "platform": "android",
+//!  "sensors": ["accelerometer", "gyroscope", "magnetometer"]}
+//! ```
+//!
+//! Readings are sent in batches of a single sensor, as JSON with `[timestamp, x, y, z]` rows:
+//!
+//! ```json
+//! {"type": "samples", "sensor": "gyroscope", "samples": [[1.25, 0.01, 0.0, -0.02]]}
+//! ```
+//!
+//! or as little endian binary batches, which can be sent back to back in a single message:
+//!
+//! | Field     | Type                 | Description                                   |
+//! |-----------|----------------------|-----------------------------------------------|
+//! | magic     | `[u8; 2]`            | `b"IB"`                                       |
+//! | version   | `u8`                 | [`PROTOCOL_VERSION`]                          |
+//! | sensor    | `u8`                 | Code of the [`SensorKind`]                    |
+//! | count     | `u16`                | Number of samples                             |
+//! | samples   | `[(f64, [f32; 3])]`  | Timestamp and `x, y, z` of every sample       |
+//!
+//! Timestamps are in seconds since the Unix epoch, so apps convert the monotonic clock of the
+//! sensor events with an offset taken once when they start. Readings use the units and axes of
+//! Android: m/s² for accelerations, rad/s for the gyroscope and µT for the magnetometer, with
+//! `x` to the right of the screen, `y` up and `z` out of the screen. iOS apps convert the
+//! accelerations from g to m/s², with the opposite sign.
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::BridgeError;
+use imu_common::traits::IMUSample;
+use imu_common::types::sensors::SensorType;
+use imu_common::types::timed::Sample3D;
+
+pub const PROTOCOL_VERSION: u8 = 1;
+pub const MAGIC: [u8; 2] = *b"IB";
+
+const HEADER_SIZE: usize = 6;
+const SAMPLE_SIZE: usize = 8 + 3 * 4;
+
+/// Sensor of the phone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    Accelerometer,
+    Gyroscope,
+    Magnetometer,
+    /// Gravity, as estimated by the OS.
+    Gravity,
+    /// Acceleration without gravity, as estimated by the OS.
+    LinearAcceleration,
+}
+
+impl SensorKind {
+    pub const ALL: [SensorKind; 5] = [
+        SensorKind::Accelerometer,
+        SensorKind::Gyroscope,
+        SensorKind::Magnetometer,
+        SensorKind::Gravity,
+        SensorKind::LinearAcceleration,
+    ];
+
+    /// Returns the code of the sensor in binary batches.
+    pub fn code(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+
+    /// Returns the kind published as `sensor_type`, if any. Gravity and linear acceleration
+    /// are published as `Other` sensors named e.g. "Gravity" and "Linear Acceleration".
+    pub fn from_sensor_type(sensor_type: &SensorType) -> Option<Self> {
+        match sensor_type {
+            SensorType::Accelerometer(_) => Some(SensorKind::Accelerometer),
+            SensorType::Gyroscope(_) => Some(SensorKind::Gyroscope),
+            SensorType::Magnetometer(_) => Some(SensorKind::Magnetometer),
+            SensorType::Other(_, name) => {
+                let name: String = name
+                    .chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .collect::<String>()
+                    .to_lowercase();
+                match name.as_str() {
+                    "gravity" => Some(SensorKind::Gravity),
+                    "linearacceleration" => Some(SensorKind::LinearAcceleration),
+                    _ => None,
+                }
+            }
+            SensorType::Vendor(..) => None,
+        }
+    }
+}
+
+/// Description of the device streaming readings.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hello {
+    pub version: u8,
+    pub device: String,
+    /// Operating system, e.g. "android" or "ios".
+    pub platform: String,
+    /// Sensors the app streams.
+    #[serde(default)]
+    pub sensors: Vec<SensorKind>,
+}
+
+/// Message decoded from a datagram or WebSocket message.
+#[derive(Clone, Debug)]
+pub enum Message {
+    Hello(Hello),
+    Samples {
+        sensor: SensorKind,
+        samples: Vec<Sample3D>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JsonMessage {
+    Hello(Hello),
+    Samples {
+        sensor: SensorKind,
+        samples: Vec<[f64; 4]>,
+    },
+}
+
+/// Decodes every message in `data`.
+pub fn parse_message(data: &[u8]) -> Result<Vec<Message>, BridgeError> {
+    match data.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'{') => parse_json(data).map(|message| vec![message]),
+        Some(_) => parse_binary(data),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Encodes `hello` as a JSON message.
+pub fn encode_hello(hello: &Hello) -> String {
+    serde_json::to_string(&JsonMessage::Hello(hello.clone())).unwrap()
+}
+
+/// Encodes a binary batch of `samples` of `sensor`.
+/// Returns an InvalidMessage error if there are more samples than fit in a batch.
+pub fn encode_samples(sensor: SensorKind, samples: &[Sample3D]) -> Result<Vec<u8>, BridgeError> {
+    let count = u16::try_from(samples.len())
+        .map_err(|_| BridgeError::InvalidMessage(format!("{} samples", samples.len())))?;
+    let mut batch = Vec::with_capacity(HEADER_SIZE + samples.len() * SAMPLE_SIZE);
+    batch.extend(MAGIC);
+    batch.extend([PROTOCOL_VERSION, sensor.code()]);
+    batch.extend(count.to_le_bytes());
+    for sample in samples {
+        batch.extend(sample.get_timestamp_secs().to_le_bytes());
+        for value in sample.get_measurement().inner() {
+            batch.extend((value as f32).to_le_bytes());
+        }
+    }
+    Ok(batch)
+}
+
+fn parse_json(data: &[u8]) -> Result<Message, BridgeError> {
+    match serde_json::from_slice(data).map_err(|e| BridgeError::InvalidMessage(e.to_string()))? {
+        JsonMessage::Hello(hello) => {
+            check_version(hello.version)?;
+            Ok(Message::Hello(hello))
+        }
+        JsonMessage::Samples { sensor, samples } => Ok(Message::Samples {
+            sensor,
+            samples: samples
+                .into_iter()
+                .map(|[t, x, y, z]| Sample3D::new(t, [x, y, z]))
+                .collect(),
+        }),
+    }
+}
+
+fn parse_binary(mut data: &[u8]) -> Result<Vec<Message>, BridgeError> {
+    let mut messages = Vec::new();
+    while !data.is_empty() {
+        let header = data
+            .get(..HEADER_SIZE)
+            .ok_or(BridgeError::InvalidMessage("Truncated header".to_string()))?;
+        if header[..2] != MAGIC {
+            return Err(BridgeError::InvalidMessage("Invalid magic".to_string()));
+        }
+        check_version(header[2])?;
+        let sensor = SensorKind::from_code(header[3]).ok_or(BridgeError::InvalidMessage(
+            format!("Unknown sensor {}", header[3]),
+        ))?;
+        let count = u16::from_le_bytes([header[4], header[5]]) as usize;
+        let end = HEADER_SIZE + count * SAMPLE_SIZE;
+        let payload = data
+            .get(HEADER_SIZE..end)
+            .ok_or(BridgeError::InvalidMessage("Truncated batch".to_string()))?;
+        let samples = payload
+            .chunks_exact(SAMPLE_SIZE)
+            .map(|b| {
+                let value = |i: usize| f32::from_le_bytes(b[i..i + 4].try_into().unwrap()) as f64;
+                let timestamp = f64::from_le_bytes(b[..8].try_into().unwrap());
+                Sample3D::new(timestamp, [value(8), value(12), value(16)])
+            })
+            .collect();
+        messages.push(Message::Samples { sensor, samples });
+        data = &data[end..];
+    }
+    Ok(messages)
+}
+
+fn check_version(version: u8) -> Result<(), BridgeError> {
+    if version != PROTOCOL_VERSION {
+        return Err(BridgeError::InvalidMessage(format!(
+            "Unsupported version {}",
+            version
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_binary_batches() {
+        let samples = vec![
+            Sample3D::new(1.5, [0.0, 0.0, 9.81]),
+            Sample3D::new(1.505, [0.0, 0.1, 9.8]),
+        ];
+        let mut data = encode_samples(SensorKind::Accelerometer, &samples).unwrap();
+        data.extend(encode_samples(SensorKind::Gyroscope, &samples[..1]).unwrap());
+        let messages = parse_message(&data).unwrap();
+
+        assert_eq!(messages.len(), 2);
+        match &messages[0] {
+            Message::Samples { sensor, samples } => {
+                assert_eq!(*sensor, SensorKind::Accelerometer);
+                assert_eq!(samples.len(), 2);
+                assert_eq!(samples[1].get_timestamp_secs(), 1.505);
+                assert_eq!(samples[0].get_measurement().inner()[2], 9.81f32 as f64);
+            }
+            message => panic!("Unexpected message {:?}", message),
+        }
+        assert!(matches!(
+            messages[1],
+            Message::Samples {
+                sensor: SensorKind::Gyroscope,
+                ..
+            }
+        ));
+
+        assert!(parse_message(&data[..data.len() - 1]).is_err());
+        data[2] = PROTOCOL_VERSION + 1;
+        assert!(parse_message(&data).is_err());
+        data[0] = b'X';
+        assert!(parse_message(&data).is_err());
+    }
+
+    #[test]
+    fn test_json_messages() {
+        let hello = Hello {
+            version: PROTOCOL_VERSION,
+            device: "Pixel 8".to_string(),
+            platform: "android".to_string(),
+            sensors: vec![SensorKind::Accelerometer, SensorKind::LinearAcceleration],
+        };
+        match &parse_message(encode_hello(&hello).as_bytes()).unwrap()[0] {
+            Message::Hello(decoded) => assert_eq!(*decoded, hello),
+            message => panic!("Unexpected message {:?}", message),
+        }
+
+        let messages = parse_message(
+            br#"{"type": "samples", "sensor": "magnetometer", "samples": [[1.0, 20, 0, -40]]}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            &messages[0],
+            Message::Samples { sensor: SensorKind::Magnetometer, samples } if samples.len() == 1
+        ));
+
+        assert!(
+            parse_message(br#"{"type": "hello", "version": 9, "device": "", "platform": ""}"#)
+                .is_err()
+        );
+        assert!(
+            parse_message(br#"{"type": "samples", "sensor": "barometer", "samples": []}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_sensor_kinds() {
+        for kind in SensorKind::ALL {
+            assert_eq!(SensorKind::from_code(kind.code()), Some(kind));
+        }
+        let uuid = Uuid::new_v4();
+        let kind = |sensor_type| SensorKind::from_sensor_type(&sensor_type);
+        assert_eq!(
+            kind(SensorType::Gyroscope(uuid)),
+            Some(SensorKind::Gyroscope)
+        );
+        assert_eq!(
+            kind(SensorType::Other(uuid, "Linear Acceleration".to_string())),
+            Some(SensorKind::LinearAcceleration)
+        );
+        assert_eq!(kind(SensorType::Other(uuid, "Pressure".to_string())), None);
+    }
+}
@@ -0,0 +1,285 @@
+use futures_util::stream::FuturesUnordered;
+use futures_util::StreamExt;
+use log::{debug, error, warn};
+use publisher::{lifetime, PublisherManager, ShutdownToken};
+use std::net::{SocketAddr, TcpListener as StdTcpListener, UdpSocket as StdUdpSocket};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite;
+use uuid::Uuid;
+
+use crate::errors::BridgeError;
+use crate::protocol::{parse_message, Hello, Message, SensorKind};
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSource, Notifiable};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+
+/// Largest datagram accepted. Longer datagrams are truncated, and fail to parse.
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// Transport the companion app streams on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transport {
+    Udp,
+    /// WebSocket server, accepting any number of apps.
+    WebSocket,
+}
+
+enum Socket {
+    Udp(UdpSocket),
+    WebSocket(TcpListener),
+}
+
+/// Source publishing the sensors streamed by a companion mobile app.
+///
+/// Every sensor in `sensors` is published with the readings of the [`SensorKind`] it
+/// corresponds to, and readings of sensors not in `sensors` are ignored. Malformed messages are
+/// logged and dropped.
+pub struct BridgeSource {
+    tag: String,
+    socket: Socket,
+    sensors: Vec<(SensorKind, SensorType)>,
+    publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+    device: Mutex<Option<Hello>>,
+    abort_signal: Arc<Notify>,
+    shutdown: ShutdownToken,
+    dropped_messages: AtomicUsize,
+}
+
+impl BridgeSource {
+    /// Binds a socket on `addr`, which can use port 0 to pick a free port. Must be called
+    /// inside a tokio runtime.
+    /// Returns an UnsupportedSensor error if a sensor has no `SensorKind` or shares it with
+    /// another sensor, a Socket error if the socket can't be bound, and DuplicatedSensor if a
+    /// sensor is already published by another source.
+    pub fn bind(
+        addr: &str,
+        transport: Transport,
+        tag: &str,
+        sensors: Vec<SensorType>,
+    ) -> Result<Self, BridgeError> {
+        let mut kinds: Vec<(SensorKind, SensorType)> = Vec::new();
+        for sensor_type in sensors.iter() {
+            let kind = SensorKind::from_sensor_type(sensor_type)
+                .ok_or(BridgeError::UnsupportedSensor(sensor_type.to_string()))?;
+            if kinds.iter().any(|(k, _)| *k == kind) {
+                return Err(BridgeError::UnsupportedSensor(format!(
+                    "{} streamed twice",
+                    sensor_type
+                )));
+            }
+            kinds.push((kind, sensor_type.clone()));
+        }
+        let publishers = PublisherManager::try_new(&sensors)
+            .map_err(|e| BridgeError::DuplicatedSensor(format!("{} in {}", e, tag)))?;
+
+        let socket_error = |e: std::io::Error| BridgeError::Socket(e.to_string());
+        let socket = match transport {
+            Transport::Udp => {
+                let socket = StdUdpSocket::bind(addr).map_err(socket_error)?;
+                socket.set_nonblocking(true).map_err(socket_error)?;
+                Socket::Udp(UdpSocket::from_std(socket).map_err(socket_error)?)
+            }
+            Transport::WebSocket => {
+                let listener = StdTcpListener::bind(addr).map_err(socket_error)?;
+                listener.set_nonblocking(true).map_err(socket_error)?;
+                Socket::WebSocket(TcpListener::from_std(listener).map_err(socket_error)?)
+            }
+        };
+
+        Ok(Self {
+            tag: tag.to_string(),
+            socket,
+            sensors: kinds,
+            publishers,
+            device: Mutex::new(None),
+            abort_signal: Arc::new(Notify::new()),
+            shutdown: ShutdownToken::global(),
+            dropped_messages: AtomicUsize::new(0),
+        })
+    }
+
+    /// Stops the source when `token` is shut down, instead of the global token.
+    pub fn with_shutdown_token(mut self, token: ShutdownToken) -> Self {
+        self.shutdown = token;
+        self
+    }
+
+    /// Returns the address the socket is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr, BridgeError> {
+        match &self.socket {
+            Socket::Udp(socket) => socket.local_addr(),
+            Socket::WebSocket(listener) => listener.local_addr(),
+        }
+        .map_err(|e| BridgeError::Socket(e.to_string()))
+    }
+
+    /// Returns the device described by the last hello message received, if any.
+    pub fn get_device(&self) -> Option<Hello> {
+        self.device.lock().unwrap().clone()
+    }
+
+    /// Returns the number of messages dropped because they were malformed.
+    pub fn get_dropped_messages(&self) -> usize {
+        self.dropped_messages.load(Ordering::Relaxed)
+    }
+
+    /// Receives and publishes readings until `stop` is called or the shutdown token is shut
+    /// down. Returns a Socket error if the socket fails.
+    pub async fn start(&self) -> Result<(), BridgeError> {
+        tokio::select! {
+            _ = self.abort_signal.notified() => Ok(()),
+            _ = self.shutdown.wait() => Ok(()),
+            result = self.receive() => result,
+        }
+    }
+
+    /// Stops `start`. If it isn't running yet, it returns as soon as it is called.
+    pub fn stop(&self) {
+        self.abort_signal.notify_one();
+    }
+
+    async fn receive(&self) -> Result<(), BridgeError> {
+        match &self.socket {
+            Socket::Udp(socket) => {
+                let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+                loop {
+                    let (len, _) = socket
+                        .recv_from(&mut buffer)
+                        .await
+                        .map_err(|e| BridgeError::Socket(e.to_string()))?;
+                    self.process_message(&buffer[..len]);
+                }
+            }
+            Socket::WebSocket(listener) => {
+                let mut clients = FuturesUnordered::new();
+                loop {
+                    tokio::select! {
+                        accepted = listener.accept() => match accepted {
+                            Ok((stream, peer)) => clients.push(self.serve_client(stream, peer)),
+                            Err(e) => error!("Error accepting WebSocket client: {}", e),
+                        },
+                        Some(()) = clients.next() => {}
+                    }
+                }
+            }
+        }
+    }
+
+    async fn serve_client(&self, stream: TcpStream, peer: SocketAddr) {
+        let mut websocket = match tokio_tungstenite::accept_async(stream).await {
+            Ok(websocket) => websocket,
+            Err(e) => {
+                warn!("WebSocket handshake with {} failed: {}", peer, e);
+                return;
+            }
+        };
+        debug!("Mobile app {} connected to {}", peer, self.tag);
+        while let Some(message) = websocket.next().await {
+            match message {
+                Ok(tungstenite::Message::Binary(data)) => self.process_message(&data),
+                Ok(tungstenite::Message::Text(text)) => self.process_message(text.as_bytes()),
+                Ok(tungstenite::Message::Close(_)) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+        debug!("Mobile app {} disconnected from {}", peer, self.tag);
+    }
+
+    /// Decodes `data`, a datagram or WebSocket message, and publishes its readings.
+    pub fn process_message(&self, data: &[u8]) {
+        let messages = match parse_message(data) {
+            Ok(messages) => messages,
+            Err(e) => {
+                warn!("Dropping message received by {}: {}", self.tag, e);
+                self.dropped_messages.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+        for message in messages {
+            match message {
+                Message::Hello(hello) => {
+                    debug!(
+                        "{} streaming from {} ({})",
+                        self.tag, hello.device, hello.platform
+                    );
+                    *self.device.lock().unwrap() = Some(hello);
+                }
+                Message::Samples { sensor, samples } => {
+                    let Some((_, sensor_type)) = self.sensors.iter().find(|(k, _)| *k == sensor)
+                    else {
+                        continue;
+                    };
+                    if samples.is_empty() {
+                        continue;
+                    }
+                    let readings =
+                        SensorReadings::from_vec(&self.tag, sensor_type.clone(), samples);
+                    self.publishers
+                        .notify_listeners(sensor_type.clone(), Arc::new(readings));
+                }
+            }
+        }
+    }
+}
+
+impl IMUSource<SensorReadings<Sample3D>, Sample3D> for BridgeSource {
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.sensors.iter().map(|(_, s)| s.clone()).collect()
+    }
+
+    fn get_tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<Sample3D>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<Sample3D>>) {
+        self.publishers.notify_listeners(sensor_type, data);
+    }
+}
+
+/// Starts a bridge source listening on `addr` in a background task. Must be called inside a
+/// tokio runtime.
+///
+/// # Returns
+///
+/// Returns a tuple containing:
+/// * A `tokio::task::JoinHandle<()>` of the task, which ends when `BridgeSource::stop` is
+///   called or the source is dropped.
+/// * An `Arc<BridgeSource>` to register listeners and stop the source.
+pub fn run_service(
+    addr: &str,
+    transport: Transport,
+    tag: &str,
+    sensors: Vec<SensorType>,
+) -> Result<(tokio::task::JoinHandle<()>, Arc<BridgeSource>), BridgeError> {
+    let source = Arc::new(BridgeSource::bind(addr, transport, tag, sensors)?);
+    let handle = tokio::spawn({
+        let source = source.clone();
+        async move {
+            let result = tokio::select! {
+                result = source.start() => result,
+                // stop once the caller drops the source
+                _ = lifetime::orphaned(&source) => Ok(()),
+            };
+            if let Err(e) = result {
+                error!("Error in bridge loop: {:?}", e);
+            }
+        }
+    });
+    Ok((handle, source))
+}
@@ -0,0 +1,182 @@
+use futures_util::SinkExt;
+use publisher::Listener;
+use std::net::UdpSocket;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+use bridge_rs::protocol::{encode_hello, encode_samples, PROTOCOL_VERSION};
+use bridge_rs::{run_service, BridgeError, Hello, SensorKind, Transport};
+use imu_common::traits::{IMUReadings, IMUSample, IMUSource};
+use imu_common::types::sensors::{SensorClusterBuilder, SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+
+fn collect(
+    source: &dyn IMUSource<SensorReadings<Sample3D>, Sample3D>,
+    sensor_type: &SensorType,
+) -> (
+    Listener<SensorReadings<Sample3D>>,
+    Arc<Mutex<Vec<Sample3D>>>,
+) {
+    let samples: Arc<Mutex<Vec<Sample3D>>> = Arc::new(Mutex::new(Vec::new()));
+    let mut listener = Listener::new({
+        let samples = samples.clone();
+        move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+            samples.lock().unwrap().extend(readings.get_samples());
+        }
+    });
+    source
+        .register_listener(&mut listener, sensor_type)
+        .unwrap();
+    (listener, samples)
+}
+
+async fn wait_for<F: Fn() -> bool>(condition: F) {
+    for _ in 0..100 {
+        if condition() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+fn hello() -> Hello {
+    Hello {
+        version: PROTOCOL_VERSION,
+        device: "iPhone 15".to_string(),
+        platform: "ios".to_string(),
+        sensors: vec![SensorKind::Accelerometer, SensorKind::Gravity],
+    }
+}
+
+#[tokio::test]
+async fn test_receive_udp_messages() {
+    let sensors = SensorClusterBuilder::new()
+        .accelerometer()
+        .other("Gravity")
+        .build()
+        .unwrap();
+    let (handle, source) =
+        run_service("127.0.0.1:0", Transport::Udp, "Phone", sensors.clone()).unwrap();
+    let (_accel_listener, accel) = collect(&*source, &sensors[0]);
+    let (_gravity_listener, gravity) = collect(&*source, &sensors[1]);
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let target = source.local_addr().unwrap();
+    socket
+        .send_to(encode_hello(&hello()).as_bytes(), target)
+        .unwrap();
+    let samples = vec![
+        Sample3D::new(1.0, [0.0, 0.0, 9.8]),
+        Sample3D::new(1.005, [0.0, 0.1, 9.8]),
+    ];
+    let mut datagram = encode_samples(SensorKind::Accelerometer, &samples).unwrap();
+    datagram.extend(encode_samples(SensorKind::Gravity, &samples[..1]).unwrap());
+    // sensor not published by the source
+    datagram.extend(encode_samples(SensorKind::Gyroscope, &samples).unwrap());
+    socket.send_to(&datagram, target).unwrap();
+    socket
+        .send_to(
+            br#"{"type": "samples", "sensor": "accelerometer", "samples": [[1.01, 0, 0.2, 9.8]]}"#,
+            target,
+        )
+        .unwrap();
+    socket.send_to(b"IB\x01", target).unwrap();
+
+    wait_for(|| accel.lock().unwrap().len() == 3 && source.get_dropped_messages() == 1).await;
+    source.stop();
+    handle.await.unwrap();
+
+    let mut timestamps: Vec<f64> = accel
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|s| s.get_timestamp_secs())
+        .collect();
+    timestamps.sort_by(f64::total_cmp);
+    assert_eq!(timestamps, vec![1.0, 1.005, 1.01]);
+    assert_eq!(gravity.lock().unwrap().len(), 1);
+    assert_eq!(source.get_dropped_messages(), 1);
+    assert_eq!(source.get_device(), Some(hello()));
+}
+
+#[tokio::test]
+async fn test_receive_websocket_messages() {
+    let sensors = SensorType::cluster_for_tag("test_bridge_websocket");
+    let (handle, source) = run_service(
+        "127.0.0.1:0",
+        Transport::WebSocket,
+        "Phone",
+        sensors.clone(),
+    )
+    .unwrap();
+    let (_listener, gyro) = collect(&*source, &sensors[1]);
+
+    let url = format!("ws://{}", source.local_addr().unwrap());
+    let (mut client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    client
+        .send(Message::Text(encode_hello(&hello())))
+        .await
+        .unwrap();
+    let samples: Vec<Sample3D> = (0..100)
+        .map(|i| Sample3D::new(i as f64 * 0.002, [0.01, 0.0, -0.02]))
+        .collect();
+    client
+        .send(Message::Binary(
+            encode_samples(SensorKind::Gyroscope, &samples).unwrap(),
+        ))
+        .await
+        .unwrap();
+
+    wait_for(|| gyro.lock().unwrap().len() == 100).await;
+    assert_eq!(gyro.lock().unwrap().len(), 100);
+    assert_eq!(source.get_device().unwrap().platform, "ios");
+
+    source.stop();
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_invalid_sensors() {
+    let sensors = SensorClusterBuilder::new()
+        .other("Pressure")
+        .build()
+        .unwrap();
+    let result = run_service("127.0.0.1:0", Transport::Udp, "Phone", sensors);
+    assert!(matches!(result, Err(BridgeError::UnsupportedSensor(_))));
+
+    // both sensors would be fed by the same stream
+    let sensors = vec![
+        SensorType::Accelerometer(Uuid::new_v4()),
+        SensorType::Accelerometer(Uuid::new_v4()),
+    ];
+    let result = run_service("127.0.0.1:0", Transport::Udp, "Phone", sensors);
+    assert!(matches!(result, Err(BridgeError::UnsupportedSensor(_))));
+}
+
+#[tokio::test]
+async fn test_stop_on_drop() {
+    let sensors = SensorType::cluster_for_tag("test_bridge_stop_on_drop");
+    let (handle, source) = run_service(
+        "127.0.0.1:0",
+        Transport::WebSocket,
+        "Phone",
+        sensors.clone(),
+    )
+    .unwrap();
+
+    drop(source);
+
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .expect("Source didn't stop")
+        .unwrap();
+    // the socket and sensors are released with the source
+    let (handle, source) = run_service("127.0.0.1:0", Transport::Udp, "Phone", sensors).unwrap();
+    source.stop();
+    handle.await.unwrap();
+}
@@ -0,0 +1,428 @@
+pub(crate) mod sink;
+pub(crate) mod source;
+
+use nalgebra::{Matrix3, Vector3};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::temperature::TemperatureTracker;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUFilter, IMUSample, IMUSink, IMUSource};
+use imu_common::types::registry::{SourceParams, SourceRegistry};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::untimed::XYZ;
+use publisher::PublisherManager;
+
+/// Source parameter with the path of the calibration file applied by `create_calibrated`.
+pub const CALIBRATION_FILE_PARAM: &str = "calibration_file";
+
+const IDENTITY: [[f64; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Calibration of a 3 axis sensor.
+///
+/// Readings are corrected as `misalignment * ((reading - bias(t)) / scale)`, where the bias
+/// depends on the temperature `t` as
+/// `bias(t) = bias + c1 * (t - t_ref) + c2 * (t - t_ref)^2 + ...`. The temperature terms are
+/// skipped while the temperature is unknown.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SensorCalibration {
+    #[serde(default)]
+    bias: [f64; 3],
+    #[serde(default = "unit_scale")]
+    scale: [f64; 3],
+    /// Rows of the matrix mapping the sensor axes to orthogonal axes.
+    #[serde(default = "identity")]
+    misalignment: [[f64; 3]; 3],
+    #[serde(default)]
+    reference_temperature: f64,
+    /// Temperature coefficients of the bias, first order first.
+    #[serde(default)]
+    temperature_coefficients: Vec<[f64; 3]>,
+    /// Seconds since the UNIX epoch.
+    #[serde(default)]
+    calibrated_at: f64,
+}
+
+fn unit_scale() -> [f64; 3] {
+    [1.0; 3]
+}
+
+fn identity() -> [[f64; 3]; 3] {
+    IDENTITY
+}
+
+impl Default for SensorCalibration {
+    fn default() -> Self {
+        Self {
+            bias: [0.0; 3],
+            scale: unit_scale(),
+            misalignment: IDENTITY,
+            reference_temperature: 0.0,
+            temperature_coefficients: Vec::new(),
+            calibrated_at: 0.0,
+        }
+    }
+}
+
+impl SensorCalibration {
+    /// Returns a calibration removing `bias` and `scale`, timestamped now.
+    pub fn new(bias: XYZ, scale: XYZ) -> Self {
+        let calibrated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_secs_f64())
+            .unwrap_or_default();
+        Self {
+            bias: bias.inner(),
+            scale: scale.inner(),
+            calibrated_at,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the rows of the matrix mapping the sensor axes to orthogonal axes.
+    pub fn with_misalignment(mut self, misalignment: [[f64; 3]; 3]) -> Self {
+        self.misalignment = misalignment;
+        self
+    }
+
+    /// Sets the temperature coefficients of the bias around `reference_temperature`, first
+    /// order first.
+    pub fn with_temperature_coefficients(
+        mut self,
+        reference_temperature: f64,
+        coefficients: Vec<XYZ>,
+    ) -> Self {
+        self.reference_temperature = reference_temperature;
+        self.temperature_coefficients = coefficients.iter().map(XYZ::inner).collect();
+        self
+    }
+
+    pub fn get_bias(&self) -> XYZ {
+        XYZ::new(self.bias)
+    }
+
+    pub fn get_scale(&self) -> XYZ {
+        XYZ::new(self.scale)
+    }
+
+    pub fn get_misalignment(&self) -> [[f64; 3]; 3] {
+        self.misalignment
+    }
+
+    /// Returns the time of the calibration, in seconds since the UNIX epoch.
+    pub fn get_calibrated_at(&self) -> f64 {
+        self.calibrated_at
+    }
+
+    /// Returns the bias at `temperature`, or the bias at the reference temperature if unknown.
+    pub fn bias_at(&self, temperature: Option<f64>) -> XYZ {
+        let bias = Vector3::from(self.bias);
+        let Some(temperature) = temperature else {
+            return XYZ::from_vector(bias);
+        };
+        let delta = temperature - self.reference_temperature;
+        let drift = self
+            .temperature_coefficients
+            .iter()
+            .rev()
+            .fold(Vector3::zeros(), |acc, c| (acc + Vector3::from(*c)) * delta);
+        XYZ::from_vector(bias + drift)
+    }
+
+    pub fn correct(&self, reading: XYZ, temperature: Option<f64>) -> XYZ {
+        let misalignment = Matrix3::from_row_slice(self.misalignment.as_flattened());
+        let unbiased = reading.0 - self.bias_at(temperature).0;
+        XYZ::from_vector(misalignment * unbiased.component_div(&Vector3::from(self.scale)))
+    }
+}
+
+impl IMUFilter<Sample3D> for SensorCalibration {
+    /// Corrects a batch of readings, without the temperature terms.
+    fn filter_batch(&mut self, samples: Vec<Sample3D>) -> Result<Vec<Sample3D>, ImuError> {
+        if samples.is_empty() {
+            return Err(ImuError::EmptyInput);
+        }
+        Ok(samples
+            .into_iter()
+            .map(|s| {
+                Sample3D::from_measurement(
+                    s.get_timestamp_secs(),
+                    self.correct(s.get_measurement(), None),
+                )
+            })
+            .collect())
+    }
+}
+
+/// Calibrations of several sensors, indexed by the uuid of the sensor and stored as JSON.
+///
+/// ```json
+/// {
+///   "sensors": {
+///     "d9ba2b3a-6c4e-4b59-a8d8-d3ee1b4f8f6a": {
+///       "bias": [0.12, -0.05, 0.3],
+///       "scale": [1.01, 0.99, 1.0],
+///       "misalignment": [[1.0, 0.01, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+///       "reference_temperature": 25.0,
+///       "temperature_coefficients": [[0.001, 0.002, 0.0]],
+///       "calibrated_at": 1735689600.0
+///     }
+///   }
+/// }
+/// ```
+///
+/// Missing fields of a sensor default to no correction.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationFile {
+    sensors: BTreeMap<String, SensorCalibration>,
+}
+
+impl CalibrationFile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImuError> {
+        let file = std::fs::File::open(path.as_ref())
+            .map_err(|e| ImuError::Io(format!("Cannot open {}: {}", path.as_ref().display(), e)))?;
+        serde_json::from_reader(std::io::BufReader::new(file)).map_err(|e| {
+            ImuError::Io(format!(
+                "Invalid calibration file {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ImuError> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| ImuError::Io(e.to_string()))?;
+        std::fs::write(path.as_ref(), json)
+            .map_err(|e| ImuError::Io(format!("Cannot write {}: {}", path.as_ref().display(), e)))
+    }
+
+    pub fn get(&self, sensor_type: &SensorType) -> Option<&SensorCalibration> {
+        self.sensors.get(&sensor_id(sensor_type).to_string())
+    }
+
+    /// Sets the calibration of `sensor_type`, replacing the previous one.
+    pub fn insert(&mut self, sensor_type: &SensorType, calibration: SensorCalibration) {
+        self.sensors
+            .insert(sensor_id(sensor_type).to_string(), calibration);
+    }
+
+    pub fn remove(&mut self, sensor_type: &SensorType) -> Option<SensorCalibration> {
+        self.sensors.remove(&sensor_id(sensor_type).to_string())
+    }
+
+    pub fn len(&self) -> usize {
+        self.sensors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sensors.is_empty()
+    }
+}
+
+fn sensor_id(sensor_type: &SensorType) -> Uuid {
+    match sensor_type {
+        SensorType::Accelerometer(uuid)
+        | SensorType::Gyroscope(uuid)
+        | SensorType::Magnetometer(uuid)
+        | SensorType::Other(uuid, _)
+        | SensorType::Vendor(uuid, _) => *uuid,
+    }
+}
+
+/// Applies the calibrations of a `CalibrationFile` to the readings of the sensors it contains.
+///
+/// Readings of other sensors are forwarded unchanged. When a temperature tracker is given, e.g.
+/// shared with a `TemperatureCompensator`, the temperature terms are applied once a temperature
+/// is known.
+#[derive(Clone)]
+pub struct CalibrationStage {
+    tag: String,
+    calibrations: Arc<CalibrationFile>,
+    temperature: Option<TemperatureTracker>,
+    /// Source feeding the stage, kept alive when created from the source registry.
+    upstream: Option<Arc<dyn IMUSource<SensorReadings<Sample3D>, Sample3D>>>,
+    publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+}
+
+impl CalibrationStage {
+    /// Returns a stage applying `calibrations`, publishing `sensor_cluster`.
+    pub fn new(tag: &str, sensor_cluster: Vec<SensorType>, calibrations: CalibrationFile) -> Self {
+        Self {
+            tag: tag.to_string(),
+            calibrations: Arc::new(calibrations),
+            temperature: None,
+            upstream: None,
+            publishers: PublisherManager::new(&sensor_cluster),
+        }
+    }
+
+    pub fn with_temperature(mut self, temperature: TemperatureTracker) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn get_calibrations(&self) -> &CalibrationFile {
+        &self.calibrations
+    }
+
+    fn calibrate(&self, sensor_type: &SensorType, samples: Vec<Sample3D>) -> Vec<Sample3D> {
+        let Some(calibration) = self.calibrations.get(sensor_type) else {
+            return samples;
+        };
+        let temperature = self.temperature.as_ref().and_then(TemperatureTracker::get);
+        samples
+            .into_iter()
+            .map(|s| {
+                Sample3D::from_measurement(
+                    s.get_timestamp_secs(),
+                    calibration.correct(s.get_measurement(), temperature),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Returns `source` unchanged, or behind a `CalibrationStage` attached to every sensor of
+/// `source` if `params` has a `calibration_file` path.
+pub fn apply_calibration(
+    source: Arc<dyn IMUSource<SensorReadings<Sample3D>, Sample3D>>,
+    params: &SourceParams,
+) -> Result<Arc<dyn IMUSource<SensorReadings<Sample3D>, Sample3D>>, ImuError> {
+    if !params.contains(CALIBRATION_FILE_PARAM) {
+        return Ok(source);
+    }
+    let calibrations = CalibrationFile::load(params.get_str(CALIBRATION_FILE_PARAM)?)?;
+    let sensor_cluster = source.get_available_sensors();
+    let mut stage = CalibrationStage::new(source.get_tag(), sensor_cluster.clone(), calibrations);
+    stage.attach_listeners(&*source, &sensor_cluster)?;
+    stage.upstream = Some(source);
+    Ok(Arc::new(stage))
+}
+
+/// Instantiates the source registered under `name`, applying the calibration file given in
+/// `params`, if any.
+pub fn create_calibrated(
+    registry: &SourceRegistry<SensorReadings<Sample3D>, Sample3D>,
+    name: &str,
+    params: &SourceParams,
+) -> Result<Arc<dyn IMUSource<SensorReadings<Sample3D>, Sample3D>>, ImuError> {
+    apply_calibration(registry.create(name, params)?, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::traits::IMUReadings;
+    use imu_common::types::sensors::SensorClusterBuilder;
+    use publisher::Listener;
+    use std::sync::Mutex;
+
+    fn temp_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("calibration_{}.json", Uuid::new_v4()))
+    }
+
+    fn assert_xyz_eq(xyz: XYZ, expected: [f64; 3]) {
+        for (v, expected) in xyz.inner().iter().zip(expected) {
+            assert!((v - expected).abs() < 1e-9, "{:?} != {:?}", xyz, expected);
+        }
+    }
+
+    #[test]
+    fn test_correct() {
+        let calibration = SensorCalibration::new(XYZ::new([0.1, 0.2, 0.3]), XYZ::new([2.0; 3]))
+            .with_misalignment([[1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]])
+            .with_temperature_coefficients(20.0, vec![XYZ::new([0.01, 0.0, 0.0])]);
+        assert!(calibration.get_calibrated_at() > 0.0);
+
+        let reading = XYZ::new([2.1, 4.2, 6.3]);
+        assert_xyz_eq(calibration.correct(reading.clone(), None), [1.0, 3.0, 2.0]);
+        // 10 degrees above the reference
+        assert_xyz_eq(
+            calibration.correct(reading.clone(), Some(30.0)),
+            [0.95, 3.0, 2.0],
+        );
+
+        let mut filter = SensorCalibration::default();
+        let samples = vec![Sample3D::new(1.0, reading.inner())];
+        assert_eq!(filter.filter_batch(samples.clone()).unwrap(), samples);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let sensor_cluster = SensorClusterBuilder::new().six_axis().build().unwrap();
+        let mut file = CalibrationFile::new();
+        file.insert(
+            &sensor_cluster[0],
+            SensorCalibration::new(XYZ::new([0.1, 0.2, 0.3]), XYZ::new([1.0, 1.5, 2.0])),
+        );
+        let path = temp_path();
+        file.save(&path).unwrap();
+        let loaded = CalibrationFile::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, file);
+        assert!(loaded.get(&sensor_cluster[1]).is_none());
+        assert!(CalibrationFile::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_partial_entries() {
+        let json =
+            r#"{"sensors": {"d9ba2b3a-6c4e-4b59-a8d8-d3ee1b4f8f6a": {"bias": [1.0, 0.0, 0.0]}}}"#;
+        let file: CalibrationFile = serde_json::from_str(json).unwrap();
+        let sensor_type =
+            SensorType::Gyroscope("d9ba2b3a-6c4e-4b59-a8d8-d3ee1b4f8f6a".parse().unwrap());
+        let calibration = file.get(&sensor_type).unwrap();
+        assert_eq!(calibration.get_scale(), XYZ::new([1.0; 3]));
+        assert_xyz_eq(
+            calibration.correct(XYZ::new([1.0, 2.0, 3.0]), Some(40.0)),
+            [0.0, 2.0, 3.0],
+        );
+    }
+
+    #[test]
+    fn test_stage() {
+        let sensor_cluster = SensorClusterBuilder::new().six_axis().build().unwrap();
+        let mut file = CalibrationFile::new();
+        file.insert(
+            &sensor_cluster[1],
+            SensorCalibration::new(XYZ::new([0.5, 0.5, 0.5]), XYZ::new([1.0; 3])),
+        );
+        let stage = CalibrationStage::new("test", sensor_cluster.clone(), file);
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut listener = Listener::new({
+            let received = received.clone();
+            move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+                received.lock().unwrap().extend(readings.get_samples());
+            }
+        });
+        for sensor_type in sensor_cluster.iter() {
+            stage.register_listener(&mut listener, sensor_type).unwrap();
+        }
+
+        for sensor_type in sensor_cluster.iter() {
+            let readings = SensorReadings::from_vec(
+                "test",
+                sensor_type.clone(),
+                vec![Sample3D::new(0.0, [1.0, 1.0, 1.0])],
+            );
+            IMUSink::<SensorReadings<Sample3D>, Sample3D>::process_samples(
+                &stage,
+                Uuid::new_v4(),
+                Arc::new(readings),
+            );
+        }
+        let received = received.lock().unwrap();
+        assert_eq!(received[0], Sample3D::new(0.0, [1.0, 1.0, 1.0]));
+        assert_eq!(received[1], Sample3D::new(0.0, [0.5, 0.5, 0.5]));
+    }
+}
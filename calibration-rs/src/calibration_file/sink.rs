@@ -0,0 +1,30 @@
+use publisher::adapters;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::CalibrationStage;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSink, IMUSource};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+
+impl<T> IMUSink<T, Sample3D> for CalibrationStage
+where
+    T: Send + Sync + IMUReadings<Sample3D> + 'static,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, Sample3D>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        let sensor_type = samples.get_sensor_type();
+        let calibrated = self.calibrate(&sensor_type, samples.get_samples());
+        let readings = SensorReadings::from_vec(&self.tag, sensor_type.clone(), calibrated);
+        self.publishers
+            .notify_listeners(sensor_type, Arc::new(readings));
+    }
+}
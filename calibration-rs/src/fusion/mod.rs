@@ -0,0 +1,566 @@
+pub(crate) mod sink;
+pub(crate) mod source;
+
+use nalgebra::{Matrix3, Rotation3, UnitQuaternion, Vector3};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUFilter, IMUSample};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::untimed::XYZ;
+use publisher::PublisherManager;
+
+/// Minimum number of static poses to estimate an alignment.
+pub const MIN_ALIGNMENT_POSES: usize = 2;
+/// Minimum angle between the gravity directions of two static poses, in radians. Closer poses add
+/// no information about the alignment.
+pub const MIN_POSE_ANGLE: f64 = 0.35;
+
+const DEFAULT_WINDOW_SIZE: usize = 50;
+const DEFAULT_VARIANCE_THRESHOLD: f64 = 0.02;
+const DEFAULT_MIN_POSES: usize = 3;
+/// Maximum time difference, in seconds, between readings of both sensors fused together.
+const PAIRING_TOLERANCE: f64 = 1e-3;
+/// Readings waiting for a reading of the other sensor, after which they are published unfused.
+const MAX_PENDING_SAMPLES: usize = 100;
+const MIN_VARIANCE: f64 = 1e-12;
+
+/// Readings of the primary and the secondary sensor.
+type ReadingPair = (Vector3<f64>, Vector3<f64>);
+
+/// Rotation mapping the readings of a secondary sensor to the axes of the primary one:
+/// `aligned = rotation * reading`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensorAlignment {
+    rotation: UnitQuaternion<f64>,
+}
+
+impl Default for SensorAlignment {
+    fn default() -> Self {
+        Self::new(UnitQuaternion::identity())
+    }
+}
+
+impl SensorAlignment {
+    pub fn new(rotation: UnitQuaternion<f64>) -> Self {
+        Self { rotation }
+    }
+
+    pub fn get_rotation(&self) -> UnitQuaternion<f64> {
+        self.rotation
+    }
+
+    pub fn align(&self, reading: XYZ) -> XYZ {
+        XYZ::from_vector(self.rotation * reading.0)
+    }
+}
+
+impl IMUFilter<Sample3D> for SensorAlignment {
+    /// Rotates a batch of samples to the axes of the primary sensor.
+    fn filter_batch(&mut self, samples: Vec<Sample3D>) -> Result<Vec<Sample3D>, ImuError> {
+        if samples.is_empty() {
+            return Err(ImuError::EmptyInput);
+        }
+        Ok(samples
+            .into_iter()
+            .map(|s| {
+                Sample3D::from_measurement(s.get_timestamp_secs(), self.align(s.get_measurement()))
+            })
+            .collect())
+    }
+}
+
+/// Estimates the [`SensorAlignment`] of two rigidly mounted accelerometers from the gravity they
+/// measure in static poses.
+///
+/// A single pose only gives the direction of gravity, so at least `MIN_ALIGNMENT_POSES` poses
+/// with different orientations are needed. The rotation best mapping the secondary readings to
+/// the primary ones is found by solving Wahba's problem. Only the directions of the readings are
+/// used, so scale errors of either sensor don't bias the alignment. The estimator is a handle:
+/// clones share the same poses.
+///
+/// ## Example
+///
+/// ```rust
+/// use calibration_rs::AlignmentEstimator;
+/// use imu_common::types::untimed::XYZ;
+///
+/// let estimator = AlignmentEstimator::new();
+/// // the secondary sensor is rotated 90° around z
+/// estimator.add_pose(XYZ::new([0.0, 0.0, 9.8]), XYZ::new([0.0, 0.0, 9.8]));
+/// estimator.add_pose(XYZ::new([9.8, 0.0, 0.0]), XYZ::new([0.0, -9.8, 0.0]));
+///
+/// let alignment = estimator.estimate().unwrap();
+/// let aligned = alignment.align(XYZ::new([0.0, -9.8, 0.0])).inner();
+/// assert!((aligned[0] - 9.8).abs() < 1e-9);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AlignmentEstimator {
+    poses: Arc<Mutex<Vec<ReadingPair>>>,
+}
+
+impl AlignmentEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of poses added.
+    pub fn len(&self) -> usize {
+        self.poses.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn reset(&self) {
+        self.poses.lock().unwrap().clear();
+    }
+
+    /// Adds the mean readings of both sensors in a static pose.
+    /// Returns false, skipping the pose, if the primary reading is within `MIN_POSE_ANGLE` of a
+    /// pose already added or is null.
+    pub fn add_pose(&self, primary: XYZ, secondary: XYZ) -> bool {
+        let (primary, secondary) = (primary.0, secondary.0);
+        if primary.norm() == 0.0 || secondary.norm() == 0.0 {
+            return false;
+        }
+        let mut poses = self.poses.lock().unwrap();
+        if poses
+            .iter()
+            .any(|(p, _)| p.angle(&primary) < MIN_POSE_ANGLE)
+        {
+            return false;
+        }
+        poses.push((primary, secondary));
+        true
+    }
+
+    /// Estimates the alignment from the poses added.
+    /// Returns an error if fewer than `MIN_ALIGNMENT_POSES` poses were added.
+    pub fn estimate(&self) -> Result<SensorAlignment, ImuError> {
+        let poses = self.poses.lock().unwrap().clone();
+        if poses.len() < MIN_ALIGNMENT_POSES {
+            return Err(ImuError::InvalidInput(format!(
+                "{} poses, at least {} needed",
+                poses.len(),
+                MIN_ALIGNMENT_POSES
+            )));
+        }
+        let attitude_profile: Matrix3<f64> = poses
+            .iter()
+            .map(|(p, s)| p.normalize() * s.normalize().transpose())
+            .sum();
+        let svd = attitude_profile.svd(true, true);
+        let (u, v_t) = (svd.u.unwrap(), svd.v_t.unwrap());
+        // closest rotation, rather than reflection
+        let sign = (u * v_t).determinant().signum();
+        let rotation = u * Matrix3::from_diagonal(&Vector3::new(1.0, 1.0, sign)) * v_t;
+        Ok(SensorAlignment::new(UnitQuaternion::from_rotation_matrix(
+            &Rotation3::from_matrix_unchecked(rotation),
+        )))
+    }
+}
+
+/// Alignment, noise and pending readings of a [`RedundantFusion`].
+#[derive(Debug, Default)]
+struct FusionState {
+    alignment: Option<SensorAlignment>,
+    /// Per axis noise variances of the primary and the aligned secondary readings.
+    variances: Option<(Vector3<f64>, Vector3<f64>)>,
+    primary: VecDeque<Sample3D>,
+    secondary: VecDeque<Sample3D>,
+    /// Latest pairs of readings, to detect static poses.
+    window: VecDeque<ReadingPair>,
+}
+
+/// Fuses the readings of two redundant accelerometers, e.g. of two phones rigidly mounted
+/// together, into a single lower noise stream before an `AHRSFilter`.
+///
+/// Readings of both sensors with the same timestamp are fused, so both streams should be
+/// resampled to the same rate first. The secondary readings are rotated to the axes of the
+/// primary sensor, and every axis is averaged weighted by the inverse of the noise variance of
+/// each sensor. Fused readings are published as readings of the primary sensor. Readings of the
+/// other sensors of the cluster, e.g. the gyroscope of the primary device, are republished
+/// unchanged.
+///
+/// Unless given, the alignment is estimated first. Every time both sensors are at rest, their
+/// mean readings are added as a static pose to an [`AlignmentEstimator`], and the alignment is
+/// estimated once the device was left at rest in enough orientations. The noise variances are
+/// updated in every static pose. Primary readings are published unfused until the alignment is
+/// known.
+#[derive(Clone)]
+pub struct RedundantFusion {
+    tag: String,
+    primary: SensorType,
+    secondary: SensorType,
+    window_size: usize,
+    variance_threshold: f64,
+    min_poses: usize,
+    estimator: AlignmentEstimator,
+    state: Arc<Mutex<FusionState>>,
+    publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+}
+
+impl RedundantFusion {
+    /// Returns a node fusing the readings of `secondary` into the readings of `primary`,
+    /// publishing `sensor_cluster`.
+    /// Returns an error if `primary` isn't in `sensor_cluster`.
+    pub fn new(
+        tag: &str,
+        sensor_cluster: Vec<SensorType>,
+        primary: SensorType,
+        secondary: SensorType,
+    ) -> Result<Self, ImuError> {
+        if !sensor_cluster.contains(&primary) {
+            return Err(ImuError::InvalidInput(format!(
+                "Sensor {} not in sensor cluster",
+                primary
+            )));
+        }
+        Ok(Self {
+            tag: tag.to_string(),
+            primary,
+            secondary,
+            window_size: DEFAULT_WINDOW_SIZE,
+            variance_threshold: DEFAULT_VARIANCE_THRESHOLD,
+            min_poses: DEFAULT_MIN_POSES,
+            estimator: AlignmentEstimator::new(),
+            state: Arc::new(Mutex::new(FusionState::default())),
+            publishers: PublisherManager::new(&sensor_cluster),
+        })
+    }
+
+    /// Sets a known alignment, e.g. from a previous run, skipping the estimation.
+    pub fn with_alignment(self, alignment: SensorAlignment) -> Self {
+        self.state.lock().unwrap().alignment = Some(alignment);
+        self
+    }
+
+    /// Detects static poses over windows of `window_size` readings, at least 2, where the sum of
+    /// the variances of the 3 axes of each sensor, in (m/s²)², is below `variance_threshold`.
+    pub fn with_stationarity(mut self, window_size: usize, variance_threshold: f64) -> Self {
+        self.window_size = window_size.max(2);
+        self.variance_threshold = variance_threshold;
+        self
+    }
+
+    /// Sets the number of static poses, at least `MIN_ALIGNMENT_POSES`, after which the alignment
+    /// is estimated.
+    pub fn with_min_poses(mut self, min_poses: usize) -> Self {
+        self.min_poses = min_poses.max(MIN_ALIGNMENT_POSES);
+        self
+    }
+
+    pub fn get_alignment(&self) -> Option<SensorAlignment> {
+        self.state.lock().unwrap().alignment.clone()
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.get_alignment().is_some()
+    }
+
+    /// Returns the per axis noise variances of the primary and the aligned secondary readings,
+    /// once estimated in a static pose.
+    pub fn get_variances(&self) -> Option<(XYZ, XYZ)> {
+        self.state
+            .lock()
+            .unwrap()
+            .variances
+            .map(|(primary, secondary)| (XYZ::from_vector(primary), XYZ::from_vector(secondary)))
+    }
+
+    /// Drops the alignment and the noise variances and starts a new estimation, e.g. after the
+    /// sensors were mounted again.
+    pub fn reset_alignment(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.alignment = None;
+        state.variances = None;
+        state.window.clear();
+        self.estimator.reset();
+    }
+
+    /// Adds readings of `sensor_type` and returns the readings ready to be published as
+    /// readings of the primary sensor.
+    fn fuse(&self, sensor_type: &SensorType, samples: Vec<Sample3D>) -> Vec<Sample3D> {
+        let mut state = self.state.lock().unwrap();
+        if *sensor_type == self.primary {
+            state.primary.extend(samples);
+        } else {
+            state.secondary.extend(samples);
+        }
+        let mut fused = Vec::new();
+        while let (Some(primary), Some(secondary)) =
+            (state.primary.front(), state.secondary.front())
+        {
+            let dt = primary.get_timestamp_secs() - secondary.get_timestamp_secs();
+            if dt.abs() <= PAIRING_TOLERANCE {
+                let primary = state.primary.pop_front().unwrap();
+                let secondary = state.secondary.pop_front().unwrap();
+                fused.push(self.fuse_pair(&mut state, primary, secondary));
+            } else if dt < 0.0 {
+                // no secondary reading for it
+                fused.push(state.primary.pop_front().unwrap());
+            } else {
+                state.secondary.pop_front();
+            }
+        }
+        // the other sensor stopped, don't hold the primary stream back
+        while state.primary.len() > MAX_PENDING_SAMPLES {
+            fused.push(state.primary.pop_front().unwrap());
+        }
+        while state.secondary.len() > MAX_PENDING_SAMPLES {
+            state.secondary.pop_front();
+        }
+        fused
+    }
+
+    fn fuse_pair(
+        &self,
+        state: &mut FusionState,
+        primary: Sample3D,
+        secondary: Sample3D,
+    ) -> Sample3D {
+        let p = primary.get_measurement().0;
+        let s = secondary.get_measurement().0;
+        self.update_estimates(state, p, s);
+        let Some(alignment) = state.alignment.as_ref() else {
+            return primary;
+        };
+        let s = alignment.rotation * s;
+        let fused = match state.variances {
+            Some((var_p, var_s)) => {
+                let (w_p, w_s) = (var_p.map(weight), var_s.map(weight));
+                (p.component_mul(&w_p) + s.component_mul(&w_s)).component_div(&(w_p + w_s))
+            }
+            None => (p + s) / 2.0,
+        };
+        Sample3D::from_measurement(primary.get_timestamp_secs(), XYZ::from_vector(fused))
+    }
+
+    /// Adds a pair of readings to the window and, if both sensors are at rest, uses it as a
+    /// static pose.
+    fn update_estimates(&self, state: &mut FusionState, p: Vector3<f64>, s: Vector3<f64>) {
+        if state.window.len() == self.window_size {
+            state.window.pop_front();
+        }
+        state.window.push_back((p, s));
+        if state.window.len() < self.window_size {
+            return;
+        }
+        let (mean_p, var_p) = mean_variance(state.window.iter().map(|(p, _)| *p));
+        let (mean_s, var_s) = mean_variance(state.window.iter().map(|(_, s)| *s));
+        if var_p.sum() >= self.variance_threshold || var_s.sum() >= self.variance_threshold {
+            return;
+        }
+        // the next static pose starts with a new window
+        state.window.clear();
+
+        if state.alignment.is_none() {
+            self.estimator
+                .add_pose(XYZ::from_vector(mean_p), XYZ::from_vector(mean_s));
+            if self.estimator.len() >= self.min_poses {
+                state.alignment = self.estimator.estimate().ok();
+            }
+        }
+        // the variance of each axis of the secondary sensor in the axes of the primary one
+        if let Some(alignment) = state.alignment.as_ref() {
+            let rotation = alignment.rotation.to_rotation_matrix().into_inner();
+            let var_s = rotation.component_mul(&rotation) * var_s;
+            state.variances = Some((var_p, var_s));
+        }
+    }
+}
+
+fn weight(variance: f64) -> f64 {
+    1.0 / variance.max(MIN_VARIANCE)
+}
+
+fn mean_variance(
+    readings: impl Iterator<Item = Vector3<f64>> + Clone,
+) -> (Vector3<f64>, Vector3<f64>) {
+    let n = readings.clone().count() as f64;
+    let mean = readings.clone().sum::<Vector3<f64>>() / n;
+    let variance = readings
+        .map(|r| (r - mean).component_mul(&(r - mean)))
+        .sum::<Vector3<f64>>()
+        / n;
+    (mean, variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAVITY: f64 = 9.81;
+
+    fn mounting() -> UnitQuaternion<f64> {
+        UnitQuaternion::from_euler_angles(0.1, -0.3, 1.2)
+    }
+
+    /// Deterministic noise in [-amplitude, amplitude].
+    fn noise(seed: &mut u64, amplitude: f64) -> f64 {
+        *seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((*seed >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0) * amplitude
+    }
+
+    /// Readings of both sensors at rest for `n_samples` samples, with gravity along `direction`
+    /// in the primary axes. The secondary sensor is mounted with `mounting`. The primary sensor is
+    /// noisier along its z axis, and the secondary one along its x axis.
+    fn static_pose(
+        direction: Vector3<f64>,
+        start: usize,
+        n_samples: usize,
+        seed: &mut u64,
+    ) -> (Vec<Sample3D>, Vec<Sample3D>) {
+        let gravity = direction.normalize() * GRAVITY;
+        let secondary_gravity = mounting().inverse() * gravity;
+        (start..start + n_samples)
+            .map(|i| {
+                let t = i as f64 * 0.01;
+                let p = gravity + Vector3::new(0.01, 0.01, 0.04).map(|a| noise(seed, a));
+                let s = secondary_gravity + Vector3::new(0.04, 0.01, 0.01).map(|a| noise(seed, a));
+                (
+                    Sample3D::from_measurement(t, XYZ::from_vector(p)),
+                    Sample3D::from_measurement(t, XYZ::from_vector(s)),
+                )
+            })
+            .unzip()
+    }
+
+    #[test]
+    fn test_estimate_alignment() {
+        let estimator = AlignmentEstimator::new();
+        let gravity = Vector3::new(0.0, 0.0, GRAVITY);
+        let rotations = [
+            UnitQuaternion::identity(),
+            UnitQuaternion::from_euler_angles(1.0, 0.0, 0.0),
+            UnitQuaternion::from_euler_angles(0.0, -1.2, 0.0),
+        ];
+        for rotation in rotations {
+            let p = rotation * gravity;
+            let s = mounting().inverse() * p;
+            assert!(estimator.add_pose(XYZ::from_vector(p), XYZ::from_vector(s) * 1.02));
+        }
+        let alignment = estimator.estimate().unwrap();
+        assert!(alignment.get_rotation().angle_to(&mounting()) < 1e-9);
+    }
+
+    #[test]
+    fn test_estimate_alignment_errors() {
+        let estimator = AlignmentEstimator::new();
+        estimator.add_pose(XYZ::new([0.0, 0.0, GRAVITY]), XYZ::new([0.0, 0.0, GRAVITY]));
+        assert!(estimator.estimate().is_err());
+        // same pose again
+        assert!(!estimator.add_pose(XYZ::new([0.1, 0.0, GRAVITY]), XYZ::new([0.1, 0.0, GRAVITY])));
+        assert_eq!(estimator.len(), 1);
+        assert!(estimator.estimate().is_err());
+    }
+
+    #[test]
+    fn test_fuse() {
+        let cluster = SensorType::cluster_for_tag("test_fusion_primary");
+        let secondary = SensorType::cluster_for_tag("test_fusion_secondary")[0].clone();
+        let fusion = RedundantFusion::new(
+            "fused",
+            cluster.clone(),
+            cluster[0].clone(),
+            secondary.clone(),
+        )
+        .unwrap()
+        .with_stationarity(50, 0.02);
+        let mut seed = 7;
+
+        // at rest in 3 orientations
+        let directions = [
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(0.0, 1.0, 1.0),
+            Vector3::new(1.0, 0.0, 0.2),
+        ];
+        for (i, direction) in directions.into_iter().enumerate() {
+            let (p, s) = static_pose(direction, i * 100, 100, &mut seed);
+            let published = fusion.fuse(&cluster[0], p.clone());
+            assert!(published.is_empty());
+            let published = fusion.fuse(&secondary, s);
+            assert_eq!(published.len(), 100);
+        }
+        let alignment = fusion.get_alignment().unwrap();
+        assert!(alignment.get_rotation().angle_to(&mounting()) < 0.01);
+        let (var_p, var_s) = fusion.get_variances().unwrap();
+        assert!(var_s
+            .inner()
+            .iter()
+            .zip(var_p.inner())
+            .any(|(s, p)| *s > 2.0 * p));
+
+        // fused readings are less noisy than the primary ones
+        let direction = Vector3::new(0.0, 0.0, 1.0);
+        let (p, s) = static_pose(direction, 300, 200, &mut seed);
+        fusion.fuse(&secondary, s);
+        let fused = fusion.fuse(&cluster[0], p.clone());
+        assert_eq!(fused.len(), 200);
+        let truth = direction * GRAVITY;
+        let error = |samples: &[Sample3D]| {
+            samples
+                .iter()
+                .map(|s| (s.get_measurement().0 - truth).norm_squared())
+                .sum::<f64>()
+        };
+        assert!(error(&fused) < 0.8 * error(&p));
+    }
+
+    #[test]
+    fn test_unpaired_readings() {
+        let cluster = SensorType::cluster_for_tag("test_fusion_unpaired");
+        let secondary = SensorType::cluster_for_tag("test_fusion_unpaired_secondary")[0].clone();
+        let fusion = RedundantFusion::new(
+            "fused",
+            cluster.clone(),
+            cluster[0].clone(),
+            secondary.clone(),
+        )
+        .unwrap()
+        .with_alignment(SensorAlignment::default());
+        assert!(RedundantFusion::new(
+            "fused",
+            cluster.clone(),
+            secondary.clone(),
+            cluster[0].clone()
+        )
+        .is_err());
+
+        let published = fusion.fuse(
+            &cluster[0],
+            vec![
+                Sample3D::new(0.0, [1.0, 0.0, 0.0]),
+                Sample3D::new(0.01, [1.0, 0.0, 0.0]),
+            ],
+        );
+        assert!(published.is_empty());
+        // the primary reading at 0.0 has no secondary reading, and the one at 0.005 no primary
+        let published = fusion.fuse(
+            &secondary,
+            vec![
+                Sample3D::new(0.005, [0.0, 0.0, 0.0]),
+                Sample3D::new(0.01, [3.0, 0.0, 0.0]),
+            ],
+        );
+        assert_eq!(
+            published,
+            vec![
+                Sample3D::new(0.0, [1.0, 0.0, 0.0]),
+                Sample3D::new(0.01, [2.0, 0.0, 0.0])
+            ]
+        );
+
+        // the secondary sensor stopped
+        let samples: Vec<_> = (2..=MAX_PENDING_SAMPLES + 2)
+            .map(|i| Sample3D::new(i as f64 * 0.01, [1.0, 0.0, 0.0]))
+            .collect();
+        assert_eq!(fusion.fuse(&cluster[0], samples).len(), 1);
+    }
+}
@@ -0,0 +1,279 @@
+pub(crate) mod sink;
+
+use nalgebra::Vector3;
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUFilter, IMUSample};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::untimed::XYZ;
+
+const DEFAULT_WINDOW_SIZE: usize = 50;
+const DEFAULT_VARIANCE_THRESHOLD: f64 = 0.02;
+const DEFAULT_MIN_SAMPLES: usize = 200;
+
+/// Detects whether the device is at rest from the variance of the latest accelerometer samples.
+///
+/// The device is stationary once the window is full and the sum of the variances of the 3 axes,
+/// in (m/s²)², is below the threshold. The detector is a handle: clones share the same window,
+/// so it can be attached to an accelerometer source and handed to a `GyroBiasEstimator`.
+#[derive(Clone, Debug)]
+pub struct StationarityDetector {
+    window_size: usize,
+    variance_threshold: f64,
+    window: Arc<RwLock<VecDeque<Vector3<f64>>>>,
+}
+
+impl Default for StationarityDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW_SIZE, DEFAULT_VARIANCE_THRESHOLD)
+    }
+}
+
+impl StationarityDetector {
+    /// Returns a detector over the last `window_size` samples, at least 2.
+    pub fn new(window_size: usize, variance_threshold: f64) -> Self {
+        let window_size = window_size.max(2);
+        Self {
+            window_size,
+            variance_threshold,
+            window: Arc::new(RwLock::new(VecDeque::with_capacity(window_size))),
+        }
+    }
+
+    /// Adds accelerometer samples to the window, dropping the oldest ones.
+    pub fn update(&self, samples: &[Sample3D]) {
+        let mut window = self.window.write().unwrap();
+        for sample in samples {
+            if window.len() == self.window_size {
+                window.pop_front();
+            }
+            window.push_back(sample.get_measurement().0);
+        }
+    }
+
+    /// Returns the sum of the variances of the 3 axes, or `None` until the window is full.
+    pub fn variance(&self) -> Option<f64> {
+        let window = self.window.read().unwrap();
+        if window.len() < self.window_size {
+            return None;
+        }
+        let n = window.len() as f64;
+        let mean = window.iter().sum::<Vector3<f64>>() / n;
+        Some(
+            window
+                .iter()
+                .map(|v| (v - mean).norm_squared())
+                .sum::<f64>()
+                / n,
+        )
+    }
+
+    pub fn is_stationary(&self) -> bool {
+        self.variance()
+            .is_some_and(|variance| variance < self.variance_threshold)
+    }
+
+    pub fn reset(&self) {
+        self.window.write().unwrap().clear();
+    }
+}
+
+/// Estimates the bias of a gyroscope while the device is at rest, and removes it afterwards.
+///
+/// Gyroscope samples received while the detector reports the device as stationary are averaged.
+/// Once `min_samples` consecutive stationary samples are averaged, the mean is taken as the bias
+/// and subtracted from every following sample. Moving restarts the estimation. Samples are
+/// returned unchanged until the bias is known.
+///
+/// ## Example
+///
+/// ```rust
+/// use calibration_rs::{GyroBiasEstimator, StationarityDetector};
+/// use imu_common::traits::IMUFilter;
+/// use imu_common::types::timed::Sample3D;
+///
+/// let detector = StationarityDetector::new(10, 0.01);
+/// let mut estimator = GyroBiasEstimator::new(detector.clone(), 10);
+///
+/// let at_rest: Vec<_> = (0..10).map(|i| Sample3D::new(i as f64 * 0.01, [0.0, 0.0, 9.8])).collect();
+/// detector.update(&at_rest);
+/// let gyro: Vec<_> = (0..10).map(|i| Sample3D::new(i as f64 * 0.01, [0.02, -0.01, 0.0])).collect();
+/// estimator.filter_batch(gyro).unwrap();
+///
+/// assert!(estimator.is_calibrated());
+/// ```
+#[derive(Clone, Debug)]
+pub struct GyroBiasEstimator {
+    detector: StationarityDetector,
+    min_samples: usize,
+    sum: XYZ,
+    n_samples: usize,
+    bias: Option<XYZ>,
+}
+
+impl GyroBiasEstimator {
+    /// Returns an estimator averaging at least `min_samples` samples, at least 1.
+    pub fn new(detector: StationarityDetector, min_samples: usize) -> Self {
+        Self {
+            detector,
+            min_samples: min_samples.max(1),
+            sum: XYZ::default(),
+            n_samples: 0,
+            bias: None,
+        }
+    }
+
+    /// Returns an estimator averaging 200 samples, i.e. 2 s at 100 Hz.
+    pub fn with_detector(detector: StationarityDetector) -> Self {
+        Self::new(detector, DEFAULT_MIN_SAMPLES)
+    }
+
+    pub fn get_bias(&self) -> Option<XYZ> {
+        self.bias.clone()
+    }
+
+    /// Sets a known bias, e.g. from a previous run, skipping the estimation.
+    pub fn set_bias(&mut self, bias: XYZ) {
+        self.bias = Some(bias);
+    }
+
+    pub fn is_calibrated(&self) -> bool {
+        self.bias.is_some()
+    }
+
+    /// Drops the estimated bias and starts a new estimation.
+    pub fn reset(&mut self) {
+        self.sum = XYZ::default();
+        self.n_samples = 0;
+        self.bias = None;
+    }
+
+    fn accumulate(&mut self, measurement: XYZ) {
+        self.sum += measurement;
+        self.n_samples += 1;
+        if self.n_samples >= self.min_samples {
+            self.bias = Some(self.sum.clone() / self.n_samples as f64);
+        }
+    }
+}
+
+impl IMUFilter<Sample3D> for GyroBiasEstimator {
+    /// Removes the bias from a batch of gyroscope samples, or uses them to estimate it.
+    fn filter_batch(&mut self, samples: Vec<Sample3D>) -> Result<Vec<Sample3D>, ImuError> {
+        if samples.is_empty() {
+            return Err(ImuError::EmptyInput);
+        }
+        if self.bias.is_none() {
+            if self.detector.is_stationary() {
+                for sample in samples.iter() {
+                    self.accumulate(sample.get_measurement());
+                }
+            } else {
+                self.sum = XYZ::default();
+                self.n_samples = 0;
+            }
+            // samples used for the estimation aren't corrected
+            return Ok(samples);
+        }
+        let bias = self.bias.clone().unwrap();
+        Ok(samples
+            .into_iter()
+            .map(|s| {
+                Sample3D::from_measurement(
+                    s.get_timestamp_secs(),
+                    s.get_measurement() - bias.clone(),
+                )
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant(n_samples: usize, measurement: [f64; 3]) -> Vec<Sample3D> {
+        (0..n_samples)
+            .map(|i| Sample3D::new(i as f64 * 0.01, measurement))
+            .collect()
+    }
+
+    #[test]
+    fn test_stationarity_detector() {
+        let detector = StationarityDetector::new(4, 0.01);
+        detector.update(&constant(3, [0.0, 0.0, 9.8]));
+        assert!(detector.variance().is_none());
+        assert!(!detector.is_stationary());
+
+        detector.update(&constant(1, [0.0, 0.0, 9.8]));
+        assert!(detector.is_stationary());
+
+        // shaking the device
+        detector.update(&[
+            Sample3D::new(0.0, [1.0, 0.0, 9.8]),
+            Sample3D::new(0.0, [-1.0, 0.0, 9.8]),
+        ]);
+        assert!((detector.variance().unwrap() - 0.5).abs() < 1e-12);
+        assert!(!detector.is_stationary());
+    }
+
+    #[test]
+    fn test_estimate_and_remove_bias() {
+        let detector = StationarityDetector::new(4, 0.01);
+        let mut estimator = GyroBiasEstimator::new(detector.clone(), 6);
+
+        // moving: nothing is accumulated
+        estimator
+            .filter_batch(constant(4, [1.0, 1.0, 1.0]))
+            .unwrap();
+        detector.update(&constant(4, [0.0, 0.0, 9.8]));
+        estimator
+            .filter_batch(constant(4, [0.1, 0.2, 0.3]))
+            .unwrap();
+        assert!(!estimator.is_calibrated());
+        let filtered = estimator
+            .filter_batch(constant(2, [0.1, 0.2, 0.3]))
+            .unwrap();
+        assert_eq!(filtered[0], Sample3D::new(0.0, [0.1, 0.2, 0.3]));
+
+        let bias = estimator.get_bias().unwrap().inner();
+        assert!((bias[0] - 0.1).abs() < 1e-12);
+        assert!((bias[2] - 0.3).abs() < 1e-12);
+        let filtered = estimator
+            .filter_batch(constant(1, [1.1, 0.2, 0.3]))
+            .unwrap();
+        for (v, expected) in filtered[0]
+            .get_measurement()
+            .inner()
+            .iter()
+            .zip([1.0, 0.0, 0.0])
+        {
+            assert!((v - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_motion_restarts_estimation() {
+        let detector = StationarityDetector::new(2, 0.01);
+        let mut estimator = GyroBiasEstimator::new(detector.clone(), 4);
+
+        detector.update(&constant(2, [0.0, 0.0, 9.8]));
+        estimator
+            .filter_batch(constant(3, [5.0, 5.0, 5.0]))
+            .unwrap();
+        detector.update(&[Sample3D::new(0.0, [3.0, 0.0, 9.8])]);
+        estimator
+            .filter_batch(constant(1, [5.0, 5.0, 5.0]))
+            .unwrap();
+        assert!(!estimator.is_calibrated());
+
+        detector.update(&constant(2, [0.0, 0.0, 9.8]));
+        estimator
+            .filter_batch(constant(4, [0.5, 0.5, 0.5]))
+            .unwrap();
+        assert_eq!(estimator.get_bias(), Some(XYZ::new([0.5, 0.5, 0.5])));
+        assert!(estimator.filter_batch(Vec::new()).is_err());
+    }
+}
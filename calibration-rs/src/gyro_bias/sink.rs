@@ -0,0 +1,26 @@
+use publisher::adapters;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::StationarityDetector;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSink, IMUSource};
+use imu_common::types::sensors::SensorType;
+use imu_common::types::timed::Sample3D;
+
+impl<T> IMUSink<T, Sample3D> for StationarityDetector
+where
+    T: Send + Sync + IMUReadings<Sample3D> + 'static,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, Sample3D>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        self.update(samples.get_samples_ref());
+    }
+}
@@ -0,0 +1,31 @@
+//! # Crate calibration-rs
+//!
+//! Sensor calibration and compensation stages. Each stage is a sink that attaches to an
+//! `IMUSource`, corrects the incoming readings, and republishes them as a source.
+//!
+//! Features include:
+//! - Calibration files with the bias, scale, misalignment and temperature coefficients of each
+//!   sensor, applied to sources created from the source registry.
+//! - Temperature compensation of gyroscope/accelerometer bias.
+//! - Gyroscope bias estimation while the device is at rest, detected from accelerometer variance.
+//! - Magnetometer hard and soft-iron calibration by ellipsoid fitting.
+//! - Variance weighted fusion of redundant accelerometers, aligned from static poses.
+//! - Accelerometer bias/scale and gyroscope bias calibration from six static orientations.
+
+pub mod calibration_file;
+pub mod fusion;
+pub mod gyro_bias;
+pub mod magnetometer;
+pub mod six_position;
+pub mod temperature;
+
+pub use calibration_file::{
+    apply_calibration, create_calibrated, CalibrationFile, CalibrationStage, SensorCalibration,
+};
+pub use fusion::{AlignmentEstimator, RedundantFusion, SensorAlignment};
+pub use gyro_bias::{GyroBiasEstimator, StationarityDetector};
+pub use magnetometer::{MagnetometerCalibration, MagnetometerCalibrator, MagnetometerCompensator};
+pub use six_position::{CalibrationEvent, InertialCalibration, Pose, SixPositionCalibration};
+pub use temperature::{
+    PolynomialTemperatureModel, TemperatureCompensator, TemperatureModel, TemperatureTracker,
+};
@@ -0,0 +1,396 @@
+pub(crate) mod sink;
+pub(crate) mod source;
+
+use nalgebra::{Matrix3, SMatrix, SVector, Vector3};
+use std::io::BufRead;
+use std::sync::{Arc, Mutex, RwLock};
+
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUFilter, IMUSample};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::untimed::XYZ;
+use publisher::PublisherManager;
+
+/// Minimum number of readings to fit an ellipsoid.
+pub const MIN_CALIBRATION_SAMPLES: usize = 50;
+
+/// Coefficients of the general quadric `a x² + b y² + c z² + 2f yz + 2g xz + 2h xy + 2p x +
+/// 2q y + 2r z = 1`.
+const N_COEFFICIENTS: usize = 9;
+
+/// Hard and soft-iron correction of a magnetometer: `correction * (reading - offset)`.
+///
+/// The offset is the hard-iron bias, and the correction matrix maps the ellipsoid of the raw
+/// readings to a sphere with the mean radius of the ellipsoid, so corrected readings keep their
+/// units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MagnetometerCalibration {
+    offset: XYZ,
+    correction: Matrix3<f64>,
+}
+
+impl Default for MagnetometerCalibration {
+    fn default() -> Self {
+        Self::new(XYZ::default(), Matrix3::identity())
+    }
+}
+
+impl MagnetometerCalibration {
+    pub fn new(offset: XYZ, correction: Matrix3<f64>) -> Self {
+        Self { offset, correction }
+    }
+
+    pub fn get_offset(&self) -> XYZ {
+        self.offset.clone()
+    }
+
+    pub fn get_correction(&self) -> Matrix3<f64> {
+        self.correction
+    }
+
+    pub fn correct(&self, reading: XYZ) -> XYZ {
+        XYZ::from_vector(self.correction * (reading - self.offset.clone()).0)
+    }
+}
+
+impl IMUFilter<Sample3D> for MagnetometerCalibration {
+    /// Corrects a batch of magnetometer samples.
+    fn filter_batch(&mut self, samples: Vec<Sample3D>) -> Result<Vec<Sample3D>, ImuError> {
+        if samples.is_empty() {
+            return Err(ImuError::EmptyInput);
+        }
+        Ok(samples
+            .into_iter()
+            .map(|s| {
+                Sample3D::from_measurement(
+                    s.get_timestamp_secs(),
+                    self.correct(s.get_measurement()),
+                )
+            })
+            .collect())
+    }
+}
+
+/// Least squares sums of the quadric fit, so readings don't need to be kept.
+#[derive(Clone, Debug)]
+struct QuadricSums {
+    design: SMatrix<f64, N_COEFFICIENTS, N_COEFFICIENTS>,
+    target: SVector<f64, N_COEFFICIENTS>,
+    n_samples: usize,
+}
+
+impl Default for QuadricSums {
+    fn default() -> Self {
+        Self {
+            design: SMatrix::zeros(),
+            target: SVector::zeros(),
+            n_samples: 0,
+        }
+    }
+}
+
+/// Estimates a [`MagnetometerCalibration`] by fitting an ellipsoid to raw magnetometer readings.
+///
+/// Readings are added online, by attaching the calibrator to a magnetometer source, or from
+/// recorded CSV files. They should cover as many orientations as possible, e.g. by drawing a
+/// figure eight with the device. The calibrator is a handle: clones share the same readings.
+///
+/// ## Example
+///
+/// ```rust
+/// use calibration_rs::MagnetometerCalibrator;
+/// use imu_common::types::timed::Sample3D;
+///
+/// let calibrator = MagnetometerCalibrator::new();
+/// let samples: Vec<Sample3D> = (0..200)
+///     .map(|i| {
+///         let (theta, phi) = (i as f64 * 0.1, i as f64 * 0.37);
+///         let reading = [
+///             10.0 + 40.0 * theta.sin() * phi.cos(),
+///             -5.0 + 50.0 * theta.sin() * phi.sin(),
+///             60.0 * theta.cos(),
+///         ];
+///         Sample3D::new(i as f64 * 0.01, reading)
+///     })
+///     .collect();
+/// calibrator.add_samples(&samples);
+///
+/// let calibration = calibrator.fit().unwrap();
+/// assert!((calibration.get_offset().inner()[0] - 10.0).abs() < 1e-6);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MagnetometerCalibrator {
+    sums: Arc<Mutex<QuadricSums>>,
+}
+
+impl MagnetometerCalibrator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of readings added.
+    pub fn len(&self) -> usize {
+        self.sums.lock().unwrap().n_samples
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn reset(&self) {
+        *self.sums.lock().unwrap() = QuadricSums::default();
+    }
+
+    pub fn add_samples(&self, samples: &[Sample3D]) {
+        let mut sums = self.sums.lock().unwrap();
+        for sample in samples {
+            let [x, y, z] = sample.get_measurement().inner();
+            let row = SVector::<f64, N_COEFFICIENTS>::from([
+                x * x,
+                y * y,
+                z * z,
+                2.0 * y * z,
+                2.0 * x * z,
+                2.0 * x * y,
+                2.0 * x,
+                2.0 * y,
+                2.0 * z,
+            ]);
+            sums.design += row * row.transpose();
+            sums.target += row;
+            sums.n_samples += 1;
+        }
+    }
+
+    /// Adds the magnetometer readings of a CSV recording, and returns how many were added.
+    ///
+    /// Both layouts written by the recorder are read: nine axis rows, with the magnetometer in
+    /// the last 3 columns, and tagged rows `tag,sensor,timestamp,x,y,z`, of which only
+    /// magnetometer rows are used. Header and comment lines are skipped.
+    /// Returns an error if the file can't be read or a row is malformed.
+    pub fn add_csv<R: BufRead>(&self, reader: R) -> Result<usize, ImuError> {
+        let mut samples = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| ImuError::Io(e.to_string()))?;
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let (timestamp, values) = match fields.as_slice() {
+                [] | [""] => continue,
+                [first, ..] if first.starts_with('#') => continue,
+                [timestamp, .., x, y, z]
+                    if fields.len() == 10 && timestamp.parse::<f64>().is_ok() =>
+                {
+                    (*timestamp, [*x, *y, *z])
+                }
+                [_, sensor, timestamp, x, y, z] if sensor.starts_with("magnetometer") => {
+                    (*timestamp, [*x, *y, *z])
+                }
+                // header or another sensor
+                _ => continue,
+            };
+            let parse = |field: &str| {
+                field
+                    .parse::<f64>()
+                    .map_err(|e| ImuError::InvalidInput(format!("Line {}: {}", index + 1, e)))
+            };
+            samples.push(Sample3D::new(
+                parse(timestamp)?,
+                [parse(values[0])?, parse(values[1])?, parse(values[2])?],
+            ));
+        }
+        self.add_samples(&samples);
+        Ok(samples.len())
+    }
+
+    /// Fits an ellipsoid to the readings added.
+    /// Returns an error if fewer than `MIN_CALIBRATION_SAMPLES` readings were added, or if they
+    /// don't cover enough orientations to fit an ellipsoid.
+    pub fn fit(&self) -> Result<MagnetometerCalibration, ImuError> {
+        let sums = self.sums.lock().unwrap().clone();
+        if sums.n_samples < MIN_CALIBRATION_SAMPLES {
+            return Err(ImuError::InvalidInput(format!(
+                "{} readings, at least {} needed",
+                sums.n_samples, MIN_CALIBRATION_SAMPLES
+            )));
+        }
+        let degenerate =
+            || ImuError::InvalidInput("Readings don't cover enough orientations".to_string());
+        let v = sums
+            .design
+            .cholesky()
+            .ok_or_else(degenerate)?
+            .solve(&sums.target);
+
+        let quadric = Matrix3::new(v[0], v[5], v[4], v[5], v[1], v[3], v[4], v[3], v[2]);
+        let linear = Vector3::new(v[6], v[7], v[8]);
+        let center = -quadric.try_inverse().ok_or_else(degenerate)? * linear;
+        // (x - center)ᵀ shape (x - center) = 1
+        let shape = quadric / (1.0 + center.dot(&(quadric * center)));
+
+        let eigen = shape.symmetric_eigen();
+        if !eigen.eigenvalues.iter().all(|v| *v > 0.0) {
+            return Err(degenerate());
+        }
+        let radius = eigen
+            .eigenvalues
+            .iter()
+            .map(|v| v.powf(-1.0 / 6.0))
+            .product::<f64>();
+        let sqrt_shape = eigen.eigenvectors
+            * Matrix3::from_diagonal(&eigen.eigenvalues.map(f64::sqrt))
+            * eigen.eigenvectors.transpose();
+        Ok(MagnetometerCalibration::new(
+            XYZ::from_vector(center),
+            sqrt_shape * radius,
+        ))
+    }
+}
+
+/// Applies a [`MagnetometerCalibration`] to the magnetometer readings of a cluster, before they
+/// reach an `AHRSFilter`.
+///
+/// Readings of the other sensors are republished unchanged, so the compensator can be inserted
+/// between a resampler and an AHRS.
+#[derive(Clone)]
+pub struct MagnetometerCompensator {
+    calibration: Arc<RwLock<MagnetometerCalibration>>,
+    tag: String,
+    publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+}
+
+impl MagnetometerCompensator {
+    pub fn new(
+        tag: &str,
+        sensor_cluster: Vec<SensorType>,
+        calibration: MagnetometerCalibration,
+    ) -> Self {
+        Self {
+            calibration: Arc::new(RwLock::new(calibration)),
+            tag: tag.to_string(),
+            publishers: PublisherManager::new(&sensor_cluster),
+        }
+    }
+
+    pub fn get_calibration(&self) -> MagnetometerCalibration {
+        self.calibration.read().unwrap().clone()
+    }
+
+    /// Replaces the calibration, e.g. with a new fit once enough readings were collected.
+    pub fn set_calibration(&self, calibration: MagnetometerCalibration) {
+        *self.calibration.write().unwrap() = calibration;
+    }
+
+    fn compensate(&self, sensor_type: &SensorType, samples: Vec<Sample3D>) -> Vec<Sample3D> {
+        if !matches!(sensor_type, SensorType::Magnetometer(_)) || samples.is_empty() {
+            return samples;
+        }
+        let mut calibration = self.get_calibration();
+        calibration.filter_batch(samples).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    const OFFSET: [f64; 3] = [12.0, -30.0, 5.0];
+    const FIELD_STRENGTH: f64 = 48.0;
+
+    fn soft_iron() -> Matrix3<f64> {
+        Matrix3::new(1.2, 0.1, 0.0, 0.1, 0.9, 0.05, 0.0, 0.05, 1.05)
+    }
+
+    /// Readings of a field of `FIELD_STRENGTH` in many orientations, distorted by `soft_iron`
+    /// and shifted by `OFFSET`.
+    fn distorted_readings(n_samples: usize) -> Vec<Sample3D> {
+        (0..n_samples)
+            .map(|i| {
+                let (theta, phi) = (i as f64 * 0.071, i as f64 * 0.53);
+                let field = Vector3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                ) * FIELD_STRENGTH;
+                let reading = soft_iron() * field + Vector3::from(OFFSET);
+                Sample3D::new(i as f64 * 0.01, [reading.x, reading.y, reading.z])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fit() {
+        let calibrator = MagnetometerCalibrator::new();
+        calibrator.add_samples(&distorted_readings(500));
+        let calibration = calibrator.fit().unwrap();
+
+        for (v, expected) in calibration.get_offset().inner().iter().zip(OFFSET) {
+            assert!((v - expected).abs() < 1e-6);
+        }
+        // corrected readings lie on a sphere
+        let norms: Vec<f64> = distorted_readings(50)
+            .into_iter()
+            .map(|s| calibration.correct(s.get_measurement()).0.norm())
+            .collect();
+        for norm in norms.iter() {
+            assert!((norm - norms[0]).abs() < 1e-6);
+        }
+        let mean_radius = soft_iron().determinant().cbrt() * FIELD_STRENGTH;
+        assert!((norms[0] - mean_radius).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fit_errors() {
+        let calibrator = MagnetometerCalibrator::new();
+        calibrator.add_samples(&distorted_readings(10));
+        assert!(calibrator.fit().is_err());
+
+        // readings turning around a single axis
+        calibrator.reset();
+        let flat: Vec<Sample3D> = (0..100)
+            .map(|i| {
+                let angle = i as f64 * 0.1;
+                Sample3D::new(0.0, [40.0 * angle.cos(), 40.0 * angle.sin(), 0.0])
+            })
+            .collect();
+        calibrator.add_samples(&flat);
+        assert!(calibrator.fit().is_err());
+    }
+
+    #[test]
+    fn test_add_csv() {
+        let mag = SensorType::Magnetometer(Uuid::new_v4());
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let csv = format!(
+            "Time (ms),Gyro X (rad/s),Gyro Y (rad/s),Gyro Z (rad/s),Accel X (m/s²),Accel Y (m/s²),Accel Z (m/s²),Mag X (µT),Mag Y (µT),Mag Z (µT)\n\
+             0,0,0,0,0,0,9.8,20,0,-40\n\
+             tag,sensor,timestamp,values\n\
+             phone,{mag},1.0,21,1,-39\n\
+             phone,{acc},1.0,0,0,9.8\n"
+        );
+        let calibrator = MagnetometerCalibrator::new();
+
+        assert_eq!(calibrator.add_csv(csv.as_bytes()).unwrap(), 2);
+        assert_eq!(calibrator.len(), 2);
+        assert!(calibrator
+            .add_csv("0,0,0,0,0,0,0,x,0,0\n".as_bytes())
+            .is_err());
+    }
+
+    #[test]
+    fn test_compensate() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_magnetometer_compensate");
+        let calibration =
+            MagnetometerCalibration::new(XYZ::new([1.0, 2.0, 3.0]), Matrix3::identity() * 2.0);
+        let compensator = MagnetometerCompensator::new("test", sensor_cluster.clone(), calibration);
+        let samples = vec![Sample3D::new(0.0, [1.0, 3.0, 5.0])];
+
+        let compensated = compensator.compensate(&sensor_cluster[2], samples.clone());
+        assert_eq!(compensated, vec![Sample3D::new(0.0, [0.0, 2.0, 4.0])]);
+        // other sensors are forwarded
+        assert_eq!(
+            compensator.compensate(&sensor_cluster[0], samples.clone()),
+            samples
+        );
+    }
+}
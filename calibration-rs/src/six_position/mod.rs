@@ -0,0 +1,573 @@
+pub(crate) mod sink;
+pub(crate) mod source;
+
+use nalgebra::Vector3;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::calibration_file::{CalibrationFile, SensorCalibration};
+use crate::gyro_bias::StationarityDetector;
+use imu_common::errors::ImuError;
+use imu_common::traits::IMUSample;
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::untimed::XYZ;
+use publisher::PublisherManager;
+
+/// Number of static orientations of the routine, each axis pointing up and down.
+pub const N_POSES: usize = 6;
+/// Gravity measured by an ideal accelerometer at rest, in m/s².
+pub const STANDARD_GRAVITY: f64 = 9.80665;
+
+const DEFAULT_WINDOW_SIZE: usize = 50;
+const DEFAULT_VARIANCE_THRESHOLD: f64 = 0.02;
+const DEFAULT_MIN_SAMPLES: usize = 200;
+/// Minimum ratio between the gravity along the vertical axis and the norm of the gravity, i.e.
+/// the cosine of the maximum tilt of a pose (~25°).
+const MIN_POSE_ALIGNMENT: f64 = 0.9;
+
+type EventCallback = Option<Arc<dyn Fn(CalibrationEvent) + Send + Sync>>;
+
+/// Static orientation of the device, named after the axis pointing up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Pose {
+    XUp,
+    XDown,
+    YUp,
+    YDown,
+    ZUp,
+    ZDown,
+}
+
+impl Pose {
+    pub const ALL: [Pose; N_POSES] = [
+        Pose::XUp,
+        Pose::XDown,
+        Pose::YUp,
+        Pose::YDown,
+        Pose::ZUp,
+        Pose::ZDown,
+    ];
+
+    /// Returns the pose of an accelerometer at rest reading `gravity`, or `None` if no axis is
+    /// close enough to the vertical.
+    pub fn from_gravity(gravity: &XYZ) -> Option<Self> {
+        let gravity = gravity.0;
+        let axis = gravity.iamax();
+        if gravity[axis].abs() < MIN_POSE_ALIGNMENT * gravity.norm() {
+            return None;
+        }
+        let index = 2 * axis + usize::from(gravity[axis] < 0.0);
+        Some(Self::ALL[index])
+    }
+
+    fn index(&self) -> usize {
+        Self::ALL.iter().position(|pose| pose == self).unwrap()
+    }
+}
+
+/// Event emitted while running the calibration routine.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CalibrationEvent {
+    /// The device was held still long enough in a new pose.
+    PoseCaptured(Pose),
+    /// Every pose was captured and the calibration is applied to the next readings.
+    Calibrated(Box<InertialCalibration>),
+    /// The calibration couldn't be estimated or saved. The poses are captured again.
+    Failed(ImuError),
+}
+
+/// Accelerometer bias and scale and gyroscope bias, per axis, as the calibrations of both
+/// sensors.
+///
+/// Readings are corrected as `(accel - accel_bias) / accel_scale` and `gyro - gyro_bias`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InertialCalibration {
+    accel: SensorCalibration,
+    gyro: SensorCalibration,
+}
+
+impl InertialCalibration {
+    pub fn new(accel_bias: XYZ, accel_scale: XYZ, gyro_bias: XYZ) -> Self {
+        Self {
+            accel: SensorCalibration::new(accel_bias, accel_scale),
+            gyro: SensorCalibration::new(gyro_bias, XYZ::new([1.0; 3])),
+        }
+    }
+
+    /// Estimates the calibration from the mean accelerometer readings in each pose, ordered as
+    /// `Pose::ALL`, and the mean gyroscope reading at rest.
+    ///
+    /// Returns an error if a scale isn't positive, e.g. if the poses were mixed up.
+    pub fn from_poses(poses: &[XYZ; N_POSES], gyro_bias: XYZ) -> Result<Self, ImuError> {
+        let mut accel_bias = [0.0; 3];
+        let mut accel_scale = [0.0; 3];
+        for axis in 0..3 {
+            let up = poses[2 * axis].0[axis];
+            let down = poses[2 * axis + 1].0[axis];
+            accel_bias[axis] = (up + down) / 2.0;
+            accel_scale[axis] = (up - down) / (2.0 * STANDARD_GRAVITY);
+            if accel_scale[axis] <= 0.0 {
+                return Err(ImuError::InvalidInput(format!(
+                    "Invalid scale {} of axis {}",
+                    accel_scale[axis], axis
+                )));
+            }
+        }
+        Ok(Self::new(
+            XYZ::new(accel_bias),
+            XYZ::new(accel_scale),
+            gyro_bias,
+        ))
+    }
+
+    /// Returns the calibrations of `accelerometer` and `gyroscope` in `file`, if both are there.
+    pub fn from_file(
+        file: &CalibrationFile,
+        accelerometer: &SensorType,
+        gyroscope: &SensorType,
+    ) -> Option<Self> {
+        Some(Self {
+            accel: file.get(accelerometer)?.clone(),
+            gyro: file.get(gyroscope)?.clone(),
+        })
+    }
+
+    /// Sets the calibrations of `accelerometer` and `gyroscope` in `file`.
+    pub fn to_file(
+        &self,
+        file: &mut CalibrationFile,
+        accelerometer: &SensorType,
+        gyroscope: &SensorType,
+    ) {
+        file.insert(accelerometer, self.accel.clone());
+        file.insert(gyroscope, self.gyro.clone());
+    }
+
+    pub fn get_accel_bias(&self) -> XYZ {
+        self.accel.get_bias()
+    }
+
+    pub fn get_accel_scale(&self) -> XYZ {
+        self.accel.get_scale()
+    }
+
+    pub fn get_gyro_bias(&self) -> XYZ {
+        self.gyro.get_bias()
+    }
+
+    pub fn correct_accel(&self, reading: XYZ) -> XYZ {
+        self.accel.correct(reading, None)
+    }
+
+    pub fn correct_gyro(&self, reading: XYZ) -> XYZ {
+        self.gyro.correct(reading, None)
+    }
+}
+
+#[derive(Default)]
+struct RoutineState {
+    calibration: Option<InertialCalibration>,
+    poses: [Option<XYZ>; N_POSES],
+    /// Sum and number of accelerometer readings since the device is at rest.
+    interval: (Vector3<f64>, usize),
+    /// Sum and number of gyroscope readings while the device is at rest.
+    gyro: (Vector3<f64>, usize),
+}
+
+impl RoutineState {
+    fn restart(&mut self) {
+        self.poses = Default::default();
+        self.interval = (Vector3::zeros(), 0);
+        self.gyro = (Vector3::zeros(), 0);
+    }
+}
+
+/// Guided calibration of an accelerometer and a gyroscope from six static orientations.
+///
+/// The device is held still with each axis pointing up and then down, in any order. A pose is
+/// captured once the accelerometer is at rest for `min_samples` readings, and the gyroscope
+/// readings at rest are averaged into its bias. After the six poses, the accelerometer bias and
+/// scale are estimated from the gravity measured along each axis, and the calibration is written
+/// to the calibration file, if any, and applied to the readings published afterwards.
+///
+/// Readings are published uncorrected while the routine runs. When the calibration file exists,
+/// the calibration is loaded from it and the routine is skipped, so later runs start calibrated.
+/// Readings of other sensors are forwarded unchanged.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// use calibration_rs::{CalibrationEvent, SixPositionCalibration};
+/// use imu_common::types::sensors::SensorClusterBuilder;
+///
+/// let sensor_cluster = SensorClusterBuilder::new().six_axis().build().unwrap();
+/// let mut calibration = SixPositionCalibration::new(
+///     "calibrated",
+///     sensor_cluster.clone(),
+///     sensor_cluster[0].clone(),
+///     sensor_cluster[1].clone(),
+/// )
+/// .unwrap()
+/// .with_file("imu_calibration.json");
+/// calibration.register_callback(|event| {
+///     if let CalibrationEvent::PoseCaptured(pose) = event {
+///         println!("Captured {:?}", pose);
+///     }
+/// });
+/// println!("Hold the device still in {:?}", calibration.get_missing_poses());
+/// ```
+#[derive(Clone)]
+pub struct SixPositionCalibration {
+    tag: String,
+    accelerometer: SensorType,
+    gyroscope: SensorType,
+    min_samples: usize,
+    file: Option<PathBuf>,
+    detector: StationarityDetector,
+    state: Arc<Mutex<RoutineState>>,
+    callback: EventCallback,
+    publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+}
+
+impl SixPositionCalibration {
+    /// Returns a node calibrating `accelerometer` and `gyroscope`, publishing `sensor_cluster`.
+    /// Returns an error if either sensor isn't in `sensor_cluster`.
+    pub fn new(
+        tag: &str,
+        sensor_cluster: Vec<SensorType>,
+        accelerometer: SensorType,
+        gyroscope: SensorType,
+    ) -> Result<Self, ImuError> {
+        for sensor_type in [&accelerometer, &gyroscope] {
+            if !sensor_cluster.contains(sensor_type) {
+                return Err(ImuError::InvalidInput(format!(
+                    "Sensor {} not in sensor cluster",
+                    sensor_type
+                )));
+            }
+        }
+        Ok(Self {
+            tag: tag.to_string(),
+            accelerometer,
+            gyroscope,
+            min_samples: DEFAULT_MIN_SAMPLES,
+            file: None,
+            detector: StationarityDetector::new(DEFAULT_WINDOW_SIZE, DEFAULT_VARIANCE_THRESHOLD),
+            state: Arc::new(Mutex::new(RoutineState::default())),
+            callback: None,
+            publishers: PublisherManager::new(&sensor_cluster),
+        })
+    }
+
+    /// Sets a known calibration, skipping the routine.
+    pub fn with_calibration(self, calibration: InertialCalibration) -> Self {
+        self.state.lock().unwrap().calibration = Some(calibration);
+        self
+    }
+
+    /// Loads the calibration from the calibration file at `path` if it has both sensors, and
+    /// otherwise adds them to the file once estimated. Other sensors of the file are kept.
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let calibration = CalibrationFile::load(&path).ok().and_then(|file| {
+            InertialCalibration::from_file(&file, &self.accelerometer, &self.gyroscope)
+        });
+        if calibration.is_some() {
+            self.state.lock().unwrap().calibration = calibration;
+        }
+        self.file = Some(path);
+        self
+    }
+
+    /// Detects rest over windows of `window_size` accelerometer readings, at least 2, where the
+    /// sum of the variances of the 3 axes, in (m/s²)², is below `variance_threshold`.
+    pub fn with_stationarity(mut self, window_size: usize, variance_threshold: f64) -> Self {
+        self.detector = StationarityDetector::new(window_size, variance_threshold);
+        self
+    }
+
+    /// Sets the number of readings at rest, at least 1, after which a pose is captured.
+    pub fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples.max(1);
+        self
+    }
+
+    /// Calls `callback` on every event of the routine.
+    pub fn register_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(CalibrationEvent) + Send + Sync + 'static,
+    {
+        self.callback = Some(Arc::new(callback));
+    }
+
+    pub fn get_calibration(&self) -> Option<InertialCalibration> {
+        self.state.lock().unwrap().calibration.clone()
+    }
+
+    pub fn is_calibrated(&self) -> bool {
+        self.get_calibration().is_some()
+    }
+
+    /// Returns the poses still to be captured.
+    pub fn get_missing_poses(&self) -> Vec<Pose> {
+        let state = self.state.lock().unwrap();
+        Pose::ALL
+            .into_iter()
+            .filter(|pose| state.poses[pose.index()].is_none())
+            .collect()
+    }
+
+    /// Drops the calibration and starts the routine again. The calibration file isn't removed.
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.calibration = None;
+        state.restart();
+        self.detector.reset();
+    }
+
+    /// Returns the accelerometer readings to publish, capturing poses while not calibrated.
+    fn process_accel(&self, samples: Vec<Sample3D>) -> Vec<Sample3D> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(calibration) = state.calibration.as_ref() {
+            return correct(samples, |reading| calibration.correct_accel(reading));
+        }
+        let mut events = Vec::new();
+        for sample in samples.iter() {
+            self.detector.update(std::slice::from_ref(sample));
+            if !self.detector.is_stationary() {
+                state.interval = (Vector3::zeros(), 0);
+                continue;
+            }
+            state.interval.0 += sample.get_measurement().0;
+            state.interval.1 += 1;
+            if state.interval.1 != self.min_samples {
+                continue;
+            }
+            let mean = XYZ::from_vector(state.interval.0 / state.interval.1 as f64);
+            if let Some(pose) = Pose::from_gravity(&mean) {
+                if state.poses[pose.index()].is_none() {
+                    state.poses[pose.index()] = Some(mean);
+                    events.push(CalibrationEvent::PoseCaptured(pose));
+                }
+            }
+        }
+        events.extend(self.try_calibrate(&mut state));
+        drop(state);
+        self.emit(events);
+        samples
+    }
+
+    /// Returns the gyroscope readings to publish, averaging the readings at rest while not
+    /// calibrated.
+    fn process_gyro(&self, samples: Vec<Sample3D>) -> Vec<Sample3D> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(calibration) = state.calibration.as_ref() {
+            return correct(samples, |reading| calibration.correct_gyro(reading));
+        }
+        if self.detector.is_stationary() {
+            for sample in samples.iter() {
+                state.gyro.0 += sample.get_measurement().0;
+                state.gyro.1 += 1;
+            }
+        }
+        let events: Vec<_> = self.try_calibrate(&mut state).into_iter().collect();
+        drop(state);
+        self.emit(events);
+        samples
+    }
+
+    /// Estimates and saves the calibration once every pose is captured.
+    fn try_calibrate(&self, state: &mut RoutineState) -> Option<CalibrationEvent> {
+        if state.poses.iter().any(Option::is_none) || state.gyro.1 < self.min_samples {
+            return None;
+        }
+        let poses = state.poses.clone().map(Option::unwrap);
+        let gyro_bias = XYZ::from_vector(state.gyro.0 / state.gyro.1 as f64);
+        let result = InertialCalibration::from_poses(&poses, gyro_bias).and_then(|calibration| {
+            if let Some(path) = self.file.as_ref() {
+                let mut file = CalibrationFile::load(path).unwrap_or_default();
+                calibration.to_file(&mut file, &self.accelerometer, &self.gyroscope);
+                file.save(path)?;
+            }
+            Ok(calibration)
+        });
+        match result {
+            Ok(calibration) => {
+                state.calibration = Some(calibration.clone());
+                Some(CalibrationEvent::Calibrated(Box::new(calibration)))
+            }
+            Err(e) => {
+                state.restart();
+                Some(CalibrationEvent::Failed(e))
+            }
+        }
+    }
+
+    fn emit(&self, events: Vec<CalibrationEvent>) {
+        if let Some(cb) = self.callback.as_ref() {
+            for event in events {
+                cb(event);
+            }
+        }
+    }
+}
+
+fn correct(samples: Vec<Sample3D>, correction: impl Fn(XYZ) -> XYZ) -> Vec<Sample3D> {
+    samples
+        .into_iter()
+        .map(|s| {
+            Sample3D::from_measurement(s.get_timestamp_secs(), correction(s.get_measurement()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::types::sensors::SensorClusterBuilder;
+
+    const ACCEL_BIAS: [f64; 3] = [0.2, -0.1, 0.3];
+    const ACCEL_SCALE: [f64; 3] = [1.02, 0.98, 1.01];
+    const GYRO_BIAS: [f64; 3] = [0.01, -0.02, 0.005];
+
+    /// Accelerometer reading at rest in `pose`, with the bias and scale above.
+    fn reading(pose: Pose) -> [f64; 3] {
+        let mut gravity = [0.0; 3];
+        let index = pose.index();
+        gravity[index / 2] = if index.is_multiple_of(2) {
+            STANDARD_GRAVITY
+        } else {
+            -STANDARD_GRAVITY
+        };
+        [0, 1, 2].map(|i| gravity[i] * ACCEL_SCALE[i] + ACCEL_BIAS[i])
+    }
+
+    fn constant(start: usize, n_samples: usize, measurement: [f64; 3]) -> Vec<Sample3D> {
+        (start..start + n_samples)
+            .map(|i| Sample3D::new(i as f64 * 0.01, measurement))
+            .collect()
+    }
+
+    fn assert_xyz_eq(xyz: XYZ, expected: [f64; 3]) {
+        for (v, expected) in xyz.inner().iter().zip(expected) {
+            assert!((v - expected).abs() < 1e-9, "{:?} != {:?}", xyz, expected);
+        }
+    }
+
+    fn routine() -> SixPositionCalibration {
+        let sensor_cluster = SensorClusterBuilder::new().six_axis().build().unwrap();
+        SixPositionCalibration::new(
+            "test",
+            sensor_cluster.clone(),
+            sensor_cluster[0].clone(),
+            sensor_cluster[1].clone(),
+        )
+        .unwrap()
+        .with_stationarity(4, 0.01)
+        .with_min_samples(10)
+    }
+
+    #[test]
+    fn test_pose_from_gravity() {
+        assert_eq!(
+            Pose::from_gravity(&XYZ::new([0.1, -0.2, 9.8])),
+            Some(Pose::ZUp)
+        );
+        assert_eq!(
+            Pose::from_gravity(&XYZ::new([0.0, -9.8, 0.3])),
+            Some(Pose::YDown)
+        );
+        // tilted 45°
+        assert_eq!(Pose::from_gravity(&XYZ::new([6.9, 0.0, 6.9])), None);
+    }
+
+    #[test]
+    fn test_from_poses() {
+        let poses = Pose::ALL.map(|pose| XYZ::new(reading(pose)));
+        let calibration = InertialCalibration::from_poses(&poses, XYZ::new(GYRO_BIAS)).unwrap();
+        assert_xyz_eq(calibration.get_accel_bias(), ACCEL_BIAS);
+        assert_xyz_eq(calibration.get_accel_scale(), ACCEL_SCALE);
+        assert_xyz_eq(
+            calibration.correct_accel(XYZ::new(reading(Pose::ZDown))),
+            [0.0, 0.0, -STANDARD_GRAVITY],
+        );
+        assert_xyz_eq(calibration.correct_gyro(XYZ::new(GYRO_BIAS)), [0.0; 3]);
+
+        // up and down swapped
+        let mut poses = poses;
+        poses.swap(0, 1);
+        assert!(InertialCalibration::from_poses(&poses, XYZ::new(GYRO_BIAS)).is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("six_position_{}.json", uuid::Uuid::new_v4()));
+        let calibration = InertialCalibration::new(
+            XYZ::new(ACCEL_BIAS),
+            XYZ::new(ACCEL_SCALE),
+            XYZ::new(GYRO_BIAS),
+        );
+        let node = routine();
+        let mut file = CalibrationFile::new();
+        calibration.to_file(&mut file, &node.accelerometer, &node.gyroscope);
+        file.save(&path).unwrap();
+
+        // later runs start calibrated
+        let node = node.with_file(&path);
+        assert_eq!(node.get_calibration(), Some(calibration));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_routine() {
+        let path = std::env::temp_dir().join(format!("six_position_{}.json", uuid::Uuid::new_v4()));
+        let mut node = routine().with_file(&path);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        node.register_callback({
+            let events = events.clone();
+            move |event| events.lock().unwrap().push(event)
+        });
+
+        let mut start = 0;
+        for pose in [
+            Pose::ZUp,
+            Pose::XUp,
+            Pose::XDown,
+            Pose::YUp,
+            Pose::YDown,
+            Pose::ZDown,
+        ] {
+            assert!(!node.is_calibrated());
+            // moving to the next pose
+            node.process_accel(vec![Sample3D::new(start as f64 * 0.01, [5.0, 5.0, 5.0])]);
+            let accel = constant(start, 20, reading(pose));
+            assert_eq!(node.process_accel(accel.clone()), accel);
+            node.process_gyro(constant(start, 20, GYRO_BIAS));
+            start += 21;
+        }
+        assert!(node.get_missing_poses().is_empty());
+
+        let events = events.lock().unwrap();
+        assert_eq!(events[0], CalibrationEvent::PoseCaptured(Pose::ZUp));
+        let Some(CalibrationEvent::Calibrated(calibration)) = events.last() else {
+            panic!("Not calibrated: {:?}", events);
+        };
+        assert_xyz_eq(calibration.get_accel_scale(), ACCEL_SCALE);
+        assert_xyz_eq(calibration.get_gyro_bias(), GYRO_BIAS);
+        let file = CalibrationFile::load(&path).unwrap();
+        let saved =
+            InertialCalibration::from_file(&file, &node.accelerometer, &node.gyroscope).unwrap();
+        assert_eq!(saved, **calibration);
+        std::fs::remove_file(&path).unwrap();
+
+        let corrected = node.process_accel(constant(start, 1, reading(Pose::ZUp)));
+        assert_xyz_eq(corrected[0].get_measurement(), [0.0, 0.0, STANDARD_GRAVITY]);
+        let corrected = node.process_gyro(constant(start, 1, GYRO_BIAS));
+        assert_xyz_eq(corrected[0].get_measurement(), [0.0; 3]);
+
+        node.reset();
+        assert!(!node.is_calibrated());
+        assert_eq!(node.get_missing_poses().len(), N_POSES);
+    }
+}
@@ -0,0 +1,163 @@
+pub(crate) mod sink;
+pub(crate) mod source;
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use imu_common::traits::IMUSample;
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::untimed::XYZ;
+use publisher::PublisherManager;
+
+/// Name used for temperature streams published as `SensorType::Other`.
+pub const TEMPERATURE_SENSOR_NAME: &str = "Temperature";
+
+/// Compensation hook returning the bias of a sensor at a given temperature.
+///
+/// The bias is subtracted from every sample of `sensor_type`.
+pub trait TemperatureModel: Send + Sync {
+    fn bias(&self, sensor_type: &SensorType, temperature: f64) -> XYZ;
+}
+
+/// Polynomial bias model per sensor: `bias(t) = c0 + c1 * (t - t_ref) + c2 * (t - t_ref)^2 + ...`
+#[derive(Clone, Debug, Default)]
+pub struct PolynomialTemperatureModel {
+    reference_temperature: f64,
+    coefficients: HashMap<SensorType, Vec<XYZ>>,
+}
+
+impl PolynomialTemperatureModel {
+    pub fn new(reference_temperature: f64) -> Self {
+        Self {
+            reference_temperature,
+            coefficients: HashMap::new(),
+        }
+    }
+
+    /// Sets the bias coefficients of `sensor_type`, lowest order first.
+    pub fn with_coefficients(mut self, sensor_type: &SensorType, coefficients: Vec<XYZ>) -> Self {
+        self.coefficients.insert(sensor_type.clone(), coefficients);
+        self
+    }
+}
+
+impl TemperatureModel for PolynomialTemperatureModel {
+    fn bias(&self, sensor_type: &SensorType, temperature: f64) -> XYZ {
+        let delta = temperature - self.reference_temperature;
+        match self.coefficients.get(sensor_type) {
+            Some(coefficients) => coefficients
+                .iter()
+                .rev()
+                .fold(XYZ::default(), |acc, c| acc * delta + c.clone()),
+            None => XYZ::default(),
+        }
+    }
+}
+
+/// Last known temperature, shared between the compensator and its temperature sink.
+#[derive(Clone, Debug, Default)]
+pub struct TemperatureTracker(Arc<RwLock<Option<f64>>>);
+
+impl TemperatureTracker {
+    pub fn get(&self) -> Option<f64> {
+        *self.0.read().unwrap()
+    }
+
+    pub fn set(&self, temperature: f64) {
+        *self.0.write().unwrap() = Some(temperature);
+    }
+}
+
+/// Removes the temperature dependent bias from 3D readings.
+///
+/// Temperature is fed by attaching `temperature_sink()` to a `SampleScalar` source, or
+/// manually with `set_temperature`. Readings are forwarded unchanged until a temperature is known.
+/// Compensated readings are republished with the same sensor type.
+#[derive(Clone)]
+pub struct TemperatureCompensator {
+    model: Arc<dyn TemperatureModel>,
+    temperature: TemperatureTracker,
+    tag: String,
+    publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+}
+
+impl TemperatureCompensator {
+    pub fn new<M>(tag: &str, sensor_cluster: Vec<SensorType>, model: M) -> Self
+    where
+        M: TemperatureModel + 'static,
+    {
+        Self {
+            model: Arc::new(model),
+            temperature: TemperatureTracker::default(),
+            tag: tag.to_string(),
+            publishers: PublisherManager::new(&sensor_cluster),
+        }
+    }
+
+    /// Returns the sink that updates the temperature used by this compensator.
+    pub fn temperature_sink(&self) -> TemperatureTracker {
+        self.temperature.clone()
+    }
+
+    pub fn set_temperature(&self, temperature: f64) {
+        self.temperature.set(temperature);
+    }
+
+    fn compensate(&self, sensor_type: &SensorType, samples: Vec<Sample3D>) -> Vec<Sample3D> {
+        let Some(temperature) = self.temperature.get() else {
+            return samples;
+        };
+        let bias = self.model.bias(sensor_type, temperature);
+        samples
+            .into_iter()
+            .map(|s| {
+                Sample3D::from_measurement(
+                    s.get_timestamp_secs(),
+                    s.get_measurement() - bias.clone(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_polynomial_model() {
+        let gyro = SensorType::Gyroscope(Uuid::new_v4());
+        let model = PolynomialTemperatureModel::new(25.0).with_coefficients(
+            &gyro,
+            vec![XYZ::new([0.1, 0.0, 0.0]), XYZ::new([0.01, 0.02, 0.0])],
+        );
+
+        assert_eq!(model.bias(&gyro, 25.0), XYZ::new([0.1, 0.0, 0.0]));
+        let bias = model.bias(&gyro, 35.0).inner();
+        assert!((bias[0] - 0.2).abs() < 1e-12);
+        assert!((bias[1] - 0.2).abs() < 1e-12);
+        assert_eq!(
+            model.bias(&SensorType::Accelerometer(Uuid::new_v4()), 35.0),
+            XYZ::default()
+        );
+    }
+
+    #[test]
+    fn test_compensate() {
+        let gyro = SensorType::Gyroscope(Uuid::new_v4());
+        let model = PolynomialTemperatureModel::new(20.0)
+            .with_coefficients(&gyro, vec![XYZ::default(), XYZ::new([0.1, 0.1, 0.1])]);
+        let compensator = TemperatureCompensator::new("test", vec![gyro.clone()], model);
+        let samples = vec![Sample3D::new(0.0, [1.0, 1.0, 1.0])];
+
+        assert_eq!(compensator.compensate(&gyro, samples.clone()), samples);
+
+        compensator.set_temperature(30.0);
+        let compensated = compensator.compensate(&gyro, samples);
+        for v in compensated[0].get_measurement().inner() {
+            assert!(v.abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use publisher::adapters;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::{TemperatureCompensator, TemperatureTracker};
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::{Sample3D, SampleScalar};
+
+impl<T> IMUSink<T, Sample3D> for TemperatureCompensator
+where
+    T: Send + Sync + IMUReadings<Sample3D> + 'static,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, Sample3D>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        let sensor_type = samples.get_sensor_type();
+        let compensated = self.compensate(&sensor_type, samples.get_samples());
+        let readings = SensorReadings::from_vec(&self.tag, sensor_type.clone(), compensated);
+        self.publishers
+            .notify_listeners(sensor_type, Arc::new(readings));
+    }
+}
+
+impl<T> IMUSink<T, SampleScalar> for TemperatureTracker
+where
+    T: Send + Sync + IMUReadings<SampleScalar> + 'static,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, SampleScalar>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    /// Only the last known temperature is tracked, so of batched readings, the samples before
+    /// the last one are superseded by it.
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        if let Some(sample) = samples.get_samples_ref().last() {
+            self.set(sample.get_measurement().inner());
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! Module errors
+
+/// Errors of the CAN source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CanError {
+    /// Error opening or reading the CAN interface.
+    Bus(String),
+
+    /// Error indicating that a sensor is already published by another source.
+    DuplicatedSensor(String),
+
+    /// Error indicating that a signal of the mapping can't be decoded.
+    InvalidMapping(String),
+}
+
+impl std::fmt::Display for CanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CanError::Bus(e) => write!(f, "Bus error: {}", e),
+            CanError::DuplicatedSensor(e) => write!(f, "Duplicated sensor: {}", e),
+            CanError::InvalidMapping(e) => write!(f, "Invalid mapping: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CanError {}
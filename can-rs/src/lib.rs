@@ -0,0 +1,24 @@
+//! # Crate can-rs
+//!
+//! ## can-rs
+//!
+//! The `can-rs` crate publishes the readings of IMUs on a CAN bus, common in vehicle and robot
+//! instrumentation, so they can feed the resampler like any other source.
+//!
+//! [`CanImuSource`] decodes frames with a [`CanMapping`] of CAN ids to the axes of its sensors,
+//! described like the signals of a DBC file. Frames are read from:
+//! - A SocketCAN interface with [`run_service`], on Linux with the `socketcan` feature, enabled
+//!   by default.
+//! - Any other interface or recording implementing [`CanBus`].
+
+pub mod errors;
+pub mod mapping;
+#[cfg(all(feature = "socketcan", target_os = "linux"))]
+mod socket;
+mod source;
+
+pub use errors::CanError;
+pub use mapping::{Axis, ByteOrder, CanMapping, CanSignal};
+#[cfg(all(feature = "socketcan", target_os = "linux"))]
+pub use socket::run_service;
+pub use source::{CanBus, CanFrame, CanImuSource};
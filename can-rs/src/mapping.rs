@@ -0,0 +1,310 @@
+//! Module mapping
+//!
+//! Mapping of the signals of CAN frames to sensor axes, following the conventions of DBC
+//! files: a signal is `length` bits starting at `start_bit`, in Intel (little endian) or
+//! Motorola (big endian) order, scaled as `raw * factor + offset`.
+//!
+//! ```
+//! use can_rs::{Axis, ByteOrder, CanMapping, CanSignal};
+//!
+//! // accelerometer in frame 0x120, as 3 signed 16 bit values of 0.001 m/s²
+//! let signal = |start_bit| CanSignal::new(start_bit, 16).signed().with_scale(0.001, 0.0);
+//! let mapping = CanMapping::new()
+//!     .with_signal(0x120, 0, Axis::X, signal(0))
+//!     .with_signal(0x120, 0, Axis::Y, signal(16))
+//!     .with_signal(0x120, 0, Axis::Z, signal(32));
+//! assert!(mapping.validate(1).is_ok());
+//! ```
+
+use crate::errors::CanError;
+
+/// Maximum payload of a classic CAN frame, in bytes.
+pub const MAX_DATA_LENGTH: usize = 8;
+
+/// Byte order of a signal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Intel order, `start_bit` is the least significant bit.
+    #[default]
+    LittleEndian,
+    /// Motorola order, `start_bit` is the most significant bit, numbered as in DBC files.
+    BigEndian,
+}
+
+/// Axis of a 3D sample.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    fn index(&self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+/// Value encoded in the payload of a CAN frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanSignal {
+    start_bit: u8,
+    length: u8,
+    byte_order: ByteOrder,
+    signed: bool,
+    factor: f64,
+    offset: f64,
+}
+
+impl CanSignal {
+    /// Returns an unsigned little endian signal of `length` bits, without scaling.
+    pub fn new(start_bit: u8, length: u8) -> Self {
+        Self {
+            start_bit,
+            length,
+            byte_order: ByteOrder::default(),
+            signed: false,
+            factor: 1.0,
+            offset: 0.0,
+        }
+    }
+
+    pub fn with_byte_order(mut self, byte_order: ByteOrder) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    /// Decodes the raw value as two's complement.
+    pub fn signed(mut self) -> Self {
+        self.signed = true;
+        self
+    }
+
+    /// Sets the physical value of the signal to `raw * factor + offset`.
+    pub fn with_scale(mut self, factor: f64, offset: f64) -> Self {
+        self.factor = factor;
+        self.offset = offset;
+        self
+    }
+
+    /// Returns an InvalidMapping error if the signal is empty or doesn't fit in a frame.
+    pub fn validate(&self) -> Result<(), CanError> {
+        let n_bits = MAX_DATA_LENGTH * 8;
+        let fits = match self.byte_order {
+            ByteOrder::LittleEndian => self.start_bit as usize + self.length as usize <= n_bits,
+            ByteOrder::BigEndian => {
+                (self.start_bit as usize) < n_bits
+                    && motorola_position(self.start_bit) + self.length as usize <= n_bits
+            }
+        };
+        if self.length == 0 || !fits {
+            return Err(CanError::InvalidMapping(format!(
+                "{} bits from bit {} don't fit in a frame",
+                self.length, self.start_bit
+            )));
+        }
+        if !self.factor.is_finite() || !self.offset.is_finite() {
+            return Err(CanError::InvalidMapping(format!(
+                "Invalid scale {} + {}",
+                self.factor, self.offset
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the physical value of the signal in `data`, or `None` if `data` is too short.
+    pub fn decode(&self, data: &[u8]) -> Option<f64> {
+        let length = self.length as usize;
+        let raw = match self.byte_order {
+            ByteOrder::LittleEndian => {
+                if self.start_bit as usize + length > data.len() * 8 {
+                    return None;
+                }
+                let mut bytes = [0u8; MAX_DATA_LENGTH];
+                bytes[..data.len()].copy_from_slice(data);
+                (u64::from_le_bytes(bytes) >> self.start_bit) & mask(length)
+            }
+            ByteOrder::BigEndian => {
+                let position = motorola_position(self.start_bit);
+                if position + length > data.len() * 8 {
+                    return None;
+                }
+                let mut bytes = [0u8; MAX_DATA_LENGTH];
+                bytes[..data.len()].copy_from_slice(data);
+                (u64::from_be_bytes(bytes) >> (64 - position - length)) & mask(length)
+            }
+        };
+        let value = if self.signed && length < 64 && (raw >> (length - 1)) & 1 == 1 {
+            (raw | !mask(length)) as i64 as f64
+        } else if self.signed {
+            raw as i64 as f64
+        } else {
+            raw as f64
+        };
+        Some(value * self.factor + self.offset)
+    }
+}
+
+fn mask(length: usize) -> u64 {
+    if length >= 64 {
+        u64::MAX
+    } else {
+        (1 << length) - 1
+    }
+}
+
+/// Position of the most significant bit of a Motorola signal, counting from the first bit
+/// of the frame.
+fn motorola_position(start_bit: u8) -> usize {
+    let start_bit = start_bit as usize;
+    (start_bit / 8) * 8 + 7 - start_bit % 8
+}
+
+/// Signal of an axis of a sensor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MappedSignal {
+    pub can_id: u32,
+    /// Index of the sensor in the source.
+    pub channel: usize,
+    pub axis: Axis,
+    pub signal: CanSignal,
+}
+
+/// Signals of every axis published by a source, by CAN id.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CanMapping {
+    signals: Vec<MappedSignal>,
+}
+
+impl CanMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps `signal` of the frames with `can_id` to `axis` of the sensor `channel`.
+    pub fn with_signal(
+        mut self,
+        can_id: u32,
+        channel: usize,
+        axis: Axis,
+        signal: CanSignal,
+    ) -> Self {
+        self.signals.push(MappedSignal {
+            can_id,
+            channel,
+            axis,
+            signal,
+        });
+        self
+    }
+
+    pub fn get_signals(&self) -> &[MappedSignal] {
+        &self.signals
+    }
+
+    /// Returns an InvalidMapping error if a signal is invalid or refers to a channel not below
+    /// `n_channels`, or if an axis is mapped twice or a sensor misses an axis.
+    pub fn validate(&self, n_channels: usize) -> Result<(), CanError> {
+        let mut axes = vec![[0usize; 3]; n_channels];
+        for mapped in self.signals.iter() {
+            mapped.signal.validate()?;
+            let Some(channel_axes) = axes.get_mut(mapped.channel) else {
+                return Err(CanError::InvalidMapping(format!(
+                    "Unknown channel {}",
+                    mapped.channel
+                )));
+            };
+            channel_axes[mapped.axis.index()] += 1;
+        }
+        for (channel, channel_axes) in axes.iter().enumerate() {
+            if channel_axes.iter().any(|n| *n > 1) {
+                return Err(CanError::InvalidMapping(format!(
+                    "Axis mapped twice in channel {}",
+                    channel
+                )));
+            }
+            if channel_axes.contains(&1) && channel_axes.contains(&0) {
+                return Err(CanError::InvalidMapping(format!(
+                    "Missing axes in channel {}",
+                    channel
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the values of the signals of the frame `can_id`, as `(channel, axis, value)`, or
+    /// `None` if a signal doesn't fit in `data`.
+    pub fn decode(&self, can_id: u32, data: &[u8]) -> Option<Vec<(usize, usize, f64)>> {
+        self.signals
+            .iter()
+            .filter(|mapped| mapped.can_id == can_id)
+            .map(|mapped| {
+                mapped
+                    .signal
+                    .decode(data)
+                    .map(|value| (mapped.channel, mapped.axis.index(), value))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_little_endian() {
+        let data = [0x34, 0x12, 0xFF, 0xFF, 0x0A, 0x00, 0x00, 0x00];
+        assert_eq!(CanSignal::new(0, 16).decode(&data), Some(4660.0));
+        assert_eq!(CanSignal::new(16, 16).signed().decode(&data), Some(-1.0));
+        assert_eq!(CanSignal::new(32, 4).decode(&data), Some(10.0));
+        assert_eq!(
+            CanSignal::new(0, 16).with_scale(0.5, -1.0).decode(&data),
+            Some(2329.0)
+        );
+        // the payload is too short
+        assert_eq!(CanSignal::new(48, 16).decode(&data[..6]), None);
+    }
+
+    #[test]
+    fn test_decode_big_endian() {
+        let data = [0x12, 0x34, 0xFF, 0xFE];
+        // DBC start bits of Motorola signals are the most significant bit
+        let signal = CanSignal::new(7, 16).with_byte_order(ByteOrder::BigEndian);
+        assert_eq!(signal.decode(&data), Some(4660.0));
+        let signal = CanSignal::new(23, 16)
+            .with_byte_order(ByteOrder::BigEndian)
+            .signed();
+        assert_eq!(signal.decode(&data), Some(-2.0));
+        let signal = CanSignal::new(3, 8).with_byte_order(ByteOrder::BigEndian);
+        assert_eq!(signal.decode(&data), Some(0x23 as f64));
+    }
+
+    #[test]
+    fn test_validate() {
+        let signal = |start_bit| CanSignal::new(start_bit, 16);
+        let mapping = CanMapping::new()
+            .with_signal(0x10, 0, Axis::X, signal(0))
+            .with_signal(0x10, 0, Axis::Y, signal(16))
+            .with_signal(0x11, 0, Axis::Z, signal(0));
+        assert!(mapping.validate(1).is_ok());
+        assert!(mapping.validate(0).is_err());
+
+        let mapping = mapping.clone().with_signal(0x12, 1, Axis::X, signal(0));
+        assert!(matches!(
+            mapping.validate(2),
+            Err(CanError::InvalidMapping(_))
+        ));
+        assert!(CanSignal::new(56, 16).validate().is_err());
+        assert!(CanSignal::new(0, 0).validate().is_err());
+        assert!(CanSignal::new(7, 64)
+            .with_byte_order(ByteOrder::BigEndian)
+            .validate()
+            .is_ok());
+    }
+}
@@ -0,0 +1,72 @@
+//! Module socket
+//!
+//! Reading of SocketCAN interfaces, such as `can0` or the virtual `vcan0`.
+
+use log::error;
+use socketcan::{CanSocket, EmbeddedFrame, Frame, Socket};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::errors::CanError;
+use crate::mapping::CanMapping;
+use crate::source::{CanBus, CanFrame, CanImuSource};
+use imu_common::types::sensors::SensorType;
+
+/// Timeout of the reads from the socket, so the source checks regularly if it was stopped.
+const READ_TIMEOUT_MILLIS: u64 = 100;
+
+impl CanBus for CanSocket {
+    /// Returns the next data frame, stamped with the time it was received by the kernel.
+    /// Remote and error frames are skipped.
+    fn read_frame(&mut self) -> std::io::Result<CanFrame> {
+        loop {
+            let (frame, received_at) = self.read_frame_with_timestamp()?;
+            let socketcan::CanFrame::Data(frame) = frame else {
+                continue;
+            };
+            let timestamp_secs = received_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64();
+            return Ok(
+                CanFrame::new(frame.raw_id(), frame.data()).with_timestamp_secs(timestamp_secs)
+            );
+        }
+    }
+}
+
+/// Starts a CAN source reading the SocketCAN `interface` in a background thread.
+///
+/// `sensors` are the sensors published, in the order of the mapping channels.
+///
+/// Returns a Bus error if the interface can't be opened, InvalidMapping if the mapping is
+/// invalid, and DuplicatedSensor if the sensors collide with the ones of a running source.
+///
+/// # Returns
+///
+/// Returns a tuple containing:
+/// * A `std::thread::JoinHandle<()>` of the thread, which ends when `CanImuSource::stop` is
+///   called, the source is dropped, or the global `ShutdownToken` is shut down.
+/// * An `Arc<CanImuSource>` to register listeners and stop the source.
+pub fn run_service(
+    interface: &str,
+    tag: &str,
+    sensors: Vec<SensorType>,
+    mapping: CanMapping,
+) -> Result<(std::thread::JoinHandle<()>, Arc<CanImuSource>), CanError> {
+    let source = Arc::new(CanImuSource::new(tag, sensors, mapping)?);
+    let socket = CanSocket::open(interface).map_err(|e| CanError::Bus(e.to_string()))?;
+    socket
+        .set_read_timeout(Duration::from_millis(READ_TIMEOUT_MILLIS))
+        .map_err(|e| CanError::Bus(e.to_string()))?;
+    let handle = std::thread::spawn({
+        let source = source.clone();
+        move || {
+            // stop once the caller drops the source
+            if let Err(e) = source.read_while(socket, || Arc::strong_count(&source) > 1) {
+                error!("Error in CAN loop: {:?}", e);
+            }
+        }
+    });
+    Ok((handle, source))
+}
@@ -0,0 +1,227 @@
+use log::warn;
+use publisher::{PublisherManager, ShutdownToken};
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::errors::CanError;
+use crate::mapping::CanMapping;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSource, Notifiable};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::Clock;
+
+/// Data frame received from a CAN bus.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanFrame {
+    /// Identifier of the frame, standard or extended.
+    pub id: u32,
+    pub data: Vec<u8>,
+    /// Time the frame was received in seconds since the epoch, or `None` to stamp it with the
+    /// time it is processed.
+    pub timestamp_secs: Option<f64>,
+}
+
+impl CanFrame {
+    pub fn new(id: u32, data: &[u8]) -> Self {
+        Self {
+            id,
+            data: data.to_vec(),
+            timestamp_secs: None,
+        }
+    }
+
+    pub fn with_timestamp_secs(mut self, timestamp_secs: f64) -> Self {
+        self.timestamp_secs = Some(timestamp_secs);
+        self
+    }
+}
+
+/// Interface frames are read from.
+pub trait CanBus: Send {
+    /// Returns the next data frame. Errors of kind `TimedOut`, `WouldBlock` and `Interrupted`
+    /// are retried, and `UnexpectedEof` ends the source.
+    fn read_frame(&mut self) -> std::io::Result<CanFrame>;
+}
+
+/// Replays recorded frames, e.g. from a log file.
+impl CanBus for std::vec::IntoIter<CanFrame> {
+    fn read_frame(&mut self) -> std::io::Result<CanFrame> {
+        self.next()
+            .ok_or_else(|| std::io::Error::from(ErrorKind::UnexpectedEof))
+    }
+}
+
+/// Source publishing the readings of IMUs on a CAN bus.
+///
+/// Frames are decoded with a [`CanMapping`], whose channels index `sensors`. A reading is
+/// published every time a frame carries an axis of a sensor, with the last values received for
+/// the axes sent in other frames, once every axis was received. Frames without mapped signals
+/// are ignored.
+pub struct CanImuSource {
+    tag: String,
+    sensors: Vec<SensorType>,
+    publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+    mapping: CanMapping,
+    // last value of every axis of every channel
+    axes: Mutex<Vec<[Option<f64>; 3]>>,
+    is_stopped: AtomicBool,
+    shutdown: ShutdownToken,
+    dropped_frames: AtomicUsize,
+}
+
+impl CanImuSource {
+    /// Creates a source decoding frames with `mapping`.
+    /// Returns an InvalidMapping error if the mapping is invalid for `sensors`, and
+    /// DuplicatedSensor if a sensor is repeated or already published by another source.
+    pub fn new(tag: &str, sensors: Vec<SensorType>, mapping: CanMapping) -> Result<Self, CanError> {
+        mapping.validate(sensors.len())?;
+        let publishers = PublisherManager::try_new(&sensors)
+            .map_err(|e| CanError::DuplicatedSensor(format!("{} in {}", e, tag)))?;
+        Ok(Self {
+            tag: tag.to_string(),
+            axes: Mutex::new(vec![[None; 3]; sensors.len()]),
+            sensors,
+            publishers,
+            mapping,
+            is_stopped: AtomicBool::new(false),
+            shutdown: ShutdownToken::global(),
+            dropped_frames: AtomicUsize::new(0),
+        })
+    }
+
+    /// Stops the source when `token` is shut down, instead of the global token.
+    pub fn with_shutdown_token(mut self, token: ShutdownToken) -> Self {
+        self.shutdown = token;
+        self
+    }
+
+    pub fn get_mapping(&self) -> &CanMapping {
+        &self.mapping
+    }
+
+    /// Returns the number of mapped frames dropped because their payload was too short.
+    pub fn get_dropped_frames(&self) -> usize {
+        self.dropped_frames.load(Ordering::Relaxed)
+    }
+
+    /// Reads and publishes readings from `bus` until it ends, `stop` is called or the shutdown
+    /// token is shut down. Read timeouts are retried, so `bus` should time out regularly for the
+    /// source to notice it was stopped.
+    /// Returns a Bus error if reading fails.
+    pub fn start<B: CanBus>(&self, bus: B) -> Result<(), CanError> {
+        self.read_while(bus, || true)
+    }
+
+    /// Same as `start`, also returning once `is_running` returns false.
+    pub(crate) fn read_while<B, F>(&self, mut bus: B, is_running: F) -> Result<(), CanError>
+    where
+        B: CanBus,
+        F: Fn() -> bool,
+    {
+        while !self.is_stopped.load(Ordering::Relaxed)
+            && !self.shutdown.is_shutdown()
+            && is_running()
+        {
+            match bus.read_frame() {
+                Ok(frame) => self.process_frames(&[frame]),
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => return Err(CanError::Bus(e.to_string())),
+            }
+        }
+        Ok(())
+    }
+
+    /// Stops `start`. If it isn't running yet, it returns as soon as it is called.
+    pub fn stop(&self) {
+        self.is_stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Decodes `frames` and publishes their readings, grouped by sensor.
+    pub fn process_frames(&self, frames: &[CanFrame]) {
+        let mut readings: Vec<SensorReadings<Sample3D>> = Vec::new();
+        let mut axes = self.axes.lock().unwrap();
+        for frame in frames {
+            let Some(values) = self.mapping.decode(frame.id, &frame.data) else {
+                warn!(
+                    "Dropping frame {:#x} of {} bytes received by {}",
+                    frame.id,
+                    frame.data.len(),
+                    self.tag
+                );
+                self.dropped_frames.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+            if values.is_empty() {
+                continue;
+            }
+            let timestamp = frame
+                .timestamp_secs
+                .unwrap_or_else(|| Clock::now().as_secs());
+            let mut channels: Vec<usize> = Vec::new();
+            for (channel, axis, value) in values {
+                axes[channel][axis] = Some(value);
+                if !channels.contains(&channel) {
+                    channels.push(channel);
+                }
+            }
+            for channel in channels {
+                let [Some(x), Some(y), Some(z)] = axes[channel] else {
+                    continue;
+                };
+                let sensor_type = &self.sensors[channel];
+                let sample = Sample3D::new(timestamp, [x, y, z]);
+                match readings
+                    .iter_mut()
+                    .find(|r| r.get_sensor_type() == *sensor_type)
+                {
+                    Some(sensor_readings) => sensor_readings.add_sample(sample),
+                    None => readings.push(SensorReadings::from_vec(
+                        &self.tag,
+                        sensor_type.clone(),
+                        vec![sample],
+                    )),
+                }
+            }
+        }
+        drop(axes);
+
+        for readings in readings {
+            self.publishers
+                .notify_listeners(readings.get_sensor_type(), Arc::new(readings));
+        }
+    }
+}
+
+impl IMUSource<SensorReadings<Sample3D>, Sample3D> for CanImuSource {
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.sensors.clone()
+    }
+
+    fn get_tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<Sample3D>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<Sample3D>>) {
+        self.publishers.notify_listeners(sensor_type, data);
+    }
+}
@@ -0,0 +1,114 @@
+use publisher::Listener;
+use std::sync::{Arc, Mutex};
+
+use can_rs::{Axis, ByteOrder, CanError, CanFrame, CanImuSource, CanMapping, CanSignal};
+use imu_common::traits::{IMUReadings, IMUSample, IMUSource};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+
+type Batches = Arc<Mutex<Vec<Vec<Sample3D>>>>;
+
+fn collect(
+    source: &CanImuSource,
+    sensor_type: &SensorType,
+) -> (Listener<SensorReadings<Sample3D>>, Batches) {
+    let batches: Batches = Arc::new(Mutex::new(Vec::new()));
+    let mut listener = Listener::new({
+        let batches = batches.clone();
+        move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+            batches.lock().unwrap().push(readings.get_samples());
+        }
+    });
+    source
+        .register_listener(&mut listener, sensor_type)
+        .unwrap();
+    (listener, batches)
+}
+
+/// Accelerometer in frame 0x100 as little endian milli-g, gyroscope split between frames 0x101
+/// (x, y) and 0x102 (z) as big endian centi-degrees per second.
+fn mapping() -> CanMapping {
+    let acc = |start_bit| {
+        CanSignal::new(start_bit, 16)
+            .signed()
+            .with_scale(0.00980665, 0.0)
+    };
+    let gyro = |start_bit| {
+        CanSignal::new(start_bit, 16)
+            .with_byte_order(ByteOrder::BigEndian)
+            .signed()
+            .with_scale(0.01, 0.0)
+    };
+    CanMapping::new()
+        .with_signal(0x100, 0, Axis::X, acc(0))
+        .with_signal(0x100, 0, Axis::Y, acc(16))
+        .with_signal(0x100, 0, Axis::Z, acc(32))
+        .with_signal(0x101, 1, Axis::X, gyro(7))
+        .with_signal(0x101, 1, Axis::Y, gyro(23))
+        .with_signal(0x102, 1, Axis::Z, gyro(7))
+}
+
+fn acc_frame(timestamp: f64, z_milli_g: i16) -> CanFrame {
+    let mut data = vec![0, 0, 0, 0];
+    data.extend(z_milli_g.to_le_bytes());
+    CanFrame::new(0x100, &data).with_timestamp_secs(timestamp)
+}
+
+#[test]
+fn test_decode_frames() {
+    let sensors = SensorType::cluster_for_tag("test_can_decode_frames");
+    let source = CanImuSource::new("Rover", sensors.clone(), mapping()).unwrap();
+    let (_acc_listener, acc) = collect(&source, &sensors[0]);
+    let (_gyro_listener, gyro) = collect(&source, &sensors[1]);
+
+    let frames = vec![
+        acc_frame(1.0, 1000),
+        // the gyroscope is published once its z axis is received
+        CanFrame::new(0x101, &[0x00, 0x64, 0xFF, 0x9C]).with_timestamp_secs(1.0),
+        CanFrame::new(0x102, &[0x01, 0x2C]).with_timestamp_secs(1.01),
+        CanFrame::new(0x101, &[0x00, 0xC8, 0x00, 0x00]).with_timestamp_secs(1.02),
+        // unmapped
+        CanFrame::new(0x7FF, &[0x01]),
+        acc_frame(1.02, -1000),
+    ];
+    source.start(frames.into_iter()).unwrap();
+
+    let acc: Vec<Sample3D> = acc.lock().unwrap().iter().flatten().cloned().collect();
+    assert_eq!(acc.len(), 2);
+    assert!((acc[0].get_measurement().inner()[2] - 9.80665).abs() < 1e-9);
+    assert!((acc[1].get_measurement().inner()[2] + 9.80665).abs() < 1e-9);
+
+    let gyro: Vec<Sample3D> = gyro.lock().unwrap().iter().flatten().cloned().collect();
+    let timestamps: Vec<f64> = gyro.iter().map(|s| s.get_timestamp_secs()).collect();
+    assert_eq!(timestamps, vec![1.01, 1.02]);
+    let expected = [[1.0, -1.0, 3.0], [2.0, 0.0, 3.0]];
+    for (sample, expected) in gyro.iter().zip(expected) {
+        for (v, e) in sample.get_measurement().inner().iter().zip(expected) {
+            assert!((v - e).abs() < 1e-9);
+        }
+    }
+    assert_eq!(source.get_dropped_frames(), 0);
+}
+
+#[test]
+fn test_drop_short_frames() {
+    let sensors = SensorType::cluster_for_tag("test_can_drop_short_frames");
+    let source = CanImuSource::new("Rover", sensors.clone(), mapping()).unwrap();
+    let (_listener, acc) = collect(&source, &sensors[0]);
+
+    source.process_frames(&[CanFrame::new(0x100, &[0x00, 0x01])]);
+
+    assert!(acc.lock().unwrap().is_empty());
+    assert_eq!(source.get_dropped_frames(), 1);
+}
+
+#[test]
+fn test_invalid_source() {
+    let sensors = SensorType::cluster_for_tag("test_can_invalid_source");
+    let result = CanImuSource::new("Rover", sensors[..1].to_vec(), mapping());
+    assert!(matches!(result, Err(CanError::InvalidMapping(_))));
+
+    let _source = CanImuSource::new("Rover", sensors.clone(), mapping()).unwrap();
+    let result = CanImuSource::new("Rover", sensors, mapping());
+    assert!(matches!(result, Err(CanError::DuplicatedSensor(_))));
+}
@@ -0,0 +1,30 @@
+//! Module errors
+
+/// Errors of the gamepad source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadError {
+    /// Error finding, opening or reading the controller.
+    Device(String),
+
+    /// Error indicating that a sensor is already published by another source.
+    DuplicatedSensor(String),
+
+    /// Error indicating that the sensors aren't a six or nine axis cluster.
+    InvalidCluster(String),
+
+    /// Error indicating that a received report is malformed or has no motion data.
+    InvalidReport(String),
+}
+
+impl std::fmt::Display for GamepadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GamepadError::Device(e) => write!(f, "Device error: {}", e),
+            GamepadError::DuplicatedSensor(e) => write!(f, "Duplicated sensor: {}", e),
+            GamepadError::InvalidCluster(e) => write!(f, "Invalid cluster: {}", e),
+            GamepadError::InvalidReport(e) => write!(f, "Invalid report: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for GamepadError {}
@@ -0,0 +1,87 @@
+//! Module hid
+//!
+//! Reading of controllers attached over USB or Bluetooth with hidapi.
+
+use hidapi::{HidApi, HidDevice};
+use log::{error, info};
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+use crate::errors::GamepadError;
+use crate::report::ControllerModel;
+use crate::source::{GamepadImuSource, ReportReader};
+use imu_common::types::sensors::SensorType;
+
+/// Size of the reads from the device, larger than any report of the supported controllers.
+const READ_BUFFER_SIZE: usize = 128;
+/// Timeout of the reads from the device, so the source checks regularly if it was stopped.
+const READ_TIMEOUT_MILLIS: i32 = 100;
+
+impl ReportReader for HidDevice {
+    fn read_report(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut buffer = [0u8; READ_BUFFER_SIZE];
+        match self.read_timeout(&mut buffer, READ_TIMEOUT_MILLIS) {
+            Ok(0) => Err(std::io::Error::from(ErrorKind::TimedOut)),
+            Ok(n) => Ok(buffer[..n].to_vec()),
+            Err(e) => Err(std::io::Error::other(e.to_string())),
+        }
+    }
+}
+
+/// Opens the first supported controller, and enables its motion sensors.
+fn open_controller() -> Result<(HidDevice, ControllerModel), GamepadError> {
+    let api = HidApi::new().map_err(|e| GamepadError::Device(e.to_string()))?;
+    let (info, model) = api
+        .device_list()
+        .find_map(|info| {
+            ControllerModel::from_ids(info.vendor_id(), info.product_id()).map(|m| (info, m))
+        })
+        .ok_or_else(|| GamepadError::Device("No supported controller found".to_string()))?;
+    let device = info
+        .open_device(&api)
+        .map_err(|e| GamepadError::Device(e.to_string()))?;
+    for report in model.init_reports() {
+        device
+            .write(&report)
+            .map_err(|e| GamepadError::Device(e.to_string()))?;
+    }
+    info!(
+        "Reading {:?} {}",
+        model,
+        info.product_string().unwrap_or_default()
+    );
+    Ok((device, model))
+}
+
+/// Starts a gamepad source reading the first supported controller in a background thread.
+///
+/// `sensors` must be a six axis cluster, or a nine axis cluster to feed an `AHRSFilter`.
+///
+/// Returns a Device error if no supported controller is found or it can't be opened,
+/// InvalidCluster if `sensors` isn't a six or nine axis cluster, and DuplicatedSensor if the
+/// sensors collide with the ones of a running source.
+///
+/// # Returns
+///
+/// Returns a tuple containing:
+/// * A `std::thread::JoinHandle<()>` of the thread, which ends when `GamepadImuSource::stop` is
+///   called, the source is dropped, the controller is disconnected or the global
+///   `ShutdownToken` is shut down.
+/// * An `Arc<GamepadImuSource>` to register listeners and stop the source.
+pub fn run_service(
+    tag: &str,
+    sensors: Vec<SensorType>,
+) -> Result<(std::thread::JoinHandle<()>, Arc<GamepadImuSource>), GamepadError> {
+    let (device, model) = open_controller()?;
+    let source = Arc::new(GamepadImuSource::new(tag, sensors, model)?);
+    let handle = std::thread::spawn({
+        let source = source.clone();
+        move || {
+            // stop once the caller drops the source
+            if let Err(e) = source.read_while(device, || Arc::strong_count(&source) > 1) {
+                error!("Error in gamepad loop: {:?}", e);
+            }
+        }
+    });
+    Ok((handle, source))
+}
@@ -0,0 +1,25 @@
+//! # Crate gamepad-rs
+//!
+//! ## gamepad-rs
+//!
+//! The `gamepad-rs` crate publishes the accelerometer and gyroscope of game controllers, a
+//! cheap and widely available 6 axis IMU, so the AHRS can run without a phone.
+//!
+//! [`GamepadImuSource`] decodes the input reports of:
+//! - Sony DualShock 4 and DualSense controllers, over USB or Bluetooth.
+//! - Nintendo Switch Pro controllers.
+//!
+//! Controllers are read with hidapi by [`run_service`], with the `hidapi` feature enabled by
+//! default, or from any other [`ReportReader`].
+
+pub mod errors;
+#[cfg(feature = "hidapi")]
+mod hid;
+pub mod report;
+mod source;
+
+pub use errors::GamepadError;
+#[cfg(feature = "hidapi")]
+pub use hid::run_service;
+pub use report::{ControllerModel, MotionFrame, ReportParser};
+pub use source::{GamepadImuSource, ReportReader};
@@ -0,0 +1,344 @@
+//! Module report
+//!
+//! Decoding of the motion data in the input reports of game controllers.
+//!
+//! Readings are converted to m/s² and rad/s with the nominal sensitivity of the sensors, as the
+//! factory calibration of the controllers isn't read. Axes follow the convention of Sony
+//! controllers for every model: `x` to the right, `y` out of the face of the controller and `z`
+//! towards the player.
+
+use crate::errors::GamepadError;
+
+pub const SONY_VENDOR_ID: u16 = 0x054C;
+pub const NINTENDO_VENDOR_ID: u16 = 0x057E;
+
+const STANDARD_GRAVITY: f64 = 9.80665;
+
+/// Nominal sensitivities of DualShock 4 and DualSense sensors, in LSB per g and per °/s.
+const SONY_ACCEL_RESOLUTION: f64 = 8192.0;
+const SONY_GYRO_RESOLUTION: f64 = 16.0;
+/// Duration of the ticks of the sensor timestamps.
+const DUALSHOCK4_TICK_SECS: f64 = 16.0 / 3.0 * 1e-6;
+const DUALSENSE_TICK_SECS: f64 = 1.0 / 3.0 * 1e-6;
+
+/// Nominal sensitivities of Switch Pro sensors, in LSB per g and per °/s.
+const SWITCH_ACCEL_RESOLUTION: f64 = 4096.0;
+const SWITCH_GYRO_RESOLUTION: f64 = 14.2842;
+/// Switch reports carry 3 readings sampled 5 ms apart.
+const SWITCH_FRAMES_PER_REPORT: usize = 3;
+const SWITCH_FRAME_PERIOD_SECS: f64 = 0.005;
+const SWITCH_IMU_OFFSET: usize = 13;
+const SWITCH_FRAME_SIZE: usize = 12;
+
+/// Supported controllers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControllerModel {
+    DualShock4,
+    DualSense,
+    SwitchPro,
+}
+
+impl ControllerModel {
+    /// Returns the model of the USB or Bluetooth device with the given ids, or `None` if it isn't
+    /// supported.
+    pub fn from_ids(vendor_id: u16, product_id: u16) -> Option<Self> {
+        match (vendor_id, product_id) {
+            (SONY_VENDOR_ID, 0x05C4 | 0x09CC | 0x0BA0) => Some(ControllerModel::DualShock4),
+            (SONY_VENDOR_ID, 0x0CE6 | 0x0DF2) => Some(ControllerModel::DualSense),
+            (NINTENDO_VENDOR_ID, 0x2009) => Some(ControllerModel::SwitchPro),
+            _ => None,
+        }
+    }
+
+    /// Returns the output reports enabling the motion sensors, to send once the device is opened.
+    pub fn init_reports(&self) -> Vec<Vec<u8>> {
+        match self {
+            // Sony controllers send motion data in their full input reports
+            ControllerModel::DualShock4 | ControllerModel::DualSense => Vec::new(),
+            // enable the IMU, then the standard full input report mode
+            ControllerModel::SwitchPro => vec![
+                switch_subcommand(0, 0x40, 0x01),
+                switch_subcommand(1, 0x03, 0x30),
+            ],
+        }
+    }
+}
+
+fn switch_subcommand(counter: u8, subcommand: u8, argument: u8) -> Vec<u8> {
+    let neutral_rumble = [0x00, 0x01, 0x40, 0x40, 0x00, 0x01, 0x40, 0x40];
+    let mut report = vec![0x01, counter];
+    report.extend(neutral_rumble);
+    report.extend([subcommand, argument]);
+    report
+}
+
+/// Motion reading decoded from a report.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MotionFrame {
+    pub timestamp_secs: f64,
+    /// Acceleration in m/s².
+    pub accel: [f64; 3],
+    /// Angular rate in rad/s.
+    pub gyro: [f64; 3],
+}
+
+/// Decoder of the input reports of a controller.
+///
+/// Sony controllers stamp their readings with a counter, which is unwrapped and anchored to the
+/// time the first report was received, so readings keep the spacing they were sampled with.
+/// Switch readings are stamped with the time their report was received.
+#[derive(Clone, Debug)]
+pub struct ReportParser {
+    model: ControllerModel,
+    last_ticks: Option<u32>,
+    elapsed_ticks: u64,
+    anchor_secs: f64,
+}
+
+impl ReportParser {
+    pub fn new(model: ControllerModel) -> Self {
+        Self {
+            model,
+            last_ticks: None,
+            elapsed_ticks: 0,
+            anchor_secs: 0.0,
+        }
+    }
+
+    pub fn get_model(&self) -> ControllerModel {
+        self.model
+    }
+
+    /// Decodes `report`, received at `received_secs`.
+    /// Returns an InvalidReport error if the report is too short or has no motion data, such as
+    /// the simple reports Sony controllers send over Bluetooth until they are configured.
+    pub fn parse(
+        &mut self,
+        report: &[u8],
+        received_secs: f64,
+    ) -> Result<Vec<MotionFrame>, GamepadError> {
+        match self.model {
+            ControllerModel::DualShock4 => self.parse_dualshock4(report, received_secs),
+            ControllerModel::DualSense => self.parse_dualsense(report, received_secs),
+            ControllerModel::SwitchPro => parse_switch(report, received_secs),
+        }
+    }
+
+    fn parse_dualshock4(
+        &mut self,
+        report: &[u8],
+        received_secs: f64,
+    ) -> Result<Vec<MotionFrame>, GamepadError> {
+        // USB and Bluetooth reports differ in the bytes before the data
+        let base = match report.first() {
+            Some(0x01) => 1,
+            Some(0x11) => 3,
+            _ => return Err(unknown_report(report)),
+        };
+        check_length(report, base + 24)?;
+        let ticks = u16::from_le_bytes([report[base + 9], report[base + 10]]) as u32;
+        let timestamp_secs = self.unwrap_ticks(
+            ticks,
+            u16::MAX as u64 + 1,
+            DUALSHOCK4_TICK_SECS,
+            received_secs,
+        );
+        Ok(vec![sony_frame(
+            timestamp_secs,
+            &report[base + 12..base + 18],
+            &report[base + 18..base + 24],
+        )])
+    }
+
+    fn parse_dualsense(
+        &mut self,
+        report: &[u8],
+        received_secs: f64,
+    ) -> Result<Vec<MotionFrame>, GamepadError> {
+        let base = match report.first() {
+            Some(0x01) => 1,
+            Some(0x31) => 2,
+            _ => return Err(unknown_report(report)),
+        };
+        check_length(report, base + 31)?;
+        let ticks = u32::from_le_bytes(report[base + 27..base + 31].try_into().unwrap());
+        let timestamp_secs = self.unwrap_ticks(
+            ticks,
+            u32::MAX as u64 + 1,
+            DUALSENSE_TICK_SECS,
+            received_secs,
+        );
+        Ok(vec![sony_frame(
+            timestamp_secs,
+            &report[base + 15..base + 21],
+            &report[base + 21..base + 27],
+        )])
+    }
+
+    /// Unwraps a counter wrapping at `period` ticks, and returns its time since the first report
+    /// anchored to the time the first report was received.
+    fn unwrap_ticks(&mut self, ticks: u32, period: u64, tick_secs: f64, received_secs: f64) -> f64 {
+        match self.last_ticks {
+            None => self.anchor_secs = received_secs,
+            Some(last_ticks) => {
+                self.elapsed_ticks += (ticks as u64 + period - last_ticks as u64) % period;
+            }
+        }
+        self.last_ticks = Some(ticks);
+        self.anchor_secs + self.elapsed_ticks as f64 * tick_secs
+    }
+}
+
+fn unknown_report(report: &[u8]) -> GamepadError {
+    GamepadError::InvalidReport(format!(
+        "Report {:#04x} has no motion data",
+        report.first().copied().unwrap_or_default()
+    ))
+}
+
+fn check_length(report: &[u8], length: usize) -> Result<(), GamepadError> {
+    if report.len() < length {
+        return Err(GamepadError::InvalidReport(format!(
+            "Report of {} bytes, expected {}",
+            report.len(),
+            length
+        )));
+    }
+    Ok(())
+}
+
+fn read_i16s(bytes: &[u8]) -> [f64; 3] {
+    let value = |i: usize| i16::from_le_bytes([bytes[2 * i], bytes[2 * i + 1]]) as f64;
+    [value(0), value(1), value(2)]
+}
+
+fn sony_frame(timestamp_secs: f64, gyro: &[u8], accel: &[u8]) -> MotionFrame {
+    let gyro = read_i16s(gyro).map(|v| (v / SONY_GYRO_RESOLUTION).to_radians());
+    let accel = read_i16s(accel).map(|v| v / SONY_ACCEL_RESOLUTION * STANDARD_GRAVITY);
+    MotionFrame {
+        timestamp_secs,
+        accel,
+        gyro,
+    }
+}
+
+fn parse_switch(report: &[u8], received_secs: f64) -> Result<Vec<MotionFrame>, GamepadError> {
+    if !matches!(report.first(), Some(0x30..=0x33)) {
+        return Err(unknown_report(report));
+    }
+    check_length(
+        report,
+        SWITCH_IMU_OFFSET + SWITCH_FRAMES_PER_REPORT * SWITCH_FRAME_SIZE,
+    )?;
+    Ok((0..SWITCH_FRAMES_PER_REPORT)
+        .map(|i| {
+            let start = SWITCH_IMU_OFFSET + i * SWITCH_FRAME_SIZE;
+            let accel = read_i16s(&report[start..start + 6])
+                .map(|v| v / SWITCH_ACCEL_RESOLUTION * STANDARD_GRAVITY);
+            let gyro = read_i16s(&report[start + 6..start + 12])
+                .map(|v| (v / SWITCH_GYRO_RESOLUTION).to_radians());
+            // the sensors of the Switch Pro have x forward, y left and z up
+            let age = (SWITCH_FRAMES_PER_REPORT - 1 - i) as f64 * SWITCH_FRAME_PERIOD_SECS;
+            MotionFrame {
+                timestamp_secs: received_secs - age,
+                accel: [-accel[1], accel[2], -accel[0]],
+                gyro: [-gyro[1], gyro[2], -gyro[0]],
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Encodes a DualShock 4 USB report.
+    pub(crate) fn dualshock4_report(ticks: u16, gyro: [i16; 3], accel: [i16; 3]) -> Vec<u8> {
+        let mut report = vec![0u8; 64];
+        report[0] = 0x01;
+        report[10..12].copy_from_slice(&ticks.to_le_bytes());
+        for i in 0..3 {
+            report[13 + 2 * i..15 + 2 * i].copy_from_slice(&gyro[i].to_le_bytes());
+            report[19 + 2 * i..21 + 2 * i].copy_from_slice(&accel[i].to_le_bytes());
+        }
+        report
+    }
+
+    fn assert_close(actual: [f64; 3], expected: [f64; 3]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_dualshock4() {
+        let mut parser = ReportParser::new(ControllerModel::DualShock4);
+        let report = dualshock4_report(0xFFF0, [16 * 90, 0, -16 * 180], [0, 8192, 0]);
+        let frames = parser.parse(&report, 100.0).unwrap();
+
+        assert_eq!(frames[0].timestamp_secs, 100.0);
+        assert_close(frames[0].accel, [0.0, STANDARD_GRAVITY, 0.0]);
+        assert_close(
+            frames[0].gyro,
+            [std::f64::consts::FRAC_PI_2, 0.0, -std::f64::consts::PI],
+        );
+
+        // the counter wraps
+        let report = dualshock4_report(0x0080, [0; 3], [0; 3]);
+        let frames = parser.parse(&report, 100.1).unwrap();
+        assert!(
+            (frames[0].timestamp_secs - 100.0 - 0x90 as f64 * DUALSHOCK4_TICK_SECS).abs() < 1e-12
+        );
+
+        // simple Bluetooth report
+        assert!(parser.parse(&[0x01, 0x80, 0x80], 100.2).is_err());
+    }
+
+    #[test]
+    fn test_dualsense_bluetooth() {
+        let mut parser = ReportParser::new(ControllerModel::DualSense);
+        let mut report = vec![0u8; 78];
+        report[0] = 0x31;
+        report[17..19].copy_from_slice(&16i16.to_le_bytes());
+        report[27..29].copy_from_slice(&(-8192i16).to_le_bytes());
+        report[29..33].copy_from_slice(&3_000_000u32.to_le_bytes());
+        let frames = parser.parse(&report, 5.0).unwrap();
+
+        assert_close(frames[0].gyro, [1f64.to_radians(), 0.0, 0.0]);
+        assert_close(frames[0].accel, [0.0, 0.0, -STANDARD_GRAVITY]);
+
+        report[29..33].copy_from_slice(&3_030_000u32.to_le_bytes());
+        let frames = parser.parse(&report, 5.5).unwrap();
+        assert!((frames[0].timestamp_secs - 5.01).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_switch() {
+        let mut parser = ReportParser::new(ControllerModel::SwitchPro);
+        let mut report = vec![0u8; 49];
+        report[0] = 0x30;
+        for i in 0..SWITCH_FRAMES_PER_REPORT {
+            let start = SWITCH_IMU_OFFSET + i * SWITCH_FRAME_SIZE;
+            // lying flat
+            report[start + 4..start + 6].copy_from_slice(&4096i16.to_le_bytes());
+        }
+        let frames = parser.parse(&report, 2.0).unwrap();
+
+        assert_eq!(frames.len(), 3);
+        assert!((frames[0].timestamp_secs - 1.99).abs() < 1e-12);
+        assert_eq!(frames[2].timestamp_secs, 2.0);
+        assert_close(frames[1].accel, [0.0, STANDARD_GRAVITY, 0.0]);
+        assert!(parser.parse(&report[..40], 2.0).is_err());
+    }
+
+    #[test]
+    fn test_models() {
+        assert_eq!(
+            ControllerModel::from_ids(SONY_VENDOR_ID, 0x0CE6),
+            Some(ControllerModel::DualSense)
+        );
+        assert_eq!(ControllerModel::from_ids(SONY_VENDOR_ID, 0x0001), None);
+        assert_eq!(ControllerModel::SwitchPro.init_reports()[0][10], 0x40);
+        assert!(ControllerModel::DualShock4.init_reports().is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+use log::warn;
+use publisher::{PublisherManager, ShutdownToken};
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::errors::GamepadError;
+use crate::report::{ControllerModel, MotionFrame, ReportParser};
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSource, Notifiable};
+use imu_common::types::sensors::{
+    check_nine_axis_cluster, check_six_axis_cluster, SensorReadings, SensorType,
+};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::Clock;
+
+/// Device input reports are read from.
+pub trait ReportReader: Send {
+    /// Returns the next input report. Errors of kind `TimedOut`, `WouldBlock` and `Interrupted`
+    /// are retried, and `UnexpectedEof` ends the source.
+    fn read_report(&mut self) -> std::io::Result<Vec<u8>>;
+}
+
+/// Replays recorded reports.
+impl ReportReader for std::vec::IntoIter<Vec<u8>> {
+    fn read_report(&mut self) -> std::io::Result<Vec<u8>> {
+        self.next()
+            .ok_or_else(|| std::io::Error::from(ErrorKind::UnexpectedEof))
+    }
+}
+
+/// Source publishing the accelerometer and gyroscope of a game controller.
+///
+/// Sensors must be a six axis cluster, or a nine axis cluster to feed an `AHRSFilter`. Controllers
+/// have no magnetometer, so in that case a zero magnetometer reading is published with every
+/// reading, and estimators rely on the accelerometer alone to correct the gyroscope drift.
+/// Reports without motion data are dropped.
+pub struct GamepadImuSource {
+    tag: String,
+    sensors: Vec<SensorType>,
+    publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+    parser: Mutex<ReportParser>,
+    is_stopped: AtomicBool,
+    shutdown: ShutdownToken,
+    dropped_reports: AtomicUsize,
+}
+
+impl GamepadImuSource {
+    /// Creates a source decoding the reports of a `model` controller.
+    /// Returns an InvalidCluster error if `sensors` isn't a six or nine axis cluster, and
+    /// DuplicatedSensor if a sensor is already published by another source.
+    pub fn new(
+        tag: &str,
+        sensors: Vec<SensorType>,
+        model: ControllerModel,
+    ) -> Result<Self, GamepadError> {
+        if check_six_axis_cluster(&sensors).is_err() {
+            check_nine_axis_cluster(&sensors)
+                .map_err(|e| GamepadError::InvalidCluster(e.to_string()))?;
+        }
+        let publishers = PublisherManager::try_new(&sensors)
+            .map_err(|e| GamepadError::DuplicatedSensor(format!("{} in {}", e, tag)))?;
+        Ok(Self {
+            tag: tag.to_string(),
+            sensors,
+            publishers,
+            parser: Mutex::new(ReportParser::new(model)),
+            is_stopped: AtomicBool::new(false),
+            shutdown: ShutdownToken::global(),
+            dropped_reports: AtomicUsize::new(0),
+        })
+    }
+
+    /// Stops the source when `token` is shut down, instead of the global token.
+    pub fn with_shutdown_token(mut self, token: ShutdownToken) -> Self {
+        self.shutdown = token;
+        self
+    }
+
+    pub fn get_model(&self) -> ControllerModel {
+        self.parser.lock().unwrap().get_model()
+    }
+
+    /// Returns the number of reports dropped because they were malformed or had no motion data.
+    pub fn get_dropped_reports(&self) -> usize {
+        self.dropped_reports.load(Ordering::Relaxed)
+    }
+
+    /// Reads and publishes readings from `reader` until it ends, `stop` is called or the
+    /// shutdown token is shut down. Read timeouts are retried, so `reader` should time out
+    /// regularly for the source to notice it was stopped.
+    /// Returns a Device error if reading fails.
+    pub fn start<R: ReportReader>(&self, reader: R) -> Result<(), GamepadError> {
+        self.read_while(reader, || true)
+    }
+
+    /// Same as `start`, also returning once `is_running` returns false.
+    pub(crate) fn read_while<R, F>(&self, mut reader: R, is_running: F) -> Result<(), GamepadError>
+    where
+        R: ReportReader,
+        F: Fn() -> bool,
+    {
+        while !self.is_stopped.load(Ordering::Relaxed)
+            && !self.shutdown.is_shutdown()
+            && is_running()
+        {
+            match reader.read_report() {
+                Ok(report) => self.process_report(&report),
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => return Err(GamepadError::Device(e.to_string())),
+            }
+        }
+        Ok(())
+    }
+
+    /// Stops `start`. If it isn't running yet, it returns as soon as it is called.
+    pub fn stop(&self) {
+        self.is_stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Decodes `report`, received now, and publishes its readings.
+    pub fn process_report(&self, report: &[u8]) {
+        self.process_report_at(report, Clock::now().as_secs());
+    }
+
+    /// Decodes `report`, received at `received_secs`, and publishes its readings.
+    pub fn process_report_at(&self, report: &[u8], received_secs: f64) {
+        let frames = match self.parser.lock().unwrap().parse(report, received_secs) {
+            Ok(frames) => frames,
+            Err(e) => {
+                warn!("Dropping report received by {}: {}", self.tag, e);
+                self.dropped_reports.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+        for sensor_type in self.sensors.iter() {
+            let reading = |frame: &MotionFrame| match sensor_type {
+                SensorType::Accelerometer(_) => frame.accel,
+                SensorType::Gyroscope(_) => frame.gyro,
+                _ => [0.0; 3],
+            };
+            let samples = frames
+                .iter()
+                .map(|frame| Sample3D::new(frame.timestamp_secs, reading(frame)))
+                .collect();
+            let readings = SensorReadings::from_vec(&self.tag, sensor_type.clone(), samples);
+            self.publishers
+                .notify_listeners(sensor_type.clone(), Arc::new(readings));
+        }
+    }
+}
+
+impl IMUSource<SensorReadings<Sample3D>, Sample3D> for GamepadImuSource {
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.sensors.clone()
+    }
+
+    fn get_tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<Sample3D>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<Sample3D>>) {
+        self.publishers.notify_listeners(sensor_type, data);
+    }
+}
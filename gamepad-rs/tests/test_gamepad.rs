@@ -0,0 +1,90 @@
+use publisher::Listener;
+use std::sync::{Arc, Mutex};
+
+use gamepad_rs::{ControllerModel, GamepadError, GamepadImuSource};
+use imu_common::traits::{IMUReadings, IMUSample, IMUSource};
+use imu_common::types::sensors::{SensorClusterBuilder, SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+
+type Batches = Arc<Mutex<Vec<Vec<Sample3D>>>>;
+
+fn collect(
+    source: &GamepadImuSource,
+    sensor_type: &SensorType,
+) -> (Listener<SensorReadings<Sample3D>>, Batches) {
+    let batches: Batches = Arc::new(Mutex::new(Vec::new()));
+    let mut listener = Listener::new({
+        let batches = batches.clone();
+        move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+            batches.lock().unwrap().push(readings.get_samples());
+        }
+    });
+    source
+        .register_listener(&mut listener, sensor_type)
+        .unwrap();
+    (listener, batches)
+}
+
+/// DualShock 4 USB report of a controller lying flat, turning at 90 °/s around y.
+fn dualshock4_report(ticks: u16) -> Vec<u8> {
+    let mut report = vec![0u8; 64];
+    report[0] = 0x01;
+    report[10..12].copy_from_slice(&ticks.to_le_bytes());
+    report[15..17].copy_from_slice(&(16i16 * 90).to_le_bytes());
+    report[21..23].copy_from_slice(&8192i16.to_le_bytes());
+    report
+}
+
+#[test]
+fn test_read_dualshock4() {
+    let sensors = SensorType::cluster_for_tag("test_read_dualshock4");
+    let source =
+        GamepadImuSource::new("DS4", sensors.clone(), ControllerModel::DualShock4).unwrap();
+    let (_acc_listener, acc) = collect(&source, &sensors[0]);
+    let (_gyro_listener, gyro) = collect(&source, &sensors[1]);
+    let (_mag_listener, mag) = collect(&source, &sensors[2]);
+
+    // 234 ticks of 5.33 µs between readings
+    let reports = vec![
+        dualshock4_report(1000),
+        vec![0x01, 0x80],
+        dualshock4_report(1234),
+    ];
+    source.start(reports.into_iter()).unwrap();
+
+    let acc: Vec<Sample3D> = acc.lock().unwrap().iter().flatten().cloned().collect();
+    assert_eq!(acc.len(), 2);
+    assert!((acc[0].get_measurement().inner()[1] - 9.80665).abs() < 1e-9);
+    let period = acc[1].get_timestamp_secs() - acc[0].get_timestamp_secs();
+    assert!((period - 0.001248).abs() < 1e-6);
+
+    let gyro = gyro.lock().unwrap();
+    assert!((gyro[0][0].get_measurement().inner()[1] - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+    // controllers have no magnetometer
+    assert_eq!(
+        mag.lock().unwrap()[1][0].get_measurement().inner(),
+        [0.0; 3]
+    );
+    assert_eq!(source.get_dropped_reports(), 1);
+}
+
+#[test]
+fn test_invalid_source() {
+    let sensors = SensorClusterBuilder::new()
+        .with_tag("test_gamepad_invalid_source")
+        .accelerometer()
+        .build()
+        .unwrap();
+    let result = GamepadImuSource::new("Pad", sensors, ControllerModel::SwitchPro);
+    assert!(matches!(result, Err(GamepadError::InvalidCluster(_))));
+
+    let sensors = SensorClusterBuilder::new()
+        .with_tag("test_gamepad_invalid_source")
+        .six_axis()
+        .build()
+        .unwrap();
+    let _source =
+        GamepadImuSource::new("Pad", sensors.clone(), ControllerModel::SwitchPro).unwrap();
+    let result = GamepadImuSource::new("Pad", sensors, ControllerModel::SwitchPro);
+    assert!(matches!(result, Err(GamepadError::DuplicatedSensor(_))));
+}
@@ -0,0 +1,30 @@
+//! Module errors
+
+/// Errors of the host sensor source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostError {
+    /// Error opening or reading the sensor.
+    Device(String),
+
+    /// Error indicating that the host has no supported accelerometer.
+    NotFound(String),
+
+    /// Error indicating that a sensor is already published by another source.
+    DuplicatedSensor(String),
+
+    /// Error indicating that the sensors aren't a single accelerometer.
+    InvalidCluster(String),
+}
+
+impl std::fmt::Display for HostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostError::Device(e) => write!(f, "Device error: {}", e),
+            HostError::NotFound(e) => write!(f, "Accelerometer not found: {}", e),
+            HostError::DuplicatedSensor(e) => write!(f, "Duplicated sensor: {}", e),
+            HostError::InvalidCluster(e) => write!(f, "Invalid cluster: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for HostError {}
@@ -0,0 +1,239 @@
+//! Module iio
+//!
+//! Accelerometers of the Linux Industrial I/O subsystem. Every IIO device is a directory in
+//! `/sys/bus/iio/devices`, and accelerometers expose their axes as:
+//!
+//! - `in_accel_{x,y,z}_raw`: raw reading of every axis.
+//! - `in_accel_scale` or `in_accel_{x,y,z}_scale`: m/s² per unit of the raw reading.
+//! - `in_accel_offset`: optional offset added to the raw reading before scaling.
+//! - `in_accel_mount_matrix` or `mount_matrix`: optional rotation from the sensor axes to the
+//!   axes of the device.
+//!
+//! Readings are `(raw + offset) * scale`, rotated by the mount matrix.
+
+use log::warn;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::errors::HostError;
+use crate::source::AccelerometerReader;
+
+/// Directory of the IIO devices.
+pub const IIO_DEVICES_PATH: &str = "/sys/bus/iio/devices";
+
+const AXES: [&str; 3] = ["x", "y", "z"];
+
+/// Accelerometer of the Linux Industrial I/O subsystem.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IioAccelerometer {
+    path: PathBuf,
+    name: String,
+    scale: [f64; 3],
+    offset: f64,
+    mount_matrix: [[f64; 3]; 3],
+}
+
+impl IioAccelerometer {
+    /// Opens the IIO device at `path`.
+    /// Returns a Device error if it isn't an accelerometer or its attributes can't be read.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, HostError> {
+        let path = path.as_ref().to_path_buf();
+        let device_error = |e: String| HostError::Device(format!("{}: {}", path.display(), e));
+        if !path.join("in_accel_x_raw").exists() {
+            return Err(device_error("Not an accelerometer".to_string()));
+        }
+        let name = read_attribute(&path, "name").unwrap_or_default();
+
+        let mut scale = [0.0; 3];
+        for (axis, scale) in AXES.iter().zip(scale.iter_mut()) {
+            *scale = read_number(&path, &format!("in_accel_{}_scale", axis))
+                .or_else(|_| read_number(&path, "in_accel_scale"))
+                .map_err(|e| device_error(e.to_string()))?;
+        }
+        let offset = read_number(&path, "in_accel_offset").unwrap_or(0.0);
+        let mount_matrix = ["in_accel_mount_matrix", "mount_matrix"]
+            .iter()
+            .find_map(|name| read_attribute(&path, name).ok())
+            .map(|matrix| parse_mount_matrix(&matrix).map_err(device_error))
+            .transpose()?
+            .unwrap_or([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+
+        Ok(Self {
+            path,
+            name,
+            scale,
+            offset,
+            mount_matrix,
+        })
+    }
+
+    /// Returns the accelerometers in `IIO_DEVICES_PATH`.
+    pub fn list() -> Vec<Self> {
+        Self::list_in(IIO_DEVICES_PATH)
+    }
+
+    /// Returns the accelerometers in `root`, sorted by path.
+    pub fn list_in<P: AsRef<Path>>(root: P) -> Vec<Self> {
+        let Ok(entries) = fs::read_dir(root) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.join("in_accel_x_raw").exists())
+            .collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .filter_map(|path| match Self::open(&path) {
+                Ok(accelerometer) => Some(accelerometer),
+                Err(e) => {
+                    warn!("Skipping IIO device: {}", e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the first accelerometer of the host, preferring the one in the display, which
+    /// follows the screen orientation.
+    /// Returns a NotFound error if the host has none.
+    pub fn discover() -> Result<Self, HostError> {
+        let mut accelerometers = Self::list();
+        if accelerometers.is_empty() {
+            return Err(HostError::NotFound(format!(
+                "No IIO accelerometer in {}",
+                IIO_DEVICES_PATH
+            )));
+        }
+        let index = accelerometers
+            .iter()
+            .position(|a| a.name.contains("display") || a.name.contains("lid"))
+            .unwrap_or(0);
+        Ok(accelerometers.swap_remove(index))
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the name of the driver, e.g. "cros-ec-accel".
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl AccelerometerReader for IioAccelerometer {
+    fn read(&mut self) -> std::io::Result<[f64; 3]> {
+        let mut reading = [0.0; 3];
+        for (i, axis) in AXES.iter().enumerate() {
+            let raw = read_number(&self.path, &format!("in_accel_{}_raw", axis))?;
+            reading[i] = (raw + self.offset) * self.scale[i];
+        }
+        let m = &self.mount_matrix;
+        Ok([0, 1, 2].map(|row| (0..3).map(|col| m[row][col] * reading[col]).sum()))
+    }
+}
+
+fn read_attribute(path: &Path, name: &str) -> std::io::Result<String> {
+    fs::read_to_string(path.join(name)).map(|value| value.trim().to_string())
+}
+
+fn read_number(path: &Path, name: &str) -> std::io::Result<f64> {
+    read_attribute(path, name)?
+        .parse::<f64>()
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}: {}", name, e)))
+}
+
+/// Parses a mount matrix as written by the kernel: `"x1, y1, z1; x2, y2, z2; x3, y3, z3"`.
+fn parse_mount_matrix(matrix: &str) -> Result<[[f64; 3]; 3], String> {
+    let rows: Vec<Vec<f64>> = matrix
+        .split(';')
+        .map(|row| {
+            row.split(',')
+                .map(|v| v.trim().parse::<f64>().map_err(|e| e.to_string()))
+                .collect()
+        })
+        .collect::<Result<_, _>>()?;
+    if rows.len() != 3 || rows.iter().any(|row| row.len() != 3) {
+        return Err(format!("Invalid mount matrix {}", matrix));
+    }
+    Ok([0, 1, 2].map(|i| [rows[i][0], rows[i][1], rows[i][2]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a fake IIO device in a temporary directory.
+    fn fake_device(root: &Path, name: &str, attributes: &[(&str, &str)]) -> PathBuf {
+        let path = root.join(name);
+        fs::create_dir_all(&path).unwrap();
+        for (attribute, value) in attributes {
+            fs::write(path.join(attribute), format!("{}\n", value)).unwrap();
+        }
+        path
+    }
+
+    fn temp_root(test: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("host_rs_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        root
+    }
+
+    #[test]
+    fn test_read_accelerometer() {
+        let root = temp_root("read");
+        let path = fake_device(
+            &root,
+            "iio:device0",
+            &[
+                ("name", "accel_3d"),
+                ("in_accel_x_raw", "100"),
+                ("in_accel_y_raw", "-50"),
+                ("in_accel_z_raw", "980"),
+                ("in_accel_scale", "0.01"),
+                ("in_accel_offset", "10"),
+                ("in_accel_mount_matrix", "0, 1, 0; -1, 0, 0; 0, 0, 1"),
+            ],
+        );
+        let mut accelerometer = IioAccelerometer::open(&path).unwrap();
+        let reading = accelerometer.read().unwrap();
+        let expected = [-0.4, -1.1, 9.9];
+        for (value, expected) in reading.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        assert_eq!(accelerometer.get_name(), "accel_3d");
+
+        fs::write(path.join("in_accel_z_raw"), "x").unwrap();
+        assert!(accelerometer.read().is_err());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_list_accelerometers() {
+        let root = temp_root("list");
+        let axes = |scale: &'static str| {
+            vec![
+                ("in_accel_x_raw", "0"),
+                ("in_accel_y_raw", "0"),
+                ("in_accel_z_raw", "0"),
+                ("in_accel_x_scale", scale),
+                ("in_accel_y_scale", scale),
+                ("in_accel_z_scale", scale),
+            ]
+        };
+        fake_device(&root, "iio:device1", &axes("0.1"));
+        fake_device(&root, "iio:device0", &axes("0.2"));
+        // not an accelerometer, and accelerometer without scale
+        fake_device(&root, "iio:device2", &[("in_illuminance_raw", "10")]);
+        fake_device(&root, "iio:device3", &[("in_accel_x_raw", "0")]);
+
+        let accelerometers = IioAccelerometer::list_in(&root);
+        assert_eq!(accelerometers.len(), 2);
+        assert!(accelerometers[0].get_path().ends_with("iio:device0"));
+        assert!(IioAccelerometer::list_in(root.join("missing")).is_empty());
+        assert!(IioAccelerometer::open(root.join("iio:device2")).is_err());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,22 @@
+//! # Crate host-rs
+//!
+//! ## host-rs
+//!
+//! The `host-rs` crate publishes the accelerometer built into the host, such as the lid sensor
+//! of a laptop or the tilt sensor of a tablet. It needs no phone or board, so it is the
+//! quickest way to see readings flowing through the pipeline before setting up a real source.
+//!
+//! [`HostImuSource`] polls an [`AccelerometerReader`]:
+//! - [`IioAccelerometer`]: Linux Industrial I/O sensors, read from sysfs. Found on most
+//!   convertibles and Chromebooks, often as separate lid and base sensors.
+//!
+//! Windows and macOS hosts have no backend yet. Other sensors are supported by implementing
+//! [`AccelerometerReader`].
+
+pub mod errors;
+pub mod iio;
+mod source;
+
+pub use errors::HostError;
+pub use iio::IioAccelerometer;
+pub use source::{run_service, AccelerometerReader, HostImuSource, DEFAULT_RATE_HZ};
@@ -0,0 +1,200 @@
+use log::error;
+use publisher::{PublisherManager, ShutdownToken};
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::errors::HostError;
+use crate::iio::IioAccelerometer;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSource, Notifiable};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::{ClockSource, SystemClock};
+
+/// Rate the accelerometer is polled at by `run_service`.
+pub const DEFAULT_RATE_HZ: f64 = 50.0;
+
+/// Accelerometer of the host.
+pub trait AccelerometerReader: Send {
+    /// Returns the current acceleration in m/s². Errors of kind `TimedOut`, `WouldBlock` and
+    /// `Interrupted` are retried, and `UnexpectedEof` ends the source.
+    fn read(&mut self) -> std::io::Result<[f64; 3]>;
+}
+
+/// Replays recorded readings.
+impl AccelerometerReader for std::vec::IntoIter<[f64; 3]> {
+    fn read(&mut self) -> std::io::Result<[f64; 3]> {
+        self.next()
+            .ok_or_else(|| std::io::Error::from(ErrorKind::UnexpectedEof))
+    }
+}
+
+/// Source publishing the accelerometer of the host, polled at a fixed rate.
+///
+/// Readings are stamped with the time they are read. Failed reads are counted and skipped,
+/// as sensors can be briefly unavailable, e.g. while the lid of a laptop is folded.
+pub struct HostImuSource {
+    tag: String,
+    sensors: Vec<SensorType>,
+    publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+    clock: Arc<dyn ClockSource>,
+    is_stopped: AtomicBool,
+    shutdown: ShutdownToken,
+    failed_reads: AtomicUsize,
+}
+
+impl HostImuSource {
+    /// Creates a source publishing the accelerometer as `sensors`.
+    /// Returns an InvalidCluster error if `sensors` isn't a single accelerometer, and
+    /// DuplicatedSensor if it is already published by another source.
+    pub fn new(tag: &str, sensors: Vec<SensorType>) -> Result<Self, HostError> {
+        if !matches!(sensors.as_slice(), [SensorType::Accelerometer(_)]) {
+            return Err(HostError::InvalidCluster(format!(
+                "Expected a single accelerometer in {}",
+                tag
+            )));
+        }
+        let publishers = PublisherManager::try_new(&sensors)
+            .map_err(|e| HostError::DuplicatedSensor(format!("{} in {}", e, tag)))?;
+        Ok(Self {
+            tag: tag.to_string(),
+            sensors,
+            publishers,
+            clock: Arc::new(SystemClock),
+            is_stopped: AtomicBool::new(false),
+            shutdown: ShutdownToken::global(),
+            failed_reads: AtomicUsize::new(0),
+        })
+    }
+
+    /// Stamps readings with the time of `clock`, instead of the wall clock.
+    pub fn with_clock(mut self, clock: Arc<dyn ClockSource>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Stops the source when `token` is shut down, instead of the global token.
+    pub fn with_shutdown_token(mut self, token: ShutdownToken) -> Self {
+        self.shutdown = token;
+        self
+    }
+
+    /// Returns the number of reads that failed.
+    pub fn get_failed_reads(&self) -> usize {
+        self.failed_reads.load(Ordering::Relaxed)
+    }
+
+    /// Polls and publishes readings from `reader` every `period` until it ends, `stop` is called
+    /// or the shutdown token is shut down.
+    pub fn start<R: AccelerometerReader>(&self, reader: R, period: Duration) {
+        self.read_while(reader, period, || true)
+    }
+
+    /// Same as `start`, also returning once `is_running` returns false.
+    pub(crate) fn read_while<R, F>(&self, mut reader: R, period: Duration, is_running: F)
+    where
+        R: AccelerometerReader,
+        F: Fn() -> bool,
+    {
+        let mut next_read = Instant::now();
+        while !self.is_stopped.load(Ordering::Relaxed)
+            && !self.shutdown.is_shutdown()
+            && is_running()
+        {
+            match reader.read() {
+                Ok(reading) => self.publish(reading),
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => {
+                    if self.failed_reads.fetch_add(1, Ordering::Relaxed) == 0 {
+                        error!("Error reading the accelerometer of {}: {}", self.tag, e);
+                    }
+                }
+            }
+            next_read += period;
+            let now = Instant::now();
+            if next_read > now {
+                std::thread::sleep(next_read - now);
+            } else {
+                // fell behind, don't try to catch up
+                next_read = now;
+            }
+        }
+    }
+
+    /// Stops `start`. If it isn't running yet, it returns as soon as it is called.
+    pub fn stop(&self) {
+        self.is_stopped.store(true, Ordering::Relaxed);
+    }
+
+    fn publish(&self, reading: [f64; 3]) {
+        let sensor_type = self.sensors[0].clone();
+        let sample = Sample3D::new(self.clock.now_secs(), reading);
+        let readings = SensorReadings::from_vec(&self.tag, sensor_type.clone(), vec![sample]);
+        self.publishers
+            .notify_listeners(sensor_type, Arc::new(readings));
+    }
+}
+
+impl IMUSource<SensorReadings<Sample3D>, Sample3D> for HostImuSource {
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.sensors.clone()
+    }
+
+    fn get_tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<Sample3D>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<Sample3D>>) {
+        self.publishers.notify_listeners(sensor_type, data);
+    }
+}
+
+/// Starts a source polling the accelerometer of the host at `rate_hz` in a background thread.
+///
+/// Returns a NotFound error if the host has no supported accelerometer, InvalidCluster if
+/// `sensors` isn't a single accelerometer, and DuplicatedSensor if it collides with the one of
+/// a running source.
+///
+/// # Returns
+///
+/// Returns a tuple containing:
+/// * A `std::thread::JoinHandle<()>` of the thread, which ends when `HostImuSource::stop` is
+///   called, the source is dropped or the global `ShutdownToken` is shut down.
+/// * An `Arc<HostImuSource>` to register listeners and stop the source.
+pub fn run_service(
+    tag: &str,
+    sensors: Vec<SensorType>,
+    rate_hz: f64,
+) -> Result<(std::thread::JoinHandle<()>, Arc<HostImuSource>), HostError> {
+    let source = Arc::new(HostImuSource::new(tag, sensors)?);
+    let accelerometer = IioAccelerometer::discover()?;
+    let period = Duration::from_secs_f64(1.0 / rate_hz);
+    let handle = std::thread::spawn({
+        let source = source.clone();
+        move || {
+            // stop once the caller drops the source
+            source.read_while(accelerometer, period, || Arc::strong_count(&source) > 1);
+        }
+    });
+    Ok((handle, source))
+}
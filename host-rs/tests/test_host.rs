@@ -0,0 +1,115 @@
+use publisher::{Listener, ShutdownToken};
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use host_rs::{AccelerometerReader, HostError, HostImuSource};
+use imu_common::traits::{IMUReadings, IMUSample, IMUSource};
+use imu_common::types::sensors::{SensorClusterBuilder, SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::VirtualClock;
+
+fn collect(
+    source: &HostImuSource,
+    sensor_type: &SensorType,
+) -> (
+    Listener<SensorReadings<Sample3D>>,
+    Arc<Mutex<Vec<Sample3D>>>,
+) {
+    let samples: Arc<Mutex<Vec<Sample3D>>> = Arc::new(Mutex::new(Vec::new()));
+    let mut listener = Listener::new({
+        let samples = samples.clone();
+        move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+            samples.lock().unwrap().extend(readings.get_samples());
+        }
+    });
+    source
+        .register_listener(&mut listener, sensor_type)
+        .unwrap();
+    (listener, samples)
+}
+
+/// Reader failing every other read.
+struct FlakyReader(usize);
+
+impl AccelerometerReader for FlakyReader {
+    fn read(&mut self) -> std::io::Result<[f64; 3]> {
+        self.0 += 1;
+        match self.0 {
+            n if n > 6 => Err(Error::from(ErrorKind::UnexpectedEof)),
+            n if n % 2 == 0 => Err(Error::other("Sensor suspended")),
+            n => Ok([0.0, n as f64, 9.8]),
+        }
+    }
+}
+
+#[test]
+fn test_poll_accelerometer() {
+    let sensors = SensorType::cluster_for_tag("test_host_poll")[..1].to_vec();
+    let clock = VirtualClock::new(100.0);
+    let source = HostImuSource::new("Laptop", sensors.clone())
+        .unwrap()
+        .with_clock(Arc::new(clock));
+    let (_listener, samples) = collect(&source, &sensors[0]);
+
+    let readings = vec![[0.0, 0.0, 9.8], [0.1, 0.0, 9.8], [0.2, 0.0, 9.8]];
+    source.start(readings.into_iter(), Duration::from_millis(1));
+
+    let samples = samples.lock().unwrap();
+    assert_eq!(samples.len(), 3);
+    assert_eq!(samples[2].get_measurement().inner(), [0.2, 0.0, 9.8]);
+    assert_eq!(samples[0].get_timestamp_secs(), 100.0);
+}
+
+#[test]
+fn test_skip_failed_reads() {
+    let sensors = SensorClusterBuilder::new().accelerometer().build().unwrap();
+    let source = HostImuSource::new("Laptop", sensors.clone()).unwrap();
+    let (_listener, samples) = collect(&source, &sensors[0]);
+
+    source.start(FlakyReader(0), Duration::from_millis(1));
+
+    assert_eq!(samples.lock().unwrap().len(), 3);
+    assert_eq!(source.get_failed_reads(), 3);
+}
+
+#[test]
+fn test_invalid_cluster() {
+    let sensors = SensorClusterBuilder::new().six_axis().build().unwrap();
+    assert!(matches!(
+        HostImuSource::new("Laptop", sensors),
+        Err(HostError::InvalidCluster(_))
+    ));
+    let sensors = SensorClusterBuilder::new().gyroscope().build().unwrap();
+    assert!(matches!(
+        HostImuSource::new("Laptop", sensors),
+        Err(HostError::InvalidCluster(_))
+    ));
+}
+
+#[test]
+fn test_stop_on_shutdown() {
+    let token = ShutdownToken::new();
+    let sensors = SensorType::cluster_for_tag("test_host_stop_on_shutdown")[..1].to_vec();
+    let source = Arc::new(
+        HostImuSource::new("Laptop", sensors)
+            .unwrap()
+            .with_shutdown_token(token.clone()),
+    );
+    let handle = std::thread::spawn({
+        let source = source.clone();
+        move || {
+            source.start(
+                std::iter::repeat_n([0.0; 3], 1_000_000)
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+                Duration::from_millis(1),
+            )
+        }
+    });
+
+    std::thread::sleep(Duration::from_millis(20));
+    token.shutdown();
+
+    handle.join().unwrap();
+}
@@ -0,0 +1,60 @@
+//! Module errors
+
+use alloc::string::String;
+
+/// Errors of the sample types, filters and pipeline nodes shared by all the crates.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ImuError {
+    /// Error indicating that a filter was called without samples.
+    #[error("No samples to filter")]
+    EmptyInput,
+
+    /// Error indicating that the samples given can't be processed.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// Error indicating that a filter or node parameter is out of range.
+    #[error("Invalid parameter: {0}")]
+    InvalidParameter(String),
+
+    /// Error indicating that no publisher exists for a sensor type.
+    #[error("Unknown publisher")]
+    UnknownPublisher,
+
+    /// Error indicating that no listener is registered with an id.
+    #[error("Unknown listener")]
+    UnknownListener,
+
+    /// Error indicating that a publisher already exists in the manager.
+    #[error("Publisher already exists")]
+    DuplicatedPublisher,
+
+    /// Error indicating that a publisher type is owned by another manager.
+    #[error("Publisher type already registered by another manager")]
+    PublisherCollision,
+
+    /// Error indicating that a sensor cluster is empty, repeats a sensor, or misses a sensor.
+    #[error("Invalid sensor cluster: {0}")]
+    InvalidSensorCluster(String),
+
+    /// Error indicating that the readings of a source don't meet the requirements of a sink.
+    #[error("Incompatible sensor: {0}")]
+    IncompatibleSensor(String),
+
+    /// Error reading or writing persisted state, such as an identity store.
+    #[error("Io error: {0}")]
+    Io(String),
+
+    /// Any other error, e.g. of the transport of a source or sink.
+    #[error("{0}")]
+    Other(String),
+}
+
+impl ImuError {
+    /// Returns whether the error only concerns the samples given, e.g. an empty batch, so the
+    /// caller can skip them and keep going. Other errors, such as invalid parameters, come from
+    /// the configuration and fail every call the same way.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self, ImuError::EmptyInput | ImuError::InvalidInput(_))
+    }
+}
@@ -1,4 +1,17 @@
 //! General functionality for `imu-rs` library
+//!
+//! Without the `std` feature, the crate is `#![no_std]` and only needs `alloc`: the sample
+//! types, buffers and filters can then be used in the firmware producing the streams.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+// tests run on the host, so they may use std, and its float methods, without the `std` feature
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
+
+pub mod errors;
+#[cfg(any(feature = "proto", test))]
+pub mod proto;
 
 #[doc(hidden)]
 pub mod traits;
@@ -0,0 +1,121 @@
+// This file is @generated by prost-build.
+/// 3D sample, e.g. of an accelerometer, gyroscope or magnetometer.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Sample3D {
+    #[prost(double, tag = "1")]
+    pub timestamp: f64,
+    #[prost(double, tag = "2")]
+    pub x: f64,
+    #[prost(double, tag = "3")]
+    pub y: f64,
+    #[prost(double, tag = "4")]
+    pub z: f64,
+}
+/// Orientation sample, as a unit quaternion.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct SampleQuaternion {
+    #[prost(double, tag = "1")]
+    pub timestamp: f64,
+    #[prost(double, tag = "2")]
+    pub w: f64,
+    #[prost(double, tag = "3")]
+    pub i: f64,
+    #[prost(double, tag = "4")]
+    pub j: f64,
+    #[prost(double, tag = "5")]
+    pub k: f64,
+}
+/// Sample of a scalar sensor, e.g. pressure or light.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct SampleScalar {
+    #[prost(double, tag = "1")]
+    pub timestamp: f64,
+    #[prost(double, tag = "2")]
+    pub value: f64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SensorType {
+    #[prost(enumeration = "SensorKind", tag = "1")]
+    pub kind: i32,
+    /// Uuid of the sensor, hyphenated.
+    #[prost(string, tag = "2")]
+    pub id: ::prost::alloc::string::String,
+    /// Name of `OTHER` sensors, and `namespace/kind` of `VENDOR` sensors. Empty otherwise.
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Samples3D {
+    #[prost(message, repeated, tag = "1")]
+    pub samples: ::prost::alloc::vec::Vec<Sample3D>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SamplesQuaternion {
+    #[prost(message, repeated, tag = "1")]
+    pub samples: ::prost::alloc::vec::Vec<SampleQuaternion>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SamplesScalar {
+    #[prost(message, repeated, tag = "1")]
+    pub samples: ::prost::alloc::vec::Vec<SampleScalar>,
+}
+/// Readings of a sensor, with the tag of the device.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SensorReadings {
+    #[prost(string, tag = "1")]
+    pub tag: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub sensor_type: ::core::option::Option<SensorType>,
+    #[prost(oneof = "sensor_readings::Samples", tags = "3, 4, 5")]
+    pub samples: ::core::option::Option<sensor_readings::Samples>,
+}
+/// Nested message and enum types in `SensorReadings`.
+pub mod sensor_readings {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Samples {
+        #[prost(message, tag = "3")]
+        Vectors(super::Samples3D),
+        #[prost(message, tag = "4")]
+        Quaternions(super::SamplesQuaternion),
+        #[prost(message, tag = "5")]
+        Scalars(super::SamplesScalar),
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SensorKind {
+    Unspecified = 0,
+    Accelerometer = 1,
+    Gyroscope = 2,
+    Magnetometer = 3,
+    Other = 4,
+    Vendor = 5,
+}
+impl SensorKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "SENSOR_KIND_UNSPECIFIED",
+            Self::Accelerometer => "SENSOR_KIND_ACCELEROMETER",
+            Self::Gyroscope => "SENSOR_KIND_GYROSCOPE",
+            Self::Magnetometer => "SENSOR_KIND_MAGNETOMETER",
+            Self::Other => "SENSOR_KIND_OTHER",
+            Self::Vendor => "SENSOR_KIND_VENDOR",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SENSOR_KIND_UNSPECIFIED" => Some(Self::Unspecified),
+            "SENSOR_KIND_ACCELEROMETER" => Some(Self::Accelerometer),
+            "SENSOR_KIND_GYROSCOPE" => Some(Self::Gyroscope),
+            "SENSOR_KIND_MAGNETOMETER" => Some(Self::Magnetometer),
+            "SENSOR_KIND_OTHER" => Some(Self::Other),
+            "SENSOR_KIND_VENDOR" => Some(Self::Vendor),
+            _ => None,
+        }
+    }
+}
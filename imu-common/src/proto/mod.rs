@@ -0,0 +1,294 @@
+//! Module proto
+//!
+//! Protobuf messages of the samples and readings, a stable wire format the transports can
+//! exchange with endpoints written in other languages. The schema is `proto/imu.proto`, and
+//! `imu.rs` is its prost-build output, checked in so that building doesn't need `protoc`.
+//!
+//! Samples convert to and from their messages with `From`. Readings, and the sensor types they
+//! carry, are checked when converted back, since a message may come from any endpoint.
+//!
+//! ```
+//! use imu_common::proto::{self, prost::Message};
+//! use imu_common::traits::IMUReadings;
+//! use imu_common::types::sensors::{SensorReadings, SensorType};
+//! use imu_common::types::timed::Sample3D;
+//! use uuid::Uuid;
+//!
+//! let readings = SensorReadings::from_vec(
+//!     "phone",
+//!     SensorType::Accelerometer(Uuid::new_v4()),
+//!     vec![Sample3D::new(0.5, [0.0, 0.0, 9.81])],
+//! );
+//! let bytes = proto::SensorReadings::from(readings.clone()).encode_to_vec();
+//!
+//! let message = proto::SensorReadings::decode(bytes.as_slice()).unwrap();
+//! let decoded = SensorReadings::<Sample3D>::try_from(message).unwrap();
+//! assert_eq!(decoded.get_samples(), readings.get_samples());
+//! ```
+
+mod imu;
+
+pub use imu::*;
+pub use prost;
+
+use crate::traits::Float;
+use crate::traits::IMUSample;
+use crate::types::timed;
+
+impl<F: Float> From<timed::Sample3D<F>> for Sample3D {
+    fn from(sample: timed::Sample3D<F>) -> Self {
+        let [x, y, z] = sample.get_measurement().inner().map(F::cast_to_f64);
+        Self {
+            timestamp: sample.get_timestamp_secs(),
+            x,
+            y,
+            z,
+        }
+    }
+}
+
+impl<F: Float> From<Sample3D> for timed::Sample3D<F> {
+    fn from(sample: Sample3D) -> Self {
+        let measurement = [sample.x, sample.y, sample.z].map(F::cast_from_f64);
+        timed::Sample3D::new(sample.timestamp, measurement)
+    }
+}
+
+impl From<timed::SampleQuaternion> for SampleQuaternion {
+    fn from(sample: timed::SampleQuaternion) -> Self {
+        let quaternion = sample.get_measurement().inner();
+        Self {
+            timestamp: sample.get_timestamp_secs(),
+            w: quaternion.w,
+            i: quaternion.i,
+            j: quaternion.j,
+            k: quaternion.k,
+        }
+    }
+}
+
+/// The quaternion is normalized, so messages of other endpoints don't need to be.
+impl From<SampleQuaternion> for timed::SampleQuaternion {
+    fn from(sample: SampleQuaternion) -> Self {
+        timed::SampleQuaternion::new(sample.timestamp, [sample.w, sample.i, sample.j, sample.k])
+    }
+}
+
+impl From<timed::SampleScalar> for SampleScalar {
+    fn from(sample: timed::SampleScalar) -> Self {
+        Self {
+            timestamp: sample.get_timestamp_secs(),
+            value: sample.get_measurement().inner(),
+        }
+    }
+}
+
+impl From<SampleScalar> for timed::SampleScalar {
+    fn from(sample: SampleScalar) -> Self {
+        timed::SampleScalar::new(sample.timestamp, sample.value)
+    }
+}
+
+#[cfg(feature = "std")]
+mod readings {
+    use uuid::Uuid;
+
+    use super::{sensor_readings::Samples, SensorKind};
+    use super::{Samples3D, SamplesQuaternion, SamplesScalar};
+    use crate::errors::ImuError;
+    use crate::traits::IMUReadings;
+    use crate::types::sensors::{self, VendorKind};
+    use crate::types::timed;
+
+    impl From<&sensors::SensorType> for super::SensorType {
+        fn from(sensor_type: &sensors::SensorType) -> Self {
+            let (kind, id, name) = match sensor_type {
+                sensors::SensorType::Accelerometer(id) => (SensorKind::Accelerometer, id, ""),
+                sensors::SensorType::Gyroscope(id) => (SensorKind::Gyroscope, id, ""),
+                sensors::SensorType::Magnetometer(id) => (SensorKind::Magnetometer, id, ""),
+                sensors::SensorType::Other(id, name) => (SensorKind::Other, id, name.as_str()),
+                sensors::SensorType::Vendor(id, kind) => (SensorKind::Vendor, id, kind.as_str()),
+            };
+            Self {
+                kind: kind.into(),
+                id: id.to_string(),
+                name: name.to_string(),
+            }
+        }
+    }
+
+    impl TryFrom<super::SensorType> for sensors::SensorType {
+        type Error = ImuError;
+
+        fn try_from(sensor_type: super::SensorType) -> Result<Self, Self::Error> {
+            let id = Uuid::parse_str(&sensor_type.id)
+                .map_err(|e| ImuError::InvalidInput(format!("Invalid sensor id: {}", e)))?;
+            match SensorKind::try_from(sensor_type.kind) {
+                Ok(SensorKind::Accelerometer) => Ok(Self::Accelerometer(id)),
+                Ok(SensorKind::Gyroscope) => Ok(Self::Gyroscope(id)),
+                Ok(SensorKind::Magnetometer) => Ok(Self::Magnetometer(id)),
+                Ok(SensorKind::Other) => Ok(Self::Other(id, sensor_type.name)),
+                Ok(SensorKind::Vendor) => VendorKind::try_from(sensor_type.name.as_str())
+                    .map(|kind| Self::Vendor(id, kind))
+                    .map_err(|e| ImuError::InvalidInput(e.to_string())),
+                _ => Err(ImuError::InvalidInput(format!(
+                    "Unknown sensor kind {}",
+                    sensor_type.kind
+                ))),
+            }
+        }
+    }
+
+    /// Conversions between the readings of `$sample` and the messages holding their samples as
+    /// `$variant`.
+    macro_rules! impl_readings {
+        ($sample:ty, $samples:ident, $variant:ident) => {
+            impl From<sensors::SensorReadings<$sample>> for super::SensorReadings {
+                fn from(readings: sensors::SensorReadings<$sample>) -> Self {
+                    Self {
+                        tag: readings.get_sensor_tag().to_string(),
+                        sensor_type: Some((&readings.get_sensor_type()).into()),
+                        samples: Some(Samples::$variant($samples {
+                            samples: readings
+                                .get_samples_ref()
+                                .iter()
+                                .cloned()
+                                .map(Into::into)
+                                .collect(),
+                        })),
+                    }
+                }
+            }
+
+            impl TryFrom<super::SensorReadings> for sensors::SensorReadings<$sample> {
+                type Error = ImuError;
+
+                /// Returns an error if the sensor type is missing or invalid, or if the message
+                /// holds samples of another kind.
+                fn try_from(readings: super::SensorReadings) -> Result<Self, Self::Error> {
+                    let sensor_type = readings
+                        .sensor_type
+                        .ok_or_else(|| ImuError::InvalidInput("Missing sensor type".to_string()))?
+                        .try_into()?;
+                    let Some(Samples::$variant(samples)) = readings.samples else {
+                        return Err(ImuError::InvalidInput(format!(
+                            "Readings don't hold {} samples",
+                            stringify!($variant)
+                        )));
+                    };
+                    let samples = samples.samples.into_iter().map(Into::into).collect();
+                    Ok(Self::from_vec(&readings.tag, sensor_type, samples))
+                }
+            }
+        };
+    }
+
+    impl_readings!(timed::Sample3D, Samples3D, Vectors);
+    impl_readings!(timed::SampleQuaternion, SamplesQuaternion, Quaternions);
+    impl_readings!(timed::SampleScalar, SamplesScalar, Scalars);
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::errors::ImuError;
+    use crate::traits::IMUReadings;
+    use crate::types::sensors::{self, SensorReadings, VendorKind};
+    use prost::Message;
+    use uuid::Uuid;
+
+    fn round_trip<T>(readings: SensorReadings<T>) -> SensorReadings<T>
+    where
+        T: IMUSample,
+        imu::SensorReadings: From<SensorReadings<T>>,
+        SensorReadings<T>: TryFrom<imu::SensorReadings, Error = ImuError>,
+    {
+        let bytes = imu::SensorReadings::from(readings).encode_to_vec();
+        let message = imu::SensorReadings::decode(bytes.as_slice()).unwrap();
+        SensorReadings::try_from(message).unwrap()
+    }
+
+    #[test]
+    fn test_readings_round_trip() {
+        let sensor_type = sensors::SensorType::Gyroscope(Uuid::new_v4());
+        let samples = vec![
+            timed::Sample3D::new(0.0, [1.0, 2.0, 3.0]),
+            timed::Sample3D::new(0.01, [4.0, 5.0, 6.0]),
+        ];
+        let decoded = round_trip(SensorReadings::from_vec(
+            "phone",
+            sensor_type.clone(),
+            samples.clone(),
+        ));
+        assert_eq!(decoded.get_sensor_tag(), "phone");
+        assert_eq!(decoded.get_sensor_type(), sensor_type);
+        assert_eq!(decoded.get_samples(), samples);
+
+        let samples = vec![timed::SampleScalar::new(0.5, 1013.25)];
+        let sensor_type = sensors::SensorType::Other(Uuid::new_v4(), "pressure".to_string());
+        let decoded = round_trip(SensorReadings::from_vec(
+            "phone",
+            sensor_type.clone(),
+            samples.clone(),
+        ));
+        assert_eq!(decoded.get_sensor_type(), sensor_type);
+        assert_eq!(decoded.get_samples(), samples);
+    }
+
+    #[test]
+    fn test_quaternion_is_normalized() {
+        let message = SampleQuaternion {
+            timestamp: 1.0,
+            w: 2.0,
+            i: 0.0,
+            j: 0.0,
+            k: 0.0,
+        };
+        let sample = timed::SampleQuaternion::from(message);
+        assert_eq!(
+            SampleQuaternion::from(sample),
+            SampleQuaternion { w: 1.0, ..message }
+        );
+    }
+
+    #[test]
+    fn test_sensor_types() {
+        let id = Uuid::new_v4();
+        let sensor_types = [
+            sensors::SensorType::Accelerometer(id),
+            sensors::SensorType::Magnetometer(id),
+            // other sensors aren't recognized by their name
+            sensors::SensorType::Other(id, "background_acc".to_string()),
+            sensors::SensorType::Vendor(id, VendorKind::new("acme", "barometer").unwrap()),
+        ];
+        for sensor_type in sensor_types {
+            let message = SensorType::from(&sensor_type);
+            assert_eq!(sensors::SensorType::try_from(message).unwrap(), sensor_type);
+        }
+
+        let unknown = SensorType {
+            kind: 42,
+            id: id.to_string(),
+            name: String::new(),
+        };
+        assert!(sensors::SensorType::try_from(unknown).is_err());
+    }
+
+    #[test]
+    fn test_wrong_samples() {
+        let readings = SensorReadings::from_vec(
+            "phone",
+            sensors::SensorType::Accelerometer(Uuid::new_v4()),
+            vec![timed::Sample3D::new(0.0, [1.0, 2.0, 3.0])],
+        );
+        let message = imu::SensorReadings::from(readings);
+        let result = SensorReadings::<timed::SampleScalar>::try_from(message.clone());
+        assert!(matches!(result, Err(ImuError::InvalidInput(_))));
+
+        let message = imu::SensorReadings {
+            sensor_type: None,
+            ..message
+        };
+        assert!(SensorReadings::<timed::Sample3D>::try_from(message).is_err());
+    }
+}
@@ -0,0 +1,33 @@
+use core::fmt::Debug;
+
+/// Floating point type of the measurements of a sample.
+///
+/// Samples default to `f64`. `f32` halves the memory and bandwidth of embedded or high-rate
+/// pipelines, at the cost of precision. Timestamps are `f64` whatever the type of the
+/// measurements, and filters keep computing their coefficients in `f64`.
+pub trait Float:
+    nalgebra::RealField + Copy + Default + Debug + PartialOrd + Send + Sync + 'static
+{
+    fn cast_from_f64(value: f64) -> Self;
+    fn cast_to_f64(self) -> f64;
+}
+
+impl Float for f64 {
+    fn cast_from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn cast_to_f64(self) -> f64 {
+        self
+    }
+}
+
+impl Float for f32 {
+    fn cast_from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn cast_to_f64(self) -> f64 {
+        self as f64
+    }
+}
@@ -1,9 +1,18 @@
-use std::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
-use std::sync::Arc;
-use uuid::Uuid;
+use alloc::vec::Vec;
+use core::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
 
+use crate::errors::ImuError;
+#[cfg(feature = "std")]
 use crate::traits::Notifiable;
+use crate::types::capabilities::SampleKind;
+#[cfg(feature = "std")]
+use crate::types::capabilities::{SensorCapability, SinkRequirements};
+#[cfg(feature = "std")]
 use crate::types::sensors::SensorType;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(feature = "std")]
+use uuid::Uuid;
 
 pub trait VecF64Convertible: Into<Vec<f64>> + TryFrom<Vec<f64>> + Sized {}
 impl<T: Into<Vec<f64>> + TryFrom<Vec<f64>> + Sized> VecF64Convertible for T {}
@@ -28,6 +37,8 @@ pub trait IMUUntimedSample: Send + Sync + Clone + Default + 'static {
 /// Timed sample from an IMU (Inertial Measurement Unit).
 pub trait IMUSample: Send + Sync + Clone + Default + 'static {
     type Untimed: IMUUntimedSample;
+    /// Kind of measurement carried by the sample.
+    const KIND: SampleKind;
 
     ///  Returns the timestamp of the sample.
     fn get_timestamp_secs(&self) -> f64;
@@ -37,13 +48,23 @@ pub trait IMUSample: Send + Sync + Clone + Default + 'static {
     fn from_measurement(timestamp: f64, measurement: Self::Untimed) -> Self;
 }
 
+#[cfg(feature = "std")]
 /// Collection of sensor readings from an IMU (Inertial Measurement Unit).
 pub trait IMUReadings<T: IMUSample>: Send + Sync + Clone {
     ///   Returns the sensor tag
     fn get_sensor_tag(&self) -> &str;
     fn get_sensor_type(&self) -> SensorType;
-    ///   Returns samples
-    fn get_samples(&self) -> Vec<T>;
+    ///   Returns a copy of the samples
+    fn get_samples(&self) -> Vec<T> {
+        self.get_samples_ref().to_vec()
+    }
+    ///   Borrows the samples. Listeners share the readings of a source through an `Arc`, so
+    ///   reading them this way doesn't copy them for every listener.
+    fn get_samples_ref(&self) -> &[T];
+    ///   Iterates over the samples, without copying them
+    fn iter(&self) -> core::slice::Iter<'_, T> {
+        self.get_samples_ref().iter()
+    }
     ///   Adds new samples
     fn extend(&mut self, elems: Vec<T>);
     ///   Creates new IMUReadings
@@ -56,10 +77,15 @@ pub trait IMUFilter<T>: Send + Sync
 where
     T: IMUSample,
 {
-    ///  Returns the resampled samples
-    fn filter_batch(&mut self, samples: Vec<T>) -> Result<Vec<T>, &str>;
+    /// Returns the filtered samples.
+    ///
+    /// Errors for which [`ImuError::is_recoverable`] holds, such as [`ImuError::EmptyInput`],
+    /// only reject the batch given, and the caller can skip it. Any other error comes from the
+    /// configuration of the filter, and repeats with every batch.
+    fn filter_batch(&mut self, samples: Vec<T>) -> Result<Vec<T>, ImuError>;
 }
 
+#[cfg(feature = "std")]
 pub trait IMUSource<T, S>: Send + Sync
 where
     T: Send + Sync + IMUReadings<S>,
@@ -72,10 +98,18 @@ where
         &self,
         listener: &mut dyn Notifiable<T>,
         sensor_type: &SensorType,
-    ) -> Result<Uuid, String>;
+    ) -> Result<Uuid, ImuError>;
     fn notify_listeners(&self, sensor_type: SensorType, data: Arc<T>);
+    /// Describes the readings published by every available sensor.
+    fn get_capabilities(&self) -> Vec<SensorCapability> {
+        self.get_available_sensors()
+            .into_iter()
+            .map(|sensor_type| SensorCapability::new(sensor_type, S::KIND))
+            .collect()
+    }
 }
 
+#[cfg(feature = "std")]
 pub trait IMUSink<T, S>: Send + Sync
 where
     T: Send + Sync + IMUReadings<S>,
@@ -85,10 +119,14 @@ where
         &self,
         source: &dyn IMUSource<T, S>,
         sensor_cluster: &[SensorType],
-    ) -> Result<Vec<Uuid>, String>;
+    ) -> Result<Vec<Uuid>, ImuError>;
     fn detach_listener(&self, source: &dyn IMUSource<T, S>, id: Uuid) {
         source.unregister_listener(id);
     }
 
     fn process_samples(&self, listener_id: Uuid, samples: Arc<T>);
+    /// Readings accepted by the sink. Checked against the source capabilities when attaching.
+    fn get_requirements(&self) -> SinkRequirements {
+        SinkRequirements::default()
+    }
 }
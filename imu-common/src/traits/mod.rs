@@ -1,8 +1,18 @@
+pub mod float;
 pub mod imu;
+#[cfg(feature = "std")]
 pub mod publisher;
+#[cfg(feature = "std")]
+pub mod tunable;
 
+pub use crate::traits::float::Float;
 pub use crate::traits::imu::{
-    BasicArithmetic, IMUFilter, IMUReadings, IMUSample, IMUSink, IMUSource, IMUUntimedSample,
+    BasicArithmetic, IMUFilter, IMUSample, IMUUntimedSample, VecF64Convertible,
 };
+#[cfg(feature = "std")]
+pub use crate::traits::imu::{IMUReadings, IMUSink, IMUSource};
 
+#[cfg(feature = "std")]
 pub use crate::traits::publisher::Notifiable;
+#[cfg(feature = "std")]
+pub use crate::traits::tunable::Tunable;
@@ -1,7 +1,12 @@
-use crate::types::Callback;
+use crate::types::{Callback, Liveness};
 use uuid::Uuid;
 
 pub trait Notifiable<T>: Sync + Send {
     fn get_callback(&self) -> Callback<T>;
     fn set_id(&mut self, id: Uuid);
+    /// Returns the liveness check of the listener. Publishers unregister the listener once the
+    /// check fails. Listeners without a check stay registered until explicitly unregistered.
+    fn get_liveness(&self) -> Option<Liveness> {
+        None
+    }
 }
@@ -0,0 +1,14 @@
+use crate::errors::ImuError;
+use crate::types::registry::SourceParams;
+
+/// Processing node whose parameters can be changed while it runs.
+///
+/// Parameters use the same named values as source factories. Setting parameters is atomic:
+/// if any parameter is unknown or invalid, an error is returned and nothing is changed.
+pub trait Tunable: Send + Sync {
+    /// Returns the current value of every tunable parameter.
+    fn get_parameters(&self) -> SourceParams;
+
+    /// Updates the parameters in `params`, leaving the rest unchanged.
+    fn set_parameters(&self, params: &SourceParams) -> Result<(), ImuError>;
+}
@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use alloc::{collections::VecDeque, vec, vec::Vec};
 /// A circular buffer type, where the buffer has a constant length of `size` elements. The buffer is filled with default samples
 /// or with some initial samples given to the constructor. When a new sample is pushed to the buffer, the oldest sample is popped out.
 ///
@@ -78,7 +78,7 @@ impl<T: Clone + Default> CircularBuffer<T> {
 
 impl<T> IntoIterator for CircularBuffer<T> {
     type Item = T;
-    type IntoIter = std::vec::IntoIter<T>;
+    type IntoIter = alloc::vec::IntoIter<T>;
 
     fn into_iter(self) -> Self::IntoIter {
         // Convert the VecDeque into a Vec and use IntoIter to iterate over it
@@ -88,7 +88,7 @@ impl<T> IntoIterator for CircularBuffer<T> {
 
 impl<'a, T> IntoIterator for &'a CircularBuffer<T> {
     type Item = &'a T;
-    type IntoIter = std::iter::Chain<std::slice::Iter<'a, T>, std::slice::Iter<'a, T>>;
+    type IntoIter = core::iter::Chain<core::slice::Iter<'a, T>, core::slice::Iter<'a, T>>;
 
     fn into_iter(self) -> Self::IntoIter {
         let (first, second) = self.buffer.as_slices();
@@ -1,3 +1,10 @@
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::error::Error;
+
 /// A circular buffer reader that allows cyclic reading of elements.
 ///
 /// # Type Parameters
@@ -29,9 +36,9 @@ pub struct CircularReader<T: Clone> {
 
 impl<T: Clone> CircularReader<T> {
     /// Creates a new CircularReader with preloaded data.
-    pub fn new(data: Vec<T>) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(data: Vec<T>) -> Result<Self, Box<dyn Error>> {
         if data.is_empty() {
-            return Err(Box::<dyn std::error::Error>::from("Buffer cannot be empty"));
+            return Err(Box::<dyn Error>::from("Buffer cannot be empty"));
         }
         Ok(Self {
             buffer: data,
@@ -56,6 +63,7 @@ impl<T: Clone> TryFrom<Vec<T>> for CircularReader<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn test_init() {
@@ -2,3 +2,6 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 pub type Callback<T> = Arc<dyn Fn(Uuid, Arc<T>) + Send + Sync>;
+
+/// Tells whether a listener is still alive, i.e. whether its callback should keep being called.
+pub type Liveness = Arc<dyn Fn() -> bool + Send + Sync>;
@@ -0,0 +1,239 @@
+//! Module capabilities
+//!
+//! Lightweight handshake between sources and sinks. A source describes the readings of each
+//! sensor with a [`SensorCapability`] (sample kind, unit and nominal rate), and a sink declares
+//! what it accepts with [`SinkRequirements`]. [`negotiate`] is run when a sink is attached, so
+//! mismatches fail with a clear error instead of producing wrong results at runtime.
+
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::fmt;
+
+#[cfg(feature = "std")]
+use crate::errors::ImuError;
+#[cfg(feature = "std")]
+use crate::types::sensors::SensorType;
+
+/// Kind of measurement carried by a sample.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SampleKind {
+    Scalar,
+    Vector3D,
+    /// Accelerometer and gyroscope readings in a single sample.
+    Vector6D,
+    Quaternion,
+    /// Roll, pitch and yaw angles.
+    Euler,
+}
+
+#[cfg(feature = "std")]
+/// Physical unit of the readings.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Unit {
+    MetersPerSecondSquared,
+    RadiansPerSecond,
+    MicroTesla,
+    Celsius,
+    Unitless,
+    Other(String),
+}
+
+#[cfg(feature = "std")]
+impl Unit {
+    /// Returns the unit used by this library for each sensor kind.
+    pub fn default_for(sensor_type: &SensorType) -> Self {
+        match sensor_type {
+            SensorType::Accelerometer(_) => Unit::MetersPerSecondSquared,
+            SensorType::Gyroscope(_) => Unit::RadiansPerSecond,
+            SensorType::Magnetometer(_) => Unit::MicroTesla,
+            SensorType::Other(_, _) | SensorType::Vendor(_, _) => Unit::Unitless,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::MetersPerSecondSquared => write!(f, "m/s^2"),
+            Unit::RadiansPerSecond => write!(f, "rad/s"),
+            Unit::MicroTesla => write!(f, "uT"),
+            Unit::Celsius => write!(f, "C"),
+            Unit::Unitless => write!(f, "unitless"),
+            Unit::Other(unit) => write!(f, "{}", unit),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// Description of the readings published by a sensor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensorCapability {
+    pub sensor_type: SensorType,
+    pub sample_kind: SampleKind,
+    pub unit: Unit,
+    /// Nominal sample rate, if known.
+    pub nominal_rate_hz: Option<f64>,
+}
+
+#[cfg(feature = "std")]
+impl SensorCapability {
+    /// Creates a capability with the default unit of `sensor_type` and unknown rate.
+    pub fn new(sensor_type: SensorType, sample_kind: SampleKind) -> Self {
+        Self {
+            unit: Unit::default_for(&sensor_type),
+            sensor_type,
+            sample_kind,
+            nominal_rate_hz: None,
+        }
+    }
+
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    pub fn with_nominal_rate(mut self, rate_hz: f64) -> Self {
+        self.nominal_rate_hz = Some(rate_hz);
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+/// Readings accepted by a sink. The default accepts anything.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SinkRequirements {
+    sample_kinds: Vec<SampleKind>,
+    sensor_kinds: Vec<String>,
+    units: HashMap<String, Unit>,
+    min_rate_hz: Option<f64>,
+}
+
+#[cfg(feature = "std")]
+impl SinkRequirements {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accepts samples of `sample_kind`. If never called, every sample kind is accepted.
+    pub fn with_sample_kind(mut self, sample_kind: SampleKind) -> Self {
+        self.sample_kinds.push(sample_kind);
+        self
+    }
+
+    /// Accepts sensors of `kind` (see `SensorType::kind`). If never called, every sensor is accepted.
+    pub fn with_sensor_kind(mut self, kind: &str) -> Self {
+        self.sensor_kinds.push(kind.to_lowercase());
+        self
+    }
+
+    /// Requires readings from sensors of `kind` to be expressed in `unit`.
+    pub fn with_unit(mut self, kind: &str, unit: Unit) -> Self {
+        self.units.insert(kind.to_lowercase(), unit);
+        self
+    }
+
+    /// Requires a nominal rate of at least `rate_hz`. Sensors with unknown rate are accepted.
+    pub fn with_min_rate(mut self, rate_hz: f64) -> Self {
+        self.min_rate_hz = Some(rate_hz);
+        self
+    }
+
+    /// Checks a single sensor capability against the requirements.
+    pub fn check(&self, capability: &SensorCapability) -> Result<(), ImuError> {
+        let sensor = &capability.sensor_type;
+        let kind = sensor.kind().to_lowercase();
+        if !self.sample_kinds.is_empty() && !self.sample_kinds.contains(&capability.sample_kind) {
+            return Err(ImuError::IncompatibleSensor(format!(
+                "Sensor {} publishes {:?} samples. Expected one of {:?}",
+                sensor, capability.sample_kind, self.sample_kinds
+            )));
+        }
+        if !self.sensor_kinds.is_empty() && !self.sensor_kinds.contains(&kind) {
+            return Err(ImuError::IncompatibleSensor(format!(
+                "Sensor {} not supported. Expected one of {:?}",
+                sensor, self.sensor_kinds
+            )));
+        }
+        if let Some(unit) = self.units.get(&kind) {
+            if *unit != capability.unit {
+                return Err(ImuError::IncompatibleSensor(format!(
+                    "Sensor {} publishes readings in {}. Expected {}",
+                    sensor, capability.unit, unit
+                )));
+            }
+        }
+        if let (Some(min_rate), Some(rate)) = (self.min_rate_hz, capability.nominal_rate_hz) {
+            if rate < min_rate {
+                return Err(ImuError::IncompatibleSensor(format!(
+                    "Sensor {} rate is {} Hz. Expected at least {} Hz",
+                    sensor, rate, min_rate
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+/// Checks that every sensor in `sensor_cluster` is offered in `capabilities` and meets `requirements`.
+pub fn negotiate(
+    capabilities: &[SensorCapability],
+    requirements: &SinkRequirements,
+    sensor_cluster: &[SensorType],
+) -> Result<(), ImuError> {
+    for sensor_type in sensor_cluster {
+        let capability = capabilities
+            .iter()
+            .find(|c| c.sensor_type == *sensor_type)
+            .ok_or_else(|| {
+                ImuError::IncompatibleSensor(format!(
+                    "Sensor {} not available in source",
+                    sensor_type
+                ))
+            })?;
+        requirements.check(capability)?;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_default_requirements_accept_anything() {
+        let other = SensorType::Other(Uuid::new_v4(), "Temperature".to_string());
+        let capability = SensorCapability::new(other.clone(), SampleKind::Scalar);
+
+        assert!(negotiate(&[capability], &SinkRequirements::default(), &[other]).is_ok());
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let cluster = [acc.clone()];
+        let capabilities = vec![SensorCapability::new(acc, SampleKind::Vector3D)
+            .with_unit(Unit::Other("g".to_string()))
+            .with_nominal_rate(50.0)];
+
+        let quaternion_sink = SinkRequirements::new().with_sample_kind(SampleKind::Quaternion);
+        assert!(negotiate(&capabilities, &quaternion_sink, &cluster).is_err());
+
+        let gyro_sink = SinkRequirements::new().with_sensor_kind("Gyroscope");
+        assert!(negotiate(&capabilities, &gyro_sink, &cluster).is_err());
+
+        let si_sink =
+            SinkRequirements::new().with_unit("accelerometer", Unit::MetersPerSecondSquared);
+        let err = negotiate(&capabilities, &si_sink, &cluster).unwrap_err();
+        assert!(err.to_string().contains("Expected m/s^2"));
+
+        let fast_sink = SinkRequirements::new().with_min_rate(100.0);
+        assert!(negotiate(&capabilities, &fast_sink, &cluster).is_err());
+
+        let missing = SensorType::Gyroscope(Uuid::new_v4());
+        assert!(negotiate(&capabilities, &SinkRequirements::default(), &[missing]).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub struct Clock(f64);
@@ -13,6 +14,45 @@ impl Clock {
         self.0
     }
 }
+
+/// Source of the current time, so that pipelines can run on simulated time in tests.
+pub trait ClockSource: Send + Sync {
+    fn now_secs(&self) -> f64;
+}
+
+/// Wall clock time, as returned by `Clock::now`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl ClockSource for SystemClock {
+    fn now_secs(&self) -> f64 {
+        Clock::now().as_secs()
+    }
+}
+
+/// Clock that only moves when told to. Clones share the same time.
+#[derive(Clone, Debug, Default)]
+pub struct VirtualClock(Arc<Mutex<f64>>);
+
+impl VirtualClock {
+    pub fn new(start_secs: f64) -> Self {
+        Self(Arc::new(Mutex::new(start_secs)))
+    }
+
+    pub fn set(&self, timestamp_secs: f64) {
+        *self.0.lock().unwrap() = timestamp_secs;
+    }
+
+    pub fn advance(&self, duration_secs: f64) {
+        *self.0.lock().unwrap() += duration_secs;
+    }
+}
+
+impl ClockSource for VirtualClock {
+    fn now_secs(&self) -> f64 {
+        *self.0.lock().unwrap()
+    }
+}
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -29,6 +69,17 @@ mod tests {
         assert_eq!(clock.as_secs(), 12345.678);
     }
 
+    #[test]
+    fn test_virtual_clock() {
+        let clock = VirtualClock::new(10.0);
+        let shared = clock.clone();
+        clock.advance(0.5);
+        assert_eq!(shared.now_secs(), 10.5);
+        shared.set(20.0);
+        assert_eq!(clock.now_secs(), 20.0);
+        assert!(SystemClock.now_secs() > 0.0);
+    }
+
     #[test]
     fn test_clock_now_is_recent() {
         let clock = Clock::now();
@@ -0,0 +1,276 @@
+//! Module control
+//!
+//! Control channel used to tune running processing nodes, for instance while watching the live
+//! plots, instead of restarting the pipeline after every change. Nodes implementing
+//! [`Tunable`] are registered under a name, and are then tuned by name, either calling
+//! [`ControlChannel::set`] or with text commands:
+//!
+//! - `list`: names of the registered nodes.
+//! - `get <node>`: current parameters of a node, one `name=value` per line.
+//! - `set <node> <name>=<value> [<name>=<value> ...]`: updates parameters of a node.
+//!
+//! Values are parsed as bools, integers, floats or strings, in that order.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::{Arc, Mutex};
+//! use imu_common::errors::ImuError;
+//! use imu_common::traits::Tunable;
+//! use imu_common::types::control::ControlChannel;
+//! use imu_common::types::registry::SourceParams;
+//!
+//! struct Gain(Mutex<f64>);
+//!
+//! impl Tunable for Gain {
+//!     fn get_parameters(&self) -> SourceParams {
+//!         SourceParams::new().with("gain", *self.0.lock().unwrap())
+//!     }
+//!     fn set_parameters(&self, params: &SourceParams) -> Result<(), ImuError> {
+//!         *self.0.lock().unwrap() = params.get_float("gain")?;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let channel = ControlChannel::new();
+//! channel.register("filter", Arc::new(Gain(Mutex::new(1.0)))).unwrap();
+//! channel.execute("set filter gain=0.5").unwrap();
+//! assert_eq!(channel.execute("get filter").unwrap(), "gain=0.5");
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::sync::{Arc, RwLock};
+
+use crate::errors::ImuError;
+use crate::traits::Tunable;
+use crate::types::registry::{ParamValue, SourceParams};
+
+/// Collection of tunable nodes indexed by name.
+#[derive(Clone, Default)]
+pub struct ControlChannel {
+    nodes: Arc<RwLock<BTreeMap<String, Arc<dyn Tunable>>>>,
+}
+
+impl ControlChannel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `node` under `name`. Names can't contain whitespace.
+    /// Returns an error if `name` is invalid or already registered.
+    pub fn register(&self, name: &str, node: Arc<dyn Tunable>) -> Result<(), ImuError> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(ImuError::InvalidParameter(format!(
+                "Invalid node name {:?}",
+                name
+            )));
+        }
+        let mut nodes = self.nodes.write().unwrap();
+        if nodes.contains_key(name) {
+            return Err(ImuError::InvalidParameter(format!(
+                "Node {} already registered",
+                name
+            )));
+        }
+        nodes.insert(name.to_string(), node);
+        Ok(())
+    }
+
+    pub fn unregister(&self, name: &str) {
+        self.nodes.write().unwrap().remove(name);
+    }
+
+    /// Returns the registered names, sorted alphabetically.
+    pub fn get_nodes(&self) -> Vec<String> {
+        self.nodes.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Returns the current parameters of `node`.
+    pub fn get(&self, node: &str) -> Result<SourceParams, ImuError> {
+        Ok(self.node(node)?.get_parameters())
+    }
+
+    /// Updates the parameters of `node`.
+    pub fn set(&self, node: &str, params: &SourceParams) -> Result<(), ImuError> {
+        self.node(node)?.set_parameters(params)
+    }
+
+    fn node(&self, name: &str) -> Result<Arc<dyn Tunable>, ImuError> {
+        self.nodes
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ImuError::InvalidParameter(format!("Unknown node {}", name)))
+    }
+
+    /// Runs a text command, and returns its output.
+    pub fn execute(&self, command: &str) -> Result<String, ImuError> {
+        let mut words = command.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("list"), None) => Ok(self.get_nodes().join("\n")),
+            (Some("get"), Some(node)) if words.clone().next().is_none() => Ok(self
+                .get(node)?
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, format_value(value)))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            (Some("set"), Some(node)) => {
+                let mut params = SourceParams::new();
+                for assignment in words {
+                    let (name, value) = assignment.split_once('=').ok_or_else(|| {
+                        ImuError::InvalidInput(format!("Invalid assignment {}", assignment))
+                    })?;
+                    params.insert(name, parse_value(value));
+                }
+                if params.iter().next().is_none() {
+                    return Err(ImuError::InvalidInput("Missing parameters".to_string()));
+                }
+                self.set(node, &params)?;
+                Ok(String::new())
+            }
+            _ => Err(ImuError::InvalidInput(format!(
+                "Invalid command {:?}",
+                command.trim()
+            ))),
+        }
+    }
+
+    /// Runs every line of `input` as a command, writing its output or error to `output`, until
+    /// `input` is closed. Used to tune the pipeline from a terminal with `std::io::stdin()`.
+    pub fn serve(&self, input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match self.execute(&line) {
+                Ok(response) if response.is_empty() => {}
+                Ok(response) => writeln!(output, "{}", response)?,
+                Err(e) => writeln!(output, "Error: {}", e)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_value(value: &str) -> ParamValue {
+    if let Ok(v) = value.parse::<bool>() {
+        ParamValue::Bool(v)
+    } else if let Ok(v) = value.parse::<i64>() {
+        ParamValue::Int(v)
+    } else if let Ok(v) = value.parse::<f64>() {
+        ParamValue::Float(v)
+    } else {
+        ParamValue::Str(value.to_string())
+    }
+}
+
+fn format_value(value: &ParamValue) -> String {
+    match value {
+        ParamValue::Bool(v) => v.to_string(),
+        ParamValue::Int(v) => v.to_string(),
+        ParamValue::Float(v) => v.to_string(),
+        ParamValue::Str(v) => v.clone(),
+        ParamValue::List(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(format_value)
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    struct TestNode {
+        params: Mutex<SourceParams>,
+    }
+
+    impl Tunable for TestNode {
+        fn get_parameters(&self) -> SourceParams {
+            self.params.lock().unwrap().clone()
+        }
+
+        fn set_parameters(&self, params: &SourceParams) -> Result<(), ImuError> {
+            let mut current = self.params.lock().unwrap();
+            if let Some((name, _)) = params.iter().find(|(name, _)| !current.contains(name)) {
+                return Err(ImuError::InvalidParameter(format!(
+                    "Unknown parameter {}",
+                    name
+                )));
+            }
+            for (name, value) in params.iter() {
+                current.insert(name, value.clone());
+            }
+            Ok(())
+        }
+    }
+
+    fn channel() -> ControlChannel {
+        let channel = ControlChannel::new();
+        let params = SourceParams::new()
+            .with("beta", 0.1)
+            .with("policy", "averaging");
+        let node = Arc::new(TestNode {
+            params: Mutex::new(params),
+        });
+        channel.register("ahrs", node).unwrap();
+        channel
+    }
+
+    #[test]
+    fn test_commands() {
+        let channel = channel();
+
+        assert_eq!(channel.execute("list").unwrap(), "ahrs");
+        assert_eq!(
+            channel.execute("set ahrs beta=1 policy=last_sample"),
+            Ok(String::new())
+        );
+        assert_eq!(
+            channel.execute(" get  ahrs ").unwrap(),
+            "beta=1\npolicy=last_sample"
+        );
+        assert_eq!(channel.get("ahrs").unwrap().get_float("beta"), Ok(1.0));
+
+        assert!(channel.execute("set ahrs gain=1").is_err());
+        assert!(channel.execute("set ahrs beta").is_err());
+        assert!(channel.execute("set ahrs").is_err());
+        assert!(channel.execute("get resampler").is_err());
+        assert!(channel.execute("reset ahrs").is_err());
+    }
+
+    #[test]
+    fn test_register() {
+        let channel = channel();
+        let node = Arc::new(TestNode {
+            params: Mutex::new(SourceParams::new()),
+        });
+
+        assert!(channel.register("ahrs", node.clone()).is_err());
+        assert!(channel.register("my node", node.clone()).is_err());
+        channel.unregister("ahrs");
+        assert!(channel.register("ahrs", node).is_ok());
+    }
+
+    #[test]
+    fn test_serve() {
+        let channel = channel();
+        let input = "set ahrs beta=0.5\n\nget ahrs\nget other\n";
+        let mut output = Vec::new();
+
+        channel.serve(input.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "beta=0.5\npolicy=averaging\nError: Invalid parameter: Unknown node other\n"
+        );
+    }
+}
@@ -1,8 +1,10 @@
+use crate::errors::ImuError;
 use crate::traits::imu::BasicArithmetic;
 use crate::traits::{IMUFilter, IMUSample, IMUUntimedSample};
 use crate::types::timed::SampleQuaternion;
 use crate::types::untimed::UnitQuaternion;
-use std::marker::PhantomData;
+use alloc::{vec, vec::Vec};
+use core::marker::PhantomData;
 
 /// An  averaging filter for IMU (Inertial Measurement Unit) data.
 ///
@@ -30,7 +32,6 @@ use std::marker::PhantomData;
 /// let filtered_samples = avg.filter_batch(samples).unwrap();
 /// ```
 ///
-
 /// Definition of moving average filter, containing `window_size` elements to do the smoothing. Samples are stored in a circular buffer.
 /// When one samples is pushed, oldest sample is popped. Contents of the buffer are used then to do the smoothind
 #[derive(Clone, Debug, Default)]
@@ -54,9 +55,9 @@ where
     U: IMUSample<Untimed = T>,
 {
     /// Filters a batch of IMU samples using the moving average filter.
-    fn filter_batch(&mut self, samples: Vec<U>) -> Result<Vec<U>, &str> {
+    fn filter_batch(&mut self, samples: Vec<U>) -> Result<Vec<U>, ImuError> {
         if samples.is_empty() {
-            return Err("No samples to filter");
+            return Err(ImuError::EmptyInput);
         }
         let aggregate = samples
             .iter()
@@ -78,9 +79,9 @@ where
     fn filter_batch(
         &mut self,
         samples: Vec<SampleQuaternion>,
-    ) -> Result<Vec<SampleQuaternion>, &str> {
+    ) -> Result<Vec<SampleQuaternion>, ImuError> {
         if samples.is_empty() {
-            return Err("No samples to filter");
+            return Err(ImuError::EmptyInput);
         }
         let mut smoothed_quaternion = samples[0].get_measurement().inner();
 
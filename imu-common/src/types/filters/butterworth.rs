@@ -0,0 +1,341 @@
+use crate::errors::ImuError;
+use crate::traits::imu::BasicArithmetic;
+use crate::traits::{IMUFilter, IMUSample, IMUUntimedSample};
+use alloc::{format, string::ToString, vec::Vec};
+use core::f64::consts::PI;
+#[cfg(not(any(feature = "std", test)))]
+use nalgebra::ComplexField;
+
+/// Band of frequencies passed by a [`Butterworth`] filter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PassBand {
+    /// Frequencies below the cutoff are passed, e.g. to band-limit accelerometer data before
+    /// resampling.
+    LowPass,
+    /// Frequencies above the cutoff are passed, e.g. to remove gravity or a slow drift.
+    HighPass,
+}
+
+/// Second order section in transposed direct form II, normalized so that `a0 = 1`.
+#[derive(Clone, Debug)]
+struct Section<T> {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [T; 2],
+}
+
+impl<T> Section<T>
+where
+    T: BasicArithmetic + Clone + Default,
+{
+    fn new(b: [f64; 3], a0: f64, a: [f64; 2]) -> Self {
+        Self {
+            b: b.map(|b| b / a0),
+            a: a.map(|a| a / a0),
+            state: [T::default(), T::default()],
+        }
+    }
+
+    /// Section with a pair of complex conjugate poles of quality `q`.
+    fn second_order(band: PassBand, w0: f64, q: f64) -> Self {
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let b = match band {
+            PassBand::LowPass => [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            PassBand::HighPass => [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+        };
+        Self::new(b, 1.0 + alpha, [-2.0 * cos, 1.0 - alpha])
+    }
+
+    /// Section with the real pole of odd orders.
+    fn first_order(band: PassBand, w0: f64) -> Self {
+        let k = (w0 / 2.0).tan();
+        let b = match band {
+            PassBand::LowPass => [k, k, 0.0],
+            PassBand::HighPass => [1.0, -1.0, 0.0],
+        };
+        Self::new(b, 1.0 + k, [k - 1.0, 0.0])
+    }
+
+    /// Sets the state reached after a constant `input`, returning the output.
+    fn settle(&mut self, input: &T, band: PassBand) -> T {
+        let output = match band {
+            PassBand::LowPass => input.clone(),
+            PassBand::HighPass => T::default(),
+        };
+        let s1 = input.clone() * self.b[2] - output.clone() * self.a[1];
+        let s0 = input.clone() * self.b[1] - output.clone() * self.a[0] + s1.clone();
+        self.state = [s0, s1];
+        output
+    }
+
+    fn process(&mut self, input: T) -> T {
+        let [s0, s1] = core::mem::take(&mut self.state);
+        let output = input.clone() * self.b[0] + s0;
+        self.state = [
+            input.clone() * self.b[1] - output.clone() * self.a[0] + s1,
+            input * self.b[2] - output.clone() * self.a[1],
+        ];
+        output
+    }
+}
+
+/// A Butterworth low-pass or high-pass filter for IMU data.
+///
+/// The filter is designed from the cutoff frequency and the sample rate of the input with the
+/// bilinear transform, as a cascade of second order sections. Higher orders have a steeper
+/// roll-off, at the cost of a longer delay. The state is initialized with the first sample as if
+/// the input had been constant, so there is no startup transient.
+///
+/// Samples are expected in timestamp order at the sample rate given. The filter is implemented for
+/// every sample implementing `BasicArithmetic`, i.e. `Sample3D` and `SampleScalar`.
+///
+/// ## Example
+///
+/// ```rust
+/// use imu_common::types::filters::Butterworth;
+/// use imu_common::types::timed::Sample3D;
+/// use imu_common::types::untimed::XYZ;
+/// use imu_common::traits::imu::IMUFilter;
+///
+/// // 4th order low-pass at 5 Hz of samples at 100 Hz
+/// let mut filter = Butterworth::<XYZ>::low_pass(4, 5.0, 100.0).unwrap();
+/// let samples = vec![
+///     Sample3D::new(0.00, [0.0, 0.0, 9.8]),
+///     Sample3D::new(0.01, [0.1, 0.0, 9.8]),
+/// ];
+/// let filtered_samples = filter.filter_batch(samples).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Butterworth<T> {
+    band: PassBand,
+    order: usize,
+    cutoff_hz: f64,
+    sample_rate_hz: f64,
+    sections: Vec<Section<T>>,
+    settled: bool,
+}
+
+impl<T> Butterworth<T>
+where
+    T: IMUUntimedSample + BasicArithmetic,
+{
+    /// Designs a filter of `order` passing `band` of the `cutoff_hz` frequency, for samples at
+    /// `sample_rate_hz`.
+    ///
+    /// Returns an error if the order is 0, or if the cutoff isn't positive and below the Nyquist
+    /// frequency.
+    pub fn new(
+        band: PassBand,
+        order: usize,
+        cutoff_hz: f64,
+        sample_rate_hz: f64,
+    ) -> Result<Self, ImuError> {
+        if order == 0 {
+            return Err(ImuError::InvalidParameter(
+                "Butterworth filter order must be at least 1".to_string(),
+            ));
+        }
+        if !(sample_rate_hz > 0.0 && sample_rate_hz.is_finite()) {
+            return Err(ImuError::InvalidParameter(format!(
+                "Invalid sample rate {} Hz",
+                sample_rate_hz
+            )));
+        }
+        if !(cutoff_hz > 0.0 && cutoff_hz < sample_rate_hz / 2.0) {
+            return Err(ImuError::InvalidParameter(format!(
+                "Cutoff {} Hz must be positive and below the Nyquist frequency {} Hz",
+                cutoff_hz,
+                sample_rate_hz / 2.0
+            )));
+        }
+        let w0 = 2.0 * PI * cutoff_hz / sample_rate_hz;
+        // each pair of poles of the analog prototype gives a second order section
+        let mut sections: Vec<Section<T>> = (0..order / 2)
+            .map(|k| {
+                let q = 1.0 / (2.0 * (PI * (2 * k + 1) as f64 / (2 * order) as f64).sin());
+                Section::second_order(band, w0, q)
+            })
+            .collect();
+        if order % 2 == 1 {
+            sections.push(Section::first_order(band, w0));
+        }
+        Ok(Self {
+            band,
+            order,
+            cutoff_hz,
+            sample_rate_hz,
+            sections,
+            settled: false,
+        })
+    }
+
+    /// Designs a low-pass filter. See [`Butterworth::new`].
+    pub fn low_pass(order: usize, cutoff_hz: f64, sample_rate_hz: f64) -> Result<Self, ImuError> {
+        Self::new(PassBand::LowPass, order, cutoff_hz, sample_rate_hz)
+    }
+
+    /// Designs a high-pass filter. See [`Butterworth::new`].
+    pub fn high_pass(order: usize, cutoff_hz: f64, sample_rate_hz: f64) -> Result<Self, ImuError> {
+        Self::new(PassBand::HighPass, order, cutoff_hz, sample_rate_hz)
+    }
+
+    pub fn get_band(&self) -> PassBand {
+        self.band
+    }
+
+    pub fn get_order(&self) -> usize {
+        self.order
+    }
+
+    pub fn get_cutoff_hz(&self) -> f64 {
+        self.cutoff_hz
+    }
+
+    pub fn get_sample_rate_hz(&self) -> f64 {
+        self.sample_rate_hz
+    }
+
+    /// Forgets the previous samples. The next sample initializes the state again.
+    pub fn reset(&mut self) {
+        self.settled = false;
+    }
+}
+
+/// General implementation of IMUFilter for samples that implement `BasicArithmetic` trait
+impl<T, U> IMUFilter<U> for Butterworth<T>
+where
+    T: IMUUntimedSample + BasicArithmetic + Default + Send + Sync + 'static + Clone + Sized,
+    U: IMUSample<Untimed = T>,
+{
+    fn filter_batch(&mut self, samples: Vec<U>) -> Result<Vec<U>, ImuError> {
+        if samples.is_empty() {
+            return Err(ImuError::EmptyInput);
+        }
+        let mut filtered_data: Vec<U> = Vec::with_capacity(samples.len());
+        for sample in samples {
+            let mut value = sample.get_measurement();
+            if !self.settled {
+                // each section settles to the output of the previous one
+                let mut input = value.clone();
+                for section in self.sections.iter_mut() {
+                    input = section.settle(&input, self.band);
+                }
+                self.settled = true;
+            }
+            for section in self.sections.iter_mut() {
+                value = section.process(value);
+            }
+            filtered_data.push(U::from_measurement(sample.get_timestamp_secs(), value));
+        }
+        Ok(filtered_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::timed::{Sample3D, SampleScalar};
+    use crate::types::untimed::{Scalar, XYZ};
+
+    const SAMPLE_RATE_HZ: f64 = 100.0;
+
+    /// Returns the amplitude of the output of `filter` for a unit sine of `frequency_hz`, once
+    /// the transient has settled.
+    fn gain(mut filter: Butterworth<Scalar>, frequency_hz: f64) -> f64 {
+        let samples = (0..2000)
+            .map(|n| {
+                let t = n as f64 / SAMPLE_RATE_HZ;
+                SampleScalar::new(t, (2.0 * PI * frequency_hz * t).sin())
+            })
+            .collect();
+        let filtered = filter.filter_batch(samples).unwrap();
+        // rms over whole periods
+        let power = filtered[1000..]
+            .iter()
+            .map(|sample| sample.get_measurement().inner().powi(2))
+            .sum::<f64>()
+            / 1000.0;
+        (2.0 * power).sqrt()
+    }
+
+    #[test]
+    fn test_design_errors() {
+        assert!(Butterworth::<XYZ>::low_pass(0, 5.0, SAMPLE_RATE_HZ).is_err());
+        assert!(Butterworth::<XYZ>::low_pass(2, 0.0, SAMPLE_RATE_HZ).is_err());
+        assert!(Butterworth::<XYZ>::low_pass(2, 50.0, SAMPLE_RATE_HZ).is_err());
+        assert!(Butterworth::<XYZ>::high_pass(2, 5.0, f64::NAN).is_err());
+        let filter = Butterworth::<XYZ>::high_pass(3, 5.0, SAMPLE_RATE_HZ).unwrap();
+        assert_eq!(filter.get_band(), PassBand::HighPass);
+        assert_eq!(filter.sections.len(), 2);
+    }
+
+    #[test]
+    fn test_low_pass_response() {
+        for order in 1..=5 {
+            let filter = || Butterworth::low_pass(order, 5.0, SAMPLE_RATE_HZ).unwrap();
+            assert!((gain(filter(), 0.5) - 1.0).abs() < 0.01, "order {}", order);
+            // -3 dB at the cutoff, whatever the order
+            assert!(
+                (gain(filter(), 5.0) - 0.5f64.sqrt()).abs() < 0.01,
+                "order {}",
+                order
+            );
+            // the roll-off is 6 dB per octave and order
+            let expected = 1.0 / (1.0 + 4f64.powi(2 * order as i32)).sqrt();
+            assert!(gain(filter(), 20.0) < 1.5 * expected, "order {}", order);
+        }
+    }
+
+    #[test]
+    fn test_high_pass_response() {
+        for order in 1..=4 {
+            let filter = || Butterworth::high_pass(order, 5.0, SAMPLE_RATE_HZ).unwrap();
+            assert!((gain(filter(), 40.0) - 1.0).abs() < 0.01, "order {}", order);
+            assert!(
+                (gain(filter(), 5.0) - 0.5f64.sqrt()).abs() < 0.01,
+                "order {}",
+                order
+            );
+            let expected = 1.0 / (1.0 + 10f64.powi(2 * order as i32)).sqrt();
+            assert!(gain(filter(), 0.5) < 1.5 * expected, "order {}", order);
+        }
+    }
+
+    #[test]
+    fn test_no_startup_transient() {
+        let samples: Vec<Sample3D> = (0..50)
+            .map(|n| Sample3D::new(n as f64 / SAMPLE_RATE_HZ, [0.0, 0.0, 9.8]))
+            .collect();
+
+        let mut low_pass = Butterworth::<XYZ>::low_pass(4, 5.0, SAMPLE_RATE_HZ).unwrap();
+        let filtered = low_pass.filter_batch(samples.clone()).unwrap();
+        assert_eq!(filtered[10].get_timestamp_secs(), 0.1);
+        for sample in filtered {
+            let error: Vec<f64> = (sample.get_measurement() - XYZ::new([0.0, 0.0, 9.8])).into();
+            assert!(error.iter().all(|e| e.abs() < 1e-9), "{:?}", error);
+        }
+
+        // gravity is removed by the high-pass filter from the first sample
+        let mut high_pass = Butterworth::<XYZ>::high_pass(2, 0.5, SAMPLE_RATE_HZ).unwrap();
+        for sample in high_pass.filter_batch(samples).unwrap() {
+            let values: Vec<f64> = sample.get_measurement().into();
+            assert!(values.iter().all(|v| v.abs() < 1e-9), "{:?}", values);
+        }
+        high_pass.reset();
+        assert!(high_pass.filter_batch(Vec::<Sample3D>::new()).is_err());
+    }
+
+    #[test]
+    fn test_f32_samples() {
+        let samples: Vec<Sample3D<f32>> = (0..50)
+            .map(|n| Sample3D::new(n as f64 / SAMPLE_RATE_HZ, [0.0, 0.0, 9.8]))
+            .collect();
+
+        let mut low_pass = Butterworth::<XYZ<f32>>::low_pass(4, 5.0, SAMPLE_RATE_HZ).unwrap();
+        for sample in low_pass.filter_batch(samples).unwrap() {
+            let [x, y, z] = sample.get_measurement().inner();
+            assert!(x.abs() < 1e-3 && y.abs() < 1e-3 && (z - 9.8).abs() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+
+use crate::errors::ImuError;
+use crate::traits::{IMUFilter, IMUSample};
+
+type StageFactory<S> = Arc<dyn Fn() -> Box<dyn IMUFilter<S>> + Send + Sync>;
+
+/// An ordered list of filters applied one after the other, e.g. a Butterworth low-pass
+/// followed by a moving average.
+///
+/// The chain implements `IMUFilter` itself, so it can be used wherever a single filter is
+/// expected. Every stage filters the output of the previous one. An empty chain returns the
+/// samples unchanged.
+///
+/// ## Example
+///
+/// ```rust
+/// use imu_common::types::filters::{Butterworth, FilterChain, MovingAverage};
+/// use imu_common::types::timed::Sample3D;
+/// use imu_common::types::untimed::XYZ;
+/// use imu_common::traits::imu::IMUFilter;
+///
+/// let mut chain = FilterChain::<Sample3D>::builder()
+///     .with(Butterworth::<XYZ>::low_pass(2, 5.0, 100.0).unwrap())
+///     .with(MovingAverage::<XYZ>::new(3))
+///     .build();
+/// let samples = vec![
+///     Sample3D::new(0.00, [0.0, 0.0, 9.8]),
+///     Sample3D::new(0.01, [0.1, 0.0, 9.8]),
+/// ];
+/// let filtered_samples = chain.filter_batch(samples).unwrap();
+/// assert_eq!(chain.len(), 2);
+/// ```
+pub struct FilterChain<S> {
+    stages: Vec<Box<dyn IMUFilter<S>>>,
+}
+
+impl<S: IMUSample> Default for FilterChain<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: IMUSample> FilterChain<S> {
+    /// Returns an empty chain.
+    pub fn new() -> Self {
+        Self { stages: Vec::new() }
+    }
+
+    /// Returns a builder of chains, to build the same chain for several sensors.
+    pub fn builder() -> FilterChainBuilder<S> {
+        FilterChainBuilder::new()
+    }
+
+    /// Appends `filter` as the last stage.
+    pub fn push(&mut self, filter: Box<dyn IMUFilter<S>>) {
+        self.stages.push(filter);
+    }
+
+    /// Returns the number of stages.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+}
+
+impl<S: IMUSample> IMUFilter<S> for FilterChain<S> {
+    /// Filters a batch of IMU samples with every stage in order. Returns the error of the first
+    /// stage failing. Stages left without samples by a previous one are skipped.
+    fn filter_batch(&mut self, samples: Vec<S>) -> Result<Vec<S>, ImuError> {
+        if samples.is_empty() {
+            return Err(ImuError::EmptyInput);
+        }
+        let mut samples = samples;
+        for stage in self.stages.iter_mut() {
+            if samples.is_empty() {
+                break;
+            }
+            samples = stage.filter_batch(samples)?;
+        }
+        Ok(samples)
+    }
+}
+
+/// Filters `samples` of a live stream with `chain`, if any, without failing.
+///
+/// Samples rejected with a recoverable error are dropped. Any other error means the chain is
+/// misconfigured and would reject every batch, so `chain` is disabled, setting it to `None`,
+/// and the following samples are passed unfiltered. The batch that failed was consumed by the
+/// chain, so it is dropped too, instead of copying every batch in case the chain fails.
+///
+/// Returns the filtered samples, and the error of the chain, if any, for the caller to report.
+pub fn filter_or_disable<S: IMUSample>(
+    chain: &mut Option<FilterChain<S>>,
+    samples: Vec<S>,
+) -> (Vec<S>, Option<ImuError>) {
+    let Some(stages) = chain.as_mut() else {
+        return (samples, None);
+    };
+    if samples.is_empty() {
+        return (samples, None);
+    }
+    match stages.filter_batch(samples) {
+        Ok(filtered) => (filtered, None),
+        Err(e) => {
+            if !e.is_recoverable() {
+                *chain = None;
+            }
+            (Vec::new(), Some(e))
+        }
+    }
+}
+
+/// Builds [`FilterChain`]s with the same stages.
+///
+/// Filters keep the state of the samples they received, so every sensor needs its own chain.
+/// The builder keeps how to create every stage instead of the stages, so it can be cloned and
+/// handed to the pipeline stages that build a chain per sensor.
+pub struct FilterChainBuilder<S> {
+    stages: Vec<StageFactory<S>>,
+}
+
+impl<S> Clone for FilterChainBuilder<S> {
+    fn clone(&self) -> Self {
+        Self {
+            stages: self.stages.clone(),
+        }
+    }
+}
+
+impl<S> core::fmt::Debug for FilterChainBuilder<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FilterChainBuilder")
+            .field("stages", &self.stages.len())
+            .finish()
+    }
+}
+
+impl<S: IMUSample> Default for FilterChainBuilder<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: IMUSample> FilterChainBuilder<S> {
+    pub fn new() -> Self {
+        Self { stages: Vec::new() }
+    }
+
+    /// Appends a stage starting as a copy of `filter`.
+    pub fn with<F>(self, filter: F) -> Self
+    where
+        F: IMUFilter<S> + Clone + 'static,
+    {
+        self.with_factory(move || filter.clone())
+    }
+
+    /// Appends a stage created by `factory`, for filters that can't be cloned.
+    pub fn with_factory<F, G>(mut self, factory: G) -> Self
+    where
+        F: IMUFilter<S> + 'static,
+        G: Fn() -> F + Send + Sync + 'static,
+    {
+        self.stages.push(Arc::new(move || {
+            Box::new(factory()) as Box<dyn IMUFilter<S>>
+        }));
+        self
+    }
+
+    /// Returns the number of stages.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Returns a new chain, with stages that haven't received any sample.
+    pub fn build(&self) -> FilterChain<S> {
+        FilterChain {
+            stages: self.stages.iter().map(|factory| factory()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::filters::{Butterworth, MovingAverage};
+    use crate::types::timed::Sample3D;
+    use crate::types::untimed::XYZ;
+    use alloc::vec;
+
+    /// Filter adding `offset` to every coordinate, to check the order of the stages.
+    #[derive(Clone)]
+    struct Offset(f64);
+
+    impl IMUFilter<Sample3D> for Offset {
+        fn filter_batch(&mut self, samples: Vec<Sample3D>) -> Result<Vec<Sample3D>, ImuError> {
+            Ok(samples
+                .into_iter()
+                .map(|s| {
+                    Sample3D::from_measurement(
+                        s.get_timestamp_secs(),
+                        s.get_measurement() + XYZ::new([self.0; 3]),
+                    )
+                })
+                .collect())
+        }
+    }
+
+    /// Filter dropping every sample.
+    #[derive(Clone)]
+    struct DropAll;
+
+    impl IMUFilter<Sample3D> for DropAll {
+        fn filter_batch(&mut self, _samples: Vec<Sample3D>) -> Result<Vec<Sample3D>, ImuError> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_chain_applies_stages_in_order() {
+        let mut chain = FilterChain::builder()
+            .with(MovingAverage::<XYZ>::new(2))
+            .with(Offset(1.0))
+            .build();
+        let filtered = chain
+            .filter_batch(vec![
+                Sample3D::new(0.0, [2.0, 2.0, 2.0]),
+                Sample3D::new(0.1, [4.0, 4.0, 4.0]),
+            ])
+            .unwrap();
+
+        assert_eq!(filtered[0], Sample3D::new(0.0, [2.0, 2.0, 2.0]));
+        assert_eq!(filtered[1], Sample3D::new(0.1, [4.0, 4.0, 4.0]));
+    }
+
+    /// Filter rejecting every batch, as a filter with invalid parameters.
+    #[derive(Clone)]
+    struct Misconfigured;
+
+    impl IMUFilter<Sample3D> for Misconfigured {
+        fn filter_batch(&mut self, _samples: Vec<Sample3D>) -> Result<Vec<Sample3D>, ImuError> {
+            Err(ImuError::InvalidParameter("Negative window".into()))
+        }
+    }
+
+    #[test]
+    fn test_empty_chain() {
+        let mut chain = FilterChain::<Sample3D>::new();
+        let samples = vec![Sample3D::new(0.0, [1.0, 2.0, 3.0])];
+
+        assert!(chain.is_empty());
+        assert_eq!(chain.filter_batch(samples.clone()).unwrap(), samples);
+        assert!(chain.filter_batch(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_stages_without_samples_are_skipped() {
+        let mut chain = FilterChain::builder()
+            .with(DropAll)
+            .with(MovingAverage::<XYZ>::new(2))
+            .build();
+        let filtered = chain
+            .filter_batch(vec![Sample3D::new(0.0, [1.0, 2.0, 3.0])])
+            .unwrap();
+        assert!(filtered.is_empty());
+    }
+
+    #[test]
+    fn test_recoverable_errors() {
+        let mut chain = FilterChain::builder().with(Misconfigured).build();
+
+        assert!(chain.filter_batch(Vec::new()).unwrap_err().is_recoverable());
+        let error = chain
+            .filter_batch(vec![Sample3D::new(0.0, [1.0, 2.0, 3.0])])
+            .unwrap_err();
+        assert!(!error.is_recoverable());
+    }
+
+    #[test]
+    fn test_filter_or_disable() {
+        let samples = vec![Sample3D::new(0.0, [1.0, 2.0, 3.0])];
+        let mut chain = None;
+        assert_eq!(
+            filter_or_disable(&mut chain, samples.clone()),
+            (samples.clone(), None)
+        );
+
+        let mut chain = Some(FilterChain::builder().with(Offset(1.0)).build());
+        let (filtered, error) = filter_or_disable(&mut chain, samples.clone());
+        assert_eq!(filtered, vec![Sample3D::new(0.0, [2.0, 3.0, 4.0])]);
+        assert!(error.is_none());
+
+        // the failing batch is dropped, and the next ones are passed unfiltered
+        let mut chain = Some(FilterChain::builder().with(Misconfigured).build());
+        let (filtered, error) = filter_or_disable(&mut chain, samples.clone());
+        assert!(filtered.is_empty());
+        assert!(!error.unwrap().is_recoverable());
+        assert!(chain.is_none());
+        assert_eq!(
+            filter_or_disable(&mut chain, samples.clone()),
+            (samples, None)
+        );
+    }
+
+    #[test]
+    fn test_built_chains_are_independent() {
+        let builder = FilterChain::builder()
+            .with_factory(|| Butterworth::<XYZ>::low_pass(2, 5.0, 100.0).unwrap());
+        let mut chain = builder.build();
+        chain
+            .filter_batch(vec![Sample3D::new(0.0, [10.0, 10.0, 10.0])])
+            .unwrap();
+
+        // a new chain doesn't carry the state of the previous one
+        let mut other = builder.clone().build();
+        let filtered = other
+            .filter_batch(vec![Sample3D::new(0.0, [1.0, 1.0, 1.0])])
+            .unwrap();
+        assert_eq!(filtered[0], Sample3D::new(0.0, [1.0, 1.0, 1.0]));
+        assert_eq!(builder.len(), 1);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::errors::ImuError;
+use crate::traits::imu::BasicArithmetic;
+use crate::traits::{IMUFilter, IMUSample, IMUUntimedSample};
+use crate::types::timed::SampleQuaternion;
+use crate::types::untimed::UnitQuaternion;
+use alloc::vec::Vec;
+
+/// A complementary filter fusing two estimates of the same quantity.
+///
+/// The filtered samples are the high-pass input, e.g. an orientation integrated from the
+/// gyroscope, which is accurate over short periods but drifts. The reference is the low-pass
+/// input, e.g. an orientation derived from the accelerometer and magnetometer, which doesn't
+/// drift but is noisy. Each filtered sample moves the estimate by the change of the high-pass
+/// input since the previous sample, and pulls it towards the latest reference:
+///
+/// `estimate = alpha * (estimate + (input - previous_input)) + (1 - alpha) * reference`
+///
+/// `alpha` close to 1 trusts the high-pass input for longer. Quaternion samples are fused the
+/// same way, composing rotations and interpolating towards the reference with slerp.
+///
+/// ## Example
+///
+/// ```rust
+/// use imu_common::types::filters::Complementary;
+/// use imu_common::types::timed::Sample3D;
+/// use imu_common::types::untimed::XYZ;
+/// use imu_common::traits::imu::IMUFilter;
+///
+/// let mut filter = Complementary::<XYZ>::new(0.98);
+/// filter.set_reference(&Sample3D::new(0.0, [0.0, 0.0, 1.0]));
+/// let samples = vec![
+///     Sample3D::new(0.01, [0.0, 0.0, 1.0]),
+///     Sample3D::new(0.02, [0.0, 0.1, 1.0]),
+/// ];
+/// let fused_samples = filter.filter_batch(samples).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Complementary<T> {
+    alpha: f64,
+    estimate: Option<T>,
+    previous_input: Option<T>,
+    reference: Option<T>,
+}
+
+impl<T: IMUUntimedSample> Complementary<T> {
+    /// Initializes a new `Complementary` filter weighting the high-pass input with `alpha`,
+    /// clamped to [0, 1].
+    pub fn new(alpha: f64) -> Self {
+        Self {
+            alpha: alpha.clamp(0.0, 1.0),
+            estimate: None,
+            previous_input: None,
+            reference: None,
+        }
+    }
+
+    pub fn get_alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Returns the current estimate, if a sample was filtered.
+    pub fn get_estimate(&self) -> Option<T> {
+        self.estimate.clone()
+    }
+
+    /// Sets the low-pass input the estimate is pulled towards, until the next reference.
+    pub fn set_reference<U>(&mut self, reference: &U)
+    where
+        U: IMUSample<Untimed = T>,
+    {
+        self.reference = Some(reference.get_measurement());
+    }
+
+    /// Forgets the estimate and the reference.
+    pub fn reset(&mut self) {
+        self.estimate = None;
+        self.previous_input = None;
+        self.reference = None;
+    }
+}
+
+/// General implementation of IMUFilter for samples that implement `BasicArithmetic` trait
+impl<T, U> IMUFilter<U> for Complementary<T>
+where
+    T: IMUUntimedSample + BasicArithmetic + Default + Send + Sync + 'static + Clone + Sized,
+    U: IMUSample<Untimed = T>,
+{
+    fn filter_batch(&mut self, samples: Vec<U>) -> Result<Vec<U>, ImuError> {
+        if samples.is_empty() {
+            return Err(ImuError::EmptyInput);
+        }
+        let mut filtered_data: Vec<U> = Vec::with_capacity(samples.len());
+        for sample in samples {
+            let input = sample.get_measurement();
+            // the estimate starts at the reference, or at the input if there is none yet
+            let predicted = match (self.estimate.take(), self.previous_input.take()) {
+                (Some(estimate), Some(previous_input)) => {
+                    estimate + (input.clone() - previous_input)
+                }
+                _ => self.reference.clone().unwrap_or(input.clone()),
+            };
+            let estimate = match self.reference.clone() {
+                Some(reference) => predicted * self.alpha + reference * (1.0 - self.alpha),
+                None => predicted,
+            };
+            filtered_data.push(U::from_measurement(
+                sample.get_timestamp_secs(),
+                estimate.clone(),
+            ));
+            self.estimate = Some(estimate);
+            self.previous_input = Some(input);
+        }
+        Ok(filtered_data)
+    }
+}
+
+/// Specific implementation of IMUFilter for quaternion samples
+impl IMUFilter<SampleQuaternion> for Complementary<UnitQuaternion> {
+    fn filter_batch(
+        &mut self,
+        samples: Vec<SampleQuaternion>,
+    ) -> Result<Vec<SampleQuaternion>, ImuError> {
+        if samples.is_empty() {
+            return Err(ImuError::EmptyInput);
+        }
+        let mut filtered_data = Vec::with_capacity(samples.len());
+        for sample in samples {
+            let input = sample.get_measurement().inner();
+            let predicted = match (self.estimate.take(), self.previous_input.take()) {
+                // rotation of the input since the previous sample, applied to the estimate
+                (Some(estimate), Some(previous_input)) => {
+                    estimate.inner() * (previous_input.inner().inverse() * input)
+                }
+                _ => self
+                    .reference
+                    .as_ref()
+                    .map(|reference| reference.inner())
+                    .unwrap_or(input),
+            };
+            let estimate = match self.reference.as_ref() {
+                Some(reference) => predicted
+                    .try_slerp(&reference.inner(), 1.0 - self.alpha, 1e-9)
+                    // opposite rotations have no unique interpolation, so keep the prediction
+                    .unwrap_or(predicted),
+                None => predicted,
+            };
+            let estimate = UnitQuaternion::from_unit_quaternion(estimate);
+            filtered_data.push(SampleQuaternion::from_measurement(
+                sample.get_timestamp_secs(),
+                estimate.clone(),
+            ));
+            self.estimate = Some(estimate);
+            self.previous_input = Some(sample.get_measurement());
+        }
+        Ok(filtered_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::timed::Sample3D;
+    use crate::types::untimed::XYZ;
+    use alloc::vec;
+
+    fn assert_close(a: XYZ, b: XYZ) {
+        let error: Vec<f64> = (a - b).into();
+        assert!(error.iter().all(|e| e.abs() < 1e-9), "{:?}", error);
+    }
+
+    #[test]
+    fn test_complementary_xyz() {
+        let mut filter = Complementary::<XYZ>::new(0.9);
+        filter.set_reference(&Sample3D::new(0.0, [0.0, 0.0, 10.0]));
+
+        // the estimate starts at the reference, and follows the changes of the input
+        let fused = filter
+            .filter_batch(vec![
+                Sample3D::new(0.1, [5.0, 0.0, 0.0]),
+                Sample3D::new(0.2, [6.0, 0.0, 0.0]),
+            ])
+            .unwrap();
+        assert_eq!(fused[0].get_timestamp_secs(), 0.1);
+        assert_close(fused[0].get_measurement(), XYZ::new([0.0, 0.0, 10.0]));
+        assert_close(fused[1].get_measurement(), XYZ::new([0.9, 0.0, 10.0]));
+    }
+
+    #[test]
+    fn test_complementary_drift() {
+        let mut filter = Complementary::<XYZ>::new(0.95);
+        filter.set_reference(&Sample3D::new(0.0, [1.0, 1.0, 1.0]));
+
+        // an input drifting away is pulled back to the reference
+        let samples = (0..500)
+            .map(|n| Sample3D::new(n as f64 * 0.01, [n as f64 * 0.01, 0.0, 0.0]))
+            .collect();
+        let fused = filter.filter_batch(samples).unwrap();
+        let last = fused.last().unwrap().get_measurement();
+        // steady state offset of alpha / (1 - alpha) times the drift per sample
+        assert_close(last, XYZ::new([1.0 + 0.19, 1.0, 1.0]));
+
+        filter.reset();
+        assert!(filter.get_estimate().is_none());
+        // without reference the input passes through
+        let fused = filter
+            .filter_batch(vec![Sample3D::new(0.0, [2.0, 3.0, 4.0])])
+            .unwrap();
+        assert_close(fused[0].get_measurement(), XYZ::new([2.0, 3.0, 4.0]));
+    }
+
+    #[test]
+    fn test_complementary_quaternion() {
+        let reference = nalgebra::UnitQuaternion::from_euler_angles(0.0, 0.0, 0.5);
+        let mut filter = Complementary::<UnitQuaternion>::new(0.9);
+        filter.set_reference(&SampleQuaternion::from_unit_quaternion(
+            0.0,
+            UnitQuaternion::from_unit_quaternion(reference),
+        ));
+
+        // a gyro orientation rotating steadily about x is corrected towards the reference
+        let samples = (0..200)
+            .map(|n| {
+                SampleQuaternion::from_unit_quaternion(
+                    n as f64 * 0.01,
+                    UnitQuaternion::from_unit_quaternion(
+                        nalgebra::UnitQuaternion::from_euler_angles(n as f64 * 0.001, 0.0, 0.0),
+                    ),
+                )
+            })
+            .collect();
+        let fused = filter.filter_batch(samples).unwrap();
+        assert_eq!(fused.len(), 200);
+        let first = fused[0].get_measurement().inner();
+        assert!(first.angle_to(&reference) < 1e-9);
+        // the estimate stays within the drift per sample of the reference
+        let last = fused.last().unwrap().get_measurement().inner();
+        assert!(last.angle_to(&reference) < 0.01);
+    }
+
+    #[test]
+    fn test_complementary_no_samples() {
+        let mut filter = Complementary::<XYZ>::new(0.5);
+        assert!(filter.filter_batch(Vec::<Sample3D>::new()).is_err());
+    }
+}
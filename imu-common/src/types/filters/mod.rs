@@ -1,7 +1,15 @@
 pub mod average;
+pub mod butterworth;
+pub mod chain;
+pub mod complementary;
 pub mod moving_average;
+pub mod rate_limit;
 pub mod weighted_average;
 
 pub use crate::types::filters::average::Average;
-pub use crate::types::filters::moving_average::MovingAverage;
-pub use crate::types::filters::weighted_average::WeightedAverage;
+pub use crate::types::filters::butterworth::{Butterworth, PassBand};
+pub use crate::types::filters::chain::{filter_or_disable, FilterChain, FilterChainBuilder};
+pub use crate::types::filters::complementary::Complementary;
+pub use crate::types::filters::moving_average::{MovingAverage, TimedMovingAverage};
+pub use crate::types::filters::rate_limit::{AngularRateLimit, RateLimitMode, RateViolation};
+pub use crate::types::filters::weighted_average::{WeightedAverage, WeightingKernel};
@@ -1,8 +1,14 @@
+use crate::errors::ImuError;
 use crate::traits::imu::BasicArithmetic;
 use crate::traits::{IMUFilter, IMUSample, IMUUntimedSample};
 use crate::types::buffers::CircularBuffer;
 use crate::types::timed::SampleQuaternion;
 use crate::types::untimed::UnitQuaternion;
+use alloc::{collections::VecDeque, vec::Vec};
+
+const DEFAULT_CAPACITY: usize = 64;
+/// Tolerance when comparing sample ages against the time constant.
+const TIME_CONSTANT_EPS: f64 = 1e-9;
 
 /// A moving average filter for IMU (Inertial Measurement Unit) data.
 /// The moving average filter is used to smooth out short-term fluctuations and highlight longer-term trends in the data.
@@ -30,10 +36,6 @@ use crate::types::untimed::UnitQuaternion;
 /// ];
 /// let filtered_samples = ma.filter_batch(samples).unwrap();
 /// ```
-///
-
-const DEFAULT_CAPACITY: usize = 64;
-
 /// Definition of moving average filter, containing `window_size` elements to do the smoothing. Samples are stored in a circular buffer.
 /// When one samples is pushed, oldest sample is popped. Contents of the buffer are used then to do the smoothind
 #[derive(Clone, Debug)]
@@ -54,6 +56,99 @@ impl<T: IMUUntimedSample> MovingAverage<T> {
     }
 }
 
+/// Moving average over the samples received during the last `time_constant` seconds, instead of a
+/// fixed number of samples.
+///
+/// The number of averaged samples adapts to the observed sample rate, so the smoothing doesn't
+/// change when the source is reconfigured to another rate. Samples are expected in timestamp
+/// order.
+///
+/// ## Example
+///
+/// ```rust
+/// use imu_common::types::filters::TimedMovingAverage;
+/// use imu_common::types::timed::Sample3D;
+/// use imu_common::types::untimed::XYZ;
+/// use imu_common::traits::imu::IMUFilter;
+///
+/// // average the samples of the last 0.2 seconds
+/// let mut ma = TimedMovingAverage::<XYZ>::new(0.2);
+/// let samples = vec![
+///     Sample3D::new(0.0, [1.0, 1.0, 1.0]),
+///     Sample3D::new(0.1, [2.0, 2.0, 2.0]),
+///     Sample3D::new(0.2, [3.0, 3.0, 3.0]),
+/// ];
+/// let filtered_samples = ma.filter_batch(samples).unwrap();
+/// assert_eq!(ma.get_window_len(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TimedMovingAverage<T> {
+    time_constant: f64,
+    window: VecDeque<(f64, T)>,
+    aggregate: T,
+}
+
+impl<T: IMUUntimedSample> TimedMovingAverage<T> {
+    /// Initializes new `TimedMovingAverage` filter averaging the samples of the last
+    /// `time_constant_secs` seconds.
+    pub fn new(time_constant_secs: f64) -> Self {
+        Self {
+            time_constant: time_constant_secs,
+            window: VecDeque::new(),
+            aggregate: T::default(),
+        }
+    }
+
+    pub fn get_time_constant(&self) -> f64 {
+        self.time_constant
+    }
+
+    /// Returns the number of samples currently averaged.
+    pub fn get_window_len(&self) -> usize {
+        self.window.len()
+    }
+}
+
+impl<T, U> IMUFilter<U> for TimedMovingAverage<T>
+where
+    T: IMUUntimedSample
+        + BasicArithmetic
+        + Default
+        + Send
+        + Sync
+        + 'static
+        + Clone
+        + core::fmt::Debug,
+    U: IMUSample<Untimed = T>,
+{
+    /// Filters a batch of IMU samples using the time constant moving average filter.
+    fn filter_batch(&mut self, samples: Vec<U>) -> Result<Vec<U>, ImuError> {
+        if samples.is_empty() {
+            return Err(ImuError::EmptyInput);
+        }
+        let mut filtered_data: Vec<U> = Vec::with_capacity(samples.len());
+        for sample in samples {
+            let measurement = sample.get_measurement();
+            let timestamp = sample.get_timestamp_secs();
+            self.aggregate += measurement.clone();
+            self.window.push_back((timestamp, measurement));
+            // the newest sample is always averaged
+            while self.window.len() > 1
+                && timestamp - self.window[0].0 >= self.time_constant - TIME_CONSTANT_EPS
+            {
+                if let Some((_, out)) = self.window.pop_front() {
+                    self.aggregate -= out;
+                }
+            }
+            filtered_data.push(U::from_measurement(
+                timestamp,
+                self.aggregate.clone() / self.window.len() as f64,
+            ));
+        }
+        Ok(filtered_data)
+    }
+}
+
 /// General implementation of IMUFIlter for samples that implement `BasicArithmetic` trait
 impl<T, U> IMUFilter<U> for MovingAverage<T>
 where
@@ -64,13 +159,13 @@ where
         + Sync
         + 'static
         + Clone
-        + std::fmt::Debug,
+        + core::fmt::Debug,
     U: IMUSample<Untimed = T>,
 {
     /// Filters a batch of IMU samples using the moving average filter.
-    fn filter_batch(&mut self, samples: Vec<U>) -> Result<Vec<U>, &str> {
+    fn filter_batch(&mut self, samples: Vec<U>) -> Result<Vec<U>, ImuError> {
         if samples.is_empty() {
-            return Err("No samples to filter");
+            return Err(ImuError::EmptyInput);
         }
         let mut filtered_data: Vec<U> = Vec::with_capacity(samples.len());
         for sample in samples {
@@ -97,9 +192,9 @@ where
     fn filter_batch(
         &mut self,
         samples: Vec<SampleQuaternion>,
-    ) -> Result<Vec<SampleQuaternion>, &str> {
+    ) -> Result<Vec<SampleQuaternion>, ImuError> {
         if samples.is_empty() {
-            return Err("No samples to filter");
+            return Err(ImuError::EmptyInput);
         }
         let mut filtered_data: Vec<SampleQuaternion> = Vec::with_capacity(DEFAULT_CAPACITY);
 
@@ -125,6 +220,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
     use once_cell::sync::Lazy;
 
     use crate::types::timed::sample_3d::Sample3D;
@@ -198,4 +294,44 @@ mod tests {
                 < tolerance
         );
     }
+
+    fn ramp(period: f64, n_samples: usize) -> Vec<Sample3D> {
+        (0..n_samples)
+            .map(|i| {
+                let t = i as f64 * period;
+                Sample3D::new(t, [t, 2.0 * t, 3.0 * t])
+            })
+            .collect()
+    }
+
+    /// The window spans the same time at any sample rate, so a ramp lags by half the time
+    /// constant at both rates.
+    #[test]
+    fn test_timed_moving_average_adapts_to_rate() {
+        for period in [0.1, 0.01] {
+            let mut ma = TimedMovingAverage::<XYZ>::new(0.3);
+            let filtered = ma.filter_batch(ramp(period, 100)).unwrap();
+
+            assert_eq!(ma.get_window_len(), (0.3 / period).round() as usize);
+            let last = filtered.last().unwrap();
+            let lag = last.get_timestamp_secs() - Vec::<f64>::from(last.get_measurement())[0];
+            assert!((lag - (0.3 - period) / 2.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_timed_moving_average_warm_up() {
+        let mut ma = TimedMovingAverage::<XYZ>::new(1.0);
+        let filtered = ma
+            .filter_batch(vec![
+                Sample3D::from_measurement(0.0, SAMPLE_1.clone()),
+                Sample3D::from_measurement(0.1, SAMPLE_3.clone()),
+            ])
+            .unwrap();
+
+        // averages the received samples only
+        assert_eq!(filtered[0].get_measurement(), SAMPLE_1.clone());
+        assert_eq!(filtered[1].get_measurement(), SAMPLE_2.clone());
+        assert!(ma.filter_batch(Vec::<Sample3D>::new()).is_err());
+    }
 }
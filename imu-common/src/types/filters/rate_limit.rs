@@ -0,0 +1,247 @@
+use alloc::{sync::Arc, vec::Vec};
+
+use crate::errors::ImuError;
+use crate::traits::{IMUFilter, IMUSample};
+use crate::types::timed::SampleQuaternion;
+use crate::types::untimed::UnitQuaternion;
+
+/// Consecutive suppressed samples after which the orientation is accepted as the new reference.
+pub const DEFAULT_MAX_SUPPRESSED: usize = 10;
+
+type ViolationCallback = Option<Arc<dyn Fn(RateViolation) + Send + Sync>>;
+
+/// What `AngularRateLimit` does with samples implying an impossible angular rate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RateLimitMode {
+    /// Keeps the samples, only reporting the violation.
+    Flag,
+    /// Drops the samples.
+    #[default]
+    Suppress,
+}
+
+/// Event emitted when an orientation jumps faster than the limit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateViolation {
+    pub timestamp: f64,
+    pub previous_timestamp: f64,
+    /// Angular rate implied by the jump, in rad/s.
+    pub rate: f64,
+    /// Whether the sample was dropped.
+    pub suppressed: bool,
+}
+
+/// Sanity check of orientation estimates, flagging or dropping orientations that imply an
+/// angular rate above a limit. Such jumps usually come from corrupted input batches rather than
+/// from the motion of the sensor.
+///
+/// Samples are compared with the last accepted one. After `max_suppressed` consecutive
+/// suppressed samples, e.g. once the estimator was reset, the next sample is accepted as the new
+/// reference.
+///
+/// ## Example
+///
+/// ```rust
+/// use imu_common::types::filters::AngularRateLimit;
+/// use imu_common::types::timed::SampleQuaternion;
+/// use imu_common::traits::imu::IMUFilter;
+///
+/// // 2000 deg/s, the range of most consumer gyroscopes
+/// let mut filter = AngularRateLimit::new(2000f64.to_radians());
+/// filter.register_callback(|violation| println!("Orientation jump: {:?}", violation));
+/// let samples = vec![
+///     SampleQuaternion::new(0.00, [1.0, 0.0, 0.0, 0.0]),
+///     SampleQuaternion::new(0.01, [0.0, 1.0, 0.0, 0.0]),
+/// ];
+/// assert_eq!(filter.filter_batch(samples).unwrap().len(), 1);
+/// assert_eq!(filter.get_violations(), 1);
+/// ```
+#[derive(Clone)]
+pub struct AngularRateLimit {
+    max_rate: f64,
+    mode: RateLimitMode,
+    max_suppressed: usize,
+    last: Option<(f64, UnitQuaternion)>,
+    n_suppressed: usize,
+    n_violations: usize,
+    callback: ViolationCallback,
+}
+
+impl AngularRateLimit {
+    /// Creates a filter suppressing orientations implying a rate above `max_rate` rad/s.
+    pub fn new(max_rate: f64) -> Self {
+        Self {
+            max_rate: max_rate.abs(),
+            mode: RateLimitMode::default(),
+            max_suppressed: DEFAULT_MAX_SUPPRESSED,
+            last: None,
+            n_suppressed: 0,
+            n_violations: 0,
+            callback: None,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: RateLimitMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets how many consecutive samples can be suppressed before the orientation is accepted.
+    pub fn with_max_suppressed(mut self, max_suppressed: usize) -> Self {
+        self.max_suppressed = max_suppressed;
+        self
+    }
+
+    /// Calls `callback` on every violation.
+    pub fn register_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(RateViolation) + Send + Sync + 'static,
+    {
+        self.callback = Some(Arc::new(callback));
+    }
+
+    /// Returns the number of violations since the filter was created.
+    pub fn get_violations(&self) -> usize {
+        self.n_violations
+    }
+
+    /// Returns the violation of `sample`, if any, and whether it is kept.
+    fn check(&self, sample: &SampleQuaternion) -> (Option<RateViolation>, bool) {
+        let timestamp = sample.get_timestamp_secs();
+        let Some((previous_timestamp, previous)) = self.last.as_ref() else {
+            return (None, true);
+        };
+        let dt = timestamp - previous_timestamp;
+        let angle = previous.inner().angle_to(&sample.get_measurement().inner());
+        // samples with the same timestamp can't move
+        let rate = if dt > 0.0 {
+            angle / dt
+        } else if angle > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        };
+        if rate <= self.max_rate {
+            return (None, true);
+        }
+        let suppressed =
+            self.mode == RateLimitMode::Suppress && self.n_suppressed < self.max_suppressed;
+        let violation = RateViolation {
+            timestamp,
+            previous_timestamp: *previous_timestamp,
+            rate,
+            suppressed,
+        };
+        (Some(violation), !suppressed)
+    }
+}
+
+impl IMUFilter<SampleQuaternion> for AngularRateLimit {
+    /// Filters a batch of quaternion samples, dropping the ones above the limit in `Suppress`
+    /// mode. The batch may be returned empty.
+    fn filter_batch(
+        &mut self,
+        samples: Vec<SampleQuaternion>,
+    ) -> Result<Vec<SampleQuaternion>, ImuError> {
+        if samples.is_empty() {
+            return Err(ImuError::EmptyInput);
+        }
+        let mut filtered_data = Vec::with_capacity(samples.len());
+        let mut violations = Vec::new();
+        for sample in samples {
+            let (violation, keep) = self.check(&sample);
+            if let Some(violation) = violation {
+                self.n_violations += 1;
+                violations.push(violation);
+            }
+            if keep {
+                self.n_suppressed = 0;
+                self.last = Some((sample.get_timestamp_secs(), sample.get_measurement()));
+                filtered_data.push(sample);
+            } else {
+                self.n_suppressed += 1;
+            }
+        }
+        if let Some(cb) = self.callback.as_ref() {
+            for violation in violations {
+                cb(violation);
+            }
+        }
+        Ok(filtered_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::{UnitQuaternion as NUnitQuaternion, Vector3};
+    use std::sync::Mutex;
+
+    /// Rotation around z of `rate` rad/s sampled every 10 ms, with a jump of `jump` rad at `at`.
+    fn rotation(n: usize, rate: f64, jump: f64, at: usize) -> Vec<SampleQuaternion> {
+        (0..n)
+            .map(|i| {
+                let t = i as f64 * 0.01;
+                let angle = rate * t + if i == at { jump } else { 0.0 };
+                let q = NUnitQuaternion::from_scaled_axis(Vector3::new(0.0, 0.0, angle));
+                SampleQuaternion::from_unit_quaternion(t, q.into())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_keep_smooth_rotation() {
+        let mut filter = AngularRateLimit::new(10.0);
+        let samples = rotation(50, 5.0, 0.0, 0);
+        assert_eq!(filter.filter_batch(samples.clone()).unwrap().len(), 50);
+        assert_eq!(filter.get_violations(), 0);
+    }
+
+    #[test]
+    fn test_suppress_jump() {
+        let mut filter = AngularRateLimit::new(10.0);
+        let violations = Arc::new(Mutex::new(Vec::new()));
+        filter.register_callback({
+            let violations = violations.clone();
+            move |violation| violations.lock().unwrap().push(violation)
+        });
+
+        let samples = rotation(20, 5.0, 1.0, 10);
+        let filtered = filter.filter_batch(samples).unwrap();
+        assert_eq!(filtered.len(), 19);
+        assert!(filtered.iter().all(|s| s.get_timestamp_secs() != 0.1));
+
+        let violations = violations.lock().unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].timestamp, 0.1);
+        assert!((violations[0].rate - 105.0).abs() < 1e-6);
+        assert!(violations[0].suppressed);
+    }
+
+    #[test]
+    fn test_flag_jump() {
+        let mut filter = AngularRateLimit::new(10.0).with_mode(RateLimitMode::Flag);
+        let samples = rotation(20, 5.0, 1.0, 10);
+        assert_eq!(filter.filter_batch(samples).unwrap().len(), 20);
+        // jumping there and back
+        assert_eq!(filter.get_violations(), 2);
+    }
+
+    #[test]
+    fn test_accept_after_max_suppressed() {
+        let mut filter = AngularRateLimit::new(10.0).with_max_suppressed(3);
+        let mut samples = rotation(5, 0.0, 0.0, 0);
+        // the orientation is reset and stays there
+        for i in 5..10 {
+            let q = NUnitQuaternion::from_scaled_axis(Vector3::new(0.0, 0.0, 2.0));
+            samples.push(SampleQuaternion::from_unit_quaternion(
+                i as f64 * 0.01,
+                q.into(),
+            ));
+        }
+        let filtered = filter.filter_batch(samples).unwrap();
+        assert_eq!(filtered.len(), 7);
+        assert_eq!(filtered[5].get_timestamp_secs(), 0.08);
+        assert_eq!(filter.get_violations(), 4);
+    }
+}
@@ -1,15 +1,58 @@
+use crate::errors::ImuError;
 use crate::traits::imu::BasicArithmetic;
 use crate::traits::{IMUFilter, IMUSample, IMUUntimedSample};
 use crate::types::timed::SampleQuaternion;
 use crate::types::untimed::UnitQuaternion;
-use std::marker::PhantomData;
+use alloc::{string::ToString, vec::Vec};
+use core::marker::PhantomData;
+#[cfg(not(any(feature = "std", test)))]
+use nalgebra::ComplexField;
+
+const WEIGHTED_AVERAGE_EPS: f64 = 1e-10;
+const WEIGHTED_AVERAGE_ALPHA: f64 = 0.8;
+
+/// Function giving the weight of a sample from its distance to the midpoint of a
+/// [`WeightedAverage`]. Sharper kernels suit low jitter inputs, while wider ones smooth noisy
+/// or irregularly sampled inputs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeightingKernel {
+    /// `(1 / (distance + eps)) ^ alpha`. `eps` avoids dividing by zero for samples at the
+    /// midpoint, and `alpha` controls how fast the weight decays with the distance.
+    InverseDistance { eps: f64, alpha: f64 },
+    /// `1 - distance / half_width`. Samples farther than `half_width` are ignored.
+    Triangular { half_width: f64 },
+    /// `exp(-distance^2 / (2 * sigma^2))`.
+    Gaussian { sigma: f64 },
+}
+
+impl Default for WeightingKernel {
+    fn default() -> Self {
+        Self::InverseDistance {
+            eps: WEIGHTED_AVERAGE_EPS,
+            alpha: WEIGHTED_AVERAGE_ALPHA,
+        }
+    }
+}
+
+impl WeightingKernel {
+    /// Returns the weight of a sample at `distance` seconds from the midpoint.
+    pub fn weight(&self, distance: f64) -> f64 {
+        let distance = distance.abs();
+        match *self {
+            Self::InverseDistance { eps, alpha } => (1.0 / (distance + eps)).powf(alpha),
+            Self::Triangular { half_width } => (1.0 - distance / half_width).max(0.0),
+            Self::Gaussian { sigma } => (-distance.powi(2) / (2.0 * sigma.powi(2))).exp(),
+        }
+    }
+}
 
 /// A weighted moving average filter for IMU data.
 ///
 /// This filter smooths IMU data by applying a weighted moving average algorithm. The weights are
 /// determined based on the distance of each sample's timestamp from a specified midpoint, with
-/// closer samples having higher weights. The weights are further adjusted using an alpha parameter
-/// to control the influence of the distance on the weights.
+/// closer samples having higher weights. By default, weights decay with the inverse of the
+/// distance, raised to an alpha parameter that controls the influence of the distance on the
+/// weights. Other kernels are chosen with `with_kernel`.
 ///
 /// # Example
 ///
@@ -25,28 +68,41 @@ use std::marker::PhantomData;
 /// let samples = vec![Sample3D::new(1.0, [1.0, 2.0, 3.0]), Sample3D::new(6.0, [4.3, 3.2, 4.3])];
 ///
 /// // Get smoothe samples
-/// let smoothed_samples = filter.filter_batch(samples);
+/// let smoothed_samples = filter.filter_batch(samples.clone());
 ///
+/// // Weight the samples with a gaussian kernel instead
+/// use imu_common::types::filters::WeightingKernel;
+/// let mut filter = WeightedAverage::new(5.0).with_kernel(WeightingKernel::Gaussian { sigma: 2.0 });
+/// let smoothed_samples = filter.filter_batch(samples);
 /// ```
-
-const WEIGHTED_AVERAGE_EPS: f64 = 1e-10;
-const WEIGHTED_AVERAGE_ALPHA: f64 = 0.8;
-
 /// Definition of moving average filter, containing `window_size` elements to do the smoothing.
 #[derive(Clone, Debug)]
 pub struct WeightedAverage<T> {
     mid_point: f64,
+    kernel: WeightingKernel,
     _phantom_data: PhantomData<T>,
 }
 
 impl<T> WeightedAverage<T> {
-    /// Initializes new `MovingAverage` filter with `window_size` elements.
+    /// Initializes new `WeightedAverage` filter centered at `mid_point`, with the default
+    /// inverse distance kernel.
     pub fn new(mid_point: f64) -> Self {
         Self {
             mid_point,
+            kernel: WeightingKernel::default(),
             _phantom_data: PhantomData,
         }
     }
+
+    /// Weights samples with `kernel`.
+    pub fn with_kernel(mut self, kernel: WeightingKernel) -> Self {
+        self.kernel = kernel;
+        self
+    }
+
+    pub fn get_kernel(&self) -> WeightingKernel {
+        self.kernel
+    }
 }
 
 /// General implementation of IMUFIlter for samples that implement `BasicArithmetic` trait
@@ -55,9 +111,9 @@ where
     T: IMUUntimedSample + BasicArithmetic + Default + Send + Sync + 'static + Clone + Sized,
     U: IMUSample<Untimed = T>,
 {
-    fn filter_batch(&mut self, samples: Vec<U>) -> Result<Vec<U>, &str> {
+    fn filter_batch(&mut self, samples: Vec<U>) -> Result<Vec<U>, ImuError> {
         if samples.is_empty() {
-            return Err("No samples to filter");
+            return Err(ImuError::EmptyInput);
         }
         let mut buffer: Vec<U> = Vec::with_capacity(1);
         let mut total_w = 0.0;
@@ -66,11 +122,15 @@ where
         for s in samples {
             let raw_samples = s.get_measurement();
             let sample_timestamp = s.get_timestamp_secs();
-            let w = (1.0 / ((sample_timestamp - self.mid_point).abs() + WEIGHTED_AVERAGE_EPS))
-                .powf(WEIGHTED_AVERAGE_ALPHA);
+            let w = self.kernel.weight(sample_timestamp - self.mid_point);
             aggregate += raw_samples * w;
             total_w += w;
         }
+        if total_w <= 0.0 {
+            return Err(ImuError::InvalidInput(
+                "No samples within the kernel support".to_string(),
+            ));
+        }
         aggregate = aggregate / total_w;
         buffer.push(U::from_measurement(self.mid_point, aggregate));
         Ok(buffer)
@@ -85,9 +145,9 @@ where
     fn filter_batch(
         &mut self,
         samples: Vec<SampleQuaternion>,
-    ) -> Result<Vec<SampleQuaternion>, &str> {
+    ) -> Result<Vec<SampleQuaternion>, ImuError> {
         if samples.is_empty() {
-            return Err("No samples to filter");
+            return Err(ImuError::EmptyInput);
         }
         let mut buffer: Vec<SampleQuaternion> = Vec::with_capacity(1);
         let mut total_w = 0.0;
@@ -96,11 +156,18 @@ where
         for s in samples {
             let raw_sample = s.get_measurement().inner();
             let timestamp = s.get_timestamp_secs();
-            let w = (1.0 / ((timestamp - self.mid_point).abs() + WEIGHTED_AVERAGE_EPS))
-                .powf(WEIGHTED_AVERAGE_ALPHA);
+            let w = self.kernel.weight(timestamp - self.mid_point);
+            if w <= 0.0 {
+                continue;
+            }
             aggregate = aggregate.slerp(&raw_sample, w / (total_w + w));
             total_w += w
         }
+        if total_w <= 0.0 {
+            return Err(ImuError::InvalidInput(
+                "No samples within the kernel support".to_string(),
+            ));
+        }
         buffer.push(SampleQuaternion::from_measurement(
             self.mid_point,
             UnitQuaternion::from_unit_quaternion(aggregate),
@@ -114,6 +181,7 @@ mod tests {
     use super::*;
     use crate::types::timed::sample_3d::Sample3D;
     use crate::types::untimed::XYZ;
+    use alloc::vec;
 
     #[test]
     fn test_weighted_moving_average_single_sample() {
@@ -142,7 +210,7 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "No samples to filter")]
+    #[should_panic(expected = "EmptyInput")]
     fn test_weighted_moving_average_no_samples() {
         let mut filter = WeightedAverage::new(5.0);
         let samples: Vec<Sample3D> = Vec::new();
@@ -164,4 +232,42 @@ mod tests {
         let smoothed_samples = filter.filter_batch(samples).unwrap();
         assert_eq!(smoothed_samples.len(), 1);
     }
+
+    #[test]
+    fn test_kernel_weights() {
+        let triangular = WeightingKernel::Triangular { half_width: 2.0 };
+        assert_eq!(triangular.weight(0.0), 1.0);
+        assert_eq!(triangular.weight(-1.0), 0.5);
+        assert_eq!(triangular.weight(3.0), 0.0);
+
+        let gaussian = WeightingKernel::Gaussian { sigma: 1.0 };
+        assert_eq!(gaussian.weight(0.0), 1.0);
+        assert!((gaussian.weight(1.0) - (-0.5f64).exp()).abs() < 1e-12);
+        assert_eq!(gaussian.weight(1.0), gaussian.weight(-1.0));
+
+        let inverse = WeightingKernel::InverseDistance {
+            eps: 1e-10,
+            alpha: 1.0,
+        };
+        assert!((inverse.weight(2.0) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_weighted_moving_average_triangular_kernel() {
+        let mut filter =
+            WeightedAverage::new(5.0).with_kernel(WeightingKernel::Triangular { half_width: 2.0 });
+        let samples = vec![
+            Sample3D::new(1.0, [100.0, 100.0, 100.0]),
+            Sample3D::new(4.0, [1.0, 2.0, 3.0]),
+            Sample3D::new(5.0, [4.0, 5.0, 6.0]),
+        ];
+        let smoothed_samples = filter.filter_batch(samples).unwrap();
+        // the first sample is outside the kernel, and the second one weights half the third one
+        let expected = XYZ::new([3.0, 4.0, 5.0]);
+        let error: Vec<f64> = (smoothed_samples[0].get_measurement() - expected).into();
+        assert!(error.iter().all(|e| e.abs() < 1e-9));
+
+        let samples = vec![Sample3D::new(1.0, [1.0, 2.0, 3.0])];
+        assert!(filter.filter_batch(samples).is_err());
+    }
 }
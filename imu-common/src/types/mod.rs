@@ -1,15 +1,33 @@
 pub mod buffers;
+#[cfg(feature = "std")]
 pub mod callback;
+pub mod capabilities;
+#[cfg(feature = "std")]
 pub mod clock;
+#[cfg(feature = "std")]
+pub mod control;
 pub mod filters;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
 pub mod sensors;
 pub mod timed;
 pub mod untimed;
 
 pub use crate::types::buffers::{CircularBuffer, CircularReader};
-pub use crate::types::callback::Callback;
-pub use crate::types::clock::Clock;
+#[cfg(feature = "std")]
+pub use crate::types::callback::{Callback, Liveness};
+pub use crate::types::capabilities::SampleKind;
+#[cfg(feature = "std")]
+pub use crate::types::capabilities::{SensorCapability, SinkRequirements, Unit};
+#[cfg(feature = "std")]
+pub use crate::types::clock::{Clock, ClockSource, SystemClock, VirtualClock};
+#[cfg(feature = "std")]
+pub use crate::types::control::ControlChannel;
 pub use crate::types::filters::{MovingAverage, WeightedAverage};
+#[cfg(feature = "std")]
+pub use crate::types::registry::{ParamValue, SourceParams, SourceRegistry};
+#[cfg(feature = "std")]
 pub use crate::types::sensors::{SensorReadings, SensorTag, SensorType};
-pub use crate::types::timed::{Sample3D, SampleQuaternion, SampleScalar};
-pub use crate::types::untimed::{Scalar, UnitQuaternion, XYZ};
+pub use crate::types::timed::{Sample3D, Sample6D, SampleEuler, SampleQuaternion, SampleScalar};
+pub use crate::types::untimed::{EulerAngles, RotationOrder, Scalar, UnitQuaternion, XYZPair, XYZ};
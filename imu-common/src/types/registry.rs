@@ -0,0 +1,303 @@
+//! Module registry
+//!
+//! Runtime registry of `IMUSource` implementations. Source crates register a factory under a
+//! name (e.g. `phyphox`, `mock`), and the configuration/CLI layer instantiates sources by that
+//! name, passing a [`SourceParams`] map with the source specific parameters.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::errors::ImuError;
+use crate::traits::{IMUReadings, IMUSample, IMUSource};
+use crate::types::sensors::SensorType;
+
+/// Value of a source parameter.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<ParamValue>),
+}
+
+impl From<bool> for ParamValue {
+    fn from(value: bool) -> Self {
+        ParamValue::Bool(value)
+    }
+}
+
+impl From<i64> for ParamValue {
+    fn from(value: i64) -> Self {
+        ParamValue::Int(value)
+    }
+}
+
+impl From<f64> for ParamValue {
+    fn from(value: f64) -> Self {
+        ParamValue::Float(value)
+    }
+}
+
+impl From<&str> for ParamValue {
+    fn from(value: &str) -> Self {
+        ParamValue::Str(value.to_string())
+    }
+}
+
+impl From<String> for ParamValue {
+    fn from(value: String) -> Self {
+        ParamValue::Str(value)
+    }
+}
+
+impl<V: Into<ParamValue>> From<Vec<V>> for ParamValue {
+    fn from(value: Vec<V>) -> Self {
+        ParamValue::List(value.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Named parameters passed to a source factory.
+///
+/// Getters return an error if the parameter is missing or has a different type. Integers are
+/// accepted where a float is expected.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourceParams {
+    params: BTreeMap<String, ParamValue>,
+}
+
+impl SourceParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with<V: Into<ParamValue>>(mut self, key: &str, value: V) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    pub fn insert<V: Into<ParamValue>>(&mut self, key: &str, value: V) {
+        self.params.insert(key.to_string(), value.into());
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.params.contains_key(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ParamValue> {
+        self.params.get(key)
+    }
+
+    /// Returns the parameters sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ParamValue)> {
+        self.params.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    fn require(&self, key: &str) -> Result<&ParamValue, ImuError> {
+        self.get(key)
+            .ok_or_else(|| ImuError::InvalidParameter(format!("Missing parameter {}", key)))
+    }
+
+    pub fn get_bool(&self, key: &str) -> Result<bool, ImuError> {
+        match self.require(key)? {
+            ParamValue::Bool(v) => Ok(*v),
+            other => Err(type_error(key, "bool", other)),
+        }
+    }
+
+    pub fn get_int(&self, key: &str) -> Result<i64, ImuError> {
+        match self.require(key)? {
+            ParamValue::Int(v) => Ok(*v),
+            other => Err(type_error(key, "int", other)),
+        }
+    }
+
+    pub fn get_float(&self, key: &str) -> Result<f64, ImuError> {
+        match self.require(key)? {
+            ParamValue::Float(v) => Ok(*v),
+            ParamValue::Int(v) => Ok(*v as f64),
+            other => Err(type_error(key, "float", other)),
+        }
+    }
+
+    pub fn get_str(&self, key: &str) -> Result<&str, ImuError> {
+        match self.require(key)? {
+            ParamValue::Str(v) => Ok(v),
+            other => Err(type_error(key, "string", other)),
+        }
+    }
+
+    /// Returns a list of sensors given as `kind::uuid` strings.
+    pub fn get_sensor_cluster(&self, key: &str) -> Result<Vec<SensorType>, ImuError> {
+        match self.require(key)? {
+            ParamValue::List(values) => values
+                .iter()
+                .map(|v| match v {
+                    ParamValue::Str(s) => {
+                        SensorType::try_from(s.as_str()).map_err(ImuError::InvalidParameter)
+                    }
+                    other => Err(type_error(key, "list of strings", other)),
+                })
+                .collect(),
+            other => Err(type_error(key, "list of strings", other)),
+        }
+    }
+}
+
+fn type_error(key: &str, expected: &str, found: &ParamValue) -> ImuError {
+    ImuError::InvalidParameter(format!(
+        "Parameter {} has invalid type. Expected {}, found {:?}",
+        key, expected, found
+    ))
+}
+
+/// Function that builds a source from its parameters.
+pub type SourceFactory<T, S> =
+    Arc<dyn Fn(&SourceParams) -> Result<Arc<dyn IMUSource<T, S>>, ImuError> + Send + Sync>;
+
+/// Collection of source factories indexed by name.
+#[derive(Clone)]
+pub struct SourceRegistry<T, S> {
+    factories: BTreeMap<String, SourceFactory<T, S>>,
+}
+
+impl<T, S> Default for SourceRegistry<T, S> {
+    fn default() -> Self {
+        Self {
+            factories: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T, S> SourceRegistry<T, S>
+where
+    T: Send + Sync + IMUReadings<S>,
+    S: Send + Sync + IMUSample,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `factory` under `name`. Names are case insensitive.
+    /// Returns an error if `name` is already registered.
+    pub fn register<F>(&mut self, name: &str, factory: F) -> Result<(), ImuError>
+    where
+        F: Fn(&SourceParams) -> Result<Arc<dyn IMUSource<T, S>>, ImuError> + Send + Sync + 'static,
+    {
+        let name = name.to_lowercase();
+        if self.factories.contains_key(&name) {
+            return Err(ImuError::InvalidParameter(format!(
+                "Source {} already registered",
+                name
+            )));
+        }
+        self.factories.insert(name, Arc::new(factory));
+        Ok(())
+    }
+
+    pub fn unregister(&mut self, name: &str) {
+        self.factories.remove(&name.to_lowercase());
+    }
+
+    /// Returns the registered names, sorted alphabetically.
+    pub fn get_available_sources(&self) -> Vec<String> {
+        self.factories.keys().cloned().collect()
+    }
+
+    /// Instantiates the source registered under `name`.
+    pub fn create(
+        &self,
+        name: &str,
+        params: &SourceParams,
+    ) -> Result<Arc<dyn IMUSource<T, S>>, ImuError> {
+        let factory = self
+            .factories
+            .get(&name.to_lowercase())
+            .ok_or_else(|| ImuError::InvalidParameter(format!("Unknown source {}", name)))?;
+        factory(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Notifiable;
+    use crate::types::sensors::SensorReadings;
+    use crate::types::timed::Sample3D;
+    use uuid::Uuid;
+
+    struct TestSource {
+        tag: String,
+        sensors: Vec<SensorType>,
+    }
+
+    impl IMUSource<SensorReadings<Sample3D>, Sample3D> for TestSource {
+        fn get_tag(&self) -> &str {
+            &self.tag
+        }
+        fn get_available_sensors(&self) -> Vec<SensorType> {
+            self.sensors.clone()
+        }
+        fn unregister_listener(&self, _id: Uuid) {}
+        fn register_listener(
+            &self,
+            _listener: &mut dyn Notifiable<SensorReadings<Sample3D>>,
+            _sensor_type: &SensorType,
+        ) -> Result<Uuid, ImuError> {
+            Ok(Uuid::new_v4())
+        }
+        fn notify_listeners(&self, _sensor_type: SensorType, _data: Arc<SensorReadings<Sample3D>>) {
+        }
+    }
+
+    fn registry() -> SourceRegistry<SensorReadings<Sample3D>, Sample3D> {
+        let mut registry = SourceRegistry::new();
+        registry
+            .register("test", |params| {
+                Ok(Arc::new(TestSource {
+                    tag: params.get_str("tag")?.to_string(),
+                    sensors: params.get_sensor_cluster("sensors")?,
+                }))
+            })
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_create_source() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let params = SourceParams::new()
+            .with("tag", "Left")
+            .with("sensors", vec![acc.to_string()]);
+
+        let source = registry().create("Test", &params).unwrap();
+
+        assert_eq!(source.get_tag(), "Left");
+        assert_eq!(source.get_available_sensors(), vec![acc]);
+    }
+
+    #[test]
+    fn test_unknown_and_duplicated_sources() {
+        let mut registry = registry();
+
+        assert!(registry.create("serial", &SourceParams::new()).is_err());
+        assert!(registry
+            .register("TEST", |_| Err(ImuError::Other("".to_string())))
+            .is_err());
+        assert_eq!(registry.get_available_sources(), vec!["test".to_string()]);
+    }
+
+    #[test]
+    fn test_param_types() {
+        let params = SourceParams::new()
+            .with("period", 100_i64)
+            .with("noise", true)
+            .with("sensors", vec![1_i64]);
+
+        assert_eq!(params.get_float("period"), Ok(100.0));
+        assert_eq!(params.get_bool("noise"), Ok(true));
+        assert!(params.get_int("noise").is_err());
+        assert!(params.get_str("missing").is_err());
+        assert!(params.get_sensor_cluster("sensors").is_err());
+    }
+}
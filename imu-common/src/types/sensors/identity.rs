@@ -0,0 +1,203 @@
+//! Module identity
+//!
+//! Sensor ids persisted per physical device, so a device gets the same `SensorType` ids every
+//! time the application runs, and recordings made on different days stay linked to it even if
+//! its clusters are built without a tag.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use super::{SensorClusterBuilder, SensorType};
+use crate::errors::ImuError;
+
+/// Tag and sensor ids of a device.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceIdentity {
+    pub tag: String,
+    /// Sensor ids by sensor kind, in lower case.
+    pub sensors: BTreeMap<String, String>,
+}
+
+impl DeviceIdentity {
+    /// Returns the id of the sensor of `kind`, if any.
+    pub fn get_sensor_id(&self, kind: &str) -> Option<Uuid> {
+        self.sensors
+            .get(&kind.to_lowercase())
+            .and_then(|id| Uuid::parse_str(id).ok())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Identities {
+    devices: BTreeMap<String, DeviceIdentity>,
+}
+
+/// Identities of the devices seen so far, keyed by a device id that is stable across runs,
+/// e.g. the unique id reported by a phyphox phone or the serial number of a board.
+///
+/// Stores opened from a file write every new identity back to it, as JSON.
+///
+/// ```
+/// use imu_common::types::sensors::{IdentityStore, SensorClusterBuilder};
+///
+/// let path = std::env::temp_dir().join(format!("identities_{}.json", std::process::id()));
+/// let mut store = IdentityStore::open(&path).unwrap();
+/// let cluster = store
+///     .resolve("phone-1234", "phone", SensorClusterBuilder::new().nine_axis())
+///     .unwrap();
+///
+/// // the next run gets the same ids, and the tag of the first run
+/// let mut store = IdentityStore::open(&path).unwrap();
+/// let builder = SensorClusterBuilder::new().nine_axis();
+/// assert_eq!(store.resolve("phone-1234", "renamed", builder).unwrap(), cluster);
+/// assert_eq!(store.get("phone-1234").unwrap().tag, "phone");
+/// # std::fs::remove_file(path).unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct IdentityStore {
+    path: Option<PathBuf>,
+    identities: Identities,
+}
+
+impl IdentityStore {
+    /// Creates a store kept in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the store persisted at `path`, or an empty one if the file doesn't exist yet.
+    /// Returns an Io error if the file can't be read, or an InvalidInput error if it isn't a
+    /// valid store.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ImuError> {
+        let path = path.as_ref();
+        let identities = match std::fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data).map_err(|e| {
+                ImuError::InvalidInput(format!("Identity store {}: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Identities::default(),
+            Err(e) => return Err(io_error(path, e)),
+        };
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            identities,
+        })
+    }
+
+    pub fn get(&self, device_id: &str) -> Option<&DeviceIdentity> {
+        self.identities.devices.get(device_id)
+    }
+
+    /// Returns the ids of the devices in the store, sorted.
+    pub fn get_device_ids(&self) -> Vec<String> {
+        self.identities.devices.keys().cloned().collect()
+    }
+
+    /// Returns the cluster of `device_id` with the sensors added to `builder`.
+    ///
+    /// Devices seen for the first time are stored with `tag`, and sensors without an id get a
+    /// random one, persisted before returning. The tag given to `builder` is ignored, and so is
+    /// `tag` once the device is known, so renaming a device doesn't change its ids.
+    pub fn resolve(
+        &mut self,
+        device_id: &str,
+        tag: &str,
+        builder: SensorClusterBuilder,
+    ) -> Result<Vec<SensorType>, ImuError> {
+        let mut identity = self
+            .get(device_id)
+            .cloned()
+            .unwrap_or_else(|| DeviceIdentity {
+                tag: tag.to_string(),
+                sensors: BTreeMap::new(),
+            });
+        let cluster = builder.build_with(|kind| {
+            identity.get_sensor_id(kind).unwrap_or_else(|| {
+                let id = Uuid::new_v4();
+                identity.sensors.insert(kind.to_string(), id.to_string());
+                id
+            })
+        })?;
+        if self.get(device_id) != Some(&identity) {
+            self.identities
+                .devices
+                .insert(device_id.to_string(), identity);
+            self.save()?;
+        }
+        Ok(cluster)
+    }
+
+    /// Removes `device_id` from the store, returning its identity.
+    pub fn forget(&mut self, device_id: &str) -> Result<Option<DeviceIdentity>, ImuError> {
+        let identity = self.identities.devices.remove(device_id);
+        if identity.is_some() {
+            self.save()?;
+        }
+        Ok(identity)
+    }
+
+    fn save(&self) -> Result<(), ImuError> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        let data = serde_json::to_string_pretty(&self.identities)
+            .map_err(|e| ImuError::Other(e.to_string()))?;
+        // written aside and renamed, so an interrupted write doesn't lose the previous ids
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, data).map_err(|e| io_error(&tmp_path, e))?;
+        std::fs::rename(&tmp_path, path).map_err(|e| io_error(path, e))
+    }
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> ImuError {
+    ImuError::Io(format!("Identity store {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_sensors() {
+        let mut store = IdentityStore::new();
+        let six_axis = store
+            .resolve("board", "imu", SensorClusterBuilder::new().six_axis())
+            .unwrap();
+        let nine_axis = store
+            .resolve("board", "imu", SensorClusterBuilder::new().nine_axis())
+            .unwrap();
+
+        assert_eq!(nine_axis[..2], six_axis[..]);
+        assert_eq!(store.get("board").unwrap().sensors.len(), 3);
+        // ids don't depend on the tag, unlike tagged clusters
+        assert_ne!(six_axis, SensorType::cluster_for_tag("imu")[..2]);
+    }
+
+    #[test]
+    fn test_distinct_devices() {
+        let mut store = IdentityStore::new();
+        let left = store
+            .resolve("left", "imu", SensorClusterBuilder::new().nine_axis())
+            .unwrap();
+        let right = store
+            .resolve("right", "imu", SensorClusterBuilder::new().nine_axis())
+            .unwrap();
+
+        assert!(left.iter().all(|sensor_type| !right.contains(sensor_type)));
+        assert_eq!(store.get_device_ids(), vec!["left", "right"]);
+        assert!(store.forget("left").unwrap().is_some());
+        assert!(store.get("left").is_none());
+    }
+
+    #[test]
+    fn test_invalid_store() {
+        let path = std::env::temp_dir().join(format!("invalid_identities_{}", Uuid::new_v4()));
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            IdentityStore::open(&path),
+            Err(ImuError::InvalidInput(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,7 +1,15 @@
+#[cfg(feature = "serde-serialize")]
+pub mod identity;
+pub mod sensor_cluster;
 pub mod sensor_readings;
 pub mod sensor_tag;
 pub mod sensor_type;
 
+#[cfg(feature = "serde-serialize")]
+pub use crate::types::sensors::identity::{DeviceIdentity, IdentityStore};
+pub use crate::types::sensors::sensor_cluster::{
+    check_nine_axis_cluster, check_six_axis_cluster, SensorClusterBuilder,
+};
 pub use crate::types::sensors::sensor_readings::SensorReadings;
 pub use crate::types::sensors::sensor_tag::SensorTag;
-pub use crate::types::sensors::sensor_type::SensorType;
+pub use crate::types::sensors::sensor_type::{SensorType, VendorKind};
@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::errors::ImuError;
+
+use super::SensorType;
+
+/// Builds sensor clusters with fresh ids.
+///
+/// Sensors get random ids, unless a tag is given with `with_tag`. In that case ids are derived
+/// from the tag, so the same tag always yields the same cluster and different tags never share
+/// a sensor.
+///
+/// # Examples
+///
+/// ```
+/// use imu_common::types::sensors::{SensorClusterBuilder, SensorType};
+///
+/// let cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
+/// assert!(matches!(cluster[0], SensorType::Accelerometer(_)));
+///
+/// let cluster = SensorClusterBuilder::new()
+///     .with_tag("Left")
+///     .six_axis()
+///     .other("Temperature")
+///     .build()
+///     .unwrap();
+/// assert_eq!(cluster[..2], SensorType::cluster_for_tag("Left")[..2]);
+///
+/// assert!(SensorClusterBuilder::new().accelerometer().accelerometer().build().is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SensorClusterBuilder {
+    tag: Option<String>,
+    kinds: Vec<String>,
+}
+
+impl SensorClusterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Derives sensor ids from `tag` instead of generating random ones.
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
+    pub fn accelerometer(self) -> Self {
+        self.other("accelerometer")
+    }
+
+    pub fn gyroscope(self) -> Self {
+        self.other("gyroscope")
+    }
+
+    pub fn magnetometer(self) -> Self {
+        self.other("magnetometer")
+    }
+
+    /// Adds an accelerometer and a gyroscope.
+    pub fn six_axis(self) -> Self {
+        self.accelerometer().gyroscope()
+    }
+
+    /// Adds an accelerometer, a gyroscope and a magnetometer.
+    pub fn nine_axis(self) -> Self {
+        self.six_axis().magnetometer()
+    }
+
+    /// Adds a sensor called `name`. Accelerometer, gyroscope and magnetometer names are mapped to
+    /// their own sensor type.
+    pub fn other(mut self, name: &str) -> Self {
+        self.kinds.push(name.to_string());
+        self
+    }
+
+    /// Adds a sensor of `kind` defined by a third-party crate under `namespace`.
+    pub fn vendor(self, namespace: &str, kind: &str) -> Self {
+        self.other(&format!("{}/{}", namespace, kind))
+    }
+
+    /// Returns the cluster. Fails if the cluster is empty, a sensor is repeated, or a vendor
+    /// sensor is malformed.
+    pub fn build(self) -> Result<Vec<SensorType>, ImuError> {
+        let tag = self.tag.clone();
+        self.build_with(|kind| match tag.as_ref() {
+            Some(tag) => tag_id(tag, kind),
+            None => Uuid::new_v4(),
+        })
+    }
+
+    /// Same as `build`, with the id of every sensor returned by `id` from its kind, in lower
+    /// case.
+    pub(crate) fn build_with<F>(self, mut id: F) -> Result<Vec<SensorType>, ImuError>
+    where
+        F: FnMut(&str) -> Uuid,
+    {
+        let cluster: Vec<SensorType> = self
+            .kinds
+            .iter()
+            .map(|kind| {
+                let id = id(&kind.to_lowercase());
+                let sensor = format!("{}::{}", kind, id);
+                SensorType::try_from(sensor.as_str()).map(|sensor_type| match sensor_type {
+                    // keep the friendly name as given
+                    SensorType::Other(id, _) => SensorType::Other(id, kind.clone()),
+                    sensor_type => sensor_type,
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map_err(ImuError::InvalidSensorCluster)?;
+        check_unique(&cluster)?;
+        Ok(cluster)
+    }
+}
+
+fn tag_id(tag: &str, kind: &str) -> Uuid {
+    Uuid::new_v5(
+        &Uuid::NAMESPACE_OID,
+        format!("{}/{}", tag, kind.to_lowercase()).as_bytes(),
+    )
+}
+
+/// Checks that the cluster isn't empty and every sensor kind appears once.
+pub fn check_unique(sensor_cluster: &[SensorType]) -> Result<(), ImuError> {
+    if sensor_cluster.is_empty() {
+        return Err(ImuError::InvalidSensorCluster(
+            "Empty sensor cluster".to_string(),
+        ));
+    }
+    let mut kinds = HashSet::new();
+    for sensor_type in sensor_cluster {
+        if !kinds.insert(sensor_type.kind().to_lowercase()) {
+            return Err(ImuError::InvalidSensorCluster(format!(
+                "Sensor {} repeated in cluster",
+                sensor_type.kind()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the cluster has exactly an accelerometer, a gyroscope and a magnetometer.
+pub fn check_nine_axis_cluster(sensor_cluster: &[SensorType]) -> Result<(), ImuError> {
+    check_kinds(
+        sensor_cluster,
+        &["accelerometer", "gyroscope", "magnetometer"],
+    )
+}
+
+/// Checks that the cluster has exactly an accelerometer and a gyroscope.
+pub fn check_six_axis_cluster(sensor_cluster: &[SensorType]) -> Result<(), ImuError> {
+    check_kinds(sensor_cluster, &["accelerometer", "gyroscope"])
+}
+
+fn check_kinds(sensor_cluster: &[SensorType], kinds: &[&str]) -> Result<(), ImuError> {
+    check_unique(sensor_cluster)?;
+    for sensor_type in sensor_cluster {
+        if !kinds.contains(&sensor_type.kind())
+            || matches!(sensor_type, SensorType::Other(..) | SensorType::Vendor(..))
+        {
+            return Err(ImuError::InvalidSensorCluster(format!(
+                "Unexpected sensor {} in cluster",
+                sensor_type
+            )));
+        }
+    }
+    if sensor_cluster.len() != kinds.len() {
+        return Err(ImuError::InvalidSensorCluster(format!(
+            "Expected {:?}",
+            kinds
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let cluster = SensorClusterBuilder::new()
+            .nine_axis()
+            .other("Temperature")
+            .build()
+            .unwrap();
+
+        assert!(matches!(cluster[1], SensorType::Gyroscope(_)));
+        assert!(matches!(&cluster[3], SensorType::Other(_, name) if name == "Temperature"));
+        assert!(check_nine_axis_cluster(&cluster).is_err());
+        assert!(check_nine_axis_cluster(&cluster[..3]).is_ok());
+        assert!(check_six_axis_cluster(&cluster[..2]).is_ok());
+    }
+
+    #[test]
+    fn test_tagged_clusters() {
+        let left = SensorClusterBuilder::new().with_tag("Left").nine_axis();
+
+        assert_eq!(left.clone().build(), left.build());
+        assert_ne!(
+            SensorType::cluster_for_tag("Left"),
+            SensorType::cluster_for_tag("Right")
+        );
+    }
+
+    #[test]
+    fn test_invalid_clusters() {
+        assert!(SensorClusterBuilder::new().build().is_err());
+        assert!(SensorClusterBuilder::new()
+            .nine_axis()
+            .gyroscope()
+            .build()
+            .is_err());
+        assert!(SensorClusterBuilder::new().other("a::b").build().is_err());
+        assert!(SensorClusterBuilder::new()
+            .vendor("", "baro")
+            .build()
+            .is_err());
+        let mixed = SensorClusterBuilder::new().six_axis().build().unwrap();
+        assert!(check_nine_axis_cluster(&mixed).is_err());
+    }
+
+    #[test]
+    fn test_vendor_sensors() {
+        let cluster = SensorClusterBuilder::new()
+            .accelerometer()
+            .vendor("acme", "accelerometer")
+            .vendor("initech", "accelerometer")
+            .build()
+            .unwrap();
+
+        assert!(matches!(cluster[0], SensorType::Accelerometer(_)));
+        assert_eq!(cluster[1].kind(), "acme/accelerometer");
+        assert_eq!(cluster[2].namespace(), Some("initech"));
+        assert!(SensorClusterBuilder::new()
+            .vendor("acme", "baro")
+            .vendor("ACME", "Baro")
+            .build()
+            .is_err());
+    }
+}
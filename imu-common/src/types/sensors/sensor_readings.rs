@@ -37,8 +37,8 @@ impl<T: IMUSample> SensorReadings<T> {
 }
 
 impl<T: IMUSample> IMUReadings<T> for SensorReadings<T> {
-    fn get_samples(&self) -> Vec<T> {
-        self.buffer.clone()
+    fn get_samples_ref(&self) -> &[T] {
+        &self.buffer
     }
     fn get_sensor_tag(&self) -> &str {
         self.tag.inner()
@@ -104,4 +104,17 @@ mod tests {
         assert_eq!(sensor.len(), 1);
         assert_eq!(sensor.get_samples()[0], sample);
     }
+
+    #[test]
+    fn test_sensor_borrow_samples() {
+        let samples = vec![Sample3D::new(0.0, [1.0, 2.0, 3.0]), Sample3D::default()];
+        let sensor = SensorReadings::from_vec(
+            "test_sensor",
+            SensorType::Accelerometer(Uuid::new_v4()),
+            samples.clone(),
+        );
+        assert_eq!(sensor.get_samples_ref(), samples.as_slice());
+        assert_eq!(sensor.iter().count(), 2);
+        assert_eq!(sensor.get_samples(), samples);
+    }
 }
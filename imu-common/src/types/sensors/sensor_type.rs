@@ -1,5 +1,106 @@
 use uuid::Uuid;
 
+use super::SensorClusterBuilder;
+use crate::errors::ImuError;
+
+/// Index space of `usize::from(SensorType)`. Every sensor kind owns a bin of `SENSOR_BINSIZE`
+/// indices:
+///
+/// | Indices         | Sensors                                         |
+/// |-----------------|-------------------------------------------------|
+/// | 0..1000         | accelerometers                                  |
+/// | 1000..2000      | gyroscopes                                      |
+/// | 2000..3000      | magnetometers                                   |
+/// | 3000..4000      | other sensors                                   |
+/// | 4000..MAX_OFFSET| vendor sensors, one bin per namespace           |
+///
+/// Vendor namespaces are assigned one of `N_VENDOR_BINS` bins by a stable hash of the
+/// namespace, so sensors of different crates don't share indices with the built-in kinds.
+///
+/// Deprecated: within a bin, the index is a byte sum of the uuid modulo `SENSOR_BINSIZE`, so two
+/// sensors of the same kind easily share an index. Sensors are ordered with `Ord` instead, which
+/// sorts by kind like the bins do, and never ties two different sensors.
+pub const SENSOR_BINSIZE: usize = 1000;
+pub const ACCELEROMETER_OFFSET: usize = 0;
+pub const GYROSCOPE_OFFSET: usize = SENSOR_BINSIZE;
+pub const MAGNETOMETER_OFFSET: usize = SENSOR_BINSIZE * 2;
+pub const OTHER_OFFSET: usize = SENSOR_BINSIZE * 3;
+pub const VENDOR_OFFSET: usize = SENSOR_BINSIZE * 4;
+pub const N_VENDOR_BINS: usize = 64;
+pub const MAX_OFFSET: usize = VENDOR_OFFSET + SENSOR_BINSIZE * N_VENDOR_BINS;
+
+const VENDOR_SEPARATOR: char = '/';
+
+/// Kind of a sensor defined by a third-party crate, qualified by the crate's namespace, e.g.
+/// `acme/barometer`. Namespaces and kinds are case insensitive, and stored in lower case.
+#[derive(Clone, Debug, PartialEq, PartialOrd, Hash, Eq, Ord)]
+pub struct VendorKind {
+    // `namespace/kind`
+    name: String,
+    separator: usize,
+}
+
+impl VendorKind {
+    /// Returns an error if `namespace` or `kind` are empty, or contain `/` or `::`.
+    pub fn new(namespace: &str, kind: &str) -> Result<Self, ImuError> {
+        for part in [namespace, kind] {
+            if part.is_empty() || part.contains(VENDOR_SEPARATOR) || part.contains("::") {
+                return Err(ImuError::InvalidParameter(format!(
+                    "Invalid vendor sensor {}/{}",
+                    namespace, kind
+                )));
+            }
+        }
+        // lower case may take a different number of bytes, e.g. for `İ`
+        let namespace = namespace.to_lowercase();
+        let kind = kind.to_lowercase();
+        Ok(Self {
+            name: format!("{}{}{}", namespace, VENDOR_SEPARATOR, kind),
+            separator: namespace.len(),
+        })
+    }
+
+    pub fn get_namespace(&self) -> &str {
+        &self.name[..self.separator]
+    }
+
+    pub fn get_kind(&self) -> &str {
+        &self.name[self.separator + 1..]
+    }
+
+    /// Returns the kind as `namespace/kind`.
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the index of the namespace bin, from a FNV-1a hash of the namespace that is
+    /// stable across builds and platforms.
+    fn get_bin(&self) -> usize {
+        let hash = self
+            .get_namespace()
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+        (hash % N_VENDOR_BINS as u64) as usize
+    }
+}
+
+impl TryFrom<&str> for VendorKind {
+    type Error = ImuError;
+
+    /// Parses `namespace/kind`.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.split_once(VENDOR_SEPARATOR) {
+            Some((namespace, kind)) => VendorKind::new(namespace, kind),
+            None => Err(ImuError::InvalidParameter(format!(
+                "Missing vendor namespace in {}",
+                value
+            ))),
+        }
+    }
+}
+
 /// Represents different types of sensors.
 ///
 /// # Variants
@@ -8,11 +109,13 @@ use uuid::Uuid;
 /// - `Gyroscope`: Represents a gyroscope sensor.
 /// - `Magnetometer`: Represents a magnetometer sensor.
 /// - `Other(String)`: Represents any other type of sensor, with a custom string description.
+/// - `Vendor(VendorKind)`: Represents a sensor defined by a third-party crate under its own
+///   namespace, written `namespace/kind`.
 ///
 /// # Examples
 ///
 /// ```
-/// use imu_common::types::sensors::SensorType;
+/// use imu_common::types::sensors::{SensorType, VendorKind};
 /// use uuid::Uuid;
 ///
 /// let sensor = SensorType::Accelerometer(Uuid::new_v4());
@@ -25,23 +128,28 @@ use uuid::Uuid;
 /// let other_id = Uuid::new_v4();
 /// let sensor = SensorType::try_from(format!("unknown::{}",other_id).as_str()).unwrap();
 /// assert_eq!(sensor, SensorType::Other(other_id, String::from("unknown")));
+///
+/// let vendor_id = Uuid::new_v4();
+/// let sensor = SensorType::try_from(format!("acme/barometer::{}", vendor_id).as_str()).unwrap();
+/// let kind = VendorKind::new("acme", "barometer").unwrap();
+/// assert_eq!(sensor, SensorType::Vendor(vendor_id, kind));
 /// ```
-
-pub const SENSOR_BINSIZE: usize = 1000;
-pub const ACCELEROMETER_OFFSET: usize = 0;
-pub const GYROSCOPE_OFFSET: usize = SENSOR_BINSIZE;
-pub const MAGNETOMETER_OFFSET: usize = SENSOR_BINSIZE * 2;
-pub const OTHER_OFFSET: usize = SENSOR_BINSIZE * 3;
-pub const MAX_OFFSET: usize = SENSOR_BINSIZE * 4;
-
-#[derive(Clone, Debug, PartialEq, PartialOrd, Hash, Eq)]
+///
+/// # Ordering
+///
+/// Sensors are ordered by kind (accelerometers, gyroscopes, magnetometers, other and vendor
+/// sensors), then by uuid and name. Different sensors never compare equal.
+#[derive(Clone, Debug, PartialEq, PartialOrd, Hash, Eq, Ord)]
 pub enum SensorType {
     Accelerometer(Uuid),
     Gyroscope(Uuid),
     Magnetometer(Uuid),
     Other(Uuid, String),
+    Vendor(Uuid, VendorKind),
 }
 
+/// Deprecated index of the sensor, see [`SENSOR_BINSIZE`]. Sensors of the same kind may share
+/// an index, so sort sensors with `Ord` instead.
 impl From<&SensorType> for usize {
     fn from(value: &SensorType) -> Self {
         let uuid_to_usize = |uuid: &Uuid| {
@@ -57,6 +165,11 @@ impl From<&SensorType> for usize {
                 MAGNETOMETER_OFFSET + uuid_to_usize(uuid) % SENSOR_BINSIZE
             }
             SensorType::Other(uuid, _) => OTHER_OFFSET + uuid_to_usize(uuid) % SENSOR_BINSIZE,
+            SensorType::Vendor(uuid, kind) => {
+                VENDOR_OFFSET
+                    + kind.get_bin() * SENSOR_BINSIZE
+                    + uuid_to_usize(uuid) % SENSOR_BINSIZE
+            }
         }
     }
 }
@@ -67,6 +180,53 @@ impl From<SensorType> for usize {
     }
 }
 
+impl SensorType {
+    /// Returns the sensor kind, i.e. the `kind` part of `kind::uuid`. Vendor sensors return
+    /// `namespace/kind`.
+    pub fn kind(&self) -> &str {
+        match self {
+            SensorType::Accelerometer(_) => "accelerometer",
+            SensorType::Gyroscope(_) => "gyroscope",
+            SensorType::Magnetometer(_) => "magnetometer",
+            SensorType::Other(_, name) => name,
+            SensorType::Vendor(_, kind) => kind.as_str(),
+        }
+    }
+
+    /// Returns the namespace of vendor sensors, or `None` for the built-in kinds.
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            SensorType::Vendor(_, kind) => Some(kind.get_namespace()),
+            _ => None,
+        }
+    }
+
+    /// Returns an accelerometer, gyroscope and magnetometer cluster for the device `tag`.
+    ///
+    /// Ids are derived from the tag, so the same tag always yields the same cluster and
+    /// different tags yield clusters that never share a sensor.
+    pub fn cluster_for_tag(tag: &str) -> Vec<SensorType> {
+        SensorClusterBuilder::new()
+            .with_tag(tag)
+            .nine_axis()
+            .build()
+            .expect("Nine axis cluster is valid")
+    }
+}
+
+/// Formats the sensor as `kind::uuid`, which can be parsed back with `SensorType::try_from`.
+impl std::fmt::Display for SensorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SensorType::Accelerometer(uuid)
+            | SensorType::Gyroscope(uuid)
+            | SensorType::Magnetometer(uuid)
+            | SensorType::Other(uuid, _)
+            | SensorType::Vendor(uuid, _) => write!(f, "{}::{}", self.kind(), uuid),
+        }
+    }
+}
+
 fn extract_sensor_and_id(value: &str) -> Result<(&str, &str), &'static str> {
     if let Some(index) = value.find("::") {
         let (part1, part2) = value.split_at(index);
@@ -94,7 +254,11 @@ impl TryFrom<&str> for SensorType {
             Ok((sensor_type, id)) => {
                 let id = get_sensor_id(id)?;
 
-                if sensor_type.contains("acc") {
+                // vendor kinds may contain the built-in kind names
+                if sensor_type.contains(VENDOR_SEPARATOR) {
+                    let kind = VendorKind::try_from(sensor_type).map_err(|e| e.to_string())?;
+                    Ok(Self::Vendor(id, kind))
+                } else if sensor_type.contains("acc") {
                     Ok(Self::Accelerometer(id))
                 } else if sensor_type.contains("gyr") {
                     Ok(Self::Gyroscope(id))
@@ -212,4 +376,83 @@ mod tests {
             SensorType::Other(other_id, String::from("other"))
         );
     }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let sensors = [
+            SensorType::Accelerometer(Uuid::new_v4()),
+            SensorType::Gyroscope(Uuid::new_v4()),
+            SensorType::Magnetometer(Uuid::new_v4()),
+            SensorType::Other(Uuid::new_v4(), String::from("temperature")),
+        ];
+        for sensor in sensors {
+            assert_eq!(SensorType::try_from(sensor.to_string()).unwrap(), sensor);
+        }
+    }
+
+    #[test]
+    fn test_vendor_sensor() {
+        let id = Uuid::new_v4();
+        let sensor = SensorType::try_from(format!("Acme/Accel::{}", id).as_str()).unwrap();
+        let kind = VendorKind::new("acme", "accel").unwrap();
+        assert_eq!(kind.get_namespace(), "acme");
+        assert_eq!(kind.get_kind(), "accel");
+        assert_eq!(sensor, SensorType::Vendor(id, kind));
+        assert_eq!(sensor.kind(), "acme/accel");
+        assert_eq!(sensor.namespace(), Some("acme"));
+        assert_eq!(SensorType::try_from(sensor.to_string()).unwrap(), sensor);
+
+        let index = usize::from(&sensor);
+        assert!((VENDOR_OFFSET..MAX_OFFSET).contains(&index));
+        // sensors of a namespace share its bin
+        let other = SensorType::Vendor(Uuid::new_v4(), VendorKind::new("acme", "gyro").unwrap());
+        assert_eq!(
+            (index - VENDOR_OFFSET) / SENSOR_BINSIZE,
+            (usize::from(&other) - VENDOR_OFFSET) / SENSOR_BINSIZE
+        );
+
+        assert!(VendorKind::new("", "baro").is_err());
+        assert!(VendorKind::new("acme", "baro/meter").is_err());
+        assert!(SensorType::try_from(format!("/baro::{}", id).as_str()).is_err());
+    }
+
+    #[test]
+    fn test_non_ascii_vendor_kind() {
+        // `İ` takes 2 bytes, and 3 in lower case
+        let kind = VendorKind::new("İmu", "Barometer").unwrap();
+        assert_eq!(kind.get_namespace(), "i\u{307}mu");
+        assert_eq!(kind.get_kind(), "barometer");
+        assert_eq!(kind.as_str(), "i\u{307}mu/barometer");
+
+        let id = Uuid::new_v4();
+        let sensor = SensorType::try_from(format!("İmu/Barometer::{}", id).as_str()).unwrap();
+        assert_eq!(sensor, SensorType::Vendor(id, kind));
+        assert_eq!(sensor.namespace(), Some("i\u{307}mu"));
+    }
+
+    #[test]
+    fn test_ordering() {
+        // uuids with the same byte sum share a deprecated index
+        let mut first = [0u8; 16];
+        let mut second = [0u8; 16];
+        first[0] = 1;
+        second[1] = 1;
+        let acc1 = SensorType::Accelerometer(Uuid::from_bytes(first));
+        let acc2 = SensorType::Accelerometer(Uuid::from_bytes(second));
+        assert_eq!(usize::from(&acc1), usize::from(&acc2));
+        assert_ne!(acc1.cmp(&acc2), std::cmp::Ordering::Equal);
+
+        let gyro = SensorType::Gyroscope(Uuid::nil());
+        let other = SensorType::Other(Uuid::nil(), "temperature".to_string());
+        let vendor = SensorType::Vendor(Uuid::nil(), VendorKind::new("acme", "baro").unwrap());
+        let mut sensors = vec![
+            vendor.clone(),
+            other.clone(),
+            acc2.clone(),
+            gyro.clone(),
+            acc1.clone(),
+        ];
+        sensors.sort();
+        assert_eq!(sensors, vec![acc2, acc1, gyro, other, vendor]);
+    }
 }
@@ -1,14 +1,22 @@
-use crate::traits::IMUSample;
+use crate::traits::{Float, IMUSample};
+use crate::types::capabilities::SampleKind;
 use crate::types::untimed::{xyz::N_XYZ_COORDINATES, XYZ};
+use alloc::vec::Vec;
 
-#[cfg(any(feature = "serde-serialize", test))]
-use nalgebra::Vector3;
 #[cfg(any(feature = "serde-serialize", test))]
 use serde::{Deserialize, Deserializer, Serialize};
 #[cfg(any(feature = "serde-serialize", test))]
 use serde_json::Value;
 
-/// A structure representing a 3D sample with a timestamp and measurement.
+const TIMESTAMP_IDX: usize = 0;
+const X_COORD_IDX: usize = 1;
+#[allow(dead_code)]
+const Y_COORD_IDX: usize = 2;
+#[allow(dead_code)]
+const Z_COORD_IDX: usize = 3;
+
+/// A structure representing a 3D sample with a timestamp and measurement, in `f64` unless
+/// another [`Float`] type is given, e.g. `Sample3D<f32>`.
 ///
 /// # Examples
 ///
@@ -24,25 +32,17 @@ use serde_json::Value;
 /// assert_eq!(sample.get_timestamp_secs(), timestamp);
 /// assert_eq!(sample.get_measurement(), XYZ::from(measurement));
 /// ```
-
-const TIMESTAMP_IDX: usize = 0;
-const X_COORD_IDX: usize = 1;
-#[allow(dead_code)]
-const Y_COORD_IDX: usize = 2;
-#[allow(dead_code)]
-const Z_COORD_IDX: usize = 3;
-
 #[cfg_attr(any(feature = "serde-serialize", test), derive(Serialize))]
 #[derive(Debug, Clone, Default, PartialEq, PartialOrd)]
-pub struct Sample3D {
+pub struct Sample3D<F: Float = f64> {
     timestamp: f64,
-    measurement: XYZ,
+    measurement: XYZ<F>,
 }
 
 /// Represents a 3D sample with a timestamp and a measurement.
-impl Sample3D {
+impl<F: Float> Sample3D<F> {
     ///  Creates a new `Sample3D` instance from a timestamp and a measurement array.
-    pub fn new(timestamp: f64, measurement: [f64; N_XYZ_COORDINATES]) -> Self {
+    pub fn new(timestamp: f64, measurement: [F; N_XYZ_COORDINATES]) -> Self {
         Self {
             timestamp,
             measurement: XYZ::new(measurement),
@@ -50,15 +50,16 @@ impl Sample3D {
     }
 
     /// Creates a new `Sample3D` instance from a timestamp and an `XYZ` measurement.
-    pub fn from_xyz(timestamp: f64, measurement: XYZ) -> Self {
+    pub fn from_xyz(timestamp: f64, measurement: XYZ<F>) -> Self {
         Self {
             timestamp,
             measurement,
         }
     }
 }
-impl IMUSample for Sample3D {
-    type Untimed = XYZ;
+impl<F: Float> IMUSample for Sample3D<F> {
+    type Untimed = XYZ<F>;
+    const KIND: SampleKind = SampleKind::Vector3D;
 
     fn get_measurement(&self) -> Self::Untimed {
         self.measurement.clone()
@@ -76,7 +77,7 @@ impl IMUSample for Sample3D {
     }
 }
 
-impl TryFrom<Vec<f64>> for Sample3D {
+impl<F: Float> TryFrom<Vec<f64>> for Sample3D<F> {
     type Error = &'static str;
 
     fn try_from(value: Vec<f64>) -> Result<Self, Self::Error> {
@@ -92,7 +93,7 @@ impl TryFrom<Vec<f64>> for Sample3D {
 }
 
 #[cfg(any(feature = "serde-serialize", test))]
-impl<'de> Deserialize<'de> for Sample3D {
+impl<'de, F: Float> Deserialize<'de> for Sample3D<F> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
@@ -111,7 +112,7 @@ impl<'de> Deserialize<'de> for Sample3D {
             // Try to extract the "measurement" field and deserialize it using XYZ's deserializer
             if let Some(measurement_value) = obj.get("measurement") {
                 // Deserialize the measurement using XYZ's deserializer
-                let measurement: XYZ = serde_json::from_value(measurement_value.clone())
+                let measurement: XYZ<F> = serde_json::from_value(measurement_value.clone())
                     .map_err(serde::de::Error::custom)?;
 
                 // Return the deserialized Sample3D
@@ -139,7 +140,7 @@ impl<'de> Deserialize<'de> for Sample3D {
             // We expect exactly 4 values (timestamp + 3 values for XYZ)
             if parts.len() == 4 {
                 let timestamp = parts[0];
-                let measurement = XYZ(Vector3::new(parts[1], parts[2], parts[3]));
+                let measurement = XYZ::new([parts[1], parts[2], parts[3]].map(F::cast_from_f64));
 
                 return Ok(Sample3D {
                     timestamp,
@@ -155,6 +156,7 @@ impl<'de> Deserialize<'de> for Sample3D {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
     #[cfg(any(feature = "serde-serialize", test))]
     use serde_json;
 
@@ -190,6 +192,19 @@ mod tests {
         assert_eq!(sample.get_timestamp_secs(), timestamp);
     }
 
+    #[test]
+    fn test_f32_sample() {
+        let sample = Sample3D::<f32>::new(1.5, [1.0, 2.0, 3.0]);
+        assert_eq!(sample.get_timestamp_secs(), 1.5);
+        assert_eq!(sample.get_measurement().inner(), [1.0f32, 2.0, 3.0]);
+        assert_eq!(std::mem::size_of_val(&sample.get_measurement()), 12);
+
+        let sample = Sample3D::<f32>::try_from(vec![1.5, 1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(sample, Sample3D::new(1.5, [1.0, 2.0, 3.0]));
+        let deserialized: Sample3D<f32> = serde_json::from_str(r#""1.5, 1.0, 2.0, 3.0""#).unwrap();
+        assert_eq!(deserialized, sample);
+    }
+
     #[cfg(any(feature = "serde-serialize", test))]
     #[test]
     fn test_sample_serialize() {
@@ -254,7 +269,7 @@ mod tests {
     #[test]
     fn test_try_from_vec_invalid_length() {
         let data = vec![1627846267.0, 1.0, 2.0];
-        let result = Sample3D::try_from(data);
+        let result = Sample3D::<f64>::try_from(data);
 
         assert!(result.is_err());
         assert_eq!(result.err(), Some("Invalid length of input vector"));
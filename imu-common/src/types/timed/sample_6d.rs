@@ -0,0 +1,138 @@
+use crate::traits::IMUSample;
+use crate::types::capabilities::SampleKind;
+use crate::types::timed::Sample3D;
+use crate::types::untimed::xyz_pair::{XYZPair, N_XYZ_PAIR_COORDINATES};
+use crate::types::untimed::XYZ;
+use alloc::vec::Vec;
+
+#[cfg(any(feature = "serde-serialize", test))]
+use serde::{Deserialize, Serialize};
+
+const TIMESTAMP_IDX: usize = 0;
+
+/// A structure representing synchronized accelerometer and gyroscope readings with a
+/// timestamp, as delivered in a single packet by many IMUs.
+///
+/// # Examples
+///
+/// ```
+/// use imu_common::types::timed::Sample6D;
+/// use imu_common::traits::IMUSample;
+///
+/// let sample = Sample6D::new(1.5, [0.0, 0.0, 9.81, 0.01, 0.0, -0.02]);
+/// let (accel, gyro) = sample.split();
+///
+/// assert_eq!(accel.get_timestamp_secs(), 1.5);
+/// assert_eq!(gyro.get_measurement().inner(), [0.01, 0.0, -0.02]);
+/// ```
+#[cfg_attr(any(feature = "serde-serialize", test), derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd)]
+pub struct Sample6D {
+    timestamp: f64,
+    measurement: XYZPair,
+}
+
+impl Sample6D {
+    /// Creates a new `Sample6D` from a timestamp and `[ax, ay, az, gx, gy, gz]`.
+    pub fn new(timestamp: f64, measurement: [f64; N_XYZ_PAIR_COORDINATES]) -> Self {
+        Self {
+            timestamp,
+            measurement: XYZPair::new(measurement),
+        }
+    }
+
+    /// Creates a new `Sample6D` from a timestamp and the accelerometer and gyroscope readings.
+    pub fn from_xyz(timestamp: f64, accel: XYZ, gyro: XYZ) -> Self {
+        Self {
+            timestamp,
+            measurement: XYZPair::from_xyz(accel, gyro),
+        }
+    }
+
+    /// Returns the accelerometer and gyroscope readings as separate samples, for sinks
+    /// consuming `Sample3D` readings.
+    pub fn split(&self) -> (Sample3D, Sample3D) {
+        (
+            Sample3D::from_xyz(self.timestamp, self.measurement.get_accel()),
+            Sample3D::from_xyz(self.timestamp, self.measurement.get_gyro()),
+        )
+    }
+}
+
+impl IMUSample for Sample6D {
+    type Untimed = XYZPair;
+    const KIND: SampleKind = SampleKind::Vector6D;
+
+    fn get_measurement(&self) -> Self::Untimed {
+        self.measurement.clone()
+    }
+
+    fn get_timestamp_secs(&self) -> f64 {
+        self.timestamp
+    }
+
+    fn from_measurement(timestamp: f64, measurement: Self::Untimed) -> Self {
+        Self {
+            timestamp,
+            measurement,
+        }
+    }
+}
+
+impl TryFrom<Vec<f64>> for Sample6D {
+    type Error = &'static str;
+
+    fn try_from(value: Vec<f64>) -> Result<Self, Self::Error> {
+        if value.len() != N_XYZ_PAIR_COORDINATES + 1 {
+            return Err("Invalid length of input vector");
+        }
+        let measurement = XYZPair::try_from(value[TIMESTAMP_IDX + 1..].to_vec())?;
+        Ok(Sample6D::from_measurement(
+            value[TIMESTAMP_IDX],
+            measurement,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    #[cfg(any(feature = "serde-serialize", test))]
+    use serde_json;
+
+    #[test]
+    fn test_sample_new() {
+        let sample = Sample6D::new(2.0, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+        assert_eq!(sample.get_timestamp_secs(), 2.0);
+        assert_eq!(
+            sample.get_measurement(),
+            XYZPair::from_xyz(XYZ::new([1.0, 2.0, 3.0]), XYZ::new([4.0, 5.0, 6.0]))
+        );
+        let (accel, gyro) = sample.split();
+        assert_eq!(accel, Sample3D::new(2.0, [1.0, 2.0, 3.0]));
+        assert_eq!(gyro, Sample3D::new(2.0, [4.0, 5.0, 6.0]));
+    }
+
+    #[test]
+    fn test_try_from_vec() {
+        let sample = Sample6D::try_from(vec![2.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+        assert_eq!(sample, Sample6D::new(2.0, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]));
+
+        let result = Sample6D::try_from(vec![2.0, 1.0, 2.0, 3.0]);
+        assert_eq!(result.err(), Some("Invalid length of input vector"));
+    }
+
+    #[cfg(any(feature = "serde-serialize", test))]
+    #[test]
+    fn test_sample_serde() {
+        let sample = Sample6D::new(2.0, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let serialized = serde_json::to_string(&sample).unwrap();
+        let expected = r#"{"timestamp":2.0,"measurement":{"accel":{"x":1.0,"y":2.0,"z":3.0},"gyro":{"x":4.0,"y":5.0,"z":6.0}}}"#;
+        assert_eq!(serialized, expected);
+
+        let deserialized: Sample6D = serde_json::from_str(expected).unwrap();
+        assert_eq!(deserialized, sample);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::traits::IMUSample;
+use crate::types::capabilities::SampleKind;
+use crate::types::timed::SampleQuaternion;
+use crate::types::untimed::euler_angles::N_EULER_COORDINATES;
+use crate::types::untimed::{EulerAngles, RotationOrder};
+use alloc::vec::Vec;
+
+#[cfg(any(feature = "serde-serialize", test))]
+use serde::{Deserialize, Deserializer, Serialize};
+#[cfg(any(feature = "serde-serialize", test))]
+use serde_json::Value;
+
+const TIMESTAMP_IDX: usize = 0;
+
+/// A structure representing an orientation as roll, pitch and yaw angles with a timestamp,
+/// for sinks displaying or logging human-readable orientations.
+///
+/// # Examples
+///
+/// ```
+/// use imu_common::types::timed::{SampleEuler, SampleQuaternion};
+/// use imu_common::types::untimed::RotationOrder;
+/// use imu_common::traits::IMUSample;
+///
+/// let quaternion = SampleQuaternion::new(2.0, [0.7071068, 0.0, 0.0, 0.7071068]);
+/// let sample = SampleEuler::from_quaternion(&quaternion, RotationOrder::ZYX);
+///
+/// assert_eq!(sample.get_timestamp_secs(), 2.0);
+/// assert!((sample.get_measurement().to_degrees()[2] - 90.0).abs() < 1e-6);
+/// ```
+#[cfg_attr(any(feature = "serde-serialize", test), derive(Serialize))]
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd)]
+pub struct SampleEuler {
+    timestamp: f64,
+    angles: EulerAngles,
+}
+
+impl SampleEuler {
+    /// Creates a new `SampleEuler` from a timestamp and `[roll, pitch, yaw]` in radians.
+    pub fn new(timestamp: f64, angles: [f64; N_EULER_COORDINATES]) -> Self {
+        Self {
+            timestamp,
+            angles: EulerAngles::new(angles),
+        }
+    }
+
+    /// Returns the angles of `sample` when rotations are applied in `order`.
+    pub fn from_quaternion(sample: &SampleQuaternion, order: RotationOrder) -> Self {
+        Self {
+            timestamp: sample.get_timestamp_secs(),
+            angles: EulerAngles::from_unit_quaternion(&sample.get_measurement(), order),
+        }
+    }
+
+    /// Returns the orientation of applying the angles in `order`.
+    pub fn to_quaternion(&self, order: RotationOrder) -> SampleQuaternion {
+        SampleQuaternion::from_unit_quaternion(
+            self.timestamp,
+            self.angles.to_unit_quaternion(order),
+        )
+    }
+}
+
+impl IMUSample for SampleEuler {
+    type Untimed = EulerAngles;
+    const KIND: SampleKind = SampleKind::Euler;
+
+    fn get_measurement(&self) -> Self::Untimed {
+        self.angles.clone()
+    }
+
+    fn get_timestamp_secs(&self) -> f64 {
+        self.timestamp
+    }
+
+    fn from_measurement(timestamp: f64, measurement: Self::Untimed) -> Self {
+        Self {
+            timestamp,
+            angles: measurement,
+        }
+    }
+}
+
+/// Angles of the orientation applied in `ZYX` order.
+impl From<SampleQuaternion> for SampleEuler {
+    fn from(value: SampleQuaternion) -> Self {
+        Self::from_quaternion(&value, RotationOrder::ZYX)
+    }
+}
+
+/// Orientation of applying the angles in `ZYX` order.
+impl From<SampleEuler> for SampleQuaternion {
+    fn from(value: SampleEuler) -> Self {
+        value.to_quaternion(RotationOrder::ZYX)
+    }
+}
+
+impl TryFrom<Vec<f64>> for SampleEuler {
+    type Error = &'static str;
+
+    fn try_from(value: Vec<f64>) -> Result<Self, Self::Error> {
+        if value.len() != N_EULER_COORDINATES + 1 {
+            return Err("Invalid length of input vector");
+        }
+        let measurement = EulerAngles::try_from(value[TIMESTAMP_IDX + 1..].to_vec())?;
+        Ok(SampleEuler::from_measurement(
+            value[TIMESTAMP_IDX],
+            measurement,
+        ))
+    }
+}
+
+#[cfg(any(feature = "serde-serialize", test))]
+impl<'de> Deserialize<'de> for SampleEuler {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value: Value = Value::deserialize(deserializer)?;
+
+        // Handle the case where the input is an object with a "timestamp" field and an "angles" field
+        if let Some(obj) = value.as_object() {
+            let timestamp = obj
+                .get("timestamp")
+                .and_then(Value::as_f64)
+                .unwrap_or_default();
+
+            if let Some(angles_value) = obj.get("angles") {
+                let angles: EulerAngles = serde_json::from_value(angles_value.clone())
+                    .map_err(serde::de::Error::custom)?;
+                return Ok(SampleEuler::from_measurement(timestamp, angles));
+            }
+        }
+
+        // Handle the comma-separated string format like "1.2, 0.1, 0.2, 0.3"
+        if let Some(scalar_str) = value.as_str() {
+            let mut parts: Vec<f64> = scalar_str
+                .split(',')
+                .filter_map(|s| s.trim().parse().ok())
+                .collect();
+            if parts.len() <= N_EULER_COORDINATES + 1 {
+                parts.resize(N_EULER_COORDINATES + 1, 0.0);
+                return Ok(SampleEuler::new(
+                    parts[TIMESTAMP_IDX],
+                    [parts[1], parts[2], parts[3]],
+                ));
+            }
+        }
+
+        Err(serde::de::Error::custom("Invalid format for SampleEuler"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use nalgebra::UnitQuaternion as NUnitQuaternion;
+
+    #[test]
+    fn test_quaternion_conversions() {
+        let quaternion = SampleQuaternion::from_unit_quaternion(
+            1.5,
+            NUnitQuaternion::from_euler_angles(0.1, 0.2, 0.3).into(),
+        );
+        let sample = SampleEuler::from(quaternion.clone());
+        assert_eq!(sample.get_timestamp_secs(), 1.5);
+        for (angle, expected) in sample.get_measurement().inner().iter().zip([0.1, 0.2, 0.3]) {
+            assert!((angle - expected).abs() < 1e-9);
+        }
+
+        let back = SampleQuaternion::from(sample);
+        assert_eq!(back.get_timestamp_secs(), 1.5);
+        assert!(
+            back.get_measurement()
+                .inner()
+                .angle_to(&quaternion.get_measurement().inner())
+                < 1e-12
+        );
+
+        let sample = SampleEuler::from_quaternion(&quaternion, RotationOrder::XYZ);
+        let back = sample.to_quaternion(RotationOrder::XYZ);
+        assert!(
+            back.get_measurement()
+                .inner()
+                .angle_to(&quaternion.get_measurement().inner())
+                < 1e-12
+        );
+    }
+
+    #[test]
+    fn test_try_from_vec() {
+        let sample = SampleEuler::try_from(vec![2.0, 0.1, 0.2, 0.3]).unwrap();
+        assert_eq!(sample, SampleEuler::new(2.0, [0.1, 0.2, 0.3]));
+
+        let result = SampleEuler::try_from(vec![2.0, 0.1, 0.2]);
+        assert_eq!(result.err(), Some("Invalid length of input vector"));
+    }
+
+    #[test]
+    fn test_sample_serde() {
+        let sample = SampleEuler::new(2.0, [0.1, 0.2, 0.3]);
+        let serialized = serde_json::to_string(&sample).unwrap();
+        let expected = r#"{"timestamp":2.0,"angles":{"pitch":0.2,"roll":0.1,"yaw":0.3}}"#;
+        assert_eq!(serialized, expected);
+
+        let deserialized: SampleEuler = serde_json::from_str(expected).unwrap();
+        assert_eq!(deserialized, sample);
+    }
+
+    #[test]
+    fn test_sample_deserialize_no_labels() {
+        let sample: SampleEuler = serde_json::from_str(r#""2.0, 0.1, 0.2, 0.3""#).unwrap();
+        assert_eq!(sample, SampleEuler::new(2.0, [0.1, 0.2, 0.3]));
+
+        let sample: SampleEuler = serde_json::from_str(r#""2.0, 0.1""#).unwrap();
+        assert_eq!(sample, SampleEuler::new(2.0, [0.1, 0.0, 0.0]));
+
+        let result: Result<SampleEuler, _> = serde_json::from_str(r#""2.0, 0.1, 0.2, 0.3, 0.4""#);
+        assert!(result.is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use crate::traits::IMUSample;
+use crate::types::capabilities::SampleKind;
 use crate::types::untimed::unit_quaternion::{N_QUATERNION_COORDINATES, W_QUATERNION_COORD_IDX};
 use crate::types::untimed::UnitQuaternion;
+use alloc::vec::Vec;
 
 #[cfg(any(feature = "serde-serialize", test))]
 use crate::types::untimed::unit_quaternion::{
@@ -11,6 +13,8 @@ use serde::{Deserialize, Deserializer, Serialize};
 #[cfg(any(feature = "serde-serialize", test))]
 use serde_json::Value;
 
+const TIMESTAMP_IDX: usize = 0;
+
 /// A structure representing a quaternion sample with a timestamp.
 ///
 /// # Examples
@@ -27,9 +31,6 @@ use serde_json::Value;
 /// assert_eq!(sample.get_timestamp_secs(), timestamp);
 /// assert_eq!(sample.get_measurement(), UnitQuaternion::from(measurement));
 /// ```
-
-const TIMESTAMP_IDX: usize = 0;
-
 #[cfg_attr(any(feature = "serde-serialize", test), derive(Serialize))]
 #[derive(Debug, Clone, Default)]
 pub struct SampleQuaternion {
@@ -56,6 +57,7 @@ impl SampleQuaternion {
 
 impl IMUSample for SampleQuaternion {
     type Untimed = UnitQuaternion;
+    const KIND: SampleKind = SampleKind::Quaternion;
 
     ///  Returns the quaternion measurement as a vector.
     fn get_measurement(&self) -> Self::Untimed {
@@ -159,6 +161,7 @@ impl<'de> Deserialize<'de> for SampleQuaternion {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn test_sample_new() {
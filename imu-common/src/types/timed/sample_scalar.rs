@@ -1,6 +1,9 @@
 use crate::traits::IMUSample;
+use crate::types::capabilities::SampleKind;
 use crate::types::untimed::Scalar;
 
+#[cfg(any(feature = "serde-serialize", test))]
+use alloc::vec::Vec;
 #[cfg(any(feature = "serde-serialize", test))]
 use serde::{Deserialize, Deserializer, Serialize};
 #[cfg(any(feature = "serde-serialize", test))]
@@ -22,7 +25,6 @@ use serde_json::Value;
 /// assert_eq!(sample.get_timestamp_secs(), timestamp);
 /// assert_eq!(sample.get_measurement(), Scalar::from(measurement));
 /// ```
-
 #[cfg_attr(any(feature = "serde-serialize", test), derive(Serialize))]
 #[derive(Debug, Clone, Default, PartialEq, PartialOrd)]
 pub struct SampleScalar {
@@ -51,6 +53,7 @@ impl SampleScalar {
 
 impl IMUSample for SampleScalar {
     type Untimed = Scalar;
+    const KIND: SampleKind = SampleKind::Scalar;
 
     fn get_measurement(&self) -> Self::Untimed {
         self.measurement.clone()
@@ -132,6 +135,7 @@ impl<'de> Deserialize<'de> for SampleScalar {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::format;
 
     #[test]
     fn test_sample_scalar_new() {
@@ -0,0 +1,324 @@
+use alloc::vec::Vec;
+use core::f64::consts::PI;
+#[cfg(not(any(feature = "std", test)))]
+use nalgebra::{ComplexField, RealField};
+use nalgebra::{UnitQuaternion as NUnitQuaternion, Vector3};
+
+#[cfg(any(feature = "serde-serialize", test))]
+use serde::{Deserialize, Deserializer, Serialize};
+#[cfg(any(feature = "serde-serialize", test))]
+use serde_json::Value;
+
+use crate::traits::IMUUntimedSample;
+use crate::types::untimed::UnitQuaternion;
+
+pub const N_EULER_COORDINATES: usize = 3;
+
+/// Order in which the rotations around the axes of the body are applied, e.g. `ZYX` rotates
+/// around z by yaw, then around the new y by pitch and finally around the new x by roll.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum RotationOrder {
+    XYZ,
+    XZY,
+    YXZ,
+    YZX,
+    ZXY,
+    /// Yaw, pitch and roll, as used in aeronautics and by `nalgebra::UnitQuaternion::from_euler_angles`.
+    #[default]
+    ZYX,
+}
+
+impl RotationOrder {
+    /// Returns the indices of the axes in the order the rotations are applied.
+    fn axes(&self) -> [usize; 3] {
+        match self {
+            RotationOrder::XYZ => [0, 1, 2],
+            RotationOrder::XZY => [0, 2, 1],
+            RotationOrder::YXZ => [1, 0, 2],
+            RotationOrder::YZX => [1, 2, 0],
+            RotationOrder::ZXY => [2, 0, 1],
+            RotationOrder::ZYX => [2, 1, 0],
+        }
+    }
+}
+
+/// Roll, pitch and yaw angles in radians, around the x, y and z axes of the body.
+#[derive(Clone, Debug, PartialEq, PartialOrd, Default)]
+pub struct EulerAngles {
+    roll: f64,
+    pitch: f64,
+    yaw: f64,
+}
+
+impl EulerAngles {
+    /// Creates angles from `[roll, pitch, yaw]` in radians.
+    pub fn new(data: [f64; N_EULER_COORDINATES]) -> Self {
+        Self {
+            roll: data[0],
+            pitch: data[1],
+            yaw: data[2],
+        }
+    }
+
+    /// Creates angles from `[roll, pitch, yaw]` in degrees.
+    pub fn from_degrees(data: [f64; N_EULER_COORDINATES]) -> Self {
+        Self::new(data.map(f64::to_radians))
+    }
+
+    /// Returns the angles of `quaternion` when rotations are applied in `order`.
+    ///
+    /// At gimbal lock, when the middle rotation is ±90°, only the sum or difference of the
+    /// other two angles is defined, and the last one is set to 0.
+    pub fn from_unit_quaternion(quaternion: &UnitQuaternion, order: RotationOrder) -> Self {
+        let q = quaternion.inner();
+        let [a, b, c] = order.axes();
+        // sign of the permutation of the axes
+        let sign = if (b + 3 - a) % 3 == 1 { 1.0 } else { -1.0 };
+        let m = q.to_rotation_matrix().into_inner();
+
+        let mut angles = [0.0; N_EULER_COORDINATES];
+        let sin_b = (sign * m[(a, c)]).clamp(-1.0, 1.0);
+        angles[b] = sin_b.asin();
+        if 1.0 - sin_b.abs() > 1e-10 {
+            angles[a] = (-sign * m[(b, c)]).atan2(m[(c, c)]);
+            angles[c] = (-sign * m[(a, b)]).atan2(m[(a, a)]);
+        } else {
+            let first = q * axis_rotation(b, angles[b]).inverse();
+            let angle = 2.0 * first.quaternion().imag()[a].atan2(first.quaternion().w);
+            // wrapped to [-π, π)
+            angles[a] = if angle >= PI {
+                angle - 2.0 * PI
+            } else if angle < -PI {
+                angle + 2.0 * PI
+            } else {
+                angle
+            };
+        }
+        Self::new(angles)
+    }
+
+    /// Returns the rotation of applying the angles in `order`.
+    pub fn to_unit_quaternion(&self, order: RotationOrder) -> UnitQuaternion {
+        let angles = self.inner();
+        let [a, b, c] = order.axes();
+        UnitQuaternion::from_unit_quaternion(
+            axis_rotation(a, angles[a]) * axis_rotation(b, angles[b]) * axis_rotation(c, angles[c]),
+        )
+    }
+
+    pub fn get_roll(&self) -> f64 {
+        self.roll
+    }
+
+    pub fn get_pitch(&self) -> f64 {
+        self.pitch
+    }
+
+    pub fn get_yaw(&self) -> f64 {
+        self.yaw
+    }
+
+    /// Returns `[roll, pitch, yaw]` in radians.
+    pub fn inner(&self) -> [f64; N_EULER_COORDINATES] {
+        [self.roll, self.pitch, self.yaw]
+    }
+
+    /// Returns `[roll, pitch, yaw]` in degrees.
+    pub fn to_degrees(&self) -> [f64; N_EULER_COORDINATES] {
+        self.inner().map(f64::to_degrees)
+    }
+}
+
+fn axis_rotation(axis: usize, angle: f64) -> NUnitQuaternion<f64> {
+    let mut scaled_axis = Vector3::zeros();
+    scaled_axis[axis] = angle;
+    NUnitQuaternion::from_scaled_axis(scaled_axis)
+}
+
+impl IMUUntimedSample for EulerAngles {
+    fn get_measurement(&self) -> Self {
+        self.clone()
+    }
+}
+
+/// Angles of the rotation applied in `ZYX` order.
+impl From<UnitQuaternion> for EulerAngles {
+    fn from(value: UnitQuaternion) -> Self {
+        Self::from_unit_quaternion(&value, RotationOrder::ZYX)
+    }
+}
+
+/// Rotation of applying the angles in `ZYX` order.
+impl From<EulerAngles> for UnitQuaternion {
+    fn from(value: EulerAngles) -> Self {
+        value.to_unit_quaternion(RotationOrder::ZYX)
+    }
+}
+
+impl From<EulerAngles> for [f64; N_EULER_COORDINATES] {
+    fn from(value: EulerAngles) -> Self {
+        value.inner()
+    }
+}
+
+impl From<[f64; N_EULER_COORDINATES]> for EulerAngles {
+    fn from(value: [f64; N_EULER_COORDINATES]) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<EulerAngles> for Vec<f64> {
+    fn from(value: EulerAngles) -> Self {
+        value.inner().to_vec()
+    }
+}
+
+impl TryFrom<Vec<f64>> for EulerAngles {
+    type Error = &'static str;
+
+    fn try_from(value: Vec<f64>) -> Result<Self, Self::Error> {
+        let array: [f64; N_EULER_COORDINATES] =
+            value.try_into().map_err(|_| "Conversion failed")?;
+        Ok(EulerAngles::new(array))
+    }
+}
+
+#[cfg(any(feature = "serde-serialize", test))]
+impl Serialize for EulerAngles {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let json = serde_json::json!({
+            "roll": self.roll,
+            "pitch": self.pitch,
+            "yaw": self.yaw,
+        });
+        json.serialize(serializer)
+    }
+}
+
+#[cfg(any(feature = "serde-serialize", test))]
+impl<'de> Deserialize<'de> for EulerAngles {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value: Value = Value::deserialize(deserializer)?;
+
+        // Handle array format [roll, pitch, yaw]
+        if let Some(arr) = value.as_array() {
+            if arr.len() == N_EULER_COORDINATES {
+                let angles = [0, 1, 2].map(|i| arr[i].as_f64().unwrap_or_default());
+                return Ok(EulerAngles::new(angles));
+            }
+        }
+
+        // Handle object format {"roll": f64, "pitch": f64, "yaw": f64}
+        if let Some(obj) = value.as_object() {
+            let angles = ["roll", "pitch", "yaw"]
+                .map(|label| obj.get(label).and_then(Value::as_f64).unwrap_or_default());
+            return Ok(EulerAngles::new(angles));
+        }
+
+        // Handle string format "0.1, 0.2, 0.3" (comma-separated values)
+        if let Some(scalar_str) = value.as_str() {
+            let mut parts: Vec<f64> = scalar_str
+                .split(',')
+                .filter_map(|s| s.trim().parse().ok())
+                .collect();
+            if parts.len() <= N_EULER_COORDINATES {
+                parts.resize(N_EULER_COORDINATES, 0.0);
+                return Ok(EulerAngles::new([parts[0], parts[1], parts[2]]));
+            }
+        }
+
+        Err(serde::de::Error::custom("Invalid format for EulerAngles"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    const ORDERS: [RotationOrder; 6] = [
+        RotationOrder::XYZ,
+        RotationOrder::XZY,
+        RotationOrder::YXZ,
+        RotationOrder::YZX,
+        RotationOrder::ZXY,
+        RotationOrder::ZYX,
+    ];
+
+    fn assert_angles_eq(angles: &EulerAngles, expected: [f64; 3]) {
+        for (angle, expected) in angles.inner().iter().zip(expected) {
+            assert!(
+                (angle - expected).abs() < 1e-9,
+                "{:?} != {:?}",
+                angles,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_zyx_matches_nalgebra() {
+        let q = NUnitQuaternion::from_euler_angles(0.1, -0.2, 0.3);
+        let angles = EulerAngles::from(UnitQuaternion::from(q));
+        assert_angles_eq(&angles, [0.1, -0.2, 0.3]);
+        assert!(UnitQuaternion::from(angles).inner().angle_to(&q) < 1e-12);
+    }
+
+    #[test]
+    fn test_round_trip_all_orders() {
+        let expected = [0.4, -0.7, 1.2];
+        for order in ORDERS {
+            let quaternion = EulerAngles::new(expected).to_unit_quaternion(order);
+            let angles = EulerAngles::from_unit_quaternion(&quaternion, order);
+            assert_angles_eq(&angles, expected);
+        }
+    }
+
+    #[test]
+    fn test_order_changes_rotation() {
+        let angles = EulerAngles::new([0.5, 0.5, 0.5]);
+        let xyz = angles.to_unit_quaternion(RotationOrder::XYZ).inner();
+        let zyx = angles.to_unit_quaternion(RotationOrder::ZYX).inner();
+        assert!(xyz.angle_to(&zyx) > 0.1);
+    }
+
+    #[test]
+    fn test_gimbal_lock() {
+        for order in ORDERS {
+            let mut expected = [0.3, 0.3, 0.3];
+            expected[order.axes()[1]] = FRAC_PI_2;
+            let quaternion = EulerAngles::new(expected).to_unit_quaternion(order);
+            let angles = EulerAngles::from_unit_quaternion(&quaternion, order);
+            assert_eq!(angles.inner()[order.axes()[2]], 0.0);
+            let back = angles.to_unit_quaternion(order);
+            assert!(back.inner().angle_to(&quaternion.inner()) < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_degrees() {
+        let angles = EulerAngles::from_degrees([90.0, 0.0, -45.0]);
+        assert_angles_eq(&angles, [FRAC_PI_2, 0.0, -FRAC_PI_2 / 2.0]);
+        assert_angles_eq(&EulerAngles::new(angles.to_degrees()), [90.0, 0.0, -45.0]);
+    }
+
+    #[test]
+    fn test_serde() {
+        let angles = EulerAngles::new([0.1, 0.2, 0.3]);
+        let serialized = serde_json::to_string(&angles).unwrap();
+        assert_eq!(serialized, r#"{"pitch":0.2,"roll":0.1,"yaw":0.3}"#);
+        let deserialized: EulerAngles = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, angles);
+
+        let deserialized: EulerAngles = serde_json::from_str(r#""0.1, 0.2""#).unwrap();
+        assert_eq!(deserialized, EulerAngles::new([0.1, 0.2, 0.0]));
+        let deserialized: EulerAngles = serde_json::from_str("[0.1, 0.2, 0.3]").unwrap();
+        assert_eq!(deserialized, angles);
+    }
+}
@@ -1,7 +1,11 @@
+pub mod euler_angles;
 pub mod scalar;
 pub mod unit_quaternion;
 pub mod xyz;
+pub mod xyz_pair;
 
+pub use crate::types::untimed::euler_angles::{EulerAngles, RotationOrder};
 pub use crate::types::untimed::scalar::Scalar;
 pub use crate::types::untimed::unit_quaternion::UnitQuaternion;
 pub use crate::types::untimed::xyz::XYZ;
+pub use crate::types::untimed::xyz_pair::XYZPair;
@@ -1,10 +1,11 @@
 use crate::traits::imu::BasicArithmetic;
 use crate::traits::IMUUntimedSample;
+use alloc::{vec, vec::Vec};
 
 #[cfg(any(feature = "serde-serialize", test))]
 use serde::{Deserialize, Serialize};
 
-use std::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
+use core::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
 
 #[cfg_attr(any(feature = "serde-serialize", test), derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, PartialEq, PartialOrd, Default)]
@@ -81,6 +82,23 @@ impl From<f64> for Scalar {
         Self(value)
     }
 }
+
+impl From<Scalar> for Vec<f64> {
+    fn from(value: Scalar) -> Self {
+        vec![value.inner()]
+    }
+}
+
+impl TryFrom<Vec<f64>> for Scalar {
+    type Error = &'static str;
+
+    fn try_from(value: Vec<f64>) -> Result<Self, Self::Error> {
+        match value.as_slice() {
+            [v] => Ok(Self(*v)),
+            _ => Err("Can't convert to Scalar"),
+        }
+    }
+}
 impl BasicArithmetic for Scalar {}
 
 #[cfg(test)]
@@ -161,6 +179,15 @@ mod tests {
         assert_eq!(measurement.inner(), 5.0);
     }
 
+    #[test]
+    fn test_scalar_vec_conversion() {
+        let scalar = Scalar::new(5.0);
+        let vec: Vec<f64> = scalar.clone().into();
+        assert_eq!(vec, vec![5.0]);
+        assert_eq!(Scalar::try_from(vec).unwrap(), scalar);
+        assert!(Scalar::try_from(vec![1.0, 2.0]).is_err());
+    }
+
     #[cfg(any(feature = "serde-serialize", test))]
     #[test]
     fn test_scalar_serialize() {
@@ -1,3 +1,4 @@
+use alloc::vec::Vec;
 use nalgebra::UnitQuaternion as NUnitQuaternion;
 
 #[cfg(any(feature = "serde-serialize", test))]
@@ -71,6 +72,35 @@ impl From<[f64; N_QUATERNION_COORDINATES]> for UnitQuaternion {
     }
 }
 
+impl From<UnitQuaternion> for NUnitQuaternion<f64> {
+    fn from(value: UnitQuaternion) -> Self {
+        value.0
+    }
+}
+
+impl From<NUnitQuaternion<f64>> for UnitQuaternion {
+    fn from(value: NUnitQuaternion<f64>) -> Self {
+        Self(value)
+    }
+}
+
+/// glam stores quaternions as (x, y, z, w).
+#[cfg(feature = "glam")]
+impl From<UnitQuaternion> for glam::Quat {
+    fn from(value: UnitQuaternion) -> Self {
+        let q = value.0;
+        glam::Quat::from_xyzw(q.i as f32, q.j as f32, q.k as f32, q.w as f32)
+    }
+}
+
+/// The quaternion is normalized, as precision may have been lost in the f32 representation.
+#[cfg(feature = "glam")]
+impl From<glam::Quat> for UnitQuaternion {
+    fn from(value: glam::Quat) -> Self {
+        Self::new([value.w, value.x, value.y, value.z].map(f64::from))
+    }
+}
+
 impl From<UnitQuaternion> for Vec<f64> {
     fn from(value: UnitQuaternion) -> Self {
         <[f64; N_QUATERNION_COORDINATES]>::from(value).to_vec()
@@ -164,10 +194,32 @@ impl<'de> Deserialize<'de> for UnitQuaternion {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
     use nalgebra::{Quaternion, UnitQuaternion as NUnitQuaternion};
     #[cfg(any(feature = "serde-serialize", test))]
     use serde_json;
 
+    #[test]
+    fn test_nalgebra_conversions() {
+        let q = NUnitQuaternion::from_euler_angles(0.1, 0.2, 0.3);
+        let unit_quaternion = UnitQuaternion::from(q);
+        assert_eq!(unit_quaternion.inner(), q);
+        assert_eq!(NUnitQuaternion::from(unit_quaternion), q);
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn test_glam_conversions() {
+        let unit_quaternion =
+            UnitQuaternion::from(NUnitQuaternion::from_euler_angles(0.1, 0.2, 0.3));
+        let quat = glam::Quat::from(unit_quaternion.clone());
+        let expected = glam::Quat::from_euler(glam::EulerRot::ZYX, 0.3, 0.2, 0.1);
+        assert!(quat.abs_diff_eq(expected, 1e-6));
+
+        let back = UnitQuaternion::from(quat);
+        assert!(back.inner().angle_to(&unit_quaternion.inner()) < 1e-6);
+    }
+
     #[test]
     fn test_unit_quaternion_new() {
         let data = [1.0, 0.0, 0.0, 0.0];
@@ -1,68 +1,104 @@
+use alloc::vec::Vec;
 use nalgebra::Vector3;
 #[cfg(any(feature = "serde-serialize", test))]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 #[cfg(any(feature = "serde-serialize", test))]
 use serde_json::Value;
 
-use std::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
+use core::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
 
 use crate::traits::imu::BasicArithmetic;
-use crate::traits::IMUUntimedSample;
+use crate::traits::{Float, IMUUntimedSample};
 
 pub const N_XYZ_COORDINATES: usize = 3;
 
+/// Three dimensional measurement, in `f64` unless another [`Float`] type is given.
 #[derive(Clone, Debug, PartialEq, PartialOrd, Default)]
-pub struct XYZ(pub Vector3<f64>);
+pub struct XYZ<F: Float = f64>(pub Vector3<F>);
 
-impl XYZ {
-    pub fn new(data: [f64; N_XYZ_COORDINATES]) -> Self {
-        Self(Vector3::from_vec(data.to_vec()))
+impl<F: Float> XYZ<F> {
+    pub fn new(data: [F; N_XYZ_COORDINATES]) -> Self {
+        Self(Vector3::from(data))
     }
 
-    pub fn from_vector(data: Vector3<f64>) -> Self {
+    pub fn from_vector(data: Vector3<F>) -> Self {
         Self(data)
     }
 
-    pub fn inner(&self) -> [f64; N_XYZ_COORDINATES] {
+    pub fn inner(&self) -> [F; N_XYZ_COORDINATES] {
         [self.0.x, self.0.y, self.0.z]
     }
+
+    /// Returns the measurement in another precision.
+    pub fn cast<G: Float>(&self) -> XYZ<G> {
+        XYZ::new(self.inner().map(|v| G::cast_from_f64(v.cast_to_f64())))
+    }
 }
 
-impl IMUUntimedSample for XYZ {
+impl<F: Float> IMUUntimedSample for XYZ<F> {
     fn get_measurement(&self) -> Self {
         self.clone()
     }
 }
 
-impl From<XYZ> for [f64; N_XYZ_COORDINATES] {
-    fn from(value: XYZ) -> Self {
+impl<F: Float> From<XYZ<F>> for [F; N_XYZ_COORDINATES] {
+    fn from(value: XYZ<F>) -> Self {
         value.inner()
     }
 }
 
-impl From<[f64; N_XYZ_COORDINATES]> for XYZ {
-    fn from(value: [f64; N_XYZ_COORDINATES]) -> Self {
+impl<F: Float> From<[F; N_XYZ_COORDINATES]> for XYZ<F> {
+    fn from(value: [F; N_XYZ_COORDINATES]) -> Self {
         Self(Vector3::from(value))
     }
 }
 
-impl From<XYZ> for Vec<f64> {
-    fn from(value: XYZ) -> Self {
-        value.inner().to_vec()
+impl<F: Float> From<XYZ<F>> for Vec<f64> {
+    fn from(value: XYZ<F>) -> Self {
+        value.inner().iter().map(|v| v.cast_to_f64()).collect()
+    }
+}
+
+impl<F: Float> From<XYZ<F>> for Vector3<F> {
+    fn from(value: XYZ<F>) -> Self {
+        value.0
+    }
+}
+
+impl<F: Float> From<Vector3<F>> for XYZ<F> {
+    fn from(value: Vector3<F>) -> Self {
+        Self(value)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl<F: Float> From<XYZ<F>> for glam::Vec3 {
+    fn from(value: XYZ<F>) -> Self {
+        let [x, y, z] = value.inner().map(|v| v.cast_to_f64() as f32);
+        glam::Vec3::new(x, y, z)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::Vec3> for XYZ {
+    fn from(value: glam::Vec3) -> Self {
+        Self::new([value.x as f64, value.y as f64, value.z as f64])
     }
 }
 
-impl TryFrom<Vec<f64>> for XYZ {
+impl<F: Float> TryFrom<Vec<f64>> for XYZ<F> {
     type Error = &'static str;
 
     fn try_from(value: Vec<f64>) -> Result<Self, Self::Error> {
         if value.len() != N_XYZ_COORDINATES {
             return Err("Can't convert to XYZ");
         }
-        Ok(Self(Vector3::from_vec(value)))
+        Ok(Self::new(
+            [value[0], value[1], value[2]].map(F::cast_from_f64),
+        ))
     }
 }
-impl Add for XYZ {
+impl<F: Float> Add for XYZ<F> {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
@@ -70,7 +106,7 @@ impl Add for XYZ {
     }
 }
 
-impl Sub for XYZ {
+impl<F: Float> Sub for XYZ<F> {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
@@ -78,59 +114,60 @@ impl Sub for XYZ {
     }
 }
 
-impl Div<f64> for XYZ {
+impl<F: Float> Div<f64> for XYZ<F> {
     type Output = Self;
 
     fn div(self, rhs: f64) -> Self::Output {
-        Self(self.0 / rhs)
+        Self(self.0 / F::cast_from_f64(rhs))
     }
 }
 
-impl AddAssign for XYZ {
+impl<F: Float> AddAssign for XYZ<F> {
     fn add_assign(&mut self, rhs: Self) {
         self.0 += rhs.0
     }
 }
 
-impl SubAssign for XYZ {
+impl<F: Float> SubAssign for XYZ<F> {
     fn sub_assign(&mut self, rhs: Self) {
         self.0 -= rhs.0
     }
 }
 
-impl Mul<f64> for XYZ {
+impl<F: Float> Mul<f64> for XYZ<F> {
     type Output = Self;
 
     fn mul(self, rhs: f64) -> Self::Output {
-        Self(self.0 * rhs)
+        Self(self.0 * F::cast_from_f64(rhs))
     }
 }
-impl BasicArithmetic for XYZ {}
+impl<F: Float> BasicArithmetic for XYZ<F> {}
 
 #[cfg(any(feature = "serde-serialize", test))]
-impl Serialize for XYZ {
+impl<F: Float> Serialize for XYZ<F> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let vec = self.0;
+        let [x, y, z] = self.inner().map(|v| v.cast_to_f64());
         let json = serde_json::json!({
-            "x": vec.x,
-            "y": vec.y,
-            "z": vec.z
+            "x": x,
+            "y": y,
+            "z": z
         });
         json.serialize(serializer)
     }
 }
 
 #[cfg(any(feature = "serde-serialize", test))]
-impl<'de> Deserialize<'de> for XYZ {
+impl<'de, F: Float> Deserialize<'de> for XYZ<F> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
         // Deserialize into a Value (serde_json::Value)
         let value: Value = Value::deserialize(deserializer)?;
+        let xyz = |x: f64, y: f64, z: f64| XYZ::new([x, y, z].map(F::cast_from_f64));
 
         // Handle array format [f64, f64, f64]
         if let Some(arr) = value.as_array() {
@@ -138,7 +175,7 @@ impl<'de> Deserialize<'de> for XYZ {
                 let x = arr[0].as_f64().unwrap_or_default();
                 let y = arr[1].as_f64().unwrap_or_default();
                 let z = arr[2].as_f64().unwrap_or_default();
-                return Ok(XYZ(Vector3::new(x, y, z)));
+                return Ok(xyz(x, y, z));
             }
         }
 
@@ -147,7 +184,7 @@ impl<'de> Deserialize<'de> for XYZ {
             let x = obj.get("x").and_then(Value::as_f64).unwrap_or_default();
             let y = obj.get("y").and_then(Value::as_f64).unwrap_or_default();
             let z = obj.get("z").and_then(Value::as_f64).unwrap_or_default();
-            return Ok(XYZ(Vector3::new(x, y, z)));
+            return Ok(xyz(x, y, z));
         }
 
         // Handle string format "0.0, 1.0, 2.0" (comma-separated values)
@@ -164,21 +201,54 @@ impl<'de> Deserialize<'de> for XYZ {
                 parts
             };
             if parts.len() == 3 {
-                return Ok(XYZ(Vector3::new(parts[0], parts[1], parts[2])));
+                return Ok(xyz(parts[0], parts[1], parts[2]));
             }
         }
 
         // Fallback to a default value if nothing else matches
-        Ok(XYZ(Vector3::default()))
+        Ok(XYZ::default())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
     #[cfg(any(feature = "serde-serialize", test))]
     use serde_json;
 
+    #[test]
+    fn test_nalgebra_conversions() {
+        let vector = Vector3::new(1.0, 2.0, 3.0);
+        let xyz = XYZ::from(vector);
+        assert_eq!(xyz.inner(), [1.0, 2.0, 3.0]);
+        assert_eq!(Vector3::from(xyz), vector);
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn test_glam_conversions() {
+        let xyz = XYZ::new([1.0, 2.5, -3.0]);
+        let vec3 = glam::Vec3::from(xyz.clone());
+        assert_eq!(vec3, glam::Vec3::new(1.0, 2.5, -3.0));
+        assert_eq!(XYZ::from(vec3), xyz);
+    }
+
+    #[test]
+    fn test_f32() {
+        let xyz = XYZ::<f32>::new([1.0, 2.0, 3.0]);
+        let result = (xyz.clone() + xyz.clone()) * 0.5 / 2.0;
+        assert_eq!(result.inner(), [0.5f32, 1.0, 1.5]);
+        assert_eq!(Vec::<f64>::from(result), vec![0.5, 1.0, 1.5]);
+        assert_eq!(xyz.cast::<f64>(), XYZ::new([1.0, 2.0, 3.0]));
+        assert_eq!(XYZ::<f32>::try_from(vec![1.0, 2.0, 3.0]).unwrap(), xyz);
+
+        let serialized = serde_json::to_string(&xyz).unwrap();
+        assert_eq!(serialized, r#"{"x":1.0,"y":2.0,"z":3.0}"#);
+        let deserialized: XYZ<f32> = serde_json::from_str(r#""1.0, 2.0, 3.0""#).unwrap();
+        assert_eq!(deserialized, xyz);
+    }
+
     #[test]
     fn test_new() {
         let data = [1.0, 2.0, 3.0];
@@ -235,14 +305,14 @@ mod tests {
     #[test]
     fn test_try_from_vec() {
         let vec = vec![1.0, 2.0, 3.0];
-        let xyz = XYZ::try_from(vec).unwrap();
+        let xyz = XYZ::<f64>::try_from(vec).unwrap();
         assert_eq!(xyz.inner(), [1.0, 2.0, 3.0]);
     }
 
     #[test]
     fn test_try_from_vec_invalid_length() {
         let vec = vec![1.0, 2.0];
-        let result = XYZ::try_from(vec);
+        let result = XYZ::<f64>::try_from(vec);
         assert!(result.is_err());
     }
 
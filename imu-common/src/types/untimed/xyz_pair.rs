@@ -0,0 +1,192 @@
+#[cfg(any(feature = "serde-serialize", test))]
+use serde::{Deserialize, Serialize};
+
+use alloc::vec::Vec;
+use core::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
+
+use crate::traits::imu::BasicArithmetic;
+use crate::traits::IMUUntimedSample;
+use crate::types::untimed::xyz::{N_XYZ_COORDINATES, XYZ};
+
+pub const N_XYZ_PAIR_COORDINATES: usize = 2 * N_XYZ_COORDINATES;
+
+/// Accelerometer and gyroscope readings taken together, as a 6 vector
+/// `[ax, ay, az, gx, gy, gz]`.
+#[cfg_attr(any(feature = "serde-serialize", test), derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Default)]
+pub struct XYZPair {
+    accel: XYZ,
+    gyro: XYZ,
+}
+
+impl XYZPair {
+    pub fn new(data: [f64; N_XYZ_PAIR_COORDINATES]) -> Self {
+        Self {
+            accel: XYZ::new([data[0], data[1], data[2]]),
+            gyro: XYZ::new([data[3], data[4], data[5]]),
+        }
+    }
+
+    pub fn from_xyz(accel: XYZ, gyro: XYZ) -> Self {
+        Self { accel, gyro }
+    }
+
+    pub fn get_accel(&self) -> XYZ {
+        self.accel.clone()
+    }
+
+    pub fn get_gyro(&self) -> XYZ {
+        self.gyro.clone()
+    }
+
+    pub fn inner(&self) -> [f64; N_XYZ_PAIR_COORDINATES] {
+        let [ax, ay, az] = self.accel.inner();
+        let [gx, gy, gz] = self.gyro.inner();
+        [ax, ay, az, gx, gy, gz]
+    }
+}
+
+impl IMUUntimedSample for XYZPair {
+    fn get_measurement(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl From<XYZPair> for [f64; N_XYZ_PAIR_COORDINATES] {
+    fn from(value: XYZPair) -> Self {
+        value.inner()
+    }
+}
+
+impl From<[f64; N_XYZ_PAIR_COORDINATES]> for XYZPair {
+    fn from(value: [f64; N_XYZ_PAIR_COORDINATES]) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<XYZPair> for Vec<f64> {
+    fn from(value: XYZPair) -> Self {
+        value.inner().to_vec()
+    }
+}
+
+impl TryFrom<Vec<f64>> for XYZPair {
+    type Error = &'static str;
+
+    fn try_from(value: Vec<f64>) -> Result<Self, Self::Error> {
+        let data: [f64; N_XYZ_PAIR_COORDINATES] =
+            value.try_into().map_err(|_| "Can't convert to XYZPair")?;
+        Ok(Self::new(data))
+    }
+}
+
+impl Add for XYZPair {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::from_xyz(self.accel + rhs.accel, self.gyro + rhs.gyro)
+    }
+}
+
+impl Sub for XYZPair {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::from_xyz(self.accel - rhs.accel, self.gyro - rhs.gyro)
+    }
+}
+
+impl Div<f64> for XYZPair {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Self::from_xyz(self.accel / rhs, self.gyro / rhs)
+    }
+}
+
+impl AddAssign for XYZPair {
+    fn add_assign(&mut self, rhs: Self) {
+        self.accel += rhs.accel;
+        self.gyro += rhs.gyro;
+    }
+}
+
+impl SubAssign for XYZPair {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.accel -= rhs.accel;
+        self.gyro -= rhs.gyro;
+    }
+}
+
+impl Mul<f64> for XYZPair {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self::from_xyz(self.accel * rhs, self.gyro * rhs)
+    }
+}
+impl BasicArithmetic for XYZPair {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    #[cfg(any(feature = "serde-serialize", test))]
+    use serde_json;
+
+    #[test]
+    fn test_new() {
+        let data = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let pair = XYZPair::new(data);
+        assert_eq!(pair.inner(), data);
+        assert_eq!(pair.get_accel(), XYZ::new([1.0, 2.0, 3.0]));
+        assert_eq!(pair.get_gyro(), XYZ::new([4.0, 5.0, 6.0]));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let pair1 = XYZPair::new([1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let pair2 = XYZPair::new([1.0, 1.0, 1.0, 2.0, 2.0, 2.0]);
+        assert_eq!(
+            (pair1.clone() + pair2.clone()).inner(),
+            [2.0, 3.0, 4.0, 6.0, 7.0, 8.0]
+        );
+        assert_eq!(
+            (pair1.clone() - pair2.clone()).inner(),
+            [0.0, 1.0, 2.0, 2.0, 3.0, 4.0]
+        );
+        assert_eq!(
+            (pair1.clone() * 2.0).inner(),
+            [2.0, 4.0, 6.0, 8.0, 10.0, 12.0]
+        );
+        assert_eq!(
+            (pair1.clone() / 2.0).inner(),
+            [0.5, 1.0, 1.5, 2.0, 2.5, 3.0]
+        );
+
+        let mut pair = pair1.clone();
+        pair += pair2.clone();
+        pair -= pair2;
+        assert_eq!(pair, pair1);
+    }
+
+    #[test]
+    fn test_try_from_vec() {
+        let pair = XYZPair::try_from(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+        assert_eq!(Vec::from(pair), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert!(XYZPair::try_from(vec![1.0, 2.0, 3.0]).is_err());
+    }
+
+    #[cfg(any(feature = "serde-serialize", test))]
+    #[test]
+    fn test_serde() {
+        let pair = XYZPair::new([1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let serialized = serde_json::to_string(&pair).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"accel":{"x":1.0,"y":2.0,"z":3.0},"gyro":{"x":4.0,"y":5.0,"z":6.0}}"#
+        );
+        let deserialized: XYZPair = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, pair);
+    }
+}
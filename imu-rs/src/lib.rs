@@ -0,0 +1,76 @@
+//! # Crate imu-rs
+//!
+//! Single entry point to the `imu-rs` crates. Every stage, source and transport is behind a
+//! feature of its own, so applications only build what they use:
+//!
+//! - `std` (default): the publishers and sensors of the pipeline. Without it, only the sample
+//!   types, buffers and filters of [`common`] are available, with `#![no_std]` and `alloc`, for
+//!   the firmware or WASM modules producing the streams.
+//! - `async`: asynchronous listeners, running on tokio.
+//! - `serde`: serialization of the sample types.
+//! - `proto`: protobuf messages of the samples and readings, a wire format shared with
+//!   endpoints written in other languages.
+//! - `plot`: gnuplot sinks, to draw readings while developing.
+//! - `resampler`, `ahrs`, `calibration`, `script`: pipeline stages.
+//! - `phyphox`, `recorder`: sources and sinks running on tokio.
+//! - `udp`, `websocket`, `mqtt`, `redis`, `influx`, `arrow`, `bridge`: network transports, or
+//!   all of them with `transports`.
+//! - `serial`, `can`, `gamepad`, `host`: device sources.
+//!
+//! ```
+//! use imu_rs::common::traits::IMUSample;
+//! use imu_rs::common::types::timed::Sample3D;
+//!
+//! let sample = Sample3D::new(0.1, [1.0, 2.0, 3.0]);
+//! assert_eq!(sample.get_timestamp_secs(), 0.1);
+//! ```
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use imu_common as common;
+
+#[cfg(feature = "std")]
+pub use publisher;
+
+#[cfg(feature = "ahrs")]
+pub use ahrs_rs as ahrs;
+#[cfg(feature = "calibration")]
+pub use calibration_rs as calibration;
+#[cfg(feature = "resampler")]
+pub use resampler_rs as resampler;
+#[cfg(feature = "script")]
+pub use script_rs as script;
+
+#[cfg(feature = "phyphox")]
+pub use phyphox_rs as phyphox;
+#[cfg(feature = "recorder")]
+pub use recorder_rs as recorder;
+
+#[cfg(feature = "arrow")]
+pub use arrow_stream_rs as arrow;
+#[cfg(feature = "bridge")]
+pub use bridge_rs as bridge;
+#[cfg(feature = "influx")]
+pub use influx_rs as influx;
+#[cfg(feature = "mqtt")]
+pub use mqtt_rs as mqtt;
+#[cfg(feature = "redis")]
+pub use redis_streams_rs as redis;
+#[cfg(feature = "udp")]
+pub use udp_rs as udp;
+#[cfg(feature = "websocket")]
+pub use websocket_rs as websocket;
+
+#[cfg(feature = "can")]
+pub use can_rs as can;
+#[cfg(feature = "gamepad")]
+pub use gamepad_rs as gamepad;
+#[cfg(feature = "host")]
+pub use host_rs as host;
+#[cfg(feature = "serial")]
+pub use serial_rs as serial;
+
+/// Plot sinks of the `test_utils` crate.
+#[cfg(feature = "plot")]
+pub mod plot {
+    pub use test_utils::sinks::{Plot1D, Plot3D};
+}
@@ -0,0 +1,133 @@
+//! Module config
+
+use reqwest::Url;
+use std::time::Duration;
+
+use crate::errors::InfluxError;
+
+const DEFAULT_MEASUREMENT: &str = "imu";
+const DEFAULT_BATCH_SIZE: usize = 5000;
+const DEFAULT_FLUSH_INTERVAL_MILLIS: u64 = 1000;
+const DEFAULT_MAX_RETRIES: usize = 3;
+const DEFAULT_RETRY_DELAY_MILLIS: u64 = 500;
+const DEFAULT_CAPACITY: usize = 1000;
+
+/// Configuration of [`InfluxSink`](crate::InfluxSink).
+#[derive(Clone, Debug, PartialEq)]
+pub struct InfluxConfig {
+    /// Url of the server, e.g. `http://127.0.0.1:8086`.
+    pub url: String,
+    pub org: String,
+    pub bucket: String,
+    /// API token, sent as `Authorization: Token <token>`.
+    pub token: Option<String>,
+    /// Measurement of the samples. Derived metrics go to `<measurement>_stats`.
+    pub measurement: String,
+    /// Adds the norm of 3D samples as a `norm` field, and a `<measurement>_stats` point per
+    /// batch of readings with the number of samples and their rate.
+    pub derived_metrics: bool,
+    /// Number of lines written per request.
+    pub batch_size: usize,
+    /// Maximum time lines wait before being written, even if the batch isn't full.
+    pub flush_interval: Duration,
+    /// Number of times a failed write is retried before its lines are dropped.
+    pub max_retries: usize,
+    /// Delay before the first retry, doubled after every failed attempt.
+    pub retry_delay: Duration,
+    /// Number of batches of readings queued while writing. Readings are dropped once it is full.
+    pub capacity: usize,
+}
+
+impl InfluxConfig {
+    /// Returns the default configuration for the bucket `bucket` of `org` at `url`.
+    pub fn new(url: &str, org: &str, bucket: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            org: org.to_string(),
+            bucket: bucket.to_string(),
+            token: None,
+            measurement: DEFAULT_MEASUREMENT.to_string(),
+            derived_metrics: true,
+            batch_size: DEFAULT_BATCH_SIZE,
+            flush_interval: Duration::from_millis(DEFAULT_FLUSH_INTERVAL_MILLIS),
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_delay: Duration::from_millis(DEFAULT_RETRY_DELAY_MILLIS),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Returns an InvalidConfig error if the configuration can't be used.
+    pub fn validate(&self) -> Result<(), InfluxError> {
+        let invalid = |e: &str| Err(InfluxError::InvalidConfig(e.to_string()));
+        self.write_url()?;
+        if self.bucket.is_empty() {
+            return invalid("Empty bucket");
+        }
+        if self.measurement.is_empty() {
+            return invalid("Empty measurement");
+        }
+        if self.batch_size == 0 {
+            return invalid("Batch size must be positive");
+        }
+        if self.flush_interval.is_zero() {
+            return invalid("Flush interval must be positive");
+        }
+        if self.capacity == 0 {
+            return invalid("Capacity must be positive");
+        }
+        Ok(())
+    }
+
+    /// Returns the url of the v2 write endpoint, with nanosecond precision.
+    pub fn write_url(&self) -> Result<Url, InfluxError> {
+        let invalid = |e: String| InfluxError::InvalidConfig(e);
+        let url = Url::parse(&self.url).map_err(|e| invalid(e.to_string()))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid(format!("Unsupported scheme {}", url.scheme())));
+        }
+        url.join("api/v2/write")
+            .and_then(|url| {
+                Url::parse_with_params(
+                    url.as_str(),
+                    &[
+                        ("org", self.org.as_str()),
+                        ("bucket", self.bucket.as_str()),
+                        ("precision", "ns"),
+                    ],
+                )
+            })
+            .map_err(|e| invalid(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_url() {
+        let config = InfluxConfig::new("http://127.0.0.1:8086", "lab", "imu data");
+        assert_eq!(
+            config.write_url().unwrap().as_str(),
+            "http://127.0.0.1:8086/api/v2/write?org=lab&bucket=imu+data&precision=ns"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = InfluxConfig::new("http://127.0.0.1:8086", "lab", "imu");
+        assert!(config.validate().is_ok());
+
+        let mut invalid = config.clone();
+        invalid.url = "udp://127.0.0.1".to_string();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = config.clone();
+        invalid.bucket.clear();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = config;
+        invalid.batch_size = 0;
+        assert!(invalid.validate().is_err());
+    }
+}
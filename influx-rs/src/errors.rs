@@ -0,0 +1,21 @@
+//! Module errors
+
+/// Errors of the InfluxDB sink.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InfluxError {
+    /// Invalid sink configuration.
+    InvalidConfig(String),
+    /// Lines were dropped, because the queue is full or the sink was stopped.
+    Dropped(String),
+}
+
+impl std::fmt::Display for InfluxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InfluxError::InvalidConfig(e) => write!(f, "Invalid configuration: {}", e),
+            InfluxError::Dropped(e) => write!(f, "Dropped: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for InfluxError {}
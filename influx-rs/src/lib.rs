@@ -0,0 +1,32 @@
+//! # Crate influx-rs
+//!
+//! ## influx-rs
+//!
+//! The `influx-rs` crate writes readings to [InfluxDB](https://www.influxdata.com/) in line
+//! protocol, so long captures can be visualized with Grafana dashboards without custom
+//! exporters.
+//!
+//! [`InfluxSink`] is an `IMUSink` adding a point per sample to [`InfluxConfig::measurement`],
+//! tagged with the tag of the readings, the kind of sensor and its id, e.g. for 3D samples:
+//!
+//! ```text
+//! imu,tag=Phone,sensor=accelerometer,uuid=67e55044-10b1-426f-9247-bb680e5fe0c8 x=0.1,y=0,z=9.8,norm=9.8005 1700000000250000000
+//! imu_stats,tag=Phone,sensor=accelerometer,uuid=67e55044-10b1-426f-9247-bb680e5fe0c8 samples=20i,rate=99.8 1700000000250000000
+//! ```
+//!
+//! Measurement fields are `value` for scalars, `x`, `y`, `z` for 3D samples and `w`, `x`, `y`,
+//! `z` for quaternions. With [`InfluxConfig::derived_metrics`], 3D samples also get their
+//! `norm`, and every batch of readings adds a `<measurement>_stats` point with its number of
+//! samples and rate. Sample timestamps are written as Unix time in nanoseconds.
+//!
+//! Lines are batched and written with the v2 HTTP API in a background task. Failed writes are
+//! retried with exponential backoff, and dropped after [`InfluxConfig::max_retries`].
+
+pub mod config;
+pub mod errors;
+mod line;
+mod sink;
+
+pub use config::InfluxConfig;
+pub use errors::InfluxError;
+pub use sink::{run_client, InfluxSink};
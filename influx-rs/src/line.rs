@@ -0,0 +1,174 @@
+//! Module line
+//!
+//! Formatting of readings in [line protocol](https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/).
+
+use imu_common::types::sensors::SensorType;
+
+/// Returns the names of the fields of measurements with `n_values` values.
+fn measurement_fields(n_values: usize) -> Vec<String> {
+    let names: &[&str] = match n_values {
+        1 => &["value"],
+        3 => &["x", "y", "z"],
+        4 => &["w", "x", "y", "z"],
+        _ => &[],
+    };
+    if names.is_empty() {
+        (0..n_values).map(|n| format!("v{}", n)).collect()
+    } else {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+}
+
+fn escape_measurement(measurement: &str) -> String {
+    measurement.replace(',', "\\,").replace(' ', "\\ ")
+}
+
+fn escape_tag(tag: &str) -> String {
+    tag.replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+fn timestamp_nanos(timestamp: f64) -> i64 {
+    (timestamp * 1e9).round() as i64
+}
+
+/// Returns `measurement,tag=<tag>,sensor=<kind>,uuid=<uuid>`. Empty tags are left out, as line
+/// protocol doesn't allow them.
+fn series_key(measurement: &str, tag: &str, sensor_type: &SensorType) -> String {
+    let uuid = match sensor_type {
+        SensorType::Accelerometer(uuid)
+        | SensorType::Gyroscope(uuid)
+        | SensorType::Magnetometer(uuid)
+        | SensorType::Other(uuid, _)
+        | SensorType::Vendor(uuid, _) => uuid,
+    };
+    let mut key = escape_measurement(measurement);
+    if !tag.is_empty() {
+        key.push_str(&format!(",tag={}", escape_tag(tag)));
+    }
+    key.push_str(&format!(
+        ",sensor={},uuid={}",
+        escape_tag(sensor_type.kind()),
+        uuid
+    ));
+    key
+}
+
+/// Returns the lines of `samples`, given as `(timestamp, measurement)`. Non finite values are
+/// left out, as InfluxDB can't store them.
+///
+/// If `derived_metrics` is set, 3D samples get a `norm` field, and a `<measurement>_stats` line
+/// is added with the number of samples and their rate in Hz, timestamped as the last sample.
+pub fn format_lines(
+    measurement: &str,
+    tag: &str,
+    sensor_type: &SensorType,
+    samples: &[(f64, Vec<f64>)],
+    derived_metrics: bool,
+) -> Vec<String> {
+    let Some((_, first)) = samples.first() else {
+        return Vec::new();
+    };
+    let fields = measurement_fields(first.len());
+    let key = series_key(measurement, tag, sensor_type);
+    let mut lines = Vec::with_capacity(samples.len() + 1);
+    for (timestamp, values) in samples {
+        let mut field_set: Vec<String> = fields
+            .iter()
+            .zip(values)
+            .filter(|(_, value)| value.is_finite())
+            .map(|(field, value)| format!("{}={}", field, value))
+            .collect();
+        if derived_metrics && values.len() == 3 {
+            let norm = values.iter().map(|value| value * value).sum::<f64>().sqrt();
+            if norm.is_finite() {
+                field_set.push(format!("norm={}", norm));
+            }
+        }
+        if field_set.is_empty() || !timestamp.is_finite() {
+            continue;
+        }
+        lines.push(format!(
+            "{} {} {}",
+            key,
+            field_set.join(","),
+            timestamp_nanos(*timestamp)
+        ));
+    }
+
+    if derived_metrics {
+        let (first_timestamp, _) = samples[0];
+        let (last_timestamp, _) = samples[samples.len() - 1];
+        let mut field_set = vec![format!("samples={}i", samples.len())];
+        let span = last_timestamp - first_timestamp;
+        if samples.len() > 1 && span > 0.0 {
+            field_set.push(format!("rate={}", (samples.len() - 1) as f64 / span));
+        }
+        if last_timestamp.is_finite() {
+            lines.push(format!(
+                "{} {} {}",
+                series_key(&format!("{}_stats", measurement), tag, sensor_type),
+                field_set.join(","),
+                timestamp_nanos(last_timestamp)
+            ));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_format_lines() {
+        let id = Uuid::new_v4();
+        let samples = vec![(1.0, vec![3.0, 0.0, 4.0]), (1.5, vec![0.0, f64::NAN, 1.0])];
+        let lines = format_lines(
+            "imu",
+            "Lab Phone",
+            &SensorType::Accelerometer(id),
+            &samples,
+            true,
+        );
+        assert_eq!(
+            lines,
+            vec![
+                format!(
+                    "imu,tag=Lab\\ Phone,sensor=accelerometer,uuid={} x=3,y=0,z=4,norm=5 1000000000",
+                    id
+                ),
+                format!(
+                    "imu,tag=Lab\\ Phone,sensor=accelerometer,uuid={} x=0,z=1 1500000000",
+                    id
+                ),
+                format!(
+                    "imu_stats,tag=Lab\\ Phone,sensor=accelerometer,uuid={} samples=2i,rate=2 1500000000",
+                    id
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_lines_without_metrics() {
+        let id = Uuid::new_v4();
+        let samples = vec![(2.0, vec![0.5])];
+        let lines = format_lines(
+            "imu",
+            "",
+            &SensorType::Other(id, "pressure".into()),
+            &samples,
+            false,
+        );
+        assert_eq!(
+            lines,
+            vec![format!(
+                "imu,sensor=pressure,uuid={} value=0.5 2000000000",
+                id
+            )]
+        );
+    }
+}
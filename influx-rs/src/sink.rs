@@ -0,0 +1,234 @@
+use log::{debug, warn};
+use publisher::{adapters, DropGuard, ShutdownToken};
+use reqwest::{Client, StatusCode, Url};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Notify};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::config::InfluxConfig;
+use crate::errors::InfluxError;
+use crate::line;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource, VecF64Convertible};
+use imu_common::types::sensors::SensorType;
+
+/// Sink writing the readings it receives to InfluxDB in line protocol.
+///
+/// Lines are written in batches of [`InfluxConfig::batch_size`], or every
+/// [`InfluxConfig::flush_interval`] if fewer lines are received. The writer stops once every
+/// clone of the sink is dropped, including the ones held by the sources it is attached to,
+/// writing the lines still pending.
+#[derive(Clone)]
+pub struct InfluxSink {
+    sender: mpsc::Sender<Vec<String>>,
+    config: Arc<InfluxConfig>,
+    abort_signal: Arc<Notify>,
+    written_lines: Arc<AtomicUsize>,
+    dropped_lines: Arc<AtomicUsize>,
+    shutdown: ShutdownToken,
+    // `None` in the clone used by the writer task, so it doesn't keep the sink alive
+    _guard: Option<Arc<DropGuard>>,
+}
+
+impl InfluxSink {
+    pub fn get_config(&self) -> &InfluxConfig {
+        &self.config
+    }
+
+    /// Returns the number of lines accepted by the server.
+    pub fn get_written_lines(&self) -> usize {
+        self.written_lines.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of lines dropped because the queue was full or the server kept
+    /// rejecting them.
+    pub fn get_dropped_lines(&self) -> usize {
+        self.dropped_lines.load(Ordering::Relaxed)
+    }
+
+    /// Writes the pending lines and ends the writer task.
+    pub fn stop(&self) {
+        self.abort_signal.notify_one();
+    }
+
+    /// Queues `lines` to be written.
+    /// Returns an error if the queue is full.
+    pub fn push(&self, lines: Vec<String>) -> Result<(), InfluxError> {
+        let n_lines = lines.len();
+        self.sender.try_send(lines).map_err(|e| {
+            self.dropped_lines.fetch_add(n_lines, Ordering::Relaxed);
+            InfluxError::Dropped(e.to_string())
+        })
+    }
+
+    async fn run(&self, client: Client, url: Url, mut receiver: mpsc::Receiver<Vec<String>>) {
+        let mut pending: Vec<String> = Vec::new();
+        let mut deadline = Instant::now();
+        loop {
+            tokio::select! {
+                _ = self.abort_signal.notified() => break,
+                _ = self.shutdown.wait() => break,
+                _ = tokio::time::sleep_until(deadline), if !pending.is_empty() => {
+                    self.flush(&client, &url, &mut pending).await;
+                }
+                lines = receiver.recv() => match lines {
+                    Some(lines) => {
+                        if pending.is_empty() {
+                            deadline = Instant::now() + self.config.flush_interval;
+                        }
+                        pending.extend(lines);
+                        while pending.len() >= self.config.batch_size {
+                            let batch: Vec<String> =
+                                pending.drain(..self.config.batch_size).collect();
+                            self.write_batch(&client, &url, &batch).await;
+                            deadline = Instant::now() + self.config.flush_interval;
+                        }
+                    }
+                    None => break,
+                },
+            }
+        }
+
+        while let Ok(lines) = receiver.try_recv() {
+            pending.extend(lines);
+        }
+        self.flush(&client, &url, &mut pending).await;
+    }
+
+    /// Writes `pending` in batches of at most `batch_size` lines, leaving it empty.
+    async fn flush(&self, client: &Client, url: &Url, pending: &mut Vec<String>) {
+        for batch in pending.chunks(self.config.batch_size) {
+            self.write_batch(client, url, batch).await;
+        }
+        pending.clear();
+    }
+
+    async fn write_batch(&self, client: &Client, url: &Url, batch: &[String]) {
+        let n_lines = batch.len();
+        match self.write(client, url, batch.join("\n")).await {
+            Ok(()) => {
+                debug!("Wrote {} lines to InfluxDB", n_lines);
+                self.written_lines.fetch_add(n_lines, Ordering::Relaxed);
+            }
+            Err(e) => {
+                warn!("Dropping {} lines: {}", n_lines, e);
+                self.dropped_lines.fetch_add(n_lines, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Posts `body`, retrying up to `max_retries` times if the server can't be reached or
+    /// answers with a server error or `429 Too Many Requests`.
+    async fn write(&self, client: &Client, url: &Url, body: String) -> Result<(), String> {
+        let mut delay = self.config.retry_delay;
+        let mut attempt = 0;
+        loop {
+            let mut request = client.post(url.clone()).body(body.clone());
+            if let Some(token) = &self.config.token {
+                request = request.header("Authorization", format!("Token {}", token));
+            }
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => {
+                    let status = response.status();
+                    let message =
+                        format!("{}: {}", status, response.text().await.unwrap_or_default());
+                    if status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS {
+                        return Err(message);
+                    }
+                    message
+                }
+                Err(e) => e.to_string(),
+            };
+            if attempt >= self.config.max_retries {
+                return Err(error);
+            }
+            attempt += 1;
+            warn!(
+                "Error writing to InfluxDB, retrying in {:?}: {}",
+                delay, error
+            );
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+impl<T, S> IMUSink<T, S> for InfluxSink
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, S>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        let entries: Vec<(f64, Vec<f64>)> = samples
+            .iter()
+            .map(|sample| (sample.get_timestamp_secs(), sample.get_measurement().into()))
+            .collect();
+        let lines = line::format_lines(
+            &self.config.measurement,
+            samples.get_sensor_tag(),
+            &samples.get_sensor_type(),
+            &entries,
+            self.config.derived_metrics,
+        );
+        if lines.is_empty() {
+            return;
+        }
+        if let Err(e) = self.push(lines) {
+            warn!("Dropping readings: {}", e);
+        }
+    }
+}
+
+/// Starts an InfluxDB writer for the server of `config` in a background task. Must be called
+/// inside a tokio runtime.
+///
+/// Returns an InvalidConfig error if `config` is invalid.
+///
+/// # Returns
+///
+/// Returns a tuple containing:
+/// * A `tokio::task::JoinHandle<()>` of the writer, which ends when `InfluxSink::stop` is
+///   called, the sink is dropped or the global `ShutdownToken` is shut down.
+/// * The `InfluxSink` to attach to sources.
+pub fn run_client(
+    config: InfluxConfig,
+) -> Result<(tokio::task::JoinHandle<()>, InfluxSink), InfluxError> {
+    config.validate()?;
+    let url = config.write_url()?;
+    let (sender, receiver) = mpsc::channel(config.capacity);
+
+    let abort_signal = Arc::new(Notify::new());
+    let sink = InfluxSink {
+        sender,
+        config: Arc::new(config),
+        abort_signal: abort_signal.clone(),
+        written_lines: Arc::new(AtomicUsize::new(0)),
+        dropped_lines: Arc::new(AtomicUsize::new(0)),
+        shutdown: ShutdownToken::global(),
+        _guard: None,
+    };
+    let handle = tokio::spawn({
+        let sink = sink.clone();
+        async move { sink.run(Client::new(), url, receiver).await }
+    });
+    let guard = DropGuard::new(move || abort_signal.notify_one());
+    Ok((
+        handle,
+        InfluxSink {
+            _guard: Some(Arc::new(guard)),
+            ..sink
+        },
+    ))
+}
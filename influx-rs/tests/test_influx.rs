@@ -0,0 +1,142 @@
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+use wiremock::matchers::{header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use imu_common::traits::{IMUReadings, IMUSink};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use influx_rs::{run_client, InfluxConfig};
+
+fn readings(sensor_type: &SensorType, timestamps: &[f64]) -> Arc<SensorReadings<Sample3D>> {
+    let samples = timestamps
+        .iter()
+        .map(|&t| Sample3D::new(t, [0.0, 3.0, 4.0]))
+        .collect();
+    Arc::new(SensorReadings::from_vec(
+        "Phone",
+        sensor_type.clone(),
+        samples,
+    ))
+}
+
+async fn wait_for_requests(server: &MockServer, n_requests: usize) -> Vec<String> {
+    tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            let requests = server.received_requests().await.unwrap();
+            if requests.len() >= n_requests {
+                return requests
+                    .iter()
+                    .map(|request| String::from_utf8_lossy(&request.body).to_string())
+                    .collect();
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_write_batches() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v2/write"))
+        .and(query_param("bucket", "imu"))
+        .and(header("Authorization", "Token secret"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await;
+
+    let mut config = InfluxConfig::new(&server.uri(), "lab", "imu");
+    config.token = Some("secret".to_string());
+    config.derived_metrics = false;
+    config.batch_size = 3;
+    config.flush_interval = Duration::from_millis(50);
+    let (handle, sink) = run_client(config).unwrap();
+
+    let id = Uuid::new_v4();
+    let sensor_type = SensorType::Accelerometer(id);
+    sink.process_samples(Uuid::new_v4(), readings(&sensor_type, &[1.0, 1.5]));
+    sink.process_samples(Uuid::new_v4(), readings(&sensor_type, &[2.0, 2.5]));
+    sink.process_samples(Uuid::new_v4(), readings(&sensor_type, &[3.0]));
+
+    // the first batch is full, the rest is written once the flush interval elapses
+    let bodies = wait_for_requests(&server, 2).await;
+    assert_eq!(bodies[0].lines().count(), 3);
+    assert_eq!(
+        bodies[1],
+        format!(
+            "imu,tag=Phone,sensor=accelerometer,uuid={id} x=0,y=3,z=4 2500000000\n\
+             imu,tag=Phone,sensor=accelerometer,uuid={id} x=0,y=3,z=4 3000000000"
+        )
+    );
+
+    sink.stop();
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sink.get_written_lines(), 5);
+    assert_eq!(sink.get_dropped_lines(), 0);
+}
+
+#[tokio::test]
+async fn test_retry() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await;
+
+    let mut config = InfluxConfig::new(&server.uri(), "lab", "imu");
+    config.retry_delay = Duration::from_millis(10);
+    config.flush_interval = Duration::from_millis(10);
+    let (handle, sink) = run_client(config).unwrap();
+
+    let sensor_type = SensorType::Gyroscope(Uuid::new_v4());
+    sink.process_samples(Uuid::new_v4(), readings(&sensor_type, &[1.0, 2.0]));
+    let bodies = wait_for_requests(&server, 3).await;
+    assert_eq!(bodies[0], bodies[2]);
+    assert!(bodies[2].contains("norm=5"));
+    assert!(bodies[2].contains("imu_stats"));
+
+    drop(sink);
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_drop_rejected_lines() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400))
+        .mount(&server)
+        .await;
+
+    let mut config = InfluxConfig::new(&server.uri(), "lab", "imu");
+    config.derived_metrics = false;
+    config.flush_interval = Duration::from_millis(10);
+    let (handle, sink) = run_client(config).unwrap();
+
+    let sensor_type = SensorType::Magnetometer(Uuid::new_v4());
+    sink.process_samples(Uuid::new_v4(), readings(&sensor_type, &[1.0]));
+    wait_for_requests(&server, 1).await;
+
+    sink.stop();
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+    // client errors aren't retried
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    assert_eq!(sink.get_dropped_lines(), 1);
+}
@@ -0,0 +1,142 @@
+//! Module config
+
+use rumqttc::QoS;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::errors::MqttError;
+use imu_common::types::sensors::SensorType;
+
+const DEFAULT_TOPIC: &str = "imu/{tag}/{sensor}/{uuid}";
+const DEFAULT_KEEP_ALIVE_SECS: u64 = 5;
+const DEFAULT_RECONNECT_DELAY_MILLIS: u64 = 1000;
+const DEFAULT_CAPACITY: usize = 1000;
+/// Longest client id MQTT 3.1.1 brokers are required to accept.
+const MAX_CLIENT_ID_LEN: usize = 23;
+
+/// Configuration of [`MqttSink`](crate::MqttSink).
+#[derive(Clone, Debug, PartialEq)]
+pub struct MqttConfig {
+    /// Host name of the broker.
+    pub host: String,
+    /// Port of the broker.
+    pub port: u16,
+    /// Client id. Defaults to a random id.
+    pub client_id: String,
+    /// Topic of the readings. `{tag}`, `{sensor}` and `{uuid}` are replaced by the tag of the
+    /// readings, the kind of sensor (e.g. `accelerometer`) and its id.
+    pub topic: String,
+    pub qos: QoS,
+    pub retain: bool,
+    pub keep_alive: Duration,
+    /// Delay between reconnection attempts.
+    pub reconnect_delay: Duration,
+    /// Number of messages queued while disconnected. Readings are dropped once it is full.
+    pub capacity: usize,
+}
+
+impl MqttConfig {
+    /// Returns the default configuration for the broker at `host:port`.
+    pub fn new(host: &str, port: u16) -> Self {
+        let client_id = format!("imu-rs-{}", Uuid::new_v4().simple());
+        Self {
+            host: host.to_string(),
+            port,
+            client_id: client_id[..MAX_CLIENT_ID_LEN].to_string(),
+            topic: DEFAULT_TOPIC.to_string(),
+            qos: QoS::AtLeastOnce,
+            retain: false,
+            keep_alive: Duration::from_secs(DEFAULT_KEEP_ALIVE_SECS),
+            reconnect_delay: Duration::from_millis(DEFAULT_RECONNECT_DELAY_MILLIS),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Returns an InvalidConfig error if the configuration can't be used.
+    pub fn validate(&self) -> Result<(), MqttError> {
+        let invalid = |e: &str| Err(MqttError::InvalidConfig(e.to_string()));
+        if self.host.is_empty() {
+            return invalid("Empty host");
+        }
+        if self.client_id.is_empty() {
+            return invalid("Empty client id");
+        }
+        if self.topic.is_empty() || self.topic.contains(['+', '#']) {
+            return invalid("Topic must be non empty and can't contain wildcards");
+        }
+        if !self.keep_alive.is_zero() && self.keep_alive < Duration::from_secs(1) {
+            return invalid("Keep alive must be zero or at least one second");
+        }
+        if self.capacity == 0 {
+            return invalid("Capacity must be positive");
+        }
+        Ok(())
+    }
+
+    /// Returns the topic of the readings of `sensor_type` with tag `tag`.
+    ///
+    /// Characters with a special meaning in topics (`/`, `+` and `#`) are replaced by `_` in
+    /// the tag and sensor kind, so every sensor gets its own topic level.
+    pub fn format_topic(&self, tag: &str, sensor_type: &SensorType) -> String {
+        let uuid = match sensor_type {
+            SensorType::Accelerometer(uuid)
+            | SensorType::Gyroscope(uuid)
+            | SensorType::Magnetometer(uuid)
+            | SensorType::Other(uuid, _)
+            | SensorType::Vendor(uuid, _) => uuid,
+        };
+        self.topic
+            .replace("{tag}", &escape_level(tag))
+            .replace("{sensor}", &escape_level(sensor_type.kind()))
+            .replace("{uuid}", &uuid.to_string())
+    }
+}
+
+fn escape_level(level: &str) -> String {
+    level.replace(['/', '+', '#'], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_topic() {
+        let mut config = MqttConfig::new("localhost", 1883);
+        let id = Uuid::new_v4();
+
+        assert_eq!(
+            config.format_topic("Phone", &SensorType::Gyroscope(id)),
+            format!("imu/Phone/gyroscope/{}", id)
+        );
+        assert_eq!(
+            config.format_topic("Lab/Phone#1", &SensorType::Other(id, "orientation".into())),
+            format!("imu/Lab_Phone_1/orientation/{}", id)
+        );
+
+        config.topic = "sensors/{uuid}".to_string();
+        assert_eq!(
+            config.format_topic("Phone", &SensorType::Gyroscope(id)),
+            format!("sensors/{}", id)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = MqttConfig::new("localhost", 1883);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.client_id.len(), MAX_CLIENT_ID_LEN);
+
+        let mut invalid = config.clone();
+        invalid.topic = "imu/+/{uuid}".to_string();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = config.clone();
+        invalid.host.clear();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = config;
+        invalid.capacity = 0;
+        assert!(invalid.validate().is_err());
+    }
+}
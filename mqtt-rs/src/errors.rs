@@ -0,0 +1,21 @@
+//! Module errors
+
+/// Errors of the MQTT sink.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MqttError {
+    /// Invalid sink configuration.
+    InvalidConfig(String),
+    /// Messages were dropped, because the queue is full or the sink was stopped.
+    Dropped(String),
+}
+
+impl std::fmt::Display for MqttError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MqttError::InvalidConfig(e) => write!(f, "Invalid configuration: {}", e),
+            MqttError::Dropped(e) => write!(f, "Dropped: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MqttError {}
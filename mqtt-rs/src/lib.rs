@@ -0,0 +1,27 @@
+//! # Crate mqtt-rs
+//!
+//! ## mqtt-rs
+//!
+//! The `mqtt-rs` crate publishes readings to an MQTT broker, so they can be consumed by home
+//! automation and telemetry stacks.
+//!
+//! [`MqttSink`] is an `IMUSink` publishing every batch of readings it receives to a topic of
+//! its sensor, `imu/<tag>/<sensor>/<uuid>` by default (see [`MqttConfig::topic`]). The payload
+//! is a JSON array with the serde representation of `imu_common` samples:
+//!
+//! ```json
+//! [{"timestamp": 1.25, "measurement": {"x": 0.1, "y": 0.0, "z": 9.8}}]
+//! ```
+//!
+//! The connection is kept alive in a background task, reconnecting whenever it is lost.
+//! Readings received while disconnected are queued up to [`MqttConfig::capacity`] messages,
+//! and dropped afterwards.
+
+pub mod config;
+pub mod errors;
+mod sink;
+
+pub use config::MqttConfig;
+pub use errors::MqttError;
+pub use rumqttc::QoS;
+pub use sink::{run_client, MqttSink};
@@ -0,0 +1,179 @@
+use log::{error, info, warn};
+use publisher::{adapters, DropGuard, ShutdownToken};
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Outgoing, Packet};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::config::MqttConfig;
+use crate::errors::MqttError;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
+use imu_common::types::sensors::SensorType;
+
+/// Time given to the disconnection to reach the broker when the sink is stopped.
+const DISCONNECT_TIMEOUT_MILLIS: u64 = 500;
+
+/// Sink publishing the readings it receives to an MQTT broker, one topic per sensor.
+///
+/// The client disconnects once every clone of the sink is dropped, including the ones held by
+/// the sources it is attached to.
+#[derive(Clone)]
+pub struct MqttSink {
+    client: AsyncClient,
+    config: Arc<MqttConfig>,
+    abort_signal: Arc<Notify>,
+    connected: Arc<AtomicBool>,
+    dropped_messages: Arc<AtomicUsize>,
+    shutdown: ShutdownToken,
+    // `None` in the clone used by the connection task, so it doesn't keep the sink alive
+    _guard: Option<Arc<DropGuard>>,
+}
+
+impl MqttSink {
+    pub fn get_config(&self) -> &MqttConfig {
+        &self.config
+    }
+
+    /// Returns true while the connection with the broker is up.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of messages dropped because the queue was full.
+    pub fn get_dropped_messages(&self) -> usize {
+        self.dropped_messages.load(Ordering::Relaxed)
+    }
+
+    /// Disconnects from the broker and ends the connection task.
+    pub fn stop(&self) {
+        self.abort_signal.notify_one();
+    }
+
+    /// Publishes `payload` to `topic` with the configured QoS.
+    /// Returns an error if the queue is full.
+    pub fn publish(&self, topic: String, payload: Vec<u8>) -> Result<(), MqttError> {
+        self.client
+            .try_publish(topic, self.config.qos, self.config.retain, payload)
+            .map_err(|e| {
+                self.dropped_messages.fetch_add(1, Ordering::Relaxed);
+                MqttError::Dropped(e.to_string())
+            })
+    }
+
+    async fn run(&self, mut event_loop: EventLoop) {
+        loop {
+            tokio::select! {
+                _ = self.abort_signal.notified() => break,
+                _ = self.shutdown.wait() => break,
+                event = event_loop.poll() => match event {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        info!("Connected to MQTT broker {}:{}", self.config.host, self.config.port);
+                        self.connected.store(true, Ordering::Relaxed);
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        if self.connected.swap(false, Ordering::Relaxed) {
+                            warn!("Disconnected from MQTT broker: {}", e);
+                        } else {
+                            warn!("Error connecting to MQTT broker: {}", e);
+                        }
+                        // the next poll reconnects
+                        tokio::select! {
+                            _ = self.abort_signal.notified() => break,
+                            _ = self.shutdown.wait() => break,
+                            _ = tokio::time::sleep(self.config.reconnect_delay) => {}
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.connected.swap(false, Ordering::Relaxed) && self.client.try_disconnect().is_ok() {
+            let _ = tokio::time::timeout(Duration::from_millis(DISCONNECT_TIMEOUT_MILLIS), async {
+                while let Ok(event) = event_loop.poll().await {
+                    if event == Event::Outgoing(Outgoing::Disconnect) {
+                        break;
+                    }
+                }
+            })
+            .await;
+        }
+    }
+}
+
+impl<T, S> IMUSink<T, S> for MqttSink
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: IMUSample + Serialize,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, S>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        let topic = self
+            .config
+            .format_topic(samples.get_sensor_tag(), &samples.get_sensor_type());
+        let payload = match serde_json::to_vec(samples.get_samples_ref()) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Error serializing readings: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = self.publish(topic, payload) {
+            warn!("Dropping readings: {}", e);
+        }
+    }
+}
+
+/// Starts an MQTT client connected to the broker of `config` in a background task. Must be
+/// called inside a tokio runtime.
+///
+/// Returns an InvalidConfig error if `config` is invalid.
+///
+/// # Returns
+///
+/// Returns a tuple containing:
+/// * A `tokio::task::JoinHandle<()>` of the connection, which ends when `MqttSink::stop` is
+///   called, the sink is dropped or the global `ShutdownToken` is shut down.
+/// * The `MqttSink` to attach to sources.
+pub fn run_client(
+    config: MqttConfig,
+) -> Result<(tokio::task::JoinHandle<()>, MqttSink), MqttError> {
+    config.validate()?;
+    let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+    options.set_keep_alive(config.keep_alive);
+    let (client, event_loop) = AsyncClient::new(options, config.capacity);
+
+    let abort_signal = Arc::new(Notify::new());
+    let sink = MqttSink {
+        client,
+        config: Arc::new(config),
+        abort_signal: abort_signal.clone(),
+        connected: Arc::new(AtomicBool::new(false)),
+        dropped_messages: Arc::new(AtomicUsize::new(0)),
+        shutdown: ShutdownToken::global(),
+        _guard: None,
+    };
+    let handle = tokio::spawn({
+        let sink = sink.clone();
+        async move { sink.run(event_loop).await }
+    });
+    let guard = DropGuard::new(move || abort_signal.notify_one());
+    Ok((
+        handle,
+        MqttSink {
+            _guard: Some(Arc::new(guard)),
+            ..sink
+        },
+    ))
+}
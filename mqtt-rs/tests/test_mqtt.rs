@@ -0,0 +1,131 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use uuid::Uuid;
+
+use imu_common::traits::{IMUReadings, IMUSink};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use mqtt_rs::{run_client, MqttConfig, QoS};
+
+const CONNECT: u8 = 1;
+const PUBLISH: u8 = 3;
+const DISCONNECT: u8 = 14;
+const CONNACK: [u8; 4] = [0x20, 0x02, 0x00, 0x00];
+
+/// Reads a packet, returning its type and body.
+async fn read_packet(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+    let header = stream.read_u8().await.unwrap();
+    let mut length = 0;
+    for shift in (0..28).step_by(7) {
+        let byte = stream.read_u8().await.unwrap();
+        length |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body).await.unwrap();
+    (header >> 4, body)
+}
+
+async fn accept_client(listener: &TcpListener) -> TcpStream {
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let (packet_type, _) = read_packet(&mut stream).await;
+    assert_eq!(packet_type, CONNECT);
+    stream.write_all(&CONNACK).await.unwrap();
+    stream
+}
+
+#[tokio::test]
+async fn test_publish_and_reconnect() {
+    let broker = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut config = MqttConfig::new("127.0.0.1", broker.local_addr().unwrap().port());
+    config.qos = QoS::AtMostOnce;
+    config.reconnect_delay = Duration::from_millis(50);
+    let (handle, sink) = run_client(config).unwrap();
+
+    let stream = tokio::time::timeout(Duration::from_secs(2), accept_client(&broker))
+        .await
+        .unwrap();
+    drop(stream);
+    let mut stream = tokio::time::timeout(Duration::from_secs(2), accept_client(&broker))
+        .await
+        .unwrap();
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while !sink.is_connected() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let id = Uuid::new_v4();
+    let readings = SensorReadings::from_vec(
+        "Phone",
+        SensorType::Accelerometer(id),
+        vec![Sample3D::new(1.0, [0.1, 0.2, 9.8])],
+    );
+    sink.process_samples(Uuid::new_v4(), Arc::new(readings));
+
+    let body = tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            match read_packet(&mut stream).await {
+                (PUBLISH, body) => return body,
+                _ => continue,
+            }
+        }
+    })
+    .await
+    .unwrap();
+    let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+    let topic = std::str::from_utf8(&body[2..2 + topic_len]).unwrap();
+    assert_eq!(topic, format!("imu/Phone/accelerometer/{}", id));
+    let payload: serde_json::Value = serde_json::from_slice(&body[2 + topic_len..]).unwrap();
+    assert_eq!(payload[0]["timestamp"], 1.0);
+    assert_eq!(payload[0]["measurement"]["z"], 9.8);
+    assert_eq!(sink.get_dropped_messages(), 0);
+
+    sink.stop();
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_invalid_config() {
+    let mut config = MqttConfig::new("127.0.0.1", 1883);
+    config.topic = "imu/#".to_string();
+    assert!(run_client(config).is_err());
+}
+
+#[tokio::test]
+async fn test_disconnect_on_drop() {
+    let broker = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let config = MqttConfig::new("127.0.0.1", broker.local_addr().unwrap().port());
+    let (handle, sink) = run_client(config).unwrap();
+    let mut stream = tokio::time::timeout(Duration::from_secs(2), accept_client(&broker))
+        .await
+        .unwrap();
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while !sink.is_connected() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    drop(sink);
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while read_packet(&mut stream).await.0 != DISCONNECT {}
+    })
+    .await
+    .unwrap();
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+}
@@ -1,20 +1,13 @@
-use uuid::Uuid;
-
 use imu_common::traits::IMUSink;
-use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::sensors::{SensorClusterBuilder, SensorReadings};
 use imu_common::types::timed::Sample3D;
 
-use phyphox_rs;
 use test_utils::sinks::Plot1D;
 use tokio::time::Duration;
 
 #[tokio::main]
 async fn main() {
-    let sensor_cluster = vec![
-        SensorType::Accelerometer(Uuid::new_v4()),
-        SensorType::Gyroscope(Uuid::new_v4()),
-        SensorType::Magnetometer(Uuid::new_v4()),
-    ];
+    let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
 
     let plot_refresh_period_millis = 200.0;
     let plot_window_size_samples = 200;
@@ -1,35 +1,39 @@
 use async_trait::async_trait;
-use rand::{rngs::StdRng, SeedableRng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
-use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::{Mutex, Notify};
 
 use super::gaussian::GaussianNoise;
 use super::timestamp::Timestamp;
-use crate::constants::N_SENSORS;
+use crate::adapters::production;
+use crate::constants::{N_SCALAR_SENSORS, N_VECTOR_SENSORS};
+use crate::helpers;
+use crate::models::connection::ConnectionStatus;
 use crate::models::errors::PhyphoxError;
-use crate::ports::PhyphoxPort;
+use crate::ports::{PhyphoxPort, PhyphoxStartOptions};
 use imu_common::traits::{IMUReadings, IMUSample};
 use imu_common::types::buffers::CircularReader;
-use imu_common::types::sensors::sensor_type;
 use imu_common::types::sensors::{SensorReadings, SensorType};
-use imu_common::types::timed::Sample3D;
+use imu_common::types::timed::{Sample3D, SampleScalar};
 use imu_common::types::untimed::XYZ;
 use imu_common::types::Clock;
-use publisher::{Publishable, Publisher};
 use test_utils::csv_loader::{self, CsvColumnMapper};
 
 const GAUSSIAN_TIME_MEAN: f64 = 0f64;
 const GAUSSIAN_SENSOR_MEAN: f64 = 0f64;
 const GAUSSIAN_SENSOR_STDEV: f64 = 0.5;
-const LSB_TIME_MASK: u8 = 0xC0;
+// share of the updates without new samples
+const NO_SAMPLES_PROBABILITY: f64 = 0.25;
 const MAX_N_SAMPLES: u8 = 15;
+/// Baseline of the pressure (hPa), light (lx), proximity (cm) and audio amplitude readings
+const SCALAR_BASELINES: [f64; N_SCALAR_SENSORS] = [1013.25, 300.0, 5.0, 0.01];
+/// Relative deviation of the noisy scalar readings per unit of sensor noise
+const SCALAR_NOISE_SCALE: f64 = 0.01;
 
 /// Configures mock data acquisition
 pub struct PhyphoxMock {
-    readings: Mutex<[CircularReader<Sample3D>; N_SENSORS]>,
+    readings: Mutex<[CircularReader<Sample3D>; N_VECTOR_SENSORS]>,
     timestamps: Mutex<Timestamp>,
     time_delta: GaussianNoise,
     sensor_noise: Option<GaussianNoise>,
@@ -46,6 +50,7 @@ impl PhyphoxMock {
         update_period_millis: f64,
         add_sensor_noise: bool,
     ) -> Result<Self, PhyphoxError> {
+        helpers::check_sensor_cluster(&sensor_cluster)?;
         let test_data = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
             .join("../test-utils/test_data/sensor_readings.csv");
         let test_data = test_data.to_str().unwrap();
@@ -87,55 +92,71 @@ impl PhyphoxMock {
         })
     }
 
-    async fn get_next_samples(&self, buffer_idx: usize) -> Vec<Sample3D> {
-        let mut new_samples = Vec::new();
-        let pending_samples = select_random_pending_samples();
-        let mut rng = StdRng::from_entropy();
+    /// Returns the timestamps of the samples of sensor `sensor_idx` pending since the last update
+    async fn next_timestamps(&self, rng: &mut StdRng, sensor_idx: usize) -> Vec<f64> {
+        let pending_samples = select_random_pending_samples(rng);
         let mut timestamps = self.timestamps.lock().await;
         let current_timestamp = timestamps.get_current_timestamp();
-        if pending_samples > 0 {
-            let mut readings = self.readings.lock().await;
-            for _ in 0..pending_samples {
+        let mut new_timestamps = Vec::with_capacity(pending_samples);
+        for _ in 0..pending_samples {
+            let sample_timestamp = self
+                .time_delta
+                .add_noise(rng, timestamps.get_reading_timestamp(sensor_idx))
+                .abs()
+                .min(current_timestamp);
+            timestamps.set_reading_timestamp(sensor_idx, sample_timestamp);
+            new_timestamps.push(sample_timestamp);
+        }
+        new_timestamps.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        new_timestamps
+    }
+
+    async fn get_next_samples(&self, buffer_idx: usize) -> Vec<Sample3D> {
+        let mut rng = StdRng::from_entropy();
+        let timestamps = self.next_timestamps(&mut rng, buffer_idx).await;
+        if timestamps.is_empty() {
+            return Vec::new();
+        }
+        let mut readings = self.readings.lock().await;
+        timestamps
+            .into_iter()
+            .map(|sample_timestamp| {
                 let next_sample = readings[buffer_idx].next_element();
-                let mut sample_timestamp = self
-                    .time_delta
-                    .add_noise(&mut rng, timestamps.get_reading_timestamp(buffer_idx))
-                    .abs();
-                sample_timestamp = sample_timestamp.min(current_timestamp);
-                timestamps.set_reading_timestamp(buffer_idx, sample_timestamp);
                 let mut next_measurement: Vec<f64> = next_sample.get_measurement().into();
 
-                if self.sensor_noise.is_some() {
-                    let rgen = self.sensor_noise.as_ref().unwrap();
+                if let Some(rgen) = self.sensor_noise.as_ref() {
                     next_measurement = rgen.add_noise_vec(&mut rng, next_measurement);
                 }
                 let next_measurement: XYZ = XYZ::try_from(next_measurement).unwrap();
-                new_samples.push(Sample3D::from_measurement(
-                    sample_timestamp,
-                    next_measurement,
-                ));
-                new_samples.sort_by(|a, b| {
-                    a.get_timestamp_secs()
-                        .partial_cmp(&b.get_timestamp_secs())
-                        .unwrap()
-                });
-            }
-        }
-        new_samples
+                Sample3D::from_measurement(sample_timestamp, next_measurement)
+            })
+            .collect()
     }
-}
 
-fn select_random_pending_samples() -> usize {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_micros();
-    let lsb: u8 = now.to_le_bytes()[0];
+    async fn get_next_scalars(&self, sensor_idx: usize) -> Vec<SampleScalar> {
+        let mut rng = StdRng::from_entropy();
+        let baseline = SCALAR_BASELINES[sensor_idx - N_VECTOR_SENSORS];
+        self.next_timestamps(&mut rng, sensor_idx)
+            .await
+            .into_iter()
+            .map(|sample_timestamp| {
+                let measurement = match self.sensor_noise.as_ref() {
+                    Some(rgen) => {
+                        baseline * (1.0 + SCALAR_NOISE_SCALE * rgen.draw_sample(&mut rng))
+                    }
+                    None => baseline,
+                };
+                SampleScalar::new(sample_timestamp, measurement)
+            })
+            .collect()
+    }
+}
 
-    if lsb & LSB_TIME_MASK == 0 {
+fn select_random_pending_samples(rng: &mut StdRng) -> usize {
+    if rng.gen_bool(NO_SAMPLES_PROBABILITY) {
         0
     } else {
-        (lsb % MAX_N_SAMPLES) as usize
+        rng.gen_range(0..MAX_N_SAMPLES) as usize
     }
 }
 
@@ -143,18 +164,30 @@ fn select_random_pending_samples() -> usize {
 impl PhyphoxPort for PhyphoxMock {
     /// Starts the data acquisition process. The process is stopped with a SIGINT signal
     /// Returns FetchData error if it can't connect to REST API.
-    async fn start(
-        &self,
-        period_millis: Duration,
-        abort_signal: Option<Arc<Notify>>,
-        publisher: Option<Vec<Publisher<SensorReadings<Sample3D>>>>,
-    ) -> Result<(), PhyphoxError> {
+    async fn start(&self, options: PhyphoxStartOptions) -> Result<(), PhyphoxError> {
+        // the mock never loses its connection, and has no export to cross-check the session
+        let PhyphoxStartOptions {
+            period_millis,
+            abort_signal,
+            publishers,
+            filters,
+            clipping,
+            ..
+        } = options;
         let abort_signal = abort_signal.unwrap_or(Arc::new(Notify::new()));
+        // the mock never loses its connection
+        if let Some(publishers) = publishers.as_ref() {
+            publishers.notify_status(ConnectionStatus::Connected);
+        }
         let timestamp_at_boot_secs = Clock::now().as_secs();
         {
             let mut timestamp = self.timestamps.lock().await;
             timestamp.update_all(timestamp_at_boot_secs);
         }
+        let mut vector_filters =
+            production::build_filter_chains(filters.vectors.as_ref(), N_VECTOR_SENSORS);
+        let mut scalar_filters =
+            production::build_filter_chains(filters.scalars.as_ref(), N_SCALAR_SENSORS);
         loop {
             tokio::select! {
                 _ = abort_signal.notified() => {
@@ -166,17 +199,28 @@ impl PhyphoxPort for PhyphoxMock {
                     drop(timestamp);
 
                     for sensor in &self.sensor_cluster {
-                        let sensor_idx = match usize::from(sensor) {
-                            sensor_type::ACCELEROMETER_OFFSET..sensor_type::GYROSCOPE_OFFSET => 0,
-                            sensor_type::GYROSCOPE_OFFSET..sensor_type::MAGNETOMETER_OFFSET => 1,
-                            sensor_type::MAGNETOMETER_OFFSET..sensor_type::MAX_OFFSET => 2,
-                            _ => 2,
+                        let Ok((_, _, sensor_idx)) = helpers::control_str(sensor) else {
+                            continue;
                         };
+                        if sensor_idx >= N_VECTOR_SENSORS {
+                            let samples = self.get_next_scalars(sensor_idx).await;
+                            let samples = production::filter_samples(sensor, &mut scalar_filters[sensor_idx - N_VECTOR_SENSORS], samples);
+                            if let (Some(publishers), false) = (publishers.as_ref(), samples.is_empty()) {
+                                let buffer = SensorReadings::from_vec(&self.sensor_cluster_tag, sensor.clone(), samples);
+                                publishers.scalars.notify_listeners(sensor.clone(), Arc::new(buffer));
+                            }
+                            continue;
+                        }
                         let samples = self.get_next_samples(sensor_idx).await;
+                        let samples = match clipping.as_ref() {
+                            Some(clipping) => clipping.check(sensor, samples),
+                            None => samples,
+                        };
+                        let samples = production::filter_samples(sensor, &mut vector_filters[sensor_idx], samples);
                         if !samples.is_empty() {
                             let buffer = SensorReadings::from_vec(&self.sensor_cluster_tag, sensor.clone(), samples);
-                            if let Some(publisher) = publisher.as_ref() {
-                                publisher[sensor_idx].notify_listeners(Arc::new(buffer));
+                            if let Some(publishers) = publishers.as_ref() {
+                                publishers.vectors.notify_listeners(sensor.clone(), Arc::new(buffer));
                             };
                         }
                     }
@@ -202,26 +246,20 @@ impl PhyphoxPort for PhyphoxMock {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use imu_common::types::sensors::SensorClusterBuilder;
+    use std::time::Duration;
     use uuid::Uuid;
 
     #[tokio::test]
     async fn test_phyphox_mock_new() {
-        let sensor_cluster = vec![
-            SensorType::Accelerometer(Uuid::new_v4()),
-            SensorType::Gyroscope(Uuid::new_v4()),
-            SensorType::Magnetometer(Uuid::new_v4()),
-        ];
+        let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
         let phyphox_mock = PhyphoxMock::new("Test", sensor_cluster, 100.0, false);
         assert!(phyphox_mock.is_ok());
     }
 
     #[tokio::test]
     async fn test_phyphox_mock_start_stop() {
-        let sensor_cluster = vec![
-            SensorType::Accelerometer(Uuid::new_v4()),
-            SensorType::Gyroscope(Uuid::new_v4()),
-            SensorType::Magnetometer(Uuid::new_v4()),
-        ];
+        let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
         let phyphox_mock =
             Arc::new(PhyphoxMock::new("Test", sensor_cluster, 100.0, false).unwrap());
         let period = Duration::from_millis(100);
@@ -238,7 +276,7 @@ mod tests {
 
         let start_handle = tokio::spawn(async move {
             phyphox_mock_clone
-                .start(period, Some(abort_signal), None)
+                .start(PhyphoxStartOptions::new(period).with_abort_signal(abort_signal))
                 .await
                 .unwrap();
         });
@@ -247,14 +285,35 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_pending_samples() {
+    async fn test_get_next_scalars() {
+        let sensor_cluster = SensorClusterBuilder::new()
+            .other("pressure")
+            .build()
+            .unwrap();
+        let phyphox_mock = PhyphoxMock::new("Test", sensor_cluster, 100.0, false).unwrap();
+        let mut samples = Vec::new();
+        for _ in 0..10 {
+            let mut timestamp = phyphox_mock.timestamps.lock().await;
+            timestamp.update_all(Clock::now().as_secs());
+            drop(timestamp);
+            samples.extend(phyphox_mock.get_next_scalars(N_VECTOR_SENSORS).await);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(!samples.is_empty());
+        assert!(samples
+            .iter()
+            .all(|s| s.get_measurement().inner() == SCALAR_BASELINES[0]));
+    }
+
+    #[test]
+    fn test_pending_samples() {
+        let mut rng = StdRng::from_entropy();
         let mut greater_than_zero = 0;
         for _ in 0..100 {
-            let pending_samples = select_random_pending_samples();
+            let pending_samples = select_random_pending_samples(&mut rng);
             if pending_samples > 0 {
                 greater_than_zero += 1;
             }
-            tokio::time::sleep(Duration::from_nanos(350)).await;
             assert!(pending_samples < MAX_N_SAMPLES as usize);
         }
         assert!(greater_than_zero > 0);
@@ -1,9 +1,10 @@
 #![allow(dead_code)]
 
-// Functionality for data acquisition from accelerometer, gyroscope and magnetometer phone sensors
+// Functionality for data acquisition from accelerometer, gyroscope, magnetometer and scalar phone sensors
 // via an HTTP API. It includes methods to fetch sensor data,
 // control common, and register listeners to receive for incoming data.
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -13,19 +14,25 @@ use serde_json::Value;
 use tokio::sync::Notify;
 use tokio::time::{interval, MissedTickBehavior};
 
-use imu_common::traits::{IMUFilter, IMUReadings, IMUSample};
-use imu_common::types::filters::moving_average::MovingAverage;
+use imu_common::traits::{IMUReadings, IMUSample};
+use imu_common::types::filters::{filter_or_disable, FilterChain, FilterChainBuilder};
 use imu_common::types::sensors::{SensorReadings, SensorType};
-use imu_common::types::timed::Sample3D;
-use imu_common::types::untimed::XYZ;
+use imu_common::types::timed::{Sample3D, SampleScalar};
 use imu_common::types::Clock;
-use publisher::{Publishable, Publisher};
+use publisher::events::{DropReason, EventKind};
+use publisher::PublisherManager;
 
-use crate::constants::N_SENSORS;
+use crate::constants::{N_SCALAR_SENSORS, N_VECTOR_SENSORS};
 use crate::helpers;
+use crate::models::clipping::ClippingMonitor;
+use crate::models::connection::{ConnectionStatus, ReconnectPolicy};
+use crate::models::discovery::DiscoveredDevice;
+use crate::models::duplicates::DuplicateFilter;
 use crate::models::errors::PhyphoxError;
+use crate::models::export::SessionLog;
 use crate::models::http_client::HttpClient;
-use crate::ports::PhyphoxPort;
+use crate::models::polling::{PollCoordinator, PollEvent, PollState, SensorPoller};
+use crate::ports::{PhyphoxPort, PhyphoxStartOptions, PortPublishers};
 
 /// Constants for HTTP endpoints and buffer keys.
 const GET_CMD: &str = "/get?";
@@ -34,14 +41,35 @@ const START_CMD: &str = "start";
 const STOP_CMD: &str = "stop";
 const CLEAR_CMD: &str = "clear";
 const CONFIG_CMD: &str = "/config?";
-
-const DEFAULT_WINDOW_SIZE: usize = 1;
+const META_CMD: &str = "/meta?";
+// zip archive with a CSV file per set, with comma separators and decimal points
+const EXPORT_CMD: &str = "/export?format=1";
 
 /// Configures data acquisition
 pub struct Phyphox {
     client: HttpClient,
     sensor_cluster_tag: String,
     sensor_cluster: Vec<SensorType>,
+    duplicates: DuplicateFilter,
+}
+
+/// Settings and shared state of the polling tasks of an acquisition.
+struct PollContext<'a> {
+    period: Duration,
+    timestamp_at_boot_secs: f64,
+    reconnect: &'a ReconnectPolicy,
+    publishers: Option<&'a PortPublishers>,
+    clipping: Option<&'a ClippingMonitor>,
+    session: Option<&'a SessionLog>,
+    coordinator: PollCoordinator,
+    // whether the source was last reported connected
+    connected: AtomicBool,
+}
+
+/// Filter chain of a polled sensor, if any.
+enum SensorFilter {
+    Vector(Option<FilterChain<Sample3D>>),
+    Scalar(Option<FilterChain<SampleScalar>>),
 }
 
 impl Phyphox {
@@ -52,12 +80,14 @@ impl Phyphox {
         sensor_cluster_tag: &str,
         sensor_cluster: Vec<SensorType>,
     ) -> Result<Self, PhyphoxError> {
+        helpers::check_sensor_cluster(&sensor_cluster)?;
         let client = HttpClient::new(base_url.to_string())?;
 
         Ok(Self {
             client,
             sensor_cluster_tag: sensor_cluster_tag.to_string(),
             sensor_cluster,
+            duplicates: DuplicateFilter::new(),
         })
     }
 
@@ -70,13 +100,16 @@ impl Phyphox {
     /// # Errors
     /// - FetchData if there is an error connecting to REST API
     /// - IncorrectDataFormat if the data retrieved from the API has an unexpected format
-    async fn get_data(
+    async fn get_data<U>(
         &self,
         time_var: &str,
         timestamp_at_boot_secs: f64,
         since: f64,
         variables: &[&str],
-    ) -> Result<(Vec<f64>, Vec<XYZ>, bool), PhyphoxError> {
+    ) -> Result<(Vec<f64>, Vec<U>, bool), PhyphoxError>
+    where
+        U: TryFrom<Vec<f64>>,
+    {
         let query = helpers::build_query(variables, time_var, Some(since));
         let data = self.fetch_json(&format!("{GET_CMD}{}", query)).await?;
         let status = helpers::get_status_from_json(&data)?;
@@ -116,8 +149,6 @@ impl Phyphox {
             .map_err(|_| "Error retrieving available sensors".to_string())?;
         let mut available_sensors: Vec<SensorType> = vec![];
 
-        let sensor_uuids = helpers::extract_uuids(&self.sensor_cluster);
-
         if let Some(exports) = json.get("export").and_then(|e| e.as_array()) {
             available_sensors = exports
                 .iter()
@@ -125,111 +156,322 @@ impl Phyphox {
                     entry
                         .get("set")
                         .and_then(|s| s.as_str())
-                        .and_then(|s| helpers::to_type_id(s, &sensor_uuids))
-                        .and_then(|s| s.try_into().ok())
+                        .and_then(|s| helpers::to_sensor_type(s, &self.sensor_cluster))
                 })
                 .collect();
         }
         Ok(available_sensors)
     }
+
+    /// Polls `sensor` until the phone stops answering for good, publishing the samples fetched.
+    /// Returns the FetchData error that stopped the task; the caller cancels it otherwise.
+    async fn poll_sensor(
+        &self,
+        index: usize,
+        sensor: &SensorType,
+        mut filter: SensorFilter,
+        context: &PollContext<'_>,
+    ) -> PhyphoxError {
+        let (time_var, variables, _) = match helpers::control_str(sensor) {
+            Ok(control) => control,
+            Err(e) => return e,
+        };
+        let mut poller = SensorPoller::new(context.reconnect.get_max_retries());
+        let mut last_time = 0.0;
+        let mut ticker = interval(context.period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            let event = match poller.get_state().clone() {
+                PollState::Idle => {
+                    ticker.tick().await;
+                    PollEvent::Tick
+                }
+                PollState::Fetching => {
+                    let data = self
+                        .get_data::<Vec<f64>>(
+                            time_var,
+                            context.timestamp_at_boot_secs,
+                            last_time,
+                            variables,
+                        )
+                        .await;
+                    match data {
+                        Ok((_, _, false)) => {
+                            log::info!("Recording stopped.");
+                            PollEvent::Fetched
+                        }
+                        Ok((timestamps, values, true)) => {
+                            helpers::update_measurement_time(
+                                &timestamps,
+                                &mut last_time,
+                                context.timestamp_at_boot_secs,
+                            );
+                            self.process(sensor, timestamps, values, &mut filter, context);
+                            PollEvent::Fetched
+                        }
+                        Err(e) => {
+                            log::error!("Error fetching data: {:?}", e);
+                            match e {
+                                PhyphoxError::FetchData(_) => PollEvent::FetchFailed,
+                                _ => PollEvent::Fetched,
+                            }
+                        }
+                    }
+                }
+                PollState::Backoff { attempt } => {
+                    log::warn!(
+                        "Connection lost. Reconnecting {} (attempt {})...",
+                        sensor,
+                        attempt
+                    );
+                    tokio::time::sleep(context.reconnect.get_backoff(attempt)).await;
+                    if self.fetch_json(CONFIG_CMD).await.is_ok() {
+                        log::info!("Reconnected {}.", sensor);
+                        PollEvent::Reconnected
+                    } else {
+                        PollEvent::ReconnectFailed
+                    }
+                }
+                PollState::Stopped => {
+                    return PhyphoxError::FetchData(format!(
+                        "Connection lost after {} retries",
+                        context.reconnect.get_max_retries()
+                    ));
+                }
+            };
+            let state = poller.handle(event).clone();
+            if let Some(status) = context.coordinator.update(index, state) {
+                self.update_status(status, context);
+            }
+        }
+    }
+
+    /// Records, filters and publishes the samples of `sensor` fetched by a poll.
+    fn process(
+        &self,
+        sensor: &SensorType,
+        timestamps: Vec<f64>,
+        values: Vec<Vec<f64>>,
+        filter: &mut SensorFilter,
+        context: &PollContext<'_>,
+    ) {
+        // overlapping fetch windows may return samples already published
+        let n_fetched = timestamps.len();
+        let (timestamps, values) = self.duplicates.filter(sensor, timestamps, values);
+        let n_duplicates = n_fetched - timestamps.len();
+        self.emit_dropped(sensor, n_duplicates, DropReason::Duplicated, context);
+
+        if let Some(session) = context.session {
+            // the export is timed from the start of the experiment
+            let relative_timestamps: Vec<f64> = timestamps
+                .iter()
+                .map(|t| t - context.timestamp_at_boot_secs)
+                .collect();
+            session.record(sensor, &relative_timestamps, &values);
+        }
+
+        match filter {
+            SensorFilter::Scalar(filter_chain) => {
+                let timed_samples: Vec<SampleScalar> = to_samples(timestamps, values);
+                let publishers = context.publishers.map(|p| &p.scalars);
+                self.publish(sensor, timed_samples, filter_chain, publishers);
+            }
+            SensorFilter::Vector(filter_chain) => {
+                let timed_samples: Vec<Sample3D> = to_samples(timestamps, values);
+                let timed_samples = match context.clipping {
+                    Some(clipping) => {
+                        let n_samples = timed_samples.len();
+                        let timed_samples = clipping.check(sensor, timed_samples);
+                        let n_clipped = n_samples - timed_samples.len();
+                        self.emit_dropped(sensor, n_clipped, DropReason::Clipped, context);
+                        timed_samples
+                    }
+                    None => timed_samples,
+                };
+                let publishers = context.publishers.map(|p| &p.vectors);
+                self.publish(sensor, timed_samples, filter_chain, publishers);
+            }
+        }
+    }
+
+    /// Publishes a change of the connection status, and emits the matching lifecycle event. The
+    /// disconnection is only emitted once while reconnecting.
+    fn update_status(&self, status: ConnectionStatus, context: &PollContext<'_>) {
+        let was_connected = context
+            .connected
+            .swap(status == ConnectionStatus::Connected, Ordering::Relaxed);
+        let source = self.sensor_cluster_tag.clone();
+        let event = match status {
+            ConnectionStatus::Connected => Some(EventKind::SourceConnected { source }),
+            ConnectionStatus::Reconnecting { .. } if was_connected => {
+                Some(EventKind::SourceDisconnected {
+                    source,
+                    will_retry: true,
+                })
+            }
+            ConnectionStatus::Reconnecting { .. } => None,
+            ConnectionStatus::Lost => Some(EventKind::SourceDisconnected {
+                source,
+                will_retry: false,
+            }),
+        };
+        notify_status(context.publishers, status);
+        if let Some(event) = event {
+            emit(context.publishers, event);
+        }
+    }
+
+    /// Emits the `count` samples of `sensor` dropped for `reason`, if any.
+    fn emit_dropped(
+        &self,
+        sensor: &SensorType,
+        count: usize,
+        reason: DropReason,
+        context: &PollContext<'_>,
+    ) {
+        if count == 0 {
+            return;
+        }
+        emit(
+            context.publishers,
+            EventKind::SamplesDropped {
+                source: self.sensor_cluster_tag.clone(),
+                sensor_type: sensor.clone(),
+                count,
+                reason,
+            },
+        );
+    }
+
+    /// Downloads the export of the experiment and compares it with the samples recorded in
+    /// `session`, logging the sensors whose streamed samples diverge.
+    async fn cross_check_export(&self, session: &SessionLog) -> Result<(), PhyphoxError> {
+        log::info!("Cross-checking export...");
+        let export = self.client.fetch_bytes(EXPORT_CMD).await?;
+        let report = session.cross_check(&export, &self.sensor_cluster)?;
+        for (sensor, divergence) in report.sensors.iter() {
+            if !divergence.is_consistent(report.tolerance) {
+                log::warn!(
+                    "Streamed samples of {} diverge from the export: {:?}",
+                    sensor,
+                    divergence
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Filters the samples of `sensor` with its filter chain, if any, and publishes them.
+    fn publish<T>(
+        &self,
+        sensor: &SensorType,
+        samples: Vec<T>,
+        filter_chain: &mut Option<FilterChain<T>>,
+        publishers: Option<&PublisherManager<SensorReadings<T>, SensorType>>,
+    ) where
+        T: IMUSample,
+    {
+        let filtered_data = filter_samples(sensor, filter_chain, samples);
+        if filtered_data.is_empty() {
+            return;
+        }
+        if let Some(publishers) = publishers {
+            let buffer =
+                SensorReadings::from_vec(&self.sensor_cluster_tag, sensor.clone(), filtered_data);
+            publishers.notify_listeners(sensor.clone(), Arc::new(buffer));
+        }
+    }
 }
 
 #[async_trait]
 impl PhyphoxPort for Phyphox {
     /// Starts the data acquisition process. The process is stopped with a SIGINT signal
     /// Returns FetchData error if it can't connect to REST API.
-    async fn start(
-        &self,
-        period_millis: Duration,
-        abort_signal: Option<Arc<Notify>>,
-        publisher: Option<Vec<Publisher<SensorReadings<Sample3D>>>>,
-    ) -> Result<(), PhyphoxError> {
+    async fn start(&self, options: PhyphoxStartOptions) -> Result<(), PhyphoxError> {
+        let PhyphoxStartOptions {
+            period_millis,
+            abort_signal,
+            publishers,
+            filters,
+            clipping,
+            reconnect,
+            session,
+        } = options;
         let timestamp_at_boot_secs = Clock::now().as_secs();
+        if let Some(session) = session.as_ref() {
+            session.clear();
+        }
         self.clear_cmd().await?;
         self.start_cmd().await?;
+        let source = self.sensor_cluster_tag.clone();
+        emit(
+            publishers.as_ref(),
+            EventKind::RecordingStarted {
+                source: source.clone(),
+            },
+        );
+        notify_status(publishers.as_ref(), ConnectionStatus::Connected);
+        emit(publishers.as_ref(), EventKind::SourceConnected { source });
 
-        let mut last_time = [0.0; N_SENSORS];
-
-        log::info!("Fetching data...");
-
-        let mut ma_filters: Vec<Option<MovingAverage<XYZ>>> =
-            vec![Some(MovingAverage::new(DEFAULT_WINDOW_SIZE)); N_SENSORS];
+        let mut vector_filters = build_filter_chains(filters.vectors.as_ref(), N_VECTOR_SENSORS);
+        let mut scalar_filters = build_filter_chains(filters.scalars.as_ref(), N_SCALAR_SENSORS);
 
         let active_sensor = self
             .get_available_sensors()
             .await
             .map_err(|e| PhyphoxError::Other(e.to_string()))?;
+        let sensors: Vec<&SensorType> = self
+            .sensor_cluster
+            .iter()
+            .filter(|sensor| active_sensor.contains(sensor))
+            .collect();
 
         let abort_signal = abort_signal.unwrap_or(Arc::new(Notify::new()));
+        let context = PollContext {
+            period: period_millis,
+            timestamp_at_boot_secs,
+            reconnect: &reconnect,
+            publishers: publishers.as_ref(),
+            clipping: clipping.as_deref(),
+            session: session.as_deref(),
+            coordinator: PollCoordinator::new(sensors.len()),
+            connected: AtomicBool::new(true),
+        };
 
-        let mut ticker = interval(period_millis);
-        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
-
-        loop {
-            tokio::select! {
-                _ = abort_signal.notified() => {
-                    break;
-                }
+        let mut tasks = Vec::with_capacity(sensors.len());
+        for (index, sensor) in sensors.iter().copied().enumerate() {
+            let (_, _, sensor_idx) = helpers::control_str(sensor)?;
+            let filter = if sensor_idx < N_VECTOR_SENSORS {
+                SensorFilter::Vector(vector_filters[sensor_idx].take())
+            } else {
+                SensorFilter::Scalar(scalar_filters[sensor_idx - N_VECTOR_SENSORS].take())
+            };
+            tasks.push(self.poll_sensor(index, sensor, filter, &context));
+        }
 
-            _ = ticker.tick() =>  {
-                let futures: Vec<Pin<Box<dyn Future<Output = Result<(Vec<f64>,Vec<XYZ>,bool),_>> + Send >>> = self.sensor_cluster.iter()
-                        .filter(|sensor| active_sensor.contains(sensor))
-                        .map(|sensor| {
-                            let sensor_idx = usize::from(sensor);
-                            let (time_str, variables, sensor_idx) = match helpers::control_str(sensor_idx) {
-                                Ok(data) => data,
-                                Err(_) => return Box::pin(async { Err(PhyphoxError::Other("Control string error".to_string())) }) as Pin<Box<dyn Future<Output = Result<_, _>> + Send>>, // Return an async error
-                            };
-
-                            Box::pin(async move {
-                                self.get_data(time_str, timestamp_at_boot_secs, last_time[sensor_idx], &variables).await
-                            }) as Pin<Box<dyn Future<Output = Result<_, _>> + Send>>
-                        })
-                        .collect();
-
-                    let results = join_all(futures).await;
-
-                    for (i, result) in results.into_iter().enumerate() {
-                        match result {
-                            Ok((timestamp_info, untimed_data_info, is_measuring)) => {
-                                if !is_measuring {
-                                    log::info!("Recording stopped.");
-                                        continue;
-                                    }
-
-                                let sensor = &self.sensor_cluster[i]; // Reconstruct sensor reference
-                                let (_,_,sensor_idx) = helpers::control_str(usize::from(sensor))?;
-
-                                helpers::update_measurement_time(&timestamp_info, &mut last_time[sensor_idx], timestamp_at_boot_secs);
-
-                                let timed_samples: Vec<Sample3D> = timestamp_info
-                                    .into_iter()
-                                    .zip(untimed_data_info.into_iter())
-                                    .map(|(t, s)| Sample3D::from_measurement(t, s))
-                                    .collect();
-
-                                let filtered_data = match ma_filters[sensor_idx].as_mut() {
-                                    Some(ma_filter) => ma_filter.filter_batch(timed_samples.clone()),
-                                    None => Ok(timed_samples.clone()),
-                                };
-
-                               if let Ok(filtered_data) = filtered_data {
-                                    let buffer = SensorReadings::from_vec(&self.sensor_cluster_tag, sensor.clone(), filtered_data);
-                                    if let Some(publisher) = publisher.as_ref() {
-                                        publisher[sensor_idx].notify_listeners(Arc::new(buffer));
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                log::error!("Error fetching data: {:?}", e);
-                            }
-                        }
-                    }
-                }
+        log::info!("Fetching data...");
+        // the tasks run until aborted, unless every sensor loses the connection
+        tokio::select! {
+            _ = abort_signal.notified() => {}
+            mut errors = join_all(tasks), if !sensors.is_empty() => {
+                return Err(errors.remove(0));
             }
         }
 
         self.stop_cmd().await?;
+        emit(
+            publishers.as_ref(),
+            EventKind::RecordingStopped {
+                source: self.sensor_cluster_tag.clone(),
+            },
+        );
+        if let Some(session) = session.as_ref() {
+            // the session did complete, so a failed cross-check is only reported
+            if let Err(e) = self.cross_check_export(session).await {
+                log::error!("Error cross-checking export: {:?}", e);
+            }
+        }
         Ok(())
     }
     fn get_tag(&self) -> &str {
@@ -239,22 +481,165 @@ impl PhyphoxPort for Phyphox {
     fn get_sensor_cluster(&self) -> Vec<SensorType> {
         self.sensor_cluster.clone()
     }
+
+    fn get_duplicate_count(&self, sensor_type: &SensorType) -> usize {
+        self.duplicates.get_duplicate_count(sensor_type)
+    }
+}
+
+/// Returns the device at `base_url` if it answers as a phyphox phone within `timeout`.
+pub(crate) async fn probe_device(base_url: String, timeout: Duration) -> Option<DiscoveredDevice> {
+    let client = HttpClient::with_timeout(base_url.clone(), timeout).ok()?;
+    let config = client.fetch_json(CONFIG_CMD).await.ok()?;
+    let available_sensors = helpers::get_sensor_kinds(&config)?;
+    // older phyphox versions don't report metadata
+    let device_id = client
+        .fetch_json(META_CMD)
+        .await
+        .ok()
+        .and_then(|meta| helpers::get_device_id(&meta));
+    Some(DiscoveredDevice {
+        base_url,
+        available_sensors,
+        device_id,
+    })
+}
+
+/// Returns a filter chain for each of `n_sensors` sensors, if `builder` is given.
+pub(crate) fn build_filter_chains<T: IMUSample>(
+    builder: Option<&FilterChainBuilder<T>>,
+    n_sensors: usize,
+) -> Vec<Option<FilterChain<T>>> {
+    (0..n_sensors)
+        .map(|_| builder.map(FilterChainBuilder::build))
+        .collect()
+}
+
+/// Returns `samples` of `sensor` filtered by `filter_chain`, if any, reporting the errors of
+/// the chain. A misconfigured chain is removed, as explained in `filter_or_disable`.
+pub(crate) fn filter_samples<T: IMUSample>(
+    sensor: &SensorType,
+    filter_chain: &mut Option<FilterChain<T>>,
+    samples: Vec<T>,
+) -> Vec<T> {
+    let (filtered, error) = filter_or_disable(filter_chain, samples);
+    match error {
+        Some(e) if e.is_recoverable() => log::debug!("Skipping samples of {}: {}", sensor, e),
+        Some(e) => log::error!("Removing the filter chain of {}: {}", sensor, e),
+        None => {}
+    }
+    filtered
+}
+
+/// Builds timed samples from the fetched timestamps and values, skipping malformed values.
+fn to_samples<T>(timestamps: Vec<f64>, values: Vec<Vec<f64>>) -> Vec<T>
+where
+    T: IMUSample,
+    T::Untimed: TryFrom<Vec<f64>>,
+{
+    timestamps
+        .into_iter()
+        .zip(values)
+        .filter_map(|(t, v)| {
+            T::Untimed::try_from(v)
+                .ok()
+                .map(|s| T::from_measurement(t, s))
+        })
+        .collect()
+}
+
+fn notify_status(publishers: Option<&PortPublishers>, status: ConnectionStatus) {
+    if let Some(publishers) = publishers {
+        publishers.notify_status(status);
+    }
+}
+
+fn emit(publishers: Option<&PortPublishers>, event: EventKind) {
+    if let Some(publishers) = publishers {
+        publishers.emit(event);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use uuid::Uuid;
-    use wiremock::matchers::method;
+    use crate::models::export::tests as export_tests;
+    use crate::ports::PortFilters;
+    use imu_common::errors::ImuError;
+    use imu_common::traits::IMUFilter;
+    use imu_common::types::filters::MovingAverage;
+    use imu_common::types::sensors::SensorClusterBuilder;
+    use imu_common::types::untimed::{Scalar, XYZ};
+    use publisher::events::{Event, EventBus};
+    use publisher::{ChannelConfig, Listener, Publishable, Publisher, PublisherManager, Receiver};
+    use std::sync::Mutex;
+    use wiremock::matchers::{method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
+    /// Mounts the config and control endpoints, exporting an accelerometer.
+    async fn mount_phone(mock_server: &MockServer) {
+        Mock::given(path("/config"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "export": [{ "set": "Accelerometer" }]
+            })))
+            .mount(mock_server)
+            .await;
+        Mock::given(path("/control"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "result": true
+            })))
+            .mount(mock_server)
+            .await;
+    }
+
+    fn acc_data() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "buffer": {
+                "accX": { "buffer": [1.0], "size": 0, "updateMode": "partial" },
+                "accY": { "buffer": [3.0], "size": 0, "updateMode": "partial" },
+                "accZ": { "buffer": [5.0], "size": 0, "updateMode": "partial" },
+                "acc_time": { "buffer": [1.0], "size": 0, "updateMode": "partial" }
+            },
+            "status": { "measuring": true }
+        }))
+    }
+
+    /// Returns the publishers of `sensor_cluster`, and the connection status events received.
+    fn status_publishers(
+        sensor_cluster: &[SensorType],
+    ) -> (PortPublishers, Arc<Mutex<Vec<ConnectionStatus>>>) {
+        let publishers = PortPublishers {
+            vectors: PublisherManager::new(sensor_cluster),
+            scalars: PublisherManager::new(&[]),
+            status: Publisher::new(),
+            events: EventBus::new(),
+        };
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut listener = Listener::new({
+            let events = events.clone();
+            move |_id, status: Arc<ConnectionStatus>| events.lock().unwrap().push((*status).clone())
+        });
+        publishers.status.register_listener(&mut listener);
+        (publishers, events)
+    }
+
+    /// Returns the kinds of the next `n` events received.
+    fn next_events(receiver: &Receiver<Event>, n: usize) -> Vec<EventKind> {
+        (0..n)
+            .filter_map(|_| receiver.recv_timeout(Duration::from_secs(1)))
+            .map(|event| event.kind.clone())
+            .collect()
+    }
+
+    fn fast_policy(max_retries: usize) -> ReconnectPolicy {
+        ReconnectPolicy::new()
+            .with_initial_backoff(Duration::from_millis(10))
+            .with_max_retries(max_retries)
+    }
+
     #[tokio::test]
     async fn test_phyphox_new() {
-        let sensor_cluster = vec![
-            SensorType::Accelerometer(Uuid::new_v4()),
-            SensorType::Gyroscope(Uuid::new_v4()),
-            SensorType::Magnetometer(Uuid::new_v4()),
-        ];
+        let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
         Phyphox::new("http://localhost", "Test", sensor_cluster)
             .expect("Error creating Phyphox instance");
     }
@@ -262,11 +647,7 @@ mod tests {
     #[tokio::test]
     async fn test_phyphox_fetch_json() {
         let mock_server = MockServer::start().await;
-        let sensor_cluster = vec![
-            SensorType::Accelerometer(Uuid::new_v4()),
-            SensorType::Gyroscope(Uuid::new_v4()),
-            SensorType::Magnetometer(Uuid::new_v4()),
-        ];
+        let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
 
         Mock::given(method("GET"))
             .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
@@ -284,11 +665,7 @@ mod tests {
     #[tokio::test]
     async fn test_phyphox_control() {
         let mock_server = MockServer::start().await;
-        let sensor_cluster = vec![
-            SensorType::Accelerometer(Uuid::new_v4()),
-            SensorType::Gyroscope(Uuid::new_v4()),
-            SensorType::Magnetometer(Uuid::new_v4()),
-        ];
+        let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
 
         Mock::given(method("GET"))
             .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
@@ -309,11 +686,7 @@ mod tests {
     #[tokio::test]
     async fn test_phyphox_get_data() {
         let mock_server = MockServer::start().await;
-        let sensor_cluster = vec![
-            SensorType::Accelerometer(Uuid::new_v4()),
-            SensorType::Gyroscope(Uuid::new_v4()),
-            SensorType::Magnetometer(Uuid::new_v4()),
-        ];
+        let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
 
         Mock::given(method("GET"))
             .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
@@ -333,7 +706,7 @@ mod tests {
         let phyphox = Phyphox::new(mock_server.uri().as_str(), "Test", sensor_cluster).unwrap();
 
         let (_timestamps, data, is_measuring) = phyphox
-            .get_data("acc_time", 0.0, 0.0, &["accX", "accY", "accZ"])
+            .get_data::<XYZ>("acc_time", 0.0, 0.0, &["accX", "accY", "accZ"])
             .await
             .unwrap();
 
@@ -350,11 +723,7 @@ mod tests {
     #[tokio::test]
     async fn test_phyphox_get_incomplete_data() {
         let mock_server = MockServer::start().await;
-        let sensor_cluster = vec![
-            SensorType::Accelerometer(Uuid::new_v4()),
-            SensorType::Gyroscope(Uuid::new_v4()),
-            SensorType::Magnetometer(Uuid::new_v4()),
-        ];
+        let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
 
         Mock::given(method("GET"))
             .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
@@ -374,11 +743,462 @@ mod tests {
         let phyphox = Phyphox::new(mock_server.uri().as_str(), "Test", sensor_cluster).unwrap();
 
         let (_timestamps, data, is_measuring) = phyphox
-            .get_data("acc_time", 0.0, 0.0, &["accX", "accY", "accZ"])
+            .get_data::<XYZ>("acc_time", 0.0, 0.0, &["accX", "accY", "accZ"])
             .await
             .unwrap();
 
         assert_eq!(data, vec![XYZ::try_from(vec![1.0, 3.0, 5.0]).unwrap(),]);
         assert!(is_measuring);
     }
+
+    #[tokio::test]
+    async fn test_reconnect_after_fetch_error() {
+        let mock_server = MockServer::start().await;
+        let sensor_cluster = SensorClusterBuilder::new().accelerometer().build().unwrap();
+        mount_phone(&mock_server).await;
+        Mock::given(path("/get"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/get"))
+            .respond_with(acc_data())
+            .mount(&mock_server)
+            .await;
+
+        let phyphox =
+            Phyphox::new(mock_server.uri().as_str(), "Test", sensor_cluster.clone()).unwrap();
+        let (publishers, events) = status_publishers(&sensor_cluster);
+        let received = Arc::new(Mutex::new(0));
+        let mut listener = Listener::new({
+            let received = received.clone();
+            move |_id, _samples: Arc<SensorReadings<Sample3D>>| *received.lock().unwrap() += 1
+        });
+        publishers
+            .vectors
+            .add_listener(&mut listener, &sensor_cluster[0])
+            .unwrap();
+        let abort_signal = Arc::new(Notify::new());
+        tokio::spawn({
+            let abort_signal = abort_signal.clone();
+            let received = received.clone();
+            async move {
+                while *received.lock().unwrap() == 0 {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                abort_signal.notify_one();
+            }
+        });
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            phyphox.start(
+                PhyphoxStartOptions::new(Duration::from_millis(10))
+                    .with_abort_signal(abort_signal)
+                    .with_publishers(publishers)
+                    .with_reconnect(fast_policy(3)),
+            ),
+        )
+        .await
+        .expect("Acquisition didn't resume");
+
+        assert!(result.is_ok());
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                ConnectionStatus::Connected,
+                ConnectionStatus::Reconnecting { attempt: 1 },
+                ConnectionStatus::Connected,
+                ConnectionStatus::Reconnecting { attempt: 1 },
+                ConnectionStatus::Connected,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sensor_failure_isolated() {
+        let mock_server = MockServer::start().await;
+        let sensor_cluster = SensorClusterBuilder::new().six_axis().build().unwrap();
+        Mock::given(path("/config"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "export": [{ "set": "Accelerometer" }, { "set": "Gyroscope" }]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/control"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .mount(&mock_server)
+            .await;
+        // the gyroscope buffers never answer
+        Mock::given(path("/get"))
+            .and(|request: &wiremock::Request| {
+                request
+                    .url
+                    .query()
+                    .is_some_and(|query| query.starts_with("gyro"))
+            })
+            .respond_with(ResponseTemplate::new(500))
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/get"))
+            .respond_with(acc_data())
+            .mount(&mock_server)
+            .await;
+
+        let phyphox =
+            Phyphox::new(mock_server.uri().as_str(), "Test", sensor_cluster.clone()).unwrap();
+        let (publishers, events) = status_publishers(&sensor_cluster);
+        let received = Arc::new(Mutex::new(0));
+        let mut listener = Listener::new({
+            let received = received.clone();
+            move |_id, _samples: Arc<SensorReadings<Sample3D>>| *received.lock().unwrap() += 1
+        });
+        publishers
+            .vectors
+            .add_listener(&mut listener, &sensor_cluster[0])
+            .unwrap();
+        let abort_signal = Arc::new(Notify::new());
+        tokio::spawn({
+            let abort_signal = abort_signal.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                abort_signal.notify_one();
+            }
+        });
+
+        let result = phyphox
+            .start(
+                PhyphoxStartOptions::new(Duration::from_millis(10))
+                    .with_abort_signal(abort_signal)
+                    .with_publishers(publishers)
+                    .with_reconnect(fast_policy(1)),
+            )
+            .await;
+
+        // the accelerometer kept publishing while the gyroscope failed
+        assert!(result.is_ok());
+        assert!(*received.lock().unwrap() > 0);
+        assert_eq!(*events.lock().unwrap(), vec![ConnectionStatus::Connected]);
+    }
+
+    #[tokio::test]
+    async fn test_connection_lost() {
+        let mock_server = MockServer::start().await;
+        let sensor_cluster = SensorClusterBuilder::new().accelerometer().build().unwrap();
+        Mock::given(path("/config"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "export": [{ "set": "Accelerometer" }]
+            })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/control"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .mount(&mock_server)
+            .await;
+
+        let phyphox =
+            Phyphox::new(mock_server.uri().as_str(), "Test", sensor_cluster.clone()).unwrap();
+        let (publishers, events) = status_publishers(&sensor_cluster);
+        let receiver = publishers.events.subscribe(ChannelConfig::new());
+
+        let result = phyphox
+            .start(
+                PhyphoxStartOptions::new(Duration::from_millis(10))
+                    .with_publishers(publishers)
+                    .with_reconnect(fast_policy(2)),
+            )
+            .await;
+
+        assert!(matches!(result, Err(PhyphoxError::FetchData(_))));
+        let source = "Test".to_string();
+        assert_eq!(
+            next_events(&receiver, 4),
+            vec![
+                EventKind::RecordingStarted {
+                    source: source.clone()
+                },
+                EventKind::SourceConnected {
+                    source: source.clone()
+                },
+                EventKind::SourceDisconnected {
+                    source: source.clone(),
+                    will_retry: true
+                },
+                EventKind::SourceDisconnected {
+                    source,
+                    will_retry: false
+                },
+            ]
+        );
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                ConnectionStatus::Connected,
+                ConnectionStatus::Reconnecting { attempt: 1 },
+                ConnectionStatus::Reconnecting { attempt: 2 },
+                ConnectionStatus::Lost,
+            ]
+        );
+    }
+
+    /// Returns the first two pressure values published with `filters`.
+    async fn publish_scalars(filters: PortFilters) -> Vec<f64> {
+        let mock_server = MockServer::start().await;
+        let sensor_cluster = SensorClusterBuilder::new()
+            .other("pressure")
+            .build()
+            .unwrap();
+        Mock::given(path("/config"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "export": [{ "set": "Pressure" }]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/control"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "result": true
+            })))
+            .mount(&mock_server)
+            .await;
+        let pressure = |values: [f64; 2], timestamps: [f64; 2]| {
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "buffer": {
+                    "pressure": { "buffer": values, "size": 0, "updateMode": "partial" },
+                    "pressure_time": { "buffer": timestamps, "size": 0, "updateMode": "partial" }
+                },
+                "status": { "measuring": true }
+            }))
+        };
+        Mock::given(path("/get"))
+            .respond_with(pressure([1013.0, 1014.0], [1.0, 2.0]))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/get"))
+            .respond_with(pressure([1015.0, 1016.0], [3.0, 4.0]))
+            .mount(&mock_server)
+            .await;
+
+        let phyphox =
+            Phyphox::new(mock_server.uri().as_str(), "Test", sensor_cluster.clone()).unwrap();
+        let publishers = PortPublishers {
+            vectors: PublisherManager::new(&[]),
+            scalars: PublisherManager::new(&sensor_cluster),
+            status: Publisher::new(),
+            events: EventBus::new(),
+        };
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut listener = Listener::new({
+            let received = received.clone();
+            move |_id, samples: Arc<SensorReadings<SampleScalar>>| {
+                received.lock().unwrap().extend(samples.get_samples())
+            }
+        });
+        publishers
+            .scalars
+            .add_listener(&mut listener, &sensor_cluster[0])
+            .unwrap();
+        let abort_signal = Arc::new(Notify::new());
+        tokio::spawn({
+            let abort_signal = abort_signal.clone();
+            let received = received.clone();
+            async move {
+                while received.lock().unwrap().is_empty() {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                abort_signal.notify_one();
+            }
+        });
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            phyphox.start(
+                PhyphoxStartOptions::new(Duration::from_millis(10))
+                    .with_abort_signal(abort_signal)
+                    .with_publishers(publishers)
+                    .with_filters(filters)
+                    .with_reconnect(fast_policy(0)),
+            ),
+        )
+        .await
+        .expect("No scalar samples received");
+
+        assert!(result.is_ok());
+        let received = received.lock().unwrap();
+        received[..2]
+            .iter()
+            .map(|sample| Vec::<f64>::from(sample.get_measurement())[0])
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_publish_scalars() {
+        let values = publish_scalars(PortFilters::default()).await;
+        assert_eq!(values, vec![1013.0, 1014.0]);
+    }
+
+    #[tokio::test]
+    async fn test_publish_filtered_scalars() {
+        let filters = PortFilters {
+            scalars: Some(FilterChain::builder().with(MovingAverage::<Scalar>::new(2))),
+            ..Default::default()
+        };
+        let values = publish_scalars(filters).await;
+        assert_eq!(values, vec![506.5, 1013.5]);
+    }
+
+    /// Filter rejecting every batch, as a filter with invalid parameters.
+    #[derive(Clone)]
+    struct Misconfigured;
+
+    impl IMUFilter<SampleScalar> for Misconfigured {
+        fn filter_batch(
+            &mut self,
+            _samples: Vec<SampleScalar>,
+        ) -> Result<Vec<SampleScalar>, ImuError> {
+            Err(ImuError::InvalidParameter("Negative window".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_with_misconfigured_filter() {
+        let filters = PortFilters {
+            scalars: Some(FilterChain::builder().with(Misconfigured)),
+            ..Default::default()
+        };
+        // the chain is removed with the batch that failed, and the next ones are published
+        // unfiltered
+        let values = publish_scalars(filters).await;
+        assert_eq!(values, vec![1015.0, 1016.0]);
+    }
+
+    #[tokio::test]
+    async fn test_drop_duplicates() {
+        let mock_server = MockServer::start().await;
+        let sensor_cluster = SensorClusterBuilder::new().accelerometer().build().unwrap();
+        mount_phone(&mock_server).await;
+        // every fetch returns the same sample, as overlapping windows would
+        Mock::given(path("/get"))
+            .respond_with(acc_data())
+            .mount(&mock_server)
+            .await;
+
+        let phyphox =
+            Phyphox::new(mock_server.uri().as_str(), "Test", sensor_cluster.clone()).unwrap();
+        let (publishers, _) = status_publishers(&sensor_cluster);
+        let receiver = publishers.events.subscribe(ChannelConfig::new());
+        let received = Arc::new(Mutex::new(0));
+        let mut listener = Listener::new({
+            let received = received.clone();
+            move |_id, samples: Arc<SensorReadings<Sample3D>>| {
+                *received.lock().unwrap() += samples.get_samples().len()
+            }
+        });
+        publishers
+            .vectors
+            .add_listener(&mut listener, &sensor_cluster[0])
+            .unwrap();
+        let abort_signal = Arc::new(Notify::new());
+        tokio::spawn({
+            let abort_signal = abort_signal.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                abort_signal.notify_one();
+            }
+        });
+
+        phyphox
+            .start(
+                PhyphoxStartOptions::new(Duration::from_millis(10))
+                    .with_abort_signal(abort_signal)
+                    .with_publishers(publishers)
+                    .with_reconnect(fast_policy(0)),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(*received.lock().unwrap(), 1);
+        let n_duplicates = phyphox.get_duplicate_count(&sensor_cluster[0]);
+        assert!(n_duplicates > 0);
+        let n_dropped: usize =
+            std::iter::from_fn(|| receiver.recv_timeout(Duration::from_millis(100)))
+                .filter_map(|event| match event.kind {
+                    EventKind::SamplesDropped {
+                        count,
+                        reason: DropReason::Duplicated,
+                        ..
+                    } => Some(count),
+                    _ => None,
+                })
+                .sum();
+        assert_eq!(n_dropped, n_duplicates);
+    }
+
+    #[tokio::test]
+    async fn test_cross_check_export() {
+        let mock_server = MockServer::start().await;
+        let sensor_cluster = SensorClusterBuilder::new().accelerometer().build().unwrap();
+        mount_phone(&mock_server).await;
+        Mock::given(path("/get"))
+            .respond_with(acc_data())
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/get"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "buffer": {
+                    "accX": { "buffer": [], "size": 0, "updateMode": "partial" },
+                    "accY": { "buffer": [], "size": 0, "updateMode": "partial" },
+                    "accZ": { "buffer": [], "size": 0, "updateMode": "partial" },
+                    "acc_time": { "buffer": [], "size": 0, "updateMode": "partial" }
+                },
+                "status": { "measuring": true }
+            })))
+            .mount(&mock_server)
+            .await;
+        // the phone recorded a sample after the last fetch
+        let export = export_tests::zip_export(&[(
+            "Accelerometer.csv",
+            "\"Time (s)\",\"X (m/s^2)\",\"Y (m/s^2)\",\"Z (m/s^2)\"\n\
+            1.000000000E0,1.000000000E0,3.000000000E0,5.000000000E0\n\
+            2.000000000E0,2.000000000E0,4.000000000E0,6.000000000E0\n",
+        )]);
+        Mock::given(path("/export"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(export))
+            .mount(&mock_server)
+            .await;
+
+        let phyphox =
+            Phyphox::new(mock_server.uri().as_str(), "Test", sensor_cluster.clone()).unwrap();
+        let session = Arc::new(SessionLog::new());
+        let abort_signal = Arc::new(Notify::new());
+        tokio::spawn({
+            let abort_signal = abort_signal.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                abort_signal.notify_one();
+            }
+        });
+
+        phyphox
+            .start(
+                PhyphoxStartOptions::new(Duration::from_millis(10))
+                    .with_abort_signal(abort_signal)
+                    .with_reconnect(fast_policy(0))
+                    .with_session(session.clone()),
+            )
+            .await
+            .unwrap();
+
+        let report = session.get_report().expect("Export not cross-checked");
+        let divergence = report.get(&sensor_cluster[0]).unwrap();
+        assert_eq!((divergence.n_exported, divergence.n_streamed), (2, 1));
+        assert_eq!((divergence.n_missing, divergence.n_unexpected), (1, 0));
+        assert!(divergence.max_value_error < 1e-9);
+        assert!(!report.is_consistent());
+    }
 }
@@ -1 +1,5 @@
-pub(crate) const N_SENSORS: usize = 3;
+/// Number of 3D sensors: accelerometer, gyroscope and magnetometer.
+pub(crate) const N_VECTOR_SENSORS: usize = 3;
+/// Number of scalar sensors: pressure, light, proximity and audio amplitude.
+pub(crate) const N_SCALAR_SENSORS: usize = 4;
+pub(crate) const N_SENSORS: usize = N_VECTOR_SENSORS + N_SCALAR_SENSORS;
@@ -1,11 +1,9 @@
 use serde_json::Value;
-use uuid::Uuid;
 
-use imu_common::types::sensors::sensor_type;
 use imu_common::types::sensors::SensorType;
 use imu_common::types::untimed::xyz::N_XYZ_COORDINATES;
-use imu_common::types::XYZ;
 
+use crate::constants::{N_SCALAR_SENSORS, N_VECTOR_SENSORS};
 use crate::models::errors::PhyphoxError;
 
 const ACC_VARIABLES: [&str; N_XYZ_COORDINATES] = ["accX", "accY", "accZ"];
@@ -21,26 +19,57 @@ const BUFFER: &str = "buffer";
 
 const EPS_MEASUREMENT_TIME: f64 = 10e-5;
 
+/// Prefixes of the phyphox sets of the 3D sensors, with their sensor kind.
+const VECTOR_KINDS: [(&str, &str); N_VECTOR_SENSORS] = [
+    ("acc", "accelerometer"),
+    ("gyr", "gyroscope"),
+    ("mag", "magnetometer"),
+];
+
+/// Scalar sensors, given as `SensorType::Other` sensors of these kinds (case insensitive), with
+/// their time and value buffers.
+const SCALAR_CONTROLS: [(&str, &str, [&str; 1]); N_SCALAR_SENSORS] = [
+    ("pressure", "pressure_time", ["pressure"]),
+    ("light", "light_time", ["light"]),
+    ("proximity", "proximity_time", ["proximity"]),
+    ("amplitude", "amplitude_time", ["amplitude"]),
+];
+
+/// Returns the time buffer, the value buffers and the index of `sensor`. 3D sensors are indexed
+/// first, followed by the scalar sensors.
+/// Returns an error if phyphox doesn't provide the sensor.
 pub(crate) fn control_str(
-    sensor: usize,
-) -> Result<(&'static str, [&'static str; N_XYZ_COORDINATES], usize), PhyphoxError> {
+    sensor: &SensorType,
+) -> Result<(&'static str, &'static [&'static str], usize), PhyphoxError> {
     match sensor {
-        sensor_type::ACCELEROMETER_OFFSET..sensor_type::GYROSCOPE_OFFSET => {
-            Ok((ACC_TIME, ACC_VARIABLES, 0))
-        }
-        sensor_type::GYROSCOPE_OFFSET..sensor_type::MAGNETOMETER_OFFSET => {
-            Ok((GYRO_TIME, GYRO_VARIABLES, 1))
-        }
-        sensor_type::MAGNETOMETER_OFFSET..sensor_type::OTHER_OFFSET => {
-            Ok((MAG_TIME, MAG_VARIABLES, 2))
-        }
-        _ => Err(PhyphoxError::Other(format!(
+        SensorType::Accelerometer(_) => Ok((ACC_TIME, &ACC_VARIABLES, 0)),
+        SensorType::Gyroscope(_) => Ok((GYRO_TIME, &GYRO_VARIABLES, 1)),
+        SensorType::Magnetometer(_) => Ok((MAG_TIME, &MAG_VARIABLES, 2)),
+        SensorType::Other(_, kind) => SCALAR_CONTROLS
+            .iter()
+            .position(|(scalar_kind, _, _)| scalar_kind.eq_ignore_ascii_case(kind))
+            .map(|index| {
+                let (_, time, variables) = &SCALAR_CONTROLS[index];
+                (*time, variables.as_slice(), N_VECTOR_SENSORS + index)
+            })
+            .ok_or(PhyphoxError::Other(format!(
+                "Sensor {} doesnt exist",
+                sensor
+            ))),
+        SensorType::Vendor(..) => Err(PhyphoxError::Other(format!(
             "Sensor {} doesnt exist",
             sensor
         ))),
     }
 }
 
+/// Returns an error if phyphox doesn't provide every sensor of `sensor_cluster`.
+pub(crate) fn check_sensor_cluster(sensor_cluster: &[SensorType]) -> Result<(), PhyphoxError> {
+    sensor_cluster
+        .iter()
+        .try_for_each(|sensor| control_str(sensor).map(|_| ()))
+}
+
 pub(crate) fn update_measurement_time(data: &[f64], timestamp: &mut f64, offset: f64) {
     if let Some(last_row) = data.last() {
         *timestamp = last_row - offset + EPS_MEASUREMENT_TIME;
@@ -94,30 +123,34 @@ pub(crate) fn parse_results(
 /// output[1] : [x[0], y[0]]
 /// output[2] : [x[1], y[1]]
 /// ...
-pub(crate) fn combine_results(
+///
+/// Samples missing a component are dropped.
+pub(crate) fn combine_results<U>(
     results: Vec<Vec<f64>>,
     timestamp_at_boot_secs: f64,
-) -> (Vec<f64>, Vec<XYZ>) {
-    let row_count = results.len().min(4);
-
+) -> (Vec<f64>, Vec<U>)
+where
+    U: TryFrom<Vec<f64>>,
+{
     let n_samples = results[0].len();
     let mut untimed_data = Vec::with_capacity(n_samples);
     let mut timestamp = Vec::with_capacity(n_samples);
 
-    // skip time row
-    for row in 0..row_count {
+    for row in 0..n_samples {
+        // skip time row
         let values: Vec<f64> = results
             .iter()
             .skip(1)
             .filter_map(|col| col.get(row))
             .cloned()
             .collect();
+        if values.len() != results.len() - 1 {
+            continue;
+        }
 
-        if let Ok(xyz) = XYZ::try_from(values) {
-            if let Some(t) = results[0].get(row) {
-                untimed_data.push(xyz);
-                timestamp.push(t + timestamp_at_boot_secs);
-            }
+        if let Ok(sample) = U::try_from(values) {
+            untimed_data.push(sample);
+            timestamp.push(results[0][row] + timestamp_at_boot_secs);
         }
     }
     (timestamp, untimed_data)
@@ -132,8 +165,8 @@ pub(crate) fn build_query(variables: &[&str], time_var: &str, since: Option<f64>
     let variable_query: String = variables
         .iter()
         .map(|&var| {
-            if since.is_some() {
-                format!("&{}={:.4}|{}", var, since.unwrap(), time_var)
+            if let Some(since_val) = since {
+                format!("&{}={:.4}|{}", var, since_val, time_var)
             } else {
                 format!("&{}", var)
             }
@@ -144,68 +177,136 @@ pub(crate) fn build_query(variables: &[&str], time_var: &str, since: Option<f64>
     query
 }
 
-pub(crate) fn to_type_id(sensor_type: &str, sensor_id_cluster: &[Uuid]) -> Option<String> {
-    let sensor_type = sensor_type.to_lowercase();
-
-    if sensor_type.contains("acc") {
-        Some(format!("Accelerometer::{}", sensor_id_cluster[0]))
-    } else if sensor_type.contains("gyr") {
-        Some(format!("Gyroscope::{}", sensor_id_cluster[1]))
-    } else if sensor_type.contains("mag") {
-        Some(format!("Magnetometer::{}", sensor_id_cluster[2]))
-    } else {
-        None
-    }
+/// Returns the sensor of `sensor_cluster` recorded by the export set `set`, e.g. `Accelerometer`
+/// or `Pressure`.
+pub(crate) fn to_sensor_type(set: &str, sensor_cluster: &[SensorType]) -> Option<SensorType> {
+    let set = set.to_lowercase();
+    sensor_cluster
+        .iter()
+        .find(|sensor| match sensor {
+            SensorType::Accelerometer(_) => set.contains("acc"),
+            SensorType::Gyroscope(_) => set.contains("gyr"),
+            SensorType::Magnetometer(_) => set.contains("mag"),
+            SensorType::Other(_, kind) => set.contains(&kind.to_lowercase()),
+            SensorType::Vendor(..) => false,
+        })
+        .cloned()
 }
 
-pub(crate) fn extract_uuids(sensors: &[SensorType]) -> Vec<Uuid> {
-    let mut uuids = vec![Uuid::nil(), Uuid::nil(), Uuid::nil()]; // Default with nil UUIDs
+/// Returns the unique id of the phone given the metadata reported by phyphox, if any.
+pub(crate) fn get_device_id(meta: &Value) -> Option<String> {
+    meta.get("uniqueID")
+        .and_then(|id| id.as_str())
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+}
 
-    for sensor in sensors {
-        match sensor {
-            SensorType::Accelerometer(uuid) => uuids[0] = *uuid,
-            SensorType::Gyroscope(uuid) => uuids[1] = *uuid,
-            SensorType::Magnetometer(uuid) => uuids[2] = *uuid,
-            _ => {} // Ignore other sensor types (if needed)
+/// Returns the sensor kinds exported by a phyphox experiment given its configuration, or `None`
+/// if it isn't a phyphox configuration.
+pub(crate) fn get_sensor_kinds(config: &Value) -> Option<Vec<String>> {
+    let exports = config.get("export")?.as_array()?;
+    let mut kinds: Vec<String> = vec![];
+    for set in exports
+        .iter()
+        .filter_map(|entry| entry.get("set").and_then(|s| s.as_str()))
+    {
+        let set = set.to_lowercase();
+        let kind = VECTOR_KINDS
+            .iter()
+            .find(|(prefix, _)| set.contains(prefix))
+            .map(|(_, kind)| *kind)
+            .or_else(|| {
+                SCALAR_CONTROLS
+                    .iter()
+                    .map(|(kind, _, _)| *kind)
+                    .find(|kind| set.contains(kind))
+            });
+        if let Some(kind) = kind.filter(|kind| !kinds.iter().any(|k| k == kind)) {
+            kinds.push(kind.to_string());
         }
     }
-
-    uuids
+    Some(kinds)
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rand::Rng;
+    use crate::constants::N_SENSORS;
+    use imu_common::types::sensors::SensorClusterBuilder;
+    use imu_common::types::untimed::Scalar;
+    use imu_common::types::XYZ;
     use serde_json::json;
+    use uuid::Uuid;
 
-    fn generate_random_integer(x: usize, y: usize) -> usize {
-        let mut rng = rand::thread_rng();
-        rng.gen_range(x..y) as usize
-    }
     #[test]
     fn test_control_str() {
-        for _ in 0..100 {
-            let idx = generate_random_integer(
-                sensor_type::ACCELEROMETER_OFFSET,
-                sensor_type::GYROSCOPE_OFFSET,
-            );
-            assert_eq!(control_str(idx).unwrap(), (ACC_TIME, ACC_VARIABLES, 0));
-
-            let idx = generate_random_integer(
-                sensor_type::GYROSCOPE_OFFSET,
-                sensor_type::MAGNETOMETER_OFFSET,
-            );
-            assert_eq!(control_str(idx).unwrap(), (GYRO_TIME, GYRO_VARIABLES, 1));
-
-            let idx = generate_random_integer(
-                sensor_type::MAGNETOMETER_OFFSET,
-                sensor_type::OTHER_OFFSET,
-            );
-            assert_eq!(control_str(idx).unwrap(), (MAG_TIME, MAG_VARIABLES, 2));
-
-            let idx = generate_random_integer(sensor_type::OTHER_OFFSET, sensor_type::MAX_OFFSET);
-            assert!(control_str(idx).is_err());
-        }
+        let id = Uuid::new_v4();
+        assert_eq!(
+            control_str(&SensorType::Accelerometer(id)).unwrap(),
+            (ACC_TIME, ACC_VARIABLES.as_slice(), 0)
+        );
+        assert_eq!(
+            control_str(&SensorType::Gyroscope(id)).unwrap(),
+            (GYRO_TIME, GYRO_VARIABLES.as_slice(), 1)
+        );
+        assert_eq!(
+            control_str(&SensorType::Magnetometer(id)).unwrap(),
+            (MAG_TIME, MAG_VARIABLES.as_slice(), 2)
+        );
+        assert_eq!(
+            control_str(&SensorType::Other(id, "light".to_string())).unwrap(),
+            ("light_time", ["light"].as_slice(), 4)
+        );
+        assert!(control_str(&SensorType::Other(id, "temperature".to_string())).is_err());
+
+        let scalars = SensorClusterBuilder::new()
+            .other("Pressure")
+            .other("Light")
+            .other("Proximity")
+            .other("Amplitude")
+            .build()
+            .unwrap();
+        let indices: Vec<usize> = scalars
+            .iter()
+            .map(|sensor| control_str(sensor).unwrap().2)
+            .collect();
+        assert_eq!(indices, (N_VECTOR_SENSORS..N_SENSORS).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_get_sensor_kinds() {
+        let config = json!({
+            "export": [
+                { "set": "Accelerometer" },
+                { "set": "Gyroscope (rotation rate)" },
+                { "set": "Light" },
+                { "set": "Accelerometer" },
+                { "set": "GPS" }
+            ]
+        });
+        assert_eq!(
+            get_sensor_kinds(&config).unwrap(),
+            vec!["accelerometer", "gyroscope", "light"]
+        );
+        assert!(get_sensor_kinds(&json!({ "key": "value" })).is_none());
+    }
+
+    #[test]
+    fn test_to_sensor_type() {
+        let sensor_cluster = SensorClusterBuilder::new()
+            .accelerometer()
+            .other("pressure")
+            .build()
+            .unwrap();
+        assert_eq!(
+            to_sensor_type("Accelerometer", &sensor_cluster),
+            Some(sensor_cluster[0].clone())
+        );
+        assert_eq!(
+            to_sensor_type("Pressure", &sensor_cluster),
+            Some(sensor_cluster[1].clone())
+        );
+        assert_eq!(to_sensor_type("Gyroscope", &sensor_cluster), None);
     }
 
     #[test]
@@ -271,9 +372,15 @@ mod tests {
             vec![0.4, 0.5, 0.6],
             vec![0.7, 0.8, 0.9],
         ];
-        let (timestamps, untimed_data) = combine_results(results, 0.0);
+        let (timestamps, untimed_data) = combine_results::<XYZ>(results, 0.0);
         assert_eq!(timestamps, vec![1.0, 2.0, 3.0]);
         assert_eq!(untimed_data.len(), 3);
+
+        // scalars, with a sample missing its value
+        let results = vec![vec![1.0, 2.0, 3.0, 4.0, 5.0], vec![0.1, 0.2, 0.3, 0.4]];
+        let (timestamps, untimed_data) = combine_results::<Scalar>(results, 10.0);
+        assert_eq!(timestamps, vec![11.0, 12.0, 13.0, 14.0]);
+        assert_eq!(untimed_data[3], Scalar::new(0.4));
     }
 
     #[test]
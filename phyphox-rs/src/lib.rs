@@ -8,12 +8,36 @@
 //!
 //! Features include:
 //! - Recording of 3-axis Accelerometer [m/s^2], Gyroscope [rad/s] and Magnetometer (&uT)
+//! - Recording of scalar Pressure [hPa], Light [lx], Proximity [cm] and audio Amplitude sensors as
+//!   `SampleScalar` readings.
 //! - Tagging sensors so that readings from different sensor placements can be distinguished.
 //! - Selection of read frequency. Note that the sample rate is configured in the mobile app.
-//! - Data smoothing with a moving average filter._
+//! - Data smoothing with a configurable `FilterChain`, e.g. a moving average filter.
+//! - Detection of clipped samples at the sensor full scale range.
+//! - Removal of the samples returned twice by overlapping fetches, with a count per sensor.
+//! - Reconnection with exponential backoff when the phone stops answering, with connection status
+//!   events published to registered status listeners.
+//! - Lifecycle events (connection, start and stop of the recording, dropped samples) emitted to an
+//!   `EventBus`, so dashboards can follow the acquisition without parsing the logs.
 //! - Registration of listeners to receive sensor data once received and processed.
+//! - Creation of `phyphox` and `mock` sources by name through a `SourceRegistry`.
+//! - Discovery of phones running phyphox remote access on the local network.
+//! - Stable sensor ids per phone, kept in an `IdentityStore` keyed by the unique id the phone
+//!   reports, so recordings of different sessions can be linked to the same device.
+//! - Cross-check of the samples streamed during a session against the experiment export
+//!   downloaded from the phone once it stops, reporting any divergence.
+//! - Duty cycled acquisition for long term, low power monitoring (e.g. 10 s on and 50 s off),
+//!   starting and stopping the experiment on the phone and publishing the phase changes so the
+//!   rest of the pipeline can pause and mark the gaps.
+//! - Soak mode for permanent deployments, checking periodically that timestamps are monotonic,
+//!   sensors keep delivering and memory stays bounded, with a heartbeat log.
 //!
-//! **NOTE** Currently, `phyphox-rs` only captures data from Accelerometer, Gyroscope and Magnetometer.
+//! Scalar sensors are declared as `SensorType::Other` sensors named `pressure`, `light`, `proximity`
+//! or `amplitude` (e.g. `SensorClusterBuilder::new().other("pressure")`), and are fetched from the
+//! phyphox buffers `<name>` and `<name>_time`. Their readings are published as `SampleScalar`
+//! readings by the `PhyphoxScalarSource` returned by `get_scalar_source`.
+//!
+//! **NOTE** Other sensors available in phyphox are not captured.
 
 pub(crate) mod adapters;
 pub(crate) mod constants;
@@ -22,4 +46,7 @@ pub mod models;
 pub(crate) mod ports;
 pub mod services;
 
-pub use services::{run_mock_service, run_service};
+pub use services::{
+    discover_devices, register_sources, run_duty_cycled_service, run_mock_service, run_service,
+    run_soak_service,
+};
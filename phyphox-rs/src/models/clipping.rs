@@ -0,0 +1,160 @@
+//! Module clipping
+//!
+//! Phone sensors saturate at their full scale range (e.g. ±2 g for some accelerometers).
+//! `ClippingMonitor` checks incoming samples against a configurable range per sensor and
+//! either counts or discards the ones at/near the limit.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use imu_common::traits::IMUSample;
+use imu_common::types::sensors::SensorType;
+use imu_common::types::timed::Sample3D;
+
+const DEFAULT_CLIP_MARGIN: f64 = 0.01;
+
+/// Action taken on samples at/near the range limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClippingPolicy {
+    /// Clipped samples are forwarded and counted.
+    #[default]
+    Count,
+    /// Clipped samples are counted and removed from the published readings.
+    Discard,
+}
+
+/// Range limit of a sensor. A sample is clipped if any of its components is within
+/// `margin` (relative) of `limit`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RangeLimit {
+    pub limit: f64,
+    pub margin: f64,
+    pub policy: ClippingPolicy,
+}
+
+impl RangeLimit {
+    pub fn new(limit: f64, policy: ClippingPolicy) -> Self {
+        Self {
+            limit: limit.abs(),
+            margin: DEFAULT_CLIP_MARGIN,
+            policy,
+        }
+    }
+
+    pub fn with_margin(mut self, margin: f64) -> Self {
+        self.margin = margin.clamp(0.0, 1.0);
+        self
+    }
+
+    fn is_clipped(&self, sample: &Sample3D) -> bool {
+        let threshold = self.limit * (1.0 - self.margin);
+        sample
+            .get_measurement()
+            .inner()
+            .iter()
+            .any(|v| v.abs() >= threshold)
+    }
+}
+
+/// Per sensor clipping detection shared between the service and the acquisition loop.
+#[derive(Debug, Default)]
+pub struct ClippingMonitor {
+    limits: RwLock<HashMap<SensorType, RangeLimit>>,
+    counters: RwLock<HashMap<SensorType, usize>>,
+}
+
+impl ClippingMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_range_limit(&self, sensor_type: &SensorType, limit: RangeLimit) {
+        let mut limits = self.limits.write().unwrap();
+        limits.insert(sensor_type.clone(), limit);
+    }
+
+    pub fn remove_range_limit(&self, sensor_type: &SensorType) {
+        let mut limits = self.limits.write().unwrap();
+        limits.remove(sensor_type);
+    }
+
+    /// Returns the number of clipped samples detected for `sensor_type` so far.
+    pub fn get_clipped_count(&self, sensor_type: &SensorType) -> usize {
+        let counters = self.counters.read().unwrap();
+        counters.get(sensor_type).copied().unwrap_or(0)
+    }
+
+    /// Counts clipped samples and applies the configured policy. Samples are returned
+    /// unchanged if `sensor_type` has no range limit.
+    pub(crate) fn check(&self, sensor_type: &SensorType, samples: Vec<Sample3D>) -> Vec<Sample3D> {
+        let limit = match self.limits.read().unwrap().get(sensor_type) {
+            Some(limit) => *limit,
+            None => return samples,
+        };
+
+        let n_clipped = samples.iter().filter(|s| limit.is_clipped(s)).count();
+        if n_clipped > 0 {
+            let mut counters = self.counters.write().unwrap();
+            *counters.entry(sensor_type.clone()).or_insert(0) += n_clipped;
+        }
+
+        match limit.policy {
+            ClippingPolicy::Count => samples,
+            ClippingPolicy::Discard => samples
+                .into_iter()
+                .filter(|s| !limit.is_clipped(s))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn samples() -> Vec<Sample3D> {
+        vec![
+            Sample3D::new(0.0, [0.0, 0.0, 9.8]),
+            Sample3D::new(0.1, [19.6, 0.0, 9.8]),
+            Sample3D::new(0.2, [0.0, -19.5, 9.8]),
+            Sample3D::new(0.3, [0.0, 0.0, 9.8]),
+        ]
+    }
+
+    #[test]
+    fn test_no_limit() {
+        let monitor = ClippingMonitor::new();
+        let sensor = SensorType::Accelerometer(Uuid::new_v4());
+
+        assert_eq!(monitor.check(&sensor, samples()), samples());
+        assert_eq!(monitor.get_clipped_count(&sensor), 0);
+    }
+
+    #[test]
+    fn test_count_policy() {
+        let monitor = ClippingMonitor::new();
+        let sensor = SensorType::Accelerometer(Uuid::new_v4());
+        monitor.set_range_limit(&sensor, RangeLimit::new(19.6, ClippingPolicy::Count));
+
+        assert_eq!(monitor.check(&sensor, samples()), samples());
+        assert_eq!(monitor.get_clipped_count(&sensor), 2);
+    }
+
+    #[test]
+    fn test_discard_policy() {
+        let monitor = ClippingMonitor::new();
+        let sensor = SensorType::Accelerometer(Uuid::new_v4());
+        monitor.set_range_limit(
+            &sensor,
+            RangeLimit::new(19.6, ClippingPolicy::Discard).with_margin(0.0),
+        );
+
+        let result = monitor.check(&sensor, samples());
+        assert_eq!(result.len(), 3);
+        assert_eq!(monitor.get_clipped_count(&sensor), 1);
+
+        monitor.check(&sensor, samples());
+        assert_eq!(monitor.get_clipped_count(&sensor), 2);
+    }
+}
@@ -0,0 +1,95 @@
+//! Module connection
+//!
+//! Reconnection policy of the phyphox HTTP client, and the connection status events published
+//! while it recovers from fetch errors.
+
+use std::time::Duration;
+
+const DEFAULT_INITIAL_BACKOFF_MILLIS: u64 = 250;
+const DEFAULT_MAX_BACKOFF_MILLIS: u64 = 8000;
+const DEFAULT_MAX_RETRIES: usize = 10;
+
+/// Connection status of a phyphox source.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectionStatus {
+    /// The phone is reachable and data is being fetched.
+    Connected,
+    /// Fetching data failed, and the client is trying to reconnect for the `attempt`-th time.
+    Reconnecting { attempt: usize },
+    /// The client gave up reconnecting, and the acquisition stopped.
+    Lost,
+}
+
+/// Exponential backoff used to reconnect after a fetch error. Acquisition resumes from the last
+/// received sample once reconnected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReconnectPolicy {
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_retries: usize,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(DEFAULT_INITIAL_BACKOFF_MILLIS),
+            max_backoff: Duration::from_millis(DEFAULT_MAX_BACKOFF_MILLIS),
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the wait before the first reconnection attempt. It is doubled after every attempt.
+    pub fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Sets the longest wait between reconnection attempts.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Sets the number of reconnection attempts before giving up. With 0 retries, the first
+    /// fetch error stops the acquisition.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn get_max_retries(&self) -> usize {
+        self.max_retries
+    }
+
+    /// Returns the wait before reconnection attempt `attempt`, starting at 1.
+    pub fn get_backoff(&self, attempt: usize) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1) as u32);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = ReconnectPolicy::new()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(1000));
+
+        assert_eq!(policy.get_backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.get_backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.get_backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.get_backoff(5), Duration::from_millis(1000));
+        assert_eq!(policy.get_backoff(100), Duration::from_millis(1000));
+    }
+}
@@ -0,0 +1,201 @@
+//! Module discovery
+//!
+//! Configuration of the search of phones running phyphox with remote access enabled, and the
+//! devices found. Phyphox doesn't announce itself on the network, so every host of a subnet is
+//! probed on the remote access port instead.
+
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::time::Duration;
+
+use super::errors::PhyphoxError;
+use imu_common::types::sensors::{IdentityStore, SensorClusterBuilder, SensorType};
+
+const DEFAULT_PORT: u16 = 8080;
+const DEFAULT_PREFIX_LEN: u8 = 24;
+const MIN_PREFIX_LEN: u8 = 16;
+const DEFAULT_TIMEOUT_MILLIS: u64 = 500;
+const DEFAULT_MAX_CONCURRENT_PROBES: usize = 64;
+
+/// Phone found running phyphox remote access.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiscoveredDevice {
+    /// Base URL to create a phyphox service with.
+    pub base_url: String,
+    /// Sensor kinds exported by the running experiment, as accepted by `SensorClusterBuilder`.
+    pub available_sensors: Vec<String>,
+    /// Unique id of the phone, as reported by phyphox, which stays the same across sessions.
+    pub device_id: Option<String>,
+}
+
+impl DiscoveredDevice {
+    /// Returns a cluster with the available sensors, with the ids stored for the phone in
+    /// `store`, so it gets the same ids every time it is discovered. Phones seen for the first
+    /// time are stored with `tag`.
+    /// Returns an Other error if the phone doesn't report its id, or the store can't be written.
+    pub fn resolve_sensor_cluster(
+        &self,
+        store: &mut IdentityStore,
+        tag: &str,
+    ) -> Result<Vec<SensorType>, PhyphoxError> {
+        let device_id = self.device_id.as_ref().ok_or_else(|| {
+            PhyphoxError::Other(format!("{} doesn't report a device id", self.base_url))
+        })?;
+        let builder = self
+            .available_sensors
+            .iter()
+            .fold(SensorClusterBuilder::new(), |builder, kind| {
+                builder.other(kind)
+            });
+        store
+            .resolve(device_id, tag, builder)
+            .map_err(|e| PhyphoxError::Other(e.to_string()))
+    }
+}
+
+/// Subnet, port and timeouts used to discover devices.
+#[derive(Clone, Debug)]
+pub struct DiscoveryConfig {
+    subnet: Option<(Ipv4Addr, u8)>,
+    port: u16,
+    timeout: Duration,
+    max_concurrent_probes: usize,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            subnet: None,
+            port: DEFAULT_PORT,
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MILLIS),
+            max_concurrent_probes: DEFAULT_MAX_CONCURRENT_PROBES,
+        }
+    }
+}
+
+impl DiscoveryConfig {
+    /// Scans the /24 subnet of the local address on the phyphox default port (8080).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scans the subnet of `address` with a prefix of `prefix_len` bits instead of the local one.
+    pub fn with_subnet(mut self, address: Ipv4Addr, prefix_len: u8) -> Self {
+        self.subnet = Some((address, prefix_len));
+        self
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Sets how long to wait for a host to answer.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how many hosts are probed at the same time.
+    pub fn with_max_concurrent_probes(mut self, max_concurrent_probes: usize) -> Self {
+        self.max_concurrent_probes = max_concurrent_probes.max(1);
+        self
+    }
+
+    pub fn get_port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn get_max_concurrent_probes(&self) -> usize {
+        self.max_concurrent_probes
+    }
+
+    /// Returns the hosts to probe.
+    /// Returns an Other error if the local address can't be found, or the subnet is larger than
+    /// a /16.
+    pub fn get_hosts(&self) -> Result<Vec<Ipv4Addr>, PhyphoxError> {
+        let (address, prefix_len) = match self.subnet {
+            Some(subnet) => subnet,
+            None => (local_address()?, DEFAULT_PREFIX_LEN),
+        };
+        if !(MIN_PREFIX_LEN..=32).contains(&prefix_len) {
+            return Err(PhyphoxError::Other(format!(
+                "Subnet prefix must be between {} and 32 bits",
+                MIN_PREFIX_LEN
+            )));
+        }
+        let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+        let network = u32::from(address) & mask;
+        let broadcast = network | !mask;
+        let hosts = if prefix_len >= 31 {
+            network..=broadcast
+        } else {
+            // skip the network and broadcast addresses
+            network + 1..=broadcast - 1
+        };
+        Ok(hosts.map(Ipv4Addr::from).collect())
+    }
+}
+
+/// Returns the address of the interface used to reach other hosts. No packet is sent.
+fn local_address() -> Result<Ipv4Addr, PhyphoxError> {
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| PhyphoxError::Other(e.to_string()))?;
+    socket
+        .connect("10.255.255.255:1")
+        .map_err(|e| PhyphoxError::Other(e.to_string()))?;
+    match socket.local_addr().map(|address| address.ip()) {
+        Ok(IpAddr::V4(address)) if !address.is_unspecified() => Ok(address),
+        _ => Err(PhyphoxError::Other(
+            "Local IPv4 address not found".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subnet_hosts() {
+        let config = DiscoveryConfig::new().with_subnet(Ipv4Addr::new(192, 168, 1, 37), 24);
+        let hosts = config.get_hosts().unwrap();
+        assert_eq!(hosts.len(), 254);
+        assert_eq!(hosts[0], Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(hosts[253], Ipv4Addr::new(192, 168, 1, 254));
+
+        let config = DiscoveryConfig::new().with_subnet(Ipv4Addr::new(10, 0, 0, 5), 32);
+        assert_eq!(
+            config.get_hosts().unwrap(),
+            vec![Ipv4Addr::new(10, 0, 0, 5)]
+        );
+
+        let config = DiscoveryConfig::new().with_subnet(Ipv4Addr::new(10, 0, 0, 5), 8);
+        assert!(config.get_hosts().is_err());
+    }
+
+    #[test]
+    fn test_resolve_sensor_cluster() {
+        let mut device = DiscoveredDevice {
+            base_url: "http://192.168.1.37:8080".to_string(),
+            available_sensors: vec!["accelerometer".to_string(), "pressure".to_string()],
+            device_id: Some("a1b2c3".to_string()),
+        };
+        let mut store = IdentityStore::new();
+        let cluster = device.resolve_sensor_cluster(&mut store, "phone").unwrap();
+        assert!(matches!(cluster[0], SensorType::Accelerometer(_)));
+        assert_eq!(cluster.len(), 2);
+
+        // same ids on the next discovery, even from another address
+        device.base_url = "http://192.168.1.40:8080".to_string();
+        assert_eq!(
+            device.resolve_sensor_cluster(&mut store, "phone").unwrap(),
+            cluster
+        );
+
+        device.device_id = None;
+        assert!(device.resolve_sensor_cluster(&mut store, "phone").is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Module duplicates
+//!
+//! Samples are fetched from phyphox with the time of the last sample received, plus a small
+//! epsilon. Since the query rounds that time, consecutive fetch windows occasionally overlap and
+//! return a sample that was already published, which would then be averaged twice.
+//! `DuplicateFilter` tracks the last timestamp delivered per sensor and drops the samples at or
+//! before it.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use imu_common::types::sensors::SensorType;
+
+// well below the sample period of any phone sensor
+const DEFAULT_DUPLICATE_TOLERANCE_SECS: f64 = 1e-4;
+
+/// Per sensor duplicate detection of the acquisition loop.
+#[derive(Debug)]
+pub struct DuplicateFilter {
+    tolerance_secs: f64,
+    last_timestamps: RwLock<HashMap<SensorType, f64>>,
+    counters: RwLock<HashMap<SensorType, usize>>,
+}
+
+impl Default for DuplicateFilter {
+    fn default() -> Self {
+        Self {
+            tolerance_secs: DEFAULT_DUPLICATE_TOLERANCE_SECS,
+            last_timestamps: RwLock::new(HashMap::new()),
+            counters: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl DuplicateFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Samples within `tolerance_secs` of the last delivered one are duplicates.
+    pub fn with_tolerance(mut self, tolerance_secs: f64) -> Self {
+        self.tolerance_secs = tolerance_secs.max(0.0);
+        self
+    }
+
+    pub fn get_tolerance(&self) -> f64 {
+        self.tolerance_secs
+    }
+
+    /// Returns the number of duplicated samples removed for `sensor_type` so far.
+    pub fn get_duplicate_count(&self, sensor_type: &SensorType) -> usize {
+        let counters = self.counters.read().unwrap();
+        counters.get(sensor_type).copied().unwrap_or(0)
+    }
+
+    /// Removes the samples of `sensor_type` that aren't later than the last delivered one, and
+    /// counts them. `timestamps` and `values` are the columns of the fetched samples.
+    pub(crate) fn filter<U>(
+        &self,
+        sensor_type: &SensorType,
+        timestamps: Vec<f64>,
+        values: Vec<U>,
+    ) -> (Vec<f64>, Vec<U>) {
+        let mut last_timestamps = self.last_timestamps.write().unwrap();
+        let mut last = last_timestamps
+            .get(sensor_type)
+            .copied()
+            .unwrap_or(f64::NEG_INFINITY);
+
+        let mut n_duplicates = 0;
+        let (timestamps, values): (Vec<f64>, Vec<U>) = timestamps
+            .into_iter()
+            .zip(values)
+            .filter(|(timestamp, _)| {
+                if *timestamp <= last + self.tolerance_secs {
+                    n_duplicates += 1;
+                    return false;
+                }
+                last = *timestamp;
+                true
+            })
+            .unzip();
+
+        last_timestamps.insert(sensor_type.clone(), last);
+        if n_duplicates > 0 {
+            log::debug!(
+                "Dropped {} duplicated samples of {}",
+                n_duplicates,
+                sensor_type
+            );
+            let mut counters = self.counters.write().unwrap();
+            *counters.entry(sensor_type.clone()).or_insert(0) += n_duplicates;
+        }
+        (timestamps, values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_overlapping_windows() {
+        let filter = DuplicateFilter::new();
+        let sensor = SensorType::Accelerometer(Uuid::new_v4());
+
+        let (timestamps, values) = filter.filter(&sensor, vec![0.0, 0.01, 0.02], vec![1, 2, 3]);
+        assert_eq!((timestamps, values), (vec![0.0, 0.01, 0.02], vec![1, 2, 3]));
+
+        // the next window starts with the last sample, and a near copy of it
+        let (timestamps, values) = filter.filter(&sensor, vec![0.02, 0.02005, 0.03], vec![3, 3, 4]);
+        assert_eq!((timestamps, values), (vec![0.03], vec![4]));
+        assert_eq!(filter.get_duplicate_count(&sensor), 2);
+    }
+
+    #[test]
+    fn test_independent_sensors() {
+        let filter = DuplicateFilter::new().with_tolerance(0.0);
+        let accelerometer = SensorType::Accelerometer(Uuid::new_v4());
+        let gyroscope = SensorType::Gyroscope(Uuid::new_v4());
+
+        filter.filter(&accelerometer, vec![0.0, 0.01], vec![1, 2]);
+        let (timestamps, _) = filter.filter(&gyroscope, vec![0.0, 0.01], vec![1, 2]);
+        assert_eq!(timestamps, vec![0.0, 0.01]);
+
+        let (timestamps, _) = filter.filter(&accelerometer, vec![0.01, 0.01, 0.005], vec![2, 2, 0]);
+        assert!(timestamps.is_empty());
+        assert_eq!(filter.get_duplicate_count(&accelerometer), 3);
+        assert_eq!(filter.get_duplicate_count(&gyroscope), 0);
+    }
+}
@@ -0,0 +1,71 @@
+//! Module duty_cycle
+//!
+//! Duty cycled acquisition for long term, low power monitoring. The experiment runs on the phone
+//! for the active period and is stopped for the rest of the cycle, so the phone can sleep in
+//! between.
+
+use std::time::Duration;
+
+use crate::models::errors::PhyphoxError;
+
+/// Active and paused periods of a duty cycled acquisition, e.g. 10 s on and 50 s off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DutyCycle {
+    on: Duration,
+    off: Duration,
+}
+
+impl DutyCycle {
+    pub fn new(on: Duration, off: Duration) -> Self {
+        Self { on, off }
+    }
+
+    pub fn get_on(&self) -> Duration {
+        self.on
+    }
+
+    pub fn get_off(&self) -> Duration {
+        self.off
+    }
+
+    /// Returns the fraction of the cycle spent acquiring.
+    pub fn get_ratio(&self) -> f64 {
+        self.on.as_secs_f64() / (self.on + self.off).as_secs_f64()
+    }
+
+    /// Returns an InvalidDutyCycle error if the active or the paused period is empty.
+    pub fn validate(&self) -> Result<(), PhyphoxError> {
+        if self.on.is_zero() || self.off.is_zero() {
+            return Err(PhyphoxError::InvalidDutyCycle(format!("{:?}", self)));
+        }
+        Ok(())
+    }
+}
+
+/// Phase of a duty cycled acquisition, published every time it changes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AcquisitionPhase {
+    /// The experiment was started on the phone at `since_secs`.
+    Active { since_secs: f64 },
+    /// The experiment was stopped at `since_secs`, and restarts at `until_secs`. No readings are
+    /// published in between.
+    Paused { since_secs: f64, until_secs: f64 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duty_cycle() {
+        let duty_cycle = DutyCycle::new(Duration::from_secs(10), Duration::from_secs(50));
+        assert!(duty_cycle.validate().is_ok());
+        assert!((duty_cycle.get_ratio() - 1.0 / 6.0).abs() < 1e-12);
+
+        let duty_cycle = DutyCycle::new(Duration::from_secs(10), Duration::ZERO);
+        assert!(matches!(
+            duty_cycle.validate(),
+            Err(PhyphoxError::InvalidDutyCycle(_))
+        ));
+    }
+}
@@ -15,5 +15,11 @@ pub enum PhyphoxError {
     /// Error indicating that the received data format is incorrect.
     IncorrectDataFormat(String),
 
+    /// Error indicating that a sensor is already published by another source.
+    DuplicatedSensor(String),
+
+    /// Error indicating that the active or paused period of a duty cycle is empty.
+    InvalidDutyCycle(String),
+
     Other(String),
 }
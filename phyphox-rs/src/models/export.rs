@@ -0,0 +1,325 @@
+//! Module export
+//!
+//! Phyphox can export the full data of an experiment (`/export`) as a zip archive with a CSV file
+//! per set. `SessionLog` records the samples fetched incrementally during a session so that, once
+//! the session ends, they can be cross-checked against the export, which is the ground truth
+//! kept by the phone.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::sync::Mutex;
+
+use imu_common::types::sensors::SensorType;
+
+use crate::helpers;
+use crate::models::errors::PhyphoxError;
+
+/// Default tolerance when matching the timestamps and values of exported and streamed samples.
+/// CSV exports round values to a few significant digits.
+const DEFAULT_EXPORT_TOLERANCE: f64 = 1e-4;
+
+/// Samples of a sensor, as the time since the start of the experiment and the values of each
+/// component.
+type Rows = Vec<(f64, Vec<f64>)>;
+
+/// Differences between the exported and the streamed samples of a sensor.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SensorDivergence {
+    pub n_exported: usize,
+    pub n_streamed: usize,
+    /// Exported samples that were never streamed.
+    pub n_missing: usize,
+    /// Streamed samples that aren't in the export.
+    pub n_unexpected: usize,
+    /// Largest difference between the values of a sample streamed and exported.
+    pub max_value_error: f64,
+}
+
+impl SensorDivergence {
+    pub fn is_consistent(&self, tolerance: f64) -> bool {
+        self.n_missing == 0 && self.n_unexpected == 0 && self.max_value_error <= tolerance
+    }
+}
+
+/// Result of the cross-check of a session against the phyphox export.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExportReport {
+    pub tolerance: f64,
+    /// Divergence of each sensor of the cluster, in the order of the sensor cluster.
+    pub sensors: Vec<(SensorType, SensorDivergence)>,
+}
+
+impl ExportReport {
+    pub fn get(&self, sensor_type: &SensorType) -> Option<&SensorDivergence> {
+        self.sensors
+            .iter()
+            .find(|(sensor, _)| sensor == sensor_type)
+            .map(|(_, divergence)| divergence)
+    }
+
+    /// Returns true if every sensor streamed exactly the exported samples.
+    pub fn is_consistent(&self) -> bool {
+        self.sensors
+            .iter()
+            .all(|(_, divergence)| divergence.is_consistent(self.tolerance))
+    }
+}
+
+/// Samples streamed during a session, shared between the service and the acquisition loop.
+#[derive(Debug)]
+pub struct SessionLog {
+    tolerance: f64,
+    rows: Mutex<HashMap<SensorType, Rows>>,
+    report: Mutex<Option<ExportReport>>,
+}
+
+impl Default for SessionLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionLog {
+    pub fn new() -> Self {
+        Self {
+            tolerance: DEFAULT_EXPORT_TOLERANCE,
+            rows: Mutex::new(HashMap::new()),
+            report: Mutex::new(None),
+        }
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance.abs();
+        self
+    }
+
+    /// Clears the samples and the report of the previous session.
+    pub(crate) fn clear(&self) {
+        self.rows.lock().unwrap().clear();
+        *self.report.lock().unwrap() = None;
+    }
+
+    /// Records the samples of `sensor_type` fetched, with `timestamps` relative to the start
+    /// of the experiment.
+    pub(crate) fn record(&self, sensor_type: &SensorType, timestamps: &[f64], values: &[Vec<f64>]) {
+        let mut rows = self.rows.lock().unwrap();
+        rows.entry(sensor_type.clone())
+            .or_default()
+            .extend(timestamps.iter().copied().zip(values.iter().cloned()));
+    }
+
+    /// Compares the samples recorded with the export of the session, and keeps the report.
+    pub(crate) fn cross_check(
+        &self,
+        export: &[u8],
+        sensor_cluster: &[SensorType],
+    ) -> Result<ExportReport, PhyphoxError> {
+        let mut exported = parse_export(export, sensor_cluster)?;
+        let rows = self.rows.lock().unwrap();
+        let report = ExportReport {
+            tolerance: self.tolerance,
+            sensors: sensor_cluster
+                .iter()
+                .map(|sensor| {
+                    let divergence = compare(
+                        exported.remove(sensor).unwrap_or_default(),
+                        rows.get(sensor).cloned().unwrap_or_default(),
+                        self.tolerance,
+                    );
+                    (sensor.clone(), divergence)
+                })
+                .collect(),
+        };
+        *self.report.lock().unwrap() = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Returns the report of the last session, if it was cross-checked.
+    pub fn get_report(&self) -> Option<ExportReport> {
+        self.report.lock().unwrap().clone()
+    }
+}
+
+/// Returns the samples of every sensor of `sensor_cluster` in the zip archive `export`. Files
+/// of sets not in the cluster, like the metadata of the experiment, are ignored.
+pub(crate) fn parse_export(
+    export: &[u8],
+    sensor_cluster: &[SensorType],
+) -> Result<HashMap<SensorType, Rows>, PhyphoxError> {
+    let format_error = |e: zip::result::ZipError| PhyphoxError::IncorrectDataFormat(e.to_string());
+    let mut archive = zip::ZipArchive::new(Cursor::new(export)).map_err(format_error)?;
+    let mut exported = HashMap::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(format_error)?;
+        let Some(set) = file.name().strip_suffix(".csv") else {
+            continue;
+        };
+        if set.contains('/') {
+            continue;
+        }
+        let Some(sensor) = helpers::to_sensor_type(set, sensor_cluster) else {
+            continue;
+        };
+        let mut csv = String::new();
+        file.read_to_string(&mut csv)
+            .map_err(|e| PhyphoxError::IncorrectDataFormat(e.to_string()))?;
+        exported.insert(sensor, parse_csv(&csv)?);
+    }
+    Ok(exported)
+}
+
+/// Parses a CSV export with comma separators and decimal points. The first row is the header,
+/// and the first column the time.
+fn parse_csv(csv: &str) -> Result<Rows, PhyphoxError> {
+    csv.lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let columns = line
+                .split(',')
+                .map(|column| column.trim().trim_matches('"').parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|e| PhyphoxError::IncorrectDataFormat(format!("{}: {}", line, e)))?;
+            match columns.split_first() {
+                Some((time, values)) => Ok((*time, values.to_vec())),
+                None => Err(PhyphoxError::IncorrectDataFormat(line.to_string())),
+            }
+        })
+        .collect()
+}
+
+/// Matches the exported and streamed samples by timestamp.
+fn compare(mut exported: Rows, mut streamed: Rows, tolerance: f64) -> SensorDivergence {
+    exported.sort_by(|a, b| a.0.total_cmp(&b.0));
+    streamed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut divergence = SensorDivergence {
+        n_exported: exported.len(),
+        n_streamed: streamed.len(),
+        ..Default::default()
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < exported.len() && j < streamed.len() {
+        let (exported_time, exported_values) = &exported[i];
+        let (streamed_time, streamed_values) = &streamed[j];
+        if (exported_time - streamed_time).abs() <= tolerance {
+            let error = exported_values
+                .iter()
+                .zip(streamed_values)
+                .map(|(e, s)| (e - s).abs())
+                .fold(0.0, f64::max);
+            divergence.max_value_error = divergence.max_value_error.max(error);
+            if exported_values.len() != streamed_values.len() {
+                divergence.max_value_error = f64::INFINITY;
+            }
+            i += 1;
+            j += 1;
+        } else if exported_time < streamed_time {
+            divergence.n_missing += 1;
+            i += 1;
+        } else {
+            divergence.n_unexpected += 1;
+            j += 1;
+        }
+    }
+    divergence.n_missing += exported.len() - i;
+    divergence.n_unexpected += streamed.len() - j;
+    divergence
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use imu_common::types::sensors::SensorClusterBuilder;
+    use std::io::Write;
+
+    /// Returns a zip archive with a CSV file for each `(name, content)`.
+    pub(crate) fn zip_export(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer
+                .start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    const ACC_CSV: &str = "\"Time (s)\",\"Acceleration x (m/s^2)\",\"Acceleration y (m/s^2)\",\"Acceleration z (m/s^2)\"\n\
+        1.000000000E0,1.000000000E0,3.000000000E0,5.000000000E0\n\
+        2.000000000E0,2.000000000E0,4.000000000E0,6.000000000E0\n\
+        3.000000000E0,2.000000000E0,4.000000000E0,6.000000000E0\n";
+
+    #[test]
+    fn test_parse_export() {
+        let sensor_cluster = SensorClusterBuilder::new()
+            .accelerometer()
+            .gyroscope()
+            .build()
+            .unwrap();
+        let export = zip_export(&[
+            ("Accelerometer.csv", ACC_CSV),
+            ("meta/device.csv", "\"property\",\"value\"\n"),
+        ]);
+
+        let exported = parse_export(&export, &sensor_cluster).unwrap();
+        assert_eq!(exported.len(), 1);
+        let rows = &exported[&sensor_cluster[0]];
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1], (2.0, vec![2.0, 4.0, 6.0]));
+
+        assert!(matches!(
+            parse_export(b"not a zip", &sensor_cluster),
+            Err(PhyphoxError::IncorrectDataFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_cross_check() {
+        let sensor_cluster = SensorClusterBuilder::new()
+            .accelerometer()
+            .gyroscope()
+            .build()
+            .unwrap();
+        let (accelerometer, gyroscope) = (&sensor_cluster[0], &sensor_cluster[1]);
+        let export = zip_export(&[("Accelerometer.csv", ACC_CSV)]);
+        let session = SessionLog::new();
+
+        // the sample at 3 s was never fetched, and the one at 2 s was fetched with other values
+        session.record(
+            accelerometer,
+            &[1.0, 2.0],
+            &[vec![1.0, 3.0, 5.0], vec![2.0, 4.0, 6.5]],
+        );
+        session.record(gyroscope, &[1.5], &[vec![0.0, 0.0, 0.0]]);
+        let report = session.cross_check(&export, &sensor_cluster).unwrap();
+
+        assert!(!report.is_consistent());
+        assert_eq!(
+            report.get(accelerometer),
+            Some(&SensorDivergence {
+                n_exported: 3,
+                n_streamed: 2,
+                n_missing: 1,
+                n_unexpected: 0,
+                max_value_error: 0.5,
+            })
+        );
+        assert_eq!(report.get(gyroscope).unwrap().n_unexpected, 1);
+        assert_eq!(session.get_report(), Some(report));
+
+        session.clear();
+        session.record(
+            accelerometer,
+            &[1.0, 2.0, 3.0],
+            &[
+                vec![1.0, 3.0, 5.0],
+                vec![2.0, 4.0, 6.0],
+                vec![2.0, 4.0, 6.0],
+            ],
+        );
+        let report = session
+            .cross_check(&export, std::slice::from_ref(accelerometer))
+            .unwrap();
+        assert!(report.is_consistent());
+    }
+}
@@ -13,8 +13,12 @@ pub(crate) struct HttpClient {
 
 impl HttpClient {
     pub(crate) fn new(base_url: String) -> Result<Self, PhyphoxError> {
+        Self::with_timeout(base_url, Duration::from_secs(CLIENT_TIMEOUT_DEFAULT))
+    }
+
+    pub(crate) fn with_timeout(base_url: String, timeout: Duration) -> Result<Self, PhyphoxError> {
         let client = ReqwestClient::builder()
-            .timeout(Duration::from_secs(CLIENT_TIMEOUT_DEFAULT))
+            .timeout(timeout)
             .build()
             .map_err(|e| PhyphoxError::ClientBuild(e.to_string()))?;
 
@@ -43,4 +47,21 @@ impl HttpClient {
             .map_err(|e| PhyphoxError::FetchData(e.to_string()))?;
         Ok(json)
     }
+
+    /// Returns the raw body of the response to `path`, e.g. an exported file.
+    pub(crate) async fn fetch_bytes(&self, path: &str) -> Result<Vec<u8>, PhyphoxError> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| PhyphoxError::FetchData(e.to_string()))?;
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| PhyphoxError::FetchData(e.to_string()))?;
+        Ok(bytes.to_vec())
+    }
 }
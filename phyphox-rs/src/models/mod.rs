@@ -1,4 +1,11 @@
+pub mod clipping;
+pub mod connection;
+pub mod discovery;
+pub mod duplicates;
+pub mod duty_cycle;
 pub mod errors;
+pub mod export;
 //pub mod filter;
 pub(crate) mod http_client;
+pub(crate) mod polling;
 pub(crate) mod shutdown;
@@ -0,0 +1,204 @@
+//! Module polling
+//!
+//! Every sensor of a phyphox source is polled by its own task, driven by a `SensorPoller` state
+//! machine, so a sensor failing to fetch or recovering from an error doesn't hold the others
+//! back. `PollCoordinator` gathers the state of every task into the connection status of the
+//! source.
+
+use std::sync::Mutex;
+
+use crate::models::connection::ConnectionStatus;
+
+/// State of the polling task of a sensor.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum PollState {
+    /// Waiting for the next poll.
+    Idle,
+    /// Fetching the samples received since the last poll.
+    Fetching,
+    /// The phone stopped answering, and the task waits before reconnecting for the
+    /// `attempt`-th time.
+    Backoff { attempt: usize },
+    /// The task gave up reconnecting.
+    Stopped,
+}
+
+/// Events of a polling task.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PollEvent {
+    /// Time to poll the sensor.
+    Tick,
+    /// The phone answered the fetch, with samples or not.
+    Fetched,
+    /// The phone didn't answer the fetch.
+    FetchFailed,
+    Reconnected,
+    ReconnectFailed,
+}
+
+/// State machine of the polling task of a sensor.
+#[derive(Debug)]
+pub(crate) struct SensorPoller {
+    state: PollState,
+    max_retries: usize,
+}
+
+impl SensorPoller {
+    /// Creates an idle poller giving up after `max_retries` failed reconnection attempts.
+    pub(crate) fn new(max_retries: usize) -> Self {
+        Self {
+            state: PollState::Idle,
+            max_retries,
+        }
+    }
+
+    pub(crate) fn get_state(&self) -> &PollState {
+        &self.state
+    }
+
+    /// Moves to the state following `event`. Events not expected in the current state are
+    /// ignored.
+    pub(crate) fn handle(&mut self, event: PollEvent) -> &PollState {
+        self.state = match (&self.state, event) {
+            (PollState::Idle, PollEvent::Tick) => PollState::Fetching,
+            (PollState::Fetching, PollEvent::Fetched) => PollState::Idle,
+            (PollState::Fetching, PollEvent::FetchFailed) => self.backoff(1),
+            (PollState::Backoff { .. }, PollEvent::Reconnected) => PollState::Idle,
+            (PollState::Backoff { attempt }, PollEvent::ReconnectFailed) => {
+                self.backoff(attempt + 1)
+            }
+            (state, _) => state.clone(),
+        };
+        &self.state
+    }
+
+    fn backoff(&self, attempt: usize) -> PollState {
+        if attempt > self.max_retries {
+            PollState::Stopped
+        } else {
+            PollState::Backoff { attempt }
+        }
+    }
+}
+
+/// States of the polling tasks of a source, and the connection status they add up to.
+#[derive(Debug)]
+pub(crate) struct PollCoordinator {
+    states: Mutex<Vec<PollState>>,
+    status: Mutex<ConnectionStatus>,
+}
+
+impl PollCoordinator {
+    /// Creates a coordinator of `n_sensors` idle tasks, connected.
+    pub(crate) fn new(n_sensors: usize) -> Self {
+        Self {
+            states: Mutex::new(vec![PollState::Idle; n_sensors]),
+            status: Mutex::new(ConnectionStatus::Connected),
+        }
+    }
+
+    /// Sets the state of the task of sensor `index`, and returns the connection status of the
+    /// source if it changed.
+    ///
+    /// The source is connected while any sensor is polled, reconnecting while every sensor
+    /// still polled is backing off, and lost once all of them gave up.
+    pub(crate) fn update(&self, index: usize, state: PollState) -> Option<ConnectionStatus> {
+        let mut states = self.states.lock().unwrap();
+        states[index] = state;
+
+        let status = if states
+            .iter()
+            .any(|state| matches!(state, PollState::Idle | PollState::Fetching))
+        {
+            ConnectionStatus::Connected
+        } else if let Some(attempt) = states
+            .iter()
+            .filter_map(|state| match state {
+                PollState::Backoff { attempt } => Some(*attempt),
+                _ => None,
+            })
+            .min()
+        {
+            ConnectionStatus::Reconnecting { attempt }
+        } else {
+            ConnectionStatus::Lost
+        };
+
+        let mut current = self.status.lock().unwrap();
+        if *current == status {
+            return None;
+        }
+        *current = status.clone();
+        Some(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poller_transitions() {
+        let mut poller = SensorPoller::new(2);
+
+        assert_eq!(poller.handle(PollEvent::Fetched), &PollState::Idle);
+        assert_eq!(poller.handle(PollEvent::Tick), &PollState::Fetching);
+        assert_eq!(poller.handle(PollEvent::Fetched), &PollState::Idle);
+        poller.handle(PollEvent::Tick);
+        assert_eq!(
+            poller.handle(PollEvent::FetchFailed),
+            &PollState::Backoff { attempt: 1 }
+        );
+        assert_eq!(poller.handle(PollEvent::Reconnected), &PollState::Idle);
+
+        poller.handle(PollEvent::Tick);
+        poller.handle(PollEvent::FetchFailed);
+        assert_eq!(
+            poller.handle(PollEvent::ReconnectFailed),
+            &PollState::Backoff { attempt: 2 }
+        );
+        assert_eq!(
+            poller.handle(PollEvent::ReconnectFailed),
+            &PollState::Stopped
+        );
+        assert_eq!(poller.handle(PollEvent::Reconnected), &PollState::Stopped);
+    }
+
+    #[test]
+    fn test_no_retries() {
+        let mut poller = SensorPoller::new(0);
+        poller.handle(PollEvent::Tick);
+        assert_eq!(poller.handle(PollEvent::FetchFailed), &PollState::Stopped);
+    }
+
+    #[test]
+    fn test_coordinated_status() {
+        let coordinator = PollCoordinator::new(2);
+
+        // one sensor failing doesn't disconnect the source
+        assert_eq!(
+            coordinator.update(0, PollState::Backoff { attempt: 1 }),
+            None
+        );
+        assert_eq!(
+            coordinator.update(1, PollState::Backoff { attempt: 2 }),
+            Some(ConnectionStatus::Reconnecting { attempt: 1 })
+        );
+        assert_eq!(
+            coordinator.update(0, PollState::Stopped),
+            Some(ConnectionStatus::Reconnecting { attempt: 2 })
+        );
+        assert_eq!(
+            coordinator.update(1, PollState::Idle),
+            Some(ConnectionStatus::Connected)
+        );
+        assert_eq!(
+            coordinator.update(1, PollState::Stopped),
+            Some(ConnectionStatus::Lost)
+        );
+        assert_eq!(
+            *coordinator.states.lock().unwrap(),
+            vec![PollState::Stopped; 2]
+        );
+    }
+}
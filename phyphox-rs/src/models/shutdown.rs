@@ -1,4 +1,5 @@
 use log::{error, info};
+use publisher::ShutdownToken;
 use std::sync::Arc;
 use tokio::signal;
 use tokio::sync::Notify;
@@ -8,11 +9,12 @@ const MAX_RETRIES: u64 = 10;
 
 pub(crate) struct ShutdownSignal {
     notify: Arc<Notify>,
+    token: ShutdownToken,
 }
 
 impl ShutdownSignal {
-    fn new(notify: Arc<Notify>) -> Self {
-        Self { notify }
+    fn new(notify: Arc<Notify>, token: ShutdownToken) -> Self {
+        Self { notify, token }
     }
 
     async fn listen_for_shutdown(&self, run_for_millis: Option<u64>) {
@@ -26,6 +28,8 @@ impl ShutdownSignal {
                     continue;
                 }
                 info!("Ctrl+C received. Sending stop signal...");
+                // stops the rest of the pipeline too
+                self.token.shutdown();
             }
             self.notify.notify_waiters();
             tokio::time::sleep(std::time::Duration::from_millis(SHUTDOWN_PERIOD_MS)).await;
@@ -41,9 +45,10 @@ impl ShutdownSignal {
 pub(crate) fn listen_for_shutdown(
     notify: Arc<Notify>,
     run_for_millis: Option<u64>,
+    token: ShutdownToken,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
-        let shutdown_signal = ShutdownSignal::new(notify);
+        let shutdown_signal = ShutdownSignal::new(notify, token);
         shutdown_signal.listen_for_shutdown(run_for_millis).await;
     })
 }
@@ -56,7 +61,7 @@ mod tests {
     #[tokio::test]
     async fn test_shutdown_signal_with_timeout() {
         let notify = Arc::new(Notify::new());
-        let shutdown_signal = ShutdownSignal::new(notify.clone());
+        let shutdown_signal = ShutdownSignal::new(notify.clone(), ShutdownToken::new());
 
         let handle = tokio::spawn(async move {
             shutdown_signal.listen_for_shutdown(Some(200)).await;
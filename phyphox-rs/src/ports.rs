@@ -4,23 +4,120 @@ use tokio::sync::Notify;
 
 use async_trait::async_trait;
 
-use imu_common::types::timed::Sample3D;
+use imu_common::types::filters::FilterChainBuilder;
+use imu_common::types::timed::{Sample3D, SampleScalar};
 use imu_common::types::{SensorReadings, SensorType};
-use publisher::Publisher;
+use publisher::events::{EventBus, EventKind};
+use publisher::{Publishable, Publisher, PublisherManager};
 
+use crate::models::clipping::ClippingMonitor;
+use crate::models::connection::{ConnectionStatus, ReconnectPolicy};
 use crate::models::errors::PhyphoxError;
+use crate::models::export::SessionLog;
+
+/// Publishers of the readings fetched by a port, for the 3D and the scalar sensors, of its
+/// connection status and of its lifecycle events.
+#[derive(Clone)]
+pub struct PortPublishers {
+    pub vectors: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+    pub scalars: PublisherManager<SensorReadings<SampleScalar>, SensorType>,
+    pub status: Publisher<ConnectionStatus>,
+    pub events: EventBus,
+}
+
+impl PortPublishers {
+    pub fn notify_status(&self, status: ConnectionStatus) {
+        self.status.notify_listeners(Arc::new(status));
+    }
+
+    pub fn emit(&self, event: EventKind) {
+        self.events.emit(event);
+    }
+}
+
+/// Filter chains applied to the readings of the 3D and the scalar sensors before publishing them.
+/// Every sensor gets its own chain when the acquisition starts. Readings are published
+/// unfiltered if there is no chain.
+#[derive(Clone, Default)]
+pub struct PortFilters {
+    pub vectors: Option<FilterChainBuilder<Sample3D>>,
+    pub scalars: Option<FilterChainBuilder<SampleScalar>>,
+}
+
+/// Options of an acquisition started with `PhyphoxPort::start`. Only the fetching period is
+/// required: by default, the acquisition runs until a SIGINT signal, publishes nothing, and
+/// follows the default reconnect policy.
+#[derive(Clone)]
+pub struct PhyphoxStartOptions {
+    pub period_millis: Duration,
+    pub abort_signal: Option<Arc<Notify>>,
+    pub publishers: Option<PortPublishers>,
+    pub filters: PortFilters,
+    pub clipping: Option<Arc<ClippingMonitor>>,
+    pub reconnect: ReconnectPolicy,
+    pub session: Option<Arc<SessionLog>>,
+}
+
+impl PhyphoxStartOptions {
+    pub fn new(period_millis: Duration) -> Self {
+        Self {
+            period_millis,
+            abort_signal: None,
+            publishers: None,
+            filters: PortFilters::default(),
+            clipping: None,
+            reconnect: ReconnectPolicy::default(),
+            session: None,
+        }
+    }
+
+    pub fn with_abort_signal(mut self, abort_signal: Arc<Notify>) -> Self {
+        self.abort_signal = Some(abort_signal);
+        self
+    }
+
+    pub fn with_publishers(mut self, publishers: PortPublishers) -> Self {
+        self.publishers = Some(publishers);
+        self
+    }
+
+    pub fn with_filters(mut self, filters: PortFilters) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    pub fn with_clipping(mut self, clipping: Arc<ClippingMonitor>) -> Self {
+        self.clipping = Some(clipping);
+        self
+    }
+
+    pub fn with_reconnect(mut self, reconnect: ReconnectPolicy) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    pub fn with_session(mut self, session: Arc<SessionLog>) -> Self {
+        self.session = Some(session);
+        self
+    }
+}
 
 #[async_trait]
 pub trait PhyphoxPort {
     /// Starts the data acquisition process. The process is stopped with a SIGINT signal
-    /// Returns FetchData error if it can't connect to REST API.
-    async fn start(
-        &self,
-        period_millis: Duration,
-        abort_signal: Option<Arc<Notify>>,
-        publisher: Option<Vec<Publisher<SensorReadings<Sample3D>>>>,
-    ) -> Result<(), PhyphoxError>;
+    /// Returns FetchData error if it can't connect to REST API, or the connection is lost and
+    /// can't be recovered following `reconnect`. Every sensor is polled independently, and
+    /// recovers on its own from fetch errors, so the acquisition only fails once every sensor
+    /// gave up.
+    /// If a session is given, the samples fetched are recorded and cross-checked against the
+    /// export of the experiment once the acquisition stops.
+    async fn start(&self, options: PhyphoxStartOptions) -> Result<(), PhyphoxError>;
 
     fn get_tag(&self) -> &str;
     fn get_sensor_cluster(&self) -> Vec<SensorType>;
+
+    /// Returns the number of duplicated samples of `sensor_type` dropped so far.
+    fn get_duplicate_count(&self, _sensor_type: &SensorType) -> usize {
+        0
+    }
 }
@@ -1,18 +1,31 @@
+use futures::stream::{self, StreamExt};
 use log::error;
-use publisher::PublisherManager;
+use publisher::events::EventBus;
+use publisher::soak::{self, SoakConfig, SoakMonitor};
+use publisher::{lifetime, Publishable, Publisher, PublisherManager, ShutdownToken};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Notify;
 use uuid::Uuid;
 
-use crate::adapters::{mock::PhyphoxMock, production::Phyphox};
+use crate::adapters::{mock::PhyphoxMock, production, production::Phyphox};
 /// Generic Phyphox service
+use crate::models::clipping::{ClippingMonitor, RangeLimit};
+use crate::models::connection::{ConnectionStatus, ReconnectPolicy};
+use crate::models::discovery::{DiscoveredDevice, DiscoveryConfig};
+use crate::models::duty_cycle::{AcquisitionPhase, DutyCycle};
 use crate::models::errors::PhyphoxError;
+use crate::models::export::{ExportReport, SessionLog};
 use crate::models::shutdown;
-use crate::ports::PhyphoxPort;
+use crate::ports::{PhyphoxPort, PhyphoxStartOptions, PortFilters, PortPublishers};
+use imu_common::errors::ImuError;
 use imu_common::traits::{IMUSource, Notifiable};
-use imu_common::types::sensors::{SensorReadings, SensorType};
-use imu_common::types::timed::Sample3D;
+use imu_common::types::filters::FilterChainBuilder;
+use imu_common::types::registry::{SourceParams, SourceRegistry};
+use imu_common::types::sensors::{IdentityStore, SensorClusterBuilder, SensorReadings, SensorType};
+use imu_common::types::timed::{Sample3D, SampleScalar};
+use imu_common::types::Clock;
 
 /// Configuration of Phyphox service
 pub struct PhyphoxService<C>
@@ -21,7 +34,17 @@ where
 {
     client: C,
     publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+    scalar_source: PhyphoxScalarSource,
+    status: Publisher<ConnectionStatus>,
+    phases: Publisher<AcquisitionPhase>,
+    filters: PortFilters,
+    reconnect: ReconnectPolicy,
     abort_signal: Arc<Notify>,
+    stopped: Arc<AtomicBool>,
+    clipping: Arc<ClippingMonitor>,
+    session: Option<Arc<SessionLog>>,
+    shutdown: ShutdownToken,
+    events: EventBus,
 }
 
 impl<C> PhyphoxService<C>
@@ -31,19 +54,145 @@ where
     /// Creates a new `Phyphox` instance with the specified configuration.
     /// Returns an ClientBuild error if Http client to connect to Phyphox API cannot be created
     pub fn new(client: C) -> Self {
-        let abort_signal = Arc::new(Notify::new());
-        let sensor_cluster = client.get_sensor_cluster();
+        let (vector_sensors, scalar_sensors) = split_sensor_cluster(client.get_sensor_cluster());
+        let publishers = PublisherManager::new(&vector_sensors);
+        let scalar_publishers = PublisherManager::new(&scalar_sensors);
+        Self::with_publishers(client, publishers, scalar_publishers)
+    }
 
-        let publishers = PublisherManager::new(&sensor_cluster);
+    /// Creates a new `Phyphox` instance that claims its sensor cluster.
+    /// Returns a DuplicatedSensor error if a sensor is repeated in the cluster, or is already
+    /// published by another source.
+    pub fn try_new(client: C) -> Result<Self, PhyphoxError> {
+        let (vector_sensors, scalar_sensors) = split_sensor_cluster(client.get_sensor_cluster());
+        let duplicated =
+            |e| PhyphoxError::DuplicatedSensor(format!("{} in {}", e, client.get_tag()));
+        let publishers = PublisherManager::try_new(&vector_sensors).map_err(duplicated)?;
+        let scalar_publishers = PublisherManager::try_new(&scalar_sensors).map_err(duplicated)?;
+        Ok(Self::with_publishers(client, publishers, scalar_publishers))
+    }
 
+    fn with_publishers(
+        client: C,
+        publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+        scalar_publishers: PublisherManager<SensorReadings<SampleScalar>, SensorType>,
+    ) -> Self {
         PhyphoxService {
-            client,
-            abort_signal,
+            abort_signal: Arc::new(Notify::new()),
+            stopped: Arc::new(AtomicBool::new(false)),
+            scalar_source: PhyphoxScalarSource {
+                tag: client.get_tag().to_string(),
+                sensors: split_sensor_cluster(client.get_sensor_cluster()).1,
+                publishers: scalar_publishers,
+            },
             publishers,
+            status: Publisher::new(),
+            phases: Publisher::new(),
+            filters: PortFilters::default(),
+            reconnect: ReconnectPolicy::default(),
+            clipping: Arc::new(ClippingMonitor::new()),
+            session: None,
+            shutdown: ShutdownToken::global(),
+            events: EventBus::global(),
+            client,
         }
     }
 
-    /// Starts the data acquisition process. The process is stopped with a SIGINT signal
+    /// Stops the service when `token` is shut down, instead of the global token.
+    pub fn with_shutdown_token(mut self, token: ShutdownToken) -> Self {
+        self.shutdown = token;
+        self
+    }
+
+    /// Emits the connection, recording and dropped sample events of the service to `events`,
+    /// instead of the global bus.
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Reconnects following `policy` when the phone stops answering, instead of the default
+    /// policy.
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Filters the readings of every 3D sensor with a chain built by `filter_chain` before
+    /// publishing them. Readings are published unfiltered by default.
+    pub fn with_filter_chain(mut self, filter_chain: FilterChainBuilder<Sample3D>) -> Self {
+        self.filters.vectors = Some(filter_chain);
+        self
+    }
+
+    /// Filters the readings of every scalar sensor with a chain built by `filter_chain` before
+    /// publishing them. Readings are published unfiltered by default.
+    pub fn with_scalar_filter_chain(
+        mut self,
+        filter_chain: FilterChainBuilder<SampleScalar>,
+    ) -> Self {
+        self.filters.scalars = Some(filter_chain);
+        self
+    }
+
+    /// Records the samples fetched during a session and, once it stops, cross-checks them against
+    /// the export of the experiment downloaded from the phone. Divergences are logged, and the
+    /// report returned by `get_export_report`.
+    pub fn with_export_check(mut self, session: SessionLog) -> Self {
+        self.session = Some(Arc::new(session));
+        self
+    }
+
+    /// Returns the cross-check of the last session against the export, if it was enabled and
+    /// the export could be downloaded.
+    pub fn get_export_report(&self) -> Option<ExportReport> {
+        self.session
+            .as_ref()
+            .and_then(|session| session.get_report())
+    }
+
+    /// Registers a listener notified of every change of the connection status.
+    pub fn register_status_listener(
+        &self,
+        listener: &mut dyn Notifiable<ConnectionStatus>,
+    ) -> Uuid {
+        self.status.register_listener(listener)
+    }
+
+    pub fn unregister_status_listener(&self, id: Uuid) {
+        self.status.unregister_listener(id);
+    }
+
+    /// Registers a listener notified every time a duty cycled acquisition is paused or resumed,
+    /// e.g. to pause a resampler or mark the gap in a recording.
+    pub fn register_phase_listener(&self, listener: &mut dyn Notifiable<AcquisitionPhase>) -> Uuid {
+        self.phases.register_listener(listener)
+    }
+
+    pub fn unregister_phase_listener(&self, id: Uuid) {
+        self.phases.unregister_listener(id);
+    }
+
+    /// Sets the full scale range of `sensor_type`. Samples at/near the range are counted as clipped,
+    /// and discarded depending on the limit policy.
+    pub fn set_range_limit(&self, sensor_type: &SensorType, limit: RangeLimit) {
+        self.clipping.set_range_limit(sensor_type, limit);
+    }
+
+    /// Returns the number of clipped samples received from `sensor_type`.
+    pub fn get_clipped_count(&self, sensor_type: &SensorType) -> usize {
+        self.clipping.get_clipped_count(sensor_type)
+    }
+
+    /// Returns the number of samples received again from `sensor_type` by overlapping fetches,
+    /// and dropped.
+    pub fn get_duplicate_count(&self, sensor_type: &SensorType) -> usize {
+        self.client.get_duplicate_count(sensor_type)
+    }
+
+    /// Starts the data acquisition process. The process runs for `run_for_millis`, or until a
+    /// SIGINT signal if `None`, and stops early if the shutdown token is shut down. SIGINT shuts
+    /// the shutdown token down, stopping the rest of the pipeline as well.
     /// Returns FetchData error if it can't connect to REST API.
     pub async fn start(
         &self,
@@ -51,15 +200,104 @@ where
         run_for_millis: Option<u64>,
     ) -> Result<(), PhyphoxError> {
         let abort_signal = self.abort_signal.clone();
-        shutdown::listen_for_shutdown(Arc::clone(&abort_signal), run_for_millis);
-        let publishers = self.publishers.get_publishers_sorted_by_index();
-        self.client
-            .start(
-                period_millis,
-                Some(self.abort_signal.clone()),
-                Some(publishers),
-            )
-            .await
+        let timer = shutdown::listen_for_shutdown(
+            Arc::clone(&abort_signal),
+            run_for_millis,
+            self.shutdown.clone(),
+        );
+        let watcher = tokio::spawn({
+            let shutdown = self.shutdown.clone();
+            async move {
+                shutdown.wait().await;
+                abort_signal.notify_one();
+            }
+        });
+        let publishers = PortPublishers {
+            vectors: self.publishers.clone(),
+            scalars: self.scalar_source.publishers.clone(),
+            status: self.status.clone(),
+            events: self.events.clone(),
+        };
+        let mut options = PhyphoxStartOptions::new(period_millis)
+            .with_abort_signal(self.abort_signal.clone())
+            .with_publishers(publishers)
+            .with_filters(self.filters.clone())
+            .with_clipping(self.clipping.clone())
+            .with_reconnect(self.reconnect.clone());
+        if let Some(session) = self.session.clone() {
+            options = options.with_session(session);
+        }
+        let result = self.client.start(options).await;
+        timer.abort();
+        watcher.abort();
+        result
+    }
+
+    /// Starts a duty cycled acquisition, fetching data every `period_millis` while active. The
+    /// experiment is started on the phone for the active period of `duty_cycle`, and stopped for
+    /// the paused period, until `stop` is called, the shutdown token is shut down or a SIGINT
+    /// signal. Phase changes are published to the phase listeners.
+    /// Returns InvalidDutyCycle error if a period of `duty_cycle` is empty, and the errors of
+    /// `start` otherwise.
+    pub async fn start_duty_cycled(
+        &self,
+        period_millis: Duration,
+        duty_cycle: DutyCycle,
+    ) -> Result<(), PhyphoxError> {
+        duty_cycle.validate()?;
+        // active periods are timed, so SIGINT is listened for over the whole acquisition
+        let signal =
+            shutdown::listen_for_shutdown(self.abort_signal.clone(), None, self.shutdown.clone());
+        let result = self.duty_cycle_loop(period_millis, duty_cycle).await;
+        signal.abort();
+        result
+    }
+
+    async fn duty_cycle_loop(
+        &self,
+        period_millis: Duration,
+        duty_cycle: DutyCycle,
+    ) -> Result<(), PhyphoxError> {
+        loop {
+            self.notify_phase(AcquisitionPhase::Active {
+                since_secs: Clock::now().as_secs(),
+            });
+            // starts the experiment on the phone, and stops it once the active period is over
+            self.start(period_millis, Some(duty_cycle.get_on().as_millis() as u64))
+                .await?;
+            if self.stopped.load(Ordering::Relaxed) || self.shutdown.is_shutdown() {
+                return Ok(());
+            }
+
+            let since_secs = Clock::now().as_secs();
+            self.notify_phase(AcquisitionPhase::Paused {
+                since_secs,
+                until_secs: since_secs + duty_cycle.get_off().as_secs_f64(),
+            });
+            tokio::select! {
+                _ = tokio::time::sleep(duty_cycle.get_off()) => {}
+                _ = self.abort_signal.notified() => return Ok(()),
+                _ = self.shutdown.wait() => return Ok(()),
+            }
+        }
+    }
+
+    fn notify_phase(&self, phase: AcquisitionPhase) {
+        log::info!("Acquisition {:?}", phase);
+        self.phases.notify_listeners(Arc::new(phase));
+    }
+
+    /// Stops the data acquisition, for instance once an `AutoStop` condition is met. If it
+    /// isn't running yet, it stops as soon as it starts. Duty cycled acquisitions aren't
+    /// resumed afterwards.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.abort_signal.notify_one();
+    }
+
+    /// Returns the source of the readings of the scalar sensors of the cluster.
+    pub fn get_scalar_source(&self) -> PhyphoxScalarSource {
+        self.scalar_source.clone()
     }
 }
 
@@ -68,7 +306,7 @@ where
     C: PhyphoxPort + Send + Sync,
 {
     fn get_available_sensors(&self) -> Vec<SensorType> {
-        self.client.get_sensor_cluster()
+        split_sensor_cluster(self.client.get_sensor_cluster()).0
     }
 
     fn get_tag(&self) -> &str {
@@ -83,7 +321,7 @@ where
         &self,
         listener: &mut dyn Notifiable<SensorReadings<Sample3D>>,
         sensor_type: &SensorType,
-    ) -> Result<Uuid, String> {
+    ) -> Result<Uuid, ImuError> {
         self.publishers.add_listener(listener, sensor_type)
     }
 
@@ -92,19 +330,63 @@ where
     }
 }
 
+/// Source of the readings of the scalar sensors (pressure, light, proximity and audio amplitude)
+/// of a `PhyphoxService`, returned by `get_scalar_source`. The service itself is the source of
+/// its 3D sensors.
+#[derive(Clone)]
+pub struct PhyphoxScalarSource {
+    tag: String,
+    sensors: Vec<SensorType>,
+    publishers: PublisherManager<SensorReadings<SampleScalar>, SensorType>,
+}
+
+impl IMUSource<SensorReadings<SampleScalar>, SampleScalar> for PhyphoxScalarSource {
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.sensors.clone()
+    }
+
+    fn get_tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<SampleScalar>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<SampleScalar>>) {
+        self.publishers.notify_listeners(sensor_type, data);
+    }
+}
+
+/// Splits `sensor_cluster` into its 3D sensors and its scalar (`SensorType::Other`) sensors.
+fn split_sensor_cluster(sensor_cluster: Vec<SensorType>) -> (Vec<SensorType>, Vec<SensorType>) {
+    sensor_cluster
+        .into_iter()
+        .partition(|sensor| !matches!(sensor, SensorType::Other(..)))
+}
+
 /// Starts the phyphox service asynchronously, handling sensor data acquisition and processing.
 ///
 /// This function initializes the required sensors, and begins
 /// data collection in a background task.
 ///
-/// An error ClientBuild is returned if http client connecting with phyphox app REST API cannot be created.
+/// An error ClientBuild is returned if http client connecting with phyphox app REST API cannot be created,
+/// and DuplicatedSensor if the sensor cluster collides with the one of a running source.
 ///
 /// # Returns
 ///
 /// Returns a tuple containing:
-/// * A `tokio::task::JoinHandle<()>` representing the spawned asynchronous task.
+/// * A `tokio::task::JoinHandle<()>` representing the spawned asynchronous task. The task stops
+///   once the service is dropped.
 /// * An `Arc<PhyphoxService<Phyphox>>` instance, allowing further interaction with the sensor system.
-
 pub fn run_service(
     base_url: &str,
     sensor_cluster_tag: &str,
@@ -112,18 +394,113 @@ pub fn run_service(
     update_period_millis: f64,
 ) -> Result<(tokio::task::JoinHandle<()>, Arc<PhyphoxService<Phyphox>>), PhyphoxError> {
     let phyphox = Phyphox::new(base_url, sensor_cluster_tag, sensor_cluster)?;
-    let phyphox_service: Arc<PhyphoxService<Phyphox>> = Arc::new(PhyphoxService::new(phyphox));
+    let phyphox_service: Arc<PhyphoxService<Phyphox>> = Arc::new(PhyphoxService::try_new(phyphox)?);
 
     let handle = tokio::spawn({
         let phyphox_service_clone = phyphox_service.clone();
         async move {
-            if let Err(e) = phyphox_service_clone
-                .start(
-                    Duration::from_secs_f64(update_period_millis / 1000.0),
-                    None, // run until ctrl-c signal
-                )
-                .await
-            {
+            let start = phyphox_service_clone.start(
+                Duration::from_secs_f64(update_period_millis / 1000.0),
+                None, // run until ctrl-c signal
+            );
+            tokio::pin!(start);
+            let result = tokio::select! {
+                result = &mut start => result,
+                // stop once the caller drops the service
+                _ = lifetime::orphaned(&phyphox_service_clone) => {
+                    phyphox_service_clone.stop();
+                    start.await
+                }
+            };
+            if let Err(e) = result {
+                error!("Error in Phyphox loop: {:?}", e);
+            }
+        }
+    });
+    Ok((handle, phyphox_service))
+}
+
+/// Starts the phyphox service as a permanent monitoring service, supervised by a `SoakMonitor`
+/// attached to its 3D sensors.
+///
+/// The monitor checks the invariants configured in `config` and logs a heartbeat until the
+/// ctrl-c signal. Errors are the ones of `run_service`, and Other if the monitor can't be
+/// attached.
+///
+/// # Returns
+///
+/// Returns the tuple of `run_service`, and the `SoakMonitor` to query the health of the pipeline.
+#[allow(clippy::type_complexity)]
+pub fn run_soak_service(
+    base_url: &str,
+    sensor_cluster_tag: &str,
+    sensor_cluster: Vec<SensorType>,
+    update_period_millis: f64,
+    config: SoakConfig,
+) -> Result<
+    (
+        tokio::task::JoinHandle<()>,
+        Arc<PhyphoxService<Phyphox>>,
+        SoakMonitor<SensorReadings<Sample3D>, Sample3D>,
+    ),
+    PhyphoxError,
+> {
+    let (sensors_3d, _) = split_sensor_cluster(sensor_cluster.clone());
+    let (handle, phyphox_service) = run_service(
+        base_url,
+        sensor_cluster_tag,
+        sensor_cluster,
+        update_period_millis,
+    )?;
+    let monitor = soak::watch(
+        &*phyphox_service,
+        &sensors_3d,
+        config,
+        phyphox_service.shutdown.clone(),
+    )
+    .map_err(|e| PhyphoxError::Other(e.to_string()))?;
+    Ok((handle, phyphox_service, monitor))
+}
+
+/// Starts the phyphox service in duty cycled mode, alternating active periods fetching data
+/// every `update_period_millis` with paused periods where the experiment is stopped on the phone,
+/// as set by `duty_cycle`.
+///
+/// Register a phase listener on the returned service to pause downstream components while the
+/// acquisition is paused. Errors are the ones of `run_service`, and InvalidDutyCycle if a period
+/// of `duty_cycle` is empty.
+///
+/// # Returns
+///
+/// Returns the tuple of `run_service`. The task stops on ctrl-c, or once the service is dropped.
+pub fn run_duty_cycled_service(
+    base_url: &str,
+    sensor_cluster_tag: &str,
+    sensor_cluster: Vec<SensorType>,
+    update_period_millis: f64,
+    duty_cycle: DutyCycle,
+) -> Result<(tokio::task::JoinHandle<()>, Arc<PhyphoxService<Phyphox>>), PhyphoxError> {
+    duty_cycle.validate()?;
+    let phyphox = Phyphox::new(base_url, sensor_cluster_tag, sensor_cluster)?;
+    let phyphox_service: Arc<PhyphoxService<Phyphox>> = Arc::new(PhyphoxService::try_new(phyphox)?);
+
+    let handle = tokio::spawn({
+        let phyphox_service_clone = phyphox_service.clone();
+        async move {
+            let start = phyphox_service_clone.start_duty_cycled(
+                Duration::from_secs_f64(update_period_millis / 1000.0),
+                duty_cycle,
+            );
+            tokio::pin!(start);
+            let result = tokio::select! {
+                result = &mut start => result,
+                // stop once the caller drops the service
+                _ = lifetime::orphaned(&phyphox_service_clone) => {
+                    phyphox_service_clone.stop();
+                    start.await
+                }
+            };
+            if let Err(e) = result {
                 error!("Error in Phyphox loop: {:?}", e);
             }
         }
@@ -134,10 +511,11 @@ pub fn run_service(
 /// Starts the a mock phyphox service that generates pre-stored data.
 ///
 /// Returns a tuple containing:
-/// - A `tokio::task::JoinHandle<()>` representing the spawned asynchronous task.
+/// - A `tokio::task::JoinHandle<()>` representing the spawned asynchronous task. The task stops
+///   once the service is dropped.
 /// - An `Arc<PhyphoxService<PhyphoxMock>>` instance, allowing further interaction with the sensor system.
 ///
-/// An error ClientBuild is returned if http client connecting with phyphox app REST API cannot be created.
+/// An error DuplicatedSensor is returned if the sensor cluster collides with the one of a running source.
 pub fn run_mock_service(
     sensor_cluster_tag: &str,
     sensor_cluster: Vec<SensorType>,
@@ -157,17 +535,25 @@ pub fn run_mock_service(
         update_period_millis,
         add_sensor_noise,
     )?;
-    let phyphox_service: Arc<PhyphoxService<PhyphoxMock>> = Arc::new(PhyphoxService::new(phyphox));
+    let phyphox_service: Arc<PhyphoxService<PhyphoxMock>> =
+        Arc::new(PhyphoxService::try_new(phyphox)?);
     let handle = tokio::spawn({
         let phyphox_service_clone = phyphox_service.clone();
         async move {
-            if let Err(e) = phyphox_service_clone
-                .start(
-                    Duration::from_secs_f64(update_period_millis / 1000.0),
-                    Some(run_for_millis),
-                )
-                .await
-            {
+            let start = phyphox_service_clone.start(
+                Duration::from_secs_f64(update_period_millis / 1000.0),
+                Some(run_for_millis),
+            );
+            tokio::pin!(start);
+            let result = tokio::select! {
+                result = &mut start => result,
+                // stop once the caller drops the service
+                _ = lifetime::orphaned(&phyphox_service_clone) => {
+                    phyphox_service_clone.stop();
+                    start.await
+                }
+            };
+            if let Err(e) = result {
                 error!("Error in Phyphox loop: {:?}", e);
             }
         }
@@ -175,6 +561,85 @@ pub fn run_mock_service(
     Ok((handle, phyphox_service))
 }
 
+/// Searches the network for phones running phyphox with remote access enabled, probing every host
+/// of the subnet given by `config`.
+///
+/// Returns the devices found, in address order, with the sensors exported by their running
+/// experiment. Their base URL can be passed to `run_service`. An error Other is returned if the
+/// subnet to scan can't be determined.
+pub async fn discover_devices(
+    config: DiscoveryConfig,
+) -> Result<Vec<DiscoveredDevice>, PhyphoxError> {
+    let hosts = config.get_hosts()?;
+    let devices = stream::iter(hosts)
+        .map(|host| {
+            let base_url = format!("http://{}:{}", host, config.get_port());
+            production::probe_device(base_url, config.get_timeout())
+        })
+        .buffered(config.get_max_concurrent_probes())
+        .filter_map(|device| async move { device })
+        .collect()
+        .await;
+    Ok(devices)
+}
+
+/// Registers the `phyphox` and `mock` sources in `registry`.
+///
+/// Parameters of `phyphox`: `url`, `tag`, `period_millis` and optionally `sensors`.
+/// Parameters of `mock`: `tag`, `period_millis`, `run_for_millis` and optionally `sensors`
+/// and `noise`.
+///
+/// `sensors` is a list of `kind::uuid` strings. If missing, an accelerometer, gyroscope and
+/// magnetometer are created. Their ids are random, unless `identity_store` gives the path of an
+/// `IdentityStore`, and `device_id` the id of the phone in the store, so the phone gets the
+/// same ids on every run. Sources are started as soon as they are created, so they must be
+/// created inside a tokio runtime.
+pub fn register_sources(
+    registry: &mut SourceRegistry<SensorReadings<Sample3D>, Sample3D>,
+) -> Result<(), ImuError> {
+    registry.register("phyphox", |params| {
+        let (_, service) = run_service(
+            params.get_str("url")?,
+            params.get_str("tag")?,
+            sensor_cluster_from_params(params)?,
+            params.get_float("period_millis")?,
+        )
+        .map_err(|e| ImuError::Other(format!("{:?}", e)))?;
+        Ok(service)
+    })?;
+    registry.register("mock", |params| {
+        let add_sensor_noise = if params.contains("noise") {
+            params.get_bool("noise")?
+        } else {
+            false
+        };
+        let (_, service) = run_mock_service(
+            params.get_str("tag")?,
+            sensor_cluster_from_params(params)?,
+            params.get_float("period_millis")?,
+            add_sensor_noise,
+            params.get_int("run_for_millis")? as u64,
+        )
+        .map_err(|e| ImuError::Other(format!("{:?}", e)))?;
+        Ok(service)
+    })
+}
+
+fn sensor_cluster_from_params(params: &SourceParams) -> Result<Vec<SensorType>, ImuError> {
+    if params.contains("sensors") {
+        return params.get_sensor_cluster("sensors");
+    }
+    if params.contains("identity_store") {
+        let mut store = IdentityStore::open(params.get_str("identity_store")?)?;
+        return store.resolve(
+            params.get_str("device_id")?,
+            params.get_str("tag")?,
+            SensorClusterBuilder::new().nine_axis(),
+        );
+    }
+    SensorClusterBuilder::new().nine_axis().build()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,11 +648,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_phyphox_client_new() {
-        let sensor_cluster = vec![
-            SensorType::Accelerometer(Uuid::new_v4()),
-            SensorType::Gyroscope(Uuid::new_v4()),
-            SensorType::Magnetometer(Uuid::new_v4()),
-        ];
+        let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
         let client = Phyphox::new("http://localhost", "Test", sensor_cluster)
             .expect("Error creating Phyphox instance");
         PhyphoxService::new(client);
@@ -195,11 +656,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_phyphox_mini_client_new() {
-        let sensor_cluster = vec![
-            SensorType::Accelerometer(Uuid::new_v4()),
-            SensorType::Gyroscope(Uuid::new_v4()),
-            SensorType::Magnetometer(Uuid::new_v4()),
-        ];
+        let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
         let client = PhyphoxMock::new("Test", sensor_cluster, 100.0, false)
             .expect("Error creating Phyphox instance");
         let client_service = Arc::new(PhyphoxService::new(client));
@@ -221,11 +678,7 @@ mod tests {
         let update_period_millis = 100.0;
         let add_sensor_noise = false;
         let run_for_millis = 1000;
-        let sensor_cluster = vec![
-            SensorType::Accelerometer(Uuid::new_v4()),
-            SensorType::Gyroscope(Uuid::new_v4()),
-            SensorType::Magnetometer(Uuid::new_v4()),
-        ];
+        let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
         let (handle, _service) = run_mock_service(
             sensor_tag,
             sensor_cluster,
@@ -237,4 +690,269 @@ mod tests {
 
         handle.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        use imu_common::traits::IMUReadings;
+        use publisher::{ChannelConfig, Receiver, Subscribe};
+
+        let sensor_cluster = SensorType::cluster_for_tag("test_subscribe");
+        let (handle, service) =
+            run_mock_service("Test", sensor_cluster.clone(), 50.0, false, 500).unwrap();
+        let receiver: Receiver<SensorReadings<Sample3D>> = service
+            .subscribe(&sensor_cluster[0], ChannelConfig::new())
+            .unwrap();
+
+        let readings = receiver.recv_async().await.unwrap();
+        assert_eq!(readings.get_sensor_type(), sensor_cluster[0]);
+        assert!(!readings.get_samples().is_empty());
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stop_service() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_stop_service");
+        let (handle, service) =
+            run_mock_service("Test", sensor_cluster, 100.0, false, 60_000).unwrap();
+
+        service.stop();
+
+        tokio::time::timeout(Duration::from_secs(2), handle)
+            .await
+            .expect("Service didn't stop")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stop_on_drop() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_stop_on_drop");
+        let (handle, service) =
+            run_mock_service("Test", sensor_cluster.clone(), 100.0, false, 60_000).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        drop(service);
+
+        tokio::time::timeout(Duration::from_secs(2), handle)
+            .await
+            .expect("Service didn't stop")
+            .unwrap();
+        // the sensors are released with the service
+        let (handle, _service) =
+            run_mock_service("Test", sensor_cluster, 100.0, false, 100).unwrap();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stop_on_shutdown() {
+        let token = ShutdownToken::new();
+        let sensor_cluster = SensorType::cluster_for_tag("test_stop_on_shutdown");
+        let client = PhyphoxMock::new("Test", sensor_cluster, 100.0, false).unwrap();
+        let service = Arc::new(PhyphoxService::new(client).with_shutdown_token(token.clone()));
+        let handle = tokio::spawn({
+            let service = service.clone();
+            async move {
+                service
+                    .start(Duration::from_millis(100), Some(60_000))
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        token.shutdown();
+
+        tokio::time::timeout(Duration::from_secs(2), handle)
+            .await
+            .expect("Service didn't stop")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scalar_sensors() {
+        let sensor_cluster = SensorClusterBuilder::new()
+            .accelerometer()
+            .other("pressure")
+            .build()
+            .unwrap();
+        let pressure = sensor_cluster[1].clone();
+        let (handle, service) =
+            run_mock_service("Test", sensor_cluster.clone(), 50.0, false, 1000).unwrap();
+
+        let scalar_source = service.get_scalar_source();
+        assert_eq!(
+            service.get_available_sensors(),
+            vec![sensor_cluster[0].clone()]
+        );
+        assert_eq!(
+            scalar_source.get_available_sensors(),
+            vec![pressure.clone()]
+        );
+        assert_eq!(scalar_source.get_tag(), "Test");
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut listener = publisher::Listener::new({
+            let received = received.clone();
+            move |_id, readings: Arc<SensorReadings<SampleScalar>>| {
+                received.lock().unwrap().push(readings.get_sensor_type());
+            }
+        });
+        scalar_source
+            .register_listener(&mut listener, &pressure)
+            .unwrap();
+
+        handle.await.unwrap();
+        let received = received.lock().unwrap();
+        assert!(!received.is_empty());
+        assert!(received.iter().all(|sensor| *sensor == pressure));
+    }
+
+    #[tokio::test]
+    async fn test_duplicated_cluster() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_duplicated_cluster");
+        let (handle, _service) =
+            run_mock_service("Test", sensor_cluster.clone(), 100.0, false, 200).unwrap();
+
+        let result = run_mock_service("Test", sensor_cluster, 100.0, false, 200);
+        assert!(matches!(result, Err(PhyphoxError::DuplicatedSensor(_))));
+
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_discover_devices() {
+        use std::net::Ipv4Addr;
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/config"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "export": [{ "set": "Accelerometer" }, { "set": "Pressure" }]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/meta"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "uniqueID": "a1b2c3", "deviceModel": "Pixel 7"
+            })))
+            .mount(&mock_server)
+            .await;
+        let config = DiscoveryConfig::new()
+            .with_subnet(Ipv4Addr::LOCALHOST, 30)
+            .with_port(mock_server.address().port())
+            .with_timeout(Duration::from_millis(200));
+
+        let devices = discover_devices(config).await.unwrap();
+
+        assert_eq!(
+            devices,
+            vec![DiscoveredDevice {
+                base_url: mock_server.uri(),
+                available_sensors: vec!["accelerometer".to_string(), "pressure".to_string()],
+                device_id: Some("a1b2c3".to_string()),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_mock_from_registry() {
+        let mut registry = SourceRegistry::new();
+        register_sources(&mut registry).unwrap();
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let params = SourceParams::new()
+            .with("tag", "Test")
+            .with("sensors", vec![acc.to_string()])
+            .with("period_millis", 100.0)
+            .with("run_for_millis", 200_i64);
+
+        let source = registry.create("mock", &params).unwrap();
+
+        assert_eq!(
+            registry.get_available_sources(),
+            vec!["mock".to_string(), "phyphox".to_string()]
+        );
+        assert_eq!(source.get_tag(), "Test");
+        assert_eq!(source.get_available_sensors(), vec![acc]);
+        assert!(registry
+            .create("mock", &SourceParams::new().with("tag", "Test"))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_duty_cycle() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_duty_cycle");
+        let client = PhyphoxMock::new("Test", sensor_cluster.clone(), 50.0, false).unwrap();
+        let service = Arc::new(PhyphoxService::new(client));
+
+        let phases = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut phase_listener = publisher::Listener::new({
+            let phases = phases.clone();
+            move |_id, phase: Arc<AcquisitionPhase>| phases.lock().unwrap().push((*phase).clone())
+        });
+        service.register_phase_listener(&mut phase_listener);
+        let timestamps = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut listener = publisher::Listener::new({
+            let timestamps = timestamps.clone();
+            move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+                timestamps.lock().unwrap().push(Clock::now().as_secs());
+                drop(readings);
+            }
+        });
+        service
+            .register_listener(&mut listener, &sensor_cluster[0])
+            .unwrap();
+
+        let handle = tokio::spawn({
+            let service = service.clone();
+            let duty_cycle = DutyCycle::new(Duration::from_millis(300), Duration::from_millis(400));
+            async move {
+                service
+                    .start_duty_cycled(Duration::from_millis(50), duty_cycle)
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        service.stop();
+        tokio::time::timeout(Duration::from_secs(2), handle)
+            .await
+            .expect("Service didn't stop")
+            .unwrap()
+            .unwrap();
+
+        let phases = phases.lock().unwrap();
+        assert!(matches!(
+            phases.as_slice(),
+            [
+                AcquisitionPhase::Active { .. },
+                AcquisitionPhase::Paused { .. },
+                AcquisitionPhase::Active { .. },
+                ..
+            ]
+        ));
+        // nothing is published while paused
+        let AcquisitionPhase::Paused {
+            since_secs,
+            until_secs,
+        } = phases[1]
+        else {
+            unreachable!()
+        };
+        let timestamps = timestamps.lock().unwrap();
+        assert!(!timestamps.is_empty());
+        assert!(timestamps
+            .iter()
+            .all(|t| *t <= since_secs || *t >= until_secs - 0.01));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_duty_cycle() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_invalid_duty_cycle");
+        let result = run_duty_cycled_service(
+            "http://localhost",
+            "Test",
+            sensor_cluster,
+            100.0,
+            DutyCycle::new(Duration::ZERO, Duration::from_secs(1)),
+        );
+        assert!(matches!(result, Err(PhyphoxError::InvalidDutyCycle(_))));
+    }
 }
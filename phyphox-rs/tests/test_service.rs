@@ -1,5 +1,7 @@
+use phyphox_rs::models::clipping::{ClippingPolicy, RangeLimit};
 use phyphox_rs::services;
-use publisher::Listener;
+use publisher::soak::{self, SoakConfig, SoakMonitor, SoakViolation};
+use publisher::{adapters, Listener, ShutdownToken};
 use std::collections::HashMap;
 use std::{
     sync::{Arc, Mutex},
@@ -11,7 +13,7 @@ use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
 use imu_common::types::sensors::{SensorReadings, SensorType};
 use imu_common::types::timed::Sample3D;
 use imu_common::types::Clock;
-use test_utils::sinks::{MockValue, SinkMock};
+use test_utils::sinks::{AlarmConfig, MockValue, SinkMock, ThresholdAlarm};
 
 fn process_samples(
     value: MockValue,
@@ -87,11 +89,39 @@ async fn test_receive_accelerometer_samples() {
     // check that samples were received by handler
     let buffer = received_samples.lock().unwrap();
     let received_id = received_id.lock().unwrap();
-    assert!(buffer.len() > 0);
+    assert!(!buffer.is_empty());
     // the listener receives an id as part of the callback, which can match to which listener function it received
     assert!(expected_id == *received_id)
 }
 
+#[tokio::test]
+async fn test_sink_mock_deliveries() {
+    let sensor_tag = "Test";
+    let acc_id = Uuid::new_v4();
+    let sensor_cluster = vec![SensorType::Accelerometer(acc_id)];
+
+    // Start phyphox mock service
+    let (handle, phyphox) =
+        services::run_mock_service(sensor_tag, sensor_cluster, 200.0, false, 2000).unwrap();
+
+    // install sink
+    let sink = SinkMock::<Sample3D>::new();
+    let expected_id = sink
+        .attach_listeners(&*phyphox, &[SensorType::Accelerometer(acc_id)])
+        .unwrap()[0];
+
+    handle.await.unwrap();
+
+    // check that samples were received by the sink
+    sink.assert_received_at_least(1);
+    sink.assert_monotone_timestamps();
+    for delivery in sink.get_deliveries() {
+        assert_eq!(delivery.readings.get_sensor_tag(), "Test");
+        // the listener receives an id as part of the callback, which can match to which listener function it received
+        assert_eq!(delivery.listener_id, expected_id);
+    }
+}
+
 #[tokio::test]
 async fn test_receive_multiple_sensors() {
     let sensor_tag = "Test";
@@ -138,7 +168,7 @@ async fn test_receive_multiple_sensors() {
             assert_eq!(tag, "Test");
             storage_lock
                 .entry(id)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .extend(value.get_samples().into_iter());
         })
     };
@@ -257,3 +287,125 @@ async fn test_sink() {
 
     handle.await.unwrap();
 }
+
+#[tokio::test]
+async fn test_discard_clipped_samples() {
+    let sensor_tag = "Test";
+    let update_period_millis = 200.0;
+    let add_sensor_noise = false;
+    let run_for_millis = 2000;
+    let received_samples: Arc<Mutex<Vec<Sample3D>>> = Arc::new(Mutex::new(Vec::new()));
+    let acc_id = Uuid::new_v4();
+    let sensor_cluster = vec![
+        SensorType::Accelerometer(acc_id),
+        SensorType::Gyroscope(Uuid::new_v4()),
+        SensorType::Magnetometer(Uuid::new_v4()),
+    ];
+
+    // Start phyphox mock service
+    let (handle, phyphox) = services::run_mock_service(
+        sensor_tag,
+        sensor_cluster,
+        update_period_millis,
+        add_sensor_noise,
+        run_for_millis,
+    )
+    .unwrap();
+
+    // gravity is always above this limit, so every accelerometer sample is clipped
+    phyphox.set_range_limit(
+        &SensorType::Accelerometer(acc_id),
+        RangeLimit::new(1.0, ClippingPolicy::Discard),
+    );
+
+    let mut listener = {
+        let received_samples = received_samples.clone();
+        Listener::new(move |_id: Uuid, value: Arc<SensorReadings<Sample3D>>| {
+            let mut buffer_lock = received_samples.lock().unwrap();
+            buffer_lock.extend(value.get_samples().into_iter());
+        })
+    };
+    phyphox
+        .register_listener(&mut listener, &SensorType::Accelerometer(acc_id))
+        .unwrap();
+
+    handle.await.unwrap();
+
+    assert!(received_samples.lock().unwrap().is_empty());
+    assert!(phyphox.get_clipped_count(&SensorType::Accelerometer(acc_id)) > 0);
+}
+
+#[tokio::test]
+async fn test_async_sink() {
+    let sensor_tag = "Test";
+    let update_period_millis = 200.0;
+    let add_sensor_noise = false;
+    let run_for_millis = 2000;
+    let raised = Arc::new(Mutex::new(false));
+    let acc_id = Uuid::new_v4();
+    let sensor_cluster = vec![
+        SensorType::Accelerometer(acc_id),
+        SensorType::Gyroscope(Uuid::new_v4()),
+        SensorType::Magnetometer(Uuid::new_v4()),
+    ];
+
+    // Start phyphox mock service
+    let (handle, phyphox) = services::run_mock_service(
+        sensor_tag,
+        sensor_cluster,
+        update_period_millis,
+        add_sensor_noise,
+        run_for_millis,
+    )
+    .unwrap();
+
+    // gravity keeps the accelerometer magnitude above the threshold
+    let mut alarm = ThresholdAlarm::magnitude(AlarmConfig::new(1.0, 0.0, 0.0));
+    let raised_clone = raised.clone();
+    alarm.register_callback(move |_event| *raised_clone.lock().unwrap() = true);
+
+    adapters::attach_async(
+        &alarm,
+        &*phyphox,
+        &[SensorType::Accelerometer(acc_id)],
+        tokio::runtime::Handle::current(),
+    )
+    .unwrap();
+
+    handle.await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert!(*raised.lock().unwrap());
+}
+
+#[tokio::test]
+async fn test_soak_monitor() {
+    let sensor_cluster = vec![
+        SensorType::Accelerometer(Uuid::new_v4()),
+        SensorType::Gyroscope(Uuid::new_v4()),
+    ];
+    let (handle, phyphox) =
+        services::run_mock_service("Test", sensor_cluster.clone(), 100.0, true, 1500).unwrap();
+
+    let token = ShutdownToken::new();
+    let config = SoakConfig::new()
+        .with_heartbeat(Duration::from_millis(100))
+        .with_stall_timeout(Duration::from_millis(500));
+    let monitor: SoakMonitor<SensorReadings<Sample3D>, Sample3D> =
+        soak::watch(&*phyphox, &sensor_cluster, config, token.clone()).unwrap();
+
+    handle.await.unwrap();
+    let report = monitor.get_report();
+    assert!(report.n_samples > 0);
+    assert!(report.is_healthy(), "{:?}", report.recent_violations);
+
+    // both sensors stall once the service stops
+    tokio::time::sleep(Duration::from_millis(800)).await;
+    let report = monitor.get_report();
+    assert_eq!(report.n_violations, 2, "{:?}", report.recent_violations);
+    assert!(report
+        .recent_violations
+        .iter()
+        .all(|violation| matches!(violation, SoakViolation::Stalled { .. })));
+    token.shutdown();
+}
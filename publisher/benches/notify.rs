@@ -0,0 +1,52 @@
+//! Compares notifying through dense and dynamic (`DashMap`) publisher sets.
+//!
+//! Run with `cargo bench -p publisher --bench notify`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::sync::Arc;
+
+use imu_common::types::sensors::{SensorClusterBuilder, SensorType};
+use publisher::delivery::Inline;
+use publisher::{Listener, PublisherManager};
+
+fn manager(sensors: &[SensorType], dynamic: bool) -> PublisherManager<u64, SensorType, Inline> {
+    let manager = PublisherManager::with_delivery(sensors, Inline);
+    let manager = if dynamic {
+        manager.into_dynamic()
+    } else {
+        manager
+    };
+    for sensor in sensors {
+        let mut listener = Listener::new(|_id, value: Arc<u64>| {
+            black_box(value);
+        });
+        manager.add_listener(&mut listener, sensor).unwrap();
+    }
+    manager
+}
+
+fn bench_notify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("notify");
+    for n_sensors in [3, 9, 64] {
+        let sensors = (0..n_sensors)
+            .fold(SensorClusterBuilder::new(), |builder, i| {
+                builder.other(&format!("sensor{}", i))
+            })
+            .build()
+            .unwrap();
+        // the last sensor is the worst case of the linear scan
+        let sensor = sensors.last().unwrap().clone();
+        let data = Arc::new(0u64);
+
+        for (name, dynamic) in [("dense", false), ("dynamic", true)] {
+            let manager = manager(&sensors, dynamic);
+            group.bench_with_input(BenchmarkId::new(name, n_sensors), &sensor, |b, sensor| {
+                b.iter(|| manager.notify_listeners(sensor.clone(), data.clone()))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_notify);
+criterion_main!(benches);
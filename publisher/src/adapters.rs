@@ -0,0 +1,219 @@
+//! Shared listener adapters for sinks.
+//!
+//! Any `IMUSink` can be attached either synchronously (samples are processed on the publisher
+//! thread) or, with the `tokio` feature, asynchronously (samples are processed on the tokio
+//! blocking pool) without the sink implementing each path itself.
+
+use std::sync::{Arc, Weak};
+#[cfg(feature = "tokio")]
+use tokio::runtime::Handle;
+use uuid::Uuid;
+
+use crate::{Listener, ShutdownToken, SubscriptionGuard};
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource, Notifiable};
+use imu_common::types::capabilities;
+use imu_common::types::sensors::SensorType;
+
+/// Registers `listener` to every sensor in `sensor_cluster`.
+pub fn attach_with<T, S>(
+    listener: &mut dyn Notifiable<T>,
+    source: &dyn IMUSource<T, S>,
+    sensor_cluster: &[SensorType],
+) -> Result<Vec<Uuid>, ImuError>
+where
+    T: Send + Sync + IMUReadings<S>,
+    S: Send + Sync + IMUSample,
+{
+    let mut ids = Vec::with_capacity(sensor_cluster.len());
+    for sensor_type in sensor_cluster {
+        ids.push(source.register_listener(listener, sensor_type)?);
+    }
+    Ok(ids)
+}
+
+/// Checks the requirements of `sink` against the capabilities of `source`.
+pub fn negotiate<K, T, S>(
+    sink: &K,
+    source: &dyn IMUSource<T, S>,
+    sensor_cluster: &[SensorType],
+) -> Result<(), ImuError>
+where
+    K: IMUSink<T, S> + ?Sized,
+    T: Send + Sync + IMUReadings<S>,
+    S: Send + Sync + IMUSample,
+{
+    capabilities::negotiate(
+        &source.get_capabilities(),
+        &sink.get_requirements(),
+        sensor_cluster,
+    )
+    .map_err(|e| match e {
+        ImuError::IncompatibleSensor(e) => {
+            ImuError::IncompatibleSensor(format!("Cannot attach to {}: {}", source.get_tag(), e))
+        }
+        e => e,
+    })
+}
+
+/// Returns a `Listener` forwarding samples to `sink`.
+pub fn sync_listener<K, T, S>(sink: &K) -> Listener<T>
+where
+    K: IMUSink<T, S> + Clone + 'static,
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+{
+    let sink = sink.clone();
+    Listener::new(move |id, samples: Arc<T>| sink.process_samples(id, samples))
+}
+
+/// Returns a `Listener` forwarding samples to `sink` from the blocking pool of `handle`.
+#[cfg(feature = "tokio")]
+pub fn async_listener<K, T, S>(sink: &K, handle: Handle) -> Listener<T>
+where
+    K: IMUSink<T, S> + Clone + 'static,
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+{
+    let sink = sink.clone();
+    Listener::from_blocking(handle, move |id, samples: Arc<T>| {
+        sink.process_samples(id, samples)
+    })
+}
+
+/// Attaches `sink` to `source` with a synchronous listener, once the capabilities are negotiated.
+pub fn attach_sync<K, T, S>(
+    sink: &K,
+    source: &dyn IMUSource<T, S>,
+    sensor_cluster: &[SensorType],
+) -> Result<Vec<Uuid>, ImuError>
+where
+    K: IMUSink<T, S> + Clone + 'static,
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+{
+    negotiate(sink, source, sensor_cluster)?;
+    attach_with(&mut sync_listener(sink), source, sensor_cluster)
+}
+
+/// Attaches `sink` to `source` with an asynchronous listener running on `handle`, once the
+/// capabilities are negotiated.
+#[cfg(feature = "tokio")]
+pub fn attach_async<K, T, S>(
+    sink: &K,
+    source: &dyn IMUSource<T, S>,
+    sensor_cluster: &[SensorType],
+    handle: Handle,
+) -> Result<Vec<Uuid>, ImuError>
+where
+    K: IMUSink<T, S> + Clone + 'static,
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+{
+    negotiate(sink, source, sensor_cluster)?;
+    attach_with(&mut async_listener(sink, handle), source, sensor_cluster)
+}
+
+/// Detaches the listeners `ids` of `sink` from `source` once `token` is shut down.
+pub fn detach_on_shutdown<K, T, S>(
+    sink: &K,
+    source: Arc<dyn IMUSource<T, S>>,
+    ids: Vec<Uuid>,
+    token: &ShutdownToken,
+) where
+    K: IMUSink<T, S> + Clone + 'static,
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+{
+    let sink = sink.clone();
+    token.on_shutdown(move || {
+        for id in ids {
+            sink.detach_listener(&*source, id);
+        }
+    });
+}
+
+/// Listeners of a sink attached with [`attach`]. They are unregistered from the source once the
+/// subscription is dropped.
+pub type Subscription = SubscriptionGuard;
+
+/// Attaches `sink` to every sensor of `sensor_cluster` published by `source`, e.g.
+/// `attach(&phyphox, &resampler, &sensor_cluster)` or `attach(&resampler, &ahrs, &sensor_cluster)`.
+///
+/// Fails before registering any listener if `source` doesn't publish every sensor of the
+/// cluster. Errors of the sink, e.g. requirements not matching the source capabilities, are
+/// returned as is. The subscription only holds the source weakly, so it doesn't keep it alive.
+pub fn attach<Src, K, T, S>(
+    source: &Arc<Src>,
+    sink: &K,
+    sensor_cluster: &[SensorType],
+) -> Result<Subscription, ImuError>
+where
+    Src: IMUSource<T, S> + 'static,
+    K: IMUSink<T, S> + ?Sized,
+    T: Send + Sync + IMUReadings<S>,
+    S: Send + Sync + IMUSample,
+{
+    if sensor_cluster.is_empty() {
+        return Err(ImuError::InvalidSensorCluster(format!(
+            "Cannot attach to {}: empty sensor cluster",
+            source.get_tag()
+        )));
+    }
+    let available_sensors = source.get_available_sensors();
+    let missing: Vec<String> = sensor_cluster
+        .iter()
+        .filter(|sensor_type| !available_sensors.contains(sensor_type))
+        .map(|sensor_type| sensor_type.to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Err(ImuError::IncompatibleSensor(format!(
+            "Cannot attach to {}: sensors not published: {}",
+            source.get_tag(),
+            missing.join(", ")
+        )));
+    }
+
+    let ids = sink.attach_listeners(&**source, sensor_cluster)?;
+    Ok(guard(source, ids))
+}
+
+/// Registers `listener` to every sensor in `sensor_cluster`, like [`attach_with`], returning a
+/// guard that unregisters it once dropped. The guard only holds the source weakly.
+pub fn register<Src, T, S>(
+    source: &Arc<Src>,
+    listener: &mut dyn Notifiable<T>,
+    sensor_cluster: &[SensorType],
+) -> Result<SubscriptionGuard, ImuError>
+where
+    Src: IMUSource<T, S> + 'static,
+    T: Send + Sync + IMUReadings<S>,
+    S: Send + Sync + IMUSample,
+{
+    let mut ids = Vec::with_capacity(sensor_cluster.len());
+    for sensor_type in sensor_cluster {
+        match source.register_listener(listener, sensor_type) {
+            Ok(id) => ids.push(id),
+            Err(e) => {
+                // dropping the guard unregisters the sensors already registered
+                drop(guard(source, ids));
+                return Err(e);
+            }
+        }
+    }
+    Ok(guard(source, ids))
+}
+
+fn guard<Src, T, S>(source: &Arc<Src>, ids: Vec<Uuid>) -> SubscriptionGuard
+where
+    Src: IMUSource<T, S> + 'static,
+    T: Send + Sync + IMUReadings<S>,
+    S: Send + Sync + IMUSample,
+{
+    let source: Weak<Src> = Arc::downgrade(source);
+    SubscriptionGuard::new(ids, move |id| {
+        if let Some(source) = source.upgrade() {
+            source.unregister_listener(id);
+        }
+    })
+}
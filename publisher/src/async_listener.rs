@@ -0,0 +1,5 @@
+//! Listeners with asynchronous callbacks, now created with [`Listener`](crate::Listener).
+
+/// Listener whose callback returns a future.
+#[deprecated(note = "`Listener` accepts asynchronous callbacks, use `Listener` instead")]
+pub type AsyncListener<T> = crate::Listener<T>;
@@ -0,0 +1,453 @@
+//! Module channel
+//!
+//! Subscriptions delivering the data of a publisher through a bounded channel, so consumers can
+//! pull it from an ordinary loop, sync or async, instead of writing a callback.
+//!
+//! ```
+//! use imu_common::types::sensors::SensorType;
+//! use publisher::channel::ChannelConfig;
+//! use publisher::delivery::Inline;
+//! use publisher::PublisherManager;
+//! use std::sync::Arc;
+//!
+//! let sensor_cluster = SensorType::cluster_for_tag("phone");
+//! let manager =
+//!     PublisherManager::<Vec<f64>, SensorType, _>::with_delivery(&sensor_cluster, Inline);
+//! let receiver = manager
+//!     .subscribe(&sensor_cluster[0], ChannelConfig::new())
+//!     .unwrap();
+//!
+//! manager.notify_listeners(sensor_cluster[0].clone(), Arc::new(vec![9.8]));
+//! assert_eq!(*receiver.recv().unwrap(), vec![9.8]);
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+#[cfg(feature = "tokio")]
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::Listener;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSource};
+use imu_common::types::sensors::SensorType;
+
+const DEFAULT_CAPACITY: usize = 64;
+
+/// What a subscription does with data published while its channel is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Drops the oldest queued data, so the consumer always gets the latest readings.
+    #[default]
+    DropOldest,
+    /// Drops the published data, keeping the queued data.
+    DropNewest,
+    /// Blocks the publisher until the consumer catches up, providing backpressure to the source.
+    Block,
+}
+
+/// Settings of the channel of a subscription.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChannelConfig {
+    capacity: usize,
+    overflow: Overflow,
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            overflow: Overflow::default(),
+        }
+    }
+}
+
+impl ChannelConfig {
+    /// Queues up to 64 notifications, dropping the oldest ones once full.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of notifications queued, at least one.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn get_overflow(&self) -> Overflow {
+        self.overflow
+    }
+}
+
+struct State<T> {
+    queue: VecDeque<Arc<T>>,
+    n_dropped: usize,
+    // no listener holds the sender anymore
+    closed: bool,
+    receiver_alive: bool,
+}
+
+struct Shared<T> {
+    config: ChannelConfig,
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    #[cfg(feature = "tokio")]
+    notify: Notify,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap()
+    }
+
+    fn wake_receiver(&self) {
+        self.not_empty.notify_one();
+        #[cfg(feature = "tokio")]
+        self.notify.notify_one();
+    }
+}
+
+/// Sending half, captured by the callback of the listener. The channel closes once the
+/// publisher drops the callback, e.g. when the listener is unregistered or the source dropped.
+struct Sender<T>(Arc<Shared<T>>);
+
+impl<T> Sender<T> {
+    fn send(&self, data: Arc<T>) {
+        let shared = &self.0;
+        let mut state = shared.lock();
+        while state.queue.len() >= shared.config.capacity && state.receiver_alive {
+            match shared.config.overflow {
+                Overflow::DropOldest => {
+                    state.queue.pop_front();
+                    state.n_dropped += 1;
+                }
+                Overflow::DropNewest => {
+                    state.n_dropped += 1;
+                    return;
+                }
+                Overflow::Block => state = shared.not_full.wait(state).unwrap(),
+            }
+        }
+        if !state.receiver_alive {
+            return;
+        }
+        state.queue.push_back(data);
+        drop(state);
+        shared.wake_receiver();
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.0.lock().closed = true;
+        self.0.wake_receiver();
+    }
+}
+
+/// Receiving half of a subscription.
+///
+/// Data is received in the order it was queued. Once the subscription is closed, the queued
+/// data is still received before `recv` returns None. Dropping the receiver unregisters its
+/// listener on the next notification of the publisher.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    pub(crate) id: Uuid,
+}
+
+impl<T> Receiver<T> {
+    /// Returns the id of the listener feeding the channel.
+    pub fn get_id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Blocks until data is available, or returns None once the subscription is closed.
+    pub fn recv(&self) -> Option<Arc<T>> {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(data) = self.pop(&mut state) {
+                return Some(data);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self.shared.not_empty.wait(state).unwrap();
+        }
+    }
+
+    /// Same as `recv`, also returning None if nothing is received within `timeout`.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Arc<T>> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(data) = self.pop(&mut state) {
+                return Some(data);
+            }
+            let now = Instant::now();
+            if state.closed || now >= deadline {
+                return None;
+            }
+            state = self
+                .shared
+                .not_empty
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// Returns the oldest queued data without waiting.
+    pub fn try_recv(&self) -> Option<Arc<T>> {
+        let mut state = self.shared.lock();
+        self.pop(&mut state)
+    }
+
+    /// Waits for data without blocking the runtime, or returns None once the subscription is
+    /// closed.
+    #[cfg(feature = "tokio")]
+    pub async fn recv_async(&self) -> Option<Arc<T>> {
+        loop {
+            {
+                let mut state = self.shared.lock();
+                if let Some(data) = self.pop(&mut state) {
+                    return Some(data);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            // senders store a permit if they notify before the receiver waits
+            self.shared.notify.notified().await;
+        }
+    }
+
+    /// Returns a blocking iterator over the received data, ending once the subscription is
+    /// closed.
+    pub fn iter(&self) -> impl Iterator<Item = Arc<T>> + '_ {
+        std::iter::from_fn(move || self.recv())
+    }
+
+    /// Returns the number of notifications dropped because the channel was full.
+    pub fn get_n_dropped(&self) -> usize {
+        self.shared.lock().n_dropped
+    }
+
+    /// Returns true once no publisher feeds the channel anymore.
+    pub fn is_closed(&self) -> bool {
+        self.shared.lock().closed
+    }
+
+    fn pop(&self, state: &mut State<T>) -> Option<Arc<T>> {
+        let data = state.queue.pop_front()?;
+        if self.shared.config.overflow == Overflow::Block {
+            self.shared.not_full.notify_one();
+        }
+        Some(data)
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receiver_alive = false;
+        state.queue.clear();
+        drop(state);
+        // unblocks a publisher waiting for room
+        self.shared.not_full.notify_all();
+    }
+}
+
+/// Returns a listener feeding a channel, and the receiver of the channel. The id of the
+/// receiver is set once the listener is registered.
+pub(crate) fn channel<T>(config: ChannelConfig) -> (Listener<T>, Receiver<T>)
+where
+    T: Send + Sync + 'static,
+{
+    let shared = Arc::new(Shared {
+        config,
+        state: Mutex::new(State {
+            queue: VecDeque::with_capacity(config.capacity),
+            n_dropped: 0,
+            closed: false,
+            receiver_alive: true,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+        #[cfg(feature = "tokio")]
+        notify: Notify::new(),
+    });
+    let sender = Sender(shared.clone());
+    let listener = Listener::with_liveness(move |_id, data: Arc<T>| sender.send(data), {
+        let shared = Arc::downgrade(&shared);
+        move || {
+            shared
+                .upgrade()
+                .is_some_and(|shared| shared.lock().receiver_alive)
+        }
+    });
+    (
+        listener,
+        Receiver {
+            shared,
+            id: Uuid::nil(),
+        },
+    )
+}
+
+/// Subscriptions to the sensors of any `IMUSource`.
+pub trait Subscribe<T, S> {
+    /// Subscribes to the readings of `sensor_type`, delivered through a channel set by `config`.
+    fn subscribe(
+        &self,
+        sensor_type: &SensorType,
+        config: ChannelConfig,
+    ) -> Result<Receiver<T>, ImuError>;
+}
+
+impl<Src, T, S> Subscribe<T, S> for Src
+where
+    Src: IMUSource<T, S> + ?Sized,
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+{
+    fn subscribe(
+        &self,
+        sensor_type: &SensorType,
+        config: ChannelConfig,
+    ) -> Result<Receiver<T>, ImuError> {
+        let (mut listener, mut receiver) = channel(config);
+        receiver.id = self.register_listener(&mut listener, sensor_type)?;
+        Ok(receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delivery::Inline;
+    use crate::PublisherManager;
+
+    fn manager() -> (PublisherManager<usize, SensorType, Inline>, SensorType) {
+        let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+        let manager = PublisherManager::with_delivery(std::slice::from_ref(&sensor_type), Inline);
+        (manager, sensor_type)
+    }
+
+    fn publish(manager: &PublisherManager<usize, SensorType, Inline>, sensor: &SensorType) {
+        for i in 0..5 {
+            manager.notify_listeners(sensor.clone(), Arc::new(i));
+        }
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let (manager, sensor_type) = manager();
+        let config = ChannelConfig::new().with_capacity(2);
+        let receiver = manager.subscribe(&sensor_type, config).unwrap();
+        publish(&manager, &sensor_type);
+
+        assert_eq!(receiver.get_n_dropped(), 3);
+        assert_eq!(*receiver.recv().unwrap(), 3);
+        assert_eq!(*receiver.recv().unwrap(), 4);
+        assert!(receiver.try_recv().is_none());
+    }
+
+    #[test]
+    fn test_drop_newest() {
+        let (manager, sensor_type) = manager();
+        let config = ChannelConfig::new()
+            .with_capacity(2)
+            .with_overflow(Overflow::DropNewest);
+        let receiver = manager.subscribe(&sensor_type, config).unwrap();
+        publish(&manager, &sensor_type);
+
+        assert_eq!(receiver.get_n_dropped(), 3);
+        let received: Vec<usize> = std::iter::from_fn(|| receiver.try_recv())
+            .map(|data| *data)
+            .collect();
+        assert_eq!(received, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_block() {
+        let (manager, sensor_type) = manager();
+        let config = ChannelConfig::new()
+            .with_capacity(1)
+            .with_overflow(Overflow::Block);
+        let receiver = manager.subscribe(&sensor_type, config).unwrap();
+        let publisher = std::thread::spawn({
+            let sensor_type = sensor_type.clone();
+            move || publish(&manager, &sensor_type)
+        });
+
+        // the subscription closes once the manager is dropped by the publishing thread
+        let received: Vec<usize> = receiver.iter().map(|data| *data).collect();
+        publisher.join().unwrap();
+        assert_eq!(received, vec![0, 1, 2, 3, 4]);
+        assert_eq!(receiver.get_n_dropped(), 0);
+    }
+
+    #[test]
+    fn test_close() {
+        let (manager, sensor_type) = manager();
+        let receiver = manager
+            .subscribe(&sensor_type, ChannelConfig::new())
+            .unwrap();
+        manager.notify_listeners(sensor_type.clone(), Arc::new(1));
+        manager.remove_listener(receiver.get_id()).unwrap();
+
+        assert!(receiver.is_closed());
+        // queued data is still received
+        assert_eq!(*receiver.recv().unwrap(), 1);
+        assert!(receiver.recv().is_none());
+        assert!(receiver.recv_timeout(Duration::from_millis(10)).is_none());
+    }
+
+    #[test]
+    fn test_drop_receiver() {
+        let (manager, sensor_type) = manager();
+        let config = ChannelConfig::new()
+            .with_capacity(1)
+            .with_overflow(Overflow::Block);
+        let receiver = manager.subscribe(&sensor_type, config).unwrap();
+        drop(receiver);
+
+        // the publisher doesn't block on the full channel of a dropped receiver
+        publish(&manager, &sensor_type);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_recv_async() {
+        let (manager, sensor_type) = manager();
+        let receiver = manager
+            .subscribe(&sensor_type, ChannelConfig::new())
+            .unwrap();
+        let consumer = tokio::spawn(async move {
+            let mut received = Vec::new();
+            while let Some(data) = receiver.recv_async().await {
+                received.push(*data);
+            }
+            received
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        publish(&manager, &sensor_type);
+        drop(manager);
+
+        let received = tokio::time::timeout(Duration::from_secs(1), consumer)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received, vec![0, 1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,116 @@
+//! Process wide record of the publisher types owned by every `PublisherManager`.
+//!
+//! Two sources publishing the same `SensorType` (e.g. copy-pasted cluster definitions) would
+//! silently mix their readings in every sink. Managers claim their publisher types here, so
+//! collisions are detected when the publisher is created.
+
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use uuid::Uuid;
+
+/// Owners of the claimed values of a single publisher type.
+trait Owners: Send {
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Releases every value claimed by `owner`.
+    fn release_all(&mut self, owner: Uuid);
+}
+
+impl<S: Eq + Hash + Send + 'static> Owners for HashMap<S, Uuid> {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn release_all(&mut self, owner: Uuid) {
+        self.retain(|_, claimed_by| *claimed_by != owner);
+    }
+}
+
+type Registry = HashMap<TypeId, Box<dyn Owners>>;
+
+fn registry() -> MutexGuard<'static, Registry> {
+    static CLAIMS: OnceLock<Mutex<Registry>> = OnceLock::new();
+    CLAIMS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+fn owners<S: Eq + Hash + Send + 'static>(registry: &mut Registry) -> &mut HashMap<S, Uuid> {
+    registry
+        .entry(TypeId::of::<S>())
+        .or_insert_with(|| Box::new(HashMap::<S, Uuid>::new()))
+        .as_any_mut()
+        .downcast_mut()
+        .expect("owners are keyed by the TypeId of their publisher type")
+}
+
+/// Result of claiming a publisher type.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Claim {
+    Granted,
+    /// Already claimed by the same owner.
+    Duplicated,
+    /// Already claimed by another owner.
+    Collision,
+}
+
+/// Publisher types claimed by a single manager. Claims are released on drop.
+#[derive(Debug)]
+pub(crate) struct Claims {
+    owner: Uuid,
+    type_ids: Mutex<HashSet<TypeId>>,
+}
+
+impl Claims {
+    pub(crate) fn new() -> Self {
+        Self {
+            owner: Uuid::new_v4(),
+            type_ids: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub(crate) fn claim<S>(&self, publisher_type: &S) -> Claim
+    where
+        S: Eq + Hash + Clone + Send + 'static,
+    {
+        self.type_ids
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(TypeId::of::<S>());
+        let mut registry = registry();
+        match owners::<S>(&mut registry).get(publisher_type) {
+            Some(owner) if *owner == self.owner => Claim::Duplicated,
+            Some(_) => Claim::Collision,
+            None => {
+                owners::<S>(&mut registry).insert(publisher_type.clone(), self.owner);
+                Claim::Granted
+            }
+        }
+    }
+
+    pub(crate) fn release<S>(&self, publisher_type: &S)
+    where
+        S: Eq + Hash + Clone + Send + 'static,
+    {
+        let mut registry = registry();
+        let owners = owners::<S>(&mut registry);
+        if owners.get(publisher_type) == Some(&self.owner) {
+            owners.remove(publisher_type);
+        }
+    }
+}
+
+impl Drop for Claims {
+    fn drop(&mut self) {
+        let type_ids = self.type_ids.get_mut().unwrap_or_else(|e| e.into_inner());
+        let mut registry = registry();
+        for type_id in type_ids.iter() {
+            if let Some(owners) = registry.get_mut(type_id) {
+                owners.release_all(self.owner);
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Module delivery
+//!
+//! Strategies used by [`Publisher`](crate::Publisher) to hand data to its listeners. The strategy
+//! is chosen at construction and decides on which thread, and how, every listener callback runs:
+//!
+//! - [`Inline`]: callbacks run sequentially on the notifying thread.
+//! - [`ThreadPool`]: callbacks run in parallel on the rayon thread pool (default).
+//! - [`BoundedChannel`]: notifications are queued to a dedicated worker thread. The publisher
+//!   blocks when the queue is full, providing backpressure to the source.
+//! - [`TokioTask`]: every callback is spawned as a task on a tokio runtime. Needs the `tokio`
+//!   feature.
+//!
+//! # Ordering
+//!
+//! With the default [`DeliveryOrder::Fifo`], every listener receives the notifications of a
+//! publisher in the order they were published, whatever the strategy, e.g. readings in timestamp
+//! order for a source. Notifications are queued per listener when published, and each listener
+//! drains its own queue, so listeners still run in parallel with each other.
+//! [`DeliveryOrder::Unordered`] hands every notification to the strategy as is, so callbacks of
+//! the same listener may run concurrently and out of order.
+
+use rayon::prelude::*;
+use std::collections::VecDeque;
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "tokio")]
+use tokio::runtime::Handle;
+use uuid::Uuid;
+
+use imu_common::types::Callback;
+
+/// Delivers `data` to every listener.
+pub trait DeliveryStrategy<T>: Send + Sync {
+    fn deliver(&self, listeners: Vec<(Uuid, Callback<T>)>, data: Arc<T>);
+}
+
+/// Runs callbacks sequentially on the notifying thread.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Inline;
+
+impl<T> DeliveryStrategy<T> for Inline {
+    fn deliver(&self, listeners: Vec<(Uuid, Callback<T>)>, data: Arc<T>) {
+        for (id, callback) in listeners {
+            callback(id, data.clone());
+        }
+    }
+}
+
+/// Runs callbacks in parallel on the rayon thread pool.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadPool;
+
+impl<T> DeliveryStrategy<T> for ThreadPool
+where
+    T: Send + Sync,
+{
+    fn deliver(&self, listeners: Vec<(Uuid, Callback<T>)>, data: Arc<T>) {
+        listeners.into_par_iter().for_each(|(id, callback)| {
+            callback(id, data.clone());
+        });
+    }
+}
+
+/// Order in which every listener receives the notifications of a publisher.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeliveryOrder {
+    /// Notifications are received in the order they were published.
+    #[default]
+    Fifo,
+    /// Notifications are received in the order the strategy runs their callbacks.
+    Unordered,
+}
+
+/// Notifications of a listener waiting to be delivered, in the order they were published.
+pub(crate) struct Mailbox<T> {
+    queue: Mutex<VecDeque<Arc<T>>>,
+    draining: AtomicBool,
+}
+
+impl<T> Default for Mailbox<T> {
+    fn default() -> Self {
+        Self {
+            queue: Mutex::new(VecDeque::new()),
+            draining: AtomicBool::new(false),
+        }
+    }
+}
+
+impl<T> Mailbox<T> {
+    pub(crate) fn push(&self, data: Arc<T>) {
+        self.queue.lock().unwrap().push_back(data);
+    }
+
+    /// Calls `callback` with every queued notification. Returns at once if another thread is
+    /// draining the mailbox, as that thread delivers the notifications queued meanwhile too.
+    pub(crate) fn drain(&self, id: Uuid, callback: &Callback<T>) {
+        loop {
+            if self
+                .draining
+                .compare_exchange(
+                    false,
+                    true,
+                    atomic::Ordering::Acquire,
+                    atomic::Ordering::Relaxed,
+                )
+                .is_err()
+            {
+                return;
+            }
+            let draining = Draining(&self.draining);
+            loop {
+                let data = self.queue.lock().unwrap().pop_front();
+                match data {
+                    Some(data) => callback(id, data),
+                    None => break,
+                }
+            }
+            drop(draining);
+            // notifications queued after the last pop but before releasing are drained here
+            if self.queue.lock().unwrap().is_empty() {
+                return;
+            }
+        }
+    }
+}
+
+/// Releases a mailbox when dropped, even if a callback panics.
+struct Draining<'a>(&'a AtomicBool);
+
+impl Drop for Draining<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, atomic::Ordering::Release);
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Queues notifications to a dedicated worker thread.
+///
+/// At most `capacity` notifications are pending at any time. Once the queue is full, `deliver`
+/// blocks until the worker catches up. The worker thread exits when the last clone is dropped.
+#[derive(Clone)]
+pub struct BoundedChannel {
+    sender: SyncSender<Job>,
+}
+
+impl BoundedChannel {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<Job>(capacity);
+        std::thread::spawn(move || {
+            while let Ok(job) = receiver.recv() {
+                job();
+            }
+        });
+        Self { sender }
+    }
+}
+
+impl<T> DeliveryStrategy<T> for BoundedChannel
+where
+    T: Send + Sync + 'static,
+{
+    fn deliver(&self, listeners: Vec<(Uuid, Callback<T>)>, data: Arc<T>) {
+        let job = Box::new(move || Inline.deliver(listeners, data));
+        if self.sender.send(job).is_err() {
+            log::error!("Delivery worker is not running");
+        }
+    }
+}
+
+/// Spawns every callback as a task on a tokio runtime.
+#[cfg(feature = "tokio")]
+#[derive(Clone, Debug)]
+pub struct TokioTask {
+    handle: Handle,
+}
+
+#[cfg(feature = "tokio")]
+impl TokioTask {
+    pub fn new(handle: Handle) -> Self {
+        Self { handle }
+    }
+
+    /// Uses the runtime of the calling context.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn current() -> Self {
+        Self::new(Handle::current())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T> DeliveryStrategy<T> for TokioTask
+where
+    T: Send + Sync + 'static,
+{
+    fn deliver(&self, listeners: Vec<(Uuid, Callback<T>)>, data: Arc<T>) {
+        for (id, callback) in listeners {
+            let data = data.clone();
+            self.handle.spawn(async move { callback(id, data) });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn counting_listeners(n: usize, counter: &Arc<AtomicUsize>) -> Vec<(Uuid, Callback<usize>)> {
+        (0..n)
+            .map(|_| {
+                let counter = counter.clone();
+                let callback: Callback<usize> = Arc::new(move |_id, value: Arc<usize>| {
+                    counter.fetch_add(*value, Ordering::SeqCst);
+                });
+                (Uuid::new_v4(), callback)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_inline() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        Inline.deliver(counting_listeners(3, &counter), Arc::new(2));
+        assert_eq!(counter.load(Ordering::SeqCst), 6);
+    }
+
+    #[test]
+    fn test_thread_pool() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        ThreadPool.deliver(counting_listeners(3, &counter), Arc::new(2));
+        assert_eq!(counter.load(Ordering::SeqCst), 6);
+    }
+
+    #[test]
+    fn test_bounded_channel() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let delivery = BoundedChannel::new(1);
+        for _ in 0..4 {
+            delivery.deliver(counting_listeners(2, &counter), Arc::new(1));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(counter.load(Ordering::SeqCst), 8);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_tokio_task() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        TokioTask::current().deliver(counting_listeners(3, &counter), Arc::new(1));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+}
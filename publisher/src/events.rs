@@ -0,0 +1,195 @@
+//! Module events
+//!
+//! Lifecycle and error events of a pipeline, published apart from the readings of any sensor:
+//! sources connecting and disconnecting, recordings starting and stopping, samples dropped and
+//! filters diverging. Sinks such as a dashboard subscribe to an [`EventBus`] instead of parsing
+//! the logs.
+//!
+//! Stages emit to [`EventBus::global`] unless given another bus.
+//!
+//! ```
+//! use publisher::events::{EventBus, EventKind};
+//! use publisher::ChannelConfig;
+//!
+//! let events = EventBus::new();
+//! let receiver = events.subscribe(ChannelConfig::new());
+//!
+//! events.emit(EventKind::SourceConnected {
+//!     source: "phone".to_string(),
+//! });
+//! let event = receiver.recv().unwrap();
+//! assert!(matches!(event.kind, EventKind::SourceConnected { .. }));
+//! ```
+
+use std::sync::{Arc, OnceLock};
+use uuid::Uuid;
+
+use imu_common::traits::Notifiable;
+use imu_common::types::sensors::SensorType;
+use imu_common::types::Clock;
+
+use crate::channel::{self, ChannelConfig, Receiver};
+use crate::{Publishable, Publisher};
+
+/// Why samples were dropped before being published.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DropReason {
+    /// The samples had already been published.
+    Duplicated,
+    /// The samples were at/near the full scale range of the sensor.
+    Clipped,
+}
+
+/// What happened.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EventKind {
+    /// `source` is reachable and streaming, for the first time or after a disconnection.
+    SourceConnected {
+        source: String,
+    },
+    /// `source` stopped answering. The source tries to reconnect if `will_retry`, and gave up
+    /// otherwise.
+    SourceDisconnected {
+        source: String,
+        will_retry: bool,
+    },
+    /// `source` started recording samples, e.g. a phyphox experiment was started on the phone.
+    RecordingStarted {
+        source: String,
+    },
+    RecordingStopped {
+        source: String,
+    },
+    /// `count` samples of `sensor_type` received from `source` weren't published.
+    SamplesDropped {
+        source: String,
+        sensor_type: SensorType,
+        count: usize,
+        reason: DropReason,
+    },
+    /// The estimator of `filter` rejected its input. Only the first failure of a run of
+    /// failures is emitted.
+    FilterDiverged {
+        filter: String,
+        reason: String,
+    },
+}
+
+/// Event emitted to an [`EventBus`], timed with the wall clock.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub timestamp_secs: f64,
+    pub kind: EventKind,
+}
+
+impl Event {
+    /// Creates an event of `kind` happening now.
+    pub fn new(kind: EventKind) -> Self {
+        Self {
+            timestamp_secs: Clock::now().as_secs(),
+            kind,
+        }
+    }
+}
+
+/// Cloneable publisher of the events of a pipeline. Clones share the same listeners.
+#[derive(Clone)]
+pub struct EventBus {
+    publisher: Publisher<Event>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            publisher: Publisher::new(),
+        }
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the bus used by stages that aren't given one.
+    pub fn global() -> Self {
+        static GLOBAL: OnceLock<EventBus> = OnceLock::new();
+        GLOBAL.get_or_init(EventBus::new).clone()
+    }
+
+    /// Publishes an event of `kind` happening now.
+    pub fn emit(&self, kind: EventKind) {
+        self.publisher.notify_listeners(Arc::new(Event::new(kind)));
+    }
+
+    /// Subscribes to the events, delivered through a channel set by `config`.
+    pub fn subscribe(&self, config: ChannelConfig) -> Receiver<Event> {
+        let (mut listener, mut receiver) = channel::channel(config);
+        receiver.id = self.register_listener(&mut listener);
+        receiver
+    }
+}
+
+impl Publishable<Event> for EventBus {
+    fn register_listener(&self, listener: &mut dyn Notifiable<Event>) -> Uuid {
+        self.publisher.register_listener(listener)
+    }
+
+    fn unregister_listener(&self, listener_id: Uuid) {
+        self.publisher.unregister_listener(listener_id);
+    }
+
+    fn unregister_all(&self) {
+        self.publisher.unregister_all();
+    }
+
+    fn notify_listeners(&self, data: Arc<Event>) {
+        self.publisher.notify_listeners(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_emit_in_order() {
+        let events = EventBus::new();
+        let receiver = events.subscribe(ChannelConfig::new());
+        let source = "phone".to_string();
+
+        events.emit(EventKind::RecordingStarted {
+            source: source.clone(),
+        });
+        events.emit(EventKind::RecordingStopped {
+            source: source.clone(),
+        });
+        let first = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        let second = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(
+            first.kind,
+            EventKind::RecordingStarted {
+                source: source.clone()
+            }
+        );
+        assert_eq!(second.kind, EventKind::RecordingStopped { source });
+        assert!(first.timestamp_secs <= second.timestamp_secs);
+    }
+
+    #[test]
+    fn test_global_bus_is_shared() {
+        let receiver = EventBus::global().subscribe(ChannelConfig::new());
+        EventBus::global().emit(EventKind::FilterDiverged {
+            filter: "test_global_bus_is_shared".to_string(),
+            reason: "Non finite reading".to_string(),
+        });
+
+        // other tests may emit to the global bus as well
+        let received = std::iter::from_fn(|| receiver.recv_timeout(Duration::from_secs(1)))
+            .any(|event| {
+                matches!(&event.kind, EventKind::FilterDiverged { filter, .. } if filter == "test_global_bus_is_shared")
+            });
+        assert!(received);
+    }
+}
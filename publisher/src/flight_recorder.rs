@@ -0,0 +1,257 @@
+//! Module flight_recorder
+//!
+//! `FlightRecorder` keeps the last seconds of readings of a source, so sinks attached mid-run
+//! can be backfilled with recent history before they start receiving live data.
+
+use dashmap::DashMap;
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::{adapters, Listener};
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
+use imu_common::types::sensors::SensorType;
+
+type History<S> = HashMap<SensorType, (String, VecDeque<S>)>;
+
+/// Sink keeping a sliding window of the readings received from every attached sensor.
+#[derive(Clone)]
+pub struct FlightRecorder<T, S> {
+    window_secs: f64,
+    history: Arc<Mutex<History<S>>>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, S> FlightRecorder<T, S>
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+{
+    /// Creates a recorder keeping the last `window_secs` seconds of readings per sensor.
+    pub fn new(window_secs: f64) -> Self {
+        Self {
+            window_secs: window_secs.max(0.0),
+            history: Arc::new(Mutex::new(HashMap::new())),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the readings of `sensor_type` received in the last `secs` seconds, relative to
+    /// the most recent one.
+    pub fn get_history(&self, sensor_type: &SensorType, secs: f64) -> Option<T> {
+        let history = self.history.lock().unwrap();
+        Self::snapshot(&history, sensor_type, secs)
+    }
+
+    fn snapshot(history: &History<S>, sensor_type: &SensorType, secs: f64) -> Option<T> {
+        let (tag, samples) = history.get(sensor_type)?;
+        let latest = samples.back()?.get_timestamp_secs();
+        let recent = samples
+            .iter()
+            .filter(|s| s.get_timestamp_secs() >= latest - secs)
+            .cloned()
+            .collect();
+        Some(T::from_vec(tag, sensor_type.clone(), recent))
+    }
+
+    /// Attaches `sink` to `source`, first delivering the readings recorded in the last
+    /// `backfill_secs` seconds. Live readings received while the backfill is in progress are
+    /// queued and delivered afterwards, so the sink sees every reading in order.
+    pub fn attach_with_backfill<K>(
+        &self,
+        sink: &K,
+        source: &dyn IMUSource<T, S>,
+        sensor_cluster: &[SensorType],
+        backfill_secs: f64,
+    ) -> Result<Vec<Uuid>, ImuError>
+    where
+        K: IMUSink<T, S> + Clone + 'static,
+    {
+        adapters::negotiate(sink, source, sensor_cluster)?;
+
+        // Readings are held back until the backfill has been delivered.
+        let pending: Arc<DashMap<Uuid, Option<Vec<Arc<T>>>>> = Arc::new(DashMap::new());
+        let mut listener = Listener::new({
+            let sink = sink.clone();
+            let pending = pending.clone();
+            move |id, samples: Arc<T>| {
+                if let Some(mut entry) = pending.get_mut(&id) {
+                    if let Some(queue) = entry.as_mut() {
+                        queue.push(samples);
+                        return;
+                    }
+                }
+                sink.process_samples(id, samples);
+            }
+        });
+
+        // Holding the lock keeps the history consistent with the registration point.
+        let history = self.history.lock().unwrap();
+        let mut ids = Vec::with_capacity(sensor_cluster.len());
+        let mut backfill = Vec::with_capacity(sensor_cluster.len());
+        for sensor_type in sensor_cluster {
+            let id = source.register_listener(&mut listener, sensor_type)?;
+            pending.insert(id, Some(Vec::new()));
+            ids.push(id);
+            backfill.push((id, Self::snapshot(&history, sensor_type, backfill_secs)));
+        }
+        drop(history);
+
+        for (id, readings) in backfill {
+            if let Some(readings) = readings {
+                sink.process_samples(id, Arc::new(readings));
+            }
+            // Deliver queued readings until the queue is found empty, then switch to live delivery.
+            loop {
+                let queued = match pending.get_mut(&id) {
+                    Some(mut entry) => match entry.take() {
+                        Some(queued) if !queued.is_empty() => {
+                            *entry = Some(Vec::new());
+                            queued
+                        }
+                        _ => break,
+                    },
+                    None => break,
+                };
+                for samples in queued {
+                    sink.process_samples(id, samples);
+                }
+            }
+        }
+        Ok(ids)
+    }
+}
+
+impl<T, S> IMUSink<T, S> for FlightRecorder<T, S>
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, S>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        let mut history = self.history.lock().unwrap();
+        let (_, buffer) = history
+            .entry(samples.get_sensor_type())
+            .or_insert_with(|| (samples.get_sensor_tag().to_string(), VecDeque::new()));
+        buffer.extend(samples.get_samples());
+
+        if let Some(latest) = buffer.back().map(|s| s.get_timestamp_secs()) {
+            while buffer
+                .front()
+                .is_some_and(|s| s.get_timestamp_secs() < latest - self.window_secs)
+            {
+                buffer.pop_front();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PublisherManager;
+    use imu_common::traits::Notifiable;
+    use imu_common::types::sensors::SensorReadings;
+    use imu_common::types::timed::Sample3D;
+
+    struct TestSource {
+        publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+        sensors: Vec<SensorType>,
+    }
+
+    impl IMUSource<SensorReadings<Sample3D>, Sample3D> for TestSource {
+        fn get_tag(&self) -> &str {
+            "test"
+        }
+        fn get_available_sensors(&self) -> Vec<SensorType> {
+            self.sensors.clone()
+        }
+        fn unregister_listener(&self, id: Uuid) {
+            let _ = self.publishers.remove_listener(id);
+        }
+        fn register_listener(
+            &self,
+            listener: &mut dyn Notifiable<SensorReadings<Sample3D>>,
+            sensor_type: &SensorType,
+        ) -> Result<Uuid, ImuError> {
+            self.publishers.add_listener(listener, sensor_type)
+        }
+        fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<Sample3D>>) {
+            self.publishers.notify_listeners(sensor_type, data);
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct TestSink {
+        timestamps: Arc<Mutex<Vec<f64>>>,
+    }
+
+    impl IMUSink<SensorReadings<Sample3D>, Sample3D> for TestSink {
+        fn attach_listeners(
+            &self,
+            source: &dyn IMUSource<SensorReadings<Sample3D>, Sample3D>,
+            sensor_cluster: &[SensorType],
+        ) -> Result<Vec<Uuid>, ImuError> {
+            adapters::attach_sync(self, source, sensor_cluster)
+        }
+
+        fn process_samples(&self, _listener_id: Uuid, samples: Arc<SensorReadings<Sample3D>>) {
+            let mut timestamps = self.timestamps.lock().unwrap();
+            timestamps.extend(samples.iter().map(|s| s.get_timestamp_secs()));
+        }
+    }
+
+    fn publish(source: &TestSource, sensor_type: &SensorType, from: usize, to: usize) {
+        let samples = (from..to)
+            .map(|i| Sample3D::new(i as f64 * 0.1, [0.0, 0.0, 9.8]))
+            .collect();
+        source.notify_listeners(
+            sensor_type.clone(),
+            Arc::new(SensorReadings::from_vec(
+                "test",
+                sensor_type.clone(),
+                samples,
+            )),
+        );
+    }
+
+    #[test]
+    fn test_backfill_late_sink() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let source = TestSource {
+            publishers: PublisherManager::new(std::slice::from_ref(&acc)),
+            sensors: vec![acc.clone()],
+        };
+        let recorder = FlightRecorder::new(0.95);
+        recorder
+            .attach_listeners(&source, std::slice::from_ref(&acc))
+            .unwrap();
+
+        publish(&source, &acc, 0, 30);
+        // Only the last second is kept
+        let history = recorder.get_history(&acc, 10.0).unwrap();
+        assert_eq!(history.get_samples().len(), 10);
+
+        let sink = TestSink::default();
+        recorder
+            .attach_with_backfill(&sink, &source, std::slice::from_ref(&acc), 0.45)
+            .unwrap();
+        publish(&source, &acc, 30, 32);
+
+        let timestamps = sink.timestamps.lock().unwrap().clone();
+        let expected: Vec<f64> = (25..32).map(|i| i as f64 * 0.1).collect();
+        assert_eq!(timestamps.len(), expected.len());
+        for (t, e) in timestamps.iter().zip(expected) {
+            assert!((t - e).abs() < 1e-9);
+        }
+    }
+}
@@ -1,12 +1,43 @@
+pub mod adapters;
+pub mod async_listener;
+pub mod channel;
+mod claims;
+pub mod delivery;
+pub mod events;
+pub mod flight_recorder;
+pub mod lifetime;
 pub mod listener;
 pub mod macros;
 pub mod publisher;
 pub mod publisher_manager;
+mod publisher_set;
+pub mod shutdown;
+#[cfg(feature = "soak")]
+pub mod soak;
+pub mod subscription;
 
 #[doc(inline)]
 pub use publisher::{Publishable, Publisher};
 #[doc(inline)]
 pub use publisher_manager::PublisherManager;
 
+#[doc(inline)]
+#[allow(deprecated)]
+pub use async_listener::AsyncListener;
+#[doc(inline)]
+pub use channel::{ChannelConfig, Overflow, Receiver, Subscribe};
+#[doc(inline)]
+pub use events::{Event, EventBus, EventKind};
+#[doc(inline)]
+pub use flight_recorder::FlightRecorder;
+#[doc(inline)]
+pub use lifetime::DropGuard;
 #[doc(inline)]
 pub use listener::Listener;
+#[doc(inline)]
+pub use shutdown::ShutdownToken;
+#[cfg(feature = "soak")]
+#[doc(inline)]
+pub use soak::{SoakConfig, SoakMonitor};
+#[doc(inline)]
+pub use subscription::SubscriptionGuard;
@@ -0,0 +1,99 @@
+//! Module lifetime
+//!
+//! Helpers to stop the worker threads and tasks of a pipeline stage once the stage is dropped,
+//! so pipelines built and dropped repeatedly, e.g. in tests or GUIs, don't leak them.
+//!
+//! Workers must not keep the stage alive themselves: stages either hand their workers a
+//! [`DropGuard`]-less clone, or the workers watch the handle returned to the caller with
+//! [`orphaned`].
+
+#[cfg(feature = "tokio")]
+use std::sync::Arc;
+#[cfg(feature = "tokio")]
+use std::time::Duration;
+
+/// Period at which [`orphaned`] checks the handle.
+#[cfg(feature = "tokio")]
+const ORPHANED_POLL_MILLIS: u64 = 100;
+
+/// Runs a closure when dropped. Stages keep it behind an `Arc` shared by the clones handed to
+/// callers, so the closure runs once the last of them is dropped.
+pub struct DropGuard {
+    on_drop: Option<Box<dyn FnOnce() + Send + Sync>>,
+}
+
+impl DropGuard {
+    pub fn new<F>(on_drop: F) -> Self
+    where
+        F: FnOnce() + Send + Sync + 'static,
+    {
+        Self {
+            on_drop: Some(Box::new(on_drop)),
+        }
+    }
+
+    /// Drops the guard without running the closure.
+    pub fn disarm(mut self) {
+        self.on_drop = None;
+    }
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        if let Some(on_drop) = self.on_drop.take() {
+            on_drop();
+        }
+    }
+}
+
+/// Resolves once `handle` is the only reference to its value left, i.e. once every other owner
+/// dropped it. Used by background tasks holding a handle also returned to the caller.
+#[cfg(feature = "tokio")]
+pub async fn orphaned<T>(handle: &Arc<T>) {
+    let mut interval = tokio::time::interval(Duration::from_millis(ORPHANED_POLL_MILLIS));
+    while Arc::strong_count(handle) > 1 {
+        interval.tick().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_drop_guard() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let guard = Arc::new(DropGuard::new({
+            let calls = calls.clone();
+            move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }
+        }));
+        let clone = guard.clone();
+        drop(guard);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        drop(clone);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_orphaned() {
+        let handle = Arc::new(());
+        let task = tokio::spawn({
+            let handle = handle.clone();
+            async move { orphaned(&handle).await }
+        });
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(!task.is_finished());
+
+        drop(handle);
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+#[cfg(feature = "tokio")]
+mod runtime;
+
+use std::sync::Arc;
+use uuid::Uuid;
+
+use imu_common::traits::Notifiable;
+use imu_common::types::{Callback, Liveness};
+
+/// Marker of callbacks that complete before returning.
+pub enum SyncCallback {}
+
+/// Marker of callbacks returning a future. Futures are awaited on the tokio runtime, so they are
+/// only accepted with the `tokio` feature.
+pub enum AsyncCallback {}
+
+/// Output of a listener callback: `()` for synchronous callbacks, or a future for asynchronous
+/// ones. The marker `M` lets [`Listener::new`] accept both kinds of closures.
+pub trait CallbackOutput<M>: Send + 'static {
+    /// Returns the callback of a listener calling `callback`, which returns None when there is
+    /// nothing to run for a notification.
+    #[doc(hidden)]
+    fn into_callback<T, F>(callback: F) -> Callback<T>
+    where
+        T: Send + Sync + 'static,
+        F: Fn(Uuid, Arc<T>) -> Option<Self> + Send + Sync + 'static,
+        Self: Sized;
+}
+
+impl CallbackOutput<SyncCallback> for () {
+    fn into_callback<T, F>(callback: F) -> Callback<T>
+    where
+        T: Send + Sync + 'static,
+        F: Fn(Uuid, Arc<T>) -> Option<Self> + Send + Sync + 'static,
+    {
+        Arc::new(move |id: Uuid, data: Arc<T>| {
+            callback(id, data);
+        })
+    }
+}
+
+/// Listener of a publisher, with either a synchronous or an asynchronous callback.
+///
+/// Synchronous callbacks run on the thread notifying the listener. Asynchronous callbacks are
+/// queued to a task on the tokio runtime, which awaits them one at a time in the order they
+/// were published, so the publisher never waits for the listener to complete. They need the
+/// `tokio` feature, as do the constructors taking a runtime `Handle`.
+#[derive(Clone)]
+pub struct Listener<T> {
+    callback: Callback<T>,
+    id: Option<Uuid>,
+    liveness: Option<Liveness>,
+}
+
+impl<T> Listener<T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Creates a listener calling `callback`, a closure returning either `()` or a future.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `callback` returns a future and the listener is created outside of a tokio
+    /// runtime.
+    pub fn new<F, R, M>(callback: F) -> Self
+    where
+        F: Fn(Uuid, Arc<T>) -> R + Send + Sync + 'static,
+        R: CallbackOutput<M>,
+    {
+        Listener {
+            callback: R::into_callback(move |id, data| Some(callback(id, data))),
+            id: None,
+            liveness: None,
+        }
+    }
+
+    /// Creates a listener that is unregistered from its publishers once `liveness` returns false.
+    pub(crate) fn with_liveness<F, L>(callback: F, liveness: L) -> Self
+    where
+        F: Fn(Uuid, Arc<T>) + Send + Sync + 'static,
+        L: Fn() -> bool + Send + Sync + 'static,
+    {
+        Listener {
+            liveness: Some(Arc::new(liveness)),
+            ..Self::new(callback)
+        }
+    }
+
+    /// Creates a listener that only holds `handler` weakly. `callback` is called with the handler
+    /// while it is alive, and may be synchronous or asynchronous as in [`Listener::new`]. Once
+    /// the handler is dropped, the listener is unregistered from its publishers, so a listener
+    /// capturing its own sink doesn't keep the sink alive forever.
+    pub fn weak<H, F, R, M>(handler: &Arc<H>, callback: F) -> Self
+    where
+        H: Send + Sync + 'static,
+        F: Fn(Arc<H>, Uuid, Arc<T>) -> R + Send + Sync + 'static,
+        R: CallbackOutput<M>,
+    {
+        let weak_handler = Arc::downgrade(handler);
+        let callback = R::into_callback(move |id, data| {
+            weak_handler
+                .upgrade()
+                .map(|handler| callback(handler, id, data))
+        });
+        let weak_handler = Arc::downgrade(handler);
+        let liveness = Arc::new(move || weak_handler.strong_count() > 0);
+
+        Listener {
+            callback,
+            id: None,
+            liveness: Some(liveness),
+        }
+    }
+}
+
+impl<T> Notifiable<T> for Listener<T> {
+    fn get_callback(&self) -> Callback<T> {
+        self.callback.clone()
+    }
+
+    fn set_id(&mut self, id: Uuid) {
+        self.id = Some(id);
+    }
+
+    fn get_liveness(&self) -> Option<Liveness> {
+        self.liveness.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::listener;
+    use std::sync::Mutex;
+
+    struct TestHandler {
+        data: Arc<Mutex<Vec<i32>>>,
+    }
+
+    impl TestHandler {
+        fn new() -> Self {
+            Self {
+                data: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        fn handle(&self, _id: Uuid, value: Arc<Vec<i32>>) {
+            let mut data = self.data.lock().unwrap();
+            *data = (*value).clone();
+            assert_eq!((*data)[0], 400);
+        }
+    }
+
+    #[test]
+    fn test_new_listener() {
+        let listener = Listener::new({
+            move |_id: Uuid, value: Arc<i32>| {
+                let data = *value;
+                assert_eq!(data, 42);
+            }
+        });
+
+        let callback = listener.get_callback();
+        callback(Uuid::new_v4(), Arc::new(42));
+    }
+
+    #[test]
+    fn test_listener_with_method() {
+        let handler = Arc::new(TestHandler::new());
+
+        let listener = Listener::new({
+            move |id: Uuid, value: Arc<Vec<i32>>| {
+                let handler = handler.clone();
+                handler.handle(id, value);
+            }
+        });
+
+        let callback = listener.get_callback();
+        callback(Uuid::new_v4(), Arc::new(vec![400]));
+    }
+
+    #[test]
+    fn test_listener_with_macro() {
+        let handler = Arc::new(TestHandler::new());
+
+        let listener = listener!(handler.handle);
+
+        let callback = listener.get_callback();
+        callback(Uuid::new_v4(), Arc::new(vec![400]));
+    }
+
+    #[test]
+    fn test_weak_listener() {
+        let handler = Arc::new(TestHandler::new());
+
+        let listener = listener!(weak handler.handle);
+        let liveness = listener.get_liveness().unwrap();
+
+        assert_eq!(Arc::strong_count(&handler), 1);
+        assert!(liveness());
+        let data = handler.data.clone();
+        listener.get_callback()(Uuid::new_v4(), Arc::new(vec![400]));
+        assert_eq!(*data.lock().unwrap(), vec![400]);
+
+        drop(handler);
+        assert!(!liveness());
+        listener.get_callback()(Uuid::new_v4(), Arc::new(vec![500]));
+        assert_eq!(*data.lock().unwrap(), vec![400]);
+    }
+}
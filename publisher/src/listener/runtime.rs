@@ -0,0 +1,211 @@
+//! Asynchronous listeners, whose callbacks are awaited on the tokio runtime.
+
+use std::future::Future;
+use std::sync::Arc;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use imu_common::types::Callback;
+
+use super::{AsyncCallback, CallbackOutput, Listener};
+
+impl<Fut> CallbackOutput<AsyncCallback> for Fut
+where
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    fn into_callback<T, F>(callback: F) -> Callback<T>
+    where
+        T: Send + Sync + 'static,
+        F: Fn(Uuid, Arc<T>) -> Option<Self> + Send + Sync + 'static,
+    {
+        ordered(Handle::current(), callback)
+    }
+}
+
+/// Queues notifications to a task on `handle`, which awaits the futures returned by `callback`
+/// one at a time, in the order they were published.
+fn ordered<T, F, Fut>(handle: Handle, callback: F) -> Callback<T>
+where
+    T: Send + Sync + 'static,
+    F: Fn(Uuid, Arc<T>) -> Option<Fut> + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (sender, mut receiver) = mpsc::unbounded_channel::<(Uuid, Arc<T>)>();
+    handle.spawn(async move {
+        while let Some((id, data)) = receiver.recv().await {
+            if let Some(task) = callback(id, data) {
+                task.await;
+            }
+        }
+    });
+    Arc::new(move |id: Uuid, data: Arc<T>| {
+        if sender.send((id, data)).is_err() {
+            log::error!("Listener task is not running");
+        }
+    })
+}
+
+impl<T> Listener<T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Creates a listener whose asynchronous callback runs on `handle`. The task ends once the
+    /// listener is unregistered from every publisher and dropped.
+    pub fn with_handle<F, Fut>(handle: Handle, callback: F) -> Self
+    where
+        F: Fn(Uuid, Arc<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Listener {
+            callback: ordered(handle, move |id, data| Some(callback(id, data))),
+            id: None,
+            liveness: None,
+        }
+    }
+
+    /// Creates a listener that spawns every notification as a new task on `handle`, without
+    /// waiting for the previous ones to complete.
+    pub fn unordered<F, Fut>(handle: Handle, callback: F) -> Self
+    where
+        F: Fn(Uuid, Arc<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let callback = Arc::new(move |id: Uuid, data: Arc<T>| {
+            handle.spawn(callback(id, data));
+        });
+
+        Listener {
+            callback,
+            id: None,
+            liveness: None,
+        }
+    }
+
+    /// Creates a listener that runs a blocking callback on the runtime blocking pool.
+    pub fn from_blocking<F>(handle: Handle, callback: F) -> Self
+    where
+        F: Fn(Uuid, Arc<T>) + Send + Sync + 'static,
+    {
+        let callback = Arc::new(callback);
+        let blocking_handle = handle.clone();
+        Self::with_handle(handle, move |id, data| {
+            let callback = Arc::clone(&callback);
+            let task = blocking_handle.spawn_blocking(move || callback(id, data));
+            async move {
+                if let Err(e) = task.await {
+                    log::error!("Listener task failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{listener, Publishable, Publisher};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    struct TestHandler {
+        data: Arc<Mutex<Vec<i32>>>,
+    }
+
+    impl TestHandler {
+        async fn handle_async(&self, _id: Uuid, value: Arc<Vec<i32>>) {
+            tokio::task::yield_now().await;
+            self.data.lock().unwrap().extend(value.iter());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_listener() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut listener = Listener::new(move |_id: Uuid, value: Arc<i32>| {
+            let tx = tx.clone();
+            async move {
+                tx.send(*value).unwrap();
+            }
+        });
+        let publisher = Publisher::new();
+        publisher.register_listener(&mut listener);
+
+        publisher.notify_listeners(Arc::new(42));
+
+        let value = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap();
+        assert_eq!(value, Some(42));
+    }
+
+    #[tokio::test]
+    async fn test_async_listener_with_macro() {
+        let handler = Arc::new(TestHandler {
+            data: Arc::new(Mutex::new(Vec::new())),
+        });
+        let mut listener = listener!(handler.handle_async);
+        let publisher = Publisher::new();
+        publisher.register_listener(&mut listener);
+
+        for value in 0..3 {
+            publisher.notify_listeners(Arc::new(vec![value]));
+        }
+
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while handler.data.lock().unwrap().len() < 3 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(*handler.data.lock().unwrap(), vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_listener() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut listener =
+            Listener::from_blocking(Handle::current(), move |id: Uuid, value: Arc<i32>| {
+                tx.send((id, *value)).unwrap();
+            });
+        let publisher = Publisher::new();
+        let id = publisher.register_listener(&mut listener);
+
+        publisher.notify_listeners(Arc::new(7));
+
+        let value = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap();
+        assert_eq!(value, Some((id, 7)));
+    }
+
+    #[tokio::test]
+    async fn test_ordered_listener() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        // earlier notifications take longer, and would complete last if run concurrently
+        let mut listener = Listener::new(move |_id: Uuid, value: Arc<u64>| {
+            let tx = tx.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(10 * (5 - *value))).await;
+                tx.send(*value).unwrap();
+            }
+        });
+        let publisher = Publisher::new();
+        publisher.register_listener(&mut listener);
+        drop(listener);
+
+        for value in 0..5 {
+            publisher.notify_listeners(Arc::new(value));
+        }
+
+        let mut received = Vec::new();
+        while received.len() < 5 {
+            let value = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+                .await
+                .unwrap();
+            received.push(value.unwrap());
+        }
+        assert_eq!(received, vec![0, 1, 2, 3, 4]);
+    }
+}
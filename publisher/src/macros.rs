@@ -1,12 +1,109 @@
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+
+use crate::listener::{AsyncCallback, SyncCallback};
+
 #[macro_export]
-/// A macro to create a new `Listener` with a cloned handler and an asynchronous method call.
+/// A macro to create a new `Listener` calling a method of a cloned handler.
+///
+/// The method may either be synchronous or `async`: synchronous methods are called when the
+/// listener is notified, and asynchronous ones are awaited by the listener task (see
+/// [`Listener::new`](crate::Listener::new)). A sink can then be attached with the same macro to
+/// publishers notifying from a thread or from the tokio runtime.
+///
+/// `listener!(weak handler.method)` only holds the `Arc` handler weakly instead, and the listener
+/// is unregistered once the handler is dropped (see [`Listener::weak`](crate::Listener::weak)).
 macro_rules! listener {
+    (weak $handler:ident.$method:ident) => {
+        $crate::Listener::weak(&$handler, |handler, id, value| {
+            $crate::listener!(@call handler.$method(id, value))
+        })
+    };
     ($handler:ident.$method:ident) => {
-        Listener::new({
+        $crate::Listener::new({
             let handler = $handler.clone(); // Clone the handler
-            move |id, value| {
-                handler.$method(id, value); // Call the method on the handler
-            }
+            move |id, value| $crate::listener!(@call handler.$method(id, value))
         })
     };
+    (@call $handler:ident.$method:ident($id:ident, $value:ident)) => {
+        // synchronous methods are called in place. Futures borrow the handler, so asynchronous
+        // methods are called from a future owning a clone of it instead
+        $crate::macros::dispatch(
+            || $handler.$method($id, ::std::sync::Arc::clone(&$value)),
+            || {
+                let (handler, value) = ($handler.clone(), ::std::sync::Arc::clone(&$value));
+                async move { $crate::macros::complete(handler.$method($id, value)).await }
+            },
+        )
+    };
+}
+
+/// Output of a method called by `listener!`, `()` or a future.
+#[doc(hidden)]
+pub trait MethodOutput<M>: Sized {
+    type Output;
+    type Future: Future<Output = ()>;
+
+    fn dispatch<C, D, Fut>(call: C, deferred: D) -> Self::Output
+    where
+        C: FnOnce() -> Self,
+        D: FnOnce() -> Fut,
+        Fut: Future<Output = ()> + Send + 'static;
+
+    fn into_future(self) -> Self::Future;
+}
+
+impl MethodOutput<SyncCallback> for () {
+    type Output = ();
+    type Future = Ready<()>;
+
+    fn dispatch<C, D, Fut>(call: C, _deferred: D)
+    where
+        C: FnOnce(),
+        D: FnOnce() -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        call()
+    }
+
+    fn into_future(self) -> Self::Future {
+        ready(())
+    }
+}
+
+impl<F> MethodOutput<AsyncCallback> for F
+where
+    F: Future<Output = ()>,
+{
+    type Output = Pin<Box<dyn Future<Output = ()> + Send>>;
+    type Future = F;
+
+    fn dispatch<C, D, Fut>(_call: C, deferred: D) -> Self::Output
+    where
+        C: FnOnce() -> Self,
+        D: FnOnce() -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Box::pin(deferred())
+    }
+
+    fn into_future(self) -> Self::Future {
+        self
+    }
+}
+
+#[doc(hidden)]
+pub fn dispatch<M, R, C, D, Fut>(call: C, deferred: D) -> R::Output
+where
+    R: MethodOutput<M>,
+    C: FnOnce() -> R,
+    D: FnOnce() -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    R::dispatch(call, deferred)
+}
+
+#[doc(hidden)]
+pub fn complete<M, R: MethodOutput<M>>(output: R) -> R::Future {
+    output.into_future()
 }
@@ -1,10 +1,12 @@
 use dashmap::DashMap;
-use rayon::prelude::*;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use imu_common::traits::Notifiable;
-use imu_common::types::Callback;
+use imu_common::types::{Callback, Liveness};
+
+use crate::delivery::{DeliveryOrder, DeliveryStrategy, Mailbox, ThreadPool};
+use crate::SubscriptionGuard;
 
 pub trait Publishable<T> {
     fn register_listener(&self, listener: &mut dyn Notifiable<T>) -> Uuid;
@@ -13,28 +15,112 @@ pub trait Publishable<T> {
     fn notify_listeners(&self, data: Arc<T>);
 }
 
+/// Keeps a set of listeners and notifies them through a [`DeliveryStrategy`].
+///
+/// `Publisher::new` uses the [`ThreadPool`] strategy. Use `Publisher::with_delivery` to choose
+/// another one.
+///
+/// Every listener receives the notifications in the order they were published, unless the
+/// publisher is set to [`DeliveryOrder::Unordered`] with `with_order`.
+///
+/// Listeners with a liveness check, such as [`Listener::weak`](crate::Listener::weak), are
+/// unregistered on the first notification after their check fails.
 #[derive(Clone, Default)]
-pub struct Publisher<T> {
-    listeners: Arc<DashMap<Uuid, Callback<T>>>,
+pub struct Publisher<T, D = ThreadPool> {
+    listeners: Arc<DashMap<Uuid, Registration<T>>>,
+    delivery: D,
+    order: DeliveryOrder,
+}
+
+struct Registration<T> {
+    callback: Callback<T>,
+    liveness: Option<Liveness>,
+    mailbox: Arc<Mailbox<T>>,
+}
+
+impl<T> Registration<T> {
+    fn is_alive(&self) -> bool {
+        self.liveness.as_ref().is_none_or(|liveness| liveness())
+    }
 }
 
 impl<T> Publisher<T> {
     pub fn new() -> Self {
+        Self::with_delivery(ThreadPool)
+    }
+}
+
+impl<T, D> Publisher<T, D> {
+    pub fn with_delivery(delivery: D) -> Self {
         Self {
             listeners: Arc::new(DashMap::new()),
+            delivery,
+            order: DeliveryOrder::default(),
+        }
+    }
+
+    /// Sets the order in which every listener receives the notifications.
+    pub fn with_order(mut self, order: DeliveryOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn get_order(&self) -> DeliveryOrder {
+        self.order
+    }
+
+    pub fn contains_listener(&self, listener_id: Uuid) -> bool {
+        self.listeners.contains_key(&listener_id)
+    }
+
+    /// Unregisters the listeners whose liveness check fails, without waiting for the next
+    /// notification, and returns their ids.
+    pub fn remove_dead_listeners(&self) -> Vec<Uuid> {
+        let dead_listeners: Vec<Uuid> = self
+            .listeners
+            .iter()
+            .filter(|entry| !entry.is_alive())
+            .map(|entry| *entry.key())
+            .collect();
+        for listener_id in dead_listeners.iter() {
+            self.listeners.remove(listener_id);
         }
+        dead_listeners
+    }
+}
+
+impl<T, D> Publisher<T, D>
+where
+    T: Send + Sync + 'static,
+    D: DeliveryStrategy<T>,
+{
+    /// Registers `listener`, returning a guard that unregisters it once dropped. The guard
+    /// doesn't keep the publisher alive.
+    pub fn register_guarded(&self, listener: &mut dyn Notifiable<T>) -> SubscriptionGuard {
+        let listener_id = self.register_listener(listener);
+        let listeners = Arc::downgrade(&self.listeners);
+        SubscriptionGuard::new(vec![listener_id], move |listener_id| {
+            if let Some(listeners) = listeners.upgrade() {
+                listeners.remove(&listener_id);
+            }
+        })
     }
 }
 
-impl<T> Publishable<T> for Publisher<T>
+impl<T, D> Publishable<T> for Publisher<T, D>
 where
     T: Send + Sync + 'static,
+    D: DeliveryStrategy<T>,
 {
     fn register_listener(&self, listener: &mut dyn Notifiable<T>) -> Uuid {
-        let callback = listener.get_callback();
+        let registration = Registration {
+            callback: listener.get_callback(),
+            liveness: listener.get_liveness(),
+            mailbox: Arc::new(Mailbox::default()),
+        };
         let listener_id = Uuid::new_v4();
         listener.set_id(listener_id);
-        self.listeners.insert(listener_id, callback);
+        self.listeners.insert(listener_id, registration);
         listener_id
     }
     fn unregister_all(&self) {
@@ -46,22 +132,34 @@ where
     }
 
     fn notify_listeners(&self, data: Arc<T>) {
-        let listeners: Vec<(Uuid, Callback<T>)> = self
-            .listeners
-            .iter()
-            .map(|entry| (*entry.key(), entry.value().clone()))
-            .collect();
+        let mut listeners: Vec<(Uuid, Callback<T>)> = Vec::with_capacity(self.listeners.len());
+        let mut dead_listeners = Vec::new();
+        for entry in self.listeners.iter() {
+            if !entry.is_alive() {
+                dead_listeners.push(*entry.key());
+            } else if self.order == DeliveryOrder::Fifo {
+                // queued here, in the order of the notifications, whenever the callback runs
+                entry.mailbox.push(data.clone());
+                let callback = entry.callback.clone();
+                let mailbox = entry.mailbox.clone();
+                let drain: Callback<T> = Arc::new(move |id, _data| mailbox.drain(id, &callback));
+                listeners.push((*entry.key(), drain));
+            } else {
+                listeners.push((*entry.key(), entry.callback.clone()));
+            }
+        }
+        for listener_id in dead_listeners {
+            self.listeners.remove(&listener_id);
+        }
 
-        listeners.into_par_iter().for_each(|(id, callback)| {
-            let data = data.clone();
-            callback(id, data);
-        });
+        self.delivery.deliver(listeners, data);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::delivery::Inline;
     use crate::{listener, listener::Listener};
     use std::sync::Mutex;
 
@@ -115,6 +213,38 @@ mod tests {
         assert_eq!(*handler.data.lock().unwrap(), 42);
     }
 
+    #[test]
+    fn test_inline_delivery() {
+        let publisher = Publisher::with_delivery(Inline);
+        let handler = Arc::new(TestHandler::new());
+
+        let mut listener = listener!(handler.handle);
+
+        publisher.register_listener(&mut listener);
+        publisher.notify_listeners(Arc::new(42));
+
+        assert_eq!(*handler.data.lock().unwrap(), 42);
+    }
+
+    #[test]
+    fn test_unregister_dropped_weak_listener() {
+        let publisher = Publisher::with_delivery(Inline);
+        let handler = Arc::new(TestHandler::new());
+        let data = handler.data.clone();
+
+        let mut listener = listener!(weak handler.handle);
+        publisher.register_listener(&mut listener);
+        publisher.notify_listeners(Arc::new(42));
+        assert_eq!(*data.lock().unwrap(), 42);
+        assert_eq!(Arc::strong_count(&handler), 1);
+
+        drop(handler);
+        publisher.notify_listeners(Arc::new(100));
+
+        assert_eq!(*data.lock().unwrap(), 42);
+        assert!(publisher.listeners.is_empty());
+    }
+
     #[test]
     fn test_unregister_listener() {
         let publisher = Publisher::new();
@@ -135,4 +265,63 @@ mod tests {
         // Should remain unchanged since listener was removed
         assert_eq!(*handler.data.lock().unwrap(), 0);
     }
+
+    #[test]
+    fn test_register_guarded() {
+        let publisher = Publisher::with_delivery(Inline);
+        let handler = Arc::new(TestHandler::new());
+
+        let mut listener = listener!(handler.handle);
+        let guard = publisher.register_guarded(&mut listener);
+        publisher.notify_listeners(Arc::new(42));
+        assert_eq!(*handler.data.lock().unwrap(), 42);
+
+        drop(guard);
+        publisher.notify_listeners(Arc::new(100));
+        assert_eq!(*handler.data.lock().unwrap(), 42);
+        assert!(publisher.listeners.is_empty());
+    }
+
+    #[test]
+    fn test_remove_dead_listeners() {
+        let publisher: Publisher<i32> = Publisher::new();
+        let handler = Arc::new(TestHandler::new());
+
+        let mut listener = listener!(weak handler.handle);
+        let id = publisher.register_listener(&mut listener);
+        assert!(publisher.remove_dead_listeners().is_empty());
+
+        drop(handler);
+        assert_eq!(publisher.remove_dead_listeners(), vec![id]);
+        assert!(!publisher.contains_listener(id));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_fifo_order() {
+        let publisher = Publisher::with_delivery(crate::delivery::TokioTask::current());
+        assert_eq!(publisher.get_order(), DeliveryOrder::Fifo);
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut listener = Listener::new({
+            let received = received.clone();
+            move |_id: Uuid, value: Arc<u64>| {
+                // tasks spawned later run while this one sleeps
+                std::thread::sleep(std::time::Duration::from_micros(*value % 7 * 100));
+                received.lock().unwrap().push(*value);
+            }
+        });
+        publisher.register_listener(&mut listener);
+
+        for value in 0..200 {
+            publisher.notify_listeners(Arc::new(value));
+        }
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while received.lock().unwrap().len() < 200 {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(*received.lock().unwrap(), (0..200).collect::<Vec<u64>>());
+    }
 }
@@ -1,103 +1,207 @@
 use dashmap::DashMap;
-use std::cmp::Eq;
 use std::hash::Hash;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::Publishable;
+use crate::channel::{self, ChannelConfig, Receiver};
+use crate::claims::{Claim, Claims};
+use crate::delivery::{DeliveryOrder, DeliveryStrategy, ThreadPool};
+use crate::publisher_set::PublisherSet;
+use crate::{Publishable, SubscriptionGuard};
 
 use super::publisher::Publisher;
+use imu_common::errors::ImuError;
 use imu_common::traits::publisher::Notifiable;
 
 /// This module defines the `PublisherManager` struct, which manages publishers and their listeners.
 /// It provides functionality to add and remove publishers, as well as to add and remove listeners
-/// to/from specific publishers. The `PublisherManager` ensures thread-safe access to its internal
-/// data structures using `tokio::sync::RwLock`.
+/// to/from specific publishers. The `PublisherManager` can be cloned and shared across threads,
+/// with all the clones seeing the same publishers and listeners.
 ///
 /// # Example
 ///
 /// ```rust
 /// use uuid::Uuid;
 /// use publisher::PublisherManager;
-/// use publisher::{listener, Listener};
+/// use publisher::listener;
 /// use imu_common::types::sensors::SensorType;
 /// use imu_common::types::timed::Sample3D;
 /// use std::sync::Arc;
 ///
+/// // Test struct
+/// #[derive(Debug, Clone)]
+/// struct TestBuffer;
 ///
-///     // Test struct
-///     #[derive(Debug, Clone)]
-///     struct TestBuffer;
-///
-///     impl TestBuffer {
-///         fn new() -> Self {
-///             Self
-///         }
+/// impl TestBuffer {
+///     fn new() -> Self {
+///         Self
+///     }
 ///
-///         async fn handle(&self, _id: Uuid, samples: Arc<Vec<Sample3D>>) {
-///             println!("Samples: {:?}", samples);
-///         }
+///     fn handle(&self, _id: Uuid, samples: Arc<Vec<Sample3D>>) {
+///         println!("Samples: {:?}", samples);
 ///     }
-///     
-///     // Create PublisherManager
-///     let mut manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
-///     // Add new publisher for Accelerometers
-///     let acc_id = Uuid::new_v4();
-///     manager.add_publisher(SensorType::Accelerometer(acc_id));
+/// }
 ///
-///     // Prepare to create listener
-///     let test_buffer = Arc::new(TestBuffer::new());
-///     let mut listener = listener!(test_buffer.handle);
+/// // Create PublisherManager
+/// let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
+/// // Add new publisher for Accelerometers
+/// let acc_id = Uuid::new_v4();
+/// manager.add_publisher(SensorType::Accelerometer(acc_id)).unwrap();
 ///
-///     // add listener to existing Accelerometer publisher
-///     let id = manager.add_listener(&mut listener, &SensorType::Accelerometer(acc_id)).unwrap();
+/// // Prepare to create listener
+/// let test_buffer = Arc::new(TestBuffer::new());
+/// let mut listener = listener!(test_buffer.handle);
 ///
-///     // remove listener from Accelerometer publisher
-///     manager.remove_listener(id).unwrap();
-///  
+/// // add listener to existing Accelerometer publisher
+/// let id = manager.add_listener(&mut listener, &SensorType::Accelerometer(acc_id)).unwrap();
+///
+/// // remove listener from Accelerometer publisher
+/// manager.remove_listener(id).unwrap();
 /// ```
+///
+/// # Storage
+///
+/// Publishers are kept in a dense vector and found by a linear scan, which is faster than a hash
+/// lookup for the small sensor clusters of a source (see the `notify` benchmark). Managers of
+/// large publisher sets that change at runtime can switch to hash lookups with `into_dynamic`.
+///
+/// # Collisions
+///
+/// Managers created with `try_new` claim their publisher types, which must be unique across all
+/// the claiming managers alive in the process. Sources use them so that two sources built from
+/// the same copy-pasted sensor cluster are rejected instead of silently mixing their readings.
+/// Managers created with `new` don't claim anything, since processing nodes republish the
+/// sensor types of their source.
 
 #[derive(Clone)]
-pub struct PublisherManager<T, S> {
-    publishers: Arc<DashMap<S, Publisher<T>>>,
+pub struct PublisherManager<T, S, D = ThreadPool> {
+    publishers: Arc<PublisherSet<S, Publisher<T, D>>>,
     control: Arc<DashMap<Uuid, S>>,
+    delivery: D,
+    order: DeliveryOrder,
+    claims: Option<Arc<Claims>>,
 }
 
 impl<T, S> PublisherManager<T, S>
 where
     T: Send + Sync + Clone + 'static,
-    S: Send + Sync + Hash + Eq + Clone + Into<usize>,
+    S: Send + Sync + Hash + Ord + Clone + 'static,
 {
     pub fn new(publisher_types: &[S]) -> Self {
-        let collection = DashMap::<S, Publisher<T>>::new();
+        Self::with_delivery(publisher_types, ThreadPool)
+    }
+
+    /// Creates a claiming manager, returning an error if any publisher type is duplicated or
+    /// owned by another claiming manager.
+    pub fn try_new(publisher_types: &[S]) -> Result<Self, ImuError> {
+        Self::try_with_delivery(publisher_types, ThreadPool)
+    }
+}
+
+impl<T, S, D> PublisherManager<T, S, D>
+where
+    T: Send + Sync + Clone + 'static,
+    S: Send + Sync + Hash + Ord + Clone + 'static,
+    D: DeliveryStrategy<T> + Clone,
+{
+    /// Creates a manager whose publishers notify their listeners through `delivery`.
+    pub fn with_delivery(publisher_types: &[S], delivery: D) -> Self {
+        let manager = Self::empty(delivery, None);
         for publisher_type in publisher_types {
-            collection.insert(publisher_type.clone(), Publisher::new());
+            manager
+                .publishers
+                .insert_with(publisher_type.clone(), || manager.new_publisher());
         }
+        manager
+    }
+
+    /// Same as `try_new`, with publishers notifying their listeners through `delivery`.
+    pub fn try_with_delivery(publisher_types: &[S], delivery: D) -> Result<Self, ImuError> {
+        let manager = Self::empty(delivery, Some(Arc::new(Claims::new())));
+        for publisher_type in publisher_types {
+            manager.try_add_publisher(publisher_type.clone())?;
+        }
+        Ok(manager)
+    }
 
+    fn empty(delivery: D, claims: Option<Arc<Claims>>) -> Self {
         Self {
-            publishers: Arc::new(collection),
+            publishers: Arc::new(PublisherSet::dense()),
             control: Arc::new(DashMap::new()),
+            delivery,
+            order: DeliveryOrder::default(),
+            claims,
+        }
+    }
+
+    /// Sets the order in which listeners receive the notifications of every publisher. Like
+    /// `into_dynamic`, call it right after creating the manager.
+    pub fn with_order(mut self, order: DeliveryOrder) -> Self {
+        self.order = order;
+        for (publisher_type, publisher) in self.publishers.entries() {
+            self.publishers
+                .insert(publisher_type, publisher.with_order(order));
+        }
+        self
+    }
+
+    /// Adds a publisher. Adding an existing publisher has no effect. In claiming managers, a
+    /// PublisherCollision error is returned, and the publisher isn't added, if the publisher type
+    /// is owned by another manager.
+    ///
+    /// Publishers are shared by all the clones of the manager, so they can be added and removed
+    /// while other clones notify their listeners.
+    pub fn add_publisher(&self, publisher_type: S) -> Result<(), ImuError> {
+        if let Some(claims) = self.claims.as_ref() {
+            if claims.claim(&publisher_type) == Claim::Collision {
+                return Err(ImuError::PublisherCollision);
+            }
+        }
+        self.publishers
+            .insert_with(publisher_type, || self.new_publisher());
+        Ok(())
+    }
+
+    /// Adds a publisher, returning an error if it already exists in this manager or, for
+    /// claiming managers, in another claiming manager.
+    pub fn try_add_publisher(&self, publisher_type: S) -> Result<(), ImuError> {
+        let claim = match self.claims.as_ref() {
+            Some(claims) => claims.claim(&publisher_type),
+            None if self.publishers.contains(&publisher_type) => Claim::Duplicated,
+            None => Claim::Granted,
+        };
+        match claim {
+            Claim::Granted => {
+                self.publishers.insert(publisher_type, self.new_publisher());
+                Ok(())
+            }
+            Claim::Duplicated => Err(ImuError::DuplicatedPublisher),
+            Claim::Collision => Err(ImuError::PublisherCollision),
         }
     }
 
-    pub fn add_publisher(&mut self, publisher_type: S) {
-        let publisher = Publisher::new();
-        self.publishers.insert(publisher_type, publisher);
+    fn new_publisher(&self) -> Publisher<T, D> {
+        Publisher::with_delivery(self.delivery.clone()).with_order(self.order)
     }
 
-    pub fn remove_publisher(&mut self, publisher_type: &S) {
-        if let Some((_, publisher)) = self.publishers.remove(publisher_type) {
+    pub fn remove_publisher(&self, publisher_type: &S) {
+        if let Some(publisher) = self.publishers.remove(publisher_type) {
             publisher.unregister_all();
+            if let Some(claims) = self.claims.as_ref() {
+                claims.release(publisher_type);
+            }
         }
     }
 
+    /// Returns the publisher types, sorted.
     pub fn get_available_publisher_types(&self) -> Vec<S> {
         let mut sensor_types: Vec<S> = self
             .publishers
-            .iter()
-            .map(|entry| entry.key().clone())
+            .entries()
+            .into_iter()
+            .map(|(publisher_type, _)| publisher_type)
             .collect();
-        sensor_types.sort_by_key(|sensor_type| (sensor_type.clone()).into());
+        sensor_types.sort();
         sensor_types
     }
 
@@ -105,40 +209,119 @@ where
         &self,
         listener: &mut dyn Notifiable<T>,
         publisher_type: &S,
-    ) -> Result<Uuid, String> {
-        if let Some(publisher) = self.publishers.get(publisher_type) {
-            let id = publisher.register_listener(listener);
+    ) -> Result<Uuid, ImuError> {
+        let id = self.publishers.with(publisher_type, |publisher| {
+            publisher.register_listener(listener)
+        });
+        if let Some(id) = id {
             self.control.insert(id, publisher_type.clone());
             return Ok(id);
         }
-        Err("Publisher doesnt exist".to_string())
+        Err(ImuError::UnknownPublisher)
+    }
+
+    /// Subscribes to `publisher_type` through a channel set by `config`, instead of a listener
+    /// callback. The listener feeding the channel is removed with the id of the receiver.
+    pub fn subscribe(
+        &self,
+        publisher_type: &S,
+        config: ChannelConfig,
+    ) -> Result<Receiver<T>, ImuError> {
+        let (mut listener, mut receiver) = channel::channel(config);
+        receiver.id = self.add_listener(&mut listener, publisher_type)?;
+        Ok(receiver)
+    }
+
+    /// Same as `add_listener`, returning a guard that removes the listener once dropped. The
+    /// guard doesn't keep the publishers alive.
+    pub fn add_listener_guarded(
+        &self,
+        listener: &mut dyn Notifiable<T>,
+        publisher_type: &S,
+    ) -> Result<SubscriptionGuard, ImuError>
+    where
+        D: Send + Sync + 'static,
+    {
+        let id = self.add_listener(listener, publisher_type)?;
+        let publishers = Arc::downgrade(&self.publishers);
+        let control = Arc::downgrade(&self.control);
+        Ok(SubscriptionGuard::new(vec![id], move |id| {
+            let (Some(publishers), Some(control)) = (publishers.upgrade(), control.upgrade())
+            else {
+                return;
+            };
+            if let Some((_, publisher_type)) = control.remove(&id) {
+                publishers.with(&publisher_type, |publisher| {
+                    publisher.unregister_listener(id)
+                });
+            }
+        }))
+    }
+
+    /// Removes the listeners whose liveness check fails, e.g. weak listeners of dropped
+    /// handlers, from every publisher. Publishers only drop them when they notify, so this also
+    /// cleans up the publishers that stopped notifying. Returns the number of listeners removed.
+    pub fn remove_dead_listeners(&self) -> usize {
+        for (_, publisher) in self.publishers.entries() {
+            publisher.remove_dead_listeners();
+        }
+        // including the ones already dropped by their publisher
+        let n_listeners = self.control.len();
+        self.control.retain(|id, publisher_type| {
+            self.publishers
+                .with(publisher_type, |publisher| publisher.contains_listener(*id))
+                .unwrap_or(false)
+        });
+        n_listeners - self.control.len()
     }
 
-    pub fn remove_listener(&self, id: Uuid) -> Result<(), String> {
+    pub fn remove_listener(&self, id: Uuid) -> Result<(), ImuError> {
         if let Some((_, publisher_type)) = self.control.remove(&id) {
-            if let Some(publisher) = self.publishers.get(&publisher_type) {
-                publisher.unregister_listener(id);
-            } else {
-                return Err("Publisher doesnt exist".to_string());
+            if self
+                .publishers
+                .with(&publisher_type, |publisher| {
+                    publisher.unregister_listener(id)
+                })
+                .is_none()
+            {
+                return Err(ImuError::UnknownPublisher);
             }
             return Ok(());
         }
-        Err("AsyncListener Id not found".to_string())
+        Err(ImuError::UnknownListener)
     }
 
     pub fn notify_listeners(&self, publisher_type: S, data: Arc<T>) {
-        if let Some(publisher) = self.publishers.get(&publisher_type) {
-            publisher.notify_listeners(data);
-        }
+        self.publishers.with(&publisher_type, |publisher| {
+            publisher.notify_listeners(data)
+        });
     }
 
-    pub fn get_publishers_sorted_by_index(&self) -> Vec<Publisher<T>> {
-        let sensor_types = self.get_available_publisher_types();
-        sensor_types
-            .iter()
-            .map(|sensor_type| self.publishers.get(sensor_type).unwrap().clone())
+    #[deprecated(note = "publishers are sorted by publisher type, use `get_publishers_sorted`")]
+    pub fn get_publishers_sorted_by_index(&self) -> Vec<Publisher<T, D>> {
+        self.get_publishers_sorted()
+    }
+
+    /// Returns the publishers in the order of their publisher types.
+    pub fn get_publishers_sorted(&self) -> Vec<Publisher<T, D>> {
+        let mut publishers = self.publishers.entries();
+        publishers.sort_by(|(left, _), (right, _)| left.cmp(right));
+        publishers
+            .into_iter()
+            .map(|(_, publisher)| publisher)
             .collect()
     }
+
+    /// Looks publishers up by hash instead of scanning a dense vector, for large publisher sets
+    /// that change at runtime. Clones taken before the conversion keep the dense storage, so
+    /// call it right after creating the manager.
+    pub fn into_dynamic(self) -> Self {
+        let publishers = PublisherSet::dynamic(self.publishers.entries());
+        Self {
+            publishers: Arc::new(publishers),
+            ..self
+        }
+    }
 }
 
 #[cfg(test)]
@@ -170,9 +353,13 @@ mod tests {
     fn test_add_publisher() {
         let acc_id = Uuid::new_v4();
         let gyro_id = Uuid::new_v4();
-        let mut manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
-        manager.add_publisher(SensorType::Accelerometer(acc_id));
-        manager.add_publisher(SensorType::Gyroscope(gyro_id));
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
+        manager
+            .add_publisher(SensorType::Accelerometer(acc_id))
+            .unwrap();
+        manager
+            .add_publisher(SensorType::Gyroscope(gyro_id))
+            .unwrap();
         let available_publishers = manager.get_available_publisher_types();
 
         assert!(available_publishers.contains(&SensorType::Accelerometer(acc_id)));
@@ -183,11 +370,13 @@ mod tests {
     #[test]
     fn test_add_duplicated_publisher() {
         let acc_id = Uuid::new_v4();
-        let mut manager =
+        let manager =
             PublisherManager::<Vec<Sample3D>, SensorType>::new(&[SensorType::Accelerometer(
                 acc_id,
             )]);
-        manager.add_publisher(SensorType::Accelerometer(acc_id));
+        manager
+            .add_publisher(SensorType::Accelerometer(acc_id))
+            .unwrap();
         let available_publishers = manager.get_available_publisher_types();
 
         assert!(available_publishers.contains(&SensorType::Accelerometer(acc_id)));
@@ -198,11 +387,13 @@ mod tests {
     fn test_add_2_accelerometer_publisher() {
         let acc_id1 = Uuid::new_v4();
         let acc_id2 = Uuid::new_v4();
-        let mut manager =
+        let manager =
             PublisherManager::<Vec<Sample3D>, SensorType>::new(&[SensorType::Accelerometer(
                 acc_id1,
             )]);
-        manager.add_publisher(SensorType::Accelerometer(acc_id2));
+        manager
+            .add_publisher(SensorType::Accelerometer(acc_id2))
+            .unwrap();
         let available_publishers = manager.get_available_publisher_types();
 
         assert!(available_publishers.contains(&SensorType::Accelerometer(acc_id1)));
@@ -214,7 +405,7 @@ mod tests {
     fn test_remove_publisher_without_listeners() {
         let acc_id = Uuid::new_v4();
         let gyro_id = Uuid::new_v4();
-        let mut manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[
             SensorType::Accelerometer(acc_id),
             SensorType::Gyroscope(gyro_id),
         ]);
@@ -232,8 +423,10 @@ mod tests {
     #[test]
     fn test_remove_unknown_publisher() {
         let acc_id = Uuid::new_v4();
-        let mut manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
-        manager.add_publisher(SensorType::Accelerometer(acc_id));
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
+        manager
+            .add_publisher(SensorType::Accelerometer(acc_id))
+            .unwrap();
         let available_publishers = manager.get_available_publisher_types();
 
         assert!(available_publishers.len() == 1);
@@ -247,7 +440,7 @@ mod tests {
 
     #[test]
     fn test_remove_publisher_from_empty_manager() {
-        let mut manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
         let available_publishers = manager.get_available_publisher_types();
 
         assert!(available_publishers.is_empty());
@@ -261,8 +454,10 @@ mod tests {
     #[test]
     fn test_add_listener() {
         let acc_id = Uuid::new_v4();
-        let mut manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
-        manager.add_publisher(SensorType::Accelerometer(acc_id));
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
+        manager
+            .add_publisher(SensorType::Accelerometer(acc_id))
+            .unwrap();
 
         let test_buffer = Arc::new(TestBuffer::new());
         let mut listener = listener!(test_buffer.handle);
@@ -279,7 +474,7 @@ mod tests {
             SensorType::Other(other_id1, "Sensor1".to_string()),
             SensorType::Other(other_id2, "Sensor1".to_string()),
         ];
-        let mut manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&sensors);
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&sensors);
         let available_publishers = manager.get_available_publisher_types();
 
         assert!(available_publishers.len() == 2);
@@ -297,7 +492,7 @@ mod tests {
             SensorType::Other(other_id1, "Sensor1".to_string()),
             SensorType::Other(other_id1, "Sensor1".to_string()),
         ];
-        let mut manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&sensors);
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&sensors);
         let available_publishers = manager.get_available_publisher_types();
 
         assert!(available_publishers.len() == 1);
@@ -308,7 +503,31 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Publisher doesnt exist")]
+    fn test_sorted_publisher_types() {
+        // uuids with the same byte sum, which used to sort in insertion order
+        let mut first = [0u8; 16];
+        let mut second = [0u8; 16];
+        first[0] = 1;
+        second[1] = 1;
+        let acc1 = SensorType::Accelerometer(Uuid::from_bytes(first));
+        let acc2 = SensorType::Accelerometer(Uuid::from_bytes(second));
+        let gyro = SensorType::Gyroscope(Uuid::nil());
+        let expected = vec![acc2.clone(), acc1.clone(), gyro.clone()];
+
+        for sensors in [
+            vec![acc1.clone(), acc2.clone(), gyro.clone()],
+            vec![gyro.clone(), acc2.clone(), acc1.clone()],
+        ] {
+            let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&sensors);
+            assert_eq!(manager.get_available_publisher_types(), expected);
+            let manager = manager.into_dynamic();
+            assert_eq!(manager.get_available_publisher_types(), expected);
+            assert_eq!(manager.get_publishers_sorted().len(), 3);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "UnknownPublisher")]
     fn test_add_listener_to_nonexistent_publisher() {
         let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
 
@@ -322,8 +541,10 @@ mod tests {
     #[test]
     fn test_remove_listener() {
         let acc_id = Uuid::new_v4();
-        let mut manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
-        manager.add_publisher(SensorType::Accelerometer(acc_id));
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
+        manager
+            .add_publisher(SensorType::Accelerometer(acc_id))
+            .unwrap();
 
         let test_buffer = Arc::new(TestBuffer::new());
         let mut listener = listener!(test_buffer.handle);
@@ -335,24 +556,175 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "AsyncListener Id not found")]
+    fn test_add_listener_guarded() {
+        let acc_id = Uuid::new_v4();
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
+        manager
+            .add_publisher(SensorType::Accelerometer(acc_id))
+            .unwrap();
+
+        let test_buffer = Arc::new(TestBuffer::new());
+        let mut listener = listener!(test_buffer.handle);
+        let guard = manager
+            .add_listener_guarded(&mut listener, &SensorType::Accelerometer(acc_id))
+            .unwrap();
+        let id = guard.get_ids()[0];
+
+        drop(guard);
+        assert_eq!(manager.remove_listener(id), Err(ImuError::UnknownListener));
+    }
+
+    #[test]
+    fn test_remove_dead_listeners() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let gyro = SensorType::Gyroscope(Uuid::new_v4());
+        let manager =
+            PublisherManager::<Vec<Sample3D>, SensorType>::new(&[acc.clone(), gyro.clone()]);
+
+        let acc_buffer = Arc::new(TestBuffer::new());
+        let gyro_buffer = Arc::new(TestBuffer::new());
+        let mut acc_listener = listener!(weak acc_buffer.handle);
+        let mut gyro_listener = listener!(weak gyro_buffer.handle);
+        let acc_id = manager.add_listener(&mut acc_listener, &acc).unwrap();
+        let gyro_id = manager.add_listener(&mut gyro_listener, &gyro).unwrap();
+        assert_eq!(manager.remove_dead_listeners(), 0);
+
+        drop(acc_buffer);
+        drop(gyro_buffer);
+        // the accelerometer publisher drops its listener itself when notifying
+        manager.notify_listeners(acc, Arc::new(vec![]));
+        assert_eq!(manager.remove_dead_listeners(), 2);
+        assert_eq!(
+            manager.remove_listener(acc_id),
+            Err(ImuError::UnknownListener)
+        );
+        assert_eq!(
+            manager.remove_listener(gyro_id),
+            Err(ImuError::UnknownListener)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "UnknownListener")]
     fn test_remove_unknown_listener() {
         let acc_id = Uuid::new_v4();
-        let mut manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
-        manager.add_publisher(SensorType::Accelerometer(acc_id));
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
+        manager
+            .add_publisher(SensorType::Accelerometer(acc_id))
+            .unwrap();
         let id = Uuid::new_v4();
 
         manager.remove_listener(id).unwrap();
     }
 
     #[test]
-    #[should_panic(expected = "Publisher doesnt exist")]
+    fn test_collision_between_managers() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let gyro = SensorType::Gyroscope(Uuid::new_v4());
+        let manager =
+            PublisherManager::<Vec<Sample3D>, SensorType>::try_new(std::slice::from_ref(&acc))
+                .unwrap();
+
+        assert!(
+            PublisherManager::<Vec<Sample3D>, SensorType>::try_new(std::slice::from_ref(&acc))
+                .is_err()
+        );
+        assert!(manager.try_add_publisher(acc.clone()).is_err());
+        assert!(manager.try_add_publisher(gyro.clone()).is_ok());
+
+        // Clones share the same claims
+        let clone = manager.clone();
+        manager.remove_publisher(&gyro);
+        assert!(PublisherManager::<Vec<Sample3D>, SensorType>::try_new(&[gyro]).is_ok());
+
+        drop(manager);
+        assert!(
+            PublisherManager::<Vec<Sample3D>, SensorType>::try_new(std::slice::from_ref(&acc))
+                .is_err()
+        );
+        drop(clone);
+        assert!(PublisherManager::<Vec<Sample3D>, SensorType>::try_new(&[acc]).is_ok());
+    }
+
+    #[test]
+    fn test_add_publisher_collision() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let _source =
+            PublisherManager::<Vec<Sample3D>, SensorType>::try_new(std::slice::from_ref(&acc))
+                .unwrap();
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::try_new(&[]).unwrap();
+
+        assert!(matches!(
+            manager.add_publisher(acc.clone()),
+            Err(ImuError::PublisherCollision)
+        ));
+        assert!(manager.get_available_publisher_types().is_empty());
+
+        let gyro = SensorType::Gyroscope(Uuid::new_v4());
+        manager.add_publisher(gyro.clone()).unwrap();
+        manager.add_publisher(gyro).unwrap();
+        assert_eq!(manager.get_available_publisher_types().len(), 1);
+    }
+
+    #[test]
+    fn test_non_claiming_managers() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let _source =
+            PublisherManager::<Vec<Sample3D>, SensorType>::try_new(std::slice::from_ref(&acc));
+        let node = PublisherManager::<Vec<Sample3D>, SensorType>::new(std::slice::from_ref(&acc));
+
+        assert!(node.try_add_publisher(acc).is_err());
+        assert_eq!(node.get_available_publisher_types().len(), 1);
+    }
+
+    #[test]
+    fn test_distinct_clusters_do_not_collide() {
+        let left = SensorType::cluster_for_tag("Left");
+        let right = SensorType::cluster_for_tag("Right");
+        let _left = PublisherManager::<Vec<Sample3D>, SensorType>::try_new(&left).unwrap();
+
+        assert!(PublisherManager::<Vec<Sample3D>, SensorType>::try_new(&right).is_ok());
+        assert!(PublisherManager::<Vec<Sample3D>, SensorType>::try_new(&left).is_err());
+    }
+
+    #[test]
+    fn test_into_dynamic() {
+        let sensors = SensorType::cluster_for_tag("test_into_dynamic");
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType, _>::with_delivery(
+            &sensors,
+            crate::delivery::Inline,
+        );
+        let received = Arc::new(std::sync::Mutex::new(0));
+        let mut listener = Listener::new({
+            let received = received.clone();
+            move |_id, _samples: Arc<Vec<Sample3D>>| *received.lock().unwrap() += 1
+        });
+        let id = manager.add_listener(&mut listener, &sensors[1]).unwrap();
+
+        let manager = manager.into_dynamic();
+        manager
+            .add_publisher(SensorType::Accelerometer(Uuid::new_v4()))
+            .unwrap();
+        manager.notify_listeners(sensors[1].clone(), Arc::new(vec![]));
+        manager.notify_listeners(sensors[0].clone(), Arc::new(vec![]));
+
+        assert_eq!(*received.lock().unwrap(), 1);
+        assert_eq!(manager.get_available_publisher_types().len(), 4);
+        assert!(manager.remove_listener(id).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "UnknownPublisher")]
     fn test_remove_publisher_with_listeners() {
-        let mut manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
+        let manager = PublisherManager::<Vec<Sample3D>, SensorType>::new(&[]);
         let acc_id = Uuid::new_v4();
         let gyro_id = Uuid::new_v4();
-        manager.add_publisher(SensorType::Accelerometer(acc_id));
-        manager.add_publisher(SensorType::Gyroscope(gyro_id));
+        manager
+            .add_publisher(SensorType::Accelerometer(acc_id))
+            .unwrap();
+        manager
+            .add_publisher(SensorType::Gyroscope(gyro_id))
+            .unwrap();
 
         let test_buffer = Arc::new(TestBuffer::new());
         let mut listener = listener!(test_buffer.handle);
@@ -0,0 +1,131 @@
+//! Storage of the publishers of a [`PublisherManager`](crate::PublisherManager).
+//!
+//! Notification is the most frequent operation in the workspace, and sources publish a small,
+//! fixed cluster of sensors. For those, a linear scan over a dense vector finds the publisher
+//! faster than hashing the publisher type into a `DashMap`, as measured by the `notify`
+//! benchmark of this crate. The `DashMap` is kept for large publisher sets that change at
+//! runtime.
+
+use dashmap::DashMap;
+use std::hash::Hash;
+use std::sync::RwLock;
+
+pub(crate) enum PublisherSet<S, P> {
+    /// Publishers stored in registration order, looked up by a linear scan.
+    Dense(RwLock<Vec<(S, P)>>),
+    /// Publishers looked up by hash.
+    Dynamic(DashMap<S, P>),
+}
+
+impl<S, P> PublisherSet<S, P>
+where
+    S: Hash + Eq + Clone,
+    P: Clone,
+{
+    pub(crate) fn dense() -> Self {
+        Self::Dense(RwLock::new(Vec::new()))
+    }
+
+    pub(crate) fn dynamic(entries: Vec<(S, P)>) -> Self {
+        Self::Dynamic(entries.into_iter().collect())
+    }
+
+    /// Runs `f` on the publisher of `key`, returning `None` if there is no such publisher.
+    pub(crate) fn with<R>(&self, key: &S, f: impl FnOnce(&P) -> R) -> Option<R> {
+        match self {
+            Self::Dense(entries) => entries
+                .read()
+                .unwrap()
+                .iter()
+                .find(|(entry_key, _)| entry_key == key)
+                .map(|(_, publisher)| f(publisher)),
+            Self::Dynamic(entries) => entries.get(key).map(|publisher| f(&publisher)),
+        }
+    }
+
+    pub(crate) fn contains(&self, key: &S) -> bool {
+        self.with(key, |_| ()).is_some()
+    }
+
+    /// Inserts the publisher returned by `f` unless `key` already has one.
+    pub(crate) fn insert_with(&self, key: S, f: impl FnOnce() -> P) {
+        match self {
+            Self::Dense(entries) => {
+                let mut entries = entries.write().unwrap();
+                if !entries.iter().any(|(entry_key, _)| *entry_key == key) {
+                    entries.push((key, f()));
+                }
+            }
+            Self::Dynamic(entries) => {
+                entries.entry(key).or_insert_with(f);
+            }
+        }
+    }
+
+    /// Inserts `publisher`, replacing the publisher of `key` if any.
+    pub(crate) fn insert(&self, key: S, publisher: P) {
+        match self {
+            Self::Dense(entries) => {
+                let mut entries = entries.write().unwrap();
+                match entries.iter_mut().find(|(entry_key, _)| *entry_key == key) {
+                    Some((_, entry)) => *entry = publisher,
+                    None => entries.push((key, publisher)),
+                }
+            }
+            Self::Dynamic(entries) => {
+                entries.insert(key, publisher);
+            }
+        }
+    }
+
+    pub(crate) fn remove(&self, key: &S) -> Option<P> {
+        match self {
+            Self::Dense(entries) => {
+                let mut entries = entries.write().unwrap();
+                let index = entries.iter().position(|(entry_key, _)| entry_key == key)?;
+                Some(entries.remove(index).1)
+            }
+            Self::Dynamic(entries) => entries.remove(key).map(|(_, publisher)| publisher),
+        }
+    }
+
+    pub(crate) fn entries(&self) -> Vec<(S, P)> {
+        match self {
+            Self::Dense(entries) => entries.read().unwrap().clone(),
+            Self::Dynamic(entries) => entries
+                .iter()
+                .map(|entry| (entry.key().clone(), entry.value().clone()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_set(set: PublisherSet<u32, &'static str>) {
+        set.insert_with(1, || "one");
+        set.insert_with(2, || "two");
+        set.insert_with(1, || "uno");
+        assert_eq!(set.with(&1, |publisher| *publisher), Some("one"));
+        assert!(set.contains(&2));
+        assert!(!set.contains(&3));
+
+        set.insert(1, "uno");
+        assert_eq!(set.with(&1, |publisher| *publisher), Some("uno"));
+        assert_eq!(set.remove(&2), Some("two"));
+        assert_eq!(set.remove(&2), None);
+        assert_eq!(set.entries(), vec![(1, "uno")]);
+    }
+
+    #[test]
+    fn test_dense_set() {
+        check_set(PublisherSet::dense());
+    }
+
+    #[test]
+    fn test_dynamic_set() {
+        check_set(PublisherSet::dynamic(Vec::new()));
+    }
+}
@@ -0,0 +1,208 @@
+//! Module shutdown
+//!
+//! `ShutdownToken` stops every stage of a pipeline together. Sources, the resampler and the
+//! network sinks stop their loops once the token is shut down, and hooks registered with
+//! [`ShutdownToken::on_shutdown`] flush recorders and detach listeners before exit.
+//!
+//! Stages use [`ShutdownToken::global`] unless given another token, and Ctrl-C shuts the global
+//! token down, so a single Ctrl-C stops the whole pipeline. Waiting for the token and Ctrl-C
+//! need the `tokio` feature.
+
+#[cfg(feature = "tokio")]
+use log::{error, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::Duration;
+#[cfg(feature = "tokio")]
+use tokio::sync::Notify;
+
+type Hook = Box<dyn FnOnce() + Send>;
+
+struct ShutdownState {
+    is_shutdown: AtomicBool,
+    #[cfg(feature = "tokio")]
+    notify: Notify,
+    // hooks pending to run, `None` once shut down
+    hooks: Mutex<Option<Vec<Hook>>>,
+    condvar: Condvar,
+}
+
+/// Cloneable token shared by the stages of a pipeline.
+#[derive(Clone)]
+pub struct ShutdownToken {
+    state: Arc<ShutdownState>,
+}
+
+impl Default for ShutdownToken {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(ShutdownState {
+                is_shutdown: AtomicBool::new(false),
+                #[cfg(feature = "tokio")]
+                notify: Notify::new(),
+                hooks: Mutex::new(Some(Vec::new())),
+                condvar: Condvar::new(),
+            }),
+        }
+    }
+}
+
+impl ShutdownToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the token used by stages that aren't given one.
+    pub fn global() -> Self {
+        static GLOBAL: OnceLock<ShutdownToken> = OnceLock::new();
+        GLOBAL.get_or_init(ShutdownToken::new).clone()
+    }
+
+    /// Shuts the token down, waking every stage waiting on it, and runs the registered hooks in
+    /// registration order. Only the first call has any effect.
+    pub fn shutdown(&self) {
+        let hooks = {
+            let mut hooks = self.state.hooks.lock().unwrap();
+            self.state.is_shutdown.store(true, Ordering::SeqCst);
+            self.state.condvar.notify_all();
+            hooks.take()
+        };
+        let Some(hooks) = hooks else {
+            return;
+        };
+        #[cfg(feature = "tokio")]
+        self.state.notify.notify_waiters();
+        for hook in hooks {
+            hook();
+        }
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.state.is_shutdown.load(Ordering::SeqCst)
+    }
+
+    /// Registers `hook` to run on shutdown. It runs right away if the token is already shut down.
+    pub fn on_shutdown<F>(&self, hook: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut hooks = self.state.hooks.lock().unwrap();
+        match hooks.as_mut() {
+            Some(hooks) => hooks.push(Box::new(hook)),
+            None => {
+                drop(hooks);
+                hook();
+            }
+        }
+    }
+
+    /// Waits until the token is shut down.
+    #[cfg(feature = "tokio")]
+    pub async fn wait(&self) {
+        let notified = self.state.notify.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        if !self.is_shutdown() {
+            notified.await;
+        }
+    }
+
+    /// Blocks the thread for `duration`, returning early if the token is shut down.
+    /// Returns true if the token is shut down.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let hooks = self.state.hooks.lock().unwrap();
+        let _ = self
+            .state
+            .condvar
+            .wait_timeout_while(hooks, duration, |_| !self.is_shutdown())
+            .unwrap();
+        self.is_shutdown()
+    }
+
+    /// Shuts the token down on Ctrl-C. Must be called inside a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn listen_for_ctrl_c(&self) -> tokio::task::JoinHandle<()> {
+        let token = self.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = token.wait() => {}
+                result = tokio::signal::ctrl_c() => match result {
+                    Ok(()) => {
+                        info!("Ctrl+C received. Shutting down...");
+                        token.shutdown();
+                    }
+                    Err(e) => error!("Error while waiting for Ctrl+C: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_hooks_run_once_in_order() {
+        let token = ShutdownToken::new();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        for i in 0..3 {
+            let calls = calls.clone();
+            token.on_shutdown(move || calls.lock().unwrap().push(i));
+        }
+        assert!(calls.lock().unwrap().is_empty());
+
+        token.shutdown();
+        token.shutdown();
+        assert!(token.is_shutdown());
+        assert_eq!(*calls.lock().unwrap(), vec![0, 1, 2]);
+
+        // late hooks run right away
+        token.on_shutdown({
+            let calls = calls.clone();
+            move || calls.lock().unwrap().push(3)
+        });
+        assert_eq!(*calls.lock().unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_sleep_interrupted() {
+        let token = ShutdownToken::new();
+        assert!(!token.sleep(Duration::from_millis(10)));
+
+        let start = Instant::now();
+        std::thread::spawn({
+            let token = token.clone();
+            move || {
+                std::thread::sleep(Duration::from_millis(50));
+                token.shutdown();
+            }
+        });
+        assert!(token.sleep(Duration::from_secs(5)));
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(token.sleep(Duration::from_secs(5)));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_wait() {
+        let token = ShutdownToken::new();
+        let waiter = tokio::spawn({
+            let token = token.clone();
+            async move { token.wait().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        token.shutdown();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        // returns right away once shut down
+        tokio::time::timeout(Duration::from_millis(10), token.wait())
+            .await
+            .unwrap();
+    }
+}
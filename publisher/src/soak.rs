@@ -0,0 +1,427 @@
+//! Module soak
+//!
+//! `SoakMonitor` supervises pipelines deployed as permanent monitoring services. It is attached
+//! to a source like any other sink, and every heartbeat it checks that:
+//! - the timestamps of every sensor never go back,
+//! - every attached sensor keeps delivering readings,
+//! - the resident memory of the process stays below a bound.
+//!
+//! Violations are logged and kept in a bounded history, so the monitor itself can run for days:
+//!
+//! ```ignore
+//! let monitor = soak::watch(&*service, &sensor_cluster, SoakConfig::new(), ShutdownToken::global())?;
+//! // ...
+//! let report = monitor.get_report();
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::{adapters, ShutdownToken};
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
+use imu_common::types::sensors::SensorType;
+
+const DEFAULT_HEARTBEAT_SECS: u64 = 60;
+const DEFAULT_STALL_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_MAX_VIOLATIONS: usize = 100;
+// /proc/self/statm counts pages
+const PAGE_SIZE_BYTES: u64 = 4096;
+
+/// Configuration of a `SoakMonitor`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoakConfig {
+    heartbeat: Duration,
+    stall_timeout: Duration,
+    max_rss_bytes: Option<u64>,
+    max_violations: usize,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SoakConfig {
+    /// Defaults to a heartbeat every minute, sensors stalled after 10 s without readings, no
+    /// memory bound, and the last 100 violations kept.
+    pub fn new() -> Self {
+        Self {
+            heartbeat: Duration::from_secs(DEFAULT_HEARTBEAT_SECS),
+            stall_timeout: Duration::from_secs(DEFAULT_STALL_TIMEOUT_SECS),
+            max_rss_bytes: None,
+            max_violations: DEFAULT_MAX_VIOLATIONS,
+        }
+    }
+
+    /// Sets the period of the checks and of the heartbeat log.
+    pub fn with_heartbeat(mut self, heartbeat: Duration) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Sets how long a sensor can go without readings before it is reported as stalled.
+    pub fn with_stall_timeout(mut self, stall_timeout: Duration) -> Self {
+        self.stall_timeout = stall_timeout;
+        self
+    }
+
+    /// Reports the process once its resident memory exceeds `max_rss_bytes`. Only checked on
+    /// Linux.
+    pub fn with_max_rss_bytes(mut self, max_rss_bytes: u64) -> Self {
+        self.max_rss_bytes = Some(max_rss_bytes);
+        self
+    }
+
+    /// Sets how many of the most recent violations are kept.
+    pub fn with_max_violations(mut self, max_violations: usize) -> Self {
+        self.max_violations = max_violations;
+        self
+    }
+
+    pub fn get_heartbeat(&self) -> Duration {
+        self.heartbeat
+    }
+}
+
+/// Invariant broken during a soak run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SoakViolation {
+    /// A sample of `sensor_type` is older than the previous one.
+    NonMonotonic {
+        sensor_type: SensorType,
+        timestamp_secs: f64,
+        previous_secs: f64,
+    },
+    /// No readings of `sensor_type` were received during `silent_for`. Reported once until
+    /// readings arrive again.
+    Stalled {
+        sensor_type: SensorType,
+        silent_for: Duration,
+    },
+    /// The resident memory of the process is above the bound.
+    MemoryBound { rss_bytes: u64, max_rss_bytes: u64 },
+}
+
+/// Health of the pipeline since the monitor started.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoakReport {
+    pub uptime: Duration,
+    pub n_samples: usize,
+    /// Resident memory of the process at the last check, if known.
+    pub rss_bytes: Option<u64>,
+    pub n_violations: usize,
+    /// Most recent violations, oldest first.
+    pub recent_violations: Vec<SoakViolation>,
+}
+
+impl SoakReport {
+    pub fn is_healthy(&self) -> bool {
+        self.n_violations == 0
+    }
+}
+
+struct SensorHealth {
+    n_samples: usize,
+    last_received: Instant,
+    last_timestamp_secs: Option<f64>,
+    stalled: bool,
+}
+
+impl SensorHealth {
+    fn new() -> Self {
+        Self {
+            n_samples: 0,
+            last_received: Instant::now(),
+            last_timestamp_secs: None,
+            stalled: false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct SoakState {
+    sensors: HashMap<SensorType, SensorHealth>,
+    n_samples: usize,
+    rss_bytes: Option<u64>,
+    n_violations: usize,
+    violations: VecDeque<SoakViolation>,
+}
+
+/// Sink checking the invariants of a long running pipeline.
+#[derive(Clone)]
+pub struct SoakMonitor<T, S> {
+    config: Arc<SoakConfig>,
+    started_at: Instant,
+    state: Arc<Mutex<SoakState>>,
+    _phantom: PhantomData<(T, S)>,
+}
+
+impl<T, S> SoakMonitor<T, S>
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+{
+    pub fn new(config: SoakConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            started_at: Instant::now(),
+            state: Arc::new(Mutex::new(SoakState::default())),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn get_report(&self) -> SoakReport {
+        let state = self.state.lock().unwrap();
+        SoakReport {
+            uptime: self.started_at.elapsed(),
+            n_samples: state.n_samples,
+            rss_bytes: state.rss_bytes,
+            n_violations: state.n_violations,
+            recent_violations: state.violations.iter().cloned().collect(),
+        }
+    }
+
+    /// Checks the attached sensors and the memory of the process, returning the new violations.
+    pub fn check(&self) -> Vec<SoakViolation> {
+        let rss_bytes = resident_memory_bytes();
+        let mut state = self.state.lock().unwrap();
+        let mut violations = Vec::new();
+        for (sensor_type, health) in state.sensors.iter_mut() {
+            let silent_for = health.last_received.elapsed();
+            if !health.stalled && silent_for >= self.config.stall_timeout {
+                health.stalled = true;
+                violations.push(SoakViolation::Stalled {
+                    sensor_type: sensor_type.clone(),
+                    silent_for,
+                });
+            }
+        }
+        state.rss_bytes = rss_bytes;
+        if let (Some(rss_bytes), Some(max_rss_bytes)) = (rss_bytes, self.config.max_rss_bytes) {
+            if rss_bytes > max_rss_bytes {
+                violations.push(SoakViolation::MemoryBound {
+                    rss_bytes,
+                    max_rss_bytes,
+                });
+            }
+        }
+        for violation in violations.iter() {
+            self.record(&mut state, violation.clone());
+        }
+        violations
+    }
+
+    /// Checks the invariants and logs a heartbeat every heartbeat period, until `token` is
+    /// shut down.
+    pub async fn run(&self, token: ShutdownToken) {
+        let mut interval = tokio::time::interval(self.config.heartbeat);
+        // the first tick completes immediately
+        interval.tick().await;
+        loop {
+            tokio::select! {
+                _ = token.wait() => return,
+                _ = interval.tick() => {
+                    self.check();
+                    self.log_heartbeat();
+                }
+            }
+        }
+    }
+
+    fn log_heartbeat(&self) {
+        let report = self.get_report();
+        log::info!(
+            "Soak heartbeat: uptime {:?}, {} samples, rss {:?} bytes, {} violations",
+            report.uptime,
+            report.n_samples,
+            report.rss_bytes,
+            report.n_violations
+        );
+    }
+
+    fn record(&self, state: &mut SoakState, violation: SoakViolation) {
+        log::warn!("Soak violation: {:?}", violation);
+        state.n_violations += 1;
+        if self.config.max_violations == 0 {
+            return;
+        }
+        if state.violations.len() == self.config.max_violations {
+            state.violations.pop_front();
+        }
+        state.violations.push_back(violation);
+    }
+}
+
+impl<T, S> IMUSink<T, S> for SoakMonitor<T, S>
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, S>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        let ids = adapters::attach_sync(self, source, sensor_cluster)?;
+        // attached sensors that never deliver a reading are reported as stalled too
+        let mut state = self.state.lock().unwrap();
+        for sensor_type in sensor_cluster {
+            state
+                .sensors
+                .entry(sensor_type.clone())
+                .or_insert_with(SensorHealth::new);
+        }
+        Ok(ids)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        let sensor_type = samples.get_sensor_type();
+        let samples = samples.get_samples_ref();
+        let mut state = self.state.lock().unwrap();
+        state.n_samples += samples.len();
+        let health = state
+            .sensors
+            .entry(sensor_type.clone())
+            .or_insert_with(SensorHealth::new);
+        health.n_samples += samples.len();
+        health.last_received = Instant::now();
+        health.stalled = false;
+        let mut violations = Vec::new();
+        for sample in samples.iter() {
+            let timestamp_secs = sample.get_timestamp_secs();
+            if let Some(previous_secs) = health.last_timestamp_secs {
+                if timestamp_secs < previous_secs {
+                    violations.push(SoakViolation::NonMonotonic {
+                        sensor_type: sensor_type.clone(),
+                        timestamp_secs,
+                        previous_secs,
+                    });
+                }
+            }
+            health.last_timestamp_secs = Some(timestamp_secs);
+        }
+        for violation in violations {
+            self.record(&mut state, violation);
+        }
+    }
+}
+
+/// Attaches a `SoakMonitor` to every sensor of `sensor_cluster` and runs its checks on the
+/// tokio runtime until `token` is shut down.
+pub fn watch<T, S>(
+    source: &dyn IMUSource<T, S>,
+    sensor_cluster: &[SensorType],
+    config: SoakConfig,
+    token: ShutdownToken,
+) -> Result<SoakMonitor<T, S>, ImuError>
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+{
+    let monitor = SoakMonitor::new(config);
+    monitor.attach_listeners(source, sensor_cluster)?;
+    tokio::spawn({
+        let monitor = monitor.clone();
+        async move { monitor.run(token).await }
+    });
+    Ok(monitor)
+}
+
+/// Returns the resident memory of the process, if known.
+fn resident_memory_bytes() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * PAGE_SIZE_BYTES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::types::sensors::SensorReadings;
+    use imu_common::types::timed::Sample3D;
+
+    type Monitor = SoakMonitor<SensorReadings<Sample3D>, Sample3D>;
+
+    fn readings(sensor_type: &SensorType, timestamps: &[f64]) -> Arc<SensorReadings<Sample3D>> {
+        let samples = timestamps
+            .iter()
+            .map(|&t| Sample3D::new(t, [0.0, 0.0, 0.0]))
+            .collect();
+        Arc::new(SensorReadings::from_vec(
+            "test",
+            sensor_type.clone(),
+            samples,
+        ))
+    }
+
+    #[test]
+    fn test_non_monotonic() {
+        let sensor = SensorType::Accelerometer(Uuid::new_v4());
+        let monitor = Monitor::new(SoakConfig::new().with_max_violations(1));
+
+        monitor.process_samples(Uuid::new_v4(), readings(&sensor, &[1.0, 2.0]));
+        assert!(monitor.get_report().is_healthy());
+        monitor.process_samples(Uuid::new_v4(), readings(&sensor, &[1.5, 0.5]));
+
+        let report = monitor.get_report();
+        assert_eq!(report.n_samples, 4);
+        assert_eq!(report.n_violations, 2);
+        // only the most recent violation is kept
+        assert_eq!(
+            report.recent_violations,
+            vec![SoakViolation::NonMonotonic {
+                sensor_type: sensor,
+                timestamp_secs: 0.5,
+                previous_secs: 1.5,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_stalled_sensor() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let gyro = SensorType::Gyroscope(Uuid::new_v4());
+        let monitor = Monitor::new(SoakConfig::new().with_stall_timeout(Duration::from_millis(50)));
+        // attached sensor that never delivers
+        monitor
+            .state
+            .lock()
+            .unwrap()
+            .sensors
+            .insert(gyro.clone(), SensorHealth::new());
+
+        std::thread::sleep(Duration::from_millis(60));
+        monitor.process_samples(Uuid::new_v4(), readings(&acc, &[1.0]));
+        let violations = monitor.check();
+        assert!(matches!(
+            &violations[..],
+            [SoakViolation::Stalled { sensor_type, .. }] if *sensor_type == gyro
+        ));
+        // a stall is reported once, until readings arrive again
+        assert!(monitor.check().is_empty());
+        monitor.process_samples(Uuid::new_v4(), readings(&gyro, &[1.0]));
+        assert!(monitor.check().is_empty());
+        assert_eq!(monitor.get_report().n_violations, 1);
+    }
+
+    #[test]
+    fn test_memory_bound() {
+        let monitor = Monitor::new(SoakConfig::new().with_max_rss_bytes(1));
+        let violations = monitor.check();
+        if resident_memory_bytes().is_some() {
+            assert!(matches!(
+                violations[..],
+                [SoakViolation::MemoryBound {
+                    max_rss_bytes: 1,
+                    ..
+                }]
+            ));
+            assert!(monitor.get_report().rss_bytes.is_some());
+        }
+    }
+}
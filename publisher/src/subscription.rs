@@ -0,0 +1,75 @@
+//! Module subscription
+//!
+//! Registrations returned as ids must be unregistered by hand, and a forgotten one keeps its
+//! listener, and everything the listener captures, alive for as long as the publisher lives.
+//! [`SubscriptionGuard`] unregisters its listeners once dropped instead.
+
+use uuid::Uuid;
+
+use crate::DropGuard;
+
+/// Listeners registered by the attach and register functions of the crate. They are
+/// unregistered once the guard is dropped.
+#[must_use = "listeners are unregistered as soon as the guard is dropped"]
+pub struct SubscriptionGuard {
+    ids: Vec<Uuid>,
+    guard: DropGuard,
+}
+
+impl SubscriptionGuard {
+    /// Creates a guard calling `unregister` with every id in `ids` once dropped.
+    pub fn new<F>(ids: Vec<Uuid>, unregister: F) -> Self
+    where
+        F: Fn(Uuid) + Send + Sync + 'static,
+    {
+        let guard = DropGuard::new({
+            let ids = ids.clone();
+            move || ids.into_iter().for_each(unregister)
+        });
+        Self { ids, guard }
+    }
+
+    pub fn get_ids(&self) -> &[Uuid] {
+        &self.ids
+    }
+
+    /// Keeps the listeners registered for as long as the publisher lives, returning their ids.
+    pub fn forget(self) -> Vec<Uuid> {
+        self.guard.disarm();
+        self.ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_unregister_on_drop() {
+        let unregistered = Arc::new(Mutex::new(Vec::new()));
+        let ids = vec![Uuid::new_v4(), Uuid::new_v4()];
+        let guard = SubscriptionGuard::new(ids.clone(), {
+            let unregistered = unregistered.clone();
+            move |id| unregistered.lock().unwrap().push(id)
+        });
+        assert_eq!(guard.get_ids(), &ids[..]);
+        assert!(unregistered.lock().unwrap().is_empty());
+
+        drop(guard);
+        assert_eq!(*unregistered.lock().unwrap(), ids);
+    }
+
+    #[test]
+    fn test_forget() {
+        let unregistered = Arc::new(Mutex::new(Vec::new()));
+        let ids = vec![Uuid::new_v4()];
+        let guard = SubscriptionGuard::new(ids.clone(), {
+            let unregistered = unregistered.clone();
+            move |id| unregistered.lock().unwrap().push(id)
+        });
+
+        assert_eq!(guard.forget(), ids);
+        assert!(unregistered.lock().unwrap().is_empty());
+    }
+}
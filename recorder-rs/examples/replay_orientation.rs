@@ -0,0 +1,30 @@
+use imu_common::traits::{IMUSink, IMUSource};
+use imu_common::types::sensors::SensorReadings;
+use imu_common::types::timed::SampleQuaternion;
+use recorder_rs::{Manifest, ReplaySource};
+use test_utils::renderable::Box3D;
+use test_utils::sinks::Plot3D;
+
+/// Replays a recorded orientation session into the 3D visualizer.
+///
+/// Usage: cargo run --example replay_orientation -- <recording dir> <manifest.json>
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 {
+        eprintln!("Usage: replay_orientation <recording dir> <manifest.json>");
+        std::process::exit(1);
+    }
+    let manifest = Manifest::from_json(&std::fs::read_to_string(&args[2]).unwrap()).unwrap();
+    let replay = ReplaySource::<SampleQuaternion>::from_csv("Replay", &args[1], &manifest).unwrap();
+
+    let plot_3d = Plot3D::new(Box3D::new());
+    IMUSink::<SensorReadings<SampleQuaternion>, SampleQuaternion>::attach_listeners(
+        &plot_3d,
+        &replay,
+        &replay.get_available_sensors(),
+    )
+    .unwrap();
+
+    replay.start(Some(1.0)).await;
+}
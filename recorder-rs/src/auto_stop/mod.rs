@@ -0,0 +1,324 @@
+//! Module auto_stop
+//!
+//! `AutoStop` ends unattended captures deterministically. It is attached to a source like any
+//! other sink, and [`AutoStop::wait`] returns as soon as one of its conditions is met, so the
+//! caller can stop the source and finalize its recorders:
+//!
+//! ```ignore
+//! let auto_stop = AutoStop::new(vec![
+//!     StopCondition::Samples(100_000),
+//!     StopCondition::DiskQuota { path: "capture".into(), max_bytes: 1 << 30 },
+//! ]);
+//! auto_stop.attach_listeners(&*service, &sensor_cluster)?;
+//! let condition = auto_stop.wait().await;
+//! service.stop();
+//! recorder.finalize()?;
+//! ```
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource, VecF64Convertible};
+use imu_common::types::sensors::SensorType;
+use publisher::adapters;
+
+/// Period between checks of the conditions that don't depend on the readings.
+const CHECK_PERIOD_MILLIS: u64 = 100;
+
+/// Condition ending a capture.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StopCondition {
+    /// Time elapsed since the `AutoStop` was created.
+    Elapsed(Duration),
+    /// Number of samples received from all the attached sensors.
+    Samples(usize),
+    /// Size in bytes of `path`, including every file below it if it is a directory.
+    DiskQuota { path: PathBuf, max_bytes: u64 },
+    /// Time, in sample timestamps, every attached sensor has been still. A sensor is still while
+    /// every component of its readings stays within `tolerance` of the reading starting the
+    /// still period.
+    Stationary { duration: Duration, tolerance: f64 },
+}
+
+/// Start of the current still period of a sensor.
+struct Stillness {
+    reference: Vec<f64>,
+    since: f64,
+    last: f64,
+}
+
+impl Stillness {
+    fn new(reference: Vec<f64>, timestamp: f64) -> Self {
+        Self {
+            reference,
+            since: timestamp,
+            last: timestamp,
+        }
+    }
+
+    fn update(&mut self, values: Vec<f64>, timestamp: f64, tolerance: f64) {
+        let is_still = values.len() == self.reference.len()
+            && values
+                .iter()
+                .zip(&self.reference)
+                .all(|(v, r)| (v - r).abs() <= tolerance);
+        if is_still {
+            self.last = timestamp;
+        } else {
+            *self = Self::new(values, timestamp);
+        }
+    }
+
+    fn still_secs(&self) -> f64 {
+        self.last - self.since
+    }
+}
+
+#[derive(Default)]
+struct AutoStopState {
+    n_samples: usize,
+    stillness: HashMap<SensorType, Stillness>,
+}
+
+/// Sink watching the readings of a source until one of its stop conditions is met.
+#[derive(Clone)]
+pub struct AutoStop<T, S> {
+    conditions: Arc<Vec<StopCondition>>,
+    started_at: Instant,
+    state: Arc<Mutex<AutoStopState>>,
+    triggered: Arc<OnceLock<StopCondition>>,
+    stop_signal: Arc<Notify>,
+    _phantom: PhantomData<(T, S)>,
+}
+
+impl<T, S> AutoStop<T, S>
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    /// Creates a watcher stopping on the first condition of `conditions` that is met.
+    pub fn new(conditions: Vec<StopCondition>) -> Self {
+        Self {
+            conditions: Arc::new(conditions),
+            started_at: Instant::now(),
+            state: Arc::new(Mutex::new(AutoStopState::default())),
+            triggered: Arc::new(OnceLock::new()),
+            stop_signal: Arc::new(Notify::new()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the condition that was met, if any.
+    pub fn get_triggered(&self) -> Option<StopCondition> {
+        self.check();
+        self.triggered.get().cloned()
+    }
+
+    /// Returns the number of samples received.
+    pub fn get_sample_count(&self) -> usize {
+        self.state.lock().unwrap().n_samples
+    }
+
+    /// Waits until a condition is met, and returns it. Never returns without conditions.
+    pub async fn wait(&self) -> StopCondition {
+        loop {
+            if let Some(condition) = self.get_triggered() {
+                return condition;
+            }
+            tokio::select! {
+                _ = self.stop_signal.notified() => {}
+                _ = tokio::time::sleep(Duration::from_millis(CHECK_PERIOD_MILLIS)) => {}
+            }
+        }
+    }
+
+    /// Checks the conditions that don't depend on the readings.
+    fn check(&self) {
+        if self.triggered.get().is_some() {
+            return;
+        }
+        let condition = self.conditions.iter().find(|condition| match condition {
+            StopCondition::Elapsed(duration) => self.started_at.elapsed() >= *duration,
+            StopCondition::DiskQuota { path, max_bytes } => disk_usage(path) >= *max_bytes,
+            _ => false,
+        });
+        if let Some(condition) = condition {
+            self.trigger(condition);
+        }
+    }
+
+    fn trigger(&self, condition: &StopCondition) {
+        if self.triggered.set(condition.clone()).is_ok() {
+            log::info!("Stop condition met: {:?}", condition);
+            self.stop_signal.notify_waiters();
+        }
+    }
+}
+
+/// Returns the size of `path`, or of all the files below it if it is a directory. Missing or
+/// unreadable entries count as empty.
+fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| disk_usage(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+impl<T, S> IMUSink<T, S> for AutoStop<T, S>
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: Send + Sync + IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, S>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        if self.triggered.get().is_some() {
+            return;
+        }
+        let sensor_type = samples.get_sensor_type();
+        let samples = samples.get_samples_ref();
+        let mut state = self.state.lock().unwrap();
+        state.n_samples += samples.len();
+
+        for condition in self.conditions.iter() {
+            let is_met = match condition {
+                StopCondition::Samples(n_samples) => state.n_samples >= *n_samples,
+                StopCondition::Stationary {
+                    duration,
+                    tolerance,
+                } => {
+                    for sample in samples.iter() {
+                        let timestamp = sample.get_timestamp_secs();
+                        let values: Vec<f64> = sample.get_measurement().into();
+                        match state.stillness.get_mut(&sensor_type) {
+                            Some(stillness) => stillness.update(values, timestamp, *tolerance),
+                            None => {
+                                let stillness = Stillness::new(values, timestamp);
+                                state.stillness.insert(sensor_type.clone(), stillness);
+                            }
+                        }
+                    }
+                    state
+                        .stillness
+                        .values()
+                        .all(|s| s.still_secs() >= duration.as_secs_f64())
+                }
+                _ => false,
+            };
+            if is_met {
+                self.trigger(condition);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::types::sensors::SensorReadings;
+    use imu_common::types::timed::Sample3D;
+
+    type Watcher = AutoStop<SensorReadings<Sample3D>, Sample3D>;
+
+    fn readings(
+        sensor_type: &SensorType,
+        from: usize,
+        to: usize,
+        z: f64,
+    ) -> Arc<SensorReadings<Sample3D>> {
+        let samples = (from..to)
+            .map(|i| Sample3D::new(i as f64 * 0.1, [0.0, 0.0, z]))
+            .collect();
+        Arc::new(SensorReadings::from_vec(
+            "test",
+            sensor_type.clone(),
+            samples,
+        ))
+    }
+
+    #[test]
+    fn test_stop_after_samples() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let gyro = SensorType::Gyroscope(Uuid::new_v4());
+        let watcher = Watcher::new(vec![StopCondition::Samples(25)]);
+
+        watcher.process_samples(Uuid::new_v4(), readings(&acc, 0, 10, 9.8));
+        watcher.process_samples(Uuid::new_v4(), readings(&gyro, 0, 10, 0.0));
+        assert_eq!(watcher.get_triggered(), None);
+
+        watcher.process_samples(Uuid::new_v4(), readings(&acc, 10, 20, 9.8));
+        assert_eq!(watcher.get_triggered(), Some(StopCondition::Samples(25)));
+        assert_eq!(watcher.get_sample_count(), 30);
+    }
+
+    #[test]
+    fn test_stop_when_stationary() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let condition = StopCondition::Stationary {
+            duration: Duration::from_secs(2),
+            tolerance: 0.05,
+        };
+        let watcher = Watcher::new(vec![condition.clone()]);
+
+        // 1.9 seconds still, then the sensor moves and the still period restarts
+        watcher.process_samples(Uuid::new_v4(), readings(&acc, 0, 20, 9.8));
+        watcher.process_samples(Uuid::new_v4(), readings(&acc, 20, 21, 10.5));
+        watcher.process_samples(Uuid::new_v4(), readings(&acc, 21, 40, 9.8));
+        assert_eq!(watcher.get_triggered(), None);
+
+        watcher.process_samples(Uuid::new_v4(), readings(&acc, 40, 45, 9.82));
+        assert_eq!(watcher.get_triggered(), Some(condition));
+    }
+
+    #[test]
+    fn test_stop_on_disk_quota() {
+        let dir = std::env::temp_dir().join(format!("auto_stop_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("segments")).unwrap();
+        let condition = StopCondition::DiskQuota {
+            path: dir.clone(),
+            max_bytes: 100,
+        };
+        let watcher = Watcher::new(vec![condition.clone()]);
+
+        std::fs::write(dir.join("segments").join("0.csv"), [0u8; 60]).unwrap();
+        assert_eq!(watcher.get_triggered(), None);
+        std::fs::write(dir.join("manifest.json"), [0u8; 60]).unwrap();
+        assert_eq!(watcher.get_triggered(), Some(condition));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_elapsed() {
+        let condition = StopCondition::Elapsed(Duration::from_millis(200));
+        let watcher = Watcher::new(vec![StopCondition::Samples(10), condition.clone()]);
+
+        let result = tokio::time::timeout(Duration::from_secs(1), watcher.wait()).await;
+        assert_eq!(result, Ok(condition));
+    }
+}
@@ -0,0 +1,46 @@
+//! # Crate recorder-rs
+//!
+//! Sinks that persist sensor readings.
+//!
+//! The recorder logic (batching, segment rotation and manifests) lives in [`Recorder`], while
+//! the actual format is delegated to a [`StorageBackend`]. Backends only need to know how to
+//! open, append to, flush and finalize a segment.
+//!
+//! Available backends:
+//! - [`CsvBackend`]: one CSV file per segment, either tagged records or the nine axis layout of
+//!   the `csv_loader` test datasets.
+//! - [`MatBackend`]: one MATLAB v5 `.mat` file per segment, with one matrix per sensor.
+//!
+//! Multi-device sessions can be partitioned by tag with [`Partitioning`], writing the readings
+//! of each device to its own segments, in a subdirectory or with a file name prefix derived
+//! from the tag.
+//!
+//! Intervals where nothing was acquired on purpose, such as the pauses of a duty cycled
+//! source, are marked with [`Recorder::mark_gap`] and listed in the manifest as [`Gap`]s.
+//!
+//! Recordings are played back with [`ReplaySource`], for any sample type (raw `Sample3D`
+//! readings as well as processed `SampleQuaternion` orientations). Nine axis CSV files, such as
+//! the `csv_loader` test datasets, are played back with [`CsvPlaybackSource`].
+//!
+//! Recordings are aligned with video recorded at the same time by clapping or tapping the
+//! device in view of the camera. [`SyncDetector`] finds the clap in the accelerometer readings,
+//! and [`SyncOffset`] shifts the recording, or a [`ReplaySource`], to video time.
+//!
+//! Unattended captures are ended by an [`AutoStop`] sink, after a number of samples, once the
+//! recording reaches a disk quota or once the device stays still.
+
+pub mod auto_stop;
+pub mod models;
+pub mod recorder;
+pub mod replay;
+pub mod storage;
+pub mod sync;
+
+pub use auto_stop::{AutoStop, StopCondition};
+pub use models::errors::RecorderError;
+pub use models::record::{Gap, Manifest, Record, SegmentSummary};
+pub use recorder::{Partitioning, Recorder, RecorderConfig};
+pub use replay::csv_playback::CsvPlaybackSource;
+pub use replay::ReplaySource;
+pub use storage::{CsvBackend, CsvLayout, MatBackend, StorageBackend};
+pub use sync::{SyncDetector, SyncEvent, SyncOffset};
@@ -0,0 +1,36 @@
+//! Module errors
+
+/// Represents the different types of errors that can occur while recording.
+#[derive(Debug)]
+pub enum RecorderError {
+    /// Error reading or writing to the storage medium.
+    Io(String),
+
+    /// Error indicating that the backend was used out of order (e.g. appending without an open segment).
+    InvalidState(String),
+
+    /// Error reported by a storage backend.
+    Backend(String),
+
+    /// Error parsing stored records.
+    Parse(String),
+}
+
+impl From<std::io::Error> for RecorderError {
+    fn from(value: std::io::Error) -> Self {
+        RecorderError::Io(value.to_string())
+    }
+}
+
+impl std::fmt::Display for RecorderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecorderError::Io(e) => write!(f, "Io error: {}", e),
+            RecorderError::InvalidState(e) => write!(f, "Invalid state: {}", e),
+            RecorderError::Backend(e) => write!(f, "Backend error: {}", e),
+            RecorderError::Parse(e) => write!(f, "Parse error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RecorderError {}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+use imu_common::traits::{IMUSample, VecF64Convertible};
+use imu_common::types::sensors::SensorType;
+
+use crate::models::errors::RecorderError;
+
+/// Single row handed to storage backends.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub tag: String,
+    pub sensor_type: SensorType,
+    pub timestamp: f64,
+    pub values: Vec<f64>,
+}
+
+impl Record {
+    pub fn from_sample<S>(tag: &str, sensor_type: &SensorType, sample: S) -> Self
+    where
+        S: IMUSample,
+        S::Untimed: VecF64Convertible,
+    {
+        Self {
+            tag: tag.to_string(),
+            sensor_type: sensor_type.clone(),
+            timestamp: sample.get_timestamp_secs(),
+            values: sample.get_measurement().into(),
+        }
+    }
+
+    /// Converts the record back into a sample. Fails if the number of values doesn't match `S`.
+    pub fn to_sample<S>(&self) -> Result<S, RecorderError>
+    where
+        S: IMUSample,
+        S::Untimed: VecF64Convertible,
+    {
+        let measurement = S::Untimed::try_from(self.values.clone()).map_err(|_| {
+            RecorderError::Parse(format!(
+                "Invalid measurement for {} at {}",
+                self.sensor_type, self.timestamp
+            ))
+        })?;
+        Ok(S::from_measurement(self.timestamp, measurement))
+    }
+}
+
+/// Summary of a finalized segment, as stored in the manifest.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SegmentSummary {
+    pub index: usize,
+    /// Path of the segment, relative to the backend directory.
+    pub name: String,
+    /// Partition of the segment, if the recorder was partitioned by tag.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    pub n_records: usize,
+    pub first_timestamp: Option<f64>,
+    pub last_timestamp: Option<f64>,
+}
+
+impl SegmentSummary {
+    pub(crate) fn new(index: usize, name: String) -> Self {
+        Self {
+            index,
+            name,
+            tag: None,
+            n_records: 0,
+            first_timestamp: None,
+            last_timestamp: None,
+        }
+    }
+
+    pub(crate) fn update(&mut self, records: &[Record]) {
+        self.n_records += records.len();
+        for record in records {
+            let first = self.first_timestamp.get_or_insert(record.timestamp);
+            *first = first.min(record.timestamp);
+            let last = self.last_timestamp.get_or_insert(record.timestamp);
+            *last = last.max(record.timestamp);
+        }
+    }
+}
+
+/// Interval where no readings were acquired on purpose, e.g. while a duty cycled source is
+/// paused, so it isn't mistaken for lost data.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Gap {
+    pub start_timestamp: f64,
+    pub end_timestamp: f64,
+}
+
+impl Gap {
+    /// Returns true if `timestamp` lies within the gap.
+    pub fn contains(&self, timestamp: f64) -> bool {
+        (self.start_timestamp..=self.end_timestamp).contains(&timestamp)
+    }
+}
+
+/// List of segments written by a recorder.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub segments: Vec<SegmentSummary>,
+    /// Gaps marked in the recording, in the order they were marked.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gaps: Vec<Gap>,
+}
+
+impl Manifest {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, RecorderError> {
+        serde_json::from_str(data).map_err(|e| RecorderError::Parse(e.to_string()))
+    }
+
+    pub fn n_records(&self) -> usize {
+        self.segments.iter().map(|s| s.n_records).sum()
+    }
+}
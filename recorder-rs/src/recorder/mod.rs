@@ -0,0 +1,435 @@
+mod partition;
+pub(crate) mod sink;
+
+use imu_common::types::sensors::SensorTag;
+use publisher::ShutdownToken;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::models::errors::RecorderError;
+use crate::models::record::{Gap, Manifest, Record, SegmentSummary};
+use crate::storage::StorageBackend;
+
+pub use partition::{partition_name, Partitioning};
+
+const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// Recorder settings.
+#[derive(Clone, Debug)]
+pub struct RecorderConfig {
+    /// Number of records buffered before they are handed to the backend.
+    pub batch_size: usize,
+    /// Maximum number of records per segment. A new segment is opened once the limit is reached.
+    pub max_records_per_segment: Option<usize>,
+    /// File where the manifest is written every time a segment is finalized.
+    pub manifest_path: Option<PathBuf>,
+    /// Splits the segments by tag, so readings of several devices aren't interleaved.
+    pub partitioning: Partitioning,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_BATCH_SIZE,
+            max_records_per_segment: None,
+            manifest_path: None,
+            partitioning: Partitioning::default(),
+        }
+    }
+}
+
+/// Segments written by a backend. Without partitioning, the recorder has a single partition.
+struct Partition {
+    /// Name of the partition, derived from the tag of its readings.
+    name: Option<String>,
+    backend: Box<dyn StorageBackend>,
+    segment: Option<SegmentSummary>,
+    n_segments: usize,
+}
+
+impl Partition {
+    fn new(name: Option<String>, backend: Box<dyn StorageBackend>) -> Self {
+        Self {
+            name,
+            backend,
+            segment: None,
+            n_segments: 0,
+        }
+    }
+}
+
+struct RecorderManager {
+    /// Backend the partitions are created from, if the recorder is partitioned by tag.
+    /// Otherwise the backend is owned by the single partition.
+    template: Option<Box<dyn StorageBackend>>,
+    config: RecorderConfig,
+    batch: Vec<Record>,
+    partitions: Vec<Partition>,
+    manifest: Manifest,
+    is_shut_down: bool,
+}
+
+impl RecorderManager {
+    fn push(&mut self, records: Vec<Record>) -> Result<(), RecorderError> {
+        if self.is_shut_down {
+            return Err(RecorderError::InvalidState(
+                "Recorder shut down".to_string(),
+            ));
+        }
+        self.batch.extend(records);
+        if self.batch.len() >= self.config.batch_size {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the index of the partition of records with `tag`, opening it if needed.
+    fn partition(&mut self, tag: &str) -> Result<usize, RecorderError> {
+        let Some(template) = self.template.as_ref() else {
+            return Ok(0);
+        };
+        let name = partition_name(&SensorTag::new(tag));
+        if let Some(index) = self
+            .partitions
+            .iter()
+            .position(|p| p.name.as_ref() == Some(&name))
+        {
+            return Ok(index);
+        }
+        let backend = template.partition(&name, self.config.partitioning)?;
+        self.partitions.push(Partition::new(Some(name), backend));
+        Ok(self.partitions.len() - 1)
+    }
+
+    fn write_batch(&mut self) -> Result<(), RecorderError> {
+        // records of each partition, in the order they were received
+        let mut groups: Vec<(usize, Vec<Record>)> = Vec::new();
+        for record in std::mem::take(&mut self.batch) {
+            let index = self.partition(&record.tag)?;
+            match groups.iter_mut().find(|(i, _)| *i == index) {
+                Some((_, records)) => records.push(record),
+                None => groups.push((index, vec![record])),
+            }
+        }
+        for (index, records) in groups {
+            self.write_partition(index, records)?;
+        }
+        Ok(())
+    }
+
+    fn write_partition(
+        &mut self,
+        index: usize,
+        mut pending: Vec<Record>,
+    ) -> Result<(), RecorderError> {
+        while !pending.is_empty() {
+            let partition = &mut self.partitions[index];
+            let segment = match partition.segment.as_mut() {
+                Some(segment) => segment,
+                None => {
+                    let segment_index = partition.n_segments;
+                    let mut name = partition.backend.open_segment(segment_index)?;
+                    let mut segment = SegmentSummary::new(segment_index, String::new());
+                    if let Some(partition_name) = partition.name.as_ref() {
+                        name = self.config.partitioning.segment_name(partition_name, name);
+                        segment.tag = Some(partition_name.clone());
+                    }
+                    segment.name = name;
+                    partition.n_segments += 1;
+                    partition.segment.insert(segment)
+                }
+            };
+            let room = self
+                .config
+                .max_records_per_segment
+                .map(|max| max.saturating_sub(segment.n_records))
+                .unwrap_or(pending.len());
+            let remaining = pending.split_off(room.min(pending.len()));
+
+            partition.backend.append(&pending)?;
+            segment.update(&pending);
+            pending = remaining;
+
+            if self
+                .config
+                .max_records_per_segment
+                .is_some_and(|max| segment.n_records >= max)
+            {
+                self.close_segment(index)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn close_segment(&mut self, index: usize) -> Result<(), RecorderError> {
+        let partition = &mut self.partitions[index];
+        if let Some(segment) = partition.segment.take() {
+            partition.backend.finalize()?;
+            self.manifest.segments.push(segment);
+            self.write_manifest()?;
+        }
+        Ok(())
+    }
+
+    fn write_manifest(&self) -> Result<(), RecorderError> {
+        if let Some(path) = self.config.manifest_path.as_ref() {
+            std::fs::write(path, self.manifest.to_json())?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), RecorderError> {
+        self.write_batch()?;
+        for partition in self.partitions.iter_mut() {
+            if partition.segment.is_some() {
+                partition.backend.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<Manifest, RecorderError> {
+        self.write_batch()?;
+        for index in 0..self.partitions.len() {
+            self.close_segment(index)?;
+        }
+        Ok(self.manifest.clone())
+    }
+}
+
+/// Sink that records every reading it receives through a [`StorageBackend`].
+///
+/// Readings are batched, and segments are rotated once they reach `max_records_per_segment`.
+/// With [`Partitioning`], the readings of each tag are written to their own segments, rotated
+/// independently, through a backend created with [`StorageBackend::partition`]. Call [`Recorder::finalize`] to write pending records and close the last segment.
+#[derive(Clone)]
+pub struct Recorder {
+    manager: Arc<Mutex<RecorderManager>>,
+}
+
+impl Recorder {
+    pub fn new<B>(backend: B, config: RecorderConfig) -> Self
+    where
+        B: StorageBackend + 'static,
+    {
+        let backend: Box<dyn StorageBackend> = Box::new(backend);
+        let (template, partitions) = match config.partitioning {
+            Partitioning::Disabled => (None, vec![Partition::new(None, backend)]),
+            _ => (Some(backend), Vec::new()),
+        };
+        Self {
+            manager: Arc::new(Mutex::new(RecorderManager {
+                template,
+                config,
+                batch: Vec::new(),
+                partitions,
+                manifest: Manifest::default(),
+                is_shut_down: false,
+            })),
+        }
+    }
+
+    pub fn record(&self, records: Vec<Record>) -> Result<(), RecorderError> {
+        self.manager.lock().unwrap().push(records)
+    }
+
+    /// Writes buffered records to the backend and flushes the open segment.
+    pub fn flush(&self) -> Result<(), RecorderError> {
+        self.manager.lock().unwrap().flush()
+    }
+
+    /// Writes buffered records, closes the open segment and returns the manifest.
+    pub fn finalize(&self) -> Result<Manifest, RecorderError> {
+        self.manager.lock().unwrap().finalize()
+    }
+
+    /// Finalizes the recording once `token` is shut down. Records received afterwards are
+    /// rejected, so register it after the hooks detaching the recorder listeners.
+    pub fn finalize_on_shutdown(&self, token: &ShutdownToken) {
+        let manager = Arc::downgrade(&self.manager);
+        token.on_shutdown(move || {
+            let Some(manager) = manager.upgrade() else {
+                return;
+            };
+            let mut manager = manager.lock().unwrap();
+            manager.is_shut_down = true;
+            if let Err(e) = manager.finalize() {
+                log::error!("Error finalizing recording on shutdown: {}", e);
+            }
+        });
+    }
+
+    /// Marks the interval between `start_timestamp` and `end_timestamp` as a gap in the
+    /// recording, and writes the manifest. Fails if the gap ends before it starts.
+    pub fn mark_gap(&self, start_timestamp: f64, end_timestamp: f64) -> Result<(), RecorderError> {
+        if end_timestamp < start_timestamp {
+            return Err(RecorderError::InvalidState(format!(
+                "Gap ends at {} before it starts at {}",
+                end_timestamp, start_timestamp
+            )));
+        }
+        let mut manager = self.manager.lock().unwrap();
+        manager.manifest.gaps.push(Gap {
+            start_timestamp,
+            end_timestamp,
+        });
+        manager.write_manifest()
+    }
+
+    /// Returns the segments finalized so far.
+    pub fn get_manifest(&self) -> Manifest {
+        self.manager.lock().unwrap().manifest.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::types::sensors::SensorType;
+    use uuid::Uuid;
+
+    #[derive(Clone, Default)]
+    struct MemoryBackend {
+        segments: Arc<Mutex<Vec<Vec<Record>>>>,
+        open: bool,
+    }
+
+    impl StorageBackend for MemoryBackend {
+        fn open_segment(&mut self, index: usize) -> Result<String, RecorderError> {
+            self.open = true;
+            self.segments.lock().unwrap().push(Vec::new());
+            Ok(format!("segment_{}", index))
+        }
+
+        fn append(&mut self, records: &[Record]) -> Result<(), RecorderError> {
+            if !self.open {
+                return Err(RecorderError::InvalidState("closed".to_string()));
+            }
+            let mut segments = self.segments.lock().unwrap();
+            segments.last_mut().unwrap().extend_from_slice(records);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), RecorderError> {
+            Ok(())
+        }
+
+        fn finalize(&mut self) -> Result<(), RecorderError> {
+            self.open = false;
+            Ok(())
+        }
+    }
+
+    fn records(n: usize) -> Vec<Record> {
+        let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+        (0..n)
+            .map(|i| Record {
+                tag: "test".to_string(),
+                sensor_type: sensor_type.clone(),
+                timestamp: i as f64,
+                values: vec![1.0, 2.0, 3.0],
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batching() {
+        let backend = MemoryBackend::default();
+        let config = RecorderConfig {
+            batch_size: 4,
+            ..Default::default()
+        };
+        let recorder = Recorder::new(backend.clone(), config);
+
+        recorder.record(records(3)).unwrap();
+        assert!(backend.segments.lock().unwrap().is_empty());
+
+        recorder.record(records(1)).unwrap();
+        assert_eq!(backend.segments.lock().unwrap()[0].len(), 4);
+    }
+
+    #[test]
+    fn test_segment_rotation() {
+        let backend = MemoryBackend::default();
+        let config = RecorderConfig {
+            batch_size: 1,
+            max_records_per_segment: Some(4),
+            ..Default::default()
+        };
+        let recorder = Recorder::new(backend.clone(), config);
+
+        recorder.record(records(10)).unwrap();
+        let manifest = recorder.finalize().unwrap();
+
+        let sizes: Vec<usize> = manifest.segments.iter().map(|s| s.n_records).collect();
+        assert_eq!(sizes, vec![4, 4, 2]);
+        assert_eq!(manifest.segments[1].name, "segment_1");
+        assert_eq!(manifest.segments[1].first_timestamp, Some(4.0));
+        assert_eq!(manifest.segments[1].last_timestamp, Some(7.0));
+        assert_eq!(backend.segments.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_finalize_on_shutdown() {
+        let backend = MemoryBackend::default();
+        let recorder = Recorder::new(backend.clone(), RecorderConfig::default());
+        let token = ShutdownToken::new();
+        recorder.finalize_on_shutdown(&token);
+
+        recorder.record(records(5)).unwrap();
+        token.shutdown();
+
+        assert_eq!(recorder.get_manifest().n_records(), 5);
+        assert_eq!(backend.segments.lock().unwrap()[0].len(), 5);
+        assert!(matches!(
+            recorder.record(records(1)),
+            Err(RecorderError::InvalidState(_))
+        ));
+    }
+
+    #[test]
+    fn test_finalize_writes_pending_records() {
+        let backend = MemoryBackend::default();
+        let recorder = Recorder::new(backend.clone(), RecorderConfig::default());
+
+        recorder.record(records(5)).unwrap();
+        let manifest = recorder.finalize().unwrap();
+
+        assert_eq!(manifest.n_records(), 5);
+        assert_eq!(backend.segments.lock().unwrap()[0].len(), 5);
+    }
+
+    #[test]
+    fn test_partitioning_not_supported() {
+        let config = RecorderConfig {
+            batch_size: 1,
+            partitioning: Partitioning::Prefix,
+            ..Default::default()
+        };
+        let recorder = Recorder::new(MemoryBackend::default(), config);
+
+        assert!(matches!(
+            recorder.record(records(1)),
+            Err(RecorderError::Backend(_))
+        ));
+    }
+
+    #[test]
+    fn test_mark_gap() {
+        let recorder = Recorder::new(MemoryBackend::default(), RecorderConfig::default());
+        recorder.record(records(5)).unwrap();
+        recorder.mark_gap(5.0, 10.0).unwrap();
+        assert!(matches!(
+            recorder.mark_gap(20.0, 15.0),
+            Err(RecorderError::InvalidState(_))
+        ));
+
+        let manifest = recorder.finalize().unwrap();
+        assert_eq!(manifest.gaps.len(), 1);
+        assert!(manifest.gaps[0].contains(7.5));
+        assert_eq!(Manifest::from_json(&manifest.to_json()).unwrap(), manifest);
+        // manifests without gaps are still read
+        let manifest = Manifest::from_json(r#"{"segments": []}"#).unwrap();
+        assert!(manifest.gaps.is_empty());
+    }
+}
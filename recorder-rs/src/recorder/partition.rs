@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+use imu_common::types::sensors::SensorTag;
+
+const UNTAGGED_PARTITION: &str = "untagged";
+
+/// How the outputs of a [`Recorder`](crate::Recorder) fed by several devices are split.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Partitioning {
+    /// Readings of every tag are written to the same segments.
+    #[default]
+    Disabled,
+    /// Readings of each tag are written to their own segments, in the `<dir>/<tag>` subdirectory
+    /// of the backend directory.
+    Subdirectory,
+    /// Readings of each tag are written to their own segments, in the backend directory with
+    /// the `<tag>_<prefix>` prefix.
+    Prefix,
+}
+
+impl Partitioning {
+    /// Returns the directory and prefix of the segments of partition `name`, for a backend
+    /// writing to `dir` with `prefix`.
+    pub(crate) fn locate(&self, dir: &Path, prefix: &str, name: &str) -> (PathBuf, String) {
+        match self {
+            Partitioning::Disabled => (dir.to_path_buf(), prefix.to_string()),
+            Partitioning::Subdirectory => (dir.join(name), prefix.to_string()),
+            Partitioning::Prefix => (dir.to_path_buf(), format!("{}_{}", name, prefix)),
+        }
+    }
+
+    /// Returns the name of segment `segment` of partition `name`, relative to the backend
+    /// directory.
+    pub(crate) fn segment_name(&self, name: &str, segment: String) -> String {
+        match self {
+            Partitioning::Subdirectory => format!("{}/{}", name, segment),
+            _ => segment,
+        }
+    }
+}
+
+/// Returns the name of the partition of `tag`, usable as a file name. Characters other than
+/// ASCII alphanumerics, `-` and `_` are replaced by `_`.
+pub fn partition_name(tag: &SensorTag) -> String {
+    let name: String = tag
+        .inner()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        UNTAGGED_PARTITION.to_string()
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition_name() {
+        assert_eq!(
+            partition_name(&SensorTag::new("Left-wrist_2")),
+            "Left-wrist_2"
+        );
+        assert_eq!(
+            partition_name(&SensorTag::new("phone 1/imu")),
+            "phone_1_imu"
+        );
+        assert_eq!(partition_name(&SensorTag::new("")), "untagged");
+    }
+
+    #[test]
+    fn test_locate() {
+        let dir = Path::new("/data");
+        assert_eq!(
+            Partitioning::Subdirectory.locate(dir, "session", "Left"),
+            (PathBuf::from("/data/Left"), "session".to_string())
+        );
+        assert_eq!(
+            Partitioning::Prefix.locate(dir, "session", "Left"),
+            (PathBuf::from("/data"), "Left_session".to_string())
+        );
+        assert_eq!(
+            Partitioning::Subdirectory.segment_name("Left", "session_0000.csv".to_string()),
+            "Left/session_0000.csv"
+        );
+    }
+}
@@ -0,0 +1,37 @@
+use publisher::adapters;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::Recorder;
+use crate::models::record::Record;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource, VecF64Convertible};
+use imu_common::types::sensors::SensorType;
+
+impl<T, S> IMUSink<T, S> for Recorder
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, S>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        let tag = samples.get_sensor_tag();
+        let sensor_type = samples.get_sensor_type();
+        let records = samples
+            .get_samples()
+            .into_iter()
+            .map(|sample| Record::from_sample(tag, &sensor_type, sample))
+            .collect();
+        if let Err(e) = self.record(records) {
+            log::error!("Error recording samples: {}", e);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::ReplaySource;
+use crate::models::errors::RecorderError;
+use crate::models::record::Record;
+use crate::storage::read_nine_axis_rows;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUSource, Notifiable};
+use imu_common::types::sensors::{check_nine_axis_cluster, SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+
+/// Source playing back nine axis CSV files: the layout read by `csv_loader` and written by
+/// `CsvBackend` with `CsvLayout::NineAxis`.
+///
+/// Every row is split into a gyroscope, an accelerometer and a magnetometer reading, published
+/// under the matching sensor of the given cluster. This allows running the resampler and AHRS
+/// pipelines offline on recorded sessions.
+#[derive(Clone)]
+pub struct CsvPlaybackSource {
+    replay: ReplaySource<Sample3D>,
+}
+
+impl CsvPlaybackSource {
+    /// Loads the readings in `path`. `sensor_cluster` must contain an accelerometer, a
+    /// gyroscope and a magnetometer.
+    pub fn from_file(
+        tag: &str,
+        path: impl AsRef<Path>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Self, RecorderError> {
+        check_nine_axis_cluster(sensor_cluster)
+            .map_err(|e| RecorderError::InvalidState(e.to_string()))?;
+        let find = |kind: &str| {
+            sensor_cluster
+                .iter()
+                .find(|s| s.kind() == kind)
+                .cloned()
+                .expect("Nine axis cluster")
+        };
+        // column order of csv_loader files
+        let sensors = [
+            find("gyroscope"),
+            find("accelerometer"),
+            find("magnetometer"),
+        ];
+
+        let rows = read_nine_axis_rows(path)?;
+        let mut records = Vec::with_capacity(rows.len() * sensors.len());
+        for (timestamp, values) in rows {
+            for (sensor_type, values) in sensors.iter().zip(values) {
+                records.push(Record {
+                    tag: tag.to_string(),
+                    sensor_type: sensor_type.clone(),
+                    timestamp,
+                    values: values.to_vec(),
+                });
+            }
+        }
+        Ok(Self {
+            replay: ReplaySource::from_records(tag, records),
+        })
+    }
+
+    /// Returns the number of readings, three per row.
+    pub fn len(&self) -> usize {
+        self.replay.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replay.is_empty()
+    }
+
+    /// Publishes the readings reproducing the original timing, scaled by `speed` (e.g. 2.0
+    /// plays back twice as fast).
+    pub async fn start(&self, speed: f64) {
+        self.replay.start(Some(speed)).await
+    }
+}
+
+impl IMUSource<SensorReadings<Sample3D>, Sample3D> for CsvPlaybackSource {
+    fn get_tag(&self) -> &str {
+        self.replay.get_tag()
+    }
+
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.replay.get_available_sensors()
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        self.replay.unregister_listener(id)
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<Sample3D>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.replay.register_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<Sample3D>>) {
+        self.replay.notify_listeners(sensor_type, data)
+    }
+}
@@ -0,0 +1,119 @@
+pub(crate) mod csv_playback;
+pub(crate) mod source;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::errors::RecorderError;
+use crate::models::record::{Manifest, Record};
+use crate::storage::CsvBackend;
+use crate::sync::SyncOffset;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSource, VecF64Convertible};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use publisher::PublisherManager;
+
+/// Source publishing previously recorded readings.
+///
+/// Every sensor found in the recording is published under its recorded sensor type, so raw
+/// (`Sample3D`) and processed (e.g. `SampleQuaternion`) sessions are replayed the same way.
+/// Records whose values don't match `S` are skipped.
+#[derive(Clone)]
+pub struct ReplaySource<S>
+where
+    S: IMUSample,
+{
+    tag: String,
+    records: Arc<Vec<Record>>,
+    sensor_cluster: Vec<SensorType>,
+    publishers: PublisherManager<SensorReadings<S>, SensorType>,
+}
+
+impl<S> ReplaySource<S>
+where
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    /// Creates a source replaying `records` in timestamp order.
+    pub fn from_records(tag: &str, mut records: Vec<Record>) -> Self {
+        records.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        let mut sensor_cluster: Vec<SensorType> = Vec::new();
+        for record in &records {
+            if !sensor_cluster.contains(&record.sensor_type) {
+                sensor_cluster.push(record.sensor_type.clone());
+            }
+        }
+        Self {
+            tag: tag.to_string(),
+            publishers: PublisherManager::new(&sensor_cluster),
+            records: Arc::new(records),
+            sensor_cluster,
+        }
+    }
+
+    /// Creates a source replaying the CSV segments listed in `manifest`, stored in `dir`.
+    pub fn from_csv(
+        tag: &str,
+        dir: impl AsRef<Path>,
+        manifest: &Manifest,
+    ) -> Result<Self, RecorderError> {
+        let mut records = Vec::with_capacity(manifest.n_records());
+        for segment in &manifest.segments {
+            records.extend(CsvBackend::read_segment(dir.as_ref().join(&segment.name))?);
+        }
+        if manifest
+            .segments
+            .iter()
+            .any(|segment| segment.tag.is_some())
+        {
+            // segments of different partitions overlap in time
+            records.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        }
+        Ok(Self::from_records(tag, records))
+    }
+
+    /// Replays the readings in video time, shifted by `offset`.
+    pub fn with_offset(mut self, offset: SyncOffset) -> Self {
+        let mut records = self.records.as_ref().clone();
+        offset.apply(&mut records);
+        self.records = Arc::new(records);
+        self
+    }
+
+    /// Returns the number of recorded readings.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Publishes the recorded readings. With `speed`, the original timing is reproduced scaled
+    /// by `speed` (e.g. 2.0 replays twice as fast). Without it, readings are published as fast
+    /// as possible.
+    pub async fn start(&self, speed: Option<f64>) {
+        let mut previous: Option<f64> = None;
+        for record in self.records.iter() {
+            if let (Some(speed), Some(previous)) = (speed, previous) {
+                let wait = (record.timestamp - previous) / speed.max(f64::EPSILON);
+                if wait > 0.0 {
+                    tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+                }
+            }
+            previous = Some(record.timestamp);
+
+            match record.to_sample::<S>() {
+                Ok(sample) => {
+                    let readings = SensorReadings::from_vec(
+                        &record.tag,
+                        record.sensor_type.clone(),
+                        vec![sample],
+                    );
+                    self.notify_listeners(record.sensor_type.clone(), Arc::new(readings));
+                }
+                Err(e) => log::warn!("Skipping record: {}", e),
+            }
+        }
+    }
+}
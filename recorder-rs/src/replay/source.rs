@@ -0,0 +1,36 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::ReplaySource;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUSample, IMUSource, Notifiable};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+
+impl<S> IMUSource<SensorReadings<S>, S> for ReplaySource<S>
+where
+    S: IMUSample,
+{
+    fn get_tag(&self) -> &str {
+        self.tag.as_str()
+    }
+
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.sensor_cluster.clone()
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<S>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<S>>) {
+        self.publishers.notify_listeners(sensor_type, data);
+    }
+}
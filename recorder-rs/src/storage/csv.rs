@@ -0,0 +1,317 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::StorageBackend;
+use crate::models::errors::RecorderError;
+use crate::models::record::Record;
+use crate::recorder::Partitioning;
+use imu_common::types::sensors::SensorType;
+
+const CSV_HEADER: &str = "tag,sensor,timestamp,values";
+const NINE_AXIS_HEADER: &str = "Time (ms),Gyro X (rad/s),Gyro Y (rad/s),Gyro Z (rad/s),Accel X (m/s²),Accel Y (m/s²),Accel Z (m/s²),Mag X (µT),Mag Y (µT),Mag Z (µT)";
+const N_AXES: usize = 3;
+const N_NINE_AXIS_SENSORS: usize = 3;
+
+/// Column layout of the CSV segments.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CsvLayout {
+    /// One row per record: `tag,sensor,timestamp,v0,v1,...`, where `sensor` uses the
+    /// `SensorType` display format. Supports any sensor and sample type.
+    #[default]
+    Tagged,
+    /// One row per timestamp: `timestamp,gyro x/y/z,accel x/y/z,mag x/y/z`, with timestamps
+    /// in milliseconds. This is the layout read by `csv_loader::CsvColumnMapper` and
+    /// `PhyphoxMock`, so recordings can be used as mock datasets and test fixtures.
+    ///
+    /// Only the accelerometer, gyroscope and magnetometer of the first tag received are
+    /// recorded. Readings are aligned by timestamp, and sensors missing at a timestamp take
+    /// their last value.
+    NineAxis,
+}
+
+/// Writes every segment to `<dir>/<prefix>_<index>.csv`, using the [`CsvLayout::Tagged`]
+/// layout unless configured otherwise with `with_layout`.
+pub struct CsvBackend {
+    dir: PathBuf,
+    prefix: String,
+    layout: CsvLayout,
+    rows: NineAxisRows,
+    writer: Option<BufWriter<File>>,
+}
+
+impl CsvBackend {
+    pub fn new(dir: impl Into<PathBuf>, prefix: &str) -> Result<Self, RecorderError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            prefix: prefix.to_string(),
+            layout: CsvLayout::default(),
+            rows: NineAxisRows::default(),
+            writer: None,
+        })
+    }
+
+    pub fn with_layout(mut self, layout: CsvLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Reads back the records of a segment written with the [`CsvLayout::Tagged`] layout.
+    /// [`CsvLayout::NineAxis`] segments are read with `csv_loader`.
+    pub fn read_segment(path: impl AsRef<Path>) -> Result<Vec<Record>, RecorderError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut records = Vec::new();
+        for (n, line) in reader.lines().enumerate() {
+            let line = line?;
+            if n == 0 {
+                if line == NINE_AXIS_HEADER {
+                    return Err(RecorderError::Parse(
+                        "Nine axis segments don't contain sensor types".to_string(),
+                    ));
+                }
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            records.push(
+                parse_row(&line)
+                    .map_err(|e| RecorderError::Parse(format!("Line {}: {}", n + 1, e)))?,
+            );
+        }
+        Ok(records)
+    }
+
+    fn writer(&mut self) -> Result<&mut BufWriter<File>, RecorderError> {
+        self.writer
+            .as_mut()
+            .ok_or(RecorderError::InvalidState("No open segment".to_string()))
+    }
+}
+
+impl StorageBackend for CsvBackend {
+    fn open_segment(&mut self, index: usize) -> Result<String, RecorderError> {
+        if self.writer.is_some() {
+            return Err(RecorderError::InvalidState(
+                "Segment already open".to_string(),
+            ));
+        }
+        let name = format!("{}_{:04}.csv", self.prefix, index);
+        let mut writer = BufWriter::new(File::create(self.dir.join(&name))?);
+        match self.layout {
+            CsvLayout::Tagged => writeln!(writer, "{}", CSV_HEADER)?,
+            CsvLayout::NineAxis => writeln!(writer, "{}", NINE_AXIS_HEADER)?,
+        }
+        self.writer = Some(writer);
+        Ok(name)
+    }
+
+    fn append(&mut self, records: &[Record]) -> Result<(), RecorderError> {
+        if self.layout == CsvLayout::NineAxis {
+            self.writer()?;
+            for record in records {
+                self.rows.push(record);
+            }
+            let rows = self.rows.take_ready(false);
+            return write_nine_axis_rows(self.writer()?, &rows);
+        }
+        let writer = self.writer()?;
+        for record in records {
+            write!(
+                writer,
+                "{},{},{}",
+                record.tag, record.sensor_type, record.timestamp
+            )?;
+            for value in &record.values {
+                write!(writer, ",{}", value)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), RecorderError> {
+        self.writer()?.flush()?;
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<(), RecorderError> {
+        if let Some(mut writer) = self.writer.take() {
+            write_nine_axis_rows(&mut writer, &self.rows.take_ready(true))?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn partition(
+        &self,
+        name: &str,
+        partitioning: Partitioning,
+    ) -> Result<Box<dyn StorageBackend>, RecorderError> {
+        let (dir, prefix) = partitioning.locate(&self.dir, &self.prefix, name);
+        Ok(Box::new(
+            CsvBackend::new(dir, &prefix)?.with_layout(self.layout),
+        ))
+    }
+}
+
+/// Timestamp in seconds and gyroscope, accelerometer and magnetometer readings.
+pub(crate) type NineAxisRow = (f64, [[f64; N_AXES]; N_NINE_AXIS_SENSORS]);
+
+/// Reads the rows of a [`CsvLayout::NineAxis`] file, such as the `csv_loader` test datasets.
+/// The header (first non empty line) is skipped and columns after the magnetometer are ignored.
+pub(crate) fn read_nine_axis_rows(
+    path: impl AsRef<Path>,
+) -> Result<Vec<NineAxisRow>, RecorderError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut rows = Vec::new();
+    let mut header_found = false;
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !header_found {
+            header_found = true;
+            continue;
+        }
+        let values = line
+            .split(',')
+            .take(1 + N_AXES * N_NINE_AXIS_SENSORS)
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|e| RecorderError::Parse(format!("Line {}: {}", n + 1, e)))?;
+        if values.len() != 1 + N_AXES * N_NINE_AXIS_SENSORS {
+            return Err(RecorderError::Parse(format!(
+                "Line {}: expected {} columns",
+                n + 1,
+                1 + N_AXES * N_NINE_AXIS_SENSORS
+            )));
+        }
+        let axes = |sensor: usize| -> [f64; N_AXES] {
+            std::array::from_fn(|axis| values[1 + sensor * N_AXES + axis])
+        };
+        rows.push((values[0] / 1000.0, [axes(0), axes(1), axes(2)]));
+    }
+    Ok(rows)
+}
+
+fn write_nine_axis_rows(
+    writer: &mut BufWriter<File>,
+    rows: &[NineAxisRow],
+) -> Result<(), RecorderError> {
+    for (timestamp, values) in rows {
+        write!(writer, "{}", timestamp * 1000.0)?;
+        for value in values.iter().flatten() {
+            write!(writer, ",{}", value)?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Aligns the readings of the three sensors of a cluster into rows, ordered by timestamp.
+///
+/// Readings of every sensor are expected in order, so a row can be written once every sensor
+/// has been seen at or after its timestamp. Missing values are held from the previous row.
+#[derive(Default)]
+struct NineAxisRows {
+    tag: Option<String>,
+    pending: Vec<(f64, [Option<[f64; N_AXES]>; N_NINE_AXIS_SENSORS])>,
+    last_seen: [Option<f64>; N_NINE_AXIS_SENSORS],
+    held: [Option<[f64; N_AXES]>; N_NINE_AXIS_SENSORS],
+}
+
+impl NineAxisRows {
+    fn push(&mut self, record: &Record) {
+        // column order of csv_loader files
+        let index = match record.sensor_type {
+            SensorType::Gyroscope(_) => 0,
+            SensorType::Accelerometer(_) => 1,
+            SensorType::Magnetometer(_) => 2,
+            SensorType::Other(..) | SensorType::Vendor(..) => return,
+        };
+        let Ok(values) = <[f64; N_AXES]>::try_from(record.values.as_slice()) else {
+            return;
+        };
+        if self.tag.get_or_insert_with(|| record.tag.clone()) != &record.tag {
+            return;
+        }
+
+        self.last_seen[index] = Some(record.timestamp);
+        let position = self
+            .pending
+            .partition_point(|(timestamp, _)| *timestamp < record.timestamp);
+        match self.pending.get_mut(position) {
+            Some((timestamp, row)) if *timestamp == record.timestamp => row[index] = Some(values),
+            _ => {
+                let mut row = [None; N_NINE_AXIS_SENSORS];
+                row[index] = Some(values);
+                self.pending.insert(position, (record.timestamp, row));
+            }
+        }
+    }
+
+    /// Returns the rows that can't change anymore, or every pending row if `all` is set. Rows
+    /// before every sensor has been seen once are dropped.
+    fn take_ready(&mut self, all: bool) -> Vec<NineAxisRow> {
+        let watermark = if all {
+            f64::INFINITY
+        } else {
+            match self.last_seen.iter().copied().collect::<Option<Vec<f64>>>() {
+                Some(last_seen) => last_seen.into_iter().fold(f64::INFINITY, f64::min),
+                None => return Vec::new(),
+            }
+        };
+        let n_ready = self
+            .pending
+            .partition_point(|(timestamp, _)| *timestamp <= watermark);
+
+        let mut rows = Vec::with_capacity(n_ready);
+        for (timestamp, row) in self.pending.drain(..n_ready) {
+            for (held, value) in self.held.iter_mut().zip(row) {
+                if value.is_some() {
+                    *held = value;
+                }
+            }
+            if let [Some(gyro), Some(accel), Some(mag)] = self.held {
+                rows.push((timestamp, [gyro, accel, mag]));
+            }
+        }
+        rows
+    }
+}
+
+fn parse_row(line: &str) -> Result<Record, String> {
+    let mut fields = line.split(',');
+    let tag = fields.next().ok_or("Missing tag")?;
+    let sensor = fields.next().ok_or("Missing sensor")?;
+    let timestamp = fields
+        .next()
+        .ok_or("Missing timestamp")?
+        .parse::<f64>()
+        .map_err(|e| e.to_string())?;
+    let values = fields
+        .map(|v| v.parse::<f64>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<f64>, String>>()?;
+    Ok(Record {
+        tag: tag.to_string(),
+        sensor_type: parse_sensor_type(sensor)?,
+        timestamp,
+        values,
+    })
+}
+
+/// Parses a sensor written with the `SensorType` display format, keeping the case of custom
+/// sensor names.
+fn parse_sensor_type(sensor: &str) -> Result<SensorType, String> {
+    match SensorType::try_from(sensor)? {
+        SensorType::Other(id, _) => {
+            let name = sensor.split("::").next().unwrap_or_default();
+            Ok(SensorType::Other(id, name.to_string()))
+        }
+        sensor_type => Ok(sensor_type),
+    }
+}
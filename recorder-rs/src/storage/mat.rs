@@ -0,0 +1,318 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::StorageBackend;
+use crate::models::errors::RecorderError;
+use crate::models::record::Record;
+use crate::recorder::Partitioning;
+use imu_common::types::sensors::SensorType;
+
+const HEADER_TEXT: &str = "MATLAB 5.0 MAT-file, Created by: imu-rs";
+const HEADER_TEXT_LEN: usize = 116;
+const VERSION: u16 = 0x0100;
+
+const MI_INT8: u32 = 1;
+const MI_INT32: u32 = 5;
+const MI_UINT32: u32 = 6;
+const MI_DOUBLE: u32 = 9;
+const MI_MATRIX: u32 = 14;
+const MX_DOUBLE_CLASS: u32 = 6;
+const MAX_NAME_LEN: usize = 63;
+
+/// Writes every segment to `<dir>/<prefix>_<index>.mat` as a MATLAB v5 file.
+///
+/// Every sensor of every tag is stored as its own variable, named `<tag>_<sensor kind>`
+/// (e.g. `Left_accelerometer`). Variables are `N x (1 + n_values)` double matrices whose first
+/// column holds the timestamps in seconds, so `Sample3D` readings become `N x 4` matrices.
+///
+/// A MAT file can't be appended to, so records are kept in memory and the file is written
+/// when the segment is finalized. Use `max_records_per_segment` to bound memory usage on long
+/// sessions.
+pub struct MatBackend {
+    dir: PathBuf,
+    prefix: String,
+    segment: Option<(PathBuf, Vec<Record>)>,
+}
+
+impl MatBackend {
+    pub fn new(dir: impl Into<PathBuf>, prefix: &str) -> Result<Self, RecorderError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            prefix: prefix.to_string(),
+            segment: None,
+        })
+    }
+
+    /// Writes `records` to `path`. Used to convert existing recordings, for instance segments
+    /// read with `CsvBackend::read_segment`.
+    pub fn write_file(path: impl AsRef<Path>, records: &[Record]) -> Result<(), RecorderError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&to_mat(records)?)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl StorageBackend for MatBackend {
+    fn open_segment(&mut self, index: usize) -> Result<String, RecorderError> {
+        if self.segment.is_some() {
+            return Err(RecorderError::InvalidState(
+                "Segment already open".to_string(),
+            ));
+        }
+        let name = format!("{}_{:04}.mat", self.prefix, index);
+        self.segment = Some((self.dir.join(&name), Vec::new()));
+        Ok(name)
+    }
+
+    fn append(&mut self, records: &[Record]) -> Result<(), RecorderError> {
+        let (_, segment) = self
+            .segment
+            .as_mut()
+            .ok_or(RecorderError::InvalidState("No open segment".to_string()))?;
+        segment.extend_from_slice(records);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), RecorderError> {
+        // nothing is written until the segment is finalized
+        match self.segment {
+            Some(_) => Ok(()),
+            None => Err(RecorderError::InvalidState("No open segment".to_string())),
+        }
+    }
+
+    fn finalize(&mut self) -> Result<(), RecorderError> {
+        if let Some((path, records)) = self.segment.take() {
+            Self::write_file(path, &records)?;
+        }
+        Ok(())
+    }
+
+    fn partition(
+        &self,
+        name: &str,
+        partitioning: Partitioning,
+    ) -> Result<Box<dyn StorageBackend>, RecorderError> {
+        let (dir, prefix) = partitioning.locate(&self.dir, &self.prefix, name);
+        Ok(Box::new(MatBackend::new(dir, &prefix)?))
+    }
+}
+
+/// Records of one sensor, stored as a single variable.
+struct Variable<'a> {
+    tag: &'a str,
+    sensor_type: &'a SensorType,
+    records: Vec<&'a Record>,
+}
+
+fn to_mat(records: &[Record]) -> Result<Vec<u8>, RecorderError> {
+    let mut variables: Vec<Variable> = Vec::new();
+    for record in records {
+        match variables
+            .iter_mut()
+            .find(|v| v.tag == record.tag && *v.sensor_type == record.sensor_type)
+        {
+            Some(variable) => variable.records.push(record),
+            None => variables.push(Variable {
+                tag: &record.tag,
+                sensor_type: &record.sensor_type,
+                records: vec![record],
+            }),
+        }
+    }
+
+    let mut out = header();
+    let mut names: Vec<String> = Vec::with_capacity(variables.len());
+    for mut variable in variables {
+        variable
+            .records
+            .sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        let name = unique_name(
+            &format!("{}_{}", variable.tag, variable.sensor_type.kind()),
+            &names,
+        );
+        write_matrix(&mut out, &name, &variable)?;
+        names.push(name);
+    }
+    Ok(out)
+}
+
+fn header() -> Vec<u8> {
+    let mut header = format!("{:<width$}", HEADER_TEXT, width = HEADER_TEXT_LEN).into_bytes();
+    // no subsystem data
+    header.extend([0u8; 8]);
+    header.extend(VERSION.to_le_bytes());
+    header.extend(b"IM");
+    header
+}
+
+fn write_matrix(out: &mut Vec<u8>, name: &str, variable: &Variable) -> Result<(), RecorderError> {
+    let n_values = variable.records[0].values.len();
+    if variable.records.iter().any(|r| r.values.len() != n_values) {
+        return Err(RecorderError::Backend(format!(
+            "Readings of {} have different lengths",
+            variable.sensor_type
+        )));
+    }
+    let n_rows = variable.records.len();
+    let n_cols = n_values + 1;
+
+    // MATLAB matrices are stored column by column
+    let mut data = Vec::with_capacity(n_rows * n_cols * 8);
+    data.extend(
+        variable
+            .records
+            .iter()
+            .flat_map(|r| r.timestamp.to_le_bytes()),
+    );
+    for col in 0..n_values {
+        data.extend(
+            variable
+                .records
+                .iter()
+                .flat_map(|r| r.values[col].to_le_bytes()),
+        );
+    }
+
+    let dims = [to_i32(n_rows)?, to_i32(n_cols)?];
+    let mut matrix = Vec::new();
+    write_element(
+        &mut matrix,
+        MI_UINT32,
+        &[MX_DOUBLE_CLASS, 0].map(u32::to_le_bytes).concat(),
+    )?;
+    write_element(&mut matrix, MI_INT32, &dims.map(i32::to_le_bytes).concat())?;
+    write_element(&mut matrix, MI_INT8, name.as_bytes())?;
+    write_element(&mut matrix, MI_DOUBLE, &data)?;
+    write_element(out, MI_MATRIX, &matrix)
+}
+
+/// Appends a data element (type, size and data padded to 8 bytes) to `out`.
+fn write_element(out: &mut Vec<u8>, data_type: u32, data: &[u8]) -> Result<(), RecorderError> {
+    let size = u32::try_from(data.len())
+        .map_err(|_| RecorderError::Backend("Variable too large for a v5 MAT file".to_string()))?;
+    out.extend(data_type.to_le_bytes());
+    out.extend(size.to_le_bytes());
+    out.extend(data);
+    out.resize(out.len() + (8 - data.len() % 8) % 8, 0);
+    Ok(())
+}
+
+fn to_i32(value: usize) -> Result<i32, RecorderError> {
+    i32::try_from(value)
+        .map_err(|_| RecorderError::Backend("Variable too large for a v5 MAT file".to_string()))
+}
+
+/// Returns a valid MATLAB identifier for `name` that isn't in `taken`: letters, digits and
+/// underscores, starting with a letter and at most 63 characters long.
+fn unique_name(name: &str, taken: &[String]) -> String {
+    let mut base: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !base.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        base.insert_str(0, "s_");
+    }
+    base.truncate(MAX_NAME_LEN);
+
+    let mut candidate = base.clone();
+    let mut n = 2;
+    while taken.contains(&candidate) {
+        let suffix = format!("_{}", n);
+        let mut prefix = base.clone();
+        prefix.truncate(MAX_NAME_LEN - suffix.len());
+        candidate = prefix + &suffix;
+        n += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    /// Reads back the (name, dimensions, data) of every variable.
+    fn parse(bytes: &[u8]) -> Vec<(String, [i32; 2], Vec<f64>)> {
+        let u32_at = |b: &[u8], i: usize| u32::from_le_bytes(b[i..i + 4].try_into().unwrap());
+        let mut variables = Vec::new();
+        let mut offset = 128;
+        while offset < bytes.len() {
+            assert_eq!(u32_at(bytes, offset), MI_MATRIX);
+            let size = u32_at(bytes, offset + 4) as usize;
+            let matrix = &bytes[offset + 8..offset + 8 + size];
+            let mut elements = Vec::new();
+            let mut i = 0;
+            while i < matrix.len() {
+                let size = u32_at(matrix, i + 4) as usize;
+                elements.push(&matrix[i + 8..i + 8 + size]);
+                i += 8 + size.div_ceil(8) * 8;
+            }
+            let dims = [0, 4].map(|i| u32_at(elements[1], i) as i32);
+            let name = String::from_utf8(elements[2].to_vec()).unwrap();
+            let data = elements[3]
+                .chunks(8)
+                .map(|c| f64::from_le_bytes(c.try_into().unwrap()))
+                .collect();
+            variables.push((name, dims, data));
+            offset += 8 + size;
+        }
+        variables
+    }
+
+    fn record(tag: &str, sensor_type: &SensorType, timestamp: f64, values: Vec<f64>) -> Record {
+        Record {
+            tag: tag.to_string(),
+            sensor_type: sensor_type.clone(),
+            timestamp,
+            values,
+        }
+    }
+
+    #[test]
+    fn test_mat_layout() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let orientation = SensorType::Other(Uuid::new_v4(), "Quaternion".to_string());
+        let records = vec![
+            record("Left", &acc, 0.1, vec![4.0, 5.0, 6.0]),
+            record("2nd device", &orientation, 0.0, vec![1.0, 0.0, 0.0, 0.0]),
+            record("Left", &acc, 0.0, vec![1.0, 2.0, 3.0]),
+        ];
+        let bytes = to_mat(&records).unwrap();
+        assert!(bytes.starts_with(HEADER_TEXT.as_bytes()));
+        assert_eq!(&bytes[126..128], b"IM");
+
+        let variables = parse(&bytes);
+        assert_eq!(variables.len(), 2);
+        let (name, dims, data) = &variables[0];
+        assert_eq!(name, "Left_accelerometer");
+        assert_eq!(*dims, [2, 4]);
+        // column major, sorted by timestamp
+        assert_eq!(*data, vec![0.0, 0.1, 1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+        assert_eq!(variables[1].0, "s_2nd_device_Quaternion");
+        assert_eq!(variables[1].1, [1, 5]);
+
+        let mut invalid = records.clone();
+        invalid.push(record("Left", &acc, 0.2, vec![1.0]));
+        assert!(to_mat(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let taken = vec!["Test_accelerometer".to_string()];
+        assert_eq!(
+            unique_name("Test_accelerometer", &taken),
+            "Test_accelerometer_2"
+        );
+        let long = "a".repeat(100);
+        let taken = vec![unique_name(&long, &[])];
+        assert_eq!(taken[0].len(), MAX_NAME_LEN);
+        let name = unique_name(&long, &taken);
+        assert_eq!(name.len(), MAX_NAME_LEN);
+        assert!(name.ends_with("_2"));
+    }
+}
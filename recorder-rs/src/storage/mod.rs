@@ -0,0 +1,37 @@
+mod csv;
+mod mat;
+
+pub(crate) use csv::read_nine_axis_rows;
+pub use csv::{CsvBackend, CsvLayout};
+pub use mat::MatBackend;
+
+use crate::models::errors::RecorderError;
+use crate::models::record::Record;
+use crate::recorder::Partitioning;
+
+/// Storage format used by a [`Recorder`](crate::Recorder).
+///
+/// A recording is split into segments. The recorder opens a segment, appends batches of
+/// records to it, flushes periodically and finalizes it before opening the next one.
+pub trait StorageBackend: Send {
+    /// Opens segment `index` and returns its name (e.g. the file name).
+    fn open_segment(&mut self, index: usize) -> Result<String, RecorderError>;
+    /// Appends records to the open segment.
+    fn append(&mut self, records: &[Record]) -> Result<(), RecorderError>;
+    /// Persists buffered data of the open segment.
+    fn flush(&mut self) -> Result<(), RecorderError>;
+    /// Closes the open segment.
+    fn finalize(&mut self) -> Result<(), RecorderError>;
+    /// Returns a backend configured like this one, writing the segments of partition `name`
+    /// where `partitioning` places them. Backends that can't be partitioned return an error.
+    fn partition(
+        &self,
+        name: &str,
+        _partitioning: Partitioning,
+    ) -> Result<Box<dyn StorageBackend>, RecorderError> {
+        Err(RecorderError::Backend(format!(
+            "Cannot create partition {}: backend doesn't support partitioning",
+            name
+        )))
+    }
+}
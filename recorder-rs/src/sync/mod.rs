@@ -0,0 +1,338 @@
+//! Module sync
+//!
+//! Alignment of IMU recordings with video recorded at the same time. A sharp event visible in
+//! both, such as a clap or a tap on the device, is detected in the accelerometer readings.
+//! Its timestamp, paired with the time of the frame showing it, gives the offset from IMU time
+//! to video time.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::errors::RecorderError;
+use crate::models::record::Record;
+use imu_common::types::sensors::SensorType;
+
+const DEFAULT_THRESHOLD: f64 = 15.0;
+const DEFAULT_BASELINE_MILLIS: f64 = 500.0;
+const DEFAULT_PEAK_WINDOW_MILLIS: f64 = 50.0;
+const DEFAULT_MIN_SEPARATION_MILLIS: f64 = 1000.0;
+
+/// Sharp acceleration detected in a recording.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncEvent {
+    pub tag: String,
+    /// Accelerometer the event was detected on, formatted as a `SensorType`.
+    pub sensor_type: String,
+    /// Timestamp of the peak, interpolated between samples.
+    pub timestamp: f64,
+    /// Deviation of the magnitude of the acceleration from its baseline at the peak, in m/s².
+    pub peak: f64,
+}
+
+impl SyncEvent {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, RecorderError> {
+        serde_json::from_str(data).map_err(|e| RecorderError::Parse(e.to_string()))
+    }
+}
+
+/// Detects claps and taps in accelerometer recordings.
+///
+/// The magnitude of the acceleration is compared with its mean over the preceding baseline
+/// window, so gravity and slow motion are ignored. Once the deviation exceeds the threshold, the
+/// largest deviation within the peak window is the event, timestamped by fitting a parabola
+/// through it and its neighbours.
+///
+/// ```
+/// use imu_common::types::sensors::SensorType;
+/// use recorder_rs::{Record, SyncDetector, SyncOffset};
+/// use uuid::Uuid;
+///
+/// let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+/// let mut records: Vec<Record> = (0..200)
+///     .map(|i| Record {
+///         tag: "phone".to_string(),
+///         sensor_type: sensor_type.clone(),
+///         timestamp: i as f64 * 0.01,
+///         values: vec![0.0, 0.0, if i == 120 { 40.0 } else { 9.8 }],
+///     })
+///     .collect();
+///
+/// let events = SyncDetector::new().detect(&records);
+/// assert_eq!(events.len(), 1);
+///
+/// // the clap is seen in the frame at 5.0 s of the video
+/// let offset = SyncOffset::align(&events[0], 5.0);
+/// offset.apply(&mut records);
+/// assert!((records[120].timestamp - 5.0).abs() < 1e-3);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SyncDetector {
+    threshold: f64,
+    baseline_millis: f64,
+    peak_window_millis: f64,
+    min_separation_millis: f64,
+}
+
+impl Default for SyncDetector {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD,
+            baseline_millis: DEFAULT_BASELINE_MILLIS,
+            peak_window_millis: DEFAULT_PEAK_WINDOW_MILLIS,
+            min_separation_millis: DEFAULT_MIN_SEPARATION_MILLIS,
+        }
+    }
+}
+
+impl SyncDetector {
+    /// Detects deviations above 15 m/s² from the mean of the last 500 ms, at least 1 s apart.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the deviation from the baseline, in m/s², that triggers an event.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the window preceding each sample whose mean magnitude is the baseline.
+    pub fn with_baseline_millis(mut self, baseline_millis: f64) -> Self {
+        self.baseline_millis = baseline_millis;
+        self
+    }
+
+    /// Sets how long after the trigger the peak is searched for.
+    pub fn with_peak_window_millis(mut self, peak_window_millis: f64) -> Self {
+        self.peak_window_millis = peak_window_millis;
+        self
+    }
+
+    /// Sets the minimum time between events, so the ringing of a clap isn't detected again.
+    pub fn with_min_separation_millis(mut self, min_separation_millis: f64) -> Self {
+        self.min_separation_millis = min_separation_millis;
+        self
+    }
+
+    /// Returns the events of every accelerometer found in `records`, by tag and sensor, in
+    /// timestamp order. Records of other sensors, or without three values, are ignored.
+    pub fn detect(&self, records: &[Record]) -> Vec<SyncEvent> {
+        let mut accelerometers: Vec<(&str, &SensorType)> = Vec::new();
+        for record in records {
+            let key = (record.tag.as_str(), &record.sensor_type);
+            if matches!(record.sensor_type, SensorType::Accelerometer(_))
+                && !accelerometers.contains(&key)
+            {
+                accelerometers.push(key);
+            }
+        }
+
+        let mut events = Vec::new();
+        for (tag, sensor_type) in accelerometers {
+            let mut samples: Vec<(f64, f64)> = records
+                .iter()
+                .filter(|r| r.tag == tag && &r.sensor_type == sensor_type && r.values.len() == 3)
+                .map(|r| {
+                    (
+                        r.timestamp,
+                        r.values.iter().map(|v| v * v).sum::<f64>().sqrt(),
+                    )
+                })
+                .collect();
+            samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+            events.extend(
+                self.detect_peaks(&samples)
+                    .into_iter()
+                    .map(|(timestamp, peak)| SyncEvent {
+                        tag: tag.to_string(),
+                        sensor_type: sensor_type.to_string(),
+                        timestamp,
+                        peak,
+                    }),
+            );
+        }
+        events.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        events
+    }
+
+    /// Returns the timestamp and deviation of the peaks of `(timestamp, magnitude)` samples.
+    fn detect_peaks(&self, samples: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        let deviations = self.deviations(samples);
+        let mut peaks = Vec::new();
+        let mut next_secs = f64::NEG_INFINITY;
+        let mut i = 0;
+        while i < samples.len() {
+            if samples[i].0 < next_secs || deviations[i] < self.threshold {
+                i += 1;
+                continue;
+            }
+            let end_secs = samples[i].0 + self.peak_window_millis / 1000.0;
+            let mut k = i;
+            let mut j = i;
+            while j < samples.len() && samples[j].0 <= end_secs {
+                if deviations[j] > deviations[k] {
+                    k = j;
+                }
+                j += 1;
+            }
+            let timestamp = refine_peak(samples, &deviations, k);
+            peaks.push((timestamp, deviations[k]));
+            next_secs = timestamp + self.min_separation_millis / 1000.0;
+            i = j;
+        }
+        peaks
+    }
+
+    /// Returns the absolute deviation of every magnitude from the mean of the preceding window.
+    fn deviations(&self, samples: &[(f64, f64)]) -> Vec<f64> {
+        let window_secs = self.baseline_millis / 1000.0;
+        let mut start = 0;
+        let mut sum = 0.0;
+        samples
+            .iter()
+            .enumerate()
+            .map(|(i, (timestamp, magnitude))| {
+                while start < i && samples[start].0 < timestamp - window_secs {
+                    sum -= samples[start].1;
+                    start += 1;
+                }
+                let deviation = match i - start {
+                    0 => 0.0,
+                    n => (magnitude - sum / n as f64).abs(),
+                };
+                sum += magnitude;
+                deviation
+            })
+            .collect()
+    }
+}
+
+/// Interpolates the timestamp of the peak at `k` with a parabola through its neighbours.
+fn refine_peak(samples: &[(f64, f64)], deviations: &[f64], k: usize) -> f64 {
+    if k == 0 || k + 1 >= samples.len() {
+        return samples[k].0;
+    }
+    let (left, center, right) = (deviations[k - 1], deviations[k], deviations[k + 1]);
+    let curvature = left - 2.0 * center + right;
+    if curvature >= 0.0 {
+        return samples[k].0;
+    }
+    let shift = (0.5 * (left - right) / curvature).clamp(-0.5, 0.5);
+    let step = if shift < 0.0 {
+        samples[k].0 - samples[k - 1].0
+    } else {
+        samples[k + 1].0 - samples[k].0
+    };
+    samples[k].0 + shift * step
+}
+
+/// Offset from IMU time to video time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncOffset {
+    pub offset_secs: f64,
+}
+
+impl SyncOffset {
+    pub fn new(offset_secs: f64) -> Self {
+        Self { offset_secs }
+    }
+
+    /// Returns the offset aligning `event` with the video frame at `video_timestamp_secs`.
+    pub fn align(event: &SyncEvent, video_timestamp_secs: f64) -> Self {
+        Self::new(video_timestamp_secs - event.timestamp)
+    }
+
+    /// Converts an IMU timestamp to video time.
+    pub fn to_video_time(&self, timestamp_secs: f64) -> f64 {
+        timestamp_secs + self.offset_secs
+    }
+
+    /// Shifts the timestamps of `records` to video time.
+    pub fn apply(&self, records: &mut [Record]) {
+        for record in records {
+            record.timestamp = self.to_video_time(record.timestamp);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    /// Accelerometer at rest sampled every `period_secs` for 4 s, with claps at `claps_secs`.
+    fn claps(sensor_type: &SensorType, period_secs: f64, claps_secs: &[f64]) -> Vec<Record> {
+        (0..(4.0 / period_secs) as usize)
+            .map(|i| {
+                let timestamp = i as f64 * period_secs;
+                // 5 ms wide pulses
+                let pulse: f64 = claps_secs
+                    .iter()
+                    .map(|clap_secs| 30.0 * (-((timestamp - clap_secs) / 0.005).powi(2)).exp())
+                    .sum();
+                Record {
+                    tag: "test".to_string(),
+                    sensor_type: sensor_type.clone(),
+                    timestamp,
+                    values: vec![pulse, 0.0, 9.81],
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_between_samples() {
+        let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+        let records = claps(&sensor_type, 0.002, &[2.0031]);
+        let events = SyncDetector::new().detect(&records);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sensor_type, sensor_type.to_string());
+        // closer than the sampling period
+        assert!(
+            (events[0].timestamp - 2.0031).abs() < 0.0005,
+            "{:?}",
+            events
+        );
+        assert!(events[0].peak > 15.0);
+    }
+
+    #[test]
+    fn test_ignore_other_sensors() {
+        let gyroscope = SensorType::Gyroscope(Uuid::new_v4());
+        assert!(SyncDetector::new()
+            .detect(&claps(&gyroscope, 0.01, &[2.0]))
+            .is_empty());
+        let accelerometer = SensorType::Accelerometer(Uuid::new_v4());
+        let detector = SyncDetector::new().with_threshold(50.0);
+        assert!(detector
+            .detect(&claps(&accelerometer, 0.01, &[2.0]))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_min_separation() {
+        let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+        // a second clap 0.5 s and a third 2 s after the first one
+        let records = claps(&sensor_type, 0.002, &[1.0, 1.5, 3.0]);
+        let events = SyncDetector::new().detect(&records);
+        assert_eq!(events.len(), 2);
+        assert!((events[1].timestamp - 3.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_align() {
+        let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+        let mut records = claps(&sensor_type, 0.01, &[2.0]);
+        let event = SyncDetector::new().detect(&records).remove(0);
+        let event = SyncEvent::from_json(&event.to_json()).unwrap();
+
+        let offset = SyncOffset::align(&event, 12.5);
+        assert!((offset.to_video_time(event.timestamp) - 12.5).abs() < 1e-12);
+        offset.apply(&mut records);
+        assert!((records[200].timestamp - 12.5).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,330 @@
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
+use imu_common::types::sensors::SensorClusterBuilder;
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::{Sample3D, SampleQuaternion};
+use publisher::Listener;
+use recorder_rs::{
+    CsvBackend, CsvLayout, CsvPlaybackSource, Manifest, MatBackend, Partitioning, Record, Recorder,
+    RecorderConfig, ReplaySource, SyncDetector, SyncOffset,
+};
+use test_utils::csv_loader::{self, CsvColumnMapper};
+
+#[test]
+fn test_csv_recording() {
+    let dir = std::env::temp_dir().join(format!("recorder-{}", Uuid::new_v4()));
+    let manifest_path = dir.join("manifest.json");
+    let backend = CsvBackend::new(&dir, "test").unwrap();
+    let config = RecorderConfig {
+        batch_size: 2,
+        max_records_per_segment: Some(3),
+        manifest_path: Some(manifest_path.clone()),
+        ..Default::default()
+    };
+    let recorder = Recorder::new(backend, config);
+
+    let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+    let samples = (0..5)
+        .map(|i| Sample3D::new(i as f64, [1.0, 2.0, 3.0]))
+        .collect();
+    let readings = SensorReadings::from_vec("Test", sensor_type.clone(), samples);
+    recorder.process_samples(Uuid::new_v4(), Arc::new(readings));
+    let manifest = recorder.finalize().unwrap();
+
+    let stored_manifest =
+        Manifest::from_json(&std::fs::read_to_string(&manifest_path).unwrap()).unwrap();
+    assert_eq!(stored_manifest, manifest);
+    assert_eq!(manifest.segments.len(), 2);
+    assert_eq!(manifest.n_records(), 5);
+
+    let segment = std::fs::read_to_string(dir.join(&manifest.segments[0].name)).unwrap();
+    let rows: Vec<&str> = segment.lines().collect();
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[1], format!("Test,{},0,1,2,3", sensor_type));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_mat_recording() {
+    let dir = std::env::temp_dir().join(format!("recorder-{}", Uuid::new_v4()));
+    let config = RecorderConfig {
+        max_records_per_segment: Some(3),
+        ..Default::default()
+    };
+    let recorder = Recorder::new(MatBackend::new(&dir, "test").unwrap(), config);
+
+    let sensor_type = SensorType::Gyroscope(Uuid::new_v4());
+    let samples = (0..5)
+        .map(|i| Sample3D::new(i as f64, [1.0, 2.0, 3.0]))
+        .collect();
+    let readings = SensorReadings::from_vec("Test", sensor_type, samples);
+    recorder.process_samples(Uuid::new_v4(), Arc::new(readings));
+    let manifest = recorder.finalize().unwrap();
+
+    assert_eq!(manifest.segments.len(), 2);
+    for segment in &manifest.segments {
+        let data = std::fs::read(dir.join(&segment.name)).unwrap();
+        assert!(data.starts_with(b"MATLAB 5.0 MAT-file"));
+        // header, matrix tag, flags, dimensions, padded name and N x 4 doubles
+        let expected = 128 + 8 + 16 + 16 + 8 + 16 + 8 + segment.n_records * 4 * 8;
+        assert_eq!(data.len(), expected);
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_nine_axis_layout() {
+    let dir = std::env::temp_dir().join(format!("recorder-{}", Uuid::new_v4()));
+    let backend = CsvBackend::new(&dir, "dataset")
+        .unwrap()
+        .with_layout(CsvLayout::NineAxis);
+    let recorder = Recorder::new(backend, RecorderConfig::default());
+
+    let cluster = SensorClusterBuilder::new()
+        .nine_axis()
+        .other("Temperature")
+        .build()
+        .unwrap();
+    // gyroscope publishes an extra reading, and the accelerometer misses one
+    let timestamps = [
+        vec![0.0, 0.05, 0.1],
+        vec![0.0, 0.05, 0.075, 0.1],
+        vec![0.0, 0.1],
+        vec![0.0],
+    ];
+    for (i, (sensor_type, timestamps)) in cluster.iter().zip(timestamps).enumerate() {
+        let samples = timestamps
+            .into_iter()
+            .map(|t| Sample3D::new(t, [i as f64, t, 0.0]))
+            .collect();
+        let readings = SensorReadings::from_vec("Test", sensor_type.clone(), samples);
+        recorder.process_samples(Uuid::new_v4(), Arc::new(readings));
+    }
+    let manifest = recorder.finalize().unwrap();
+
+    let segment = dir.join(&manifest.segments[0].name);
+    let segment = segment.to_str().unwrap();
+    assert!(CsvBackend::read_segment(segment).is_err());
+    let mut mapper = CsvColumnMapper::new();
+    mapper.add_timestamp().add_gyro().add_accel().add_mag();
+    let rows = csv_loader::load_csv_columns::<Vec<f64>>(segment, &mapper.columns()).unwrap();
+
+    assert_eq!(rows.len(), 4);
+    assert_eq!(
+        rows[2],
+        vec![75.0, 1.0, 0.075, 0.0, 0.0, 0.05, 0.0, 2.0, 0.0, 0.0]
+    );
+    assert_eq!(rows[3][0], 100.0);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_quaternion_record_and_replay() {
+    let dir = std::env::temp_dir().join(format!("recorder-{}", Uuid::new_v4()));
+    let backend = CsvBackend::new(&dir, "orientation").unwrap();
+    let recorder = Recorder::new(backend, RecorderConfig::default());
+
+    let sensor_type = SensorType::Other(Uuid::new_v4(), "Quaternion".to_string());
+    let samples: Vec<SampleQuaternion> = (0..10)
+        .map(|i| {
+            let angle = i as f64 * 0.1;
+            SampleQuaternion::new(
+                i as f64 * 0.01,
+                [(angle / 2.0).cos(), 0.0, 0.0, (angle / 2.0).sin()],
+            )
+        })
+        .collect();
+    let readings = SensorReadings::from_vec("Test", sensor_type.clone(), samples.clone());
+    recorder.process_samples(Uuid::new_v4(), Arc::new(readings));
+    let manifest = recorder.finalize().unwrap();
+
+    let replay = ReplaySource::<SampleQuaternion>::from_csv("Replay", &dir, &manifest).unwrap();
+    assert_eq!(replay.get_available_sensors(), vec![sensor_type.clone()]);
+    assert_eq!(replay.len(), 10);
+
+    let replayed = Arc::new(Mutex::new(Vec::new()));
+    let mut listener = Listener::new({
+        let replayed = replayed.clone();
+        move |_id, readings: Arc<SensorReadings<SampleQuaternion>>| {
+            assert_eq!(readings.get_sensor_tag(), "Test");
+            replayed.lock().unwrap().extend(readings.get_samples());
+        }
+    });
+    replay
+        .register_listener(&mut listener, &sensor_type)
+        .unwrap();
+    replay.start(Some(10.0)).await;
+
+    let replayed = replayed.lock().unwrap();
+    assert_eq!(replayed.len(), samples.len());
+    for (replayed, expected) in replayed.iter().zip(&samples) {
+        assert_eq!(replayed.get_timestamp_secs(), expected.get_timestamp_secs());
+        let angle = replayed
+            .get_measurement()
+            .inner()
+            .angle_to(&expected.get_measurement().inner());
+        assert!(angle < 1e-9);
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_csv_playback() {
+    let cluster = SensorType::cluster_for_tag("Playback");
+    let playback = CsvPlaybackSource::from_file(
+        "Playback",
+        "../test-utils/test_data/sensor_readings.csv",
+        &cluster,
+    )
+    .unwrap();
+    assert_eq!(playback.len(), 21 * 3);
+    assert!(CsvPlaybackSource::from_file(
+        "Playback",
+        "../test-utils/test_data/sensor_readings.csv",
+        &cluster[..2]
+    )
+    .is_err());
+
+    let played = Arc::new(Mutex::new(Vec::new()));
+    let mut listener = Listener::new({
+        let played = played.clone();
+        move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+            let mut played = played.lock().unwrap();
+            played.extend(readings.get_samples());
+        }
+    });
+    playback
+        .register_listener(&mut listener, &cluster[0])
+        .unwrap();
+
+    // one second of readings, ten times faster
+    let start = std::time::Instant::now();
+    playback.start(10.0).await;
+    assert!(start.elapsed() >= std::time::Duration::from_millis(90));
+
+    let played = played.lock().unwrap();
+    assert_eq!(played.len(), 21);
+    assert_eq!(played[1].get_timestamp_secs(), 0.05);
+    assert_eq!(played[0].get_measurement().inner(), [0.0, 0.0, 9.81]);
+}
+
+fn tagged_readings(
+    tag: &str,
+    sensor_type: &SensorType,
+    from: usize,
+    to: usize,
+) -> Arc<SensorReadings<Sample3D>> {
+    let samples = (from..to)
+        .map(|i| Sample3D::new(i as f64, [1.0, 2.0, 3.0]))
+        .collect();
+    Arc::new(SensorReadings::from_vec(tag, sensor_type.clone(), samples))
+}
+
+#[test]
+fn test_partitioned_recording() {
+    let dir = std::env::temp_dir().join(format!("recorder-{}", Uuid::new_v4()));
+    let config = RecorderConfig {
+        batch_size: 4,
+        max_records_per_segment: Some(3),
+        partitioning: Partitioning::Subdirectory,
+        ..Default::default()
+    };
+    let recorder = Recorder::new(CsvBackend::new(&dir, "test").unwrap(), config);
+
+    let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+    recorder.process_samples(Uuid::new_v4(), tagged_readings("Left", &sensor_type, 0, 2));
+    recorder.process_samples(
+        Uuid::new_v4(),
+        tagged_readings("Right hand", &sensor_type, 0, 4),
+    );
+    recorder.process_samples(Uuid::new_v4(), tagged_readings("Left", &sensor_type, 2, 4));
+    let manifest = recorder.finalize().unwrap();
+
+    let mut names: Vec<&str> = manifest.segments.iter().map(|s| s.name.as_str()).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "Left/test_0000.csv",
+            "Left/test_0001.csv",
+            "Right_hand/test_0000.csv",
+            "Right_hand/test_0001.csv",
+        ]
+    );
+    for segment in &manifest.segments {
+        let tag = segment.tag.clone().unwrap();
+        let records = CsvBackend::read_segment(dir.join(&segment.name)).unwrap();
+        assert_eq!(records.len(), segment.n_records);
+        // devices aren't interleaved
+        assert!(records
+            .iter()
+            .all(|record| record.tag.replace(' ', "_") == tag));
+    }
+
+    // partitions are merged back in timestamp order
+    let source = ReplaySource::<Sample3D>::from_csv("Replay", &dir, &manifest).unwrap();
+    assert_eq!(source.len(), 8);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_prefix_partitioning() {
+    let dir = std::env::temp_dir().join(format!("recorder-{}", Uuid::new_v4()));
+    let config = RecorderConfig {
+        partitioning: Partitioning::Prefix,
+        ..Default::default()
+    };
+    let recorder = Recorder::new(MatBackend::new(&dir, "test").unwrap(), config);
+
+    let sensor_type = SensorType::Gyroscope(Uuid::new_v4());
+    recorder.process_samples(Uuid::new_v4(), tagged_readings("Left", &sensor_type, 0, 2));
+    recorder.process_samples(Uuid::new_v4(), tagged_readings("Right", &sensor_type, 0, 2));
+    let manifest = recorder.finalize().unwrap();
+
+    assert_eq!(manifest.segments.len(), 2);
+    assert!(dir.join("Left_test_0000.mat").exists());
+    assert!(dir.join("Right_test_0000.mat").exists());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_replay_in_video_time() {
+    let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+    let records: Vec<Record> = (0..300)
+        .map(|i| Record {
+            tag: "Test".to_string(),
+            sensor_type: sensor_type.clone(),
+            timestamp: 100.0 + i as f64 * 0.01,
+            values: vec![0.0, if i == 150 { 30.0 } else { 0.0 }, 9.81],
+        })
+        .collect();
+    let events = SyncDetector::new().detect(&records);
+    assert_eq!(events.len(), 1);
+
+    // the clap is seen 2 s into the video
+    let offset = SyncOffset::align(&events[0], 2.0);
+    let replay = ReplaySource::<Sample3D>::from_records("Replay", records).with_offset(offset);
+    let replayed = Arc::new(Mutex::new(Vec::new()));
+    let mut listener = Listener::new({
+        let replayed = replayed.clone();
+        move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+            replayed.lock().unwrap().extend(readings.get_samples());
+        }
+    });
+    replay
+        .register_listener(&mut listener, &sensor_type)
+        .unwrap();
+    replay.start(None).await;
+
+    let replayed = replayed.lock().unwrap();
+    assert!((replayed[150].get_timestamp_secs() - 2.0).abs() < 1e-3);
+    assert!((replayed[0].get_timestamp_secs() - 0.5).abs() < 1e-3);
+}
@@ -0,0 +1,130 @@
+//! Module config
+
+use std::time::Duration;
+
+use crate::errors::RedisStreamsError;
+use imu_common::types::sensors::SensorType;
+
+const DEFAULT_KEY: &str = "imu:{tag}:{sensor}:{uuid}";
+const DEFAULT_MAX_LEN: usize = 100_000;
+const DEFAULT_RECONNECT_DELAY_MILLIS: u64 = 1000;
+const DEFAULT_CAPACITY: usize = 1000;
+
+/// Configuration of [`RedisSink`](crate::RedisSink).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RedisConfig {
+    /// Url of the server, e.g. `redis://127.0.0.1:6379`.
+    pub url: String,
+    /// Key of the streams. `{tag}`, `{sensor}` and `{uuid}` are replaced by the tag of the
+    /// readings, the kind of sensor (e.g. `accelerometer`) and its id.
+    pub key: String,
+    /// Maximum number of entries of every stream. Streams aren't trimmed if `None`.
+    pub max_len: Option<usize>,
+    /// Trims streams with `MAXLEN ~`, letting Redis keep a few more entries than `max_len`,
+    /// which is much cheaper than exact trimming.
+    pub approximate_trim: bool,
+    /// Delay between reconnection attempts.
+    pub reconnect_delay: Duration,
+    /// Number of batches of readings queued while disconnected. Readings are dropped once it
+    /// is full.
+    pub capacity: usize,
+}
+
+impl RedisConfig {
+    /// Returns the default configuration for the server at `url`.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            key: DEFAULT_KEY.to_string(),
+            max_len: Some(DEFAULT_MAX_LEN),
+            approximate_trim: true,
+            reconnect_delay: Duration::from_millis(DEFAULT_RECONNECT_DELAY_MILLIS),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Returns an InvalidConfig error if the configuration can't be used.
+    pub fn validate(&self) -> Result<(), RedisStreamsError> {
+        let invalid = |e: &str| Err(RedisStreamsError::InvalidConfig(e.to_string()));
+        if let Err(e) = redis::Client::open(self.url.as_str()) {
+            return Err(RedisStreamsError::InvalidConfig(e.to_string()));
+        }
+        if self.key.is_empty() {
+            return invalid("Empty key");
+        }
+        if self.max_len == Some(0) {
+            return invalid("Max length must be positive");
+        }
+        if self.capacity == 0 {
+            return invalid("Capacity must be positive");
+        }
+        Ok(())
+    }
+
+    /// Returns the key of the stream of `sensor_type` with tag `tag`.
+    ///
+    /// Key separators (`:`) and whitespace are replaced by `_` in the tag and sensor kind, so
+    /// every sensor gets its own key segment.
+    pub fn format_key(&self, tag: &str, sensor_type: &SensorType) -> String {
+        let uuid = match sensor_type {
+            SensorType::Accelerometer(uuid)
+            | SensorType::Gyroscope(uuid)
+            | SensorType::Magnetometer(uuid)
+            | SensorType::Other(uuid, _)
+            | SensorType::Vendor(uuid, _) => uuid,
+        };
+        self.key
+            .replace("{tag}", &escape_segment(tag))
+            .replace("{sensor}", &escape_segment(sensor_type.kind()))
+            .replace("{uuid}", &uuid.to_string())
+    }
+}
+
+fn escape_segment(segment: &str) -> String {
+    segment.replace(|c: char| c == ':' || c.is_whitespace(), "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_format_key() {
+        let mut config = RedisConfig::new("redis://127.0.0.1");
+        let id = Uuid::new_v4();
+
+        assert_eq!(
+            config.format_key("Phone", &SensorType::Gyroscope(id)),
+            format!("imu:Phone:gyroscope:{}", id)
+        );
+        assert_eq!(
+            config.format_key("Lab:Phone 1", &SensorType::Other(id, "orientation".into())),
+            format!("imu:Lab_Phone_1:orientation:{}", id)
+        );
+
+        config.key = "sensors:{uuid}".to_string();
+        assert_eq!(
+            config.format_key("Phone", &SensorType::Gyroscope(id)),
+            format!("sensors:{}", id)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = RedisConfig::new("redis://127.0.0.1:6379");
+        assert!(config.validate().is_ok());
+
+        let mut invalid = config.clone();
+        invalid.url = "http://127.0.0.1".to_string();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = config.clone();
+        invalid.max_len = Some(0);
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = config;
+        invalid.capacity = 0;
+        assert!(invalid.validate().is_err());
+    }
+}
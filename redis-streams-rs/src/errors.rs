@@ -0,0 +1,21 @@
+//! Module errors
+
+/// Errors of the Redis Streams sink.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RedisStreamsError {
+    /// Invalid sink configuration.
+    InvalidConfig(String),
+    /// Batches of readings were dropped, because the queue is full or the sink was stopped.
+    Dropped(String),
+}
+
+impl std::fmt::Display for RedisStreamsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RedisStreamsError::InvalidConfig(e) => write!(f, "Invalid configuration: {}", e),
+            RedisStreamsError::Dropped(e) => write!(f, "Dropped: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RedisStreamsError {}
@@ -0,0 +1,29 @@
+//! # Crate redis-streams-rs
+//!
+//! ## redis-streams-rs
+//!
+//! The `redis-streams-rs` crate pushes readings into [Redis Streams](https://redis.io/docs/latest/develop/data-types/streams/),
+//! which many lab dashboards already consume.
+//!
+//! [`RedisSink`] is an `IMUSink` adding an entry per sample to the stream of its sensor,
+//! `imu:<tag>:<sensor>:<uuid>` by default (see [`RedisConfig::key`]). Entries hold the
+//! timestamp in seconds and the measurement, e.g. for 3D samples:
+//!
+//! ```text
+//! XADD imu:Phone:accelerometer:67e55044-10b1-426f-9247-bb680e5fe0c8 MAXLEN ~ 100000 * timestamp 1.25 x 0.1 y 0 z 9.8
+//! ```
+//!
+//! Measurement fields are `value` for scalars, `x`, `y`, `z` for 3D samples and `w`, `x`, `y`,
+//! `z` for quaternions. Streams are trimmed to [`RedisConfig::max_len`] entries.
+//!
+//! The connection is kept alive in a background task, reconnecting whenever it is lost.
+//! Readings received while disconnected are queued up to [`RedisConfig::capacity`] batches,
+//! and dropped afterwards.
+
+pub mod config;
+pub mod errors;
+mod sink;
+
+pub use config::RedisConfig;
+pub use errors::RedisStreamsError;
+pub use sink::{run_client, RedisSink};
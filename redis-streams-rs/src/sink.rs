@@ -0,0 +1,236 @@
+use log::{info, warn};
+use publisher::{adapters, DropGuard, ShutdownToken};
+use redis::aio::MultiplexedConnection;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Notify};
+use uuid::Uuid;
+
+use crate::config::RedisConfig;
+use crate::errors::RedisStreamsError;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource, VecF64Convertible};
+use imu_common::types::sensors::SensorType;
+
+/// Entries added to a stream, as `(timestamp, measurement)`.
+struct StreamBatch {
+    key: String,
+    entries: Vec<(f64, Vec<f64>)>,
+}
+
+/// Returns the names of the measurement fields of samples with `n_values` values.
+fn measurement_fields(n_values: usize) -> Vec<String> {
+    let names: &[&str] = match n_values {
+        1 => &["value"],
+        3 => &["x", "y", "z"],
+        4 => &["w", "x", "y", "z"],
+        _ => &[],
+    };
+    if names.is_empty() {
+        (0..n_values).map(|n| format!("v{}", n)).collect()
+    } else {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+}
+
+/// Sink adding the readings it receives to Redis Streams, one stream per sensor.
+///
+/// The client disconnects once every clone of the sink is dropped, including the ones held by
+/// the sources it is attached to.
+#[derive(Clone)]
+pub struct RedisSink {
+    sender: mpsc::Sender<StreamBatch>,
+    config: Arc<RedisConfig>,
+    abort_signal: Arc<Notify>,
+    connected: Arc<AtomicBool>,
+    dropped_batches: Arc<AtomicUsize>,
+    shutdown: ShutdownToken,
+    // `None` in the clone used by the connection task, so it doesn't keep the sink alive
+    _guard: Option<Arc<DropGuard>>,
+}
+
+impl RedisSink {
+    pub fn get_config(&self) -> &RedisConfig {
+        &self.config
+    }
+
+    /// Returns true while the connection with the server is up.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of batches of readings dropped because the queue was full.
+    pub fn get_dropped_batches(&self) -> usize {
+        self.dropped_batches.load(Ordering::Relaxed)
+    }
+
+    /// Disconnects from the server and ends the connection task.
+    pub fn stop(&self) {
+        self.abort_signal.notify_one();
+    }
+
+    /// Adds an entry per `(timestamp, measurement)` to the stream `key`.
+    /// Returns an error if the queue is full.
+    pub fn push(
+        &self,
+        key: String,
+        entries: Vec<(f64, Vec<f64>)>,
+    ) -> Result<(), RedisStreamsError> {
+        self.sender
+            .try_send(StreamBatch { key, entries })
+            .map_err(|e| {
+                self.dropped_batches.fetch_add(1, Ordering::Relaxed);
+                RedisStreamsError::Dropped(e.to_string())
+            })
+    }
+
+    async fn run(&self, client: redis::Client, mut receiver: mpsc::Receiver<StreamBatch>) {
+        let mut connection: Option<MultiplexedConnection> = None;
+        // batch that failed to be added, retried once reconnected
+        let mut pending: Option<StreamBatch> = None;
+        loop {
+            let Some(con) = connection.as_mut() else {
+                match client.get_multiplexed_async_connection().await {
+                    Ok(con) => {
+                        info!("Connected to Redis server {}", self.config.url);
+                        self.connected.store(true, Ordering::Relaxed);
+                        connection = Some(con);
+                        continue;
+                    }
+                    Err(e) => warn!("Error connecting to Redis server: {}", e),
+                }
+                tokio::select! {
+                    _ = self.abort_signal.notified() => break,
+                    _ = self.shutdown.wait() => break,
+                    _ = tokio::time::sleep(self.config.reconnect_delay) => continue,
+                }
+            };
+            let batch = match pending.take() {
+                Some(batch) => batch,
+                None => tokio::select! {
+                    _ = self.abort_signal.notified() => break,
+                    _ = self.shutdown.wait() => break,
+                    batch = receiver.recv() => match batch {
+                        Some(batch) => batch,
+                        None => break,
+                    },
+                },
+            };
+            if let Err(e) = self.pipeline(&batch).query_async::<()>(con).await {
+                warn!("Disconnected from Redis server: {}", e);
+                self.connected.store(false, Ordering::Relaxed);
+                connection = None;
+                pending = Some(batch);
+            }
+        }
+        self.connected.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns the `XADD` commands adding the entries of `batch`.
+    fn pipeline(&self, batch: &StreamBatch) -> redis::Pipeline {
+        let mut pipeline = redis::pipe();
+        let Some((_, measurement)) = batch.entries.first() else {
+            return pipeline;
+        };
+        let fields = measurement_fields(measurement.len());
+        for (timestamp, measurement) in batch.entries.iter() {
+            let command = pipeline.cmd("XADD").arg(&batch.key);
+            if let Some(max_len) = self.config.max_len {
+                command.arg("MAXLEN");
+                if self.config.approximate_trim {
+                    command.arg("~");
+                }
+                command.arg(max_len);
+            }
+            command.arg("*").arg("timestamp").arg(*timestamp);
+            for (field, value) in fields.iter().zip(measurement) {
+                command.arg(field).arg(*value);
+            }
+            command.ignore();
+        }
+        pipeline
+    }
+}
+
+impl<T, S> IMUSink<T, S> for RedisSink
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, S>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        let key = self
+            .config
+            .format_key(samples.get_sensor_tag(), &samples.get_sensor_type());
+        let entries = samples
+            .iter()
+            .map(|sample| (sample.get_timestamp_secs(), sample.get_measurement().into()))
+            .collect();
+        if let Err(e) = self.push(key, entries) {
+            warn!("Dropping readings: {}", e);
+        }
+    }
+}
+
+/// Starts a Redis client connected to the server of `config` in a background task. Must be
+/// called inside a tokio runtime.
+///
+/// Returns an InvalidConfig error if `config` is invalid.
+///
+/// # Returns
+///
+/// Returns a tuple containing:
+/// * A `tokio::task::JoinHandle<()>` of the connection, which ends when `RedisSink::stop` is
+///   called, the sink is dropped or the global `ShutdownToken` is shut down.
+/// * The `RedisSink` to attach to sources.
+pub fn run_client(
+    config: RedisConfig,
+) -> Result<(tokio::task::JoinHandle<()>, RedisSink), RedisStreamsError> {
+    config.validate()?;
+    let client = redis::Client::open(config.url.as_str())
+        .map_err(|e| RedisStreamsError::InvalidConfig(e.to_string()))?;
+    let (sender, receiver) = mpsc::channel(config.capacity);
+
+    let abort_signal = Arc::new(Notify::new());
+    let sink = RedisSink {
+        sender,
+        config: Arc::new(config),
+        abort_signal: abort_signal.clone(),
+        connected: Arc::new(AtomicBool::new(false)),
+        dropped_batches: Arc::new(AtomicUsize::new(0)),
+        shutdown: ShutdownToken::global(),
+        _guard: None,
+    };
+    let handle = tokio::spawn({
+        let sink = sink.clone();
+        async move { sink.run(client, receiver).await }
+    });
+    let guard = DropGuard::new(move || abort_signal.notify_one());
+    Ok((
+        handle,
+        RedisSink {
+            _guard: Some(Arc::new(guard)),
+            ..sink
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measurement_fields() {
+        assert_eq!(measurement_fields(1), vec!["value"]);
+        assert_eq!(measurement_fields(4), vec!["w", "x", "y", "z"]);
+        assert_eq!(measurement_fields(2), vec!["v0", "v1"]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use uuid::Uuid;
+
+use imu_common::traits::{IMUReadings, IMUSink};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use redis_streams_rs::{run_client, RedisConfig};
+
+/// Reads a command sent by the client as an array of bulk strings.
+async fn read_command(reader: &mut BufReader<TcpStream>) -> Option<Vec<String>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await.ok()? == 0 {
+        return None;
+    }
+    let n_args: usize = line.trim().strip_prefix('*')?.parse().ok()?;
+    let mut args = Vec::with_capacity(n_args);
+    for _ in 0..n_args {
+        line.clear();
+        reader.read_line(&mut line).await.ok()?;
+        let len: usize = line.trim().strip_prefix('$')?.parse().ok()?;
+        let mut arg = vec![0; len + 2];
+        reader.read_exact(&mut arg).await.ok()?;
+        args.push(String::from_utf8_lossy(&arg[..len]).to_string());
+    }
+    Some(args)
+}
+
+/// Answers the commands of a client like a Redis server, returning the `XADD` commands once
+/// `n_entries` were received.
+async fn serve(stream: TcpStream, n_entries: usize) -> Vec<Vec<String>> {
+    let mut reader = BufReader::new(stream);
+    let mut entries = Vec::new();
+    while entries.len() < n_entries {
+        let command = read_command(&mut reader).await.unwrap();
+        if command[0] == "XADD" {
+            let id = format!("{}-0", entries.len());
+            let reply = format!("${}\r\n{}\r\n", id.len(), id);
+            reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            entries.push(command);
+        } else {
+            reader.get_mut().write_all(b"+OK\r\n").await.unwrap();
+        }
+    }
+    entries
+}
+
+fn readings(sensor_type: &SensorType, timestamps: &[f64]) -> Arc<SensorReadings<Sample3D>> {
+    let samples = timestamps
+        .iter()
+        .map(|&t| Sample3D::new(t, [0.1, 0.2, 9.8]))
+        .collect();
+    Arc::new(SensorReadings::from_vec(
+        "Phone",
+        sensor_type.clone(),
+        samples,
+    ))
+}
+
+#[tokio::test]
+async fn test_add_entries() {
+    let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut config = RedisConfig::new(&format!("redis://{}", server.local_addr().unwrap()));
+    config.max_len = Some(100);
+    let (handle, sink) = run_client(config).unwrap();
+
+    let id = Uuid::new_v4();
+    let sensor_type = SensorType::Accelerometer(id);
+    sink.process_samples(Uuid::new_v4(), readings(&sensor_type, &[1.0, 1.5]));
+
+    let (stream, _) = server.accept().await.unwrap();
+    let entries = tokio::time::timeout(Duration::from_secs(2), serve(stream, 2))
+        .await
+        .unwrap();
+    let key = format!("imu:Phone:accelerometer:{}", id);
+    assert_eq!(
+        entries[1],
+        vec![
+            "XADD",
+            key.as_str(),
+            "MAXLEN",
+            "~",
+            "100",
+            "*",
+            "timestamp",
+            "1.5",
+            "x",
+            "0.1",
+            "y",
+            "0.2",
+            "z",
+            "9.8"
+        ]
+    );
+    assert!(sink.is_connected());
+
+    sink.stop();
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!sink.is_connected());
+}
+
+#[tokio::test]
+async fn test_reconnect() {
+    let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut config = RedisConfig::new(&format!("redis://{}", server.local_addr().unwrap()));
+    config.max_len = None;
+    config.reconnect_delay = Duration::from_millis(50);
+    let (handle, sink) = run_client(config).unwrap();
+
+    let sensor_type = SensorType::Gyroscope(Uuid::new_v4());
+    sink.process_samples(Uuid::new_v4(), readings(&sensor_type, &[1.0]));
+    let (stream, _) = server.accept().await.unwrap();
+    let entries = tokio::time::timeout(Duration::from_secs(2), serve(stream, 1))
+        .await
+        .unwrap();
+    assert_eq!(entries[0][2], "*");
+    // the server closes the connection
+
+    // readings received meanwhile are added once reconnected
+    sink.process_samples(Uuid::new_v4(), readings(&sensor_type, &[2.0]));
+    let (stream, _) = tokio::time::timeout(Duration::from_secs(2), server.accept())
+        .await
+        .unwrap()
+        .unwrap();
+    let entries = tokio::time::timeout(Duration::from_secs(2), serve(stream, 1))
+        .await
+        .unwrap();
+    assert_eq!(entries[0][4], "2.0");
+
+    drop(sink);
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+}
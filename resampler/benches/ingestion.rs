@@ -0,0 +1,65 @@
+//! Compares buffering raw samples under a mutex and in a lock-free ring buffer, while the
+//! resampler collects them on another thread.
+//!
+//! Run with `cargo bench -p resampler_rs --bench ingestion`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use imu_common::traits::{IMUReadings, IMUSink};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use resampler_rs::{IngestionBuffer, ResamplerPipeline};
+
+type Pipeline = ResamplerPipeline<SensorReadings<Sample3D>, Sample3D>;
+
+fn bench_ingestion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ingestion");
+    let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+    for batch_size in [1, 16] {
+        let samples = (0..batch_size)
+            .map(|i| Sample3D::new(i as f64 * 0.001, [0.0, 0.0, 9.8]))
+            .collect();
+        let readings = Arc::new(SensorReadings::from_vec(
+            "bench",
+            sensor_type.clone(),
+            samples,
+        ));
+        group.throughput(Throughput::Elements(batch_size as u64));
+
+        for (name, ingestion) in [
+            ("locked", IngestionBuffer::Locked),
+            ("ring", IngestionBuffer::ring()),
+        ] {
+            let pipeline = Arc::new(
+                Pipeline::new("bench", vec![sensor_type.clone()]).with_ingestion_buffer(ingestion),
+            );
+            // the resampler keeps collecting the samples, as it would at a high rate
+            let running = Arc::new(AtomicBool::new(true));
+            let collector = std::thread::spawn({
+                let pipeline = pipeline.clone();
+                let running = running.clone();
+                move || {
+                    while running.load(Ordering::Relaxed) {
+                        black_box(pipeline.collect_samples(0.0));
+                    }
+                }
+            });
+
+            group.bench_with_input(
+                BenchmarkId::new(name, batch_size),
+                &readings,
+                |b, readings| b.iter(|| pipeline.process_samples(Uuid::nil(), readings.clone())),
+            );
+
+            running.store(false, Ordering::Relaxed);
+            collector.join().unwrap();
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_ingestion);
+criterion_main!(benches);
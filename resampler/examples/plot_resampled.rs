@@ -1,22 +1,15 @@
-use uuid::Uuid;
-
 use imu_common::traits::IMUSink;
-use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::sensors::{SensorClusterBuilder, SensorReadings};
 use imu_common::types::timed::Sample3D;
 
-use phyphox_rs;
-use resampler_rs::{self, SmothingPolicy};
+use resampler_rs::{ResamplerBuilder, SmothingPolicy};
 use test_utils::sinks::Plot1D;
 use tokio::time::Duration;
 
 #[tokio::main]
 async fn main() {
     let tag = "Test";
-    let sensor_cluster = vec![
-        SensorType::Accelerometer(Uuid::new_v4()),
-        SensorType::Gyroscope(Uuid::new_v4()),
-        SensorType::Magnetometer(Uuid::new_v4()),
-    ];
+    let sensor_cluster = SensorClusterBuilder::new().nine_axis().build().unwrap();
 
     let plot_window_size_samples = 200;
 
@@ -45,13 +38,12 @@ async fn main() {
     let resampling_period_millis = 100.0;
     let resampling_delay_millis = 500.0;
     let smoothing_policy = SmothingPolicy::WeightedAverage;
-    let (_handle_resample, resampler) = resampler_rs::run::<SensorReadings<Sample3D>, _>(
-        tag,
-        sensor_cluster.clone(),
-        resampling_period_millis,
-        resampling_delay_millis,
-        smoothing_policy,
-    );
+    let (_handle_resample, resampler) = ResamplerBuilder::new(tag, sensor_cluster.clone())
+        .with_resampling_period_millis(resampling_period_millis)
+        .with_resampling_delay_millis(resampling_delay_millis)
+        .with_smoothing_policy(smoothing_policy)
+        .run::<SensorReadings<Sample3D>, _>()
+        .unwrap();
 
     resampler
         .attach_listeners(&*phyphox, &sensor_cluster)
@@ -0,0 +1,400 @@
+//! Module builder
+//!
+//! Configuration and start of a resampling pipeline.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::errors::ResamplerError;
+use crate::pipeline::batching::OutputBatching;
+use crate::pipeline::cache::{Cache, Interpolable};
+use crate::pipeline::delivery::OutputDelivery;
+use crate::pipeline::drift::DriftCorrection;
+use crate::pipeline::ingestion::IngestionBuffer;
+use crate::pipeline::offline::OfflineResampler;
+use crate::pipeline::MIN_RESAMPLING_PERIOD_MILLIS;
+use crate::{ResamplerPipeline, SensorSettings, SmothingPolicy};
+use imu_common::traits::imu::{IMUFilter, IMUUntimedSample};
+use imu_common::traits::{IMUReadings, IMUSample};
+use imu_common::types::filters::{Average, WeightedAverage};
+use imu_common::types::sensors::SensorType;
+use imu_common::types::ClockSource;
+use publisher::delivery::{BoundedChannel, Inline};
+
+const DEFAULT_RESAMPLING_PERIOD_MILLIS: f64 = 10.0;
+const DEFAULT_RESAMPLING_DELAY_MILLIS: f64 = 200.0;
+
+/// Handle of the resampling thread, and the pipeline it resamples.
+pub type RunningResampler<T, S> = (std::thread::JoinHandle<()>, Arc<ResamplerPipeline<T, S>>);
+
+/// Builds and starts a `ResamplerPipeline`.
+///
+/// Defaults to a resampling period of 10 ms, a delay of 200 ms, the default smoothing policy,
+/// and listeners notified on the thread pool every resampling period, without buffering.
+///
+/// ```
+/// use imu_common::types::sensors::{SensorReadings, SensorType};
+/// use imu_common::types::timed::Sample3D;
+/// use resampler_rs::{ResamplerBuilder, SmothingPolicy};
+///
+/// let (_handle, pipeline) = ResamplerBuilder::new("phone", SensorType::cluster_for_tag("phone"))
+///     .with_resampling_period_millis(20.0)
+///     .with_smoothing_policy(SmothingPolicy::Averaging)
+///     .run::<SensorReadings<Sample3D>, Sample3D>()
+///     .unwrap();
+/// assert_eq!(pipeline.get_smoothing_policy(), SmothingPolicy::Averaging);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ResamplerBuilder {
+    tag: String,
+    sensor_cluster: Vec<SensorType>,
+    resampling_period_millis: f64,
+    resampling_delay_millis: f64,
+    smoothing_policy: SmothingPolicy,
+    output_capacity: Option<usize>,
+    output_batching: OutputBatching,
+    ingestion: IngestionBuffer,
+    drift_correction: Option<DriftCorrection>,
+    sensor_settings: Vec<(SensorType, SensorSettings)>,
+}
+
+impl ResamplerBuilder {
+    pub fn new(tag: &str, sensor_cluster: Vec<SensorType>) -> Self {
+        Self {
+            tag: tag.to_string(),
+            sensor_cluster,
+            resampling_period_millis: DEFAULT_RESAMPLING_PERIOD_MILLIS,
+            resampling_delay_millis: DEFAULT_RESAMPLING_DELAY_MILLIS,
+            smoothing_policy: SmothingPolicy::default(),
+            output_capacity: None,
+            output_batching: OutputBatching::default(),
+            ingestion: IngestionBuffer::default(),
+            drift_correction: None,
+            sensor_settings: Vec::new(),
+        }
+    }
+
+    /// Sets the period of the resampled readings. It must be at least 5 ms.
+    pub fn with_resampling_period_millis(mut self, resampling_period_millis: f64) -> Self {
+        self.resampling_period_millis = resampling_period_millis;
+        self
+    }
+
+    /// Sets how long raw samples are waited for before being resampled.
+    pub fn with_resampling_delay_millis(mut self, resampling_delay_millis: f64) -> Self {
+        self.resampling_delay_millis = resampling_delay_millis;
+        self
+    }
+
+    pub fn with_smoothing_policy(mut self, smoothing_policy: SmothingPolicy) -> Self {
+        self.smoothing_policy = smoothing_policy;
+        self
+    }
+
+    /// Queues resampled readings to a worker thread notifying the listeners, holding at most
+    /// `output_capacity` pending readings. Resampling blocks while the queue is full.
+    pub fn with_output_capacity(mut self, output_capacity: usize) -> Self {
+        self.output_capacity = Some(output_capacity);
+        self
+    }
+
+    /// Publishes the outputs of several resampling periods as a single reading per sensor, to
+    /// reduce the number of callbacks of downstream sinks at high rates.
+    pub fn with_output_batching(mut self, output_batching: OutputBatching) -> Self {
+        self.output_batching = output_batching;
+        self
+    }
+
+    /// Buffers the raw samples of every sensor as set by `ingestion`, e.g. in a lock-free ring
+    /// buffer so that sources publishing at 1 kHz or more don't wait for the resampler.
+    pub fn with_ingestion_buffer(mut self, ingestion: IngestionBuffer) -> Self {
+        self.ingestion = ingestion;
+        self
+    }
+
+    /// Corrects the resampling period by a few ppm so that output timestamps stay aligned with
+    /// the wall clock over hours, e.g. to synchronize them with video. Ignored by offline
+    /// pipelines, resampled on their own clock.
+    pub fn with_drift_correction(mut self, drift_correction: DriftCorrection) -> Self {
+        self.drift_correction = Some(drift_correction);
+        self
+    }
+
+    /// Overrides the smoothing policy or resampling period of `sensor_type`, e.g. to publish a
+    /// magnetometer less often than an accelerometer.
+    pub fn with_sensor_settings(
+        mut self,
+        sensor_type: &SensorType,
+        settings: SensorSettings,
+    ) -> Self {
+        self.sensor_settings.push((sensor_type.clone(), settings));
+        self
+    }
+
+    /// Returns an error if the sensor cluster is empty or has duplicated sensors, the period is
+    /// below the minimum, the delay is negative, the output or ingestion capacity is 0, or output
+    /// batches would be empty, or the drift correction is invalid. Sensor settings
+    /// must refer to sensors of the cluster, with periods not shorter than the pipeline period.
+    pub fn validate(&self) -> Result<(), ResamplerError> {
+        if self.sensor_cluster.is_empty() {
+            return Err(ResamplerError::EmptySensorCluster);
+        }
+        let mut sensors = HashSet::new();
+        if let Some(sensor) = self.sensor_cluster.iter().find(|s| !sensors.insert(*s)) {
+            return Err(ResamplerError::DuplicatedSensor(format!("{:?}", sensor)));
+        }
+        if !(self.resampling_period_millis >= MIN_RESAMPLING_PERIOD_MILLIS
+            && self.resampling_period_millis.is_finite())
+        {
+            return Err(ResamplerError::InvalidPeriod(self.resampling_period_millis));
+        }
+        if !(self.resampling_delay_millis >= 0.0 && self.resampling_delay_millis.is_finite()) {
+            return Err(ResamplerError::InvalidDelay(self.resampling_delay_millis));
+        }
+        if self.output_capacity == Some(0) {
+            return Err(ResamplerError::InvalidCapacity(0));
+        }
+        self.output_batching.validate()?;
+        self.ingestion.validate()?;
+        if let Some(drift_correction) = self.drift_correction {
+            drift_correction.validate()?;
+        }
+        for (sensor_type, settings) in self.sensor_settings.iter() {
+            if !self.sensor_cluster.contains(sensor_type) {
+                return Err(ResamplerError::UnknownSensor(format!("{:?}", sensor_type)));
+            }
+            if let Some(period_millis) = settings.get_resampling_period_millis() {
+                if !(period_millis >= self.resampling_period_millis && period_millis.is_finite()) {
+                    return Err(ResamplerError::InvalidPeriod(period_millis));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates the configuration and starts resampling on a new thread.
+    /// Returns the handle of the thread and the pipeline. The pipeline stops once the global
+    /// `ShutdownToken` is shut down, e.g. on Ctrl-C, or once the returned pipeline is dropped.
+    pub fn run<T, S>(self) -> Result<RunningResampler<T, S>, ResamplerError>
+    where
+        S: IMUSample + std::fmt::Debug,
+        T: Send + Sync + IMUReadings<S> + std::fmt::Debug + 'static,
+        S::Untimed: IMUUntimedSample,
+        Average<S::Untimed>: IMUFilter<S>,
+        WeightedAverage<S::Untimed>: IMUFilter<S>,
+        Cache<S, S::Untimed>: Interpolable<S, S::Untimed>,
+    {
+        self.validate()?;
+        let delivery = match self.output_capacity {
+            Some(capacity) => OutputDelivery::Bounded(BoundedChannel::new(capacity)),
+            None => OutputDelivery::default(),
+        };
+        let mut pipeline =
+            ResamplerPipeline::with_delivery(&self.tag, self.sensor_cluster, delivery)
+                .with_ingestion_buffer(self.ingestion);
+        if let Some(drift_correction) = self.drift_correction {
+            pipeline = pipeline.with_drift_correction(drift_correction);
+        }
+        let pipeline = Arc::new(pipeline);
+        pipeline.set_smoothing_policy(self.smoothing_policy);
+        pipeline.set_output_batching(self.output_batching)?;
+        for (sensor_type, settings) in self.sensor_settings {
+            pipeline.set_sensor_settings(&sensor_type, settings)?;
+        }
+
+        let pipeline_weak = Arc::downgrade(&pipeline);
+        let handle = std::thread::spawn(move || {
+            ResamplerPipeline::start_weak(
+                pipeline_weak,
+                self.smoothing_policy,
+                self.resampling_period_millis,
+                self.resampling_delay_millis,
+            )
+        });
+
+        Ok((handle, pipeline))
+    }
+
+    /// Validates the configuration and returns a pipeline resampled on `clock` every time
+    /// `OfflineResampler::step` is called, instead of on a thread. Listeners are notified on
+    /// the calling thread, so the output capacity is ignored.
+    pub fn offline<T, S>(
+        self,
+        clock: Arc<dyn ClockSource>,
+    ) -> Result<OfflineResampler<T, S>, ResamplerError>
+    where
+        S: IMUSample + std::fmt::Debug,
+        T: Send + Sync + IMUReadings<S> + std::fmt::Debug + 'static,
+        S::Untimed: IMUUntimedSample,
+        Average<S::Untimed>: IMUFilter<S>,
+        WeightedAverage<S::Untimed>: IMUFilter<S>,
+        Cache<S, S::Untimed>: Interpolable<S, S::Untimed>,
+    {
+        self.validate()?;
+        let pipeline = ResamplerPipeline::with_delivery(
+            &self.tag,
+            self.sensor_cluster,
+            OutputDelivery::Inline(Inline),
+        )
+        .with_clock(clock)
+        .with_ingestion_buffer(self.ingestion);
+        pipeline.set_smoothing_policy(self.smoothing_policy);
+        pipeline.set_output_batching(self.output_batching)?;
+        for (sensor_type, settings) in self.sensor_settings {
+            pipeline.set_sensor_settings(&sensor_type, settings)?;
+        }
+        Ok(OfflineResampler::new(
+            Arc::new(pipeline),
+            self.resampling_period_millis,
+            self.resampling_delay_millis,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::traits::{IMUSink, IMUSource};
+    use imu_common::types::sensors::SensorReadings;
+    use imu_common::types::timed::Sample3D;
+    use publisher::Listener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_validate() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_validate");
+        assert!(ResamplerBuilder::new("test", sensor_cluster.clone())
+            .validate()
+            .is_ok());
+
+        assert_eq!(
+            ResamplerBuilder::new("test", vec![]).validate(),
+            Err(ResamplerError::EmptySensorCluster)
+        );
+        let accelerometer = SensorType::Accelerometer(Uuid::new_v4());
+        assert!(matches!(
+            ResamplerBuilder::new("test", vec![accelerometer.clone(), accelerometer]).validate(),
+            Err(ResamplerError::DuplicatedSensor(_))
+        ));
+        assert_eq!(
+            ResamplerBuilder::new("test", sensor_cluster.clone())
+                .with_resampling_period_millis(1.0)
+                .validate(),
+            Err(ResamplerError::InvalidPeriod(1.0))
+        );
+        assert!(matches!(
+            ResamplerBuilder::new("test", sensor_cluster.clone())
+                .with_resampling_period_millis(f64::NAN)
+                .validate(),
+            Err(ResamplerError::InvalidPeriod(_))
+        ));
+        assert_eq!(
+            ResamplerBuilder::new("test", sensor_cluster.clone())
+                .with_resampling_delay_millis(-10.0)
+                .validate(),
+            Err(ResamplerError::InvalidDelay(-10.0))
+        );
+        assert_eq!(
+            ResamplerBuilder::new("test", sensor_cluster.clone())
+                .with_output_capacity(0)
+                .validate(),
+            Err(ResamplerError::InvalidCapacity(0))
+        );
+        assert!(matches!(
+            ResamplerBuilder::new("test", sensor_cluster.clone())
+                .with_output_batching(OutputBatching::Ticks(0))
+                .validate(),
+            Err(ResamplerError::InvalidBatching(_))
+        ));
+        assert_eq!(
+            ResamplerBuilder::new("test", sensor_cluster.clone())
+                .with_ingestion_buffer(IngestionBuffer::Ring(0))
+                .validate(),
+            Err(ResamplerError::InvalidIngestion(0))
+        );
+        assert!(matches!(
+            ResamplerBuilder::new("test", sensor_cluster.clone())
+                .with_drift_correction(DriftCorrection::new().with_max_correction_ppm(-1.0))
+                .validate(),
+            Err(ResamplerError::InvalidDriftCorrection(_))
+        ));
+        let settings = SensorSettings::new().with_resampling_period_millis(5.0);
+        assert_eq!(
+            ResamplerBuilder::new("test", sensor_cluster.clone())
+                .with_sensor_settings(&sensor_cluster[0], settings)
+                .validate(),
+            Err(ResamplerError::InvalidPeriod(5.0))
+        );
+        assert!(matches!(
+            ResamplerBuilder::new("test", sensor_cluster)
+                .with_sensor_settings(&SensorType::Gyroscope(Uuid::new_v4()), settings)
+                .validate(),
+            Err(ResamplerError::UnknownSensor(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_bounded_output() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_bounded_output");
+        let (_, pipeline) = ResamplerBuilder::new("test", sensor_cluster.clone())
+            .with_resampling_delay_millis(50.0)
+            .with_output_capacity(4)
+            .run::<SensorReadings<Sample3D>, Sample3D>()
+            .unwrap();
+        let (_, source) =
+            phyphox_rs::run_mock_service("test", sensor_cluster.clone(), 10.0, false, 500).unwrap();
+        pipeline
+            .attach_listeners(&*source, &sensor_cluster)
+            .unwrap();
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let mut listener = Listener::new({
+            let received = received.clone();
+            move |_id, _readings: Arc<SensorReadings<Sample3D>>| {
+                received.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        pipeline
+            .register_listener(&mut listener, &sensor_cluster[0])
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert!(received.load(Ordering::Relaxed) > 0);
+    }
+
+    #[tokio::test]
+    async fn test_ring_ingestion() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_ring_ingestion");
+        let (_, pipeline) = ResamplerBuilder::new("test", sensor_cluster.clone())
+            .with_resampling_delay_millis(50.0)
+            .with_ingestion_buffer(IngestionBuffer::ring())
+            .run::<SensorReadings<Sample3D>, Sample3D>()
+            .unwrap();
+        let (_, source) =
+            phyphox_rs::run_mock_service("test", sensor_cluster.clone(), 10.0, false, 500).unwrap();
+        pipeline
+            .attach_listeners(&*source, &sensor_cluster)
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let metrics = pipeline.get_metrics();
+        let metrics = metrics.get(&sensor_cluster[0]).unwrap();
+        assert!(metrics.n_samples > 0);
+    }
+
+    #[test]
+    fn test_drift_correction() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_drift_correction");
+        let (_, pipeline) = ResamplerBuilder::new("test", sensor_cluster)
+            .with_drift_correction(DriftCorrection::new())
+            .run::<SensorReadings<Sample3D>, Sample3D>()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+
+        let drift = pipeline.get_clock_drift().unwrap();
+        assert!(drift.correction_ppm.abs() <= 500.0);
+        assert!(drift.offset_millis.abs() < 1000.0);
+    }
+}
@@ -0,0 +1,52 @@
+//! Module errors
+
+/// Errors of the resampler configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResamplerError {
+    /// Error indicating that the sensor cluster has no sensors.
+    EmptySensorCluster,
+
+    /// Error indicating that a sensor appears more than once in the sensor cluster.
+    DuplicatedSensor(String),
+
+    /// Error indicating that a sensor isn't part of the sensor cluster.
+    UnknownSensor(String),
+
+    /// Error indicating that the resampling period is below the minimum or not finite.
+    InvalidPeriod(f64),
+
+    /// Error indicating that the resampling delay is negative or not finite.
+    InvalidDelay(f64),
+
+    /// Error indicating that the output buffer can't hold any notification.
+    InvalidCapacity(usize),
+
+    /// Error indicating that output batches would be empty.
+    InvalidBatching(String),
+
+    /// Error indicating that the ingestion ring buffer can't hold any sample.
+    InvalidIngestion(usize),
+
+    /// Error indicating that a setting of the drift correction isn't positive and finite.
+    InvalidDriftCorrection(String),
+}
+
+impl std::fmt::Display for ResamplerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResamplerError::EmptySensorCluster => write!(f, "Empty sensor cluster"),
+            ResamplerError::DuplicatedSensor(e) => write!(f, "Duplicated sensor: {}", e),
+            ResamplerError::UnknownSensor(e) => write!(f, "Unknown sensor: {}", e),
+            ResamplerError::InvalidPeriod(e) => write!(f, "Invalid resampling period: {} ms", e),
+            ResamplerError::InvalidDelay(e) => write!(f, "Invalid resampling delay: {} ms", e),
+            ResamplerError::InvalidCapacity(e) => write!(f, "Invalid output capacity: {}", e),
+            ResamplerError::InvalidBatching(e) => write!(f, "Invalid output batching: {}", e),
+            ResamplerError::InvalidIngestion(e) => write!(f, "Invalid ingestion capacity: {}", e),
+            ResamplerError::InvalidDriftCorrection(e) => {
+                write!(f, "Invalid drift correction: {}", e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResamplerError {}
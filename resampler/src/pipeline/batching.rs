@@ -0,0 +1,177 @@
+//! Module batching
+//!
+//! Resampled outputs are published once per resampling period by default. At high rates the
+//! per-callback overhead dominates downstream sinks, so outputs can instead be accumulated and
+//! published as a single reading per sensor every few periods. Downstream stages, e.g. the AHRS
+//! filter, process every sample of a batch, as if the periods had been published one by one.
+
+use imu_common::types::sensors::SensorType;
+
+use crate::errors::ResamplerError;
+
+// resampling timestamps are sums of periods, so they aren't exact
+const EPS_BATCH_SECS: f64 = 1e-9;
+
+/// How resampled outputs are grouped before notifying the listeners.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputBatching {
+    /// Outputs are published every resampling period.
+    #[default]
+    Disabled,
+    /// Outputs of this many resampling periods are published together.
+    Ticks(usize),
+    /// Outputs of the resampling periods within this many milliseconds are published
+    /// together, once the next period starts.
+    Millis(f64),
+}
+
+impl OutputBatching {
+    /// Returns an InvalidBatching error for 0 ticks, or a non positive or non finite duration.
+    pub fn validate(&self) -> Result<(), ResamplerError> {
+        match *self {
+            OutputBatching::Ticks(0) => Err(ResamplerError::InvalidBatching(format!("{:?}", self))),
+            OutputBatching::Millis(millis) if !(millis > 0.0 && millis.is_finite()) => {
+                Err(ResamplerError::InvalidBatching(format!("{:?}", self)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Outputs accumulated since the last batch was published, in the order sensors were resampled.
+#[derive(Debug)]
+pub(crate) struct OutputBatch<S> {
+    pending: Vec<(SensorType, Vec<S>)>,
+    n_ticks: usize,
+    start_secs: Option<f64>,
+}
+
+impl<S> Default for OutputBatch<S> {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            n_ticks: 0,
+            start_secs: None,
+        }
+    }
+}
+
+impl<S> OutputBatch<S> {
+    /// Adds the outputs of the resampling period at `timestamp_secs`, returning the outputs to
+    /// publish, if a batch is complete.
+    pub(crate) fn push(
+        &mut self,
+        batching: OutputBatching,
+        timestamp_secs: f64,
+        outputs: Vec<(SensorType, S)>,
+    ) -> Vec<(SensorType, Vec<S>)> {
+        let mut batch = Vec::new();
+        if let (OutputBatching::Millis(millis), Some(start_secs)) = (batching, self.start_secs) {
+            if timestamp_secs - start_secs >= millis / 1000.0 - EPS_BATCH_SECS {
+                batch = self.take();
+            }
+        }
+        for (sensor_type, sample) in outputs {
+            match self.pending.iter_mut().find(|(s, _)| *s == sensor_type) {
+                Some((_, samples)) => samples.push(sample),
+                None => self.pending.push((sensor_type, vec![sample])),
+            }
+        }
+        self.n_ticks += 1;
+        self.start_secs.get_or_insert(timestamp_secs);
+
+        let complete = match batching {
+            OutputBatching::Disabled => true,
+            OutputBatching::Ticks(n_ticks) => self.n_ticks >= n_ticks,
+            OutputBatching::Millis(_) => false,
+        };
+        if complete {
+            // a batch closed by this period in `Millis` mode can't complete here, so the
+            // batches are never merged
+            batch.extend(self.take());
+        }
+        batch
+    }
+
+    /// Drops the pending outputs of `sensor_type`.
+    pub(crate) fn remove_sensor(&mut self, sensor_type: &SensorType) {
+        self.pending.retain(|(s, _)| s != sensor_type);
+    }
+
+    /// Returns the pending outputs, starting a new batch.
+    pub(crate) fn take(&mut self) -> Vec<(SensorType, Vec<S>)> {
+        self.n_ticks = 0;
+        self.start_secs = None;
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn n_samples(batch: &[(SensorType, Vec<usize>)]) -> Vec<usize> {
+        batch.iter().map(|(_, samples)| samples.len()).collect()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(OutputBatching::Disabled.validate().is_ok());
+        assert!(OutputBatching::Ticks(1).validate().is_ok());
+        assert!(OutputBatching::Millis(0.5).validate().is_ok());
+        assert!(OutputBatching::Ticks(0).validate().is_err());
+        assert!(OutputBatching::Millis(0.0).validate().is_err());
+        assert!(OutputBatching::Millis(f64::NAN).validate().is_err());
+    }
+
+    #[test]
+    fn test_batch_ticks() {
+        let (acc, gyro) = (
+            SensorType::Accelerometer(Uuid::new_v4()),
+            SensorType::Gyroscope(Uuid::new_v4()),
+        );
+        let mut batch = OutputBatch::default();
+        let batching = OutputBatching::Ticks(3);
+
+        assert!(batch
+            .push(batching, 0.0, vec![(acc.clone(), 0), (gyro.clone(), 0)])
+            .is_empty());
+        // a sensor missing a period is batched with the samples it has
+        assert!(batch.push(batching, 0.1, vec![(acc.clone(), 1)]).is_empty());
+        let published = batch.push(batching, 0.2, vec![(acc.clone(), 2), (gyro.clone(), 2)]);
+        assert_eq!(
+            published,
+            vec![(acc.clone(), vec![0, 1, 2]), (gyro.clone(), vec![0, 2])]
+        );
+
+        // disabling batching publishes the pending outputs with the next period
+        assert!(batch.push(batching, 0.3, vec![(acc.clone(), 3)]).is_empty());
+        let published = batch.push(OutputBatching::Disabled, 0.4, vec![(acc.clone(), 4)]);
+        assert_eq!(published, vec![(acc, vec![3, 4])]);
+    }
+
+    #[test]
+    fn test_batch_millis() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let mut batch = OutputBatch::default();
+        let batching = OutputBatching::Millis(50.0);
+
+        let published: Vec<Vec<usize>> = (0..12)
+            .map(|n| batch.push(batching, 1.0 + n as f64 * 0.01, vec![(acc.clone(), n)]))
+            .map(|published| n_samples(&published))
+            .collect();
+        // periods from 0 to 40 ms are published when the period at 50 ms starts
+        assert_eq!(published[5], vec![5]);
+        assert_eq!(published[10], vec![5]);
+        assert_eq!(
+            published.iter().filter(|p| !p.is_empty()).count(),
+            2,
+            "{:?}",
+            published
+        );
+
+        batch.remove_sensor(&acc);
+        assert!(batch.take().is_empty());
+    }
+}
@@ -2,15 +2,16 @@ use std::collections::HashMap;
 use std::marker::PhantomData;
 
 use imu_common::traits::BasicArithmetic;
-use imu_common::traits::{IMUSample, IMUUntimedSample};
+use imu_common::traits::{Float, IMUSample, IMUUntimedSample};
 use imu_common::types::buffers::CircularBuffer;
 use imu_common::types::sensors::SensorType;
 use imu_common::types::timed::SampleQuaternion;
-use imu_common::types::untimed::{Scalar, UnitQuaternion, XYZ};
+use imu_common::types::untimed::{Scalar, UnitQuaternion, XYZPair, XYZ};
 
 trait Lerp: BasicArithmetic {}
-impl Lerp for XYZ {}
+impl<F: Float> Lerp for XYZ<F> {}
 impl Lerp for Scalar {}
+impl Lerp for XYZPair {}
 
 pub trait Interpolable<T, U>
 where
@@ -45,6 +46,19 @@ where
             _phantom_data: PhantomData,
         }
     }
+    /// Adds a cache slot for `sensor_type`, holding default samples at `timestamp_secs` until
+    /// its first samples are pushed. Adding an existing sensor has no effect.
+    pub(crate) fn add_sensor(&mut self, sensor_type: &SensorType, timestamp_secs: f64) {
+        self.cache.entry(sensor_type.clone()).or_insert_with(|| {
+            let sample = T::from_measurement(timestamp_secs, T::default().get_measurement());
+            CircularBuffer::from_vec(vec![sample.clone(), sample])
+        });
+    }
+
+    pub(crate) fn remove_sensor(&mut self, sensor_type: &SensorType) {
+        self.cache.remove(sensor_type);
+    }
+
     pub(crate) fn push(&mut self, sensor_type: &SensorType, elem: T) {
         if let Some(buffer) = self.cache.get_mut(sensor_type) {
             buffer.push(elem);
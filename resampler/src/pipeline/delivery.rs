@@ -0,0 +1,37 @@
+//! Delivery of the resampled readings to the listeners of a `ResamplerPipeline`.
+
+use std::sync::Arc;
+use uuid::Uuid;
+
+use imu_common::types::Callback;
+use publisher::delivery::{BoundedChannel, DeliveryStrategy, Inline, ThreadPool};
+
+#[derive(Clone)]
+pub(crate) enum OutputDelivery {
+    /// Listeners run on the rayon thread pool, as soon as a sample is resampled.
+    Unbounded(ThreadPool),
+    /// Readings are queued to a worker thread. The resampling loop blocks once the queue is
+    /// full, so slow listeners slow down resampling instead of piling up readings.
+    Bounded(BoundedChannel),
+    /// Listeners run on the resampling thread, for offline resampling.
+    Inline(Inline),
+}
+
+impl Default for OutputDelivery {
+    fn default() -> Self {
+        Self::Unbounded(ThreadPool)
+    }
+}
+
+impl<T> DeliveryStrategy<T> for OutputDelivery
+where
+    T: Send + Sync + 'static,
+{
+    fn deliver(&self, listeners: Vec<(Uuid, Callback<T>)>, data: Arc<T>) {
+        match self {
+            OutputDelivery::Unbounded(delivery) => delivery.deliver(listeners, data),
+            OutputDelivery::Bounded(delivery) => delivery.deliver(listeners, data),
+            OutputDelivery::Inline(delivery) => delivery.deliver(listeners, data),
+        }
+    }
+}
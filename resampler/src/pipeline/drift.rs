@@ -0,0 +1,247 @@
+//! Module drift
+//!
+//! The resampling loop sleeps one period after every resampling period, so over hours its
+//! periods add up to more or less than the wall clock time. Outputs can instead be timestamped
+//! on a grid whose period is slowly corrected by a few ppm, so they stay aligned with the wall
+//! clock, e.g. to synchronize them with video.
+
+use crate::errors::ResamplerError;
+
+const DEFAULT_TIME_CONSTANT_SECS: f64 = 60.0;
+const DEFAULT_MAX_CORRECTION_PPM: f64 = 500.0;
+const DEFAULT_MAX_OFFSET_MILLIS: f64 = 1000.0;
+
+/// Settings of the servo correcting the resampling period.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DriftCorrection {
+    time_constant_secs: f64,
+    max_correction_ppm: f64,
+    max_offset_millis: f64,
+}
+
+impl Default for DriftCorrection {
+    fn default() -> Self {
+        Self {
+            time_constant_secs: DEFAULT_TIME_CONSTANT_SECS,
+            max_correction_ppm: DEFAULT_MAX_CORRECTION_PPM,
+            max_offset_millis: DEFAULT_MAX_OFFSET_MILLIS,
+        }
+    }
+}
+
+impl DriftCorrection {
+    /// Corrects the period within 500 ppm, converging in about a minute.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how fast offsets to the wall clock are corrected. Longer time constants filter
+    /// more of the scheduling jitter.
+    pub fn with_time_constant_secs(mut self, time_constant_secs: f64) -> Self {
+        self.time_constant_secs = time_constant_secs;
+        self
+    }
+
+    /// Sets the largest correction of the period, in parts per million.
+    pub fn with_max_correction_ppm(mut self, max_correction_ppm: f64) -> Self {
+        self.max_correction_ppm = max_correction_ppm;
+        self
+    }
+
+    /// Sets the offset to the wall clock above which outputs are timestamped with the wall
+    /// clock again instead of being corrected, e.g. after the system was suspended or its clock
+    /// was set.
+    pub fn with_max_offset_millis(mut self, max_offset_millis: f64) -> Self {
+        self.max_offset_millis = max_offset_millis;
+        self
+    }
+
+    pub fn get_time_constant_secs(&self) -> f64 {
+        self.time_constant_secs
+    }
+
+    pub fn get_max_correction_ppm(&self) -> f64 {
+        self.max_correction_ppm
+    }
+
+    pub fn get_max_offset_millis(&self) -> f64 {
+        self.max_offset_millis
+    }
+
+    /// Returns an InvalidDriftCorrection error if a setting isn't positive and finite.
+    pub fn validate(&self) -> Result<(), ResamplerError> {
+        let settings = [
+            self.time_constant_secs,
+            self.max_correction_ppm,
+            self.max_offset_millis,
+        ];
+        if settings.iter().all(|s| *s > 0.0 && s.is_finite()) {
+            Ok(())
+        } else {
+            Err(ResamplerError::InvalidDriftCorrection(format!(
+                "{:?}",
+                self
+            )))
+        }
+    }
+}
+
+/// State of the drift correction, as of the last resampling period.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ClockDrift {
+    /// Wall clock time minus the timestamp of the last output.
+    pub offset_millis: f64,
+    /// Correction of the resampling period, in parts per million.
+    pub correction_ppm: f64,
+    /// Times the outputs were realigned with the wall clock after a large offset.
+    pub n_resyncs: usize,
+}
+
+/// Second order servo timestamping resampling periods on a grid that follows the wall clock.
+///
+/// The grid advances one corrected period every resampling period. The correction is a
+/// proportional and integral term of the offset to the wall clock, critically damped, so a
+/// loop that is consistently late by some ppm ends up with a period corrected by the same ppm
+/// and no offset.
+#[derive(Clone, Debug)]
+pub(crate) struct DriftServo {
+    settings: DriftCorrection,
+    period_secs: f64,
+    grid_secs: Option<f64>,
+    offset_secs: f64,
+    // integral term of the correction, as a fraction of the period
+    frequency: f64,
+    drift: ClockDrift,
+}
+
+impl DriftServo {
+    pub(crate) fn new(settings: DriftCorrection, period_secs: f64) -> Self {
+        Self {
+            settings,
+            period_secs,
+            grid_secs: None,
+            offset_secs: 0.0,
+            frequency: 0.0,
+            drift: ClockDrift::default(),
+        }
+    }
+
+    /// Returns the timestamp of the resampling period starting at `now_secs` on the wall clock.
+    pub(crate) fn timestamp(&mut self, now_secs: f64) -> f64 {
+        let max_correction = self.settings.max_correction_ppm * 1e-6;
+        let Some(previous_secs) = self.grid_secs else {
+            self.grid_secs = Some(now_secs);
+            return now_secs;
+        };
+        let correction = self.correction().clamp(-max_correction, max_correction);
+        let grid_secs = previous_secs + self.period_secs * (1.0 + correction);
+        let offset_secs = now_secs - grid_secs;
+        if offset_secs.abs() * 1000.0 > self.settings.max_offset_millis {
+            self.grid_secs = Some(now_secs);
+            self.offset_secs = 0.0;
+            self.frequency = 0.0;
+            self.drift = ClockDrift {
+                n_resyncs: self.drift.n_resyncs + 1,
+                ..ClockDrift::default()
+            };
+            return now_secs;
+        }
+
+        let omega = 1.0 / self.settings.time_constant_secs;
+        self.frequency = (self.frequency + omega * omega * offset_secs * self.period_secs)
+            .clamp(-max_correction, max_correction);
+        self.grid_secs = Some(grid_secs);
+        self.offset_secs = offset_secs;
+        self.drift.offset_millis = offset_secs * 1000.0;
+        self.drift.correction_ppm = correction * 1e6;
+        grid_secs
+    }
+
+    pub(crate) fn get_drift(&self) -> ClockDrift {
+        self.drift
+    }
+
+    fn correction(&self) -> f64 {
+        let omega = 1.0 / self.settings.time_constant_secs;
+        2.0 * omega * self.offset_secs + self.frequency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `n` periods of a loop whose periods last `drift_ppm` longer than `period_secs`.
+    fn run(servo: &mut DriftServo, n: usize, period_secs: f64, drift_ppm: f64) -> f64 {
+        let mut now_secs = 0.0;
+        let mut timestamp = 0.0;
+        for _ in 0..n {
+            timestamp = servo.timestamp(now_secs);
+            now_secs += period_secs * (1.0 + drift_ppm * 1e-6);
+        }
+        timestamp
+    }
+
+    #[test]
+    fn test_converge_to_drift() {
+        let settings = DriftCorrection::new().with_time_constant_secs(10.0);
+        let mut servo = DriftServo::new(settings, 0.01);
+        // an hour of a loop running 200 ppm slow
+        run(&mut servo, 360_000, 0.01, 200.0);
+
+        let drift = servo.get_drift();
+        assert!((drift.correction_ppm - 200.0).abs() < 1.0, "{:?}", drift);
+        assert!(drift.offset_millis.abs() < 0.01, "{:?}", drift);
+        assert_eq!(drift.n_resyncs, 0);
+    }
+
+    #[test]
+    fn test_no_drift() {
+        let mut servo = DriftServo::new(DriftCorrection::new(), 0.01);
+        let timestamp = run(&mut servo, 1000, 0.01, 0.0);
+        assert!((timestamp - 9.99).abs() < 1e-9);
+        assert!(servo.get_drift().correction_ppm.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_limit_correction() {
+        let settings = DriftCorrection::new()
+            .with_time_constant_secs(1.0)
+            .with_max_correction_ppm(50.0)
+            .with_max_offset_millis(1e6);
+        let mut servo = DriftServo::new(settings, 0.01);
+        run(&mut servo, 100_000, 0.01, 1000.0);
+
+        let drift = servo.get_drift();
+        assert!(drift.correction_ppm <= 50.0);
+        assert!(drift.offset_millis > 0.0);
+    }
+
+    #[test]
+    fn test_resync_after_jump() {
+        let mut servo = DriftServo::new(DriftCorrection::new(), 0.01);
+        assert_eq!(servo.timestamp(0.0), 0.0);
+        assert!((servo.timestamp(0.01) - 0.01).abs() < 1e-9);
+        // the system was suspended
+        assert_eq!(servo.timestamp(60.0), 60.0);
+        assert_eq!(servo.get_drift().n_resyncs, 1);
+        assert!((servo.timestamp(60.01) - 60.01).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(DriftCorrection::new().validate().is_ok());
+        assert!(matches!(
+            DriftCorrection::new()
+                .with_time_constant_secs(0.0)
+                .validate(),
+            Err(ResamplerError::InvalidDriftCorrection(_))
+        ));
+        assert!(matches!(
+            DriftCorrection::new()
+                .with_max_correction_ppm(f64::NAN)
+                .validate(),
+            Err(ResamplerError::InvalidDriftCorrection(_))
+        ));
+    }
+}
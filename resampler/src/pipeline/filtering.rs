@@ -0,0 +1,137 @@
+//! Module filtering
+//!
+//! Filter chains applied to the raw samples of every sensor before they are smoothed and
+//! resampled, e.g. to band-limit them.
+
+use std::collections::HashMap;
+
+use imu_common::traits::IMUSample;
+use imu_common::types::filters::{filter_or_disable, FilterChain, FilterChainBuilder};
+use imu_common::types::sensors::SensorType;
+
+/// Chains of the sensors of a pipeline, all built by the same builder when a sensor first
+/// receives samples.
+pub(crate) struct RawFilters<S> {
+    builder: Option<FilterChainBuilder<S>>,
+    /// Chain of every sensor, or `None` once the chain failed with a non recoverable error.
+    chains: HashMap<SensorType, Option<FilterChain<S>>>,
+}
+
+impl<S> Default for RawFilters<S> {
+    fn default() -> Self {
+        Self {
+            builder: None,
+            chains: HashMap::new(),
+        }
+    }
+}
+
+impl<S> RawFilters<S> {
+    pub(crate) fn get_builder(&self) -> Option<FilterChainBuilder<S>> {
+        self.builder.clone()
+    }
+
+    /// Replaces the chain of every sensor, dropping the state of the previous chains.
+    pub(crate) fn set_builder(&mut self, builder: Option<FilterChainBuilder<S>>) {
+        self.builder = builder;
+        self.chains.clear();
+    }
+
+    pub(crate) fn remove_sensor(&mut self, sensor_type: &SensorType) {
+        self.chains.remove(sensor_type);
+    }
+}
+
+impl<S: IMUSample> RawFilters<S> {
+    /// Returns `samples` of `sensor_type` filtered by its chain, or unchanged if there is no
+    /// chain.
+    ///
+    /// A chain failing with a non recoverable error is misconfigured, so it is disabled as
+    /// explained in `filter_or_disable`, and the samples of `sensor_type` are passed unfiltered
+    /// until the builder is replaced.
+    pub(crate) fn filter(&mut self, sensor_type: &SensorType, samples: Vec<S>) -> Vec<S> {
+        let Some(builder) = self.builder.as_ref() else {
+            return samples;
+        };
+        if samples.is_empty() {
+            return samples;
+        }
+        let chain = self
+            .chains
+            .entry(sensor_type.clone())
+            .or_insert_with(|| Some(builder.build()));
+        let (filtered, error) = filter_or_disable(chain, samples);
+        match error {
+            Some(e) if e.is_recoverable() => {
+                log::debug!("Skipping samples of {}: {}", sensor_type, e)
+            }
+            Some(e) => log::error!("Disabling the filter chain of {}: {}", sensor_type, e),
+            None => {}
+        }
+        filtered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::errors::ImuError;
+    use imu_common::traits::IMUFilter;
+    use imu_common::types::filters::MovingAverage;
+    use imu_common::types::timed::Sample3D;
+    use imu_common::types::untimed::XYZ;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_chain_per_sensor() {
+        let accelerometer = SensorType::Accelerometer(Uuid::new_v4());
+        let gyroscope = SensorType::Gyroscope(Uuid::new_v4());
+        let mut filters = RawFilters::default();
+        let samples = vec![Sample3D::new(0.0, [2.0, 2.0, 2.0])];
+        assert_eq!(filters.filter(&accelerometer, samples.clone()), samples);
+
+        filters.set_builder(Some(
+            FilterChain::builder().with(MovingAverage::<XYZ>::new(2)),
+        ));
+        filters.filter(&accelerometer, samples.clone());
+        // the accelerometer chain averages with the previous sample, the gyroscope one doesn't
+        assert_eq!(
+            filters.filter(&accelerometer, samples.clone()),
+            vec![Sample3D::new(0.0, [2.0, 2.0, 2.0])]
+        );
+        assert_eq!(
+            filters.filter(&gyroscope, samples),
+            vec![Sample3D::new(0.0, [1.0, 1.0, 1.0])]
+        );
+    }
+
+    /// Filter rejecting every batch, as a filter with invalid parameters.
+    #[derive(Clone)]
+    struct Misconfigured;
+
+    impl IMUFilter<Sample3D> for Misconfigured {
+        fn filter_batch(&mut self, _samples: Vec<Sample3D>) -> Result<Vec<Sample3D>, ImuError> {
+            Err(ImuError::InvalidParameter("Negative window".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_misconfigured_chain_is_disabled() {
+        let accelerometer = SensorType::Accelerometer(Uuid::new_v4());
+        let mut filters = RawFilters::default();
+        filters.set_builder(Some(FilterChain::builder().with(Misconfigured)));
+
+        // the failing batch is dropped, and the next ones are passed unfiltered
+        let samples = vec![Sample3D::new(0.0, [2.0, 2.0, 2.0])];
+        assert!(filters.filter(&accelerometer, samples.clone()).is_empty());
+        assert!(filters.chains[&accelerometer].is_none());
+        assert_eq!(filters.filter(&accelerometer, samples.clone()), samples);
+
+        // a new builder enables the chains again
+        filters.set_builder(Some(
+            FilterChain::builder().with(MovingAverage::<XYZ>::new(2)),
+        ));
+        filters.filter(&accelerometer, samples.clone());
+        assert!(filters.chains[&accelerometer].is_some());
+    }
+}
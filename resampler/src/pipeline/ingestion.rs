@@ -0,0 +1,340 @@
+//! Module ingestion
+//!
+//! Buffers of the raw samples received from the source, drained every resampling period.
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::errors::ResamplerError;
+use imu_common::traits::{IMUReadings, IMUSample};
+use imu_common::types::sensors::SensorType;
+
+/// Samples per sensor held by default by a ring buffer, about 4 s at 1 kHz.
+pub const DEFAULT_RING_CAPACITY: usize = 4096;
+
+/// How the raw samples of every sensor are buffered until they are resampled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum IngestionBuffer {
+    /// Readings extended under a mutex. Unbounded, but the source blocks while the resampler
+    /// collects the samples.
+    #[default]
+    Locked,
+    /// Lock-free single producer single consumer ring buffer holding at least the given number
+    /// of samples, rounded up to a power of two. The source only contends with other threads
+    /// pushing to the same sensor, never with the resampler collecting the samples. Samples
+    /// received while the ring is full are dropped and logged. Suited to sensors sampled at
+    /// 1 kHz or more.
+    Ring(usize),
+}
+
+impl IngestionBuffer {
+    /// Ring buffer holding `DEFAULT_RING_CAPACITY` samples per sensor.
+    pub fn ring() -> Self {
+        IngestionBuffer::Ring(DEFAULT_RING_CAPACITY)
+    }
+
+    /// Returns an InvalidIngestion error if a ring buffer can't hold any sample.
+    pub fn validate(&self) -> Result<(), ResamplerError> {
+        match self {
+            IngestionBuffer::Ring(0) => Err(ResamplerError::InvalidIngestion(0)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Raw samples of a sensor, pushed by the source and taken by the resampler.
+pub(crate) enum SensorBuffer<T, S> {
+    Locked(Mutex<T>),
+    Ring {
+        // empty readings with the tag and sensor type of the ring
+        readings: T,
+        // each side of the ring is locked on its own, so pushing never waits for the resampler
+        producer: Mutex<Producer<S>>,
+        consumer: Mutex<Consumer<S>>,
+        n_dropped: AtomicUsize,
+    },
+}
+
+impl<T, S> SensorBuffer<T, S>
+where
+    S: IMUSample,
+    T: IMUReadings<S>,
+{
+    pub(crate) fn new(tag: &str, sensor_type: SensorType, ingestion: IngestionBuffer) -> Self {
+        let readings = T::from_vec(tag, sensor_type, vec![]);
+        match ingestion {
+            IngestionBuffer::Locked => SensorBuffer::Locked(Mutex::new(readings)),
+            IngestionBuffer::Ring(capacity) => {
+                let (producer, consumer) = spsc_ring(capacity);
+                SensorBuffer::Ring {
+                    readings,
+                    producer: Mutex::new(producer),
+                    consumer: Mutex::new(consumer),
+                    n_dropped: AtomicUsize::new(0),
+                }
+            }
+        }
+    }
+
+    pub(crate) fn push(&self, samples: &T) {
+        match self {
+            SensorBuffer::Locked(mutex) => {
+                let mut data = mutex.lock().unwrap();
+                data.extend(samples.get_samples());
+            }
+            SensorBuffer::Ring {
+                producer,
+                n_dropped,
+                ..
+            } => {
+                let dropped = producer
+                    .lock()
+                    .unwrap()
+                    .push_slice(samples.get_samples_ref());
+                if dropped > 0 {
+                    n_dropped.fetch_add(dropped, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    /// Returns the buffered samples, leaving the buffer empty.
+    pub(crate) fn take(&self) -> T {
+        match self {
+            SensorBuffer::Locked(mutex) => {
+                let mut data = mutex.lock().unwrap();
+                let readings = data.clone();
+                data.clear();
+                readings
+            }
+            SensorBuffer::Ring {
+                readings,
+                consumer,
+                n_dropped,
+                ..
+            } => {
+                let n_dropped = n_dropped.swap(0, Ordering::Relaxed);
+                if n_dropped > 0 {
+                    log::warn!(
+                        "Ingestion buffer of {:?} full, dropped {} samples",
+                        readings.get_sensor_type(),
+                        n_dropped
+                    );
+                }
+                let mut readings = readings.clone();
+                readings.extend(consumer.lock().unwrap().drain());
+                readings
+            }
+        }
+    }
+}
+
+/// Creates a bounded single producer single consumer queue holding at least `capacity` samples,
+/// rounded up to a power of two.
+///
+/// The ring is only reachable through the returned handles. Neither is `Clone` and both take
+/// `&mut self`, so there is a single producer and a single consumer at any time. The producer
+/// only writes the tail and the consumer only writes the head, so pushing and draining run
+/// concurrently without locks.
+pub(crate) fn spsc_ring<S>(capacity: usize) -> (Producer<S>, Consumer<S>) {
+    let capacity = capacity.max(1).next_power_of_two();
+    let ring = Arc::new(Ring {
+        slots: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        mask: capacity - 1,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+    });
+    (
+        Producer {
+            ring: ring.clone(),
+            tail: 0,
+            head: 0,
+        },
+        Consumer { ring, head: 0 },
+    )
+}
+
+struct Ring<S> {
+    slots: Box<[UnsafeCell<MaybeUninit<S>>]>,
+    mask: usize,
+    // index of the next slot to read, only written by the consumer
+    head: AtomicUsize,
+    // index of the next slot to write, only written by the producer
+    tail: AtomicUsize,
+}
+
+// samples are moved between threads, and slots are only accessed by the handle owning them
+unsafe impl<S: Send> Send for Ring<S> {}
+unsafe impl<S: Send> Sync for Ring<S> {}
+
+impl<S> Ring<S> {
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+impl<S> Drop for Ring<S> {
+    fn drop(&mut self) {
+        let tail = *self.tail.get_mut();
+        let mut head = *self.head.get_mut();
+        while head != tail {
+            // SAFETY: slots between head and tail hold samples that were never read
+            unsafe { self.slots[head & self.mask].get_mut().assume_init_drop() };
+            head = head.wrapping_add(1);
+        }
+    }
+}
+
+/// Writing side of a `spsc_ring`.
+pub(crate) struct Producer<S> {
+    ring: Arc<Ring<S>>,
+    // local copy of the tail, only written by this handle
+    tail: usize,
+    // last head seen, refreshed when the ring looks full
+    head: usize,
+}
+
+impl<S: Clone> Producer<S> {
+    pub(crate) fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    /// Appends copies of `samples`, dropping the ones that don't fit. Returns the number of
+    /// dropped samples.
+    pub(crate) fn push_slice(&mut self, samples: &[S]) -> usize {
+        let mut n_dropped = 0;
+        for sample in samples {
+            if self.tail.wrapping_sub(self.head) == self.capacity() {
+                self.head = self.ring.head.load(Ordering::Acquire);
+                if self.tail.wrapping_sub(self.head) == self.capacity() {
+                    n_dropped += 1;
+                    continue;
+                }
+            }
+            // SAFETY: the slot is free, as the consumer published a head past it, and only
+            // this handle writes slots until the tail is published
+            unsafe { (*self.ring.slots[self.tail & self.ring.mask].get()).write(sample.clone()) };
+            self.tail = self.tail.wrapping_add(1);
+        }
+        self.ring.tail.store(self.tail, Ordering::Release);
+        n_dropped
+    }
+}
+
+/// Reading side of a `spsc_ring`.
+pub(crate) struct Consumer<S> {
+    ring: Arc<Ring<S>>,
+    // local copy of the head, only written by this handle
+    head: usize,
+}
+
+impl<S> Consumer<S> {
+    /// Removes and returns the samples pushed so far, oldest first.
+    pub(crate) fn drain(&mut self) -> Vec<S> {
+        let tail = self.ring.tail.load(Ordering::Acquire);
+        let mut samples = Vec::with_capacity(tail.wrapping_sub(self.head));
+        while self.head != tail {
+            // SAFETY: slots between head and tail were written by the producer before
+            // publishing the tail, and are only read once
+            samples.push(unsafe {
+                (*self.ring.slots[self.head & self.ring.mask].get()).assume_init_read()
+            });
+            self.head = self.head.wrapping_add(1);
+        }
+        self.ring.head.store(self.head, Ordering::Release);
+        samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::types::sensors::SensorReadings;
+    use imu_common::types::timed::Sample3D;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_push_and_drain() {
+        let (mut producer, mut consumer) = spsc_ring(5);
+        assert_eq!(producer.capacity(), 8);
+        // wraps around the end of the slots
+        for round in 0..3 {
+            let samples: Vec<usize> = (0..6).map(|i| round * 10 + i).collect();
+            assert_eq!(producer.push_slice(&samples), 0);
+            assert_eq!(consumer.drain(), samples);
+        }
+        assert!(consumer.drain().is_empty());
+    }
+
+    #[test]
+    fn test_drop_when_full() {
+        let (mut producer, mut consumer) = spsc_ring(4);
+        assert_eq!(producer.push_slice(&[0, 1, 2]), 0);
+        assert_eq!(producer.push_slice(&[3, 4, 5]), 2);
+        assert_eq!(consumer.drain(), vec![0, 1, 2, 3]);
+        assert_eq!(producer.push_slice(&[6]), 0);
+        assert_eq!(consumer.drain(), vec![6]);
+    }
+
+    #[test]
+    fn test_drop_pending_samples() {
+        let sample = Arc::new(0);
+        let (mut producer, consumer) = spsc_ring(4);
+        producer.push_slice(&[sample.clone(), sample.clone()]);
+        assert_eq!(Arc::strong_count(&sample), 3);
+        drop(producer);
+        assert_eq!(Arc::strong_count(&sample), 3);
+        drop(consumer);
+        assert_eq!(Arc::strong_count(&sample), 1);
+    }
+
+    #[test]
+    fn test_concurrent_producer() {
+        let (mut producer, mut consumer) = spsc_ring(64);
+        let producer = std::thread::spawn(move || {
+            (0..10_000usize)
+                .map(|i| producer.push_slice(&[i]))
+                .sum::<usize>()
+        });
+        let mut received = Vec::new();
+        while !producer.is_finished() {
+            received.extend(consumer.drain());
+        }
+        let n_dropped = producer.join().unwrap();
+        received.extend(consumer.drain());
+        // samples arrive in order, and are either received or dropped
+        assert!(received.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(received.len() + n_dropped, 10_000);
+    }
+
+    #[test]
+    fn test_sensor_buffer() {
+        let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+        for ingestion in [IngestionBuffer::Locked, IngestionBuffer::Ring(4)] {
+            let buffer: SensorBuffer<SensorReadings<Sample3D>, Sample3D> =
+                SensorBuffer::new("test", sensor_type.clone(), ingestion);
+            let samples = vec![
+                Sample3D::new(0.0, [1.0, 2.0, 3.0]),
+                Sample3D::new(0.1, [4.0, 5.0, 6.0]),
+            ];
+            buffer.push(&SensorReadings::from_vec(
+                "test",
+                sensor_type.clone(),
+                samples.clone(),
+            ));
+            let readings = buffer.take();
+            assert_eq!(readings.get_sensor_tag(), "test");
+            assert_eq!(readings.get_sensor_type(), sensor_type);
+            assert_eq!(readings.get_samples(), samples);
+            assert!(buffer.take().is_empty());
+        }
+        assert_eq!(
+            IngestionBuffer::Ring(0).validate(),
+            Err(ResamplerError::InvalidIngestion(0))
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Module metrics
+//!
+//! Statistics of the samples received and published by a resampler pipeline, to spot sensors
+//! that arrive late, irregularly or not at all.
+
+use std::collections::HashMap;
+
+use imu_common::types::sensors::{SensorClusterBuilder, SensorType};
+
+/// Statistics of a single sensor since it was added to the pipeline.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SensorMetrics {
+    /// Raw samples received from the source.
+    pub n_samples: usize,
+    /// Raw samples dropped because they arrived after their resampling period was buffered.
+    pub n_late: usize,
+    /// Input rate, from the mean interval between consecutive raw samples.
+    pub input_rate_hz: f64,
+    /// Standard deviation of the interval between consecutive raw samples.
+    pub jitter_millis: f64,
+    /// Outputs resampled from raw samples.
+    pub n_measured: usize,
+    /// Outputs resampled while no raw sample was received, holding the previous value.
+    pub n_interpolated: usize,
+}
+
+/// Snapshot of the metrics of every sensor of a pipeline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PipelineMetrics {
+    /// Diagnostic sensor the snapshot is published as.
+    pub sensor_type: SensorType,
+    pub timestamp_secs: f64,
+    /// Metrics of each sensor, in the order of the sensor cluster.
+    pub sensors: Vec<(SensorType, SensorMetrics)>,
+}
+
+impl PipelineMetrics {
+    pub fn get(&self, sensor_type: &SensorType) -> Option<&SensorMetrics> {
+        self.sensors
+            .iter()
+            .find(|(sensor, _)| sensor == sensor_type)
+            .map(|(_, metrics)| metrics)
+    }
+}
+
+/// Returns the diagnostic sensor the metrics of the pipeline tagged `tag` are published as.
+pub(crate) fn metrics_sensor(tag: &str) -> SensorType {
+    SensorClusterBuilder::new()
+        .with_tag(tag)
+        .vendor("resampler", "metrics")
+        .build()
+        .expect("Metrics sensor is valid")
+        .remove(0)
+}
+
+#[derive(Clone, Debug, Default)]
+struct SensorStats {
+    metrics: SensorMetrics,
+    last_timestamp_secs: Option<f64>,
+    // Welford's running mean and sum of squared deviations of the sample intervals
+    n_intervals: usize,
+    mean_interval_secs: f64,
+    m2_interval_secs: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MetricsCollector {
+    stats: HashMap<SensorType, SensorStats>,
+}
+
+impl MetricsCollector {
+    /// Records the raw samples of `sensor_type` collected at `buffering_timestamp_secs`.
+    /// Samples older than the buffering timestamp are counted as late.
+    pub(crate) fn record_samples(
+        &mut self,
+        sensor_type: &SensorType,
+        timestamps_secs: &[f64],
+        buffering_timestamp_secs: f64,
+    ) {
+        let stats = self.stats.entry(sensor_type.clone()).or_default();
+        for &timestamp_secs in timestamps_secs {
+            stats.metrics.n_samples += 1;
+            if timestamp_secs < buffering_timestamp_secs {
+                stats.metrics.n_late += 1;
+            }
+            if let Some(last_timestamp_secs) = stats.last_timestamp_secs {
+                let interval_secs = timestamp_secs - last_timestamp_secs;
+                stats.n_intervals += 1;
+                let delta = interval_secs - stats.mean_interval_secs;
+                stats.mean_interval_secs += delta / stats.n_intervals as f64;
+                stats.m2_interval_secs += delta * (interval_secs - stats.mean_interval_secs);
+            }
+            stats.last_timestamp_secs = Some(timestamp_secs);
+        }
+        if stats.n_intervals > 0 && stats.mean_interval_secs > 0.0 {
+            stats.metrics.input_rate_hz = 1.0 / stats.mean_interval_secs;
+            stats.metrics.jitter_millis =
+                (stats.m2_interval_secs / stats.n_intervals as f64).sqrt() * 1000.0;
+        }
+    }
+
+    /// Records an output of `sensor_type`, resampled from raw samples if `measured`.
+    pub(crate) fn record_output(&mut self, sensor_type: &SensorType, measured: bool) {
+        let metrics = &mut self.stats.entry(sensor_type.clone()).or_default().metrics;
+        if measured {
+            metrics.n_measured += 1;
+        } else {
+            metrics.n_interpolated += 1;
+        }
+    }
+
+    pub(crate) fn remove_sensor(&mut self, sensor_type: &SensorType) {
+        self.stats.remove(sensor_type);
+    }
+
+    pub(crate) fn snapshot(
+        &self,
+        sensor_type: SensorType,
+        sensor_cluster: &[SensorType],
+        timestamp_secs: f64,
+    ) -> PipelineMetrics {
+        PipelineMetrics {
+            sensor_type,
+            timestamp_secs,
+            sensors: sensor_cluster
+                .iter()
+                .map(|sensor| {
+                    let metrics = self
+                        .stats
+                        .get(sensor)
+                        .map(|stats| stats.metrics.clone())
+                        .unwrap_or_default();
+                    (sensor.clone(), metrics)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_rate_and_jitter() {
+        let sensor = SensorType::Accelerometer(Uuid::new_v4());
+        let mut collector = MetricsCollector::default();
+
+        // intervals of 10 and 30 ms, in two batches
+        collector.record_samples(&sensor, &[1.0, 1.01], 0.5);
+        collector.record_samples(&sensor, &[1.04, 1.05, 1.08], 1.02);
+        collector.record_output(&sensor, true);
+        collector.record_output(&sensor, false);
+        collector.record_output(&sensor, false);
+
+        let snapshot =
+            collector.snapshot(metrics_sensor("test"), std::slice::from_ref(&sensor), 2.0);
+        let metrics = snapshot.get(&sensor).unwrap();
+        assert_eq!(metrics.n_samples, 5);
+        assert_eq!(metrics.n_late, 0);
+        assert!((metrics.input_rate_hz - 50.0).abs() < 1e-6);
+        assert!((metrics.jitter_millis - 10.0).abs() < 1e-6);
+        assert_eq!((metrics.n_measured, metrics.n_interpolated), (1, 2));
+    }
+
+    #[test]
+    fn test_late_samples() {
+        let sensor = SensorType::Gyroscope(Uuid::new_v4());
+        let mut collector = MetricsCollector::default();
+
+        collector.record_samples(&sensor, &[0.9, 1.1, 1.2], 1.0);
+        let snapshot =
+            collector.snapshot(metrics_sensor("test"), std::slice::from_ref(&sensor), 2.0);
+        assert_eq!(snapshot.get(&sensor).unwrap().n_late, 1);
+
+        collector.remove_sensor(&sensor);
+        let snapshot =
+            collector.snapshot(metrics_sensor("test"), std::slice::from_ref(&sensor), 2.0);
+        assert_eq!(snapshot.get(&sensor), Some(&SensorMetrics::default()));
+    }
+}
@@ -1,35 +1,72 @@
+pub mod batching;
 pub(crate) mod cache;
+pub(crate) mod delivery;
+pub mod drift;
+pub(crate) mod filtering;
+pub mod ingestion;
+pub mod metrics;
+pub mod offline;
 pub(crate) mod resampler;
 pub mod sink;
 pub mod source;
+pub(crate) mod tuning;
 
 pub(crate) use resampler::Resampler;
 
+use dashmap::DashMap;
 use imu_common::types::filters::Average;
 use imu_common::types::filters::WeightedAverage;
-use dashmap::DashMap;
+use std::collections::HashMap;
 use std::marker::PhantomData;
-use std::sync::{Arc, Mutex};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
 use std::time::{Duration, Instant};
 
+use crate::errors::ResamplerError;
+use crate::pipeline::batching::{OutputBatch, OutputBatching};
 use crate::pipeline::cache::{Cache, Interpolable};
+use crate::pipeline::delivery::OutputDelivery;
+use crate::pipeline::drift::{ClockDrift, DriftCorrection, DriftServo};
+use crate::pipeline::filtering::RawFilters;
+use crate::pipeline::ingestion::{IngestionBuffer, SensorBuffer};
+use crate::pipeline::metrics::{MetricsCollector, PipelineMetrics};
+use crate::pipeline::resampler::SensorSettings;
 use crate::utils;
 use crate::SmothingPolicy;
-use imu_common::traits::{IMUFilter, IMUReadings, IMUSample, IMUSource, IMUUntimedSample};
-use imu_common::types::filters::MovingAverage;
+use imu_common::traits::{
+    IMUFilter, IMUReadings, IMUSample, IMUSource, IMUUntimedSample, Notifiable,
+};
+use imu_common::types::filters::FilterChainBuilder;
 use imu_common::types::sensors::SensorType;
-use imu_common::types::Clock;
-use publisher::PublisherManager;
+use imu_common::types::{ClockSource, SystemClock};
+use publisher::{Publishable, Publisher, PublisherManager, ShutdownToken};
+use uuid::Uuid;
 
-const MIN_RESAMPLING_PERIOD_MILLIS: f64 = 5.0;
+pub(crate) const MIN_RESAMPLING_PERIOD_MILLIS: f64 = 5.0;
 
 #[derive(Clone)]
 pub struct ResamplerPipeline<T, S> {
     // buffer to store samples received from IMU Source
-    buffer: Arc<DashMap<SensorType, Mutex<T>>>,
-    publishers: PublisherManager<T, SensorType>,
+    buffer: Arc<DashMap<SensorType, SensorBuffer<T, S>>>,
+    ingestion: IngestionBuffer,
+    publishers: PublisherManager<T, SensorType, OutputDelivery>,
     tag: String,
-    sensor_cluster: Vec<SensorType>,
+    sensor_cluster: Arc<RwLock<Vec<SensorType>>>,
+    smoothing_policy: Arc<RwLock<SmothingPolicy>>,
+    smoothing_window_millis: Arc<RwLock<f64>>,
+    sensor_settings: Arc<RwLock<HashMap<SensorType, SensorSettings>>>,
+    batching: Arc<RwLock<OutputBatching>>,
+    batch: Arc<Mutex<OutputBatch<S>>>,
+    raw_filters: Arc<Mutex<RawFilters<S>>>,
+    metrics: Arc<Mutex<MetricsCollector>>,
+    metrics_publisher: Publisher<PipelineMetrics>,
+    metrics_sensor: SensorType,
+    clock: Arc<dyn ClockSource>,
+    drift_correction: Option<DriftCorrection>,
+    clock_drift: Arc<RwLock<Option<ClockDrift>>>,
+    paused: Arc<AtomicBool>,
+    shutdown: ShutdownToken,
     _phantom_data: PhantomData<S>,
 }
 
@@ -40,75 +77,492 @@ where
     S::Untimed: IMUUntimedSample,
     Average<S::Untimed>: IMUFilter<S>,
     WeightedAverage<S::Untimed>: IMUFilter<S>,
-    MovingAverage<S::Untimed>: IMUFilter<S>,
     Cache<S, S::Untimed>: Interpolable<S, S::Untimed>,
 {
     pub fn new(tag: &str, sensor_cluster: Vec<SensorType>) -> Self {
-        let buffer: DashMap<SensorType, Mutex<T>> = sensor_cluster
-            .iter()
-            .map(|s| (s.clone(), Mutex::new(T::from_vec(tag, s.clone(), vec![]))))
-            .collect();
+        Self::with_delivery(tag, sensor_cluster, OutputDelivery::default())
+    }
+
+    pub(crate) fn with_delivery(
+        tag: &str,
+        sensor_cluster: Vec<SensorType>,
+        delivery: OutputDelivery,
+    ) -> Self {
+        let ingestion = IngestionBuffer::default();
         Self {
-            buffer: Arc::new(buffer),
-            publishers: PublisherManager::new(&sensor_cluster),
+            buffer: Arc::new(sensor_buffers(tag, &sensor_cluster, ingestion)),
+            ingestion,
+            publishers: PublisherManager::with_delivery(&sensor_cluster, delivery),
             tag: tag.to_string(),
-            sensor_cluster,
+            sensor_cluster: Arc::new(RwLock::new(sensor_cluster)),
+            smoothing_policy: Arc::new(RwLock::new(SmothingPolicy::default())),
+            smoothing_window_millis: Arc::new(RwLock::new(0.0)),
+            sensor_settings: Arc::new(RwLock::new(HashMap::new())),
+            batching: Arc::new(RwLock::new(OutputBatching::default())),
+            batch: Arc::new(Mutex::new(OutputBatch::default())),
+            raw_filters: Arc::new(Mutex::new(RawFilters::default())),
+            metrics: Arc::new(Mutex::new(MetricsCollector::default())),
+            metrics_publisher: Publisher::new(),
+            metrics_sensor: metrics::metrics_sensor(tag),
+            clock: Arc::new(SystemClock),
+            drift_correction: None,
+            clock_drift: Arc::new(RwLock::new(None)),
+            paused: Arc::new(AtomicBool::new(false)),
+            shutdown: ShutdownToken::global(),
             _phantom_data: PhantomData,
         }
     }
 
+    /// Stops the pipeline when `token` is shut down, instead of the global token.
+    pub fn with_shutdown_token(mut self, token: ShutdownToken) -> Self {
+        self.shutdown = token;
+        self
+    }
+
+    /// Reads the time from `clock` instead of the system clock, e.g. to resample on simulated
+    /// time.
+    pub fn with_clock(mut self, clock: Arc<dyn ClockSource>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Timestamps the resampling periods on a grid following the clock, correcting the
+    /// resampling period by a few ppm, instead of reading the clock every period. Takes effect
+    /// once the pipeline is started.
+    pub fn with_drift_correction(mut self, drift_correction: DriftCorrection) -> Self {
+        self.drift_correction = Some(drift_correction);
+        self
+    }
+
+    /// Buffers the raw samples of every sensor as set by `ingestion`. Must be called before the
+    /// pipeline is attached to a source, as samples already buffered are dropped.
+    pub fn with_ingestion_buffer(mut self, ingestion: IngestionBuffer) -> Self {
+        let sensor_cluster = self.sensor_cluster.read().unwrap().clone();
+        self.buffer = Arc::new(sensor_buffers(&self.tag, &sensor_cluster, ingestion));
+        self.ingestion = ingestion;
+        self
+    }
+
+    /// Adds `sensor_type` to the pipeline, allocating its buffer and publisher, and its cache slot
+    /// from the next resampling period. Sensors are never added in the middle of a resampling
+    /// period. It is resampled once the pipeline is attached to a source publishing it.
+    /// Returns a DuplicatedSensor error if the pipeline already resamples `sensor_type`.
+    pub fn add_sensor(&self, sensor_type: SensorType) -> Result<(), ResamplerError> {
+        let mut sensor_cluster = self.sensor_cluster.write().unwrap();
+        if sensor_cluster.contains(&sensor_type) {
+            return Err(ResamplerError::DuplicatedSensor(format!(
+                "{:?}",
+                sensor_type
+            )));
+        }
+        self.buffer.insert(
+            sensor_type.clone(),
+            SensorBuffer::new(&self.tag, sensor_type.clone(), self.ingestion),
+        );
+        self.publishers
+            .add_publisher(sensor_type.clone())
+            .expect("Resampler publishers don't claim their sensor types");
+        sensor_cluster.push(sensor_type);
+        Ok(())
+    }
+
+    /// Removes `sensor_type` from the pipeline, dropping its buffered samples, pending outputs,
+    /// metrics and cache slot and unregistering its listeners, in between resampling periods. Samples still
+    /// received from the source for `sensor_type` are ignored.
+    /// Returns an UnknownSensor error if the pipeline doesn't resample `sensor_type`.
+    pub fn remove_sensor(&self, sensor_type: &SensorType) -> Result<(), ResamplerError> {
+        let mut sensor_cluster = self.sensor_cluster.write().unwrap();
+        let Some(index) = sensor_cluster.iter().position(|s| s == sensor_type) else {
+            return Err(ResamplerError::UnknownSensor(format!("{:?}", sensor_type)));
+        };
+        sensor_cluster.remove(index);
+        self.buffer.remove(sensor_type);
+        self.publishers.remove_publisher(sensor_type);
+        self.sensor_settings.write().unwrap().remove(sensor_type);
+        self.metrics.lock().unwrap().remove_sensor(sensor_type);
+        self.batch.lock().unwrap().remove_sensor(sensor_type);
+        self.raw_filters.lock().unwrap().remove_sensor(sensor_type);
+        Ok(())
+    }
+
     pub fn collect_samples(&self, buffering_timestamp_secs: f64) -> Vec<T> {
-        let mut buffer_clone = utils::clone_and_clear(self.buffer.clone());
+        let mut buffer_clone = utils::clone_and_clear(&self.buffer);
+        let mut metrics = self.metrics.lock().unwrap();
+        let mut raw_filters = self.raw_filters.lock().unwrap();
         for sensor_buffer in buffer_clone.iter_mut() {
+            let timestamps: Vec<f64> = sensor_buffer
+                .get_samples()
+                .iter()
+                .map(|sample| sample.get_timestamp_secs())
+                .collect();
+            metrics.record_samples(
+                &sensor_buffer.get_sensor_type(),
+                &timestamps,
+                buffering_timestamp_secs,
+            );
             utils::collect_samples(sensor_buffer, buffering_timestamp_secs);
+            let samples = raw_filters.filter(
+                &sensor_buffer.get_sensor_type(),
+                sensor_buffer.get_samples(),
+            );
+            sensor_buffer.clear();
+            sensor_buffer.extend(samples);
         }
 
         buffer_clone
     }
 
-    fn notify(&self, buffer: Vec<(SensorType, S)>) {
+    fn notify(&self, buffer: Vec<(SensorType, Vec<S>)>) {
         for (sensor_type, samples) in buffer {
-            let readings = T::from_vec(&self.tag, sensor_type.clone(), vec![samples]);
+            let readings = T::from_vec(&self.tag, sensor_type.clone(), samples);
             self.notify_listeners(sensor_type, Arc::new(readings));
         }
     }
 
+    /// Resamples the buffered readings every `resampling_period_millis` until the shutdown token
+    /// is shut down.
+    /// Returns an InvalidPeriod error, without resampling, if the period is below the minimum
+    /// resampling period.
     pub fn start(
         &self,
         resample_policy: SmothingPolicy,
         resampling_period_millis: f64,
         resampling_delay_millis: f64,
+    ) -> Result<(), ResamplerError> {
+        if !(resampling_period_millis >= MIN_RESAMPLING_PERIOD_MILLIS
+            && resampling_period_millis.is_finite())
+        {
+            return Err(ResamplerError::InvalidPeriod(resampling_period_millis));
+        }
+        Self::resample_loop(
+            || Some(self),
+            resample_policy,
+            resampling_period_millis,
+            resampling_delay_millis,
+        );
+        Ok(())
+    }
+
+    /// Same as `start`, but only holding `pipeline` while resampling, so the loop also ends
+    /// once the pipeline is dropped. The period must have been validated by the builder.
+    pub(crate) fn start_weak(
+        pipeline: Weak<Self>,
+        resample_policy: SmothingPolicy,
+        resampling_period_millis: f64,
+        resampling_delay_millis: f64,
     ) {
-        let resampling_period_millis =
-            f64::max(resampling_period_millis, MIN_RESAMPLING_PERIOD_MILLIS);
+        Self::resample_loop(
+            || pipeline.upgrade(),
+            resample_policy,
+            resampling_period_millis,
+            resampling_delay_millis,
+        );
+    }
+
+    /// Runs a single resampling period at `timestamp_now_secs`, publishing the readings
+    /// resampled `resampling_delay_secs` before.
+    fn resample(
+        &self,
+        resampler: &mut Resampler<S, S::Untimed>,
+        timestamp_now_secs: f64,
+        resampling_delay_secs: f64,
+    ) {
+        // raw samples received while paused are discarded, and resampling restarts from scratch
+        // on resume, so no readings are interpolated across the gap
+        if self.is_paused() {
+            utils::clone_and_clear(&self.buffer);
+            *resampler = Resampler::new(&self.get_sensor_cluster(), self.get_smoothing_policy());
+            return;
+        }
+        let buffering_timestamp = timestamp_now_secs - resampling_delay_secs;
+        let resample_timestamp = timestamp_now_secs - resampling_delay_secs / 2.0;
+
+        // sensors are added and removed while holding the cluster for writing, so they appear
+        // or disappear all at once between resampling periods. The cluster is released before
+        // notifying, so listeners can add and remove sensors too.
+        let sensor_cluster = self.sensor_cluster.read().unwrap();
+        resampler.set_sensor_cluster(&sensor_cluster);
+
+        // collect samples every buffering period = resampling_period * buffering_factor.
+        let buffered = buffering_timestamp > resampler.peek_newest_timestamp();
+        if buffered {
+            resampler.set_policy(self.get_smoothing_policy());
+            resampler.set_smoothing_window(self.get_smoothing_window_millis() / 1000.0);
+            resampler.set_sensor_settings(self.sensor_settings.read().unwrap().clone());
+            // raw samples are samples collected by imu source with timestamp after buffering timestamp
+            let raw_samples = self.collect_samples(buffering_timestamp);
+
+            // smooth collected samples and add timestamp
+            resampler.buffer_samples(raw_samples, resample_timestamp);
+        }
+        let processed_samples = resampler.interpolate(buffering_timestamp);
+        let mut metrics = self.metrics.lock().unwrap();
+        for (sensor_type, _) in processed_samples.iter() {
+            metrics.record_output(sensor_type, !resampler.is_held(sensor_type));
+        }
+        drop(metrics);
+        let batch = self.batch.lock().unwrap().push(
+            self.get_output_batching(),
+            timestamp_now_secs,
+            processed_samples,
+        );
+        drop(sensor_cluster);
+
+        if buffered {
+            self.publish_metrics(timestamp_now_secs);
+        }
+        // readings of sensors removed in the meantime are dropped, as their publisher is gone
+        self.notify(batch);
+    }
+
+    fn resample_loop<P, F>(
+        pipeline: F,
+        resample_policy: SmothingPolicy,
+        resampling_period_millis: f64,
+        resampling_delay_millis: f64,
+    ) where
+        P: Deref<Target = Self>,
+        F: Fn() -> Option<P>,
+    {
+        let (shutdown, mut resampler) = match pipeline() {
+            Some(pipeline) => {
+                pipeline.set_smoothing_policy(resample_policy);
+                (
+                    pipeline.shutdown.clone(),
+                    Resampler::<S, S::Untimed>::new(
+                        &pipeline.get_sensor_cluster(),
+                        resample_policy,
+                    ),
+                )
+            }
+            None => return,
+        };
         let resampling_period_secs = resampling_period_millis / 1000.0;
         let resampling_delay_secs = resampling_delay_millis / 1000.0;
-        let mut resampler = Resampler::<S, S::Untimed>::new(&self.sensor_cluster, resample_policy);
         let resampling_duration_secs = Duration::from_secs_f64(resampling_period_secs);
+        let mut servo = pipeline().and_then(|pipeline| {
+            pipeline
+                .drift_correction
+                .map(|settings| DriftServo::new(settings, resampling_period_secs))
+        });
 
-        loop {
+        while !shutdown.is_shutdown() {
             let start_time = Instant::now();
+            let Some(pipeline) = pipeline() else {
+                return;
+            };
 
-            let timestamp_now_secs = Clock::now().as_secs();
-            let buffering_timestamp = timestamp_now_secs - resampling_delay_secs;
-            let resample_timestamp = timestamp_now_secs - resampling_delay_secs / 2.0;
-
-            // collect samples every buffering period = resampling_period * buffering_factor.
-            if buffering_timestamp > resampler.peek_newest_timestamp() {
-                // raw samples are samples collected by imu source with timestamp after buffering timestamp
-                let raw_samples = self.collect_samples(buffering_timestamp);
-
-                // smooth collected samples and add timestamp
-                resampler.buffer_samples(raw_samples, resample_timestamp);
+            let mut timestamp_now_secs = pipeline.clock.now_secs();
+            if let Some(servo) = servo.as_mut() {
+                timestamp_now_secs = servo.timestamp(timestamp_now_secs);
+                *pipeline.clock_drift.write().unwrap() = Some(servo.get_drift());
             }
-            let processed_samples = resampler.interpolate(buffering_timestamp);
-            self.notify(processed_samples);
+            pipeline.resample(&mut resampler, timestamp_now_secs, resampling_delay_secs);
+            drop(pipeline);
 
             let elapsed = start_time.elapsed();
             if elapsed < resampling_duration_secs {
-                std::thread::sleep(resampling_duration_secs - elapsed);
+                shutdown.sleep(resampling_duration_secs - elapsed);
+            }
+        }
+    }
+}
+
+fn sensor_buffers<T, S>(
+    tag: &str,
+    sensor_cluster: &[SensorType],
+    ingestion: IngestionBuffer,
+) -> DashMap<SensorType, SensorBuffer<T, S>>
+where
+    S: IMUSample,
+    T: IMUReadings<S>,
+{
+    sensor_cluster
+        .iter()
+        .map(|s| (s.clone(), SensorBuffer::new(tag, s.clone(), ingestion)))
+        .collect()
+}
+
+impl<T, S> ResamplerPipeline<T, S> {
+    /// Returns the metrics of every sensor resampled by the pipeline.
+    pub fn get_metrics(&self) -> PipelineMetrics {
+        self.snapshot_metrics(self.clock.now_secs())
+    }
+
+    /// Returns the state of the drift correction, or None if the period isn't corrected or
+    /// the pipeline wasn't started.
+    pub fn get_clock_drift(&self) -> Option<ClockDrift> {
+        *self.clock_drift.read().unwrap()
+    }
+
+    /// Pauses resampling, e.g. while a duty cycled source is stopped. Nothing is published
+    /// until `resume` is called, and the samples received in the meantime are discarded.
+    ///
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use imu_common::traits::IMUSink;
+    /// use imu_common::types::sensors::{SensorReadings, SensorType};
+    /// use imu_common::types::timed::Sample3D;
+    /// use phyphox_rs::models::duty_cycle::{AcquisitionPhase, DutyCycle};
+    /// use publisher::Listener;
+    /// use resampler_rs::ResamplerBuilder;
+    ///
+    /// # async fn run() {
+    /// let sensor_cluster = SensorType::cluster_for_tag("phone");
+    /// let duty_cycle = DutyCycle::new(Duration::from_secs(10), Duration::from_secs(50));
+    /// let (_, source) = phyphox_rs::run_duty_cycled_service(
+    ///     "http://192.168.1.10:8080",
+    ///     "phone",
+    ///     sensor_cluster.clone(),
+    ///     100.0,
+    ///     duty_cycle,
+    /// )
+    /// .unwrap();
+    /// let (_, pipeline) = ResamplerBuilder::new("phone", sensor_cluster.clone())
+    ///     .run::<SensorReadings<Sample3D>, Sample3D>()
+    ///     .unwrap();
+    /// pipeline.attach_listeners(&*source, &sensor_cluster).unwrap();
+    ///
+    /// let mut listener = Listener::new({
+    ///     let pipeline = pipeline.clone();
+    ///     move |_id, phase: Arc<AcquisitionPhase>| match *phase {
+    ///         AcquisitionPhase::Active { .. } => pipeline.resume(),
+    ///         AcquisitionPhase::Paused { .. } => pipeline.pause(),
+    ///     }
+    /// });
+    /// source.register_phase_listener(&mut listener);
+    /// # }
+    /// ```
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Resumes resampling after `pause`, from the samples received from then on.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Returns the diagnostic sensor the metrics are published as.
+    pub fn get_metrics_sensor(&self) -> SensorType {
+        self.metrics_sensor.clone()
+    }
+
+    /// Registers a listener notified of the metrics of the pipeline every time raw samples are
+    /// collected.
+    pub fn register_metrics_listener(
+        &self,
+        listener: &mut dyn Notifiable<PipelineMetrics>,
+    ) -> Uuid {
+        self.metrics_publisher.register_listener(listener)
+    }
+
+    pub fn unregister_metrics_listener(&self, id: Uuid) {
+        self.metrics_publisher.unregister_listener(id);
+    }
+
+    fn publish_metrics(&self, timestamp_secs: f64) {
+        let metrics = self.snapshot_metrics(timestamp_secs);
+        self.metrics_publisher.notify_listeners(Arc::new(metrics));
+    }
+
+    fn snapshot_metrics(&self, timestamp_secs: f64) -> PipelineMetrics {
+        // the cluster is locked before the metrics everywhere, so they can't deadlock
+        let sensor_cluster = self.get_sensor_cluster();
+        self.metrics.lock().unwrap().snapshot(
+            self.metrics_sensor.clone(),
+            &sensor_cluster,
+            timestamp_secs,
+        )
+    }
+
+    pub fn get_sensor_cluster(&self) -> Vec<SensorType> {
+        self.sensor_cluster.read().unwrap().clone()
+    }
+
+    pub fn get_smoothing_policy(&self) -> SmothingPolicy {
+        *self.smoothing_policy.read().unwrap()
+    }
+
+    /// Changes the smoothing policy of a running pipeline. It takes effect from the next
+    /// resampling period.
+    pub fn set_smoothing_policy(&self, policy: SmothingPolicy) {
+        *self.smoothing_policy.write().unwrap() = policy;
+    }
+
+    pub fn get_filter_chain(&self) -> Option<FilterChainBuilder<S>> {
+        self.raw_filters.lock().unwrap().get_builder()
+    }
+
+    /// Filters the raw samples of every sensor with a chain built by `builder` before they are
+    /// smoothed, or stops filtering them if `None`. It takes effect from the next resampling
+    /// period, with chains that haven't received any sample.
+    pub fn set_filter_chain(&self, builder: Option<FilterChainBuilder<S>>) {
+        self.raw_filters.lock().unwrap().set_builder(builder);
+    }
+
+    pub fn get_smoothing_window_millis(&self) -> f64 {
+        *self.smoothing_window_millis.read().unwrap()
+    }
+
+    /// Smooths the raw samples received during the last `smoothing_window_millis` before
+    /// interpolating, instead of only the samples of the last resampling period. This trades
+    /// latency for noise reduction without changing the output rate. A window of 0, the
+    /// default, smooths each period on its own. Negative windows are treated as 0.
+    pub fn set_smoothing_window_millis(&self, smoothing_window_millis: f64) {
+        *self.smoothing_window_millis.write().unwrap() = smoothing_window_millis.max(0.0);
+    }
+
+    pub fn get_output_batching(&self) -> OutputBatching {
+        *self.batching.read().unwrap()
+    }
+
+    /// Accumulates the outputs of several resampling periods into a single reading per sensor
+    /// before notifying the listeners. It takes effect from the next resampling period, and
+    /// outputs pending when batching is disabled are published with that period.
+    /// Returns an InvalidBatching error if batches would be empty.
+    pub fn set_output_batching(&self, batching: OutputBatching) -> Result<(), ResamplerError> {
+        batching.validate()?;
+        *self.batching.write().unwrap() = batching;
+        Ok(())
+    }
+
+    /// Returns the settings of `sensor_type`. Unset settings follow those of the pipeline.
+    pub fn get_sensor_settings(&self, sensor_type: &SensorType) -> SensorSettings {
+        self.sensor_settings
+            .read()
+            .unwrap()
+            .get(sensor_type)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Overrides the smoothing policy or resampling period of `sensor_type`. It takes effect
+    /// from the next resampling period.
+    /// Returns an UnknownSensor error if `sensor_type` isn't resampled by this pipeline, and an
+    /// InvalidPeriod error if the period is below the minimum resampling period.
+    pub fn set_sensor_settings(
+        &self,
+        sensor_type: &SensorType,
+        settings: SensorSettings,
+    ) -> Result<(), ResamplerError> {
+        if !self.sensor_cluster.read().unwrap().contains(sensor_type) {
+            return Err(ResamplerError::UnknownSensor(format!("{:?}", sensor_type)));
+        }
+        if let Some(period_millis) = settings.get_resampling_period_millis() {
+            if !(period_millis >= MIN_RESAMPLING_PERIOD_MILLIS && period_millis.is_finite()) {
+                return Err(ResamplerError::InvalidPeriod(period_millis));
             }
         }
+        self.sensor_settings
+            .write()
+            .unwrap()
+            .insert(sensor_type.clone(), settings);
+        Ok(())
     }
 }
 
@@ -119,6 +573,7 @@ mod tests {
     use imu_common::traits::Notifiable;
     use imu_common::types::sensors::SensorReadings;
     use imu_common::types::timed::Sample3D;
+    use imu_common::types::Clock;
     use publisher::{listener, Listener};
     use std::sync::mpsc;
     use uuid::Uuid;
@@ -149,9 +604,8 @@ mod tests {
             .get(&SensorType::Accelerometer(acc_id))
             .unwrap();
 
-        let data = buffer.lock().unwrap();
-        let snapshot = data.clone();
-        drop(data);
+        let snapshot = buffer.take();
+        drop(buffer);
         callback(Uuid::new_v4(), Arc::new(snapshot.clone()));
     }
 
@@ -168,16 +622,14 @@ mod tests {
         ));
         let listener = listener!(pipeline.process_samples);
 
-        //let listener = async_listener!(resampler.handle);
         let callback = listener.get_callback();
         let buffer = pipeline
             .buffer
             .get(&SensorType::Accelerometer(acc_id))
             .unwrap();
 
-        let data = buffer.lock().unwrap();
-        let snapshot = data.clone();
-        drop(data);
+        let snapshot = buffer.take();
+        drop(buffer);
         callback(Uuid::new_v4(), Arc::new(snapshot.clone()));
     }
 
@@ -197,18 +649,252 @@ mod tests {
         // Wait for the result with a timeout
         rx.recv_timeout(timeout).ok()
     }
+    #[test]
+    fn test_stop_on_shutdown() {
+        let token = ShutdownToken::new();
+        let pipeline = ResamplerPipeline::<SensorReadings<Sample3D>, _>::new(
+            "test",
+            SensorType::cluster_for_tag("test_stop_on_shutdown"),
+        )
+        .with_shutdown_token(token.clone());
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            pipeline
+                .start(SmothingPolicy::default(), 10.0, 50.0)
+                .unwrap();
+            let _ = tx.send(());
+        });
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+        token.shutdown();
+        assert!(rx.recv_timeout(Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn test_start_invalid_period() {
+        let pipeline = ResamplerPipeline::<SensorReadings<Sample3D>, _>::new(
+            "test",
+            SensorType::cluster_for_tag("test_start_invalid_period"),
+        );
+
+        for period_millis in [1.0, f64::NAN] {
+            assert!(matches!(
+                pipeline.start(SmothingPolicy::default(), period_millis, 50.0),
+                Err(ResamplerError::InvalidPeriod(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hot_plug_sensor() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_hot_plug_sensor");
+        let (accelerometer, gyroscope) = (sensor_cluster[0].clone(), sensor_cluster[1].clone());
+        let (_, pipeline) = crate::ResamplerBuilder::new("test", vec![accelerometer.clone()])
+            .with_resampling_delay_millis(50.0)
+            .run::<SensorReadings<Sample3D>, Sample3D>()
+            .unwrap();
+        let (_, source) =
+            phyphox_rs::run_mock_service("test", sensor_cluster.clone(), 10.0, false, 1000)
+                .unwrap();
+        pipeline
+            .attach_listeners(&*source, std::slice::from_ref(&accelerometer))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(pipeline.add_sensor(accelerometer.clone()).is_err());
+        pipeline.add_sensor(gyroscope.clone()).unwrap();
+        pipeline
+            .attach_listeners(&*source, std::slice::from_ref(&gyroscope))
+            .unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut listener = Listener::new(move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+            let _ = tx.send(readings.get_sensor_type());
+        });
+        pipeline
+            .register_listener(&mut listener, &gyroscope)
+            .unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)).unwrap(), gyroscope);
+
+        pipeline.remove_sensor(&gyroscope).unwrap();
+        assert!(pipeline.remove_sensor(&gyroscope).is_err());
+        assert_eq!(
+            pipeline.get_available_sensors(),
+            vec![accelerometer.clone()]
+        );
+        assert_eq!(pipeline.get_sensor_cluster(), vec![accelerometer]);
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_metrics");
+        let (_, pipeline) = crate::ResamplerBuilder::new("test", sensor_cluster.clone())
+            .with_resampling_delay_millis(50.0)
+            .run::<SensorReadings<Sample3D>, Sample3D>()
+            .unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut listener = Listener::new(move |_id, metrics: Arc<PipelineMetrics>| {
+            let _ = tx.send(metrics);
+        });
+        pipeline.register_metrics_listener(&mut listener);
+        let (_, source) =
+            phyphox_rs::run_mock_service("test", sensor_cluster.clone(), 10.0, false, 1000)
+                .unwrap();
+        pipeline
+            .attach_listeners(&*source, &sensor_cluster)
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let metrics = pipeline.get_metrics();
+        assert_eq!(metrics.sensor_type, pipeline.get_metrics_sensor());
+        assert_eq!(metrics.sensors.len(), sensor_cluster.len());
+        for sensor_type in sensor_cluster.iter() {
+            let sensor_metrics = metrics.get(sensor_type).unwrap();
+            assert!(sensor_metrics.n_samples > 0);
+            assert!(sensor_metrics.input_rate_hz > 0.0);
+            assert!(sensor_metrics.n_measured + sensor_metrics.n_interpolated > 0);
+        }
+        let published = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(published.sensor_type, pipeline.get_metrics_sensor());
+    }
+
+    #[tokio::test]
+    async fn test_pause() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_pause");
+        let (_, pipeline) = crate::ResamplerBuilder::new("test", sensor_cluster.clone())
+            .with_resampling_delay_millis(50.0)
+            .run::<SensorReadings<Sample3D>, Sample3D>()
+            .unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut listener = Listener::new(move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+            let _ = tx.send(readings.get_samples());
+        });
+        pipeline
+            .register_listener(&mut listener, &sensor_cluster[0])
+            .unwrap();
+        let (_, source) =
+            phyphox_rs::run_mock_service("test", sensor_cluster.clone(), 10.0, false, 2000)
+                .unwrap();
+        pipeline
+            .attach_listeners(&*source, &sensor_cluster)
+            .unwrap();
+        rx.recv_timeout(Duration::from_secs(1)).unwrap();
+
+        pipeline.pause();
+        assert!(pipeline.is_paused());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        while rx.try_recv().is_ok() {}
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(rx.try_recv().is_err());
+
+        pipeline.resume();
+        let resumed_secs = Clock::now().as_secs();
+        let samples = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        // nothing is interpolated across the pause, outputs start one delay before resuming
+        assert!(samples
+            .iter()
+            .all(|sample| sample.get_timestamp_secs() >= resumed_secs - 0.05));
+    }
+
+    #[tokio::test]
+    async fn test_hot_plug_while_resampling() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_hot_plug_while_resampling");
+        let (accelerometer, gyroscope) = (sensor_cluster[0].clone(), sensor_cluster[1].clone());
+        let (_, pipeline) = crate::ResamplerBuilder::new("test", vec![accelerometer.clone()])
+            .with_resampling_period_millis(MIN_RESAMPLING_PERIOD_MILLIS)
+            .with_resampling_delay_millis(20.0)
+            .run::<SensorReadings<Sample3D>, Sample3D>()
+            .unwrap();
+        let (_, source) =
+            phyphox_rs::run_mock_service("test", sensor_cluster.clone(), 5.0, false, 1000).unwrap();
+        pipeline
+            .attach_listeners(&*source, std::slice::from_ref(&accelerometer))
+            .unwrap();
+
+        let hot_plug = std::thread::spawn({
+            let pipeline = pipeline.clone();
+            let source = source.clone();
+            move || {
+                for _ in 0..100 {
+                    pipeline.add_sensor(gyroscope.clone()).unwrap();
+                    pipeline
+                        .attach_listeners(&*source, std::slice::from_ref(&gyroscope))
+                        .unwrap();
+                    std::thread::sleep(Duration::from_millis(1));
+                    pipeline.remove_sensor(&gyroscope).unwrap();
+                }
+            }
+        });
+        hot_plug.join().unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let available: Vec<SensorType> = pipeline
+            .buffer
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        assert_eq!(available, vec![accelerometer.clone()]);
+        assert_eq!(
+            pipeline.get_available_sensors(),
+            vec![accelerometer.clone()]
+        );
+        assert_eq!(pipeline.get_metrics().sensors.len(), 1);
+
+        // the accelerometer is still resampled
+        let n_outputs = || {
+            let metrics = pipeline.get_metrics();
+            let metrics = metrics.get(&accelerometer).unwrap();
+            metrics.n_measured + metrics.n_interpolated
+        };
+        let n_before = n_outputs();
+        for _ in 0..100 {
+            if n_outputs() > n_before {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("Accelerometer no longer resampled");
+    }
+
+    #[tokio::test]
+    async fn test_drop_pipeline() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_drop_pipeline");
+        let (handle, pipeline) = crate::ResamplerBuilder::new("test", sensor_cluster.clone())
+            .with_resampling_period_millis(10.0)
+            .with_resampling_delay_millis(50.0)
+            .with_smoothing_policy(SmothingPolicy::default())
+            .run::<SensorReadings<Sample3D>, Sample3D>()
+            .unwrap();
+        let (_, source) =
+            phyphox_rs::run_mock_service("test", sensor_cluster.clone(), 10.0, false, 500).unwrap();
+        pipeline
+            .attach_listeners(&*source, &sensor_cluster)
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let weak = Arc::downgrade(&pipeline);
+        drop(pipeline);
+        assert!(weak.upgrade().is_none());
+
+        // the resampling thread ends, while the source keeps publishing
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(handle.join());
+        });
+        assert!(rx.recv_timeout(Duration::from_secs(1)).unwrap().is_ok());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
     #[test]
     fn test_with_timeout() {
-        let result = run_with_timeout(|| test_callback(), Duration::from_secs(3));
-        match result {
-            None => panic!("test_callback() aborted after 3 seconds"),
-            _ => (),
+        let result = run_with_timeout(test_callback, Duration::from_secs(3));
+        if result.is_none() {
+            panic!("test_callback() aborted after 3 seconds")
         }
 
-        let result = run_with_timeout(|| test_callback_with_macro(), Duration::from_secs(3));
-        match result {
-            None => panic!("test_callback_with_macro() aborted after 3 seconds"),
-            _ => (),
+        let result = run_with_timeout(test_callback_with_macro, Duration::from_secs(3));
+        if result.is_none() {
+            panic!("test_callback_with_macro() aborted after 3 seconds")
         }
     }
 }
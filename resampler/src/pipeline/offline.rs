@@ -0,0 +1,76 @@
+//! Module offline
+//!
+//! Resampling driven by the caller instead of a resampling thread, so that pipelines can be
+//! stepped on simulated time.
+
+use std::sync::Arc;
+
+use super::cache::{Cache, Interpolable};
+use super::{Resampler, ResamplerPipeline};
+use imu_common::traits::{IMUFilter, IMUReadings, IMUSample, IMUUntimedSample};
+use imu_common::types::filters::{Average, WeightedAverage};
+
+/// Pipeline resampled every time `step` is called, built with `ResamplerBuilder::offline`.
+/// Listeners of the pipeline are notified before `step` returns.
+pub struct OfflineResampler<T, S>
+where
+    S: IMUSample,
+{
+    pipeline: Arc<ResamplerPipeline<T, S>>,
+    resampler: Resampler<S, S::Untimed>,
+    resampling_period_secs: f64,
+    resampling_delay_secs: f64,
+    // time of the first step, and number of periods run since
+    start_secs: Option<f64>,
+    n_steps: usize,
+}
+
+impl<T, S> OfflineResampler<T, S>
+where
+    S: IMUSample + std::fmt::Debug,
+    T: Send + Sync + IMUReadings<S> + std::fmt::Debug + 'static,
+    S::Untimed: IMUUntimedSample,
+    Average<S::Untimed>: IMUFilter<S>,
+    WeightedAverage<S::Untimed>: IMUFilter<S>,
+    Cache<S, S::Untimed>: Interpolable<S, S::Untimed>,
+{
+    pub(crate) fn new(
+        pipeline: Arc<ResamplerPipeline<T, S>>,
+        resampling_period_millis: f64,
+        resampling_delay_millis: f64,
+    ) -> Self {
+        let resampler = Resampler::new(
+            &pipeline.get_sensor_cluster(),
+            pipeline.get_smoothing_policy(),
+        );
+        Self {
+            pipeline,
+            resampler,
+            resampling_period_secs: resampling_period_millis / 1000.0,
+            resampling_delay_secs: resampling_delay_millis / 1000.0,
+            start_secs: None,
+            n_steps: 0,
+        }
+    }
+
+    /// Returns the pipeline, to attach it to a source and register its listeners.
+    pub fn get_pipeline(&self) -> Arc<ResamplerPipeline<T, S>> {
+        self.pipeline.clone()
+    }
+
+    /// Runs every resampling period due up to `timestamp_now_secs`. The first call resamples
+    /// once at `timestamp_now_secs`, and later calls keep the resampling period from there.
+    pub fn step(&mut self, timestamp_now_secs: f64) {
+        let start_secs = *self.start_secs.get_or_insert(timestamp_now_secs);
+        loop {
+            // periods are counted from the first step, so timestamps don't drift from the grid
+            let step_secs = start_secs + self.n_steps as f64 * self.resampling_period_secs;
+            if step_secs > timestamp_now_secs {
+                return;
+            }
+            self.pipeline
+                .resample(&mut self.resampler, step_secs, self.resampling_delay_secs);
+            self.n_steps += 1;
+        }
+    }
+}
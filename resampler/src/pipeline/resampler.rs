@@ -1,4 +1,7 @@
+use std::collections::{HashMap, HashSet};
+
 use crate::utils;
+use imu_common::errors::ImuError;
 use imu_common::types::filters::Average;
 use imu_common::types::filters::WeightedAverage;
 
@@ -7,7 +10,7 @@ use imu_common::types::sensors::SensorType;
 
 use super::cache::{Cache, Interpolable};
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SmothingPolicy {
     Averaging,
     FirstSample,
@@ -16,6 +19,68 @@ pub enum SmothingPolicy {
     WeightedAverage,
 }
 
+impl SmothingPolicy {
+    /// Returns the name of the policy, as accepted by `try_from`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmothingPolicy::Averaging => "averaging",
+            SmothingPolicy::FirstSample => "first_sample",
+            SmothingPolicy::LastSample => "last_sample",
+            SmothingPolicy::WeightedAverage => "weighted_average",
+        }
+    }
+}
+
+impl TryFrom<&str> for SmothingPolicy {
+    type Error = String;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        [
+            SmothingPolicy::Averaging,
+            SmothingPolicy::FirstSample,
+            SmothingPolicy::LastSample,
+            SmothingPolicy::WeightedAverage,
+        ]
+        .into_iter()
+        .find(|policy| policy.as_str() == name)
+        .ok_or_else(|| format!("Unknown smoothing policy {}", name))
+    }
+}
+
+/// Resampling settings of a single sensor, overriding those of the pipeline.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct SensorSettings {
+    smoothing_policy: Option<SmothingPolicy>,
+    resampling_period_millis: Option<f64>,
+}
+
+impl SensorSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_smoothing_policy(mut self, smoothing_policy: SmothingPolicy) -> Self {
+        self.smoothing_policy = Some(smoothing_policy);
+        self
+    }
+
+    /// Publishes the sensor every `resampling_period_millis` instead of every pipeline period.
+    /// The sensor is still resampled every pipeline period, so the period is effectively
+    /// rounded to a multiple of the pipeline period.
+    pub fn with_resampling_period_millis(mut self, resampling_period_millis: f64) -> Self {
+        self.resampling_period_millis = Some(resampling_period_millis);
+        self
+    }
+
+    pub fn get_smoothing_policy(&self) -> Option<SmothingPolicy> {
+        self.smoothing_policy
+    }
+
+    pub fn get_resampling_period_millis(&self) -> Option<f64> {
+        self.resampling_period_millis
+    }
+}
+
 #[derive(Default, Clone)]
 pub(crate) struct Resampler<T, U>
 where
@@ -25,6 +90,14 @@ where
     interpolator: Cache<T, U>,
     policy: SmothingPolicy,
     sensor_cluster: Vec<SensorType>,
+    sensor_settings: HashMap<SensorType, SensorSettings>,
+    // timestamp from which each sensor with its own resampling period is published again
+    next_output_secs: HashMap<SensorType, f64>,
+    // raw samples older than the current batch still within the smoothing window
+    history: HashMap<SensorType, Vec<T>>,
+    smoothing_window_secs: f64,
+    // sensors without raw samples in the last buffering period, holding their previous value
+    held: HashSet<SensorType>,
 }
 
 impl<T, U> Resampler<T, U>
@@ -38,6 +111,11 @@ where
             policy,
             interpolator: Cache::new(sensor_cluster),
             sensor_cluster: sensor_cluster.to_vec().clone(),
+            sensor_settings: HashMap::new(),
+            next_output_secs: HashMap::new(),
+            history: HashMap::new(),
+            smoothing_window_secs: 0.0,
+            held: HashSet::new(),
         }
     }
 
@@ -53,64 +131,172 @@ where
     {
         for imu_samples in imu_samples_vec.into_iter() {
             let sensor_type = imu_samples.get_sensor_type();
-            let resampled_samples = match self.smoothing(&imu_samples, new_sample_timestamp_secs) {
-                None => T::from_measurement(
-                    new_sample_timestamp_secs,
-                    self.interpolator
-                        .peek_newest(&sensor_type)
-                        .unwrap()
-                        .clone()
-                        .get_measurement(),
-                ),
-                Some(sample) => sample,
-            };
+            let samples = self.windowed_samples(
+                &sensor_type,
+                imu_samples.get_samples(),
+                new_sample_timestamp_secs,
+            );
+            let resampled_samples =
+                match self.smoothing(&sensor_type, samples, new_sample_timestamp_secs) {
+                    None => match self.interpolator.peek_newest(&sensor_type) {
+                        Some(newest) => {
+                            self.held.insert(sensor_type.clone());
+                            T::from_measurement(
+                                new_sample_timestamp_secs,
+                                newest.clone().get_measurement(),
+                            )
+                        }
+                        // sensor added to the pipeline after the last cluster update
+                        None => continue,
+                    },
+                    Some(sample) => {
+                        self.held.remove(&sensor_type);
+                        sample
+                    }
+                };
             // Insert the processed sample into cache
             self.interpolator
                 .push(&sensor_type, resampled_samples.clone());
         }
     }
 
+    pub(crate) fn set_policy(&mut self, policy: SmothingPolicy) {
+        self.policy = policy;
+    }
+
+    /// Adds and removes cache slots so that the sensors resampled match `sensor_cluster`.
+    pub(crate) fn set_sensor_cluster(&mut self, sensor_cluster: &[SensorType]) {
+        if self.sensor_cluster == sensor_cluster {
+            return;
+        }
+        let timestamp_secs = self.peek_newest_timestamp();
+        for sensor_type in sensor_cluster {
+            self.interpolator.add_sensor(sensor_type, timestamp_secs);
+        }
+        for sensor_type in self.sensor_cluster.iter() {
+            if !sensor_cluster.contains(sensor_type) {
+                self.interpolator.remove_sensor(sensor_type);
+                self.history.remove(sensor_type);
+                self.next_output_secs.remove(sensor_type);
+                self.held.remove(sensor_type);
+            }
+        }
+        self.sensor_cluster = sensor_cluster.to_vec();
+    }
+
+    pub(crate) fn set_sensor_settings(
+        &mut self,
+        sensor_settings: HashMap<SensorType, SensorSettings>,
+    ) {
+        self.sensor_settings = sensor_settings;
+    }
+
+    fn get_policy(&self, sensor_type: &SensorType) -> SmothingPolicy {
+        self.sensor_settings
+            .get(sensor_type)
+            .and_then(SensorSettings::get_smoothing_policy)
+            .unwrap_or(self.policy)
+    }
+
+    /// Returns true if `sensor_type` is due to be published at `timestamp_secs`. Sensors
+    /// without their own resampling period are published every time.
+    fn is_due(&mut self, sensor_type: &SensorType, timestamp_secs: f64) -> bool {
+        let Some(period_secs) = self
+            .sensor_settings
+            .get(sensor_type)
+            .and_then(SensorSettings::get_resampling_period_millis)
+            .map(|period_millis| period_millis / 1000.0)
+        else {
+            return true;
+        };
+        let next_output_secs = self
+            .next_output_secs
+            .entry(sensor_type.clone())
+            .or_insert(f64::NEG_INFINITY);
+        if timestamp_secs < *next_output_secs {
+            return false;
+        }
+        // keep publishing on the same grid, unless we fell more than a period behind
+        *next_output_secs = if timestamp_secs - *next_output_secs < period_secs {
+            *next_output_secs + period_secs
+        } else {
+            timestamp_secs + period_secs
+        };
+        true
+    }
+
+    /// Sets how far back raw samples are kept to be smoothed together with the newest batch.
+    /// With a window of 0, only the samples of the newest batch are smoothed.
+    pub(crate) fn set_smoothing_window(&mut self, smoothing_window_secs: f64) {
+        self.smoothing_window_secs = smoothing_window_secs.max(0.0);
+        if self.smoothing_window_secs == 0.0 {
+            self.history.clear();
+        }
+    }
+
+    /// Returns the samples to smooth at `sample_time`: the newest `batch`, preceded by the
+    /// raw samples of previous batches that are still within the smoothing window.
+    fn windowed_samples(
+        &mut self,
+        sensor_type: &SensorType,
+        batch: Vec<T>,
+        sample_time: f64,
+    ) -> Vec<T> {
+        if self.smoothing_window_secs == 0.0 {
+            return batch;
+        }
+        let oldest_timestamp = sample_time - self.smoothing_window_secs;
+        let history = self.history.entry(sensor_type.clone()).or_default();
+        history.retain(|sample| sample.get_timestamp_secs() >= oldest_timestamp);
+        history.extend(batch);
+        history.clone()
+    }
+
+    /// Returns true if no raw sample of `sensor_type` was received in the last buffering
+    /// period, so its newest resampled value was held from the previous one.
+    pub(crate) fn is_held(&self, sensor_type: &SensorType) -> bool {
+        self.held.contains(sensor_type)
+    }
+
     pub(crate) fn peek_newest_timestamp(&self) -> f64 {
-        self.interpolator
-            .peek_newest_timestamp(&self.sensor_cluster[0])
-            .unwrap()
+        self.sensor_cluster
+            .first()
+            .and_then(|sensor_type| self.interpolator.peek_newest_timestamp(sensor_type))
+            .unwrap_or_default()
     }
 
-    fn smoothing<R>(&self, imu_samples: &R, sample_time: f64) -> Option<T>
+    fn smoothing(&self, sensor_type: &SensorType, samples: Vec<T>, sample_time: f64) -> Option<T>
     where
-        R: Send + Sync + IMUReadings<T> + 'static,
         Average<T::Untimed>: IMUFilter<T>,
         WeightedAverage<T::Untimed>: IMUFilter<T>,
         Cache<T, T::Untimed>: Interpolable<T, T::Untimed>,
     {
-        let n_samples = imu_samples.get_samples().len();
+        let n_samples = samples.len();
 
         match n_samples {
             0 => None,
             1 => {
-                let sample = T::from_measurement(
-                    sample_time,
-                    imu_samples.get_samples()[0].clone().get_measurement(),
-                );
+                let sample = T::from_measurement(sample_time, samples[0].clone().get_measurement());
                 Some(sample)
             }
             _ => {
                 // Handle case where there are multiple samples
-                match self.policy {
+                match self.get_policy(sensor_type) {
                     SmothingPolicy::Averaging => {
-                        utils::compute_average(sample_time, imu_samples.get_samples()).ok()
+                        smoothed(sensor_type, utils::compute_average(sample_time, samples))
                     }
                     SmothingPolicy::FirstSample => Some(T::from_measurement(
                         sample_time,
-                        imu_samples.get_samples()[0].get_measurement(),
+                        samples[0].get_measurement(),
                     )),
                     SmothingPolicy::LastSample => Some(T::from_measurement(
                         sample_time,
-                        imu_samples.get_samples()[n_samples - 1].get_measurement(),
+                        samples[n_samples - 1].get_measurement(),
                     )),
-                    SmothingPolicy::WeightedAverage => {
-                        utils::compute_weighted_average(sample_time, imu_samples.get_samples()).ok()
-                    }
+                    SmothingPolicy::WeightedAverage => smoothed(
+                        sensor_type,
+                        utils::compute_weighted_average(sample_time, samples),
+                    ),
                 }
             }
         }
@@ -120,7 +306,23 @@ where
     where
         Cache<T, T::Untimed>: Interpolable<T, T::Untimed>,
     {
-        self.interpolator.interpolate_samples(timestamp_now_secs)
+        let mut samples = self.interpolator.interpolate_samples(timestamp_now_secs);
+        samples.retain(|(sensor_type, _)| self.is_due(sensor_type, timestamp_now_secs));
+        samples
+    }
+}
+
+/// Returns the smoothed sample of `sensor_type`, if any. Samples the filter can't smooth leave
+/// the period without a sample, while other errors come from the filter configuration and are
+/// reported.
+fn smoothed<T>(sensor_type: &SensorType, result: Result<T, ImuError>) -> Option<T> {
+    match result {
+        Ok(sample) => Some(sample),
+        Err(e) if e.is_recoverable() => None,
+        Err(e) => {
+            log::error!("Failed to smooth the samples of {}: {}", sensor_type, e);
+            None
+        }
     }
 }
 
@@ -131,16 +333,26 @@ mod tests {
     use imu_common::types::timed::Sample3D;
     use uuid::Uuid;
 
+    #[test]
+    fn test_policy_names() {
+        for policy in [SmothingPolicy::Averaging, SmothingPolicy::WeightedAverage] {
+            assert_eq!(SmothingPolicy::try_from(policy.as_str()), Ok(policy));
+        }
+        assert!(SmothingPolicy::try_from("median").is_err());
+    }
+
     #[tokio::test]
     async fn test_smoothing_policy_averaging() {
         let acc_id = Uuid::new_v4();
         let sensor = SensorType::Accelerometer(acc_id);
         let sample1 = Sample3D::new(950.0, [1.0, 2.0, 3.0]);
         let sample2 = Sample3D::new(960.0, [4.0, 5.0, 6.0]);
-        let resampler = Resampler::new(&[sensor.clone()], SmothingPolicy::Averaging);
+        let resampler = Resampler::new(std::slice::from_ref(&sensor), SmothingPolicy::Averaging);
 
-        let readings = SensorReadings::from_vec("Test", sensor, vec![sample1, sample2]);
-        let resampled_sample = resampler.smoothing(&readings, 1000.0).unwrap();
+        let readings = SensorReadings::from_vec("Test", sensor.clone(), vec![sample1, sample2]);
+        let resampled_sample = resampler
+            .smoothing(&sensor, readings.get_samples(), 1000.0)
+            .unwrap();
 
         assert_eq!(resampled_sample.get_measurement(), [2.5, 3.5, 4.5].into());
         assert_eq!(resampled_sample.get_timestamp_secs(), 1000.0);
@@ -151,11 +363,13 @@ mod tests {
         let acc_id = Uuid::new_v4();
         let sensor = SensorType::Accelerometer(acc_id);
         let sample1 = Sample3D::new(950.0, [1.0, 2.0, 3.0]);
-        let resampler = Resampler::new(&[sensor.clone()], SmothingPolicy::Averaging);
+        let resampler = Resampler::new(std::slice::from_ref(&sensor), SmothingPolicy::Averaging);
 
         let readings = SensorReadings::from_vec("Test", sensor.clone(), vec![sample1]);
 
-        let resampled_sample = resampler.smoothing(&readings, 1000.0).unwrap();
+        let resampled_sample = resampler
+            .smoothing(&sensor, readings.get_samples(), 1000.0)
+            .unwrap();
 
         assert_eq!(resampled_sample.get_measurement(), [1.0, 2.0, 3.0].into());
         assert_eq!(resampled_sample.get_timestamp_secs(), 1000.0);
@@ -165,10 +379,11 @@ mod tests {
     async fn test_smoothing_policy_averaging_no_samples() {
         let acc_id = Uuid::new_v4();
         let sensor = SensorType::Accelerometer(acc_id);
-        let resampler = Resampler::<Sample3D, _>::new(&[sensor.clone()], SmothingPolicy::Averaging);
+        let resampler =
+            Resampler::<Sample3D, _>::new(std::slice::from_ref(&sensor), SmothingPolicy::Averaging);
 
         let readings = SensorReadings::from_vec("Test", sensor.clone(), vec![]);
-        let resampled_sample = resampler.smoothing(&readings, 1000.0);
+        let resampled_sample = resampler.smoothing(&sensor, readings.get_samples(), 1000.0);
         assert!(resampled_sample.is_none());
     }
 
@@ -178,10 +393,12 @@ mod tests {
         let sensor = SensorType::Accelerometer(acc_id);
         let sample1 = Sample3D::new(950.0, [1.0, 2.0, 3.0]);
         let sample2 = Sample3D::new(960.0, [4.0, 5.0, 6.0]);
-        let resampler = Resampler::new(&[sensor.clone()], SmothingPolicy::FirstSample);
+        let resampler = Resampler::new(std::slice::from_ref(&sensor), SmothingPolicy::FirstSample);
 
         let readings = SensorReadings::from_vec("Test", sensor.clone(), vec![sample1, sample2]);
-        let resampled_sample = resampler.smoothing(&readings, 1000.0).unwrap();
+        let resampled_sample = resampler
+            .smoothing(&sensor, readings.get_samples(), 1000.0)
+            .unwrap();
 
         assert_eq!(resampled_sample.get_measurement(), [1.0, 2.0, 3.0].into());
         assert_eq!(resampled_sample.get_timestamp_secs(), 1000.0);
@@ -193,10 +410,12 @@ mod tests {
         let sensor = SensorType::Accelerometer(acc_id);
         let sample1 = Sample3D::new(950.0, [1.0, 2.0, 3.0]);
         let sample2 = Sample3D::new(960.0, [4.0, 5.0, 6.0]);
-        let resampler = Resampler::new(&[sensor.clone()], SmothingPolicy::LastSample);
+        let resampler = Resampler::new(std::slice::from_ref(&sensor), SmothingPolicy::LastSample);
 
         let readings = SensorReadings::from_vec("Test", sensor.clone(), vec![sample1, sample2]);
-        let resampled_sample = resampler.smoothing(&readings, 1000.0).unwrap();
+        let resampled_sample = resampler
+            .smoothing(&sensor, readings.get_samples(), 1000.0)
+            .unwrap();
 
         assert_eq!(resampled_sample.get_measurement(), [4.0, 5.0, 6.0].into());
         assert_eq!(resampled_sample.get_timestamp_secs(), 1000.0);
@@ -208,15 +427,22 @@ mod tests {
         let sensor = SensorType::Accelerometer(acc_id);
         let sample1 = Sample3D::new(950.0, [1.0, 2.0, 3.0]);
         let sample2 = Sample3D::new(960.0, [4.0, 5.0, 6.0]);
-        let resampler = Resampler::new(&[sensor.clone()], SmothingPolicy::WeightedAverage);
+        let resampler = Resampler::new(
+            std::slice::from_ref(&sensor),
+            SmothingPolicy::WeightedAverage,
+        );
 
         let readings = SensorReadings::from_vec(
             "Test",
             sensor.clone(),
             vec![sample1.clone(), sample2.clone()],
         );
-        let resampled_sample1 = resampler.smoothing(&readings, 950.0).unwrap();
-        let resampled_sample2 = resampler.smoothing(&readings, 960.0).unwrap();
+        let resampled_sample1 = resampler
+            .smoothing(&sensor, readings.get_samples(), 950.0)
+            .unwrap();
+        let resampled_sample2 = resampler
+            .smoothing(&sensor, readings.get_samples(), 960.0)
+            .unwrap();
 
         let eps = 1e-5;
         assert!(
@@ -230,4 +456,134 @@ mod tests {
         );
         assert_eq!(resampled_sample2.get_timestamp_secs(), 960.0);
     }
+
+    #[tokio::test]
+    async fn test_smoothing_window() {
+        let acc_id = Uuid::new_v4();
+        let sensor = SensorType::Accelerometer(acc_id);
+        let mut resampler =
+            Resampler::new(std::slice::from_ref(&sensor), SmothingPolicy::Averaging);
+        resampler.set_smoothing_window(0.2);
+
+        let batch1 = vec![
+            Sample3D::new(0.95, [1.0, 2.0, 3.0]),
+            Sample3D::new(0.98, [3.0, 4.0, 5.0]),
+        ];
+        let samples = resampler.windowed_samples(&sensor, batch1, 1.0);
+        assert_eq!(samples.len(), 2);
+
+        // previous batch is still within the window
+        let batch2 = vec![Sample3D::new(1.04, [5.0, 6.0, 7.0])];
+        let samples = resampler.windowed_samples(&sensor, batch2, 1.05);
+        assert_eq!(samples.len(), 3);
+        let resampled_sample = resampler.smoothing(&sensor, samples, 1.05).unwrap();
+        assert_eq!(resampled_sample.get_measurement(), [3.0, 4.0, 5.0].into());
+
+        // all samples are older than the window
+        let samples = resampler.windowed_samples(&sensor, vec![], 1.3);
+        assert!(samples.is_empty());
+
+        resampler.set_smoothing_window(0.0);
+        let batch3 = vec![Sample3D::new(1.35, [1.0, 1.0, 1.0])];
+        let samples = resampler.windowed_samples(&sensor, batch3.clone(), 1.4);
+        assert_eq!(samples, batch3);
+    }
+
+    #[test]
+    fn test_sensor_policy() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let mag = SensorType::Magnetometer(Uuid::new_v4());
+        let mut resampler = Resampler::new(&[acc.clone(), mag.clone()], SmothingPolicy::Averaging);
+        resampler.set_sensor_settings(HashMap::from([(
+            mag.clone(),
+            SensorSettings::new().with_smoothing_policy(SmothingPolicy::LastSample),
+        )]));
+        let samples = vec![
+            Sample3D::new(950.0, [1.0, 2.0, 3.0]),
+            Sample3D::new(960.0, [4.0, 5.0, 6.0]),
+        ];
+
+        let acc_sample = resampler.smoothing(&acc, samples.clone(), 1000.0).unwrap();
+        let mag_sample = resampler.smoothing(&mag, samples, 1000.0).unwrap();
+
+        assert_eq!(acc_sample.get_measurement(), [2.5, 3.5, 4.5].into());
+        assert_eq!(mag_sample.get_measurement(), [4.0, 5.0, 6.0].into());
+    }
+
+    #[test]
+    fn test_sensor_period() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let mag = SensorType::Magnetometer(Uuid::new_v4());
+        let mut resampler =
+            Resampler::<Sample3D, _>::new(&[acc.clone(), mag.clone()], SmothingPolicy::default());
+        resampler.set_sensor_settings(HashMap::from([(
+            mag.clone(),
+            SensorSettings::new().with_resampling_period_millis(750.0),
+        )]));
+
+        let mut n_acc = 0;
+        let mut n_mag = 0;
+        for tick in 0..9 {
+            for (sensor_type, _) in resampler.interpolate(tick as f64 * 0.25) {
+                if sensor_type == mag {
+                    n_mag += 1;
+                } else {
+                    n_acc += 1;
+                }
+            }
+        }
+        assert_eq!(n_acc, 9);
+        assert_eq!(n_mag, 3);
+    }
+
+    #[test]
+    fn test_set_sensor_cluster() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let gyro = SensorType::Gyroscope(Uuid::new_v4());
+        let mut resampler =
+            Resampler::<Sample3D, _>::new(std::slice::from_ref(&acc), SmothingPolicy::Averaging);
+        let readings = SensorReadings::from_vec(
+            "Test",
+            acc.clone(),
+            vec![Sample3D::new(0.95, [1.0, 2.0, 3.0])],
+        );
+        resampler.buffer_samples(vec![readings], 1.0);
+
+        // samples of sensors missing from the cache are skipped
+        let readings = SensorReadings::<Sample3D>::from_vec("Test", gyro.clone(), vec![]);
+        resampler.buffer_samples(vec![readings.clone()], 1.0);
+        assert!(resampler.interpolator.peek_newest(&gyro).is_none());
+
+        resampler.set_sensor_cluster(&[acc.clone(), gyro.clone()]);
+        let newest = resampler.interpolator.peek_newest(&gyro).unwrap();
+        assert_eq!(newest.get_timestamp_secs(), 1.0);
+        resampler.buffer_samples(vec![readings], 1.1);
+        assert_eq!(resampler.interpolate(1.05).len(), 2);
+
+        resampler.set_sensor_cluster(std::slice::from_ref(&gyro));
+        let samples = resampler.interpolate(1.05);
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].0, gyro);
+        assert_eq!(resampler.peek_newest_timestamp(), 1.1);
+    }
+
+    #[test]
+    fn test_held_samples() {
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let mut resampler =
+            Resampler::<Sample3D, _>::new(std::slice::from_ref(&acc), SmothingPolicy::Averaging);
+        let readings = SensorReadings::from_vec(
+            "Test",
+            acc.clone(),
+            vec![Sample3D::new(0.95, [1.0, 2.0, 3.0])],
+        );
+        resampler.buffer_samples(vec![readings], 1.0);
+        assert!(!resampler.is_held(&acc));
+
+        let readings = SensorReadings::<Sample3D>::from_vec("Test", acc.clone(), vec![]);
+        resampler.buffer_samples(vec![readings], 1.1);
+        assert!(resampler.is_held(&acc));
+        let newest = resampler.interpolator.peek_newest(&acc).unwrap();
+        assert_eq!(newest.get_measurement(), [1.0, 2.0, 3.0].into());
+    }
 }
@@ -1,8 +1,11 @@
-use publisher::{listener, Listener};
+use dashmap::DashMap;
+use publisher::{adapters, Listener};
 use std::sync::Arc;
 use uuid::Uuid;
 
+use super::ingestion::SensorBuffer;
 use super::ResamplerPipeline;
+use imu_common::errors::ImuError;
 use imu_common::traits::{IMUFilter, IMUReadings, IMUSample, IMUSink, IMUSource};
 use imu_common::types::filters::Average;
 use imu_common::types::filters::WeightedAverage;
@@ -19,24 +22,36 @@ where
         &self,
         source: &dyn IMUSource<T, S>,
         sensor_cluster: &[SensorType],
-    ) -> Result<Vec<Uuid>, String> {
-        let mut listener = listener!(self.process_samples);
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::negotiate(self, source, sensor_cluster)?;
+        // the listener only holds the buffer weakly, so the pipeline can be dropped while the
+        // source is still running
+        let mut listener = Listener::weak(&self.buffer, |buffer, _id, samples: Arc<T>| {
+            buffer_samples(&buffer, samples);
+        });
         let mut ids = Vec::with_capacity(sensor_cluster.len());
         for sensor_type in sensor_cluster {
             if let Ok(id) = source.register_listener(&mut listener, sensor_type) {
                 ids.push(id);
             } else {
-                return Err("Incorrect sensor".to_string());
+                return Err(ImuError::IncompatibleSensor(sensor_type.to_string()));
             }
         }
         Ok(ids)
     }
 
     fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
-        let sensor_type = samples.get_sensor_type();
-        if let Some(mutex) = self.buffer.get(&sensor_type) {
-            let mut data = mutex.lock().unwrap();
-            data.extend(samples.get_samples());
-        }
+        buffer_samples(&self.buffer, samples);
+    }
+}
+
+fn buffer_samples<T, S>(buffer: &DashMap<SensorType, SensorBuffer<T, S>>, samples: Arc<T>)
+where
+    S: IMUSample,
+    T: IMUReadings<S>,
+{
+    let sensor_type = samples.get_sensor_type();
+    if let Some(sensor_buffer) = buffer.get(&sensor_type) {
+        sensor_buffer.push(&samples);
     }
 }
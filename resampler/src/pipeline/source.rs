@@ -2,7 +2,10 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::ResamplerPipeline;
-use imu_common::traits::{IMUFilter, IMUReadings, IMUSample, IMUSource, IMUUntimedSample, Notifiable};
+use imu_common::errors::ImuError;
+use imu_common::traits::{
+    IMUFilter, IMUReadings, IMUSample, IMUSource, IMUUntimedSample, Notifiable,
+};
 use imu_common::types::filters::Average;
 use imu_common::types::filters::WeightedAverage;
 use imu_common::types::sensors::SensorType;
@@ -31,7 +34,7 @@ where
         &self,
         listener: &mut dyn Notifiable<T>,
         sensor_type: &SensorType,
-    ) -> Result<Uuid, String> {
+    ) -> Result<Uuid, ImuError> {
         self.publishers.add_listener(listener, sensor_type)
     }
 
@@ -0,0 +1,121 @@
+//! Live tuning of `ResamplerPipeline` through a `ControlChannel`.
+//!
+//! Parameters:
+//! - `smoothing_policy`: `averaging`, `first_sample`, `last_sample` or `weighted_average`.
+//! - `smoothing_window_millis`: length of the window of raw samples smoothed before
+//!   interpolating. 0 smooths each resampling period on its own.
+//!
+//! Parameters left out of `set_parameters` keep their value.
+
+use imu_common::errors::ImuError;
+use imu_common::traits::Tunable;
+use imu_common::types::registry::SourceParams;
+
+use super::ResamplerPipeline;
+use crate::SmothingPolicy;
+
+const PARAMETERS: [&str; 2] = ["smoothing_policy", "smoothing_window_millis"];
+
+impl<T, S> Tunable for ResamplerPipeline<T, S>
+where
+    T: Send + Sync,
+    S: Send + Sync,
+{
+    fn get_parameters(&self) -> SourceParams {
+        SourceParams::new()
+            .with("smoothing_policy", self.get_smoothing_policy().as_str())
+            .with(
+                "smoothing_window_millis",
+                self.get_smoothing_window_millis(),
+            )
+    }
+
+    fn set_parameters(&self, params: &SourceParams) -> Result<(), ImuError> {
+        if let Some((name, _)) = params.iter().find(|(name, _)| !PARAMETERS.contains(name)) {
+            return Err(ImuError::InvalidParameter(format!(
+                "Unknown parameter {}",
+                name
+            )));
+        }
+        // validate every parameter before changing any
+        let policy = match params.contains("smoothing_policy") {
+            true => Some(
+                SmothingPolicy::try_from(params.get_str("smoothing_policy")?)
+                    .map_err(ImuError::InvalidParameter)?,
+            ),
+            false => None,
+        };
+        let window = match params.contains("smoothing_window_millis") {
+            true => {
+                let window = params.get_float("smoothing_window_millis")?;
+                if !(window >= 0.0 && window.is_finite()) {
+                    return Err(ImuError::InvalidParameter(format!(
+                        "Invalid smoothing window {} ms",
+                        window
+                    )));
+                }
+                Some(window)
+            }
+            false => None,
+        };
+        if let Some(policy) = policy {
+            self.set_smoothing_policy(policy);
+        }
+        if let Some(window) = window {
+            self.set_smoothing_window_millis(window);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::types::sensors::{SensorReadings, SensorType};
+    use imu_common::types::timed::Sample3D;
+
+    #[test]
+    fn test_tune_smoothing_policy() {
+        let pipeline = ResamplerPipeline::<SensorReadings<Sample3D>, Sample3D>::new(
+            "Test",
+            SensorType::cluster_for_tag("Test"),
+        );
+        let params = SourceParams::new().with("smoothing_policy", "last_sample");
+
+        pipeline.set_parameters(&params).unwrap();
+
+        assert_eq!(pipeline.get_smoothing_policy(), SmothingPolicy::LastSample);
+        assert_eq!(
+            pipeline.get_parameters(),
+            params.clone().with("smoothing_window_millis", 0.0)
+        );
+        assert!(pipeline
+            .set_parameters(&SourceParams::new().with("smoothing_policy", "median"))
+            .is_err());
+        assert!(pipeline
+            .set_parameters(&params.clone().with("delay", 100.0))
+            .is_err());
+        assert_eq!(pipeline.get_smoothing_policy(), SmothingPolicy::LastSample);
+    }
+
+    #[test]
+    fn test_tune_smoothing_window() {
+        let pipeline = ResamplerPipeline::<SensorReadings<Sample3D>, Sample3D>::new(
+            "Test",
+            SensorType::cluster_for_tag("Test"),
+        );
+
+        pipeline
+            .set_parameters(&SourceParams::new().with("smoothing_window_millis", 200))
+            .unwrap();
+        assert_eq!(pipeline.get_smoothing_window_millis(), 200.0);
+        assert_eq!(pipeline.get_smoothing_policy(), SmothingPolicy::default());
+
+        let params = SourceParams::new()
+            .with("smoothing_policy", "averaging")
+            .with("smoothing_window_millis", -1.0);
+        assert!(pipeline.set_parameters(&params).is_err());
+        assert_eq!(pipeline.get_smoothing_window_millis(), 200.0);
+        assert_eq!(pipeline.get_smoothing_policy(), SmothingPolicy::default());
+    }
+}
@@ -1,3 +1,4 @@
+use imu_common::errors::ImuError;
 use imu_common::traits::imu::IMUFilter;
 use imu_common::traits::{IMUSample, IMUUntimedSample};
 use imu_common::types::filters::{Average, WeightedAverage};
@@ -9,10 +10,7 @@ use imu_common::types::filters::{Average, WeightedAverage};
 ///
 /// # Panics
 /// Panics if the input vector `samples` is empty.
-pub(crate) fn compute_average<T>(
-    timestamp: f64,
-    samples: Vec<T>,
-) -> Result<T, Box<dyn std::error::Error>>
+pub(crate) fn compute_average<T>(timestamp: f64, samples: Vec<T>) -> Result<T, ImuError>
 where
     T: IMUSample,
     T::Untimed: IMUUntimedSample,
@@ -31,10 +29,7 @@ where
 /// A small epsilon (`1e-10`) is added to avoid division by zero for very close timestamps.
 ///
 /// The result is an array `[t_weighted_avg, x_weighted_avg, y_weighted_avg, z_weighted_avg]`.
-pub(crate) fn compute_weighted_average<T>(
-    timestamp: f64,
-    samples: Vec<T>,
-) -> Result<T, Box<dyn std::error::Error>>
+pub(crate) fn compute_weighted_average<T>(timestamp: f64, samples: Vec<T>) -> Result<T, ImuError>
 where
     T: IMUSample,
     T::Untimed: IMUUntimedSample,
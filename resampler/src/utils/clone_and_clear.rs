@@ -1,9 +1,10 @@
+use dashmap::DashMap;
 use imu_common::traits::{IMUReadings, IMUSample, IMUUntimedSample};
 use imu_common::types::sensors::SensorType;
-use dashmap::DashMap;
-use std::sync::{Arc, Mutex};
 
-pub(crate) fn clone_and_clear<T, S>(buffer: Arc<DashMap<SensorType, Mutex<T>>>) -> Vec<T>
+use crate::pipeline::ingestion::SensorBuffer;
+
+pub(crate) fn clone_and_clear<T, S>(buffer: &DashMap<SensorType, SensorBuffer<T, S>>) -> Vec<T>
 where
     S: IMUSample,
     T: Send + Sync + IMUReadings<S> + 'static,
@@ -13,10 +14,8 @@ where
 
     let mut buffer_clone: Vec<T> = Vec::new();
     for sensor_type in sensor_types {
-        if let Some(mutex) = buffer.get(&sensor_type) {
-            let mut data = mutex.lock().unwrap();
-            buffer_clone.push(data.clone());
-            data.clear();
+        if let Some(sensor_buffer) = buffer.get(&sensor_type) {
+            buffer_clone.push(sensor_buffer.take());
         }
     }
     buffer_clone
@@ -25,8 +24,10 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pipeline::ingestion::IngestionBuffer;
     use imu_common::types::sensors::SensorReadings;
     use imu_common::types::timed::Sample3D;
+    use std::sync::Mutex;
     use uuid::Uuid;
 
     #[test]
@@ -34,13 +35,16 @@ mod tests {
         let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
         let sample = Sample3D::new(0.0, [1.0, 2.0, 3.0]);
         let mut readings = SensorReadings::new("Test", sensor_type.clone());
-        let buffer: Arc<DashMap<SensorType, Mutex<SensorReadings<Sample3D>>>> =
-            Arc::new(DashMap::new());
+        let buffer: DashMap<SensorType, SensorBuffer<SensorReadings<Sample3D>, Sample3D>> =
+            DashMap::new();
 
         readings.add_sample(sample.clone());
-        buffer.insert(sensor_type.clone(), Mutex::new(readings));
+        buffer.insert(
+            sensor_type.clone(),
+            SensorBuffer::Locked(Mutex::new(readings)),
+        );
 
-        let result = clone_and_clear(buffer.clone());
+        let result = clone_and_clear(&buffer);
 
         // Check that buffer is cloned
         assert_eq!(result.len(), 1);
@@ -51,8 +55,7 @@ mod tests {
         );
         // check that buffer is cleared
         let buffer = buffer.get(&sensor_type).unwrap();
-        let buffer = buffer.lock().unwrap();
-        assert!(buffer.is_empty());
+        assert!(buffer.take().is_empty());
     }
 
     #[test]
@@ -62,17 +65,23 @@ mod tests {
         let sensor_type2 = SensorType::Gyroscope(Uuid::new_v4());
         let mut readings_acc = SensorReadings::new("Test", sensor_type1.clone());
         let mut readings_gyro = SensorReadings::new("Test", sensor_type2.clone());
-        let buffer: Arc<DashMap<SensorType, Mutex<SensorReadings<Sample3D>>>> =
-            Arc::new(DashMap::new());
+        let buffer: DashMap<SensorType, SensorBuffer<SensorReadings<Sample3D>, Sample3D>> =
+            DashMap::new();
         let sample_acc = Sample3D::new(0.0, [1.0, 2.0, 3.0]);
         let sample_gyro = Sample3D::new(0.0, [5.0, 6.0, 7.0]);
         readings_acc.add_sample(sample_acc.clone());
         readings_gyro.add_sample(sample_gyro.clone());
 
-        buffer.insert(sensor_type1.clone(), Mutex::new(readings_acc));
-        buffer.insert(sensor_type2.clone(), Mutex::new(readings_gyro));
+        buffer.insert(
+            sensor_type1.clone(),
+            SensorBuffer::Locked(Mutex::new(readings_acc)),
+        );
+        buffer.insert(
+            sensor_type2.clone(),
+            SensorBuffer::Locked(Mutex::new(readings_gyro)),
+        );
 
-        let result = clone_and_clear(buffer.clone());
+        let result = clone_and_clear(&buffer);
 
         // Check that buffer is cloned
         assert_eq!(result.len(), 2);
@@ -99,22 +108,36 @@ mod tests {
         }
 
         // check that buffer is cleared
-        let readings = buffer.get(&sensor_type1).unwrap();
-        let buffer_locked = readings.lock().unwrap();
-        assert!(buffer_locked.is_empty());
-        drop(buffer_locked);
-
-        let readings = buffer.get(&sensor_type2).unwrap();
-        let buffer_locked = readings.lock().unwrap();
-        assert!(buffer_locked.is_empty());
+        assert!(buffer.get(&sensor_type1).unwrap().take().is_empty());
+        assert!(buffer.get(&sensor_type2).unwrap().take().is_empty());
+    }
+
+    #[test]
+    fn test_clone_and_clear_ring() {
+        let sensor_type = SensorType::Gyroscope(Uuid::new_v4());
+        let buffer: DashMap<SensorType, SensorBuffer<SensorReadings<Sample3D>, Sample3D>> =
+            DashMap::new();
+        let sensor_buffer = SensorBuffer::new("Test", sensor_type.clone(), IngestionBuffer::ring());
+        let sample = Sample3D::new(0.0, [1.0, 2.0, 3.0]);
+        sensor_buffer.push(&SensorReadings::from_vec(
+            "Test",
+            sensor_type.clone(),
+            vec![sample.clone()],
+        ));
+        buffer.insert(sensor_type.clone(), sensor_buffer);
+
+        let result = clone_and_clear(&buffer);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].get_samples(), vec![sample]);
+        assert!(buffer.get(&sensor_type).unwrap().take().is_empty());
     }
 
     #[test]
     fn test_clone_and_clear_empty_buffer() {
-        let buffer: Arc<DashMap<SensorType, Mutex<SensorReadings<Sample3D>>>> =
-            Arc::new(DashMap::new());
+        let buffer: DashMap<SensorType, SensorBuffer<SensorReadings<Sample3D>, Sample3D>> =
+            DashMap::new();
 
-        let result = clone_and_clear(buffer.clone());
+        let result = clone_and_clear(&buffer);
         assert_eq!(result.len(), 0);
     }
 }
@@ -1,4 +1,4 @@
-use resampler_rs::run;
+use resampler_rs::ResamplerBuilder;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -52,13 +52,12 @@ async fn test_resampler_pipeline() {
     let resampling_period_millis = 10.0;
     let resampling_delay_millis = 200.0;
     let resampling_policy = SmothingPolicy::WeightedAverage;
-    let (_handle_resampler, resampler) = run::<SensorReadings<Sample3D>, _>(
-        sensor_tag,
-        sensor_cluster.clone(),
-        resampling_period_millis,
-        resampling_delay_millis,
-        resampling_policy,
-    );
+    let (_handle_resampler, resampler) = ResamplerBuilder::new(sensor_tag, sensor_cluster.clone())
+        .with_resampling_period_millis(resampling_period_millis)
+        .with_resampling_delay_millis(resampling_delay_millis)
+        .with_smoothing_policy(resampling_policy)
+        .run::<SensorReadings<Sample3D>, _>()
+        .unwrap();
 
     // connect resampler to phyphox
     resampler
@@ -79,4 +78,7 @@ async fn test_resampler_pipeline() {
         handle_phyphox.await.unwrap();
     })
     .await;
+
+    sink.assert_received_at_least(sensor_cluster.len());
+    sink.assert_monotone_timestamps();
 }
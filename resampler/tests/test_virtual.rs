@@ -0,0 +1,232 @@
+use std::sync::Arc;
+
+use imu_common::traits::{IMUSample, IMUSink};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use publisher::adapters;
+use resampler_rs::{OutputBatching, ResamplerBuilder, SmothingPolicy};
+use test_utils::harness::{VirtualPipelineHarness, VirtualSource};
+use test_utils::sinks::SinkMock;
+
+const START_SECS: f64 = 1000.0;
+// periods with an exact binary representation, so timestamps can be compared exactly
+const STEP_MILLIS: f64 = 15.625;
+const SAMPLING_PERIOD_MILLIS: f64 = 7.8125;
+const RESAMPLING_DELAY_MILLIS: f64 = 125.0;
+
+/// Resamples a ramp for one simulated second, returning the accelerometer outputs.
+fn resample_ramp() -> Vec<Sample3D> {
+    let sensor_cluster = SensorType::cluster_for_tag("test_virtual");
+    let mut harness = VirtualPipelineHarness::new(START_SECS, STEP_MILLIS);
+    let source = Arc::new(VirtualSource::<Sample3D>::new(
+        "test",
+        sensor_cluster.clone(),
+        SAMPLING_PERIOD_MILLIS,
+        |_sensor, timestamp| [timestamp - START_SECS, 0.0, 0.0].into(),
+    ));
+    let mut resampler = ResamplerBuilder::new("test", sensor_cluster.clone())
+        .with_resampling_period_millis(STEP_MILLIS)
+        .with_resampling_delay_millis(RESAMPLING_DELAY_MILLIS)
+        .with_smoothing_policy(SmothingPolicy::Averaging)
+        .offline::<SensorReadings<Sample3D>, Sample3D>(Arc::new(harness.get_clock()))
+        .unwrap();
+    let pipeline = resampler.get_pipeline();
+    pipeline
+        .attach_listeners(&*source, &sensor_cluster)
+        .unwrap();
+    let captured = harness.capture(&*pipeline, &sensor_cluster[0]).unwrap();
+
+    harness.add_stage(move |timestamp| source.emit_until(timestamp));
+    harness.add_stage(move |timestamp| resampler.step(timestamp));
+    harness.run_for_millis(1000.0);
+
+    captured.get_samples()
+}
+
+#[test]
+fn test_virtual_pipeline() {
+    let samples = resample_ramp();
+    assert!(samples.len() > 50);
+
+    // one output every resampling period, the resampling delay behind the clock
+    let last_timestamp = samples.last().unwrap().get_timestamp_secs();
+    assert_eq!(
+        last_timestamp,
+        START_SECS + 1.0 - RESAMPLING_DELAY_MILLIS / 1000.0
+    );
+    for pair in samples.windows(2) {
+        assert_eq!(
+            pair[1].get_timestamp_secs() - pair[0].get_timestamp_secs(),
+            STEP_MILLIS / 1000.0
+        );
+        assert!(pair[1].get_measurement().0.x >= pair[0].get_measurement().0.x);
+    }
+
+    // no wall clock involved, so every run emits the same samples
+    assert_eq!(resample_ramp(), samples);
+}
+
+#[test]
+fn test_virtual_hot_plug() {
+    let sensor_cluster = SensorType::cluster_for_tag("test_virtual_hot_plug");
+    let (accelerometer, gyroscope) = (sensor_cluster[0].clone(), sensor_cluster[1].clone());
+    let mut harness = VirtualPipelineHarness::new(START_SECS, STEP_MILLIS);
+    let source = Arc::new(VirtualSource::<Sample3D>::new(
+        "test",
+        sensor_cluster.clone(),
+        SAMPLING_PERIOD_MILLIS,
+        |_sensor, timestamp| [timestamp - START_SECS, 0.0, 0.0].into(),
+    ));
+    let mut resampler = ResamplerBuilder::new("test", vec![accelerometer.clone()])
+        .with_resampling_period_millis(STEP_MILLIS)
+        .with_resampling_delay_millis(RESAMPLING_DELAY_MILLIS)
+        .offline::<SensorReadings<Sample3D>, Sample3D>(Arc::new(harness.get_clock()))
+        .unwrap();
+    let pipeline = resampler.get_pipeline();
+    pipeline
+        .attach_listeners(&*source, std::slice::from_ref(&accelerometer))
+        .unwrap();
+    harness.add_stage({
+        let source = source.clone();
+        move |timestamp| source.emit_until(timestamp)
+    });
+    harness.add_stage(move |timestamp| resampler.step(timestamp));
+    harness.run_for_millis(500.0);
+
+    // a sensor joining mid-session flows without restarting the pipeline
+    pipeline.add_sensor(gyroscope.clone()).unwrap();
+    pipeline
+        .attach_listeners(&*source, std::slice::from_ref(&gyroscope))
+        .unwrap();
+    let captured = harness.capture(&*pipeline, &gyroscope).unwrap();
+    let joined_secs = harness.now_secs();
+    harness.run_for_millis(500.0);
+    assert!(!captured.is_empty());
+    assert!(captured.get_timestamps()[0] >= joined_secs - RESAMPLING_DELAY_MILLIS / 1000.0);
+
+    pipeline.remove_sensor(&gyroscope).unwrap();
+    let n_samples = captured.len();
+    harness.run_for_millis(500.0);
+    assert_eq!(captured.len(), n_samples);
+    assert!(pipeline.get_metrics().get(&gyroscope).is_none());
+}
+
+#[test]
+fn test_virtual_attach() {
+    let sensor_cluster = SensorType::cluster_for_tag("test_virtual_attach");
+    let accelerometer = sensor_cluster[0].clone();
+    let mut harness = VirtualPipelineHarness::new(START_SECS, STEP_MILLIS);
+    let source = Arc::new(VirtualSource::<Sample3D>::new(
+        "test",
+        vec![accelerometer.clone()],
+        SAMPLING_PERIOD_MILLIS,
+        |_sensor, timestamp| [timestamp - START_SECS, 0.0, 0.0].into(),
+    ));
+    let mut resampler = ResamplerBuilder::new("test", sensor_cluster.clone())
+        .with_resampling_period_millis(STEP_MILLIS)
+        .with_resampling_delay_millis(RESAMPLING_DELAY_MILLIS)
+        .offline::<SensorReadings<Sample3D>, Sample3D>(Arc::new(harness.get_clock()))
+        .unwrap();
+    let pipeline = resampler.get_pipeline();
+
+    // the source doesn't publish the gyroscope, so nothing is attached
+    let error = adapters::attach(&source, &*pipeline, &sensor_cluster)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains(&sensor_cluster[1].to_string()));
+    assert!(adapters::attach(&source, &*pipeline, &[]).is_err());
+
+    let subscription =
+        adapters::attach(&source, &*pipeline, std::slice::from_ref(&accelerometer)).unwrap();
+    assert_eq!(subscription.get_ids().len(), 1);
+    let captured = harness.capture(&*pipeline, &accelerometer).unwrap();
+    harness.add_stage({
+        let source = source.clone();
+        move |timestamp| source.emit_until(timestamp)
+    });
+    harness.add_stage(move |timestamp| resampler.step(timestamp));
+    harness.run_for_millis(500.0);
+    let n_samples = pipeline
+        .get_metrics()
+        .get(&accelerometer)
+        .unwrap()
+        .n_samples;
+    assert!(n_samples > 0);
+    assert!(!captured.is_empty());
+
+    // dropping the subscription detaches the pipeline from the source, so once the samples
+    // already buffered are collected, no new ones arrive
+    drop(subscription);
+    harness.run_for_millis(500.0);
+    let n_samples = pipeline
+        .get_metrics()
+        .get(&accelerometer)
+        .unwrap()
+        .n_samples;
+    harness.run_for_millis(500.0);
+    assert_eq!(
+        pipeline
+            .get_metrics()
+            .get(&accelerometer)
+            .unwrap()
+            .n_samples,
+        n_samples
+    );
+}
+
+#[test]
+fn test_virtual_batching() {
+    let sensor_cluster = SensorType::cluster_for_tag("test_virtual_batching");
+    let accelerometer = sensor_cluster[0].clone();
+    let mut harness = VirtualPipelineHarness::new(START_SECS, STEP_MILLIS);
+    let source = Arc::new(VirtualSource::<Sample3D>::new(
+        "test",
+        sensor_cluster.clone(),
+        SAMPLING_PERIOD_MILLIS,
+        |_sensor, timestamp| [timestamp - START_SECS, 0.0, 0.0].into(),
+    ));
+    let mut resampler = ResamplerBuilder::new("test", sensor_cluster.clone())
+        .with_resampling_period_millis(STEP_MILLIS)
+        .with_resampling_delay_millis(RESAMPLING_DELAY_MILLIS)
+        .with_output_batching(OutputBatching::Ticks(4))
+        .offline::<SensorReadings<Sample3D>, Sample3D>(Arc::new(harness.get_clock()))
+        .unwrap();
+    let pipeline = resampler.get_pipeline();
+    pipeline
+        .attach_listeners(&*source, &sensor_cluster)
+        .unwrap();
+    let sink = SinkMock::<Sample3D>::new();
+    sink.attach_listeners(&*pipeline, std::slice::from_ref(&accelerometer))
+        .unwrap();
+    harness.add_stage(move |timestamp| source.emit_until(timestamp));
+    harness.add_stage(move |timestamp| resampler.step(timestamp));
+    harness.run_for_millis(1000.0);
+
+    // resampling periods published 4 at a time
+    let deliveries = sink.get_deliveries();
+    assert!(deliveries.len() >= 12);
+    assert!(deliveries
+        .iter()
+        .all(|delivery| delivery.timestamps.len() == 4));
+    sink.assert_monotone_timestamps();
+    let timestamps: Vec<f64> = sink
+        .get_samples(&accelerometer)
+        .iter()
+        .map(|sample| sample.get_timestamp_secs())
+        .collect();
+    for pair in timestamps.windows(2) {
+        assert_eq!(pair[1] - pair[0], STEP_MILLIS / 1000.0);
+    }
+
+    // batches of 62.5 ms hold the same 4 periods
+    pipeline
+        .set_output_batching(OutputBatching::Millis(4.0 * STEP_MILLIS))
+        .unwrap();
+    sink.clear_deliveries();
+    harness.run_for_millis(500.0);
+    let deliveries = sink.get_deliveries();
+    assert!(deliveries.len() >= 7);
+    assert!(deliveries
+        .iter()
+        .all(|delivery| delivery.timestamps.len() == 4));
+}
@@ -0,0 +1,3 @@
+pub mod node;
+
+pub use node::{ScriptEvent, ScriptLimits, ScriptNode};
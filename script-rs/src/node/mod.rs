@@ -0,0 +1,349 @@
+pub(crate) mod sink;
+pub(crate) mod source;
+
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+use std::sync::{Arc, Mutex};
+
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUSample, VecF64Convertible};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use publisher::PublisherManager;
+
+type ScriptEventCallback = Arc<Option<Arc<dyn Fn(ScriptEvent) + Send + Sync>>>;
+
+/// Event raised from a script by calling `emit(name, value)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptEvent {
+    pub name: String,
+    pub value: f64,
+}
+
+/// Resources a script may use to process a batch. A script exceeding any of them, e.g. stuck in
+/// an infinite loop, fails with an error instead of blocking the pipeline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScriptLimits {
+    /// Operations evaluated per batch.
+    pub max_operations: u64,
+    /// Nesting of function calls.
+    pub max_call_levels: usize,
+    /// Nesting of expressions, at global level and within functions.
+    pub max_expr_depth: usize,
+    pub max_function_expr_depth: usize,
+    /// Length of strings, in bytes.
+    pub max_string_size: usize,
+    /// Number of items of arrays.
+    pub max_array_size: usize,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            max_operations: 1_000_000,
+            max_call_levels: 32,
+            max_expr_depth: 64,
+            max_function_expr_depth: 32,
+            max_string_size: 64 * 1024,
+            max_array_size: 100_000,
+        }
+    }
+}
+
+impl ScriptLimits {
+    pub fn with_max_operations(mut self, max_operations: u64) -> Self {
+        self.max_operations = max_operations;
+        self
+    }
+
+    pub fn with_max_call_levels(mut self, max_call_levels: usize) -> Self {
+        self.max_call_levels = max_call_levels;
+        self
+    }
+
+    pub fn with_max_expr_depths(
+        mut self,
+        max_expr_depth: usize,
+        max_function_expr_depth: usize,
+    ) -> Self {
+        self.max_expr_depth = max_expr_depth;
+        self.max_function_expr_depth = max_function_expr_depth;
+        self
+    }
+
+    pub fn with_max_string_size(mut self, max_string_size: usize) -> Self {
+        self.max_string_size = max_string_size;
+        self
+    }
+
+    pub fn with_max_array_size(mut self, max_array_size: usize) -> Self {
+        self.max_array_size = max_array_size;
+        self
+    }
+
+    fn apply(&self, engine: &mut Engine) {
+        engine
+            .set_max_operations(self.max_operations)
+            .set_max_call_levels(self.max_call_levels)
+            .set_max_expr_depths(self.max_expr_depth, self.max_function_expr_depth)
+            .set_max_string_size(self.max_string_size)
+            .set_max_array_size(self.max_array_size);
+    }
+}
+
+/// Compiled script together with its persistent state.
+///
+/// The scope only holds the `state` map between batches. Anything else declared by the
+/// script is dropped once the batch has been processed.
+struct ScriptState {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    events: Arc<Mutex<Vec<ScriptEvent>>>,
+}
+
+impl ScriptState {
+    fn new(script: &str, limits: &ScriptLimits) -> Result<Self, ImuError> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+        limits.apply(&mut engine);
+        let events_clone = Arc::clone(&events);
+        engine.register_fn("emit", move |name: &str, value: f64| {
+            events_clone.lock().unwrap().push(ScriptEvent {
+                name: name.to_string(),
+                value,
+            });
+        });
+
+        let ast = engine
+            .compile(script)
+            .map_err(|e| ImuError::InvalidParameter(format!("Invalid script: {}", e)))?;
+        let mut scope = Scope::new();
+        scope.push("state", Map::new());
+
+        Ok(Self {
+            engine,
+            ast,
+            scope,
+            events,
+        })
+    }
+
+    fn process(&mut self, samples: Array) -> Result<(Dynamic, Vec<ScriptEvent>), String> {
+        let scope_len = self.scope.len();
+        self.scope.push("samples", samples);
+        let result = self
+            .engine
+            .eval_ast_with_scope::<Dynamic>(&mut self.scope, &self.ast)
+            .map_err(|e| e.to_string());
+        self.scope.rewind(scope_len);
+        let events = std::mem::take(&mut *self.events.lock().unwrap());
+        result.map(|output| (output, events))
+    }
+}
+
+/// Processing node that forwards every incoming batch to a user provided Rhai script.
+///
+/// The script is evaluated once per batch with two variables in scope:
+/// - `samples`: array of maps `#{ t: <timestamp secs>, v: [<measurement>] }`.
+/// - `state`: object map preserved between batches.
+///
+/// The script evaluates to an array of maps with the same layout, which is published
+/// as `new_measurement`. Evaluating to `()` or an empty array publishes nothing.
+/// Events are raised with `emit(name, value)`.
+///
+/// Scripts run within the default [`ScriptLimits`], unless built with `with_limits`.
+#[derive(Clone)]
+pub struct ScriptNode<S>
+where
+    S: IMUSample,
+{
+    state: Arc<Mutex<ScriptState>>,
+    callback: ScriptEventCallback,
+    tag: String,
+    publishers: PublisherManager<SensorReadings<S>, SensorType>,
+    new_measurement: SensorType,
+}
+
+impl<S> ScriptNode<S>
+where
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    pub fn new(tag: &str, new_measurement: SensorType, script: &str) -> Result<Self, ImuError> {
+        Self::with_limits(tag, new_measurement, script, ScriptLimits::default())
+    }
+
+    /// Creates a node running `script` within `limits`.
+    pub fn with_limits(
+        tag: &str,
+        new_measurement: SensorType,
+        script: &str,
+        limits: ScriptLimits,
+    ) -> Result<Self, ImuError> {
+        let state = ScriptState::new(script, &limits)?;
+        Ok(Self {
+            state: Arc::new(Mutex::new(state)),
+            callback: Arc::new(None),
+            tag: tag.to_string(),
+            publishers: PublisherManager::new(std::slice::from_ref(&new_measurement)),
+            new_measurement,
+        })
+    }
+
+    pub fn register_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(ScriptEvent) + Send + Sync + 'static,
+    {
+        self.callback = Arc::new(Some(Arc::new(callback)));
+    }
+
+    fn run_batch(&self, samples: Vec<S>) -> Result<(Vec<S>, Vec<ScriptEvent>), String> {
+        let input: Array = samples.into_iter().map(to_dynamic).collect();
+        let mut state = self.state.lock().unwrap();
+        let (output, events) = state.process(input)?;
+        drop(state);
+
+        if output.is_unit() {
+            return Ok((Vec::new(), events));
+        }
+        let output = output
+            .try_cast::<Array>()
+            .ok_or("Script must evaluate to an array")?;
+        let samples = output
+            .into_iter()
+            .map(from_dynamic::<S>)
+            .collect::<Result<Vec<S>, String>>()?;
+        Ok((samples, events))
+    }
+}
+
+fn to_dynamic<S>(sample: S) -> Dynamic
+where
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    let measurement: Vec<f64> = sample.get_measurement().into();
+    let mut map = Map::new();
+    map.insert("t".into(), Dynamic::from_float(sample.get_timestamp_secs()));
+    map.insert(
+        "v".into(),
+        Dynamic::from_array(measurement.into_iter().map(Dynamic::from_float).collect()),
+    );
+    Dynamic::from_map(map)
+}
+
+fn from_dynamic<S>(value: Dynamic) -> Result<S, String>
+where
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    let map = value
+        .try_cast::<Map>()
+        .ok_or("Output samples must be maps")?;
+    let timestamp = map
+        .get("t")
+        .and_then(as_f64)
+        .ok_or("Output sample is missing t")?;
+    let measurement = map
+        .get("v")
+        .and_then(|v| v.clone().try_cast::<Array>())
+        .ok_or("Output sample is missing v")?
+        .iter()
+        .map(|v| as_f64(v).ok_or("Output measurement must be numeric"))
+        .collect::<Result<Vec<f64>, _>>()?;
+    let measurement = S::Untimed::try_from(measurement)
+        .map_err(|_| "Output measurement has incorrect size".to_string())?;
+
+    Ok(S::from_measurement(timestamp, measurement))
+}
+
+fn as_f64(value: &Dynamic) -> Option<f64> {
+    value
+        .as_float()
+        .ok()
+        .or_else(|| value.as_int().ok().map(|v| v as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::types::timed::{Sample3D, SampleScalar};
+
+    fn node<S>(script: &str) -> ScriptNode<S>
+    where
+        S: IMUSample,
+        S::Untimed: VecF64Convertible,
+    {
+        ScriptNode::new(
+            "test",
+            SensorType::Other(uuid::Uuid::new_v4(), "Scripted".to_string()),
+            script,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_script_maps_samples() {
+        let node = node::<Sample3D>("samples.map(|s| #{ t: s.t, v: s.v.map(|x| x * 2.0) })");
+        let (output, events) = node
+            .run_batch(vec![Sample3D::new(1.0, [1.0, 2.0, 3.0])])
+            .unwrap();
+
+        assert!(events.is_empty());
+        assert_eq!(output, vec![Sample3D::new(1.0, [2.0, 4.0, 6.0])]);
+    }
+
+    #[test]
+    fn test_script_keeps_state_and_emits_events() {
+        let node = node::<SampleScalar>(
+            r#"
+            state.count = (state.count ?? 0) + samples.len();
+            if state.count >= 3 { emit("count", state.count.to_float()); }
+            "#,
+        );
+        let batch = vec![SampleScalar::new(0.0, 1.0), SampleScalar::new(0.1, 1.0)];
+
+        let (output, events) = node.run_batch(batch.clone()).unwrap();
+        assert!(output.is_empty());
+        assert!(events.is_empty());
+
+        let (_, events) = node.run_batch(batch).unwrap();
+        assert_eq!(
+            events,
+            vec![ScriptEvent {
+                name: "count".to_string(),
+                value: 4.0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_invalid_script() {
+        let tag = SensorType::Other(uuid::Uuid::new_v4(), "Scripted".to_string());
+        assert!(ScriptNode::<SampleScalar>::new("test", tag, "samples.map(").is_err());
+    }
+
+    #[test]
+    fn test_script_limits() {
+        let node = node::<SampleScalar>("loop { }");
+        let error = node.run_batch(vec![SampleScalar::default()]).err().unwrap();
+        assert!(error.contains("operations"));
+
+        let tag = SensorType::Other(uuid::Uuid::new_v4(), "Scripted".to_string());
+        let limits = ScriptLimits::default().with_max_array_size(2);
+        let node = ScriptNode::<SampleScalar>::with_limits(
+            "test",
+            tag,
+            "let t = []; for s in samples { t.push(s.t); }",
+            limits,
+        )
+        .unwrap();
+        assert!(node.run_batch(vec![SampleScalar::default(); 2]).is_ok());
+        assert!(node.run_batch(vec![SampleScalar::default(); 3]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_output() {
+        let node = node::<Sample3D>("[#{ t: 0.0, v: [1.0] }]");
+        assert!(node.run_batch(vec![Sample3D::default()]).is_err());
+    }
+}
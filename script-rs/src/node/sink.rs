@@ -0,0 +1,46 @@
+use publisher::adapters;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::ScriptNode;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource, VecF64Convertible};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+
+impl<T, S> IMUSink<T, S> for ScriptNode<S>
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, S>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        let (output, events) = match self.run_batch(samples.get_samples()) {
+            Ok(result) => result,
+            Err(e) => {
+                log::warn!("Script node {} failed: {}", self.tag, e);
+                return;
+            }
+        };
+
+        if let Some(cb) = self.callback.as_ref() {
+            for event in events {
+                cb(event);
+            }
+        }
+
+        if !output.is_empty() {
+            let readings =
+                SensorReadings::from_vec(&self.tag, self.new_measurement.clone(), output);
+            self.publishers
+                .notify_listeners(self.new_measurement.clone(), Arc::new(readings));
+        }
+    }
+}
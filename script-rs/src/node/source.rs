@@ -0,0 +1,37 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::ScriptNode;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUSample, IMUSource, Notifiable, VecF64Convertible};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+
+impl<S> IMUSource<SensorReadings<S>, S> for ScriptNode<S>
+where
+    S: IMUSample,
+    S::Untimed: VecF64Convertible,
+{
+    fn get_tag(&self) -> &str {
+        self.tag.as_str()
+    }
+
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.publishers.get_available_publisher_types()
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<S>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<S>>) {
+        self.publishers.notify_listeners(sensor_type, data);
+    }
+}
@@ -0,0 +1,26 @@
+//! Module errors
+
+/// Errors of the serial source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SerialError {
+    /// Error opening or reading the serial port.
+    Port(String),
+
+    /// Error indicating that a sensor is already published by another source.
+    DuplicatedSensor(String),
+
+    /// Error indicating that a received frame is malformed.
+    InvalidFrame(String),
+}
+
+impl std::fmt::Display for SerialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SerialError::Port(e) => write!(f, "Port error: {}", e),
+            SerialError::DuplicatedSensor(e) => write!(f, "Duplicated sensor: {}", e),
+            SerialError::InvalidFrame(e) => write!(f, "Invalid frame: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SerialError {}
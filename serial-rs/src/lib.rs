@@ -0,0 +1,24 @@
+//! # Crate serial-rs
+//!
+//! ## serial-rs
+//!
+//! The `serial-rs` crate publishes the readings of USB attached IMUs, such as an MPU-9250
+//! breakout behind an Arduino or WitMotion sensors, so they can feed the resampler like any
+//! other source.
+//!
+//! [`SerialImuSource`] reads a serial port and decodes its stream with a [`FrameParser`]:
+//! - [`CsvLineParser`]: text lines with a timestamp followed by the `x, y, z` values of every
+//!   sensor, optionally as NMEA-like sentences (`$IMU,...*checksum`).
+//! - [`BinaryFrameParser`]: raw little endian frames with a sync header and a checksum.
+//!
+//! Other protocols are supported by implementing [`FrameParser`].
+
+pub mod errors;
+pub mod parser;
+mod source;
+
+pub use errors::SerialError;
+pub use parser::binary::BinaryFrameParser;
+pub use parser::csv::CsvLineParser;
+pub use parser::{FrameParser, SerialReading};
+pub use source::{run_service, SerialImuSource, TimestampMode};
@@ -0,0 +1,143 @@
+//! Module binary
+//!
+//! Raw little endian frames, for boards streaming at rates too high for text:
+//!
+//! | Field     | Type       | Description                                        |
+//! |-----------|------------|----------------------------------------------------|
+//! | sync      | `[u8; 2]`  | `0xA5, 0x5A`                                       |
+//! | channel   | `u8`       | Index of the sensor in the source                  |
+//! | timestamp | `f64`      | Timestamp in seconds                               |
+//! | values    | `[f32; 3]` | `x, y, z`                                          |
+//! | checksum  | `u8`       | Wrapping sum of the bytes from channel to values   |
+//!
+//! Bytes before a sync header, and frames with an invalid checksum, are skipped, so the parser
+//! resynchronizes after lost bytes.
+
+use super::{FrameParser, SerialReading};
+use crate::errors::SerialError;
+use imu_common::types::timed::Sample3D;
+
+pub const SYNC: [u8; 2] = [0xA5, 0x5A];
+pub const FRAME_SIZE: usize = SYNC.len() + 1 + 8 + 3 * 4 + 1;
+
+/// Parser of binary frames.
+#[derive(Clone, Debug, Default)]
+pub struct BinaryFrameParser {
+    buffer: Vec<u8>,
+}
+
+impl BinaryFrameParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Encodes a binary frame.
+pub fn encode_frame(channel: u8, timestamp: f64, values: [f32; 3]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(FRAME_SIZE);
+    frame.extend(SYNC);
+    frame.push(channel);
+    frame.extend(timestamp.to_le_bytes());
+    frame.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+    frame.push(checksum(&frame[SYNC.len()..]));
+    frame
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |acc, b| acc.wrapping_add(*b))
+}
+
+fn decode_frame(frame: &[u8]) -> SerialReading {
+    let channel = frame[2] as usize;
+    let timestamp = f64::from_le_bytes(frame[3..11].try_into().unwrap());
+    let value = |i: usize| {
+        let start = 11 + i * 4;
+        f32::from_le_bytes(frame[start..start + 4].try_into().unwrap()) as f64
+    };
+    SerialReading {
+        channel,
+        sample: Sample3D::new(timestamp, [value(0), value(1), value(2)]),
+    }
+}
+
+impl FrameParser for BinaryFrameParser {
+    fn parse(&mut self, bytes: &[u8]) -> Vec<Result<SerialReading, SerialError>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut readings = Vec::new();
+        let mut start = 0;
+        while self.buffer.len() - start >= FRAME_SIZE {
+            let pending = &self.buffer[start..];
+            let Some(offset) = pending.windows(SYNC.len()).position(|w| w == SYNC) else {
+                // keep the last byte, it may be the start of a sync header
+                readings.push(Err(SerialError::InvalidFrame(format!(
+                    "Skipped {} bytes",
+                    pending.len() - 1
+                ))));
+                start = self.buffer.len() - 1;
+                break;
+            };
+            if offset > 0 {
+                readings.push(Err(SerialError::InvalidFrame(format!(
+                    "Skipped {} bytes",
+                    offset
+                ))));
+                start += offset;
+                continue;
+            }
+            let frame = &pending[..FRAME_SIZE];
+            if checksum(&frame[SYNC.len()..FRAME_SIZE - 1]) != frame[FRAME_SIZE - 1] {
+                readings.push(Err(SerialError::InvalidFrame(
+                    "Invalid checksum".to_string(),
+                )));
+                start += 1;
+                continue;
+            }
+            readings.push(Ok(decode_frame(frame)));
+            start += FRAME_SIZE;
+        }
+        self.buffer.drain(..start);
+        readings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::traits::IMUSample;
+
+    #[test]
+    fn test_parse_frames() {
+        let mut parser = BinaryFrameParser::new();
+        let mut stream = encode_frame(0, 1.5, [0.0, 0.0, 9.81]);
+        stream.extend(encode_frame(2, 1.5, [0.1, 0.2, 0.3]));
+
+        // frames can be split across reads
+        let mut readings = parser.parse(&stream[..30]);
+        readings.extend(parser.parse(&stream[30..]));
+
+        assert_eq!(readings.len(), 2);
+        let acc = readings[0].as_ref().unwrap();
+        assert_eq!(acc.channel, 0);
+        assert_eq!(acc.sample.get_timestamp_secs(), 1.5);
+        assert_eq!(acc.sample.get_measurement().inner()[2], 9.81f32 as f64);
+        assert_eq!(readings[1].as_ref().unwrap().channel, 2);
+    }
+
+    #[test]
+    fn test_resynchronize() {
+        let mut parser = BinaryFrameParser::new();
+        let mut corrupted = encode_frame(1, 2.0, [1.0, 2.0, 3.0]);
+        corrupted[5] ^= 0xFF;
+        let mut stream = vec![0x00, 0xA5, 0x01];
+        stream.extend(corrupted);
+        stream.extend(encode_frame(1, 2.0, [1.0, 2.0, 3.0]));
+
+        let readings = parser.parse(&stream);
+
+        let valid: Vec<_> = readings.iter().filter_map(|r| r.as_ref().ok()).collect();
+        assert_eq!(valid.len(), 1);
+        assert_eq!(valid[0].sample.get_timestamp_secs(), 2.0);
+        assert!(readings.iter().any(|r| r.is_err()));
+        assert!(parser.buffer.is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+//! Module csv
+//!
+//! Text lines with a timestamp followed by the `x, y, z` values of every sensor:
+//!
+//! ```text
+//! 1250,0.01,-0.02,9.81,0.001,0.0,0.002
+//! $IMU,1250,0.01,-0.02,9.81,0.001,0.0,0.002*3A
+//! ```
+//!
+//! Lines starting with `$` are NMEA-like sentences: the first field is the sentence id, and the
+//! optional `*HH` suffix is the XOR of the bytes between `$` and `*`, in hexadecimal. Empty
+//! lines and lines starting with `#` are ignored, so boards can print comments.
+
+use super::{FrameParser, SerialReading};
+use crate::errors::SerialError;
+use imu_common::types::timed::Sample3D;
+
+/// Longest line accepted. Longer lines are dropped.
+const MAX_LINE_LEN: usize = 1024;
+
+/// Parser of CSV lines. Values are grouped in threes, the first group being channel 0.
+#[derive(Clone, Debug)]
+pub struct CsvLineParser {
+    buffer: Vec<u8>,
+    timestamp_scale: f64,
+    sentence: Option<String>,
+    is_overflowed: bool,
+}
+
+impl Default for CsvLineParser {
+    fn default() -> Self {
+        Self {
+            buffer: Vec::new(),
+            timestamp_scale: 1.0,
+            sentence: None,
+            is_overflowed: false,
+        }
+    }
+}
+
+impl CsvLineParser {
+    /// Creates a parser of lines with timestamps in seconds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Multiplies timestamps by `scale`, e.g. `1e-3` for the `millis()` of an Arduino.
+    pub fn with_timestamp_scale(mut self, scale: f64) -> Self {
+        self.timestamp_scale = scale;
+        self
+    }
+
+    /// Only accepts `$<id>` sentences. Other sentences are ignored, and plain lines rejected.
+    pub fn with_sentence(mut self, id: &str) -> Self {
+        self.sentence = Some(id.to_string());
+        self
+    }
+
+    fn parse_line(&self, line: &str) -> Option<Result<Vec<SerialReading>, SerialError>> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let invalid = |e: String| Some(Err(SerialError::InvalidFrame(e)));
+
+        let fields = match line.strip_prefix('$') {
+            Some(sentence) => {
+                let (sentence, checksum) = match sentence.split_once('*') {
+                    Some((sentence, checksum)) => (sentence, Some(checksum)),
+                    None => (sentence, None),
+                };
+                if let Some(checksum) = checksum {
+                    let expected = sentence.bytes().fold(0u8, |acc, b| acc ^ b);
+                    if u8::from_str_radix(checksum, 16) != Ok(expected) {
+                        return invalid(format!("Invalid checksum in {}", line));
+                    }
+                }
+                let (id, fields) = sentence.split_once(',').unwrap_or((sentence, ""));
+                if self.sentence.as_ref().is_some_and(|s| s != id) {
+                    return None;
+                }
+                fields
+            }
+            None if self.sentence.is_some() => {
+                return invalid(format!("Expected sentence in {}", line))
+            }
+            None => line,
+        };
+
+        let values: Result<Vec<f64>, _> = fields.split(',').map(|f| f.trim().parse()).collect();
+        let Ok(values) = values else {
+            return invalid(format!("Invalid value in {}", line));
+        };
+        if values.len() < 4 || !(values.len() - 1).is_multiple_of(3) {
+            return invalid(format!("{} values in {}", values.len(), line));
+        }
+        let timestamp = values[0] * self.timestamp_scale;
+        let readings = values[1..]
+            .chunks_exact(3)
+            .enumerate()
+            .map(|(channel, v)| SerialReading {
+                channel,
+                sample: Sample3D::new(timestamp, [v[0], v[1], v[2]]),
+            })
+            .collect();
+        Some(Ok(readings))
+    }
+}
+
+impl FrameParser for CsvLineParser {
+    fn parse(&mut self, bytes: &[u8]) -> Vec<Result<SerialReading, SerialError>> {
+        let mut readings = Vec::new();
+        for &byte in bytes {
+            if byte != b'\n' {
+                if self.buffer.len() < MAX_LINE_LEN {
+                    self.buffer.push(byte);
+                } else {
+                    self.is_overflowed = true;
+                }
+                continue;
+            }
+            let line = std::mem::take(&mut self.buffer);
+            if std::mem::take(&mut self.is_overflowed) {
+                readings.push(Err(SerialError::InvalidFrame("Line too long".to_string())));
+                continue;
+            }
+            match self.parse_line(&String::from_utf8_lossy(&line)) {
+                Some(Ok(line_readings)) => readings.extend(line_readings.into_iter().map(Ok)),
+                Some(Err(e)) => readings.push(Err(e)),
+                None => {}
+            }
+        }
+        readings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::traits::IMUSample;
+
+    #[test]
+    fn test_parse_lines() {
+        let mut parser = CsvLineParser::new().with_timestamp_scale(1e-3);
+
+        // lines can be split across reads
+        assert!(parser
+            .parse(b"# MPU9250 ready\n1250,0.1,0.2,9.8,")
+            .is_empty());
+        let readings = parser.parse(b"0.01,0.02,0.03\r\n1260,1,2\n");
+
+        assert_eq!(readings.len(), 3);
+        let gyro = readings[1].as_ref().unwrap();
+        assert_eq!(gyro.channel, 1);
+        assert_eq!(gyro.sample.get_timestamp_secs(), 1.25);
+        assert_eq!(gyro.sample.get_measurement().inner(), [0.01, 0.02, 0.03]);
+        assert!(readings[2].is_err());
+    }
+
+    #[test]
+    fn test_parse_sentences() {
+        let mut parser = CsvLineParser::new().with_sentence("IMU");
+        let sentence = "IMU,1.5,0.1,0.2,9.8";
+        let checksum = sentence.bytes().fold(0u8, |acc, b| acc ^ b);
+        let input = format!(
+            "${}*{:02X}\n$GPS,1,2,3,4\n${}*00\n1.5,0.1,0.2,9.8\n",
+            sentence, checksum, sentence
+        );
+
+        let readings = parser.parse(input.as_bytes());
+
+        assert_eq!(readings.len(), 3);
+        assert_eq!(
+            readings[0].as_ref().unwrap().sample.get_timestamp_secs(),
+            1.5
+        );
+        // invalid checksum, and plain line
+        assert!(readings[1].is_err());
+        assert!(readings[2].is_err());
+    }
+
+    #[test]
+    fn test_long_line() {
+        let mut parser = CsvLineParser::new();
+        let mut input = vec![b'1'; MAX_LINE_LEN + 1];
+        input.extend(b"\n1,1,2,3\n");
+
+        let readings = parser.parse(&input);
+
+        assert_eq!(readings.len(), 2);
+        assert!(readings[0].is_err());
+        assert!(readings[1].is_ok());
+    }
+}
@@ -0,0 +1,24 @@
+//! Module parser
+//!
+//! Decoders of the byte stream read from a serial port.
+
+pub mod binary;
+pub mod csv;
+
+use crate::errors::SerialError;
+use imu_common::types::timed::Sample3D;
+
+/// Reading decoded from the stream. `channel` is the index of its sensor in the source.
+#[derive(Clone, Debug)]
+pub struct SerialReading {
+    pub channel: usize,
+    pub sample: Sample3D,
+}
+
+/// Decoder of the byte stream of a device.
+pub trait FrameParser: Send {
+    /// Consumes `bytes` read from the port, and returns the readings of every complete frame, or
+    /// an InvalidFrame error for the malformed ones. Incomplete frames are kept until the next
+    /// call.
+    fn parse(&mut self, bytes: &[u8]) -> Vec<Result<SerialReading, SerialError>>;
+}
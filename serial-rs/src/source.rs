@@ -0,0 +1,251 @@
+use log::{error, warn};
+use publisher::{PublisherManager, ShutdownToken};
+use std::io::{ErrorKind, Read};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::errors::SerialError;
+use crate::parser::FrameParser;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSource, Notifiable};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::Clock;
+
+/// Size of the reads from the port.
+const READ_BUFFER_SIZE: usize = 1024;
+/// Timeout of the reads from the port, so the source checks regularly if it was stopped.
+const READ_TIMEOUT_MILLIS: u64 = 100;
+
+/// Time base of the published readings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimestampMode {
+    /// Timestamps sent by the device, unchanged.
+    Device,
+    /// Timestamps sent by the device, shifted so the first reading is stamped with the time it
+    /// was received. Used with devices counting from boot, so their readings can be resampled
+    /// with the ones of other sources.
+    #[default]
+    Anchored,
+}
+
+/// Source publishing the readings of a device attached to a serial port.
+///
+/// The stream is decoded by a [`FrameParser`], whose channels index `sensors`. Readings of one
+/// read are published together. Malformed frames and frames of unknown channels are logged and
+/// dropped.
+pub struct SerialImuSource {
+    tag: String,
+    sensors: Vec<SensorType>,
+    publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+    parser: Mutex<Box<dyn FrameParser>>,
+    timestamp_mode: TimestampMode,
+    clock_offset: Mutex<Option<f64>>,
+    is_stopped: AtomicBool,
+    shutdown: ShutdownToken,
+    dropped_frames: AtomicUsize,
+}
+
+impl SerialImuSource {
+    /// Creates a source decoding the stream with `parser`.
+    /// Returns a DuplicatedSensor error if a sensor is repeated or already published by another
+    /// source.
+    pub fn new<P>(tag: &str, sensors: Vec<SensorType>, parser: P) -> Result<Self, SerialError>
+    where
+        P: FrameParser + 'static,
+    {
+        let publishers = PublisherManager::try_new(&sensors)
+            .map_err(|e| SerialError::DuplicatedSensor(format!("{} in {}", e, tag)))?;
+        Ok(Self {
+            tag: tag.to_string(),
+            sensors,
+            publishers,
+            parser: Mutex::new(Box::new(parser)),
+            timestamp_mode: TimestampMode::default(),
+            clock_offset: Mutex::new(None),
+            is_stopped: AtomicBool::new(false),
+            shutdown: ShutdownToken::global(),
+            dropped_frames: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn with_timestamp_mode(mut self, timestamp_mode: TimestampMode) -> Self {
+        self.timestamp_mode = timestamp_mode;
+        self
+    }
+
+    /// Stops the source when `token` is shut down, instead of the global token.
+    pub fn with_shutdown_token(mut self, token: ShutdownToken) -> Self {
+        self.shutdown = token;
+        self
+    }
+
+    /// Returns the number of frames dropped because they were malformed or referenced an
+    /// unknown channel.
+    pub fn get_dropped_frames(&self) -> usize {
+        self.dropped_frames.load(Ordering::Relaxed)
+    }
+
+    /// Reads and publishes readings from `reader` until it ends, `stop` is called or the
+    /// shutdown token is shut down. Read timeouts are retried, so `reader` should time out
+    /// regularly for the source to notice it was stopped.
+    /// Returns a Port error if reading fails.
+    pub fn start<R: Read>(&self, reader: R) -> Result<(), SerialError> {
+        self.read_while(reader, || true)
+    }
+
+    /// Same as `start`, also returning once `is_running` returns false.
+    fn read_while<R, F>(&self, mut reader: R, is_running: F) -> Result<(), SerialError>
+    where
+        R: Read,
+        F: Fn() -> bool,
+    {
+        let mut buffer = [0u8; READ_BUFFER_SIZE];
+        while !self.is_stopped.load(Ordering::Relaxed)
+            && !self.shutdown.is_shutdown()
+            && is_running()
+        {
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(n) => self.process_bytes(&buffer[..n]),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => return Err(SerialError::Port(e.to_string())),
+            }
+        }
+        Ok(())
+    }
+
+    /// Stops `start`. If it isn't running yet, it returns as soon as it is called.
+    pub fn stop(&self) {
+        self.is_stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Decodes `bytes` and publishes the readings of the complete frames.
+    pub fn process_bytes(&self, bytes: &[u8]) {
+        let frames = self.parser.lock().unwrap().parse(bytes);
+        let mut readings: Vec<SensorReadings<Sample3D>> = Vec::new();
+        for frame in frames {
+            let frame = match frame {
+                Ok(frame) => frame,
+                Err(e) => {
+                    warn!("Dropping frame received by {}: {}", self.tag, e);
+                    self.dropped_frames.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+            };
+            let Some(sensor_type) = self.sensors.get(frame.channel) else {
+                warn!(
+                    "Dropping frame of unknown channel {} in {}",
+                    frame.channel, self.tag
+                );
+                self.dropped_frames.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+            let sample = self.to_time_base(frame.sample);
+            match readings
+                .iter_mut()
+                .find(|r| r.get_sensor_type() == *sensor_type)
+            {
+                Some(sensor_readings) => sensor_readings.add_sample(sample),
+                None => readings.push(SensorReadings::from_vec(
+                    &self.tag,
+                    sensor_type.clone(),
+                    vec![sample],
+                )),
+            }
+        }
+
+        for readings in readings {
+            self.publishers
+                .notify_listeners(readings.get_sensor_type(), Arc::new(readings));
+        }
+    }
+
+    fn to_time_base(&self, sample: Sample3D) -> Sample3D {
+        match self.timestamp_mode {
+            TimestampMode::Device => sample,
+            TimestampMode::Anchored => {
+                let timestamp = sample.get_timestamp_secs();
+                let offset = *self
+                    .clock_offset
+                    .lock()
+                    .unwrap()
+                    .get_or_insert_with(|| Clock::now().as_secs() - timestamp);
+                Sample3D::from_measurement(timestamp + offset, sample.get_measurement())
+            }
+        }
+    }
+}
+
+impl IMUSource<SensorReadings<Sample3D>, Sample3D> for SerialImuSource {
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.sensors.clone()
+    }
+
+    fn get_tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<Sample3D>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<Sample3D>>) {
+        self.publishers.notify_listeners(sensor_type, data);
+    }
+}
+
+/// Starts a serial source reading `port` at `baud_rate` in a background thread.
+///
+/// `sensors` are the sensors of the device, in the order of the parser channels.
+///
+/// Returns a Port error if the port can't be opened, and DuplicatedSensor if the sensors
+/// collide with the ones of a running source.
+///
+/// # Returns
+///
+/// Returns a tuple containing:
+/// * A `std::thread::JoinHandle<()>` of the thread, which ends when `SerialImuSource::stop` is
+///   called, the source is dropped, the port is closed or the global `ShutdownToken` is shut
+///   down.
+/// * An `Arc<SerialImuSource>` to register listeners and stop the source.
+pub fn run_service<P>(
+    port: &str,
+    baud_rate: u32,
+    tag: &str,
+    sensors: Vec<SensorType>,
+    parser: P,
+) -> Result<(std::thread::JoinHandle<()>, Arc<SerialImuSource>), SerialError>
+where
+    P: FrameParser + 'static,
+{
+    let source = Arc::new(SerialImuSource::new(tag, sensors, parser)?);
+    let port = serialport::new(port, baud_rate)
+        .timeout(Duration::from_millis(READ_TIMEOUT_MILLIS))
+        .open()
+        .map_err(|e| SerialError::Port(e.to_string()))?;
+    let handle = std::thread::spawn({
+        let source = source.clone();
+        move || {
+            // stop once the caller drops the source
+            if let Err(e) = source.read_while(port, || Arc::strong_count(&source) > 1) {
+                error!("Error in serial loop: {:?}", e);
+            }
+        }
+    });
+    Ok((handle, source))
+}
@@ -0,0 +1,82 @@
+use publisher::Listener;
+use std::sync::{Arc, Mutex};
+
+use imu_common::traits::{IMUReadings, IMUSample, IMUSource};
+use imu_common::types::sensors::{SensorClusterBuilder, SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use imu_common::types::Clock;
+use serial_rs::parser::binary::encode_frame;
+use serial_rs::{BinaryFrameParser, CsvLineParser, SerialError, SerialImuSource, TimestampMode};
+
+type Batches = Arc<Mutex<Vec<Vec<Sample3D>>>>;
+
+fn collect(
+    source: &SerialImuSource,
+    sensor_type: &SensorType,
+) -> (Listener<SensorReadings<Sample3D>>, Batches) {
+    let batches: Batches = Arc::new(Mutex::new(Vec::new()));
+    let mut listener = Listener::new({
+        let batches = batches.clone();
+        move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+            batches.lock().unwrap().push(readings.get_samples());
+        }
+    });
+    source
+        .register_listener(&mut listener, sensor_type)
+        .unwrap();
+    (listener, batches)
+}
+
+#[test]
+fn test_read_csv_stream() {
+    let sensors = SensorClusterBuilder::new().six_axis().build().unwrap();
+    let source = SerialImuSource::new("Arduino", sensors.clone(), CsvLineParser::new())
+        .unwrap()
+        .with_timestamp_mode(TimestampMode::Device);
+    let (_listener, gyro) = collect(&source, &sensors[1]);
+
+    let stream = "# ready\n1.0,0,0,9.8,0.1,0.2,0.3\n1.1,0,0,9.8,0.1,0.2\n1.2,0,0,9.8,0.4,0.5,0.6\n";
+    source.start(stream.as_bytes()).unwrap();
+
+    let gyro = gyro.lock().unwrap();
+    let timestamps: Vec<f64> = gyro
+        .iter()
+        .flatten()
+        .map(|s| s.get_timestamp_secs())
+        .collect();
+    assert_eq!(timestamps, vec![1.0, 1.2]);
+    assert_eq!(source.get_dropped_frames(), 1);
+}
+
+#[test]
+fn test_read_binary_stream_anchored() {
+    let sensors = SensorType::cluster_for_tag("test_read_binary_stream_anchored");
+    let source =
+        SerialImuSource::new("WitMotion", sensors.clone(), BinaryFrameParser::new()).unwrap();
+    let (_listener, acc) = collect(&source, &sensors[0]);
+
+    let mut stream = Vec::new();
+    for i in 0..4 {
+        stream.extend(encode_frame(0, 10.0 + i as f64 * 0.01, [0.0, 0.0, 9.81]));
+    }
+    stream.extend(encode_frame(7, 10.0, [0.0, 0.0, 0.0]));
+    let received_at = Clock::now().as_secs();
+    source.process_bytes(&stream);
+
+    let acc = acc.lock().unwrap();
+    // readings of one read are published together
+    assert_eq!(acc.len(), 1);
+    let first = acc[0][0].get_timestamp_secs();
+    assert!((first - received_at).abs() < 1.0);
+    assert!((acc[0][3].get_timestamp_secs() - first - 0.03).abs() < 1e-6);
+    assert_eq!(source.get_dropped_frames(), 1);
+}
+
+#[test]
+fn test_duplicated_sensors() {
+    let sensors = SensorType::cluster_for_tag("test_serial_duplicated_sensors");
+    let _source = SerialImuSource::new("Board", sensors.clone(), CsvLineParser::new()).unwrap();
+
+    let result = SerialImuSource::new("Board", sensors, CsvLineParser::new());
+    assert!(matches!(result, Err(SerialError::DuplicatedSensor(_))));
+}
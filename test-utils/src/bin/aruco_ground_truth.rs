@@ -0,0 +1,85 @@
+//! Prints the orientation of a device carrying an ArUco marker (`DICT_4X4_50`), tracked with a
+//! webcam, as CSV `timestamp,w,x,y,z` relative to the first pose. Requires the `aruco` feature.
+//!
+//! Usage: aruco_ground_truth [--camera <index>] [--marker-id <id>] [--marker-length <m>]
+//!        [--intrinsics <fx>,<fy>,<cx>,<cy>[,<k1>,<k2>,<p1>,<p2>,<k3>]]
+
+use std::env;
+use std::fmt::Display;
+use std::process;
+
+use imu_common::types::sensors::SensorType;
+use test_utils::ground_truth::{ArucoTracker, CameraIntrinsics, MarkerGroundTruth};
+use uuid::Uuid;
+
+fn main() {
+    let mut camera_index = 0;
+    let mut marker_id = 0;
+    let mut marker_length = 0.05;
+    let mut intrinsics = CameraIntrinsics::approximate(640.0, 480.0);
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_default();
+        match arg.as_str() {
+            "--camera" => camera_index = value.parse().unwrap_or_else(|_| exit_with_usage()),
+            "--marker-id" => marker_id = value.parse().unwrap_or_else(|_| exit_with_usage()),
+            "--marker-length" => {
+                marker_length = value.parse().unwrap_or_else(|_| exit_with_usage())
+            }
+            "--intrinsics" => intrinsics = parse_intrinsics(&value),
+            _ => exit_with_usage(),
+        }
+    }
+
+    let sensor_type = SensorType::Other(Uuid::new_v4(), "Ground Truth".to_string());
+    let ground_truth = MarkerGroundTruth::new("Camera", sensor_type).unwrap_or_else(|e| fail(&e));
+    let mut tracker = ArucoTracker::open(camera_index, &intrinsics, marker_id, marker_length)
+        .unwrap_or_else(|e| fail(&e));
+
+    println!("timestamp,w,x,y,z");
+    loop {
+        match tracker.next_pose() {
+            Ok(Some(pose)) => {
+                let q = ground_truth.orientation(&pose).inner();
+                println!("{},{},{},{},{}", pose.timestamp_secs, q.w, q.i, q.j, q.k);
+            }
+            Ok(None) => {}
+            Err(e) => fail(&e),
+        }
+    }
+}
+
+fn parse_intrinsics(value: &str) -> CameraIntrinsics {
+    let values: Vec<f64> = value
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|_| exit_with_usage());
+    let mut distortion = [0.0; 5];
+    match values.len() {
+        4 => {}
+        9 => distortion.copy_from_slice(&values[4..]),
+        _ => exit_with_usage(),
+    }
+    CameraIntrinsics {
+        fx: values[0],
+        fy: values[1],
+        cx: values[2],
+        cy: values[3],
+        distortion,
+    }
+}
+
+fn fail(e: &impl Display) -> ! {
+    eprintln!("{}", e);
+    process::exit(1);
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!(
+        "Usage: aruco_ground_truth [--camera <index>] [--marker-id <id>] [--marker-length <m>] \
+         [--intrinsics <fx>,<fy>,<cx>,<cy>[,<k1>,<k2>,<p1>,<p2>,<k3>]]"
+    );
+    process::exit(2);
+}
@@ -0,0 +1,74 @@
+//! Exports a recorded orientation stream as a glTF animation of a box (or of an OBJ/STL model),
+//! or as an OBJ path.
+//!
+//! The orientation file has `timestamp, w, x, y, z` columns, and the optional positions file
+//! `timestamp, x, y, z` columns. The output format is chosen from the output file extension.
+//!
+//! Usage: export_path <orientation.csv> <output.gltf|output.obj> [--positions <positions.csv>] [--model <model.obj|model.stl>]
+
+use std::env;
+use std::process;
+
+use imu_common::types::timed::{Sample3D, SampleQuaternion};
+use test_utils::csv_loader::load_csv_columns;
+use test_utils::export::OrientationPath;
+use test_utils::renderable::{Box3D, Mesh3D};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut files = Vec::new();
+    let mut positions_path = None;
+    let mut model_path = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--positions" => {
+                positions_path = Some(args.next().unwrap_or_else(|| exit_with_usage()))
+            }
+            "--model" => model_path = Some(args.next().unwrap_or_else(|| exit_with_usage())),
+            _ => files.push(arg),
+        }
+    }
+    let [input_path, output_path] = files.as_slice() else {
+        exit_with_usage();
+    };
+
+    let samples = load_csv_columns::<SampleQuaternion>(input_path, &[0, 1, 2, 3, 4])
+        .unwrap_or_else(|e| exit_with_error(&format!("Failed to read {}: {}", input_path, e)));
+    let mut path = OrientationPath::from_samples(&samples);
+    if let Some(positions_path) = positions_path {
+        let positions = load_csv_columns::<Sample3D>(&positions_path, &[0, 1, 2, 3])
+            .unwrap_or_else(|e| {
+                exit_with_error(&format!("Failed to read {}: {}", positions_path, e))
+            });
+        path = path
+            .with_positions(&positions)
+            .unwrap_or_else(|e| exit_with_error(&e));
+    }
+
+    let result = if output_path.ends_with(".obj") {
+        path.write_obj(output_path)
+    } else if output_path.ends_with(".gltf") {
+        match model_path {
+            Some(model_path) => Mesh3D::from_file(&model_path)
+                .and_then(|model| path.write_gltf(&model.normalized(), output_path)),
+            None => path.write_gltf(&Box3D::new(), output_path),
+        }
+    } else {
+        exit_with_usage();
+    };
+    if let Err(e) = result {
+        exit_with_error(&format!("Failed to write {}: {}", output_path, e));
+    }
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("Usage: export_path <orientation.csv> <output.gltf|output.obj> [--positions <positions.csv>] [--model <model.obj|model.stl>]");
+    process::exit(2);
+}
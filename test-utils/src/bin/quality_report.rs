@@ -0,0 +1,48 @@
+//! Prints a data quality report of a recording in `csv_loader` layout.
+//!
+//! Usage: quality_report <file.csv> [--json] [--ms] [--range <sensor>=<limit>]...
+
+use std::env;
+use std::process;
+
+use test_utils::quality_report::{QualityConfig, QualityReport};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut file_path = None;
+    let mut json = false;
+    let mut config = QualityConfig::default();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--ms" => config.timestamp_scale = 1e-3,
+            "--range" => {
+                let range = args.next().unwrap_or_default();
+                match range.split_once('=').map(|(s, l)| (s, l.parse::<f64>())) {
+                    Some((sensor, Ok(limit))) => config = config.with_range_limit(sensor, limit),
+                    _ => exit_with_usage(),
+                }
+            }
+            _ => file_path = Some(arg),
+        }
+    }
+
+    let Some(file_path) = file_path else {
+        exit_with_usage();
+    };
+    match QualityReport::from_csv(&file_path, &config) {
+        Ok(report) if json => println!("{}", report.to_json()),
+        Ok(report) => println!("{}", report.to_markdown()),
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", file_path, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("Usage: quality_report <file.csv> [--json] [--ms] [--range <sensor>=<limit>]...");
+    process::exit(2);
+}
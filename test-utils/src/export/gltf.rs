@@ -0,0 +1,229 @@
+use base64::Engine;
+use serde_json::{json, Value};
+
+use super::OrientationPath;
+use crate::renderable::Renderable3D;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const MODE_LINES: u32 = 1;
+
+/// Binary buffer with its views and accessors.
+#[derive(Default)]
+struct BufferBuilder {
+    data: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl BufferBuilder {
+    /// Appends `values` as a new accessor and returns its index. Bounds are always included, as
+    /// they are required for vertex positions and animation inputs.
+    fn push_floats(
+        &mut self,
+        values: &[f32],
+        kind: &str,
+        n_components: usize,
+        target: Option<u32>,
+    ) -> usize {
+        let (min, max) = min_max_by_component(values, n_components);
+        let accessor = json!({
+            "bufferView": self.push_view(values.iter().flat_map(|v| v.to_le_bytes()), target),
+            "componentType": FLOAT,
+            "count": values.len() / n_components,
+            "type": kind,
+            "min": min,
+            "max": max,
+        });
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_indices(&mut self, values: &[u32]) -> usize {
+        let accessor = json!({
+            "bufferView": self.push_view(
+                values.iter().flat_map(|v| v.to_le_bytes()),
+                Some(ELEMENT_ARRAY_BUFFER),
+            ),
+            "componentType": UNSIGNED_INT,
+            "count": values.len(),
+            "type": "SCALAR",
+        });
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_view(&mut self, bytes: impl Iterator<Item = u8>, target: Option<u32>) -> usize {
+        // every component is 4 bytes long, so views stay aligned
+        let offset = self.data.len();
+        self.data.extend(bytes);
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": self.data.len() - offset,
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.views.push(view);
+        self.views.len() - 1
+    }
+}
+
+fn min_max_by_component(values: &[f32], n_components: usize) -> (Vec<f32>, Vec<f32>) {
+    let mut min = vec![f32::MAX; n_components];
+    let mut max = vec![f32::MIN; n_components];
+    for chunk in values.chunks(n_components) {
+        for (i, v) in chunk.iter().enumerate() {
+            min[i] = min[i].min(*v);
+            max[i] = max[i].max(*v);
+        }
+    }
+    (min, max)
+}
+
+pub(super) fn to_gltf<R: Renderable3D>(
+    path: &OrientationPath,
+    model: &R,
+) -> Result<String, String> {
+    let keyframes = path.get_keyframes();
+    let Some(first) = keyframes.first() else {
+        return Err("Empty orientation path".to_string());
+    };
+    let vertices = model.vertices();
+    let edges = model.edges();
+    if vertices.is_empty() || edges.is_empty() {
+        return Err("Model has no vertices or edges".to_string());
+    }
+    if edges
+        .iter()
+        .any(|&(i, j)| i >= vertices.len() || j >= vertices.len())
+    {
+        return Err("Model edge references a missing vertex".to_string());
+    }
+
+    let mut buffer = BufferBuilder::default();
+    let positions: Vec<f32> = vertices
+        .iter()
+        .flat_map(|&(x, y, z)| [x as f32, y as f32, z as f32])
+        .collect();
+    let indices: Vec<u32> = edges
+        .iter()
+        .flat_map(|&(i, j)| [i as u32, j as u32])
+        .collect();
+    let position_accessor = buffer.push_floats(&positions, "VEC3", 3, Some(ARRAY_BUFFER));
+    let index_accessor = buffer.push_indices(&indices);
+
+    let times: Vec<f32> = keyframes
+        .iter()
+        .map(|k| (k.timestamp - first.timestamp) as f32)
+        .collect();
+    // glTF quaternions are stored as (x, y, z, w)
+    let rotations: Vec<f32> = keyframes
+        .iter()
+        .flat_map(|k| {
+            let q = k.rotation.quaternion();
+            [q.i as f32, q.j as f32, q.k as f32, q.w as f32]
+        })
+        .collect();
+    let time_accessor = buffer.push_floats(&times, "SCALAR", 1, None);
+    let rotation_accessor = buffer.push_floats(&rotations, "VEC4", 4, None);
+
+    let mut samplers = vec![json!({
+        "input": time_accessor,
+        "output": rotation_accessor,
+        "interpolation": "LINEAR",
+    })];
+    let mut channels = vec![json!({"sampler": 0, "target": {"node": 0, "path": "rotation"}})];
+    if keyframes.iter().all(|k| k.position.is_some()) {
+        let translations: Vec<f32> = keyframes
+            .iter()
+            .flat_map(|k| {
+                let p = k.position.unwrap_or_default();
+                [p.x as f32, p.y as f32, p.z as f32]
+            })
+            .collect();
+        samplers.push(json!({
+            "input": time_accessor,
+            "output": buffer.push_floats(&translations, "VEC3", 3, None),
+            "interpolation": "LINEAR",
+        }));
+        channels.push(json!({"sampler": 1, "target": {"node": 0, "path": "translation"}}));
+    }
+
+    let data = base64::engine::general_purpose::STANDARD.encode(&buffer.data);
+    let document = json!({
+        "asset": {"version": "2.0", "generator": "imu-rs"},
+        "scene": 0,
+        "scenes": [{"nodes": [0]}],
+        "nodes": [{"name": "Body", "mesh": 0}],
+        "meshes": [{
+            "primitives": [{
+                "attributes": {"POSITION": position_accessor},
+                "indices": index_accessor,
+                "mode": MODE_LINES,
+            }],
+        }],
+        "animations": [{"name": "Orientation", "samplers": samplers, "channels": channels}],
+        "buffers": [{
+            "byteLength": buffer.data.len(),
+            "uri": format!("data:application/octet-stream;base64,{}", data),
+        }],
+        "bufferViews": buffer.views,
+        "accessors": buffer.accessors,
+    });
+    serde_json::to_string_pretty(&document).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderable::Box3D;
+    use imu_common::types::timed::{Sample3D, SampleQuaternion};
+
+    fn path() -> OrientationPath {
+        let samples: Vec<SampleQuaternion> = (0..10)
+            .map(|i| SampleQuaternion::new(100.0 + i as f64 * 0.1, [1.0, 0.0, 0.0, i as f64 * 0.1]))
+            .collect();
+        OrientationPath::from_samples(&samples)
+    }
+
+    #[test]
+    fn test_gltf_document() {
+        let gltf = path().to_gltf(&Box3D::new()).unwrap();
+        let document = ::gltf::Gltf::from_slice(gltf.as_bytes()).unwrap();
+
+        let animation = document.animations().next().unwrap();
+        assert_eq!(animation.channels().count(), 1);
+        let input = animation.samplers().next().unwrap().input();
+        assert_eq!(input.count(), 10);
+        assert_eq!(input.min(), Some(json!([0.0])));
+
+        let primitive = document
+            .meshes()
+            .next()
+            .unwrap()
+            .primitives()
+            .next()
+            .unwrap();
+        assert_eq!(primitive.mode(), ::gltf::mesh::Mode::Lines);
+        assert_eq!(primitive.indices().unwrap().count(), 24);
+    }
+
+    #[test]
+    fn test_gltf_translations() {
+        let positions = [
+            Sample3D::new(100.0, [0.0, 0.0, 0.0]),
+            Sample3D::new(100.5, [1.0, 0.0, 0.0]),
+        ];
+        let path = path().with_positions(&positions).unwrap();
+        assert_eq!(path.get_keyframes()[9].position.unwrap().x, 1.0);
+
+        let gltf = path.to_gltf(&Box3D::new()).unwrap();
+        let document = ::gltf::Gltf::from_slice(gltf.as_bytes()).unwrap();
+        let animation = document.animations().next().unwrap();
+        assert_eq!(animation.channels().count(), 2);
+    }
+}
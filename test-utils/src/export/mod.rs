@@ -0,0 +1,105 @@
+//! Module export
+//!
+//! Exports a recorded orientation stream so it can be inspected outside of this library:
+//!
+//! - [`OrientationPath::to_gltf`]: glTF 2.0 animation of a [`Renderable3D`] model (e.g. `Box3D`),
+//!   viewable in any standard 3D viewer (Blender, three.js editor, online glTF viewers).
+//! - [`OrientationPath::to_obj`]: Wavefront OBJ polyline of the path followed by the model.
+//!
+//! Coordinates are exported as they are, without any change of axes.
+
+mod gltf;
+mod obj;
+
+use nalgebra::{UnitQuaternion, Vector3};
+use std::fs;
+
+use imu_common::traits::IMUSample;
+use imu_common::types::timed::{Sample3D, SampleQuaternion};
+
+use crate::renderable::Renderable3D;
+
+/// Pose of the model at a given time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe {
+    pub timestamp: f64,
+    pub rotation: UnitQuaternion<f64>,
+    pub position: Option<Vector3<f64>>,
+}
+
+/// Sequence of poses, sorted by timestamp.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrientationPath {
+    keyframes: Vec<Keyframe>,
+}
+
+impl OrientationPath {
+    /// Builds a path from a quaternion stream. Samples are sorted by timestamp, and samples with a
+    /// repeated timestamp are dropped, as animation keyframes must be strictly increasing.
+    pub fn from_samples(samples: &[SampleQuaternion]) -> Self {
+        let mut keyframes: Vec<Keyframe> = samples
+            .iter()
+            .map(|s| Keyframe {
+                timestamp: s.get_timestamp_secs(),
+                rotation: s.get_measurement().inner(),
+                position: None,
+            })
+            .collect();
+        keyframes.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        keyframes.dedup_by(|a, b| a.timestamp == b.timestamp);
+        Self { keyframes }
+    }
+
+    /// Adds the position of the model at every keyframe. Every keyframe takes the most recent
+    /// position at or before its timestamp, or the first position if there is none.
+    pub fn with_positions(mut self, positions: &[Sample3D]) -> Result<Self, String> {
+        if positions.is_empty() {
+            return Err("No positions provided".to_string());
+        }
+        let mut positions: Vec<&Sample3D> = positions.iter().collect();
+        positions.sort_by(|a, b| a.get_timestamp_secs().total_cmp(&b.get_timestamp_secs()));
+
+        let mut next = 0;
+        for keyframe in self.keyframes.iter_mut() {
+            while next + 1 < positions.len()
+                && positions[next + 1].get_timestamp_secs() <= keyframe.timestamp
+            {
+                next += 1;
+            }
+            let position = positions[next].get_measurement().inner();
+            keyframe.position = Some(Vector3::new(position[0], position[1], position[2]));
+        }
+        Ok(self)
+    }
+
+    pub fn get_keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn len(&self) -> usize {
+        self.keyframes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Returns a self-contained glTF 2.0 document (JSON, with embedded buffers) animating `model`.
+    pub fn to_gltf<R: Renderable3D>(&self, model: &R) -> Result<String, String> {
+        gltf::to_gltf(self, model)
+    }
+
+    /// Returns an OBJ document with the path followed by the model: its positions if known, or
+    /// otherwise the tip of its rotated x axis.
+    pub fn to_obj(&self) -> Result<String, String> {
+        obj::to_obj(self)
+    }
+
+    pub fn write_gltf<R: Renderable3D>(&self, model: &R, file_path: &str) -> Result<(), String> {
+        fs::write(file_path, self.to_gltf(model)?).map_err(|e| e.to_string())
+    }
+
+    pub fn write_obj(&self, file_path: &str) -> Result<(), String> {
+        fs::write(file_path, self.to_obj()?).map_err(|e| e.to_string())
+    }
+}
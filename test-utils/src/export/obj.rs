@@ -0,0 +1,48 @@
+use nalgebra::Vector3;
+use std::fmt::Write;
+
+use super::OrientationPath;
+
+pub(super) fn to_obj(path: &OrientationPath) -> Result<String, String> {
+    if path.is_empty() {
+        return Err("Empty orientation path".to_string());
+    }
+    let mut obj = String::from("# Orientation path exported by imu-rs\no path\n");
+    for keyframe in path.get_keyframes() {
+        let point = keyframe
+            .position
+            .unwrap_or_else(|| keyframe.rotation * Vector3::x());
+        writeln!(obj, "v {} {} {}", point.x, point.y, point.z).map_err(|e| e.to_string())?;
+    }
+    if path.len() > 1 {
+        let indices: Vec<String> = (1..=path.len()).map(|i| i.to_string()).collect();
+        writeln!(obj, "l {}", indices.join(" ")).map_err(|e| e.to_string())?;
+    }
+    Ok(obj)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::types::timed::SampleQuaternion;
+
+    #[test]
+    fn test_obj_path() {
+        let half_turn = std::f64::consts::FRAC_1_SQRT_2;
+        let samples = [
+            SampleQuaternion::new(0.0, [1.0, 0.0, 0.0, 0.0]),
+            SampleQuaternion::new(1.0, [half_turn, 0.0, 0.0, half_turn]),
+        ];
+        let obj = OrientationPath::from_samples(&samples).to_obj().unwrap();
+        let lines: Vec<&str> = obj.lines().collect();
+
+        assert_eq!(lines[2], "v 1 0 0");
+        let tip: Vec<f64> = lines[3][2..]
+            .split(' ')
+            .map(|v| v.parse().unwrap())
+            .collect();
+        assert!((tip[0]).abs() < 1e-9 && (tip[1] - 1.0).abs() < 1e-9);
+        assert_eq!(lines[4], "l 1 2");
+        assert!(OrientationPath::default().to_obj().is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use opencv::core::{Mat, Point2f, Point3f, Vector};
+use opencv::prelude::*;
+use opencv::{calib3d, objdetect, videoio};
+
+use super::{CameraIntrinsics, MarkerGroundTruth, MarkerPose};
+use imu_common::errors::ImuError;
+use imu_common::types::Clock;
+
+fn opencv_error(e: opencv::Error) -> ImuError {
+    ImuError::Other(format!("OpenCV error: {}", e))
+}
+
+/// Tracks a 4x4 ArUco marker (`DICT_4X4_50`) in the frames of a camera.
+pub struct ArucoTracker {
+    capture: videoio::VideoCapture,
+    detector: objdetect::ArucoDetector,
+    camera_matrix: Mat,
+    distortion: Mat,
+    object_points: Vector<Point3f>,
+    marker_id: i32,
+}
+
+impl ArucoTracker {
+    /// Opens camera `camera_index` to track marker `marker_id`, whose side is `marker_length`
+    /// long. Poses are returned in the units of `marker_length`.
+    pub fn open(
+        camera_index: i32,
+        intrinsics: &CameraIntrinsics,
+        marker_id: i32,
+        marker_length: f64,
+    ) -> Result<Self, ImuError> {
+        let capture =
+            videoio::VideoCapture::new(camera_index, videoio::CAP_ANY).map_err(opencv_error)?;
+        if !capture.is_opened().map_err(opencv_error)? {
+            return Err(ImuError::Io(format!("Can't open camera {}", camera_index)));
+        }
+        let dictionary =
+            objdetect::get_predefined_dictionary(objdetect::PredefinedDictionaryType::DICT_4X4_50)
+                .map_err(opencv_error)?;
+        let detector = objdetect::ArucoDetector::new(
+            &dictionary,
+            &objdetect::DetectorParameters::default().map_err(opencv_error)?,
+            objdetect::RefineParameters::new_def().map_err(opencv_error)?,
+        )
+        .map_err(opencv_error)?;
+        let camera_matrix = Mat::from_slice_2d(&[
+            [intrinsics.fx, 0.0, intrinsics.cx],
+            [0.0, intrinsics.fy, intrinsics.cy],
+            [0.0, 0.0, 1.0],
+        ])
+        .map_err(opencv_error)?;
+        let distortion = Mat::from_slice(&intrinsics.distortion)
+            .and_then(|distortion| distortion.try_clone())
+            .map_err(opencv_error)?;
+        // corners in the order detected, as required by SOLVEPNP_IPPE_SQUARE
+        let half = (marker_length / 2.0) as f32;
+        let object_points = Vector::from_slice(&[
+            Point3f::new(-half, half, 0.0),
+            Point3f::new(half, half, 0.0),
+            Point3f::new(half, -half, 0.0),
+            Point3f::new(-half, -half, 0.0),
+        ]);
+
+        Ok(Self {
+            capture,
+            detector,
+            camera_matrix,
+            distortion,
+            object_points,
+            marker_id,
+        })
+    }
+
+    /// Grabs a frame and returns the pose of the marker, or None if it isn't visible.
+    /// Returns an error if the camera is closed.
+    pub fn next_pose(&mut self) -> Result<Option<MarkerPose>, ImuError> {
+        let mut frame = Mat::default();
+        if !self.capture.read(&mut frame).map_err(opencv_error)? || frame.empty() {
+            return Err(ImuError::Io("Camera closed".to_string()));
+        }
+        let timestamp_secs = Clock::now().as_secs();
+
+        let mut corners = Vector::<Vector<Point2f>>::new();
+        let mut ids = Vector::<i32>::new();
+        let mut rejected = Vector::<Vector<Point2f>>::new();
+        self.detector
+            .detect_markers(&frame, &mut corners, &mut ids, &mut rejected)
+            .map_err(opencv_error)?;
+        let Some(index) = ids.iter().position(|id| id == self.marker_id) else {
+            return Ok(None);
+        };
+        let image_points = corners.get(index).map_err(opencv_error)?;
+
+        let mut rvec = Mat::default();
+        let mut tvec = Mat::default();
+        let found = calib3d::solve_pnp(
+            &self.object_points,
+            &image_points,
+            &self.camera_matrix,
+            &self.distortion,
+            &mut rvec,
+            &mut tvec,
+            false,
+            calib3d::SOLVEPNP_IPPE_SQUARE,
+        )
+        .map_err(opencv_error)?;
+        if !found {
+            return Ok(None);
+        }
+        Ok(Some(MarkerPose {
+            timestamp_secs,
+            rotation: to_array(&rvec)?,
+            translation: to_array(&tvec)?,
+        }))
+    }
+
+    /// Publishes the orientations tracked with `ground_truth` until the camera is closed or
+    /// `is_running` returns false.
+    pub fn run<F: Fn() -> bool>(
+        &mut self,
+        ground_truth: &MarkerGroundTruth,
+        is_running: F,
+    ) -> Result<(), ImuError> {
+        while is_running() {
+            if let Some(pose) = self.next_pose()? {
+                ground_truth.process_poses(&[pose]);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_array(vector: &Mat) -> Result<[f64; 3], ImuError> {
+    let mut array = [0.0; 3];
+    for (i, value) in array.iter_mut().enumerate() {
+        *value = *vector.at::<f64>(i as i32).map_err(opencv_error)?;
+    }
+    Ok(array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_missing_camera() {
+        let intrinsics = CameraIntrinsics::approximate(640.0, 480.0);
+        assert!(ArucoTracker::open(99, &intrinsics, 0, 0.05).is_err());
+    }
+
+    #[test]
+    fn test_to_array() {
+        let vector = Mat::from_slice(&[1.0f64, 2.0, 3.0])
+            .and_then(|vector| vector.try_clone())
+            .unwrap();
+        assert_eq!(to_array(&vector).unwrap(), [1.0, 2.0, 3.0]);
+    }
+}
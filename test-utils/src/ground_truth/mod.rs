@@ -0,0 +1,228 @@
+//! Module ground_truth
+//!
+//! Orientation ground truth from a camera tracking a fiducial marker glued to the device, to
+//! validate an AHRS against real motion. Marker poses, as estimated by OpenCV, are converted to
+//! orientations and published by [`MarkerGroundTruth`] through the same `IMUSource` interface
+//! as an `AHRSFilter`, so both streams can be resampled and compared directly.
+//!
+//! Tracking ArUco markers with a webcam requires the `aruco` feature, and OpenCV 4.7 or
+//! later with its development files. The `aruco_ground_truth` tool prints the orientation
+//! stream of a webcam as CSV.
+
+#[cfg(feature = "aruco")]
+mod aruco;
+
+#[cfg(feature = "aruco")]
+pub use aruco::ArucoTracker;
+
+use nalgebra::{UnitQuaternion as NUnitQuaternion, Vector3};
+use publisher::PublisherManager;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSource, Notifiable};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::SampleQuaternion;
+use imu_common::types::untimed::UnitQuaternion;
+
+/// Intrinsic parameters of a calibrated camera, as returned by `cv::calibrateCamera`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraIntrinsics {
+    pub fx: f64,
+    pub fy: f64,
+    pub cx: f64,
+    pub cy: f64,
+    /// Distortion coefficients `k1, k2, p1, p2, k3`.
+    pub distortion: [f64; 5],
+}
+
+impl CameraIntrinsics {
+    /// Returns rough intrinsics of an uncalibrated webcam with a ~60° horizontal field of view
+    /// and no distortion. Good enough to check a setup, but calibrate the camera for accurate
+    /// ground truth.
+    pub fn approximate(width: f64, height: f64) -> Self {
+        let focal_length = width / (2.0 * (30f64).to_radians().tan());
+        Self {
+            fx: focal_length,
+            fy: focal_length,
+            cx: width / 2.0,
+            cy: height / 2.0,
+            distortion: [0.0; 5],
+        }
+    }
+}
+
+/// Pose of a marker in the camera frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarkerPose {
+    pub timestamp_secs: f64,
+    /// Rotation from the marker frame to the camera frame, as a Rodrigues vector.
+    pub rotation: [f64; 3],
+    /// Position of the marker in the camera frame, in the units of the marker length.
+    pub translation: [f64; 3],
+}
+
+/// Source publishing the orientation of the device carrying a tracked marker.
+///
+/// Orientations are relative to the first pose received, or to the one following a call to
+/// `reset_reference`, so they can be compared with the AHRS orientation relative to the same
+/// instant. The camera must stay still during a session.
+pub struct MarkerGroundTruth {
+    tag: String,
+    sensor_type: SensorType,
+    publishers: PublisherManager<SensorReadings<SampleQuaternion>, SensorType>,
+    alignment: NUnitQuaternion<f64>,
+    reference: Mutex<Option<NUnitQuaternion<f64>>>,
+}
+
+impl MarkerGroundTruth {
+    /// Creates a source publishing orientations as `sensor_type`.
+    /// Returns an error if `sensor_type` is already published by another source.
+    pub fn new(tag: &str, sensor_type: SensorType) -> Result<Self, ImuError> {
+        let publishers = PublisherManager::try_new(std::slice::from_ref(&sensor_type))?;
+        Ok(Self {
+            tag: tag.to_string(),
+            sensor_type,
+            publishers,
+            alignment: NUnitQuaternion::identity(),
+            reference: Mutex::new(None),
+        })
+    }
+
+    /// Sets the rotation from the IMU axes to the marker axes, when the marker isn't glued
+    /// with its axes matching the ones of the IMU.
+    pub fn with_alignment(mut self, alignment: UnitQuaternion) -> Self {
+        self.alignment = alignment.inner();
+        self
+    }
+
+    /// Makes the next pose the reference orientation.
+    pub fn reset_reference(&self) {
+        *self.reference.lock().unwrap() = None;
+    }
+
+    /// Returns the orientation of the device at `pose`, relative to the reference pose.
+    pub fn orientation(&self, pose: &MarkerPose) -> UnitQuaternion {
+        let marker = NUnitQuaternion::from_scaled_axis(Vector3::from(pose.rotation));
+        let device = marker * self.alignment;
+        let reference = *self.reference.lock().unwrap().get_or_insert(device);
+        UnitQuaternion::from_unit_quaternion(reference.inverse() * device)
+    }
+
+    /// Publishes the orientations of `poses`.
+    pub fn process_poses(&self, poses: &[MarkerPose]) {
+        if poses.is_empty() {
+            return;
+        }
+        let samples = poses
+            .iter()
+            .map(|pose| {
+                SampleQuaternion::from_unit_quaternion(pose.timestamp_secs, self.orientation(pose))
+            })
+            .collect();
+        let readings = SensorReadings::from_vec(&self.tag, self.sensor_type.clone(), samples);
+        self.publishers
+            .notify_listeners(self.sensor_type.clone(), Arc::new(readings));
+    }
+}
+
+impl IMUSource<SensorReadings<SampleQuaternion>, SampleQuaternion> for MarkerGroundTruth {
+    fn get_tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        vec![self.sensor_type.clone()]
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<SampleQuaternion>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(
+        &self,
+        sensor_type: SensorType,
+        data: Arc<SensorReadings<SampleQuaternion>>,
+    ) {
+        self.publishers.notify_listeners(sensor_type, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::traits::IMUSample;
+    use publisher::Listener;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn pose(timestamp_secs: f64, rotation: [f64; 3]) -> MarkerPose {
+        MarkerPose {
+            timestamp_secs,
+            rotation,
+            translation: [0.0, 0.0, 0.5],
+        }
+    }
+
+    #[test]
+    fn test_relative_orientation() {
+        let sensor_type = SensorType::Other(Uuid::new_v4(), "Ground Truth".to_string());
+        let ground_truth = MarkerGroundTruth::new("Camera", sensor_type).unwrap();
+
+        // marker facing the camera, then turned 90° around its normal
+        let first = ground_truth.orientation(&pose(0.0, [std::f64::consts::PI, 0.0, 0.0]));
+        assert!(first.inner().angle() < 1e-12);
+        let turned =
+            NUnitQuaternion::from_scaled_axis(Vector3::new(std::f64::consts::PI, 0.0, 0.0))
+                * NUnitQuaternion::from_scaled_axis(Vector3::new(0.0, 0.0, FRAC_PI_2));
+        let orientation = ground_truth.orientation(&pose(0.1, turned.scaled_axis().into()));
+        let expected = NUnitQuaternion::from_scaled_axis(Vector3::new(0.0, 0.0, FRAC_PI_2));
+        assert!(orientation.inner().angle_to(&expected) < 1e-9);
+
+        ground_truth.reset_reference();
+        let orientation = ground_truth.orientation(&pose(0.2, turned.scaled_axis().into()));
+        assert!(orientation.inner().angle() < 1e-12);
+    }
+
+    #[test]
+    fn test_publish_orientations() {
+        let sensor_type = SensorType::Other(Uuid::new_v4(), "Ground Truth".to_string());
+        let alignment = UnitQuaternion::from_unit_quaternion(NUnitQuaternion::from_euler_angles(
+            0.0, 0.0, FRAC_PI_2,
+        ));
+        let ground_truth = MarkerGroundTruth::new("Camera", sensor_type.clone())
+            .unwrap()
+            .with_alignment(alignment.clone());
+        assert!(MarkerGroundTruth::new("Camera", sensor_type.clone()).is_err());
+
+        let samples: Arc<Mutex<Vec<SampleQuaternion>>> = Arc::new(Mutex::new(Vec::new()));
+        let mut listener = Listener::new({
+            let samples = samples.clone();
+            move |_id, readings: Arc<SensorReadings<SampleQuaternion>>| {
+                samples.lock().unwrap().extend(readings.get_samples());
+            }
+        });
+        ground_truth
+            .register_listener(&mut listener, &sensor_type)
+            .unwrap();
+
+        ground_truth.process_poses(&[pose(1.0, [0.0, 0.0, 0.0]), pose(1.1, [0.0, 0.3, 0.0])]);
+
+        let samples = samples.lock().unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].get_timestamp_secs(), 1.1);
+        // rotations of the marker are seen in the axes of the device
+        let expected = alignment.inner().inverse()
+            * NUnitQuaternion::from_scaled_axis(Vector3::new(0.0, 0.3, 0.0))
+            * alignment.inner();
+        assert!(samples[1].get_measurement().inner().angle_to(&expected) < 1e-9);
+    }
+}
@@ -0,0 +1,270 @@
+//! Module harness
+//!
+//! Runs a pipeline on simulated time. The harness owns a `VirtualClock` and a list of stages,
+//! e.g. a `VirtualSource` generating samples and an offline resampler, that are run in order
+//! every time the clock is stepped. Every stage notifies its listeners before returning, so
+//! the readings emitted up to a step can be asserted right after it, without sleeping.
+
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSource, Notifiable};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::{ClockSource, VirtualClock};
+use publisher::delivery::Inline;
+use publisher::{Listener, PublisherManager};
+
+type Stage = Box<dyn FnMut(f64) + Send>;
+type Signal<S> = Arc<dyn Fn(&SensorType, f64) -> <S as IMUSample>::Untimed + Send + Sync>;
+
+/// Steps a `VirtualClock` and the stages of a pipeline.
+pub struct VirtualPipelineHarness {
+    clock: VirtualClock,
+    start_secs: f64,
+    step_secs: f64,
+    n_steps: usize,
+    stages: Vec<Stage>,
+}
+
+impl VirtualPipelineHarness {
+    /// Starts the clock at `start_secs`, advancing it `step_millis` every step.
+    pub fn new(start_secs: f64, step_millis: f64) -> Self {
+        Self {
+            clock: VirtualClock::new(start_secs),
+            start_secs,
+            step_secs: step_millis / 1000.0,
+            n_steps: 0,
+            stages: Vec::new(),
+        }
+    }
+
+    /// Returns the clock of the harness, to be shared with the pipeline.
+    pub fn get_clock(&self) -> VirtualClock {
+        self.clock.clone()
+    }
+
+    pub fn now_secs(&self) -> f64 {
+        self.clock.now_secs()
+    }
+
+    /// Adds a stage run with the current time on every step, after the stages added before.
+    pub fn add_stage<F>(&mut self, stage: F)
+    where
+        F: FnMut(f64) + Send + 'static,
+    {
+        self.stages.push(Box::new(stage));
+    }
+
+    /// Advances the clock one step and runs every stage.
+    pub fn step(&mut self) {
+        self.n_steps += 1;
+        // steps are counted from the start, so the clock doesn't drift
+        let timestamp_secs = self.start_secs + self.n_steps as f64 * self.step_secs;
+        self.clock.set(timestamp_secs);
+        for stage in self.stages.iter_mut() {
+            stage(timestamp_secs);
+        }
+    }
+
+    /// Steps the clock until `duration_millis` have elapsed.
+    pub fn run_for_millis(&mut self, duration_millis: f64) {
+        let n_steps = (duration_millis / 1000.0 / self.step_secs).round() as usize;
+        for _ in 0..n_steps {
+            self.step();
+        }
+    }
+
+    /// Records the readings published by `source` for `sensor_type`.
+    pub fn capture<S>(
+        &self,
+        source: &dyn IMUSource<SensorReadings<S>, S>,
+        sensor_type: &SensorType,
+    ) -> Result<Captured<S>, ImuError>
+    where
+        S: IMUSample,
+    {
+        let captured = Captured {
+            samples: Arc::new(Mutex::new(Vec::new())),
+        };
+        let mut listener = Listener::new({
+            let samples = captured.samples.clone();
+            move |_id, readings: Arc<SensorReadings<S>>| {
+                samples.lock().unwrap().extend(readings.get_samples());
+            }
+        });
+        source.register_listener(&mut listener, sensor_type)?;
+        Ok(captured)
+    }
+}
+
+/// Samples recorded by `VirtualPipelineHarness::capture`.
+#[derive(Clone)]
+pub struct Captured<S> {
+    samples: Arc<Mutex<Vec<S>>>,
+}
+
+impl<S> Captured<S>
+where
+    S: IMUSample,
+{
+    pub fn get_samples(&self) -> Vec<S> {
+        self.samples.lock().unwrap().clone()
+    }
+
+    pub fn get_timestamps(&self) -> Vec<f64> {
+        self.samples
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|sample| sample.get_timestamp_secs())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.samples.lock().unwrap().clear();
+    }
+}
+
+/// Source publishing a sample of `signal` every sampling period, for every sensor of its
+/// cluster. Listeners are notified on the thread calling `emit_until`.
+pub struct VirtualSource<S>
+where
+    S: IMUSample,
+{
+    tag: String,
+    sensor_cluster: Vec<SensorType>,
+    publishers: PublisherManager<SensorReadings<S>, SensorType, Inline>,
+    sampling_period_secs: f64,
+    signal: Signal<S>,
+    // time of the first sample, and number of samples published since
+    timing: Mutex<(Option<f64>, usize)>,
+}
+
+impl<S> VirtualSource<S>
+where
+    S: IMUSample,
+{
+    /// Creates a source sampling `signal(sensor_type, timestamp_secs)` every
+    /// `sampling_period_millis`.
+    pub fn new<F>(
+        tag: &str,
+        sensor_cluster: Vec<SensorType>,
+        sampling_period_millis: f64,
+        signal: F,
+    ) -> Self
+    where
+        F: Fn(&SensorType, f64) -> S::Untimed + Send + Sync + 'static,
+    {
+        Self {
+            tag: tag.to_string(),
+            publishers: PublisherManager::with_delivery(&sensor_cluster, Inline),
+            sensor_cluster,
+            sampling_period_secs: sampling_period_millis / 1000.0,
+            signal: Arc::new(signal),
+            timing: Mutex::new((None, 0)),
+        }
+    }
+
+    /// Publishes the samples due up to `timestamp_secs`, one batch per sensor. The first call
+    /// samples once at `timestamp_secs`.
+    pub fn emit_until(&self, timestamp_secs: f64) {
+        let mut timing = self.timing.lock().unwrap();
+        let start_secs = *timing.0.get_or_insert(timestamp_secs);
+        let mut timestamps = Vec::new();
+        loop {
+            let sample_secs = start_secs + timing.1 as f64 * self.sampling_period_secs;
+            if sample_secs > timestamp_secs {
+                break;
+            }
+            timestamps.push(sample_secs);
+            timing.1 += 1;
+        }
+        drop(timing);
+        if timestamps.is_empty() {
+            return;
+        }
+        for sensor_type in self.sensor_cluster.iter() {
+            let samples = timestamps
+                .iter()
+                .map(|&timestamp| {
+                    S::from_measurement(timestamp, (self.signal)(sensor_type, timestamp))
+                })
+                .collect();
+            let readings = SensorReadings::from_vec(&self.tag, sensor_type.clone(), samples);
+            self.publishers
+                .notify_listeners(sensor_type.clone(), Arc::new(readings));
+        }
+    }
+}
+
+impl<S> IMUSource<SensorReadings<S>, S> for VirtualSource<S>
+where
+    S: IMUSample,
+{
+    fn get_tag(&self) -> &str {
+        self.tag.as_str()
+    }
+
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.publishers.get_available_publisher_types()
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<S>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<S>>) {
+        self.publishers.notify_listeners(sensor_type, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::types::timed::Sample3D;
+
+    #[test]
+    fn test_virtual_source() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_virtual_source");
+        let source = Arc::new(VirtualSource::<Sample3D>::new(
+            "test",
+            sensor_cluster.clone(),
+            125.0,
+            |_sensor, timestamp| [timestamp, 0.0, 0.0].into(),
+        ));
+        let mut harness = VirtualPipelineHarness::new(100.0, 250.0);
+        let captured = harness.capture(&*source, &sensor_cluster[0]).unwrap();
+        harness.add_stage({
+            let source = source.clone();
+            move |timestamp| source.emit_until(timestamp)
+        });
+
+        harness.run_for_millis(1000.0);
+
+        assert_eq!(harness.now_secs(), 101.0);
+        // first sample at the first step, then every 125 ms
+        assert_eq!(
+            captured.get_timestamps(),
+            vec![100.25, 100.375, 100.5, 100.625, 100.75, 100.875, 101.0]
+        );
+        let samples = captured.get_samples();
+        assert_eq!(samples[6].get_measurement(), [101.0, 0.0, 0.0].into());
+    }
+}
@@ -1,3 +1,7 @@
 pub mod csv_loader;
+pub mod export;
+pub mod ground_truth;
+pub mod harness;
+pub mod quality_report;
 pub mod renderable;
 pub mod sinks;
@@ -0,0 +1,304 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Write;
+
+use crate::csv_loader::{self, CsvColumnMapper};
+use imu_common::traits::{IMUSample, VecF64Convertible};
+use imu_common::types::timed::Sample3D;
+
+const DEFAULT_GAP_FACTOR: f64 = 2.0;
+const DEFAULT_CLIP_MARGIN: f64 = 0.01;
+
+type ColumnSelector = fn(&mut CsvColumnMapper) -> &mut CsvColumnMapper;
+
+/// Parameters used when scanning a recording.
+#[derive(Clone, Debug)]
+pub struct QualityConfig {
+    /// Multiplier converting recorded timestamps to seconds (e.g. 1e-3 for milliseconds).
+    pub timestamp_scale: f64,
+    /// A gap is reported when the interval between samples exceeds `gap_factor` times the median period.
+    pub gap_factor: f64,
+    /// Full scale range per sensor name. Samples within `clip_margin` of the range are flagged as clipped.
+    pub range_limits: HashMap<String, f64>,
+    pub clip_margin: f64,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            timestamp_scale: 1.0,
+            gap_factor: DEFAULT_GAP_FACTOR,
+            range_limits: HashMap::new(),
+            clip_margin: DEFAULT_CLIP_MARGIN,
+        }
+    }
+}
+
+impl QualityConfig {
+    pub fn with_range_limit(mut self, sensor: &str, limit: f64) -> Self {
+        self.range_limits.insert(sensor.to_string(), limit.abs());
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Gap {
+    pub start_secs: f64,
+    pub duration_secs: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SensorQuality {
+    pub sensor: String,
+    pub n_samples: usize,
+    pub rate_hz: f64,
+    pub median_period_secs: f64,
+    /// Standard deviation of the sampling period relative to its mean.
+    pub period_jitter: f64,
+    pub gaps: Vec<Gap>,
+    pub n_clipped: usize,
+    /// Per axis white noise estimate, computed from the spread of consecutive differences.
+    pub noise_stdev: Vec<f64>,
+    pub n_duplicate_timestamps: usize,
+    pub n_non_monotonic_timestamps: usize,
+}
+
+/// Quality summary of a recording, one entry per sensor.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct QualityReport {
+    pub sensors: Vec<SensorQuality>,
+}
+
+impl QualityReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scans a recording with the `csv_loader` column layout (timestamp, gyro, accel, mag).
+    pub fn from_csv(file_path: &str, config: &QualityConfig) -> Result<Self, Box<dyn Error>> {
+        let mut report = Self::new();
+        let sensors: [(&str, ColumnSelector); 3] = [
+            ("gyroscope", CsvColumnMapper::add_gyro),
+            ("accelerometer", CsvColumnMapper::add_accel),
+            ("magnetometer", CsvColumnMapper::add_mag),
+        ];
+        for (name, add_columns) in sensors {
+            let mut mapper = CsvColumnMapper::new();
+            add_columns(mapper.add_timestamp());
+            let samples = csv_loader::load_csv_columns::<Sample3D>(file_path, &mapper.columns())?;
+            report.add_sensor(name, &samples, config);
+        }
+        Ok(report)
+    }
+
+    /// Scans the samples of a single sensor and appends the result to the report.
+    pub fn add_sensor<T>(&mut self, sensor: &str, samples: &[T], config: &QualityConfig)
+    where
+        T: IMUSample,
+        T::Untimed: VecF64Convertible,
+    {
+        let timestamps: Vec<f64> = samples
+            .iter()
+            .map(|s| s.get_timestamp_secs() * config.timestamp_scale)
+            .collect();
+        let measurements: Vec<Vec<f64>> =
+            samples.iter().map(|s| s.get_measurement().into()).collect();
+
+        let deltas: Vec<f64> = timestamps.windows(2).map(|w| w[1] - w[0]).collect();
+        let n_duplicate_timestamps = deltas.iter().filter(|&&dt| dt == 0.0).count();
+        let n_non_monotonic_timestamps = deltas.iter().filter(|&&dt| dt < 0.0).count();
+        let periods: Vec<f64> = deltas.iter().copied().filter(|&dt| dt > 0.0).collect();
+
+        let median_period_secs = median(&periods);
+        let mean_period = mean(&periods);
+        let period_jitter = if mean_period > 0.0 {
+            stdev(&periods) / mean_period
+        } else {
+            0.0
+        };
+        let rate_hz = if median_period_secs > 0.0 {
+            1.0 / median_period_secs
+        } else {
+            0.0
+        };
+
+        let gaps = timestamps
+            .windows(2)
+            .filter(|w| {
+                median_period_secs > 0.0 && w[1] - w[0] > config.gap_factor * median_period_secs
+            })
+            .map(|w| Gap {
+                start_secs: w[0],
+                duration_secs: w[1] - w[0],
+            })
+            .collect();
+
+        let n_clipped = match config.range_limits.get(sensor) {
+            Some(limit) => {
+                let threshold = limit * (1.0 - config.clip_margin);
+                measurements
+                    .iter()
+                    .filter(|m| m.iter().any(|v| v.abs() >= threshold))
+                    .count()
+            }
+            None => 0,
+        };
+
+        let n_axis = measurements.first().map(|m| m.len()).unwrap_or(0);
+        let noise_stdev = (0..n_axis)
+            .map(|axis| {
+                let diffs: Vec<f64> = measurements
+                    .windows(2)
+                    .map(|w| w[1][axis] - w[0][axis])
+                    .collect();
+                stdev(&diffs) / std::f64::consts::SQRT_2
+            })
+            .collect();
+
+        self.sensors.push(SensorQuality {
+            sensor: sensor.to_string(),
+            n_samples: samples.len(),
+            rate_hz,
+            median_period_secs,
+            period_jitter,
+            gaps,
+            n_clipped,
+            noise_stdev,
+            n_duplicate_timestamps,
+            n_non_monotonic_timestamps,
+        });
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Data quality report\n\n");
+        out.push_str("| Sensor | Samples | Rate (Hz) | Jitter | Gaps | Clipped | Noise | Dup. ts | Non-monotonic ts |\n");
+        out.push_str("|---|---|---|---|---|---|---|---|---|\n");
+        for s in &self.sensors {
+            let noise = s
+                .noise_stdev
+                .iter()
+                .map(|n| format!("{:.4}", n))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = writeln!(
+                out,
+                "| {} | {} | {:.2} | {:.2}% | {} | {} | {} | {} | {} |",
+                s.sensor,
+                s.n_samples,
+                s.rate_hz,
+                s.period_jitter * 100.0,
+                s.gaps.len(),
+                s.n_clipped,
+                noise,
+                s.n_duplicate_timestamps,
+                s.n_non_monotonic_timestamps
+            );
+        }
+
+        for s in self.sensors.iter().filter(|s| !s.gaps.is_empty()) {
+            let _ = writeln!(out, "\n## Gaps in {}\n", s.sensor);
+            for gap in &s.gaps {
+                let _ = writeln!(
+                    out,
+                    "- {:.3} s: {:.3} s without samples",
+                    gap.start_secs, gap.duration_secs
+                );
+            }
+        }
+        out
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn stdev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(values);
+    let var = values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    var.sqrt()
+}
+
+fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::types::timed::SampleScalar;
+
+    #[test]
+    fn test_rate_and_gaps() {
+        let mut samples: Vec<SampleScalar> = (0..10)
+            .map(|i| SampleScalar::new(i as f64 * 0.01, 1.0))
+            .collect();
+        samples.push(SampleScalar::new(0.2, 1.0));
+        let mut report = QualityReport::new();
+        report.add_sensor("scalar", &samples, &QualityConfig::default());
+
+        let quality = &report.sensors[0];
+        assert!((quality.rate_hz - 100.0).abs() < 1e-6);
+        assert_eq!(
+            quality.gaps,
+            vec![Gap {
+                start_secs: 0.09,
+                duration_secs: 0.2 - 0.09
+            }]
+        );
+        assert_eq!(quality.noise_stdev, vec![0.0]);
+    }
+
+    #[test]
+    fn test_clipping_and_timestamp_anomalies() {
+        let samples = vec![
+            Sample3D::new(0.0, [0.0, 0.0, 19.6]),
+            Sample3D::new(0.0, [0.0, 0.0, 9.8]),
+            Sample3D::new(0.1, [-19.6, 0.0, 9.8]),
+            Sample3D::new(0.05, [0.0, 0.0, 9.8]),
+        ];
+        let config = QualityConfig::default().with_range_limit("accelerometer", 19.6);
+        let mut report = QualityReport::new();
+        report.add_sensor("accelerometer", &samples, &config);
+
+        let quality = &report.sensors[0];
+        assert_eq!(quality.n_clipped, 2);
+        assert_eq!(quality.n_duplicate_timestamps, 1);
+        assert_eq!(quality.n_non_monotonic_timestamps, 1);
+    }
+
+    #[test]
+    fn test_report_from_csv() {
+        let config = QualityConfig {
+            timestamp_scale: 1e-3,
+            ..Default::default()
+        };
+        let report = QualityReport::from_csv("./test_data/sensor_readings.csv", &config).unwrap();
+
+        assert_eq!(report.sensors.len(), 3);
+        assert!(report.to_markdown().contains("| accelerometer |"));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["sensors"][0]["sensor"], "gyroscope");
+    }
+}
@@ -0,0 +1,316 @@
+use nalgebra::{Quaternion, UnitQuaternion, Vector3};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+
+use super::{Renderable3D, RigidBody};
+
+const STL_HEADER_LEN: usize = 80;
+const STL_TRIANGLE_LEN: usize = 50;
+
+/// Wireframe model loaded from an OBJ or STL file.
+///
+/// Faces are rendered as their edges. Models are usually expressed in arbitrary units and
+/// origins, so `normalized` can be used to center them and scale them to the size of `Box3D`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh3D {
+    vertices: Vec<Vector3<f64>>,
+    edges: Vec<(usize, usize)>,
+}
+
+impl Mesh3D {
+    /// Creates a mesh, checking that every edge references an existing vertex.
+    pub fn new(vertices: Vec<Vector3<f64>>, edges: Vec<(usize, usize)>) -> Result<Self, String> {
+        if vertices.is_empty() {
+            return Err("Mesh has no vertices".to_string());
+        }
+        if let Some(edge) = edges
+            .iter()
+            .find(|&&(i, j)| i >= vertices.len() || j >= vertices.len())
+        {
+            return Err(format!("Edge {:?} references a missing vertex", edge));
+        }
+        Ok(Self { vertices, edges })
+    }
+
+    /// Loads an OBJ or STL file, depending on its extension.
+    pub fn from_file(file_path: &str) -> Result<Self, String> {
+        let extension = file_path
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_lowercase());
+        match extension.as_deref() {
+            Some("obj") => Self::from_obj(file_path),
+            Some("stl") => Self::from_stl(file_path),
+            _ => Err(format!("Unsupported mesh file {}", file_path)),
+        }
+    }
+
+    pub fn from_obj(file_path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(file_path).map_err(|e| e.to_string())?;
+        Self::from_obj_str(&content)
+    }
+
+    /// Parses the vertices (`v`), faces (`f`) and lines (`l`) of an OBJ document. Other
+    /// statements (normals, textures, materials, groups) are ignored.
+    pub fn from_obj_str(content: &str) -> Result<Self, String> {
+        let mut vertices = Vec::new();
+        let mut edges = BTreeSet::new();
+        for (line_number, line) in content.lines().enumerate() {
+            let mut tokens = line.split_whitespace();
+            let error = |e: String| format!("Line {}: {}", line_number + 1, e);
+            match tokens.next() {
+                Some("v") => {
+                    let coords = tokens
+                        .take(3)
+                        .map(|t| t.parse::<f64>().map_err(|e| error(e.to_string())))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if coords.len() != 3 {
+                        return Err(error("Vertex with less than 3 coordinates".to_string()));
+                    }
+                    vertices.push(Vector3::new(coords[0], coords[1], coords[2]));
+                }
+                Some(statement @ ("f" | "l")) => {
+                    let indices = tokens
+                        .map(|t| obj_index(t, vertices.len()).map_err(error))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    add_polyline(&mut edges, &indices, statement == "f");
+                }
+                _ => {}
+            }
+        }
+        Self::new(vertices, edges.into_iter().collect())
+    }
+
+    pub fn from_stl(file_path: &str) -> Result<Self, String> {
+        let content = fs::read(file_path).map_err(|e| e.to_string())?;
+        Self::from_stl_bytes(&content)
+    }
+
+    /// Parses an ASCII or binary STL document.
+    pub fn from_stl_bytes(content: &[u8]) -> Result<Self, String> {
+        let triangles = if is_binary_stl(content) {
+            parse_binary_stl(content)?
+        } else {
+            let content = std::str::from_utf8(content).map_err(|e| e.to_string())?;
+            parse_ascii_stl(content)?
+        };
+
+        // STL repeats shared vertices in every triangle
+        let mut vertices = Vec::new();
+        let mut index_by_vertex = HashMap::new();
+        let mut edges = BTreeSet::new();
+        for triangle in triangles {
+            let indices: Vec<usize> = triangle
+                .iter()
+                .map(|v| {
+                    *index_by_vertex
+                        .entry([v.x.to_bits(), v.y.to_bits(), v.z.to_bits()])
+                        .or_insert_with(|| {
+                            vertices.push(*v);
+                            vertices.len() - 1
+                        })
+                })
+                .collect();
+            add_polyline(&mut edges, &indices, true);
+        }
+        Self::new(vertices, edges.into_iter().collect())
+    }
+
+    /// Returns the mesh centered at the origin and scaled so its largest dimension is 1.
+    pub fn normalized(&self) -> Self {
+        let mut min = self.vertices[0];
+        let mut max = self.vertices[0];
+        for v in &self.vertices {
+            min = min.inf(v);
+            max = max.sup(v);
+        }
+        let center = (min + max) / 2.0;
+        let size = (max - min).max();
+        let scale = if size > 0.0 { 1.0 / size } else { 1.0 };
+        Self {
+            vertices: self.vertices.iter().map(|v| (v - center) * scale).collect(),
+            edges: self.edges.clone(),
+        }
+    }
+
+    pub fn n_vertices(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn n_edges(&self) -> usize {
+        self.edges.len()
+    }
+}
+
+/// Resolves a 1-based (or negative, relative) OBJ index, ignoring texture and normal indices.
+fn obj_index(token: &str, n_vertices: usize) -> Result<usize, String> {
+    let index: i64 = token
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .parse()
+        .map_err(|_| format!("Invalid vertex index {}", token))?;
+    let index = if index < 0 {
+        n_vertices as i64 + index
+    } else {
+        index - 1
+    };
+    if index < 0 || index as usize >= n_vertices {
+        return Err(format!("Vertex index {} out of bounds", token));
+    }
+    Ok(index as usize)
+}
+
+fn add_polyline(edges: &mut BTreeSet<(usize, usize)>, indices: &[usize], closed: bool) {
+    let n = indices.len();
+    let n_edges = if closed && n > 2 {
+        n
+    } else {
+        n.saturating_sub(1)
+    };
+    for i in 0..n_edges {
+        let (a, b) = (indices[i], indices[(i + 1) % n]);
+        if a != b {
+            edges.insert((a.min(b), a.max(b)));
+        }
+    }
+}
+
+/// Binary files may also start with `solid`, so the expected length is checked first.
+fn is_binary_stl(content: &[u8]) -> bool {
+    if content.len() < STL_HEADER_LEN + 4 {
+        return false;
+    }
+    let count = u32::from_le_bytes(
+        content[STL_HEADER_LEN..STL_HEADER_LEN + 4]
+            .try_into()
+            .unwrap(),
+    ) as usize;
+    content.len() == STL_HEADER_LEN + 4 + count * STL_TRIANGLE_LEN || !content.starts_with(b"solid")
+}
+
+fn parse_binary_stl(content: &[u8]) -> Result<Vec<[Vector3<f64>; 3]>, String> {
+    let data = &content[STL_HEADER_LEN + 4..];
+    if !data.len().is_multiple_of(STL_TRIANGLE_LEN) {
+        return Err("Truncated binary STL".to_string());
+    }
+    let read_f32 = |bytes: &[u8], i: usize| {
+        f32::from_le_bytes(bytes[4 * i..4 * i + 4].try_into().unwrap()) as f64
+    };
+    Ok(data
+        .chunks(STL_TRIANGLE_LEN)
+        .map(|triangle| {
+            // skip the normal, stored first
+            let vertex = |i: usize| {
+                Vector3::new(
+                    read_f32(triangle, 3 + 3 * i),
+                    read_f32(triangle, 4 + 3 * i),
+                    read_f32(triangle, 5 + 3 * i),
+                )
+            };
+            [vertex(0), vertex(1), vertex(2)]
+        })
+        .collect())
+}
+
+fn parse_ascii_stl(content: &str) -> Result<Vec<[Vector3<f64>; 3]>, String> {
+    let mut vertices = Vec::new();
+    for line in content.lines() {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() == Some("vertex") {
+            let coords = tokens
+                .map(|t| t.parse::<f64>().map_err(|e| e.to_string()))
+                .collect::<Result<Vec<_>, _>>()?;
+            if coords.len() != 3 {
+                return Err(format!("Invalid STL vertex {}", line.trim()));
+            }
+            vertices.push(Vector3::new(coords[0], coords[1], coords[2]));
+        }
+    }
+    if !vertices.len().is_multiple_of(3) {
+        return Err("STL facet without 3 vertices".to_string());
+    }
+    Ok(vertices.chunks(3).map(|v| [v[0], v[1], v[2]]).collect())
+}
+
+impl Renderable3D for Mesh3D {
+    fn vertices(&self) -> Vec<(f64, f64, f64)> {
+        self.vertices.iter().map(|v| (v.x, v.y, v.z)).collect()
+    }
+
+    fn edges(&self) -> Vec<(usize, usize)> {
+        self.edges.clone()
+    }
+}
+
+impl RigidBody for Mesh3D {
+    fn rotate(&self, q: &Quaternion<f64>) -> Vec<(f64, f64, f64)> {
+        let q = UnitQuaternion::from_quaternion(*q);
+        self.vertices
+            .iter()
+            .map(|v| {
+                let v = q * v;
+                (v.x, v.y, v.z)
+            })
+            .collect()
+    }
+
+    fn translate(&self, t: &Vector3<f64>) -> Vec<(f64, f64, f64)> {
+        self.vertices
+            .iter()
+            .map(|v| {
+                let v = v + t;
+                (v.x, v.y, v.z)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE_OBJ: &str =
+        "# square\nv 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nvn 0 0 1\nf 1//1 2//1 3//1 4//1\n";
+
+    #[test]
+    fn test_mesh_from_obj() {
+        let mesh = Mesh3D::from_obj_str(SQUARE_OBJ).unwrap();
+        assert_eq!(mesh.n_vertices(), 4);
+        assert_eq!(mesh.edges(), vec![(0, 1), (0, 3), (1, 2), (2, 3)]);
+
+        let normalized = mesh.normalized();
+        assert_eq!(normalized.vertices()[2], (0.5, 0.5, 0.0));
+
+        assert!(Mesh3D::from_obj_str("v 0 0 0\nf 1 2 3\n").is_err());
+        assert!(Mesh3D::from_obj_str("v 0 0\n").is_err());
+    }
+
+    #[test]
+    fn test_mesh_from_stl() {
+        let ascii = "solid t\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 0 1 0\nendloop\nendfacet\n\
+            facet normal 0 0 1\nouter loop\nvertex 1 0 0\nvertex 1 1 0\nvertex 0 1 0\nendloop\nendfacet\nendsolid t\n";
+        let mesh = Mesh3D::from_stl_bytes(ascii.as_bytes()).unwrap();
+        assert_eq!(mesh.n_vertices(), 4);
+        assert_eq!(mesh.n_edges(), 5);
+
+        let mut binary = vec![0u8; STL_HEADER_LEN];
+        binary.extend(1u32.to_le_bytes());
+        for v in [
+            0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+        ] {
+            binary.extend(v.to_le_bytes());
+        }
+        binary.extend([0u8; 2]);
+        let mesh = Mesh3D::from_stl_bytes(&binary).unwrap();
+        assert_eq!(mesh.n_vertices(), 3);
+        assert_eq!(mesh.n_edges(), 3);
+    }
+
+    #[test]
+    fn test_mesh_rotate() {
+        let mesh = Mesh3D::from_obj_str(SQUARE_OBJ).unwrap();
+        let q = UnitQuaternion::from_euler_angles(0.0, 0.0, std::f64::consts::FRAC_PI_2);
+        let rotated = mesh.rotate(q.quaternion());
+        assert!((rotated[1].0).abs() < 1e-9 && (rotated[1].1 - 2.0).abs() < 1e-9);
+    }
+}
@@ -1,6 +1,8 @@
 pub mod box3d;
+pub mod mesh;
 
 pub use box3d::Box3D;
+pub use mesh::Mesh3D;
 
 pub trait Renderable3D {
     fn vertices(&self) -> Vec<(f64, f64, f64)>;
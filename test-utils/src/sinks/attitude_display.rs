@@ -0,0 +1,186 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::SampleQuaternion;
+use publisher::adapters;
+
+const DEFAULT_BAR_WIDTH: usize = 21;
+
+type Writer = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// Headless sink printing a textual roll/pitch/yaw readout, one line per update.
+///
+/// Every angle is shown in degrees next to a bar centered at zero, e.g.
+/// `roll   +45.0 [..........|#####.....]`. Output goes to stdout by default, and is
+/// limited to `rate_hz` lines per second so slow serial consoles can keep up.
+#[derive(Clone)]
+pub struct AttitudeDisplay {
+    min_interval: Duration,
+    bar_width: usize,
+    last_update: Arc<Mutex<Option<Instant>>>,
+    writer: Writer,
+}
+
+impl AttitudeDisplay {
+    /// Creates a display printing at most `rate_hz` lines per second. A non positive rate
+    /// prints every sample.
+    pub fn new(rate_hz: f64) -> Self {
+        let min_interval = if rate_hz > 0.0 {
+            Duration::from_secs_f64(1.0 / rate_hz)
+        } else {
+            Duration::ZERO
+        };
+        Self {
+            min_interval,
+            bar_width: DEFAULT_BAR_WIDTH,
+            last_update: Arc::new(Mutex::new(None)),
+            writer: Arc::new(Mutex::new(Box::new(io::stdout()))),
+        }
+    }
+
+    /// Prints to `writer` instead of stdout.
+    pub fn with_writer<W: Write + Send + 'static>(mut self, writer: W) -> Self {
+        self.writer = Arc::new(Mutex::new(Box::new(writer)));
+        self
+    }
+
+    /// Sets the width of each bar in characters. Widths are rounded up to an odd number, so
+    /// zero falls on the middle character.
+    pub fn with_bar_width(mut self, width: usize) -> Self {
+        self.bar_width = width.max(3) | 1;
+        self
+    }
+
+    /// Returns the readout line of `sample`.
+    pub fn render(&self, tag: &str, sample: &SampleQuaternion) -> String {
+        let (roll, pitch, yaw) = sample.get_measurement().inner().euler_angles();
+        // rounded before printing, so tiny negative angles aren't shown as -0.0
+        let degrees = |angle: f64| (angle.to_degrees() * 10.0).round() / 10.0 + 0.0;
+        let angles = [
+            ("roll", degrees(roll), 180.0),
+            ("pitch", degrees(pitch), 90.0),
+            ("yaw", degrees(yaw), 180.0),
+        ];
+        let bars: Vec<String> = angles
+            .iter()
+            .map(|(name, angle, range)| {
+                format!(
+                    "{:<5} {:>+6.1} [{}]",
+                    name,
+                    angle,
+                    render_bar(*angle, *range, self.bar_width)
+                )
+            })
+            .collect();
+        format!(
+            "{} {:>10.3}s  {}",
+            tag,
+            sample.get_timestamp_secs(),
+            bars.join("  ")
+        )
+    }
+
+    fn print(&self, tag: &str, sample: &SampleQuaternion) {
+        {
+            let mut last_update = self.last_update.lock().unwrap();
+            let now = Instant::now();
+            if last_update.is_some_and(|last| now.duration_since(last) < self.min_interval) {
+                return;
+            }
+            *last_update = Some(now);
+        }
+        let line = self.render(tag, sample);
+        let mut writer = self.writer.lock().unwrap();
+        let _ = writeln!(writer, "{}", line).and_then(|_| writer.flush());
+    }
+}
+
+/// Returns a bar of `width` characters filled from the center towards `value`, which is
+/// clamped to `[-range, range]`.
+fn render_bar(value: f64, range: f64, width: usize) -> String {
+    let center = width / 2;
+    let offset = (value.clamp(-range, range) / range * center as f64).round() as isize;
+    let position = (center as isize + offset) as usize;
+    let (from, to) = (position.min(center), position.max(center));
+    (0..width)
+        .map(|i| {
+            if i == center {
+                '|'
+            } else if (from..=to).contains(&i) {
+                '#'
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
+
+impl IMUSink<SensorReadings<SampleQuaternion>, SampleQuaternion> for AttitudeDisplay {
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<SensorReadings<SampleQuaternion>, SampleQuaternion>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<SensorReadings<SampleQuaternion>>) {
+        if let Some(sample) = samples.get_samples_ref().last() {
+            self.print(samples.get_sensor_tag(), sample);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn sample(timestamp: f64, roll_deg: f64) -> SampleQuaternion {
+        let q = nalgebra::UnitQuaternion::from_euler_angles(roll_deg.to_radians(), 0.0, 0.0);
+        SampleQuaternion::from_unit_quaternion(timestamp, q.into())
+    }
+
+    #[test]
+    fn test_render_bar() {
+        assert_eq!(render_bar(0.0, 90.0, 5), "..|..");
+        assert_eq!(render_bar(90.0, 90.0, 5), "..|##");
+        assert_eq!(render_bar(-45.0, 90.0, 5), ".#|..");
+        assert_eq!(render_bar(-500.0, 90.0, 5), "##|..");
+    }
+
+    #[test]
+    fn test_rate_limited_output() {
+        let buffer = SharedBuffer::default();
+        let display = AttitudeDisplay::new(1.0)
+            .with_writer(buffer.clone())
+            .with_bar_width(6);
+        let line = display.render("Test", &sample(1.0, 120.0));
+        assert!(line.contains("roll  +120.0 [...|##.]  pitch   +0.0 [...|...]"));
+
+        let acc = SensorType::Other(Uuid::new_v4(), "Orientation".to_string());
+        for i in 0..10 {
+            let readings =
+                SensorReadings::from_vec("Test", acc.clone(), vec![sample(i as f64, 0.0)]);
+            display.process_samples(Uuid::new_v4(), Arc::new(readings));
+        }
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(output.lines().count(), 1);
+    }
+}
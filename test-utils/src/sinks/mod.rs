@@ -1,7 +1,17 @@
+mod attitude_display;
+#[cfg(feature = "plot")]
 mod plot1d;
+#[cfg(feature = "plot")]
 mod plot3d;
 mod sink_mock;
+mod sonification;
+mod threshold_alarm;
 
+pub use attitude_display::AttitudeDisplay;
+#[cfg(feature = "plot")]
 pub use plot1d::Plot1D;
+#[cfg(feature = "plot")]
 pub use plot3d::Plot3D;
-pub use sink_mock::{MockValue, SinkMock};
+pub use sink_mock::{Delivery, MockValue, SinkMock};
+pub use sonification::{Oscillator, SonificationConfig, SonificationMode, SonificationSink, Tone};
+pub use threshold_alarm::{AlarmConfig, AlarmEvent, AlarmEventKind, ThresholdAlarm};
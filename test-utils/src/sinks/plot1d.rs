@@ -6,12 +6,13 @@ use std::thread;
 use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+use imu_common::errors::ImuError;
 use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
 use imu_common::types::buffers::CircularBuffer;
 use imu_common::types::clock::Clock;
 use imu_common::types::sensors::{SensorReadings, SensorType};
 use imu_common::types::timed::Sample3D;
-use publisher::{listener, Listener};
+use publisher::adapters;
 
 type PlotDataVec = (
     CircularBuffer<f64>,
@@ -151,18 +152,8 @@ impl IMUSink<SensorReadings<Sample3D>, Sample3D> for Plot1D {
         &self,
         source: &dyn IMUSource<SensorReadings<Sample3D>, Sample3D>,
         sensor_cluster: &[SensorType],
-    ) -> Result<Vec<Uuid>, String> {
-        let mut listener = listener!(self.process_samples);
-        let mut ids = Vec::with_capacity(sensor_cluster.len());
-        for sensor_type in sensor_cluster {
-            match source.register_listener(&mut listener, sensor_type) {
-                Ok(id) => {
-                    ids.push(id);
-                }
-                Err(e) => return Err(e),
-            }
-        }
-        Ok(ids)
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
     }
     fn detach_listener(
         &self,
@@ -174,7 +165,7 @@ impl IMUSink<SensorReadings<Sample3D>, Sample3D> for Plot1D {
 
     fn process_samples(&self, _listener_id: Uuid, samples: Arc<SensorReadings<Sample3D>>) {
         let sensor_type = samples.get_sensor_type();
-        if let Some(samples) = samples.get_samples().last() {
+        if let Some(samples) = samples.get_samples_ref().last() {
             let mut plot = self.0.lock().unwrap();
             let timestamp = samples.get_timestamp_secs();
             let [x_val, y_val, z_val] = samples.get_measurement().inner();
@@ -1,14 +1,22 @@
 use gnuplot::{AxesCommon, Color, Figure};
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+use imu_common::errors::ImuError;
 use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
 use imu_common::types::sensors::{SensorReadings, SensorType};
 use imu_common::types::timed::{Sample3D, SampleQuaternion};
-use publisher::{listener, Listener};
+use publisher::adapters;
 
 use crate::renderable::{Renderable3D, RigidBody};
 
+type Point3D = (f64, f64, f64);
+
+/// Fading trail color, from light grey (oldest) to red (newest).
+const TRAIL_OLDEST_RGB: (f64, f64, f64) = (221.0, 221.0, 221.0);
+const TRAIL_NEWEST_RGB: (f64, f64, f64) = (255.0, 0.0, 0.0);
+
 #[derive(Clone)]
 pub struct Plot3D<T>
 where
@@ -16,6 +24,8 @@ where
 {
     fg: Arc<Mutex<Figure>>,
     object_3d: T,
+    trail: Arc<Mutex<VecDeque<Point3D>>>,
+    trail_length: usize,
 }
 
 impl<T> Plot3D<T>
@@ -28,9 +38,34 @@ where
         Self {
             fg: Arc::new(Mutex::new(fg)),
             object_3d,
+            trail: Arc::new(Mutex::new(VecDeque::new())),
+            trail_length: 0,
         }
     }
 
+    /// Renders a fading trail with the last `length` positions of the object. When plotting
+    /// orientations, the trail follows the tip of the rotated x axis, so heading drift and
+    /// motion patterns are easy to spot.
+    pub fn with_trail(mut self, length: usize) -> Self {
+        self.trail_length = length;
+        self
+    }
+
+    fn push_trail(&self, point: Point3D) {
+        if self.trail_length == 0 {
+            return;
+        }
+        let mut trail = self.trail.lock().unwrap();
+        trail.push_back(point);
+        while trail.len() > self.trail_length {
+            trail.pop_front();
+        }
+    }
+
+    pub fn clear_trail(&self) {
+        self.trail.lock().unwrap().clear();
+    }
+
     fn clear_axes(&self) {
         let mut fg = self.fg.lock().unwrap();
         fg.clear_axes();
@@ -70,10 +105,34 @@ where
             );
         }
 
+        let trail = self.trail.lock().unwrap();
+        let n_segments = trail.len().saturating_sub(1);
+        for (i, (from, to)) in trail.iter().zip(trail.iter().skip(1)).enumerate() {
+            let color = fade_color((i + 1) as f64 / n_segments as f64);
+            ax.lines(
+                [from.0, to.0],
+                [from.1, to.1],
+                [from.2, to.2],
+                &[Color(color.as_str())],
+            );
+        }
+        drop(trail);
+
         fg.show_and_keep_running().unwrap();
     }
 }
 
+/// Returns the trail color of a segment, given its age (0.0 oldest, 1.0 newest).
+fn fade_color(age: f64) -> String {
+    let blend = |oldest: f64, newest: f64| (oldest + (newest - oldest) * age).round() as u8;
+    format!(
+        "#{:02X}{:02X}{:02X}",
+        blend(TRAIL_OLDEST_RGB.0, TRAIL_NEWEST_RGB.0),
+        blend(TRAIL_OLDEST_RGB.1, TRAIL_NEWEST_RGB.1),
+        blend(TRAIL_OLDEST_RGB.2, TRAIL_NEWEST_RGB.2)
+    )
+}
+
 impl<R> IMUSink<SensorReadings<SampleQuaternion>, SampleQuaternion> for Plot3D<R>
 where
     R: Renderable3D + RigidBody + Send + Sync + Clone + 'static,
@@ -82,18 +141,8 @@ where
         &self,
         source: &dyn IMUSource<SensorReadings<SampleQuaternion>, SampleQuaternion>,
         sensor_cluster: &[SensorType],
-    ) -> Result<Vec<Uuid>, String> {
-        let mut listener = listener!(self.process_samples);
-        let mut ids = Vec::with_capacity(sensor_cluster.len());
-        for sensor_type in sensor_cluster {
-            match source.register_listener(&mut listener, sensor_type) {
-                Ok(id) => {
-                    ids.push(id);
-                }
-                Err(e) => return Err(e),
-            }
-        }
-        Ok(ids)
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
     }
     fn detach_listener(
         &self,
@@ -104,8 +153,10 @@ where
     }
 
     fn process_samples(&self, _id: Uuid, samples: Arc<SensorReadings<SampleQuaternion>>) {
-        if let Some(q) = samples.get_samples().first() {
+        if let Some(q) = samples.get_samples_ref().first() {
             let q = q.get_measurement().inner();
+            let tip = q * nalgebra::Vector3::x();
+            self.push_trail((tip.x, tip.y, tip.z));
             let rotated_vertices = self.object_3d.rotate(&q);
             self.update(&rotated_vertices);
         }
@@ -120,18 +171,8 @@ where
         &self,
         source: &dyn IMUSource<SensorReadings<Sample3D>, Sample3D>,
         sensor_cluster: &[SensorType],
-    ) -> Result<Vec<Uuid>, String> {
-        let mut listener = listener!(self.process_samples);
-        let mut ids = Vec::with_capacity(sensor_cluster.len());
-        for sensor_type in sensor_cluster {
-            match source.register_listener(&mut listener, sensor_type) {
-                Ok(id) => {
-                    ids.push(id);
-                }
-                Err(e) => return Err(e),
-            }
-        }
-        Ok(ids)
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
     }
     fn detach_listener(
         &self,
@@ -142,8 +183,9 @@ where
     }
 
     fn process_samples(&self, _id: Uuid, samples: Arc<SensorReadings<Sample3D>>) {
-        if let Some(acc) = samples.get_samples().first() {
-            let acc = nalgebra::Vector3::from_vec(acc.get_measurement().inner().to_vec());
+        if let Some(acc) = samples.get_samples_ref().first() {
+            let acc = nalgebra::Vector3::from(acc.get_measurement());
+            self.push_trail((acc.x, acc.y, acc.z));
             let traslated_vertices = self.object_3d.translate(&acc);
             self.update(&traslated_vertices);
         }
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
-use imu_common::traits::{IMUSample, IMUSink, IMUSource};
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
 use imu_common::types::sensors::{SensorReadings, SensorType};
-use publisher::{listener, Listener};
+use publisher::listener;
 
 type MockAsyncCallback<T> =
     Arc<Option<Arc<dyn Fn(MockValue, SensorType, Arc<SensorReadings<T>>) + Send + Sync>>>;
@@ -21,11 +23,24 @@ impl Default for MockValue {
         MockValue::Int(0)
     }
 }
+/// Readings delivered to a `SinkMock`.
+#[derive(Clone, Debug)]
+pub struct Delivery<T> {
+    /// Position of the delivery among all the deliveries to the sink, starting at 0.
+    pub order: usize,
+    pub listener_id: Uuid,
+    pub sensor_type: SensorType,
+    /// Timestamps of the delivered samples.
+    pub timestamps: Vec<f64>,
+    pub readings: Arc<SensorReadings<T>>,
+}
+
 #[derive(Clone, Default)]
 pub struct SinkMock<T> {
     control: Arc<RwLock<HashMap<Uuid, SensorType>>>,
     callback: MockAsyncCallback<T>,
     value: MockValue,
+    deliveries: Arc<Mutex<Vec<Delivery<T>>>>,
 }
 
 impl<T> SinkMock<T>
@@ -37,6 +52,7 @@ where
             control: Arc::new(RwLock::new(HashMap::new())),
             callback: Arc::new(None),
             value: MockValue::default(),
+            deliveries: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -50,6 +66,74 @@ where
     pub fn set_value(&mut self, value: MockValue) {
         self.value = value;
     }
+
+    /// Returns every delivery received, in the order they were received.
+    pub fn get_deliveries(&self) -> Vec<Delivery<T>> {
+        self.deliveries.lock().unwrap().clone()
+    }
+
+    /// Returns the samples received for `sensor_type`, in the order they were received.
+    pub fn get_samples(&self, sensor_type: &SensorType) -> Vec<T> {
+        self.deliveries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|delivery| delivery.sensor_type == *sensor_type)
+            .flat_map(|delivery| delivery.readings.get_samples())
+            .collect()
+    }
+
+    pub fn get_n_deliveries(&self) -> usize {
+        self.deliveries.lock().unwrap().len()
+    }
+
+    pub fn clear_deliveries(&self) {
+        self.deliveries.lock().unwrap().clear();
+    }
+
+    /// Waits up to `timeout` for `n` deliveries. Returns false on timeout.
+    pub fn wait_for_deliveries(&self, n: usize, timeout: Duration) -> bool {
+        let start = Instant::now();
+        while self.get_n_deliveries() < n {
+            if start.elapsed() > timeout {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+
+    /// Panics unless at least `n` deliveries were received.
+    pub fn assert_received_at_least(&self, n: usize) {
+        let n_deliveries = self.get_n_deliveries();
+        assert!(
+            n_deliveries >= n,
+            "Expected at least {} deliveries, received {}",
+            n,
+            n_deliveries
+        );
+    }
+
+    /// Panics if the timestamps received for any sensor ever go backwards.
+    pub fn assert_monotone_timestamps(&self) {
+        let mut newest: HashMap<SensorType, f64> = HashMap::new();
+        for delivery in self.deliveries.lock().unwrap().iter() {
+            for &timestamp in delivery.timestamps.iter() {
+                let newest = newest
+                    .entry(delivery.sensor_type.clone())
+                    .or_insert(f64::NEG_INFINITY);
+                assert!(
+                    timestamp >= *newest,
+                    "Timestamp of {:?} went back from {} to {} in delivery {}",
+                    delivery.sensor_type,
+                    newest,
+                    timestamp,
+                    delivery.order
+                );
+                *newest = timestamp;
+            }
+        }
+    }
 }
 
 impl<T> IMUSink<SensorReadings<T>, T> for SinkMock<T>
@@ -60,7 +144,7 @@ where
         &self,
         source: &dyn IMUSource<SensorReadings<T>, T>,
         sensor_cluster: &[SensorType],
-    ) -> Result<Vec<Uuid>, String> {
+    ) -> Result<Vec<Uuid>, ImuError> {
         let mut listener = listener!(self.process_samples);
         let mut ids = Vec::with_capacity(sensor_cluster.len());
         for sensor_type in sensor_cluster {
@@ -84,6 +168,20 @@ where
     fn process_samples(&self, id: Uuid, samples: Arc<SensorReadings<T>>) {
         let control = self.control.read().unwrap();
         if let Some(sensor_type) = control.get(&id) {
+            let mut deliveries = self.deliveries.lock().unwrap();
+            let order = deliveries.len();
+            deliveries.push(Delivery {
+                order,
+                listener_id: id,
+                sensor_type: sensor_type.clone(),
+                timestamps: samples
+                    .get_samples()
+                    .iter()
+                    .map(|sample| sample.get_timestamp_secs())
+                    .collect(),
+                readings: samples.clone(),
+            });
+            drop(deliveries);
             if let Some(cb) = self.callback.as_ref() {
                 cb(self.value.clone(), sensor_type.clone(), samples);
             }
@@ -96,6 +194,72 @@ impl<T> std::fmt::Debug for SinkMock<T> {
         f.debug_struct("SinkMock")
             .field("control", &self.control)
             .field("callback", &"<callback_fn>")
+            .field("n_deliveries", &self.deliveries.lock().unwrap().len())
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::VirtualSource;
+    use imu_common::types::timed::Sample3D;
+
+    #[test]
+    fn test_record_deliveries() {
+        let sensor_cluster = SensorType::cluster_for_tag("test_record_deliveries");
+        let source = VirtualSource::<Sample3D>::new(
+            "test",
+            sensor_cluster.clone(),
+            250.0,
+            |_sensor, _timestamp| [0.0, 0.0, 0.0].into(),
+        );
+        let sink = SinkMock::<Sample3D>::new();
+        let ids = sink.attach_listeners(&source, &sensor_cluster).unwrap();
+
+        source.emit_until(1.0);
+        source.emit_until(1.5);
+
+        let deliveries = sink.get_deliveries();
+        assert_eq!(deliveries.len(), 6);
+        assert_eq!(
+            deliveries.iter().map(|d| d.order).collect::<Vec<_>>(),
+            (0..6).collect::<Vec<_>>()
+        );
+        assert_eq!(deliveries[0].listener_id, ids[0]);
+        assert_eq!(deliveries[3].timestamps, vec![1.25, 1.5]);
+        assert_eq!(sink.get_samples(&sensor_cluster[1]).len(), 3);
+        assert!(sink.wait_for_deliveries(6, Duration::ZERO));
+        sink.assert_received_at_least(6);
+        sink.assert_monotone_timestamps();
+
+        sink.clear_deliveries();
+        assert_eq!(sink.get_n_deliveries(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "went back")]
+    fn test_timestamps_going_back() {
+        let sensor = SensorType::cluster_for_tag("test_timestamps_going_back")[0].clone();
+        let source = VirtualSource::<Sample3D>::new(
+            "test",
+            vec![sensor.clone()],
+            250.0,
+            |_sensor, _timestamp| [0.0, 0.0, 0.0].into(),
+        );
+        let sink = SinkMock::<Sample3D>::new();
+        sink.attach_listeners(&source, std::slice::from_ref(&sensor))
+            .unwrap();
+
+        source.emit_until(2.0);
+        source.notify_listeners(
+            sensor.clone(),
+            Arc::new(SensorReadings::from_vec(
+                "test",
+                sensor,
+                vec![Sample3D::new(1.0, [0.0, 0.0, 0.0])],
+            )),
+        );
+        sink.assert_monotone_timestamps();
+    }
+}
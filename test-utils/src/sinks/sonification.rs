@@ -0,0 +1,370 @@
+use std::f64::consts::TAU;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::{Sample3D, SampleScalar};
+use publisher::adapters;
+
+type LevelFn<T> = Arc<dyn Fn(&T) -> f64 + Send + Sync>;
+
+/// Audible property driven by the level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SonificationMode {
+    /// Higher levels sound higher, at constant volume.
+    #[default]
+    Pitch,
+    /// Higher levels sound louder, at constant pitch.
+    Volume,
+    /// Higher levels sound both higher and louder.
+    PitchAndVolume,
+}
+
+/// Maps levels in `[min_level, max_level]` to tones. Levels out of range are clamped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SonificationConfig {
+    pub mode: SonificationMode,
+    pub min_level: f64,
+    pub max_level: f64,
+    pub min_frequency_hz: f64,
+    pub max_frequency_hz: f64,
+    /// Amplitude in `[0, 1]`. The maximum amplitude in volume modes.
+    pub volume: f64,
+}
+
+impl SonificationConfig {
+    pub fn new(mode: SonificationMode, min_level: f64, max_level: f64) -> Self {
+        Self {
+            mode,
+            min_level,
+            max_level,
+            min_frequency_hz: 220.0,
+            max_frequency_hz: 880.0,
+            volume: 0.2,
+        }
+    }
+
+    pub fn with_frequency_range(mut self, min_hz: f64, max_hz: f64) -> Self {
+        self.min_frequency_hz = min_hz.max(0.0);
+        self.max_frequency_hz = max_hz.max(0.0);
+        self
+    }
+
+    pub fn with_volume(mut self, volume: f64) -> Self {
+        self.volume = volume.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Returns the tone of `level`. Pitch changes exponentially, so equal level steps sound
+    /// like equal musical intervals.
+    pub fn tone(&self, level: f64) -> Tone {
+        let span = self.max_level - self.min_level;
+        let ratio = if span.abs() > f64::EPSILON && level.is_finite() {
+            ((level - self.min_level) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let frequency = |ratio: f64| {
+            self.min_frequency_hz.max(1.0)
+                * (self.max_frequency_hz.max(1.0) / self.min_frequency_hz.max(1.0)).powf(ratio)
+        };
+        match self.mode {
+            SonificationMode::Pitch => Tone {
+                frequency_hz: frequency(ratio),
+                amplitude: self.volume,
+            },
+            SonificationMode::Volume => Tone {
+                frequency_hz: self.min_frequency_hz,
+                amplitude: self.volume * ratio,
+            },
+            SonificationMode::PitchAndVolume => Tone {
+                frequency_hz: frequency(ratio),
+                amplitude: self.volume * ratio,
+            },
+        }
+    }
+}
+
+/// Sine tone being played.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tone {
+    pub frequency_hz: f64,
+    pub amplitude: f64,
+}
+
+/// Sine oscillator following a target tone. Phase is kept across tone changes, and amplitude
+/// is smoothed, so updates don't produce clicks.
+#[derive(Clone, Debug)]
+pub struct Oscillator {
+    sample_rate_hz: f64,
+    phase: f64,
+    amplitude: f64,
+}
+
+/// Fraction of the remaining amplitude change applied on every audio sample.
+const AMPLITUDE_SMOOTHING: f64 = 0.001;
+
+impl Oscillator {
+    pub fn new(sample_rate_hz: f64) -> Self {
+        Self {
+            sample_rate_hz,
+            phase: 0.0,
+            amplitude: 0.0,
+        }
+    }
+
+    /// Returns the next audio sample, in `[-1, 1]`.
+    pub fn next_sample(&mut self, tone: &Tone) -> f32 {
+        self.amplitude += (tone.amplitude - self.amplitude) * AMPLITUDE_SMOOTHING;
+        self.phase = (self.phase + TAU * tone.frequency_hz / self.sample_rate_hz) % TAU;
+        (self.amplitude * self.phase.sin()) as f32
+    }
+}
+
+/// Sink mapping a level derived from every sample (an axis, a magnitude...) to an audio tone,
+/// so vibration or rotation can be heard while watching the experiment.
+///
+/// The sink only keeps the current tone up to date. Audio is played with `play`, available with
+/// the `sonification` feature, or by any other backend reading `get_tone`.
+#[derive(Clone)]
+pub struct SonificationSink<T> {
+    config: SonificationConfig,
+    level: LevelFn<T>,
+    tone: Arc<Mutex<Tone>>,
+}
+
+impl<T> SonificationSink<T>
+where
+    T: IMUSample,
+{
+    /// Creates a sink where `level` maps every sample to the value being sonified.
+    pub fn new<F>(config: SonificationConfig, level: F) -> Self
+    where
+        F: Fn(&T) -> f64 + Send + Sync + 'static,
+    {
+        Self {
+            config,
+            level: Arc::new(level),
+            tone: Arc::new(Mutex::new(Tone::default())),
+        }
+    }
+
+    pub fn get_tone(&self) -> Tone {
+        *self.tone.lock().unwrap()
+    }
+
+    /// Silences the output until the next sample is received.
+    pub fn mute(&self) {
+        self.tone.lock().unwrap().amplitude = 0.0;
+    }
+}
+
+impl SonificationSink<Sample3D> {
+    /// Sonifies the euclidean norm of 3D samples.
+    pub fn magnitude(config: SonificationConfig) -> Self {
+        Self::new(config, |sample: &Sample3D| {
+            sample.get_measurement().0.norm()
+        })
+    }
+
+    /// Sonifies a single axis (0 for x, 1 for y, 2 for z) of 3D samples.
+    pub fn axis(config: SonificationConfig, axis: usize) -> Result<Self, ImuError> {
+        if axis > 2 {
+            return Err(ImuError::InvalidParameter(format!("Invalid axis {}", axis)));
+        }
+        Ok(Self::new(config, move |sample: &Sample3D| {
+            sample.get_measurement().0[axis]
+        }))
+    }
+}
+
+impl SonificationSink<SampleScalar> {
+    /// Sonifies the value of scalar samples.
+    pub fn scalar(config: SonificationConfig) -> Self {
+        Self::new(config, |sample: &SampleScalar| {
+            f64::from(sample.get_measurement())
+        })
+    }
+}
+
+#[cfg(feature = "sonification")]
+mod audio {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use cpal::{FromSample, SizedSample, Stream, StreamConfig};
+
+    use super::{Oscillator, SonificationSink};
+    use imu_common::errors::ImuError;
+    use imu_common::traits::IMUSample;
+
+    fn audio_error(e: impl std::fmt::Display) -> ImuError {
+        ImuError::Io(format!("Audio error: {}", e))
+    }
+
+    impl<T> SonificationSink<T>
+    where
+        T: IMUSample,
+    {
+        /// Plays the tone on the default output device. Audio stops when the returned stream
+        /// is dropped.
+        pub fn play(&self) -> Result<Stream, ImuError> {
+            let device = cpal::default_host()
+                .default_output_device()
+                .ok_or_else(|| ImuError::Io("No audio output device".to_string()))?;
+            let config = device.default_output_config().map_err(audio_error)?;
+            let stream = match config.sample_format() {
+                cpal::SampleFormat::F32 => self.build_stream::<f32>(&device, &config.into()),
+                cpal::SampleFormat::I16 => self.build_stream::<i16>(&device, &config.into()),
+                cpal::SampleFormat::U16 => self.build_stream::<u16>(&device, &config.into()),
+                format => Err(ImuError::Other(format!(
+                    "Unsupported sample format {}",
+                    format
+                ))),
+            }?;
+            stream.play().map_err(audio_error)?;
+            Ok(stream)
+        }
+
+        fn build_stream<S>(
+            &self,
+            device: &cpal::Device,
+            config: &StreamConfig,
+        ) -> Result<Stream, ImuError>
+        where
+            S: SizedSample + FromSample<f32>,
+        {
+            let channels = config.channels as usize;
+            let mut oscillator = Oscillator::new(config.sample_rate.0 as f64);
+            let tone = self.tone.clone();
+            device
+                .build_output_stream(
+                    config,
+                    move |data: &mut [S], _: &cpal::OutputCallbackInfo| {
+                        let tone = *tone.lock().unwrap();
+                        for frame in data.chunks_mut(channels) {
+                            let value = S::from_sample(oscillator.next_sample(&tone));
+                            frame.fill(value);
+                        }
+                    },
+                    |e| eprintln!("Audio stream error: {}", e),
+                    None,
+                )
+                .map_err(audio_error)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::sinks::sonification::{SonificationConfig, SonificationMode};
+        use imu_common::types::timed::SampleScalar;
+
+        #[test]
+        fn test_play() {
+            let sink = SonificationSink::<SampleScalar>::scalar(SonificationConfig::new(
+                SonificationMode::Pitch,
+                0.0,
+                1.0,
+            ));
+            // CI runners have no audio device, so only check that failures are reported
+            match sink.play() {
+                Ok(stream) => drop(stream),
+                Err(e) => assert!(matches!(e, ImuError::Io(_) | ImuError::Other(_))),
+            }
+        }
+    }
+}
+
+impl<T> IMUSink<SensorReadings<T>, T> for SonificationSink<T>
+where
+    T: IMUSample,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<SensorReadings<T>, T>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<SensorReadings<T>>) {
+        if let Some(sample) = samples.get_samples_ref().last() {
+            let tone = self.config.tone((self.level)(sample));
+            *self.tone.lock().unwrap() = tone;
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for SonificationSink<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SonificationSink")
+            .field("config", &self.config)
+            .field("tone", &self.tone)
+            .field("level", &"<level_fn>")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone_mapping() {
+        let config = SonificationConfig::new(SonificationMode::Pitch, 0.0, 10.0)
+            .with_frequency_range(100.0, 400.0)
+            .with_volume(0.5);
+        assert_eq!(config.tone(-5.0).frequency_hz, 100.0);
+        assert!((config.tone(5.0).frequency_hz - 200.0).abs() < 1e-9);
+        assert_eq!(config.tone(50.0).frequency_hz, 400.0);
+        assert_eq!(config.tone(5.0).amplitude, 0.5);
+
+        let config = SonificationConfig {
+            mode: SonificationMode::Volume,
+            ..config
+        };
+        assert_eq!(config.tone(5.0).amplitude, 0.25);
+        assert_eq!(config.tone(f64::NAN).amplitude, 0.0);
+    }
+
+    #[test]
+    fn test_sink_updates_tone() {
+        let config = SonificationConfig::new(SonificationMode::PitchAndVolume, 0.0, 20.0);
+        let sink = SonificationSink::axis(config, 2).unwrap();
+        assert!(SonificationSink::axis(
+            SonificationConfig::new(SonificationMode::Pitch, 0.0, 1.0),
+            3
+        )
+        .is_err());
+
+        let acc = SensorType::Accelerometer(Uuid::new_v4());
+        let samples = vec![
+            Sample3D::new(0.0, [0.0, 0.0, 0.0]),
+            Sample3D::new(0.1, [0.0, 0.0, 10.0]),
+        ];
+        sink.process_samples(
+            Uuid::new_v4(),
+            Arc::new(SensorReadings::from_vec("Test", acc, samples)),
+        );
+
+        let tone = sink.get_tone();
+        assert!((tone.frequency_hz - 440.0).abs() < 1e-9);
+        assert!((tone.amplitude - 0.1).abs() < 1e-9);
+        sink.mute();
+        assert_eq!(sink.get_tone().amplitude, 0.0);
+    }
+
+    #[test]
+    fn test_oscillator() {
+        let mut oscillator = Oscillator::new(8000.0);
+        let tone = Tone {
+            frequency_hz: 1000.0,
+            amplitude: 1.0,
+        };
+        let samples: Vec<f32> = (0..20000).map(|_| oscillator.next_sample(&tone)).collect();
+        let peak = samples[12000..].iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(samples.iter().all(|s| s.abs() <= 1.0));
+        assert!(peak > 0.99);
+    }
+}
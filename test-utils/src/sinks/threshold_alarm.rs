@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::{Sample3D, SampleScalar};
+use publisher::adapters;
+
+type LevelFn<T> = Arc<dyn Fn(&T) -> f64 + Send + Sync>;
+type AlarmCallback = Arc<Option<Arc<dyn Fn(AlarmEvent) + Send + Sync>>>;
+
+/// Alarm trigger parameters.
+///
+/// The alarm is raised once the level stays above `threshold` for at least `debounce_secs`,
+/// and cleared once it stays below `threshold - hysteresis` for the same amount of time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlarmConfig {
+    pub threshold: f64,
+    pub hysteresis: f64,
+    pub debounce_secs: f64,
+}
+
+impl AlarmConfig {
+    pub fn new(threshold: f64, hysteresis: f64, debounce_secs: f64) -> Self {
+        Self {
+            threshold,
+            hysteresis: hysteresis.abs(),
+            debounce_secs: debounce_secs.max(0.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AlarmEventKind {
+    Raised,
+    Cleared,
+}
+
+/// Event emitted when the alarm changes state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlarmEvent {
+    pub kind: AlarmEventKind,
+    pub sensor_type: SensorType,
+    pub timestamp: f64,
+    pub value: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+struct AlarmState {
+    active: bool,
+    pending_since: Option<f64>,
+}
+
+impl AlarmState {
+    fn update(
+        &mut self,
+        config: &AlarmConfig,
+        timestamp: f64,
+        value: f64,
+    ) -> Option<AlarmEventKind> {
+        let crossing = if self.active {
+            value < config.threshold - config.hysteresis
+        } else {
+            value > config.threshold
+        };
+
+        if !crossing {
+            self.pending_since = None;
+            return None;
+        }
+
+        let since = *self.pending_since.get_or_insert(timestamp);
+        if timestamp - since < config.debounce_secs {
+            return None;
+        }
+
+        self.pending_since = None;
+        self.active = !self.active;
+        if self.active {
+            Some(AlarmEventKind::Raised)
+        } else {
+            Some(AlarmEventKind::Cleared)
+        }
+    }
+}
+
+/// Sink that watches a scalar level derived from each sample and notifies a callback when
+/// a threshold is crossed. Detaching the listener of a sensor clears its alarm.
+#[derive(Clone)]
+pub struct ThresholdAlarm<T> {
+    config: AlarmConfig,
+    level: LevelFn<T>,
+    state: Arc<Mutex<HashMap<SensorType, AlarmState>>>,
+    control: Arc<Mutex<HashMap<Uuid, SensorType>>>,
+    callback: AlarmCallback,
+}
+
+impl<T> ThresholdAlarm<T>
+where
+    T: IMUSample,
+{
+    /// Creates an alarm where `level` maps every sample to the value compared against the threshold.
+    pub fn new<F>(config: AlarmConfig, level: F) -> Self
+    where
+        F: Fn(&T) -> f64 + Send + Sync + 'static,
+    {
+        Self {
+            config,
+            level: Arc::new(level),
+            state: Arc::new(Mutex::new(HashMap::new())),
+            control: Arc::new(Mutex::new(HashMap::new())),
+            callback: Arc::new(None),
+        }
+    }
+
+    pub fn register_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(AlarmEvent) + Send + Sync + 'static,
+    {
+        self.callback = Arc::new(Some(Arc::new(callback)));
+    }
+
+    /// Returns true if the alarm is currently raised for `sensor_type`.
+    pub fn is_active(&self, sensor_type: &SensorType) -> bool {
+        let state = self.state.lock().unwrap();
+        state.get(sensor_type).map(|s| s.active).unwrap_or(false)
+    }
+
+    fn check_samples(&self, sensor_type: SensorType, samples: &[T]) {
+        let mut events = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            let alarm_state = state.entry(sensor_type.clone()).or_default();
+            for sample in samples {
+                let timestamp = sample.get_timestamp_secs();
+                let value = (self.level)(sample);
+                if let Some(kind) = alarm_state.update(&self.config, timestamp, value) {
+                    events.push(AlarmEvent {
+                        kind,
+                        sensor_type: sensor_type.clone(),
+                        timestamp,
+                        value,
+                    });
+                }
+            }
+        }
+        if let Some(cb) = self.callback.as_ref() {
+            for event in events {
+                cb(event);
+            }
+        }
+    }
+}
+
+impl ThresholdAlarm<Sample3D> {
+    /// Creates an alarm on the euclidean norm of 3D samples.
+    pub fn magnitude(config: AlarmConfig) -> Self {
+        Self::new(config, |sample: &Sample3D| {
+            sample.get_measurement().0.norm()
+        })
+    }
+}
+
+impl ThresholdAlarm<SampleScalar> {
+    /// Creates an alarm on the value of scalar samples.
+    pub fn scalar(config: AlarmConfig) -> Self {
+        Self::new(config, |sample: &SampleScalar| {
+            f64::from(sample.get_measurement())
+        })
+    }
+}
+
+impl<T> IMUSink<SensorReadings<T>, T> for ThresholdAlarm<T>
+where
+    T: IMUSample,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<SensorReadings<T>, T>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        let ids = adapters::attach_sync(self, source, sensor_cluster)?;
+        let mut control = self.control.lock().unwrap();
+        control.extend(ids.iter().cloned().zip(sensor_cluster.iter().cloned()));
+        Ok(ids)
+    }
+    fn detach_listener(&self, source: &dyn IMUSource<SensorReadings<T>, T>, id: Uuid) {
+        source.unregister_listener(id);
+        if let Some(sensor_type) = self.control.lock().unwrap().remove(&id) {
+            self.state.lock().unwrap().remove(&sensor_type);
+        }
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<SensorReadings<T>>) {
+        self.check_samples(samples.get_sensor_type(), samples.get_samples_ref());
+    }
+}
+
+impl<T> std::fmt::Debug for ThresholdAlarm<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ThresholdAlarm")
+            .field("config", &self.config)
+            .field("state", &self.state)
+            .field("callback", &"<callback_fn>")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::VirtualSource;
+
+    fn readings(samples: &[(f64, f64)]) -> Arc<SensorReadings<SampleScalar>> {
+        let data = samples
+            .iter()
+            .map(|(t, v)| SampleScalar::new(*t, *v))
+            .collect();
+        Arc::new(SensorReadings::from_vec(
+            "test",
+            SensorType::Other(Uuid::new_v4(), "vibration".to_string()),
+            data,
+        ))
+    }
+
+    fn alarm_with_log(
+        config: AlarmConfig,
+    ) -> (ThresholdAlarm<SampleScalar>, Arc<Mutex<Vec<AlarmEvent>>>) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let log_clone = Arc::clone(&log);
+        let mut alarm = ThresholdAlarm::scalar(config);
+        alarm.register_callback(move |event| log_clone.lock().unwrap().push(event));
+        (alarm, log)
+    }
+
+    #[test]
+    fn test_raise_after_debounce() {
+        let (alarm, log) = alarm_with_log(AlarmConfig::new(2.0, 0.5, 0.5));
+        let samples = readings(&[(0.0, 2.5), (0.2, 2.5), (0.4, 2.5), (0.6, 2.5)]);
+
+        alarm.process_samples(Uuid::new_v4(), samples.clone());
+
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].kind, AlarmEventKind::Raised);
+        assert_eq!(log[0].timestamp, 0.6);
+        assert!(alarm.is_active(&samples.get_sensor_type()));
+    }
+
+    #[test]
+    fn test_short_spike_is_ignored() {
+        let (alarm, log) = alarm_with_log(AlarmConfig::new(2.0, 0.5, 0.5));
+        let samples = readings(&[(0.0, 2.5), (0.2, 2.5), (0.4, 1.0), (0.6, 2.5), (0.8, 1.0)]);
+
+        alarm.process_samples(Uuid::new_v4(), samples.clone());
+
+        assert!(log.lock().unwrap().is_empty());
+        assert!(!alarm.is_active(&samples.get_sensor_type()));
+    }
+
+    #[test]
+    fn test_hysteresis_before_clear() {
+        let (alarm, log) = alarm_with_log(AlarmConfig::new(2.0, 0.5, 0.0));
+        let samples = readings(&[(0.0, 2.5), (0.1, 1.8), (0.2, 1.6), (0.3, 1.4)]);
+
+        alarm.process_samples(Uuid::new_v4(), samples);
+
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].kind, AlarmEventKind::Raised);
+        assert_eq!(log[1].kind, AlarmEventKind::Cleared);
+        assert_eq!(log[1].value, 1.4);
+    }
+
+    #[test]
+    fn test_magnitude_level() {
+        let mut alarm = ThresholdAlarm::magnitude(AlarmConfig::new(1.0, 0.0, 0.0));
+        let raised = Arc::new(Mutex::new(false));
+        let raised_clone = Arc::clone(&raised);
+        alarm.register_callback(move |_| *raised_clone.lock().unwrap() = true);
+        let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+        let samples = Arc::new(SensorReadings::from_vec(
+            "test",
+            sensor_type,
+            vec![Sample3D::new(0.0, [0.6, 0.6, 0.6])],
+        ));
+
+        alarm.process_samples(Uuid::new_v4(), samples);
+
+        assert!(*raised.lock().unwrap());
+    }
+
+    #[test]
+    fn test_detach_listener() {
+        let (alarm, log) = alarm_with_log(AlarmConfig::new(2.0, 0.5, 0.0));
+        let sensor_type = SensorType::Other(Uuid::new_v4(), "vibration".to_string());
+        let source = VirtualSource::<SampleScalar>::new(
+            "test",
+            vec![sensor_type.clone()],
+            100.0,
+            |_sensor, _timestamp| 2.5.into(),
+        );
+        let ids = alarm
+            .attach_listeners(&source, std::slice::from_ref(&sensor_type))
+            .unwrap();
+        source.emit_until(0.0);
+        assert!(alarm.is_active(&sensor_type));
+
+        // the alarm is cleared, and no longer fed by the source
+        alarm.detach_listener(&source, ids[0]);
+        assert!(!alarm.is_active(&sensor_type));
+        source.emit_until(1.0);
+        assert!(!alarm.is_active(&sensor_type));
+        assert_eq!(log.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,26 @@
+//! Module errors
+
+/// Errors of the UDP source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UdpError {
+    /// Error binding or reading the socket.
+    Socket(String),
+
+    /// Error indicating that a sensor is already published by another source.
+    DuplicatedSensor(String),
+
+    /// Error indicating that a received frame is malformed.
+    InvalidFrame(String),
+}
+
+impl std::fmt::Display for UdpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UdpError::Socket(e) => write!(f, "Socket error: {}", e),
+            UdpError::DuplicatedSensor(e) => write!(f, "Duplicated sensor: {}", e),
+            UdpError::InvalidFrame(e) => write!(f, "Invalid frame: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for UdpError {}
@@ -0,0 +1,199 @@
+//! Module frame
+//!
+//! Wire format of the readings sent to [`UdpImuSource`](crate::UdpImuSource). A datagram
+//! holds either one or more binary frames, or JSON.
+//!
+//! Binary frames are little endian and can be sent back to back in a single datagram:
+//!
+//! | Field     | Type       | Description                                             |
+//! |-----------|------------|---------------------------------------------------------|
+//! | length    | `u16`      | Number of bytes after this field (22 or 26)             |
+//! | channel   | `u8`       | Index of the sensor in the source                       |
+//! | kind      | `u8`       | `0` for 3D readings, `1` for quaternions                |
+//! | timestamp | `f64`      | Timestamp in seconds                                    |
+//! | values    | `[f32; n]` | `x, y, z` for 3D readings, `w, x, y, z` for quaternions |
+//!
+//! JSON datagrams hold a frame object or an array of them, with the number of values
+//! selecting the kind:
+//!
+//! ```json
+//! {"channel": 0, "timestamp": 1.25, "values": [0.0, 0.0, 9.81]}
+//! ```
+
+use serde::Deserialize;
+
+use crate::errors::UdpError;
+use imu_common::types::timed::{Sample3D, SampleQuaternion};
+
+pub const KIND_VECTOR: u8 = 0;
+pub const KIND_QUATERNION: u8 = 1;
+
+const N_VECTOR_VALUES: usize = 3;
+const N_QUATERNION_VALUES: usize = 4;
+const LENGTH_SIZE: usize = 2;
+const HEADER_SIZE: usize = 2 + 8;
+
+/// Reading decoded from a frame.
+#[derive(Clone, Debug)]
+pub enum Frame {
+    Vector {
+        channel: u8,
+        sample: Sample3D,
+    },
+    Quaternion {
+        channel: u8,
+        sample: SampleQuaternion,
+    },
+}
+
+#[derive(Deserialize)]
+struct JsonFrame {
+    channel: u8,
+    timestamp: f64,
+    values: Vec<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonDatagram {
+    One(JsonFrame),
+    Many(Vec<JsonFrame>),
+}
+
+/// Decodes every frame in `datagram`.
+pub fn parse_datagram(datagram: &[u8]) -> Result<Vec<Frame>, UdpError> {
+    match datagram.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'{') | Some(b'[') => parse_json(datagram),
+        Some(_) => parse_binary(datagram),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Encodes a binary frame. `values` holds 3 values for 3D readings and 4 for quaternions.
+pub fn encode_frame(channel: u8, timestamp: f64, values: &[f32]) -> Result<Vec<u8>, UdpError> {
+    let kind = match values.len() {
+        N_VECTOR_VALUES => KIND_VECTOR,
+        N_QUATERNION_VALUES => KIND_QUATERNION,
+        n => return Err(UdpError::InvalidFrame(format!("{} values", n))),
+    };
+    let length = (HEADER_SIZE + values.len() * 4) as u16;
+    let mut frame = Vec::with_capacity(LENGTH_SIZE + length as usize);
+    frame.extend(length.to_le_bytes());
+    frame.extend([channel, kind]);
+    frame.extend(timestamp.to_le_bytes());
+    frame.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+    Ok(frame)
+}
+
+fn parse_binary(mut datagram: &[u8]) -> Result<Vec<Frame>, UdpError> {
+    let mut frames = Vec::new();
+    while !datagram.is_empty() {
+        let length = datagram
+            .get(..LENGTH_SIZE)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+            .ok_or(UdpError::InvalidFrame("Truncated length".to_string()))?;
+        let payload = datagram
+            .get(LENGTH_SIZE..LENGTH_SIZE + length)
+            .ok_or(UdpError::InvalidFrame("Truncated frame".to_string()))?;
+        if length < HEADER_SIZE {
+            return Err(UdpError::InvalidFrame(format!(
+                "Frame too short: {}",
+                length
+            )));
+        }
+        let (channel, kind) = (payload[0], payload[1]);
+        let timestamp = f64::from_le_bytes(payload[2..HEADER_SIZE].try_into().unwrap());
+        let values: Vec<f64> = payload[HEADER_SIZE..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()) as f64)
+            .collect();
+        let expected = match kind {
+            KIND_VECTOR => N_VECTOR_VALUES,
+            KIND_QUATERNION => N_QUATERNION_VALUES,
+            kind => return Err(UdpError::InvalidFrame(format!("Unknown kind {}", kind))),
+        };
+        if length != HEADER_SIZE + expected * 4 {
+            return Err(UdpError::InvalidFrame(format!(
+                "Invalid length {} for kind {}",
+                length, kind
+            )));
+        }
+        frames.push(to_frame(channel, timestamp, values)?);
+        datagram = &datagram[LENGTH_SIZE + length..];
+    }
+    Ok(frames)
+}
+
+fn parse_json(datagram: &[u8]) -> Result<Vec<Frame>, UdpError> {
+    let frames = match serde_json::from_slice(datagram)
+        .map_err(|e| UdpError::InvalidFrame(e.to_string()))?
+    {
+        JsonDatagram::One(frame) => vec![frame],
+        JsonDatagram::Many(frames) => frames,
+    };
+    frames
+        .into_iter()
+        .map(|f| to_frame(f.channel, f.timestamp, f.values))
+        .collect()
+}
+
+fn to_frame(channel: u8, timestamp: f64, values: Vec<f64>) -> Result<Frame, UdpError> {
+    match values.len() {
+        N_VECTOR_VALUES => Ok(Frame::Vector {
+            channel,
+            sample: Sample3D::new(timestamp, [values[0], values[1], values[2]]),
+        }),
+        N_QUATERNION_VALUES => Ok(Frame::Quaternion {
+            channel,
+            sample: SampleQuaternion::new(timestamp, [values[0], values[1], values[2], values[3]]),
+        }),
+        n => Err(UdpError::InvalidFrame(format!("{} values", n))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imu_common::traits::IMUSample;
+
+    #[test]
+    fn test_binary_frames() {
+        let mut datagram = encode_frame(2, 1.5, &[0.0, 0.0, 9.81]).unwrap();
+        datagram.extend(encode_frame(0, 1.5, &[1.0, 0.0, 0.0, 0.0]).unwrap());
+        let frames = parse_datagram(&datagram).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        match &frames[0] {
+            Frame::Vector { channel, sample } => {
+                assert_eq!(*channel, 2);
+                assert_eq!(sample.get_timestamp_secs(), 1.5);
+                assert_eq!(sample.get_measurement().inner()[2], 9.81f32 as f64);
+            }
+            frame => panic!("Unexpected frame {:?}", frame),
+        }
+        assert!(matches!(frames[1], Frame::Quaternion { channel: 0, .. }));
+
+        assert!(parse_datagram(&datagram[..datagram.len() - 1]).is_err());
+        datagram[3] = 7;
+        assert!(parse_datagram(&datagram).is_err());
+        assert!(encode_frame(0, 0.0, &[1.0]).is_err());
+    }
+
+    #[test]
+    fn test_json_frames() {
+        let frames =
+            parse_datagram(br#"{"channel": 1, "timestamp": 2.0, "values": [1, 2, 3]}"#).unwrap();
+        assert!(matches!(frames[0], Frame::Vector { channel: 1, .. }));
+
+        let frames = parse_datagram(
+            br#"[{"channel": 0, "timestamp": 2.0, "values": [1, 0, 0, 0]},
+                 {"channel": 1, "timestamp": 2.0, "values": [1, 2, 3]}]"#,
+        )
+        .unwrap();
+        assert_eq!(frames.len(), 2);
+        assert!(matches!(frames[0], Frame::Quaternion { channel: 0, .. }));
+
+        assert!(parse_datagram(br#"{"channel": 1, "timestamp": 2.0, "values": [1]}"#).is_err());
+        assert!(parse_datagram(b"{").is_err());
+    }
+}
@@ -0,0 +1,20 @@
+//! # Crate udp-rs
+//!
+//! ## udp-rs
+//!
+//! The `udp-rs` crate receives IMU readings over UDP, typically streamed by microcontroller
+//! boards such as an ESP32, and publishes them like any other source, so they can be fed into
+//! the resampler and AHRS pipeline.
+//!
+//! Features include:
+//! - Compact little endian binary frames and JSON frames. See [`frame`] for the wire format.
+//! - 3D readings (`Sample3D`) and orientations (`SampleQuaternion`) from the same board.
+//! - Several samples per datagram, published together.
+//! - Registration of listeners to receive sensor data once received.
+
+pub mod errors;
+pub mod frame;
+mod service;
+
+pub use errors::UdpError;
+pub use service::{run_service, UdpImuSource};
@@ -0,0 +1,281 @@
+use log::{error, warn};
+use publisher::{lifetime, PublisherManager, ShutdownToken};
+use std::net::{SocketAddr, UdpSocket as StdUdpSocket};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::errors::UdpError;
+use crate::frame::{parse_datagram, Frame};
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSource, Notifiable};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::{Sample3D, SampleQuaternion};
+
+/// Largest datagram accepted. Longer datagrams are truncated, and fail to parse.
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// Source publishing the readings received on a UDP socket.
+///
+/// Frames carry the index of their sensor: 3D frames index `sensors` and quaternion frames
+/// index `orientations`. Readings of one datagram are published together, so a board can batch
+/// several samples of a sensor in a single datagram. Malformed datagrams and frames of unknown
+/// sensors are logged and dropped.
+///
+/// The source publishes 3D readings and quaternions, so it implements `IMUSource` for both
+/// `Sample3D` and `SampleQuaternion`.
+pub struct UdpImuSource {
+    tag: String,
+    socket: UdpSocket,
+    sensors: Vec<SensorType>,
+    orientations: Vec<SensorType>,
+    vector_publishers: PublisherManager<SensorReadings<Sample3D>, SensorType>,
+    quaternion_publishers: PublisherManager<SensorReadings<SampleQuaternion>, SensorType>,
+    abort_signal: Arc<Notify>,
+    shutdown: ShutdownToken,
+    dropped_frames: AtomicUsize,
+}
+
+impl UdpImuSource {
+    /// Binds a socket on `addr`, which can use port 0 to pick a free port. Must be called
+    /// inside a tokio runtime.
+    /// Returns a Socket error if the socket can't be bound, and DuplicatedSensor if a sensor is
+    /// repeated or already published by another source.
+    pub fn bind(
+        addr: &str,
+        tag: &str,
+        sensors: Vec<SensorType>,
+        orientations: Vec<SensorType>,
+    ) -> Result<Self, UdpError> {
+        let duplicated = |e: ImuError| UdpError::DuplicatedSensor(format!("{} in {}", e, tag));
+        if let Some(sensor_type) = orientations.iter().find(|s| sensors.contains(s)) {
+            return Err(duplicated(ImuError::InvalidSensorCluster(format!(
+                "Sensor {} repeated",
+                sensor_type
+            ))));
+        }
+        let vector_publishers = PublisherManager::try_new(&sensors).map_err(duplicated)?;
+        let quaternion_publishers = PublisherManager::try_new(&orientations).map_err(duplicated)?;
+
+        let socket = StdUdpSocket::bind(addr).map_err(|e| UdpError::Socket(e.to_string()))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| UdpError::Socket(e.to_string()))?;
+        let socket = UdpSocket::from_std(socket).map_err(|e| UdpError::Socket(e.to_string()))?;
+
+        Ok(Self {
+            tag: tag.to_string(),
+            socket,
+            sensors,
+            orientations,
+            vector_publishers,
+            quaternion_publishers,
+            abort_signal: Arc::new(Notify::new()),
+            shutdown: ShutdownToken::global(),
+            dropped_frames: AtomicUsize::new(0),
+        })
+    }
+
+    /// Stops the source when `token` is shut down, instead of the global token.
+    pub fn with_shutdown_token(mut self, token: ShutdownToken) -> Self {
+        self.shutdown = token;
+        self
+    }
+
+    /// Returns the address the socket is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr, UdpError> {
+        self.socket
+            .local_addr()
+            .map_err(|e| UdpError::Socket(e.to_string()))
+    }
+
+    /// Returns the number of datagrams and frames dropped because they were malformed or
+    /// referenced an unknown sensor.
+    pub fn get_dropped_frames(&self) -> usize {
+        self.dropped_frames.load(Ordering::Relaxed)
+    }
+
+    /// Receives and publishes readings until `stop` is called or the shutdown token is shut
+    /// down. Returns a Socket error if the socket fails.
+    pub async fn start(&self) -> Result<(), UdpError> {
+        let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+        loop {
+            tokio::select! {
+                _ = self.abort_signal.notified() => return Ok(()),
+                _ = self.shutdown.wait() => return Ok(()),
+                received = self.socket.recv_from(&mut buffer) => {
+                    let (len, _) = received.map_err(|e| UdpError::Socket(e.to_string()))?;
+                    self.process_datagram(&buffer[..len]);
+                }
+            }
+        }
+    }
+
+    /// Stops `start`. If it isn't running yet, it returns as soon as it is called.
+    pub fn stop(&self) {
+        self.abort_signal.notify_one();
+    }
+
+    fn process_datagram(&self, datagram: &[u8]) {
+        let frames = match parse_datagram(datagram) {
+            Ok(frames) => frames,
+            Err(e) => {
+                warn!("Dropping datagram received by {}: {}", self.tag, e);
+                self.dropped_frames.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+
+        let mut vectors: Vec<SensorReadings<Sample3D>> = Vec::new();
+        let mut quaternions: Vec<SensorReadings<SampleQuaternion>> = Vec::new();
+        for frame in frames {
+            match frame {
+                Frame::Vector { channel, sample } => match self.sensors.get(channel as usize) {
+                    Some(sensor_type) => add_sample(&mut vectors, &self.tag, sensor_type, sample),
+                    None => self.drop_frame(channel),
+                },
+                Frame::Quaternion { channel, sample } => {
+                    match self.orientations.get(channel as usize) {
+                        Some(sensor_type) => {
+                            add_sample(&mut quaternions, &self.tag, sensor_type, sample)
+                        }
+                        None => self.drop_frame(channel),
+                    }
+                }
+            }
+        }
+
+        for readings in vectors {
+            self.vector_publishers
+                .notify_listeners(readings.get_sensor_type(), Arc::new(readings));
+        }
+        for readings in quaternions {
+            self.quaternion_publishers
+                .notify_listeners(readings.get_sensor_type(), Arc::new(readings));
+        }
+    }
+
+    fn drop_frame(&self, channel: u8) {
+        warn!(
+            "Dropping frame of unknown channel {} in {}",
+            channel, self.tag
+        );
+        self.dropped_frames.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Appends `sample` to the readings of `sensor_type`, creating them if needed.
+fn add_sample<S>(
+    readings: &mut Vec<SensorReadings<S>>,
+    tag: &str,
+    sensor_type: &SensorType,
+    sample: S,
+) where
+    S: imu_common::traits::IMUSample,
+{
+    match readings
+        .iter_mut()
+        .find(|r| r.get_sensor_type() == *sensor_type)
+    {
+        Some(sensor_readings) => sensor_readings.add_sample(sample),
+        None => readings.push(SensorReadings::from_vec(
+            tag,
+            sensor_type.clone(),
+            vec![sample],
+        )),
+    }
+}
+
+impl IMUSource<SensorReadings<Sample3D>, Sample3D> for UdpImuSource {
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.sensors.clone()
+    }
+
+    fn get_tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.vector_publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<Sample3D>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.vector_publishers.add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(&self, sensor_type: SensorType, data: Arc<SensorReadings<Sample3D>>) {
+        self.vector_publishers.notify_listeners(sensor_type, data);
+    }
+}
+
+impl IMUSource<SensorReadings<SampleQuaternion>, SampleQuaternion> for UdpImuSource {
+    fn get_available_sensors(&self) -> Vec<SensorType> {
+        self.orientations.clone()
+    }
+
+    fn get_tag(&self) -> &str {
+        &self.tag
+    }
+
+    fn unregister_listener(&self, id: Uuid) {
+        let _ = self.quaternion_publishers.remove_listener(id);
+    }
+
+    fn register_listener(
+        &self,
+        listener: &mut dyn Notifiable<SensorReadings<SampleQuaternion>>,
+        sensor_type: &SensorType,
+    ) -> Result<Uuid, ImuError> {
+        self.quaternion_publishers
+            .add_listener(listener, sensor_type)
+    }
+
+    fn notify_listeners(
+        &self,
+        sensor_type: SensorType,
+        data: Arc<SensorReadings<SampleQuaternion>>,
+    ) {
+        self.quaternion_publishers
+            .notify_listeners(sensor_type, data);
+    }
+}
+
+/// Starts a UDP source listening on `addr` in a background task.
+///
+/// `sensors` are the 3D sensors and `orientations` the quaternion sensors of the board, in the
+/// order of their frame channels. Must be called inside a tokio runtime.
+///
+/// # Returns
+///
+/// Returns a tuple containing:
+/// * A `tokio::task::JoinHandle<()>` of the task, which ends when `UdpImuSource::stop` is called
+///   or the source is dropped.
+/// * An `Arc<UdpImuSource>` to register listeners and stop the source.
+pub fn run_service(
+    addr: &str,
+    tag: &str,
+    sensors: Vec<SensorType>,
+    orientations: Vec<SensorType>,
+) -> Result<(tokio::task::JoinHandle<()>, Arc<UdpImuSource>), UdpError> {
+    let source = Arc::new(UdpImuSource::bind(addr, tag, sensors, orientations)?);
+    let handle = tokio::spawn({
+        let source = source.clone();
+        async move {
+            let result = tokio::select! {
+                result = source.start() => result,
+                // stop once the caller drops the source
+                _ = lifetime::orphaned(&source) => Ok(()),
+            };
+            if let Err(e) = result {
+                error!("Error in UDP loop: {:?}", e);
+            }
+        }
+    });
+    Ok((handle, source))
+}
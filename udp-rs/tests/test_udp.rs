@@ -0,0 +1,144 @@
+use publisher::{Listener, ShutdownToken};
+use std::net::UdpSocket;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use imu_common::traits::{IMUReadings, IMUSample, IMUSource};
+use imu_common::types::sensors::{SensorClusterBuilder, SensorReadings, SensorType};
+use imu_common::types::timed::{Sample3D, SampleQuaternion};
+use udp_rs::frame::encode_frame;
+use udp_rs::{run_service, UdpError, UdpImuSource};
+
+#[tokio::test]
+async fn test_receive_udp_frames() {
+    let sensors = SensorClusterBuilder::new().six_axis().build().unwrap();
+    let orientations = SensorClusterBuilder::new()
+        .other("Orientation")
+        .build()
+        .unwrap();
+    let (handle, source) = run_service(
+        "127.0.0.1:0",
+        "Board",
+        sensors.clone(),
+        orientations.clone(),
+    )
+    .unwrap();
+
+    let vectors: Arc<Mutex<Vec<Sample3D>>> = Arc::new(Mutex::new(Vec::new()));
+    let mut vector_listener = Listener::new({
+        let vectors = vectors.clone();
+        move |_id, readings: Arc<SensorReadings<Sample3D>>| {
+            vectors.lock().unwrap().extend(readings.get_samples());
+        }
+    });
+    source
+        .register_listener(&mut vector_listener, &sensors[1])
+        .unwrap();
+    let quaternions: Arc<Mutex<Vec<SampleQuaternion>>> = Arc::new(Mutex::new(Vec::new()));
+    let mut quaternion_listener = Listener::new({
+        let quaternions = quaternions.clone();
+        move |_id, readings: Arc<SensorReadings<SampleQuaternion>>| {
+            quaternions.lock().unwrap().extend(readings.get_samples());
+        }
+    });
+    source
+        .register_listener(&mut quaternion_listener, &orientations[0])
+        .unwrap();
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let target = source.local_addr().unwrap();
+    let mut datagram = encode_frame(1, 1.0, &[0.1, 0.2, 0.3]).unwrap();
+    datagram.extend(encode_frame(1, 1.01, &[0.4, 0.5, 0.6]).unwrap());
+    datagram.extend(encode_frame(0, 1.01, &[1.0, 0.0, 0.0, 0.0]).unwrap());
+    socket.send_to(&datagram, target).unwrap();
+    socket
+        .send_to(
+            br#"{"channel": 1, "timestamp": 1.02, "values": [0.7, 0.8, 0.9]}"#,
+            target,
+        )
+        .unwrap();
+    // unknown channel and malformed datagram
+    socket
+        .send_to(&encode_frame(5, 1.03, &[0.0, 0.0, 0.0]).unwrap(), target)
+        .unwrap();
+    socket.send_to(b"\x01", target).unwrap();
+
+    for _ in 0..50 {
+        if source.get_dropped_frames() == 2 && vectors.lock().unwrap().len() == 3 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    source.stop();
+    handle.await.unwrap();
+
+    let mut timestamps: Vec<f64> = vectors
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|s| s.get_timestamp_secs())
+        .collect();
+    timestamps.sort_by(f64::total_cmp);
+    assert_eq!(timestamps, vec![1.0, 1.01, 1.02]);
+    assert_eq!(quaternions.lock().unwrap().len(), 1);
+    assert_eq!(source.get_dropped_frames(), 2);
+    assert_eq!(
+        IMUSource::<SensorReadings<Sample3D>, Sample3D>::get_available_sensors(&*source),
+        sensors
+    );
+}
+
+#[tokio::test]
+async fn test_duplicated_sensors() {
+    let sensors = SensorType::cluster_for_tag("test_udp_duplicated_sensors");
+    let (handle, source) = run_service("127.0.0.1:0", "Board", sensors.clone(), vec![]).unwrap();
+
+    let result = run_service("127.0.0.1:0", "Board", sensors.clone(), vec![]);
+    assert!(matches!(result, Err(UdpError::DuplicatedSensor(_))));
+    source.stop();
+    handle.await.unwrap();
+
+    let other = SensorClusterBuilder::new().accelerometer().build().unwrap();
+    let result = run_service("127.0.0.1:0", "Board", other.clone(), other);
+    assert!(matches!(result, Err(UdpError::DuplicatedSensor(_))));
+}
+
+#[tokio::test]
+async fn test_stop_on_shutdown() {
+    let token = ShutdownToken::new();
+    let sensors = SensorType::cluster_for_tag("test_udp_stop_on_shutdown");
+    let source = Arc::new(
+        UdpImuSource::bind("127.0.0.1:0", "Board", sensors, vec![])
+            .unwrap()
+            .with_shutdown_token(token.clone()),
+    );
+    let handle = tokio::spawn({
+        let source = source.clone();
+        async move { source.start().await }
+    });
+
+    token.shutdown();
+
+    tokio::time::timeout(Duration::from_secs(1), handle)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_stop_on_drop() {
+    let sensors = SensorType::cluster_for_tag("test_udp_stop_on_drop");
+    let (handle, source) = run_service("127.0.0.1:0", "Board", sensors.clone(), vec![]).unwrap();
+
+    drop(source);
+
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .expect("Source didn't stop")
+        .unwrap();
+    // the socket and sensors are released with the source
+    let (handle, source) = run_service("127.0.0.1:0", "Board", sensors, vec![]).unwrap();
+    source.stop();
+    handle.await.unwrap();
+}
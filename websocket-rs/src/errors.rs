@@ -0,0 +1,18 @@
+//! Module errors
+
+/// Errors of the WebSocket sink.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebSocketError {
+    /// Error binding the server socket.
+    Socket(String),
+}
+
+impl std::fmt::Display for WebSocketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebSocketError::Socket(e) => write!(f, "Socket error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WebSocketError {}
@@ -0,0 +1,26 @@
+//! # Crate websocket-rs
+//!
+//! ## websocket-rs
+//!
+//! The `websocket-rs` crate streams processed readings to browsers, so live dashboards can be
+//! built on top of any source or processing node, such as the resampler output.
+//!
+//! [`WebSocketSink`] is an `IMUSink` running a WebSocket server. Every batch of readings it
+//! receives is broadcast to all the connected clients as a JSON text message:
+//!
+//! ```json
+//! {
+//!   "tag": "Phone",
+//!   "sensor": "accelerometer::67e55044-10b1-426f-9247-bb680e5fe0c8",
+//!   "samples": [{"timestamp": 1.25, "measurement": {"x": 0.1, "y": 0.0, "z": 9.8}}]
+//! }
+//! ```
+//!
+//! Samples use the serde representation of `imu_common` samples. Slow clients skip the
+//! messages they can't keep up with instead of delaying the rest.
+
+pub mod errors;
+mod sink;
+
+pub use errors::WebSocketError;
+pub use sink::{run_server, WebSocketSink};
@@ -0,0 +1,204 @@
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, error, warn};
+use publisher::{adapters, DropGuard, ShutdownToken};
+use serde::Serialize;
+use std::net::{SocketAddr, TcpListener as StdTcpListener};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, Notify};
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+use crate::errors::WebSocketError;
+use imu_common::errors::ImuError;
+use imu_common::traits::{IMUReadings, IMUSample, IMUSink, IMUSource};
+use imu_common::types::sensors::SensorType;
+
+/// Number of messages buffered for every client. Clients further behind skip messages.
+const CLIENT_BUFFER_CAPACITY: usize = 256;
+
+#[derive(Serialize)]
+struct ReadingsMessage<'a, S> {
+    tag: &'a str,
+    sensor: String,
+    samples: Vec<S>,
+}
+
+/// Sink broadcasting the readings it receives to every client connected to its WebSocket
+/// server. Readings are only serialized while there are clients connected.
+///
+/// The server stops once every clone of the sink is dropped, including the ones held by the
+/// sources it is attached to.
+#[derive(Clone)]
+pub struct WebSocketSink {
+    sender: broadcast::Sender<Arc<str>>,
+    local_addr: SocketAddr,
+    abort_signal: Arc<Notify>,
+    n_clients: Arc<AtomicUsize>,
+    shutdown: ShutdownToken,
+    // `None` in the clones used by the server tasks, so they don't keep the sink alive
+    _guard: Option<Arc<DropGuard>>,
+}
+
+impl WebSocketSink {
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns the number of connected clients.
+    pub fn get_client_count(&self) -> usize {
+        self.n_clients.load(Ordering::Relaxed)
+    }
+
+    /// Stops accepting clients, and closes the open connections.
+    pub fn stop(&self) {
+        abort(&self.abort_signal);
+    }
+
+    /// Broadcasts `message` to the connected clients.
+    pub fn broadcast(&self, message: &str) {
+        // sending only fails if there are no clients
+        let _ = self.sender.send(Arc::from(message));
+    }
+
+    async fn serve(&self, listener: TcpListener) {
+        loop {
+            tokio::select! {
+                _ = self.abort_signal.notified() => return,
+                _ = self.shutdown.wait() => {
+                    self.stop();
+                    return;
+                }
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        let sink = self.clone();
+                        tokio::spawn(async move { sink.serve_client(stream, peer).await });
+                    }
+                    Err(e) => error!("Error accepting WebSocket client: {}", e),
+                }
+            }
+        }
+    }
+
+    async fn serve_client(&self, stream: TcpStream, peer: SocketAddr) {
+        let websocket = match tokio_tungstenite::accept_async(stream).await {
+            Ok(websocket) => websocket,
+            Err(e) => {
+                warn!("WebSocket handshake with {} failed: {}", peer, e);
+                return;
+            }
+        };
+        debug!("WebSocket client {} connected", peer);
+        let (mut outgoing, mut incoming) = websocket.split();
+        let mut receiver = self.sender.subscribe();
+        self.n_clients.fetch_add(1, Ordering::Relaxed);
+
+        loop {
+            tokio::select! {
+                _ = self.abort_signal.notified() => {
+                    let _ = outgoing.send(Message::Close(None)).await;
+                    break;
+                }
+                message = receiver.recv() => match message {
+                    Ok(text) => {
+                        if outgoing.send(Message::Text(text.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("WebSocket client {} skipped {} messages", peer, n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                // clients aren't expected to send anything but pings and close frames
+                message = incoming.next() => match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+        self.n_clients.fetch_sub(1, Ordering::Relaxed);
+        debug!("WebSocket client {} disconnected", peer);
+    }
+}
+
+impl<T, S> IMUSink<T, S> for WebSocketSink
+where
+    T: Send + Sync + IMUReadings<S> + 'static,
+    S: IMUSample + Serialize,
+{
+    fn attach_listeners(
+        &self,
+        source: &dyn IMUSource<T, S>,
+        sensor_cluster: &[SensorType],
+    ) -> Result<Vec<Uuid>, ImuError> {
+        adapters::attach_sync(self, source, sensor_cluster)
+    }
+
+    fn process_samples(&self, _listener_id: Uuid, samples: Arc<T>) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        let message = ReadingsMessage {
+            tag: samples.get_sensor_tag(),
+            sensor: samples.get_sensor_type().to_string(),
+            samples: samples.get_samples(),
+        };
+        match serde_json::to_string(&message) {
+            Ok(message) => self.broadcast(&message),
+            Err(e) => error!("Error serializing readings: {}", e),
+        }
+    }
+}
+
+/// Starts a WebSocket server listening on `addr` in a background task. `addr` can use port 0
+/// to pick a free port. Must be called inside a tokio runtime.
+///
+/// Returns a Socket error if the server can't be bound.
+///
+/// # Returns
+///
+/// Returns a tuple containing:
+/// * A `tokio::task::JoinHandle<()>` of the server, which ends when `WebSocketSink::stop` is
+///   called, the sink is dropped or the global `ShutdownToken` is shut down.
+/// * The `WebSocketSink` to attach to sources.
+pub fn run_server(
+    addr: &str,
+) -> Result<(tokio::task::JoinHandle<()>, WebSocketSink), WebSocketError> {
+    let socket_error = |e: std::io::Error| WebSocketError::Socket(e.to_string());
+    let listener = StdTcpListener::bind(addr).map_err(socket_error)?;
+    listener.set_nonblocking(true).map_err(socket_error)?;
+    let local_addr = listener.local_addr().map_err(socket_error)?;
+    let listener = TcpListener::from_std(listener).map_err(socket_error)?;
+
+    let (sender, _) = broadcast::channel(CLIENT_BUFFER_CAPACITY);
+    let abort_signal = Arc::new(Notify::new());
+    let sink = WebSocketSink {
+        sender,
+        local_addr,
+        abort_signal: abort_signal.clone(),
+        n_clients: Arc::new(AtomicUsize::new(0)),
+        shutdown: ShutdownToken::global(),
+        _guard: None,
+    };
+    let handle = tokio::spawn({
+        let sink = sink.clone();
+        async move { sink.serve(listener).await }
+    });
+    let guard = DropGuard::new(move || abort(&abort_signal));
+    Ok((
+        handle,
+        WebSocketSink {
+            _guard: Some(Arc::new(guard)),
+            ..sink
+        },
+    ))
+}
+
+fn abort(abort_signal: &Notify) {
+    abort_signal.notify_waiters();
+    // the server may not be waiting yet
+    abort_signal.notify_one();
+}
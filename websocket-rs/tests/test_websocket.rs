@@ -0,0 +1,86 @@
+use futures_util::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+use imu_common::traits::{IMUReadings, IMUSink};
+use imu_common::types::sensors::{SensorReadings, SensorType};
+use imu_common::types::timed::Sample3D;
+use websocket_rs::run_server;
+
+#[tokio::test]
+async fn test_broadcast_readings() {
+    let (handle, sink) = run_server("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", sink.local_addr());
+    let (mut client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while sink.get_client_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let sensor_type = SensorType::Accelerometer(Uuid::new_v4());
+    let readings = SensorReadings::from_vec(
+        "Phone",
+        sensor_type.clone(),
+        vec![
+            Sample3D::new(1.0, [0.1, 0.2, 9.8]),
+            Sample3D::new(1.1, [0.1, 0.2, 9.7]),
+        ],
+    );
+    sink.process_samples(Uuid::new_v4(), Arc::new(readings));
+
+    let message = tokio::time::timeout(Duration::from_secs(2), client.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let message: serde_json::Value = match message {
+        Message::Text(text) => serde_json::from_str(&text).unwrap(),
+        message => panic!("Unexpected message {:?}", message),
+    };
+    assert_eq!(message["tag"], "Phone");
+    assert_eq!(message["sensor"], sensor_type.to_string());
+    assert_eq!(message["samples"].as_array().unwrap().len(), 2);
+    assert_eq!(message["samples"][1]["timestamp"], 1.1);
+    assert_eq!(message["samples"][0]["measurement"]["z"], 9.8);
+
+    sink.stop();
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_stop_on_drop() {
+    let (handle, sink) = run_server("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", sink.local_addr());
+    let (mut client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while sink.get_client_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let clone = sink.clone();
+    drop(sink);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!handle.is_finished());
+
+    drop(clone);
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .unwrap()
+        .unwrap();
+    let message = tokio::time::timeout(Duration::from_secs(2), client.next())
+        .await
+        .unwrap();
+    assert!(matches!(message, Some(Ok(Message::Close(_))) | None));
+}